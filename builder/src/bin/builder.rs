@@ -43,7 +43,7 @@ fn main() -> anyhow::Result<()> {
 
     match cli.command {
         Commands::Build { script, output, force, open, icon, open_cmd } => {
-            build_executable(&script, output, force, open, icon, open_cmd, None)?;
+            build_executable(&script, output, force, open, icon, open_cmd, None, Default::default())?;
         }
     }
     Ok(())
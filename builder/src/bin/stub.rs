@@ -2,12 +2,16 @@
 use std::env;
 use std::fs::{self, File};
 use std::io::{self, copy};
-use std::process::Command;
-use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 use zip::ZipArchive;
+use lunu_builder::manifest::{BuildManifest, MANIFEST_NAME};
+use lunu_builder::{crash, extract, updates};
 
 static OPEN_CMD: AtomicBool = AtomicBool::new(true);
+static MANIFEST: OnceLock<BuildManifest> = OnceLock::new();
 
 // Minimal Error type to avoid anyhow overhead
 type StubResult<T> = Result<T, Box<dyn std::error::Error>>;
@@ -16,6 +20,12 @@ fn main() {
     // Custom panic hook to keep window open on error if needed
     std::panic::set_hook(Box::new(|info| {
         eprintln!("\n[Lunu Stub] CRITICAL PANIC: {}", info);
+        if let Some(manifest) = MANIFEST.get() {
+            let reason = format!("stub panic: {}", info);
+            if let Ok(path) = crash::write_report(&crash::crash_dir(&manifest.app), manifest, &reason, "") {
+                crash::show_crash_message(&manifest.app, &path);
+            }
+        }
         if OPEN_CMD.load(Ordering::Relaxed) {
             eprintln!("Press Enter to exit...");
             let mut buffer = String::new();
@@ -56,36 +66,55 @@ fn run() -> StubResult<()> {
         }
     };
 
-    // Create temp dir manually to avoid tempfile dependency
-    let temp_base = env::temp_dir();
-    let unique_name = format!("lunu_{}", std::process::id()); // Simple unique ID
-    let root = temp_base.join(unique_name);
-    
-    // Cleanup previous run if exists
-    if root.exists() {
-        let _ = fs::remove_dir_all(&root);
+    updates::cleanup_previous(&exe_path);
+    let manifest = read_manifest(&mut archive);
+    let _ = MANIFEST.set(manifest.clone());
+    let check_due = || updates::take_check(&updates::check_stamp_path(&exe_path), updates::CHECK_INTERVAL);
+    if manifest.updates.is_some() && env::var_os("LUNU_UPDATED").is_none() && check_due() {
+        match updates::check_and_apply(&exe_path, &manifest) {
+            Ok(Some(updated)) => {
+                drop(archive);
+                // Hand over to the new build; the flag stops it from checking again
+                let status = Command::new(&updated)
+                    .args(env::args_os().skip(1))
+                    .env("LUNU_UPDATED", "1")
+                    .status()?;
+                std::process::exit(status.code().unwrap_or(1));
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("[Lunu Stub] Update check failed: {}", e),
+        }
     }
-    fs::create_dir_all(&root)?;
-
-    // Extract
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let outpath = match file.enclosed_name() {
-            Some(path) => root.join(path),
-            None => continue,
-        };
 
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath)?;
-        } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p)?;
+    let root = extract::run_dir(&manifest, &exe_path);
+    if !extract::is_extracted(&root) {
+        // Cleanup previous run if exists
+        if root.exists() {
+            let _ = fs::remove_dir_all(&root);
+        }
+        fs::create_dir_all(&root)?;
+
+        // Extract
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let outpath = match file.enclosed_name() {
+                Some(path) => root.join(path),
+                None => continue,
+            };
+
+            if file.name().ends_with('/') {
+                fs::create_dir_all(&outpath)?;
+            } else {
+                if let Some(p) = outpath.parent() {
+                    if !p.exists() {
+                        fs::create_dir_all(p)?;
+                    }
                 }
+                let mut outfile = File::create(&outpath)?;
+                copy(&mut file, &mut outfile)?;
             }
-            let mut outfile = File::create(&outpath)?;
-            copy(&mut file, &mut outfile)?;
         }
+        extract::mark_extracted(&root)?;
     }
 
     // Locate Lune
@@ -105,26 +134,65 @@ fn run() -> StubResult<()> {
     let mut cmd = Command::new(&lune_exe);
     cmd.arg("run")
        .arg(&main_script)
-       .current_dir(&root);
+       .current_dir(&root)
+       .env("LUNU_RESOURCES_DIR", root.join("assets"));
        
     // Forward args? The stub might receive args.
     // For now, simple run.
     
+    // Output is mirrored to the console and kept so a crash log can include it
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
     let mut child = cmd.spawn()?;
+    let capture = crash::OutputCapture::default();
+    let mut relays = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        relays.push(crash::tee(stdout, io::stdout(), capture.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        relays.push(crash::tee(stderr, io::stderr(), capture.clone()));
+    }
     let status = child.wait()?;
+    for relay in relays {
+        let _ = relay.join();
+    }
 
     if !status.success() {
         eprintln!("\n[Lunu Stub] Script exited with code: {:?}", status.code());
+        let app = if manifest.app.is_empty() { exe_stem(&exe_path) } else { manifest.app.clone() };
+        let reason = match status.code() {
+            Some(code) => format!("script exited with code {}", code),
+            None => "script was terminated".to_string(),
+        };
+        match crash::write_report(&crash::crash_dir(&app), &manifest, &reason, &capture.contents()) {
+            Ok(path) => crash::show_crash_message(&app, &path),
+            Err(e) => eprintln!("[Lunu Stub] Failed to write crash log: {}", e),
+        }
     }
 
-    // Cleanup Temp?
-    // In a real optimized stub, we might leave it or delete it.
-    // Deleting is good.
-    let _ = fs::remove_dir_all(&root);
+    if extract::should_cleanup(manifest.extract.cleanup, status.success()) {
+        let _ = fs::remove_dir_all(&root);
+    } else if !status.success() {
+        eprintln!("[Lunu Stub] Extracted files kept at {:?}", root);
+    }
 
     Ok(())
 }
 
+fn exe_stem(exe_path: &Path) -> String {
+    exe_path.file_stem().and_then(|s| s.to_str()).unwrap_or("app").to_string()
+}
+
+fn read_manifest<R: io::Read + io::Seek>(archive: &mut ZipArchive<R>) -> BuildManifest {
+    let mut content = String::new();
+    match archive.by_name(MANIFEST_NAME) {
+        Ok(mut file) => {
+            let _ = io::Read::read_to_string(&mut file, &mut content);
+        }
+        Err(_) => return BuildManifest::default(),
+    }
+    BuildManifest::from_json(&content).unwrap_or_default()
+}
+
 fn read_open_cmd(root: &Path) -> bool {
     let flag_path = root.join("lunu_open_cmd.txt");
     if let Ok(content) = fs::read_to_string(flag_path) {
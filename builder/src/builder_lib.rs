@@ -3,10 +3,16 @@ use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use zip::write::FileOptions;
 use walkdir::WalkDir;
+use crate::exclude::ExcludeSet;
+use crate::manifest::{BuildManifest, BuildSettings, MANIFEST_NAME};
+use crate::payload_cache::{self, PayloadCache};
+use crate::progress;
+use crate::timings;
 
 // Embed the stub binary
 const STUB_BYTES: &[u8] = include_bytes!("resources/lunu-stub.exe");
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_executable(
     script: &Path,
     output: Option<PathBuf>,
@@ -15,6 +21,7 @@ pub fn build_executable(
     icon: Option<PathBuf>,
     open_cmd: Option<bool>,
     custom_runtime_path: Option<PathBuf>,
+    settings: BuildSettings,
 ) -> anyhow::Result<()> {
     println!("Lunu Builder v0.1.2 (Internal)");
     println!("-------------------------------");
@@ -37,36 +44,44 @@ pub fn build_executable(
     });
 
     println!("[1/5] Target: {:?}", output_path);
+    progress::emit("build", Some(0.0), &format!("Target: {}", output_path.display()));
     
     // 2. Dependencies
     let self_exe = std::env::current_exe()?;
     let self_dir = self_exe.parent().unwrap();
     
-    // 3. Cache System
-    let cache_dir = dirs::cache_dir().unwrap_or(cwd.join(".cache")).join("lunu-builder");
-    fs::create_dir_all(&cache_dir)?;
+    // 3. Cache System (one payload per project)
+    let project_root = find_project_root(self_dir, &cwd)?;
+    let cache_dir = payload_cache::builder_cache_dir(&cwd);
+    let payload = PayloadCache::for_project(&cache_dir, &project_root);
+    fs::create_dir_all(payload.dir())?;
     
-    let cache_file = cache_dir.join("runtime_payload.zip");
-    let cache_meta = cache_dir.join("runtime_payload.meta");
+    let cache_file = payload.payload();
+    let cache_meta = payload.meta();
     let use_cache = !force && cache_file.exists();
 
     let mut base_zip_buffer = Vec::new();
 
+    // The cache key covers the modules filter, which extends `settings.exclude`
+    let modules_exclude = settings.exclude.clone().with_paths(&settings.dev_modules);
     let mut cache_ok = false;
     if use_cache && cache_meta.exists() {
-        let project_root = find_project_root(self_dir, &cwd)?;
         let lune_path = resolve_lune_path(&project_root)?;
-        cache_ok = is_cache_valid(&cache_meta, &project_root, &lune_path)?;
+        cache_ok = is_cache_valid(&cache_meta, &project_root, &lune_path, &modules_exclude)?;
     }
 
     if cache_ok {
+        timings::cache_hit("payload");
         println!("[2/5] Loading runtime from cache...");
+        progress::emit("build", Some(20.0), "Loading runtime from cache");
         let mut f = File::open(&cache_file)?;
         f.read_to_end(&mut base_zip_buffer)?;
     } else {
+        timings::cache_miss("payload");
+        let _timing = timings::span("zip");
         println!("[2/5] Building runtime payload (this takes a moment)...");
+        progress::emit("build", Some(20.0), "Building runtime payload");
         
-        let project_root = find_project_root(self_dir, &cwd)?;
         let settings_path = project_root.join("config").join("settings.json");
         if !settings_path.exists() {
             return Err(anyhow::anyhow!("Config not found at {:?}. Run 'lunu init' in the project directory.", settings_path));
@@ -100,10 +115,11 @@ pub fn build_executable(
              return Err(anyhow::anyhow!("Modules directory not found at {:?}. Are you running the builder from the correct location?", modules_dir));
         }
 
-        add_dir_to_zip(&mut zip_writer, &bridge_dir, "src/bridge", options)?;
-        add_dir_to_zip(&mut zip_writer, &libs_dir, "src/libs", options)?;
-        add_dir_to_zip(&mut zip_writer, &config_dir, "config", options)?;
-        add_dir_to_zip(&mut zip_writer, &modules_dir, "modules", options)?;
+        let exclude = &settings.exclude;
+        add_dir_to_zip(&mut zip_writer, &bridge_dir, "src/bridge", options, exclude)?;
+        add_dir_to_zip(&mut zip_writer, &libs_dir, "src/libs", options, exclude)?;
+        add_dir_to_zip(&mut zip_writer, &config_dir, "config", options, exclude)?;
+        add_dir_to_zip(&mut zip_writer, &modules_dir, "modules", options, &modules_exclude)?;
 
         let init_path = project_root.join("init.luau");
         if init_path.exists() {
@@ -131,12 +147,18 @@ pub fn build_executable(
         
         let mut f = File::create(&cache_file)?;
         f.write_all(&base_zip_buffer)?;
-        let meta = build_cache_meta(&project_root, &lune_path)?;
+        let meta = build_cache_meta(&project_root, &lune_path, &modules_exclude)?;
         let mut mf = File::create(&cache_meta)?;
         mf.write_all(meta.as_bytes())?;
     }
+    payload.touch()?;
+    if let Err(err) = payload_cache::evict(&cache_dir, payload_cache::MAX_ENTRIES) {
+        println!("Warning: could not prune old payload caches: {}", err);
+    }
 
+    let injecting = timings::span("zip");
     println!("[3/5] Injecting user script...");
+    progress::emit("build", Some(40.0), "Injecting user script");
     
     let temp_zip_path = temp_zip_path();
     fs::copy(&cache_file, &temp_zip_path)?;
@@ -158,21 +180,40 @@ pub fn build_executable(
         zip_writer.write_all(b"0")?;
     }
 
+    let app_name = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("app").to_string();
+    let mut manifest = BuildManifest::new(&app_name);
+    manifest.extract = settings.extract.clone();
+    if let Some(updates) = &settings.updates {
+        manifest.version = updates.version.clone();
+        manifest.updates = Some(updates.channel.clone());
+    }
+    zip_writer.start_file(MANIFEST_NAME, options)?;
+    zip_writer.write_all(manifest.to_json()?.as_bytes())?;
+
+    // Project assets are exposed to scripts through @lunu/resources
+    let assets_dir = project_root.join("assets");
+    add_dir_to_zip(&mut zip_writer, &assets_dir, "assets", options, &settings.exclude)?;
+
     if let Some(icon_path) = icon {
         if !icon_path.exists() {
             return Err(anyhow::anyhow!("Icon file not found: {:?}", icon_path));
         }
         let icon_name = icon_path.file_name().and_then(|s| s.to_str()).unwrap_or("icon.ico");
-        let icon_zip_path = format!("assets/{}", icon_name);
-        zip_writer.start_file(icon_zip_path, options)?;
-        let mut icon_content = Vec::new();
-        File::open(&icon_path)?.read_to_end(&mut icon_content)?;
-        zip_writer.write_all(&icon_content)?;
+        if !assets_dir.join(icon_name).is_file() {
+            let icon_zip_path = format!("assets/{}", icon_name);
+            zip_writer.start_file(icon_zip_path, options)?;
+            let mut icon_content = Vec::new();
+            File::open(&icon_path)?.read_to_end(&mut icon_content)?;
+            zip_writer.write_all(&icon_content)?;
+        }
     }
     
     zip_writer.finish()?;
+    drop(injecting);
 
     println!("[4/5] Assembling executable...");
+    let assembling = timings::span("zip");
+    progress::emit("build", Some(60.0), "Assembling executable");
 
     // 4. Concatenate Embedded Stub + Final Zip
     let mut final_exe = File::create(&output_path)?;
@@ -185,11 +226,33 @@ pub fn build_executable(
     final_exe.write_all(&final_zip_content)?;
 
     let _ = fs::remove_file(temp_zip_path);
+    drop(assembling);
 
     println!("[5/5] Done!");
+    progress::emit("build", Some(100.0), &format!("Created {}", output_path.display()));
     println!("Created: {:?}", output_path);
     println!("Size: {} bytes", final_exe.metadata()?.len());
 
+    let report = crate::report::SizeReport::from_payload(STUB_BYTES.len() as u64, &final_zip_content)?;
+    println!("{}", report.summary());
+    if settings.analyze {
+        println!("{}", report.tree());
+    }
+    let keep = settings.history.unwrap_or(crate::history::DEFAULT_KEEP);
+    if let Err(err) = crate::history::record(&project_root, &manifest, &report, &output_path, keep) {
+        println!("Warning: could not record build history: {}", err);
+    }
+
+    if let Some(updates) = &settings.updates {
+        if updates.signing_key.is_some() {
+            let out_dir = output_path.parent().map(Path::to_path_buf).unwrap_or(cwd.clone());
+            let update_manifest = crate::updates::write_release(&out_dir, &app_name, updates, &final_zip_content)?;
+            println!("Update release {} written to {:?}", updates.version, update_manifest);
+        } else {
+            println!("Auto-update enabled; set [build.updates] signing_key to produce signed update payloads.");
+        }
+    }
+
     if open {
         open_output(&output_path);
     }
@@ -205,6 +268,8 @@ fn open_output(path: &Path) {
             .arg(path)
             .spawn();
     }
+    #[cfg(not(windows))]
+    let _ = path;
 }
 
 fn find_project_root(self_dir: &Path, cwd: &Path) -> anyhow::Result<PathBuf> {
@@ -326,18 +391,25 @@ fn find_rokit_tool_storage_lune() -> Option<PathBuf> {
     }
     best.map(|(_, path)| path)
 }
-fn build_cache_meta(project_root: &Path, lune_path: &Path) -> anyhow::Result<String> {
+fn build_cache_meta(project_root: &Path, lune_path: &Path, exclude: &ExcludeSet) -> anyhow::Result<String> {
     let lune_meta = std::fs::metadata(lune_path)?;
     let lune_mtime = lune_meta.modified().ok()
         .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
         .map(|d| d.as_secs())
         .unwrap_or(0);
     let lune_size = lune_meta.len();
-    Ok(format!("root={}\nlune_path={}\nlune_mtime={}\nlune_size={}\n", project_root.display(), lune_path.display(), lune_mtime, lune_size))
+    Ok(format!(
+        "root={}\nlune_path={}\nlune_mtime={}\nlune_size={}\nexclude={}\n",
+        project_root.display(),
+        lune_path.display(),
+        lune_mtime,
+        lune_size,
+        exclude.fingerprint()
+    ))
 }
 
-fn is_cache_valid(cache_meta: &Path, project_root: &Path, lune_path: &Path) -> anyhow::Result<bool> {
-    let current = build_cache_meta(project_root, lune_path)?;
+fn is_cache_valid(cache_meta: &Path, project_root: &Path, lune_path: &Path, exclude: &ExcludeSet) -> anyhow::Result<bool> {
+    let current = build_cache_meta(project_root, lune_path, exclude)?;
     let saved = std::fs::read_to_string(cache_meta).unwrap_or_default();
     Ok(current == saved)
 }
@@ -385,38 +457,89 @@ fn add_dir_to_zip<W: Write + std::io::Seek>(
     src_dir: &Path,
     dst_dir: &str,
     options: FileOptions,
+    exclude: &ExcludeSet,
 ) -> anyhow::Result<()> {
     if !src_dir.exists() {
         return Ok(());
     }
 
-    for entry in WalkDir::new(src_dir) {
+    let mut files = Vec::new();
+    // Excluded directories are pruned without being walked
+    let walker = WalkDir::new(src_dir).into_iter().filter_entry(|entry| {
+        let relative = entry.path().strip_prefix(src_dir).unwrap_or(entry.path());
+        !exclude.is_excluded(&relative.to_string_lossy())
+    });
+    for entry in walker {
         let entry = entry?;
         let path = entry.path();
-        
-        // Filter out unnecessary files
-        if path.components().any(|c| {
-            let s = c.as_os_str().to_string_lossy();
-            s == "target" || s == ".git" || s == "node_modules" || s == "__pycache__"
-        }) {
-            continue;
-        }
 
         let name = path.strip_prefix(src_dir)?;
         let path_str = name.to_string_lossy().replace("\\", "/");
         let zip_path = format!("{}/{}", dst_dir, path_str);
 
         if path.is_file() {
-            zip.start_file(zip_path, options)?;
-            let mut f = File::open(path)?;
-            let mut buffer = Vec::new();
-            f.read_to_end(&mut buffer)?;
-            zip.write_all(&buffer)?;
+            files.push((path.to_path_buf(), zip_path));
         }
     }
+    // Sorted so the payload (and its cache) is identical between builds
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+
+    for entry in compress_parallel(&files, options)? {
+        let mut archive = zip::ZipArchive::new(io::Cursor::new(entry))?;
+        zip.raw_copy_file(archive.by_index_raw(0)?)?;
+    }
     Ok(())
 }
 
+/// Compresses each file into its own single-entry zip on worker threads. The results keep the
+/// order of `files` and are copied into the payload without recompressing.
+fn compress_parallel(files: &[(PathBuf, String)], options: FileOptions) -> anyhow::Result<Vec<Vec<u8>>> {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(files.len().max(1));
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let mut results: Vec<Option<anyhow::Result<Vec<u8>>>> = (0..files.len()).map(|_| None).collect();
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some((path, zip_path)) = files.get(index) else { break };
+                        done.push((index, compress_entry(path, zip_path, options)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (index, result) in handle.join().expect("compression worker panicked") {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("File was not compressed"))))
+        .collect()
+}
+
+fn compress_entry(path: &Path, zip_path: &str, options: FileOptions) -> anyhow::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    File::open(path)?.read_to_end(&mut buffer)?;
+    let mut out = Vec::new();
+    let mut zip = zip::ZipWriter::new(io::Cursor::new(&mut out));
+    zip.start_file(zip_path, options)?;
+    zip.write_all(&buffer)?;
+    zip.finish()?;
+    drop(zip);
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,7 +573,7 @@ mod tests {
     }
 
     fn write_file_with_size(path: &Path, size: u64) {
-        let mut file = OpenOptions::new().create(true).write(true).open(path).unwrap();
+        let mut file = OpenOptions::new().create(true).truncate(true).write(true).open(path).unwrap();
         file.write_all(b"x").unwrap();
         file.set_len(size).unwrap();
     }
@@ -507,6 +630,52 @@ mod tests {
         let _ = std::fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn add_dir_to_zip_uses_forward_slashes() {
+        let root = temp_test_dir();
+        let assets = root.join("assets");
+        std::fs::create_dir_all(assets.join("img")).unwrap();
+        std::fs::write(assets.join("img").join("icon.png"), b"png").unwrap();
+
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+            add_dir_to_zip(&mut zip, &assets, "assets", FileOptions::default(), &ExcludeSet::default()).unwrap();
+            zip.finish().unwrap();
+        }
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(buf)).unwrap();
+        let mut content = String::new();
+        archive.by_name("assets/img/icon.png").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "png");
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn add_dir_to_zip_orders_entries_deterministically() {
+        let root = temp_test_dir();
+        for name in ["b.luau", "a.luau", "c/d.luau", "c/a.luau", "c/__pycache__/x.pyc", ".venv/lib.py"] {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name.repeat(100)).unwrap();
+        }
+        let build = || {
+            let mut buf = Vec::new();
+            {
+                let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+                let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+                add_dir_to_zip(&mut zip, &root, "modules", options, &ExcludeSet::default()).unwrap();
+                zip.finish().unwrap();
+            }
+            buf
+        };
+        let first = build();
+        assert_eq!(first, build());
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(first)).unwrap();
+        let names: Vec<String> = (0..archive.len()).map(|i| archive.by_index(i).unwrap().name().to_string()).collect();
+        assert_eq!(names, ["modules/a.luau", "modules/b.luau", "modules/c/a.luau", "modules/c/d.luau"]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn is_rokit_shim_detects_rokit_bin() {
         let shim = PathBuf::from("C:\\Users\\User\\.rokit\\bin\\lune.exe");
@@ -0,0 +1,152 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::manifest::BuildManifest;
+
+/// Only the tail of the script output is kept for crash logs.
+const CAPTURE_LIMIT: usize = 256 * 1024;
+
+/// Shared buffer holding the most recent output of the bundled script.
+#[derive(Clone, Default)]
+pub struct OutputCapture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl OutputCapture {
+    pub fn push(&self, bytes: &[u8]) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.extend_from_slice(bytes);
+        if buffer.len() > CAPTURE_LIMIT {
+            let excess = buffer.len() - CAPTURE_LIMIT;
+            buffer.drain(..excess);
+        }
+    }
+
+    pub fn contents(&self) -> String {
+        let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&buffer).to_string()
+    }
+}
+
+/// Copies `reader` to `writer` on a background thread while recording it in `capture`.
+pub fn tee<R, W>(mut reader: R, mut writer: W, capture: OutputCapture) -> std::thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let _ = writer.write_all(&chunk[..n]);
+                    let _ = writer.flush();
+                    capture.push(&chunk[..n]);
+                }
+            }
+        }
+    })
+}
+
+/// `%LOCALAPPDATA%/lunu/crashes/<app>` (or the platform equivalent).
+pub fn crash_dir(app: &str) -> PathBuf {
+    let app = if app.is_empty() { "app" } else { app };
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("lunu")
+        .join("crashes")
+        .join(sanitize(app))
+}
+
+pub(crate) fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+pub fn render_report(manifest: &BuildManifest, reason: &str, output: &str) -> String {
+    let mut report = String::new();
+    report.push_str(&format!("App: {}\n", manifest.app));
+    if !manifest.version.is_empty() {
+        report.push_str(&format!("Version: {}\n", manifest.version));
+    }
+    report.push_str(&format!("Builder: {}\n", manifest.builder));
+    report.push_str(&format!("Built at: {}\n", manifest.built_at));
+    report.push_str(&format!("OS: {} ({})\n", std::env::consts::OS, std::env::consts::ARCH));
+    report.push_str(&format!("Reason: {}\n", reason));
+    report.push_str("\n--- Output ---\n");
+    report.push_str(output);
+    if !output.ends_with('\n') {
+        report.push('\n');
+    }
+    report
+}
+
+/// Writes a crash log to `dir` named after the current time and returns its path.
+pub fn write_report(dir: &Path, manifest: &BuildManifest, reason: &str, output: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("{}.log", timestamp));
+    std::fs::write(&path, render_report(manifest, reason, output))?;
+    Ok(path)
+}
+
+/// Tells the user where the crash log went: a message box on Windows, stderr elsewhere.
+pub fn show_crash_message(app: &str, log_path: &Path) {
+    let text = format!(
+        "{} stopped unexpectedly.\n\nA crash log was saved to:\n{}\n\nPlease include this file when reporting the problem.",
+        app,
+        log_path.display()
+    );
+    eprintln!("\n[Lunu Stub] {}", text);
+    #[cfg(windows)]
+    message_box(&format!("{} crashed", app), &text);
+}
+
+#[cfg(windows)]
+fn message_box(title: &str, text: &str) {
+    #[link(name = "user32")]
+    extern "system" {
+        fn MessageBoxW(hwnd: *mut std::ffi::c_void, text: *const u16, caption: *const u16, utype: u32) -> i32;
+    }
+    const MB_OK: u32 = 0x0000_0000;
+    const MB_ICONERROR: u32 = 0x0000_0010;
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let (text, title) = (wide(text), wide(title));
+    unsafe {
+        MessageBoxW(std::ptr::null_mut(), text.as_ptr(), title.as_ptr(), MB_OK | MB_ICONERROR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_keeps_only_the_tail() {
+        let capture = OutputCapture::default();
+        capture.push(&vec![b'a'; CAPTURE_LIMIT]);
+        capture.push(b"end");
+        let contents = capture.contents();
+        assert_eq!(contents.len(), CAPTURE_LIMIT);
+        assert!(contents.ends_with("end"));
+    }
+
+    #[test]
+    fn report_includes_manifest_and_output() {
+        let dir = std::env::temp_dir().join(format!("lunu_crash_test_{}", std::process::id()));
+        let mut manifest = BuildManifest::new("my app");
+        manifest.version = "1.2.0".to_string();
+        let path = write_report(&dir, &manifest, "exit code 1", "boom").unwrap();
+        let report = std::fs::read_to_string(&path).unwrap();
+        assert!(report.contains("App: my app"));
+        assert!(report.contains("Version: 1.2.0"));
+        assert!(report.trim_end().ends_with("boom"));
+        assert!(crash_dir("my app").ends_with("my_app"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
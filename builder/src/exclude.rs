@@ -0,0 +1,179 @@
+/// Development artifacts left out of payloads and dist bundles unless `--include-all` is used.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    ".git",
+    ".venv",
+    "venv",
+    "node_modules",
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+    // Build output and test suites of the project itself; nested `target`/`tests` folders (e.g.
+    // `assets/target/`) are content
+    "/target",
+    "/tests",
+    "*.pyc",
+    "*.pyo",
+    "*.test.luau",
+    "*.spec.luau",
+    ".DS_Store",
+    "Thumbs.db",
+];
+
+/// Patterns matched against paths relative to the directory being packed.
+///
+/// A pattern without `/` matches any single path component (`*.pyc`, `.venv`); a pattern with
+/// `/` matches from the start of the relative path (`assets/raw/`), and so does one starting with
+/// `/` (`/target` is only the top-level `target`). `*` and `?` are wildcards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExcludeSet {
+    patterns: Vec<String>,
+    keep: Vec<String>,
+    /// Exact relative paths dropped with everything below them; `keep` does not apply
+    paths: Vec<String>,
+}
+
+impl Default for ExcludeSet {
+    fn default() -> Self {
+        Self::new(true, &[], &[])
+    }
+}
+
+impl ExcludeSet {
+    pub fn new(defaults: bool, extra: &[String], keep: &[String]) -> Self {
+        let mut patterns: Vec<String> = if defaults {
+            DEFAULT_EXCLUDES.iter().map(|p| p.to_string()).collect()
+        } else {
+            Vec::new()
+        };
+        patterns.extend(extra.iter().map(|p| normalize_pattern(p)).filter(|p| !p.is_empty() && p != "/"));
+        Self {
+            patterns,
+            keep: keep.iter().map(|p| normalize(p)).filter(|p| !p.is_empty()).collect(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Also drops `paths` (and everything below them) without treating them as patterns, e.g.
+    /// the module directories of dev-dependencies.
+    pub fn with_paths(mut self, paths: &[String]) -> Self {
+        self.paths.extend(paths.iter().map(|p| normalize(p)).filter(|p| !p.is_empty()));
+        self
+    }
+
+    /// Includes everything (`--include-all`).
+    pub fn none() -> Self {
+        Self { patterns: Vec::new(), keep: Vec::new(), paths: Vec::new() }
+    }
+
+    pub fn is_excluded(&self, relative: &str) -> bool {
+        let relative = normalize(relative);
+        if relative.is_empty() {
+            return false;
+        }
+        if self.paths.iter().any(|p| relative == *p || relative.starts_with(&format!("{}/", p))) {
+            return true;
+        }
+        let excluded = self.patterns.iter().any(|p| matches_path(p, &relative));
+        excluded && !self.keep.iter().any(|p| matches_path(p, &relative))
+    }
+
+    /// Stable description for cache keys.
+    pub fn fingerprint(&self) -> String {
+        format!("{}|{}|{}", self.patterns.join(","), self.keep.join(","), self.paths.join(","))
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.trim().replace('\\', "/").trim_matches('/').trim_start_matches("./").to_string()
+}
+
+/// Like `normalize`, but keeps a leading `/`, which anchors the pattern.
+fn normalize_pattern(pattern: &str) -> String {
+    let anchored = pattern.trim().replace('\\', "/").starts_with('/');
+    let pattern = normalize(pattern);
+    if anchored { format!("/{}", pattern) } else { pattern }
+}
+
+fn matches_path(pattern: &str, relative: &str) -> bool {
+    let parts: Vec<&str> = relative.split('/').collect();
+    if !pattern.contains('/') {
+        return parts.iter().any(|part| wildcard(pattern, part));
+    }
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    // Anchored: every pattern segment must match the leading path segments
+    let segments: Vec<&str> = pattern.split('/').collect();
+    segments.len() <= parts.len() && segments.iter().zip(&parts).all(|(s, p)| wildcard(s, p))
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters and `?` one.
+pub fn wildcard(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_drop_development_artifacts() {
+        let set = ExcludeSet::default();
+        assert!(set.is_excluded("ml/.venv/lib/site.py"));
+        assert!(set.is_excluded("ml/__pycache__/worker.cpython-311.pyc"));
+        assert!(set.is_excluded("ml/worker.pyc"));
+        assert!(set.is_excluded("tests/decode.luau"));
+        assert!(set.is_excluded("target/release/app"));
+        assert!(!set.is_excluded("json/tests/decode.luau"));
+        assert!(!set.is_excluded("assets/target/sprite.png"));
+        assert!(set.is_excluded("json/src/decode.test.luau"));
+        assert!(!set.is_excluded("ml/worker.py"));
+        assert!(!set.is_excluded("json/src/decode.luau"));
+        assert!(!ExcludeSet::none().is_excluded("ml/.venv/lib/site.py"));
+    }
+
+    #[test]
+    fn extra_patterns_and_keep_overrides() {
+        let set = ExcludeSet::new(true, &["*.psd".to_string(), "ml/data/".to_string(), "tests".to_string()], &["ml/tests".to_string()]);
+        assert!(set.is_excluded("art/logo.psd"));
+        assert!(set.is_excluded("ml/data/train.csv"));
+        assert!(!set.is_excluded("other/ml/data/train.csv"));
+        assert!(!set.is_excluded("ml/tests/test_worker.py"));
+        assert!(set.is_excluded("json/tests/decode.luau"));
+
+        let custom = ExcludeSet::new(false, &["*.log".to_string()], &[]);
+        assert!(!custom.is_excluded("ml/.venv/x"));
+        assert!(custom.is_excluded("logs\\run.log"));
+
+        let anchored = ExcludeSet::new(false, &["/build".to_string()], &[]);
+        assert!(anchored.is_excluded("build/out.zip"));
+        assert!(!anchored.is_excluded("docs/build/index.html"));
+    }
+
+    #[test]
+    fn exact_paths_drop_only_that_directory() {
+        let set = ExcludeSet::none().with_paths(&["testez".to_string(), "@dev/mock/".to_string()]);
+        assert!(set.is_excluded("testez"));
+        assert!(set.is_excluded("testez/src/init.luau"));
+        assert!(set.is_excluded("@dev/mock/init.luau"));
+        assert!(!set.is_excluded("testez-extra/init.luau"));
+        assert!(!set.is_excluded("json/testez/init.luau"));
+        assert!(!set.is_excluded("@dev/other/init.luau"));
+    }
+}
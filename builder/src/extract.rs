@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+use crate::manifest::{BuildManifest, CleanupPolicy, ExtractSettings};
+
+/// Written last so an interrupted extraction is never reused.
+const EXTRACTED_MARKER: &str = ".lunu_extracted";
+
+/// Resolves the configured extraction base directory for this executable.
+pub fn base_dir(settings: &ExtractSettings, app: &str, exe_path: &Path) -> PathBuf {
+    let exe_dir = exe_path.parent().map(Path::to_path_buf).unwrap_or_else(std::env::temp_dir);
+    let app = crate::crash::sanitize(app);
+    match settings.dir.trim().to_ascii_lowercase().as_str() {
+        "" | "temp" => std::env::temp_dir(),
+        "localappdata" => dirs::data_local_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("lunu")
+            .join("apps")
+            .join(app),
+        "beside-exe" => exe_dir.join(format!("{}_data", app)),
+        _ => {
+            let custom = PathBuf::from(expand_env(settings.dir.trim()));
+            if custom.is_absolute() {
+                custom
+            } else {
+                exe_dir.join(custom)
+            }
+        }
+    }
+}
+
+/// Directory the payload is unpacked into. Builds that are never cleaned up extract once per
+/// build into a stable folder; the others get a per-process folder.
+pub fn run_dir(manifest: &BuildManifest, exe_path: &Path) -> PathBuf {
+    let base = base_dir(&manifest.extract, &manifest.app, exe_path);
+    if manifest.extract.cleanup == CleanupPolicy::Never {
+        base.join(format!("{}-{}", crate::crash::sanitize(&manifest.app), manifest.built_at))
+    } else {
+        base.join(format!("lunu_{}", std::process::id()))
+    }
+}
+
+pub fn should_cleanup(policy: CleanupPolicy, success: bool) -> bool {
+    match policy {
+        CleanupPolicy::Always => true,
+        CleanupPolicy::Never => false,
+        CleanupPolicy::OnSuccess => success,
+    }
+}
+
+pub fn is_extracted(dir: &Path) -> bool {
+    dir.join(EXTRACTED_MARKER).is_file()
+}
+
+pub fn mark_extracted(dir: &Path) -> std::io::Result<()> {
+    std::fs::write(dir.join(EXTRACTED_MARKER), b"")
+}
+
+/// Expands `%VAR%` and `${VAR}` references; unknown variables are left untouched.
+fn expand_env(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while !rest.is_empty() {
+        let (open, close) = if rest.starts_with('%') {
+            ("%", "%")
+        } else if rest.starts_with("${") {
+            ("${", "}")
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+            continue;
+        };
+        let after = &rest[open.len()..];
+        match after.find(close) {
+            Some(end) => match std::env::var(&after[..end]) {
+                Ok(var) if end > 0 => {
+                    out.push_str(&var);
+                    rest = &after[end + close.len()..];
+                }
+                _ => {
+                    out.push_str(open);
+                    rest = after;
+                }
+            },
+            None => {
+                out.push_str(rest);
+                break;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_extract_locations() {
+        let exe = if cfg!(windows) { PathBuf::from("C:\\Apps\\demo.exe") } else { PathBuf::from("/opt/apps/demo") };
+        let exe_dir = exe.parent().unwrap().to_path_buf();
+        let settings = |dir: &str| ExtractSettings { dir: dir.to_string(), ..Default::default() };
+
+        assert_eq!(base_dir(&settings("temp"), "demo", &exe), std::env::temp_dir());
+        assert_eq!(base_dir(&settings("beside-exe"), "demo", &exe), exe_dir.join("demo_data"));
+        assert_eq!(base_dir(&settings("cache/run"), "demo", &exe), exe_dir.join("cache/run"));
+        assert!(base_dir(&settings("localappdata"), "demo", &exe).ends_with("lunu/apps/demo"));
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        std::env::set_var("LUNU_EXTRACT_TEST", "/data");
+        assert_eq!(expand_env("%LUNU_EXTRACT_TEST%/app"), "/data/app");
+        assert_eq!(expand_env("${LUNU_EXTRACT_TEST}/app"), "/data/app");
+        assert_eq!(expand_env("%LUNU_MISSING_VAR%/50%"), "%LUNU_MISSING_VAR%/50%");
+        std::env::remove_var("LUNU_EXTRACT_TEST");
+    }
+
+    #[test]
+    fn cleanup_policy_and_stable_dirs() {
+        assert!(should_cleanup(CleanupPolicy::Always, false));
+        assert!(!should_cleanup(CleanupPolicy::Never, true));
+        assert!(should_cleanup(CleanupPolicy::OnSuccess, true));
+        assert!(!should_cleanup(CleanupPolicy::OnSuccess, false));
+
+        let mut manifest = BuildManifest::new("demo");
+        manifest.extract.cleanup = CleanupPolicy::Never;
+        let exe = std::env::temp_dir().join("demo.exe");
+        assert_eq!(run_dir(&manifest, &exe), run_dir(&manifest, &exe));
+        assert!(run_dir(&manifest, &exe).ends_with(format!("demo-{}", manifest.built_at)));
+        assert_eq!("on-success".parse::<CleanupPolicy>().unwrap(), CleanupPolicy::OnSuccess);
+        assert!("sometimes".parse::<CleanupPolicy>().is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Build history (`dist/history/<timestamp>/`): the manifest and payload listing of recent builds,
+//! so `lunu build --list`/`--diff` can show what changed between two of them.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use crate::manifest::BuildManifest;
+use crate::report::{format_size, SizeReport};
+
+pub const DEFAULT_KEEP: usize = 10;
+const CONTENTS_FILE: &str = "contents.json";
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileRecord {
+    pub name: String,
+    pub compressed: u64,
+    pub uncompressed: u64,
+}
+
+/// What `contents.json` records about one build.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BuildRecord {
+    #[serde(skip)]
+    pub id: String,
+    pub output: String,
+    pub stub: u64,
+    pub files: Vec<FileRecord>,
+}
+
+impl BuildRecord {
+    pub fn total(&self) -> u64 {
+        self.stub + self.files.iter().map(|f| f.compressed).sum::<u64>()
+    }
+}
+
+pub fn history_dir(project_root: &Path) -> PathBuf {
+    project_root.join("dist").join("history")
+}
+
+/// Saves a build under a new `<timestamp>` directory and prunes all but the newest `keep`.
+pub fn record(project_root: &Path, manifest: &BuildManifest, report: &SizeReport, output: &Path, keep: usize) -> anyhow::Result<Option<PathBuf>> {
+    if keep == 0 {
+        return Ok(None);
+    }
+    let base = history_dir(project_root);
+    let mut dir = base.join(manifest.built_at.to_string());
+    let mut suffix = 1;
+    while dir.exists() {
+        dir = base.join(format!("{}-{}", manifest.built_at, suffix));
+        suffix += 1;
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let record = BuildRecord {
+        id: String::new(),
+        output: output.to_string_lossy().to_string(),
+        stub: report.stub,
+        files: report
+            .entries
+            .iter()
+            .map(|e| FileRecord { name: e.name.clone(), compressed: e.compressed, uncompressed: e.uncompressed })
+            .collect(),
+    };
+    std::fs::write(dir.join(MANIFEST_FILE), manifest.to_json()?)?;
+    std::fs::write(dir.join(CONTENTS_FILE), serde_json::to_string_pretty(&record)?)?;
+    for old in list(project_root)?.into_iter().skip(keep) {
+        let _ = std::fs::remove_dir_all(base.join(old.id));
+    }
+    Ok(Some(dir))
+}
+
+/// Recorded builds, newest first.
+pub fn list(project_root: &Path) -> anyhow::Result<Vec<BuildRecord>> {
+    let Ok(read_dir) = std::fs::read_dir(history_dir(project_root)) else {
+        return Ok(Vec::new());
+    };
+    let mut records = Vec::new();
+    for entry in read_dir.flatten() {
+        let Ok(content) = std::fs::read_to_string(entry.path().join(CONTENTS_FILE)) else {
+            continue;
+        };
+        let mut record: BuildRecord = serde_json::from_str(&content).with_context(|| format!("Invalid {:?}", entry.path().join(CONTENTS_FILE)))?;
+        record.id = entry.file_name().to_string_lossy().to_string();
+        records.push(record);
+    }
+    records.sort_by_key(|r| std::cmp::Reverse(sort_key(&r.id)));
+    Ok(records)
+}
+
+fn sort_key(id: &str) -> (u64, u64) {
+    let (time, suffix) = id.split_once('-').unwrap_or((id, "0"));
+    (time.parse().unwrap_or(0), suffix.parse().unwrap_or(0))
+}
+
+/// A build by id, or by its position in `lunu build --list` (1 = newest).
+pub fn find<'a>(records: &'a [BuildRecord], reference: &str) -> anyhow::Result<&'a BuildRecord> {
+    if let Some(record) = records.iter().find(|r| r.id == reference) {
+        return Ok(record);
+    }
+    reference
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| index.checked_sub(1))
+        .and_then(|index| records.get(index))
+        .ok_or_else(|| anyhow::anyhow!("No build '{}' in dist/history. Run 'lunu build --list'.", reference))
+}
+
+fn signed_size(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_size(delta.unsigned_abs()))
+}
+
+/// Files added, removed and resized between two builds, plus the total size change.
+pub fn diff(from: &BuildRecord, to: &BuildRecord) -> String {
+    let before: BTreeMap<&str, u64> = from.files.iter().map(|f| (f.name.as_str(), f.compressed)).collect();
+    let after: BTreeMap<&str, u64> = to.files.iter().map(|f| (f.name.as_str(), f.compressed)).collect();
+    let mut out = format!(
+        "{} -> {}: {} -> {} ({})\n",
+        from.id,
+        to.id,
+        format_size(from.total()),
+        format_size(to.total()),
+        signed_size(to.total() as i64 - from.total() as i64)
+    );
+    let mut changes = 0;
+    for (name, size) in &after {
+        match before.get(name) {
+            None => out.push_str(&format!("  + {:<48} {}\n", name, format_size(*size))),
+            Some(old) if old != size => out.push_str(&format!("  ~ {:<48} {}\n", name, signed_size(*size as i64 - *old as i64))),
+            Some(_) => continue,
+        }
+        changes += 1;
+    }
+    for (name, size) in &before {
+        if !after.contains_key(name) {
+            out.push_str(&format!("  - {:<48} {}\n", name, format_size(*size)));
+            changes += 1;
+        }
+    }
+    if changes == 0 {
+        out.push_str("  (payload contents unchanged)\n");
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::EntrySize;
+
+    fn report(files: &[(&str, u64)]) -> SizeReport {
+        SizeReport {
+            stub: 100,
+            entries: files.iter().map(|(name, size)| EntrySize { name: name.to_string(), compressed: *size, uncompressed: size * 2 }).collect(),
+        }
+    }
+
+    #[test]
+    fn records_prunes_and_diffs_builds() {
+        let dir = std::env::temp_dir().join(format!("lunu_history_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut manifest = BuildManifest::new("app");
+        manifest.built_at = 1000;
+        let out = Path::new("app.exe");
+        record(&dir, &manifest, &report(&[("src/main.luau", 10), ("bin/lune.exe", 500)]), out, 2).unwrap();
+        record(&dir, &manifest, &report(&[("src/main.luau", 12), ("bin/lune.exe", 500), ("assets/big.png", 2048)]), out, 2).unwrap();
+        manifest.built_at = 2000;
+        record(&dir, &manifest, &report(&[("src/main.luau", 12), ("assets/big.png", 2048)]), out, 2).unwrap();
+
+        let records = list(&dir).unwrap();
+        assert_eq!(records.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["2000", "1000-1"]);
+        assert!(!history_dir(&dir).join("1000").exists());
+        assert!(history_dir(&dir).join("2000").join(MANIFEST_FILE).exists());
+
+        let older = find(&records, "2").unwrap();
+        assert_eq!(older.id, "1000-1");
+        assert!(find(&records, "3").is_err());
+        let text = diff(older, find(&records, "2000").unwrap());
+        assert!(text.starts_with("1000-1 -> 2000:"));
+        assert!(text.contains("  - bin/lune.exe"));
+        assert!(!text.contains("big.png"));
+        assert!(diff(older, older).contains("unchanged"));
+
+        assert!(record(&dir, &manifest, &report(&[]), out, 0).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
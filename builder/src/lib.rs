@@ -1,2 +1,13 @@
 pub mod builder_lib;
+pub mod crash;
+pub mod exclude;
+pub mod extract;
+pub mod history;
+pub mod manifest;
+pub mod minisign;
+pub mod payload_cache;
+pub mod progress;
+pub mod report;
+pub mod timings;
+pub mod updates;
 pub use builder_lib::build_executable;
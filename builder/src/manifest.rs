@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Name of the manifest stored at the root of every payload.
+pub const MANIFEST_NAME: &str = "lunu_manifest.json";
+
+/// Build information embedded in the payload and read back by the stub.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BuildManifest {
+    pub app: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub builder: String,
+    #[serde(default)]
+    pub built_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updates: Option<UpdateChannel>,
+    #[serde(default)]
+    pub extract: ExtractSettings,
+}
+
+/// Where the stub unpacks the payload and when it removes it again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtractSettings {
+    /// `temp`, `localappdata`, `beside-exe` or a custom path (relative paths start at the exe)
+    #[serde(default = "default_extract_dir")]
+    pub dir: String,
+    #[serde(default)]
+    pub cleanup: CleanupPolicy,
+}
+
+impl Default for ExtractSettings {
+    fn default() -> Self {
+        Self {
+            dir: default_extract_dir(),
+            cleanup: CleanupPolicy::default(),
+        }
+    }
+}
+
+fn default_extract_dir() -> String {
+    "temp".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CleanupPolicy {
+    #[default]
+    Always,
+    Never,
+    OnSuccess,
+}
+
+impl std::str::FromStr for CleanupPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "always" => Ok(CleanupPolicy::Always),
+            "never" => Ok(CleanupPolicy::Never),
+            "on-success" | "on_success" => Ok(CleanupPolicy::OnSuccess),
+            other => Err(anyhow::anyhow!("Unknown cleanup policy '{}' (expected always, never or on-success)", other)),
+        }
+    }
+}
+
+/// Where a built app looks for updates and the key its payloads must be signed with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UpdateChannel {
+    pub url: String,
+    #[serde(default = "default_channel")]
+    pub channel: String,
+    pub public_key: String,
+}
+
+fn default_channel() -> String {
+    "stable".to_string()
+}
+
+/// Project settings from lunu.toml that shape the payload, plus build output options.
+#[derive(Debug, Clone, Default)]
+pub struct BuildSettings {
+    pub updates: Option<UpdateSettings>,
+    pub extract: ExtractSettings,
+    /// Print the full payload tree instead of only the summary
+    pub analyze: bool,
+    pub exclude: crate::exclude::ExcludeSet,
+    /// Module directories (relative to `modules/`) left out of the payload: dev-dependencies
+    pub dev_modules: Vec<String>,
+    /// Builds kept under dist/history; `None` keeps `history::DEFAULT_KEEP`, 0 disables it
+    pub history: Option<usize>,
+}
+
+/// `[build.updates]` as handed to the builder. The signing key never ends up in the payload.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSettings {
+    pub version: String,
+    pub channel: UpdateChannel,
+    pub signing_key: Option<PathBuf>,
+}
+
+impl BuildManifest {
+    pub fn new(app: &str) -> Self {
+        Self {
+            app: app.to_string(),
+            builder: env!("CARGO_PKG_VERSION").to_string(),
+            built_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_json(content: &str) -> Option<Self> {
+        serde_json::from_str(content).ok()
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
@@ -0,0 +1,148 @@
+//! Minisign-compatible signatures made with a Lunu signing key (see `updates::generate_key`), so
+//! downloads can be checked with the stock `minisign -V` as well as `lunu package verify`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, Verifier};
+use sha2::Sha256;
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+const PUBLIC_KEY_ALG: &[u8; 2] = b"Ed";
+/// Prehashed (BLAKE2b-512) signatures, the default since minisign 0.10.
+const SIGNATURE_ALG: &[u8; 2] = b"ED";
+
+/// Minisign key ids are arbitrary; derive one from the public key so it stays stable.
+pub fn key_id(key: &VerifyingKey) -> [u8; 8] {
+    let digest = Sha256::digest(key.to_bytes());
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&digest[..8]);
+    id
+}
+
+fn key_id_hex(id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*id))
+}
+
+/// The base64 line minisign accepts with `-P`.
+pub fn public_key_base64(key: &VerifyingKey) -> String {
+    let mut bytes = PUBLIC_KEY_ALG.to_vec();
+    bytes.extend_from_slice(&key_id(key));
+    bytes.extend_from_slice(&key.to_bytes());
+    STANDARD.encode(bytes)
+}
+
+/// Contents of a `minisign.pub` file.
+pub fn public_key_file(key: &VerifyingKey) -> String {
+    format!(
+        "untrusted comment: minisign public key {}\n{}\n",
+        key_id_hex(&key_id(key)),
+        public_key_base64(key)
+    )
+}
+
+/// Accepts a hex key (as printed by `lunu update-key`), a minisign base64 key, or a minisign.pub file.
+pub fn parse_public_key(text: &str) -> anyhow::Result<VerifyingKey> {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty() && !l.starts_with("untrusted comment:"))
+        .ok_or_else(|| anyhow::anyhow!("Empty public key"))?;
+    if line.len() == 64 && line.chars().all(|c| c.is_ascii_hexdigit()) {
+        let bytes: [u8; 32] = hex::decode(line)?.try_into().map_err(|_| anyhow::anyhow!("Invalid public key"))?;
+        return Ok(VerifyingKey::from_bytes(&bytes)?);
+    }
+    let bytes = STANDARD.decode(line).map_err(|_| anyhow::anyhow!("Invalid public key encoding"))?;
+    if bytes.len() != 42 || &bytes[..2] != PUBLIC_KEY_ALG {
+        return Err(anyhow::anyhow!("Unsupported public key format"));
+    }
+    let key: [u8; 32] = bytes[10..].try_into()?;
+    Ok(VerifyingKey::from_bytes(&key)?)
+}
+
+/// Signs `content`, returning the contents of a `.minisig` file.
+pub fn sign(key: &SigningKey, content: &[u8], trusted_comment: &str) -> String {
+    let signature = key.sign(&Blake2b512::digest(content));
+    let mut sig_bytes = SIGNATURE_ALG.to_vec();
+    sig_bytes.extend_from_slice(&key_id(&key.verifying_key()));
+    sig_bytes.extend_from_slice(&signature.to_bytes());
+
+    let mut global = signature.to_bytes().to_vec();
+    global.extend_from_slice(trusted_comment.as_bytes());
+    let global_signature = key.sign(&global);
+
+    format!(
+        "untrusted comment: signature from lunu secret key\n{}\ntrusted comment: {}\n{}\n",
+        STANDARD.encode(sig_bytes),
+        trusted_comment,
+        STANDARD.encode(global_signature.to_bytes())
+    )
+}
+
+/// Checks a `.minisig` signature of `content` and returns its trusted comment.
+pub fn verify(key: &VerifyingKey, content: &[u8], signature_file: &str) -> anyhow::Result<String> {
+    let mut lines = signature_file.lines().map(str::trim_end);
+    let mut next = |what: &str| lines.next().ok_or_else(|| anyhow::anyhow!("Signature file is missing the {}", what));
+    next("untrusted comment")?;
+    let sig_bytes = STANDARD
+        .decode(next("signature")?)
+        .map_err(|_| anyhow::anyhow!("Invalid signature encoding"))?;
+    let trusted_comment = next("trusted comment")?
+        .strip_prefix("trusted comment: ")
+        .ok_or_else(|| anyhow::anyhow!("Malformed trusted comment"))?
+        .to_string();
+    let global_bytes = STANDARD
+        .decode(next("global signature")?)
+        .map_err(|_| anyhow::anyhow!("Invalid global signature encoding"))?;
+
+    if sig_bytes.len() != 74 {
+        return Err(anyhow::anyhow!("Invalid signature length"));
+    }
+    if sig_bytes[2..10] != key_id(key) {
+        return Err(anyhow::anyhow!("Signature was made with a different key"));
+    }
+    let raw: [u8; 64] = sig_bytes[10..].try_into()?;
+    let signature = Signature::from_bytes(&raw);
+    let verified = match &sig_bytes[..2] {
+        b"ED" => key.verify(&Blake2b512::digest(content), &signature),
+        b"Ed" => key.verify(content, &signature),
+        _ => return Err(anyhow::anyhow!("Unsupported signature algorithm")),
+    };
+    verified.map_err(|_| anyhow::anyhow!("Signature verification failed"))?;
+
+    let global: [u8; 64] = global_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid global signature length"))?;
+    let mut signed = raw.to_vec();
+    signed.extend_from_slice(trusted_comment.as_bytes());
+    key.verify(&signed, &Signature::from_bytes(&global))
+        .map_err(|_| anyhow::anyhow!("Trusted comment signature verification failed"))?;
+    Ok(trusted_comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_and_verifies_round_trip() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let public = key.verifying_key();
+        let signature = sign(&key, b"abc  dist/app.zip\n", "timestamp:1 file:SHA256SUMS");
+        assert_eq!(verify(&public, b"abc  dist/app.zip\n", &signature).unwrap(), "timestamp:1 file:SHA256SUMS");
+        assert!(verify(&public, b"tampered\n", &signature).is_err());
+        let forged = signature.replace("timestamp:1", "timestamp:2");
+        assert!(verify(&public, b"abc  dist/app.zip\n", &forged).is_err());
+
+        let other = SigningKey::from_bytes(&[4u8; 32]).verifying_key();
+        assert!(verify(&other, b"abc  dist/app.zip\n", &signature).is_err());
+    }
+
+    #[test]
+    fn parses_public_key_formats() {
+        let public = SigningKey::from_bytes(&[3u8; 32]).verifying_key();
+        assert_eq!(parse_public_key(&public_key_file(&public)).unwrap(), public);
+        assert_eq!(parse_public_key(&public_key_base64(&public)).unwrap(), public);
+        assert_eq!(parse_public_key(&hex::encode(public.to_bytes())).unwrap(), public);
+        assert!(parse_public_key("not a key").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Runtime payload caches, one per project, so building two projects in turn does not rebuild
+//! each other's payload: `<cache dir>/lunu-builder/payloads/<hash of project root>/`. The least
+//! recently used entries beyond `MAX_ENTRIES` are deleted after every build.
+
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+
+pub const MAX_ENTRIES: usize = 8;
+const PAYLOAD_FILE: &str = "payload.zip";
+const META_FILE: &str = "payload.meta";
+const USED_FILE: &str = "last_used";
+/// Single shared cache of builders before per-project caches
+const LEGACY_FILES: &[&str] = &["runtime_payload.zip", "runtime_payload.meta"];
+
+/// `lunu-builder` under the user cache directory (or `<cwd>/.cache`).
+pub fn builder_cache_dir(cwd: &Path) -> PathBuf {
+    dirs::cache_dir().unwrap_or(cwd.join(".cache")).join("lunu-builder")
+}
+
+/// The cache entry of one project.
+#[derive(Debug, Clone)]
+pub struct PayloadCache {
+    dir: PathBuf,
+}
+
+impl PayloadCache {
+    pub fn for_project(cache_dir: &Path, project_root: &Path) -> Self {
+        let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+        let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+        Self { dir: cache_dir.join("payloads").join(hex::encode(&digest[..8])) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn payload(&self) -> PathBuf {
+        self.dir.join(PAYLOAD_FILE)
+    }
+
+    pub fn meta(&self) -> PathBuf {
+        self.dir.join(META_FILE)
+    }
+
+    /// Marks the entry as just used, for `evict`.
+    pub fn touch(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        std::fs::write(self.dir.join(USED_FILE), now.to_string())
+    }
+
+    /// Deletes the entry; `false` when there was none.
+    pub fn clear(&self) -> std::io::Result<bool> {
+        if !self.dir.exists() {
+            return Ok(false);
+        }
+        std::fs::remove_dir_all(&self.dir)?;
+        Ok(true)
+    }
+}
+
+/// Deletes the least recently used entries until at most `keep` remain, plus the payload of the
+/// old single shared cache. Returns the deleted entry directories.
+pub fn evict(cache_dir: &Path, keep: usize) -> std::io::Result<Vec<PathBuf>> {
+    for legacy in LEGACY_FILES {
+        let _ = std::fs::remove_file(cache_dir.join(legacy));
+    }
+    let Ok(entries) = std::fs::read_dir(cache_dir.join("payloads")) else {
+        return Ok(Vec::new());
+    };
+    let mut entries: Vec<(u128, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let used = std::fs::read_to_string(e.path().join(USED_FILE))
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(0);
+            (used, e.path())
+        })
+        .collect();
+    entries.sort_by(|a, b| b.cmp(a));
+    let mut removed = Vec::new();
+    for (_, dir) in entries.into_iter().skip(keep) {
+        std::fs::remove_dir_all(&dir)?;
+        removed.push(dir);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_one_entry_per_project_and_evicts_the_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("lunu_payload_cache_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = dir.join("cache");
+        let projects: Vec<PathBuf> = (0..3).map(|i| dir.join(format!("project{}", i))).collect();
+        let entries: Vec<PayloadCache> = projects.iter().map(|p| PayloadCache::for_project(&cache, p)).collect();
+        assert_ne!(entries[0].dir(), entries[1].dir());
+        assert_eq!(PayloadCache::for_project(&cache, &projects[0]).dir(), entries[0].dir());
+        std::fs::create_dir_all(&cache).unwrap();
+        std::fs::write(cache.join("runtime_payload.zip"), "old").unwrap();
+
+        for (used, entry) in [(3, &entries[0]), (1, &entries[1]), (2, &entries[2])] {
+            std::fs::create_dir_all(entry.dir()).unwrap();
+            std::fs::write(entry.payload(), "zip").unwrap();
+            std::fs::write(entry.dir().join(USED_FILE), used.to_string()).unwrap();
+        }
+        let removed = evict(&cache, 2).unwrap();
+        assert_eq!(removed, vec![entries[1].dir().to_path_buf()]);
+        assert!(entries[0].payload().exists() && entries[2].payload().exists());
+        assert!(!cache.join("runtime_payload.zip").exists());
+
+        assert!(entries[0].clear().unwrap());
+        assert!(!entries[0].clear().unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,76 @@
+//! Machine-readable progress (`lunu --progress json`): one JSON object per line on stderr, so
+//! GUIs and editor extensions can draw progress bars without parsing the human-readable output.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProgressEvent {
+    /// Always `"progress"`, so consumers can tell events from other stderr output
+    pub event: String,
+    pub phase: String,
+    /// 0-100, or `None` when the total is unknown
+    pub percent: Option<f64>,
+    pub message: String,
+}
+
+impl ProgressEvent {
+    pub fn new(phase: &str, percent: Option<f64>, message: &str) -> Self {
+        Self {
+            event: "progress".to_string(),
+            phase: phase.to_string(),
+            percent: percent.map(|p| (p.clamp(0.0, 100.0) * 10.0).round() / 10.0),
+            message: message.to_string(),
+        }
+    }
+
+    /// Parses a stderr line, returning `None` for anything that is not a progress event.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str::<Self>(line).ok().filter(|e| e.event == "progress")
+    }
+}
+
+pub fn enable_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+pub fn emit(phase: &str, percent: Option<f64>, message: &str) {
+    if !is_json() {
+        return;
+    }
+    if let Ok(line) = serde_json::to_string(&ProgressEvent::new(phase, percent, message)) {
+        eprintln!("{}", line);
+    }
+}
+
+/// Percentage of `done` out of `total` steps.
+pub fn fraction(done: usize, total: usize) -> Option<f64> {
+    (total > 0).then(|| done as f64 * 100.0 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip_and_clamp() {
+        let event = ProgressEvent::new("install", Some(133.333), "Installing json");
+        assert_eq!(event.percent, Some(100.0));
+        let line = serde_json::to_string(&event).unwrap();
+        assert_eq!(ProgressEvent::parse(&line), Some(event));
+        assert_eq!(ProgressEvent::new("build", fraction(1, 3), "").percent, Some(33.3));
+        assert!(ProgressEvent::parse("error: build failed").is_none());
+        assert!(ProgressEvent::parse("{\"event\":\"other\",\"phase\":\"x\",\"percent\":null,\"message\":\"\"}").is_none());
+        assert_eq!(fraction(0, 0), None);
+    }
+}
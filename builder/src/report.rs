@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+const LARGEST_FILES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntrySize {
+    pub name: String,
+    pub compressed: u64,
+    pub uncompressed: u64,
+}
+
+/// Size breakdown of a payload, printed after `lunu build`.
+#[derive(Debug, Default)]
+pub struct SizeReport {
+    pub stub: u64,
+    pub entries: Vec<EntrySize>,
+}
+
+#[derive(Default)]
+struct DirNode {
+    compressed: u64,
+    uncompressed: u64,
+    files: u64,
+    children: BTreeMap<String, DirNode>,
+}
+
+impl SizeReport {
+    pub fn from_payload(stub: u64, payload: &[u8]) -> anyhow::Result<Self> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(payload))?;
+        let mut entries = Vec::new();
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            entries.push(EntrySize {
+                name: file.name().to_string(),
+                compressed: file.compressed_size(),
+                uncompressed: file.size(),
+            });
+        }
+        Ok(Self { stub, entries })
+    }
+
+    pub fn total_compressed(&self) -> u64 {
+        self.entries.iter().map(|e| e.compressed).sum()
+    }
+
+    /// Compressed/uncompressed totals per top-level directory, largest first.
+    pub fn by_top_level(&self) -> Vec<(String, u64, u64)> {
+        let mut groups: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for entry in &self.entries {
+            let top = match entry.name.split_once('/') {
+                Some((dir, _)) => format!("{}/", dir),
+                None => entry.name.clone(),
+            };
+            let group = groups.entry(top).or_default();
+            group.0 += entry.compressed;
+            group.1 += entry.uncompressed;
+        }
+        let mut groups: Vec<(String, u64, u64)> = groups.into_iter().map(|(k, (c, u))| (k, c, u)).collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        groups
+    }
+
+    pub fn largest(&self, count: usize) -> Vec<&EntrySize> {
+        let mut entries: Vec<&EntrySize> = self.entries.iter().collect();
+        entries.sort_by(|a, b| b.compressed.cmp(&a.compressed).then_with(|| a.name.cmp(&b.name)));
+        entries.truncate(count);
+        entries
+    }
+
+    pub fn summary(&self) -> String {
+        let total = self.stub + self.total_compressed();
+        let mut out = format!("Size report ({} total):\n", format_size(total));
+        out.push_str(&format!("  {:<28} {:>10}\n", "stub", format_size(self.stub)));
+        for (name, compressed, uncompressed) in self.by_top_level() {
+            out.push_str(&format!(
+                "  {:<28} {:>10}  ({} uncompressed, {:.1}%)\n",
+                name,
+                format_size(compressed),
+                format_size(uncompressed),
+                percent(compressed, total)
+            ));
+        }
+        out.push_str("Largest files:\n");
+        for entry in self.largest(LARGEST_FILES) {
+            out.push_str(&format!("  {:>10}  {}\n", format_size(entry.compressed), entry.name));
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Every directory and file with its compressed size, largest first at each level.
+    pub fn tree(&self) -> String {
+        let mut root = DirNode::default();
+        for entry in &self.entries {
+            let mut node = &mut root;
+            node.compressed += entry.compressed;
+            node.uncompressed += entry.uncompressed;
+            node.files += 1;
+            for part in entry.name.split('/').filter(|p| !p.is_empty()) {
+                node = node.children.entry(part.to_string()).or_default();
+                node.compressed += entry.compressed;
+                node.uncompressed += entry.uncompressed;
+                node.files += 1;
+            }
+        }
+        let mut out = format!(
+            "Payload tree ({} compressed, {} uncompressed, {} files):\n",
+            format_size(root.compressed),
+            format_size(root.uncompressed),
+            root.files
+        );
+        render_tree(&root, 1, &mut out);
+        out.trim_end().to_string()
+    }
+}
+
+fn render_tree(node: &DirNode, depth: usize, out: &mut String) {
+    let mut children: Vec<(&String, &DirNode)> = node.children.iter().collect();
+    children.sort_by(|a, b| b.1.compressed.cmp(&a.1.compressed).then_with(|| a.0.cmp(b.0)));
+    for (name, child) in children {
+        let label = if child.children.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/ ({} files)", name, child.files)
+        };
+        out.push_str(&format!("{:>10}  {}{}\n", format_size(child.compressed), "  ".repeat(depth - 1), label));
+        render_tree(child, depth + 1, out);
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    fn report() -> SizeReport {
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(Cursor::new(&mut buf));
+            let stored = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            for (name, size) in [("bin/lune.exe", 5000), ("modules/ml/model.bin", 3000), ("modules/ml/worker.py", 100), ("src/main.luau", 10)] {
+                zip.start_file(name, stored).unwrap();
+                zip.write_all(&vec![b'x'; size]).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        SizeReport::from_payload(1000, &buf).unwrap()
+    }
+
+    #[test]
+    fn groups_by_top_level_and_finds_largest() {
+        let report = report();
+        let groups = report.by_top_level();
+        assert_eq!(groups[0], ("bin/".to_string(), 5000, 5000));
+        assert_eq!(groups[1].0, "modules/");
+        assert_eq!(groups[1].1, 3100);
+        assert_eq!(report.largest(2)[1].name, "modules/ml/model.bin");
+        assert!(report.summary().contains("Largest files:"));
+    }
+
+    #[test]
+    fn tree_nests_directories() {
+        let tree = report().tree();
+        assert!(tree.contains("modules/ (2 files)"));
+        assert!(tree.contains("    model.bin"));
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(12), "12 B");
+    }
+}
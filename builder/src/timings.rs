@@ -0,0 +1,142 @@
+//! Phase timings and cache statistics for `lunu --timings`. Recording is a no-op until enabled,
+//! so instrumented code paths cost nothing on normal runs.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Phases in the order they are reported; anything else is listed after them.
+pub const PHASES: &[&str] = &["resolution", "network", "extraction", "compile", "zip"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static PHASE_TIMES: Mutex<BTreeMap<String, (Duration, u32)>> = Mutex::new(BTreeMap::new());
+static CACHES: Mutex<BTreeMap<String, (u32, u32)>> = Mutex::new(BTreeMap::new());
+
+pub fn enable(enabled: bool) {
+    STARTED.get_or_init(Instant::now);
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn record(phase: &str, elapsed: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    let mut phases = PHASE_TIMES.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = phases.entry(phase.to_string()).or_default();
+    entry.0 += elapsed;
+    entry.1 += 1;
+}
+
+/// Adds the time until the returned guard is dropped to `phase`.
+pub fn span(phase: &'static str) -> Span {
+    Span { phase, start: Instant::now() }
+}
+
+pub struct Span {
+    phase: &'static str,
+    start: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(self.phase, self.start.elapsed());
+    }
+}
+
+fn count_cache(cache: &str, hit: bool) {
+    if !is_enabled() {
+        return;
+    }
+    let mut caches = CACHES.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = caches.entry(cache.to_string()).or_default();
+    if hit {
+        entry.0 += 1;
+    } else {
+        entry.1 += 1;
+    }
+}
+
+pub fn cache_hit(cache: &str) {
+    count_cache(cache, true);
+}
+
+pub fn cache_miss(cache: &str) {
+    count_cache(cache, false);
+}
+
+fn format_duration(d: Duration) -> String {
+    if d.as_secs() >= 1 {
+        format!("{:.2}s", d.as_secs_f64())
+    } else {
+        format!("{}ms", d.as_millis())
+    }
+}
+
+/// The `--timings` table: every phase that ran (known phases first), then cache hits/misses.
+/// Phases can overlap (a download inside resolution), so they need not add up to the total,
+/// which is measured from the first `enable` call.
+pub fn report() -> String {
+    let total = STARTED.get().map(Instant::elapsed).unwrap_or_default();
+    let phases = PHASE_TIMES.lock().unwrap_or_else(|e| e.into_inner());
+    let caches = CACHES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut out = String::from("Timings:\n");
+    let ordered = PHASES
+        .iter()
+        .filter_map(|p| phases.get_key_value(*p))
+        .chain(phases.iter().filter(|(p, _)| !PHASES.contains(&p.as_str())));
+    let mut any = false;
+    for (phase, (elapsed, count)) in ordered {
+        any = true;
+        let calls = if *count == 1 { String::new() } else { format!(" ({} calls)", count) };
+        out.push_str(&format!("  {:<12}{:>10}{}\n", phase, format_duration(*elapsed), calls));
+    }
+    if !any {
+        out.push_str("  (no phases recorded)\n");
+    }
+    out.push_str(&format!("  {:<12}{:>10}\n", "total", format_duration(total)));
+    if !caches.is_empty() {
+        out.push_str("Cache:\n");
+        for (cache, (hits, misses)) in caches.iter() {
+            out.push_str(&format!("  {:<12}{} hit, {} miss\n", cache, hits, misses));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_phases_in_order_with_cache_counts() {
+        record("zip", Duration::from_millis(5));
+        cache_hit("runtime");
+        assert!(!report().contains("zip"));
+
+        enable(true);
+        record("zip", Duration::from_millis(20));
+        record("network", Duration::from_millis(1500));
+        record("network", Duration::from_millis(500));
+        drop(span("resolution"));
+        cache_hit("runtime");
+        cache_hit("runtime");
+        cache_miss("metadata");
+        let text = report();
+        enable(false);
+
+        let network = text.find("network").unwrap();
+        assert!(text.find("resolution").unwrap() < network);
+        assert!(network < text.find("zip").unwrap());
+        assert!(text.contains("2.00s (2 calls)"));
+        assert!(text.contains("20ms\n"));
+        assert!(text.contains("total"));
+        assert!(text.contains("runtime     2 hit, 0 miss"));
+        assert!(text.contains("metadata    0 hit, 1 miss"));
+    }
+}
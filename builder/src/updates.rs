@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::FileOptions;
+use zip::ZipArchive;
+use crate::manifest::{BuildManifest, UpdateChannel, UpdateSettings, MANIFEST_NAME};
+
+/// Lists removed entries and the version a delta payload applies to.
+const DELTA_INFO_NAME: &str = "lunu_delta.json";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Minimum time between two update checks of a built app, so only a few launches a day wait on
+/// the network (up to `CHECK_TIMEOUT`).
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Update manifest served at `[build.updates] url`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateManifest {
+    #[serde(default)]
+    pub channels: BTreeMap<String, ChannelRelease>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelRelease {
+    pub version: String,
+    pub payload: Artifact,
+    /// Deltas keyed by the version they upgrade from.
+    #[serde(default)]
+    pub deltas: BTreeMap<String, Artifact>,
+}
+
+/// A downloadable payload. Relative URLs are resolved against the update manifest URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub url: String,
+    pub sha256: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DeltaInfo {
+    from: String,
+    to: String,
+    #[serde(default)]
+    removed: Vec<String>,
+}
+
+/// Writes a new signing key to `path` and returns the public key for `[build.updates] public_key`.
+pub fn generate_key(path: &Path) -> anyhow::Result<String> {
+    if path.exists() {
+        return Err(anyhow::anyhow!("Refusing to overwrite existing key at {:?}", path));
+    }
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| anyhow::anyhow!("Failed to generate key: {}", e))?;
+    let key = SigningKey::from_bytes(&seed);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // The seed is the private key; only its owner may read it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(hex::encode(seed).as_bytes())?;
+    Ok(hex::encode(key.verifying_key().to_bytes()))
+}
+
+pub fn load_signing_key(path: &Path) -> anyhow::Result<SigningKey> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read signing key {:?}: {}", path, e))?;
+    let bytes: [u8; 32] = hex::decode(content.trim())?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Signing key {:?} must be 32 hex-encoded bytes", path))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+pub fn sign_artifact(key: &SigningKey, url: &str, bytes: &[u8]) -> Artifact {
+    Artifact {
+        url: url.to_string(),
+        sha256: hex::encode(Sha256::digest(bytes)),
+        signature: hex::encode(key.sign(bytes).to_bytes()),
+    }
+}
+
+pub fn verify_artifact(public_key: &str, artifact: &Artifact, bytes: &[u8]) -> anyhow::Result<()> {
+    if hex::encode(Sha256::digest(bytes)) != artifact.sha256.to_lowercase() {
+        return Err(anyhow::anyhow!("Checksum mismatch for {}", artifact.url));
+    }
+    let key: [u8; 32] = hex::decode(public_key.trim())?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid update public key"))?;
+    let signature: [u8; 64] = hex::decode(artifact.signature.trim())?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid signature for {}", artifact.url))?;
+    VerifyingKey::from_bytes(&key)?
+        .verify(bytes, &Signature::from_bytes(&signature))
+        .map_err(|_| anyhow::anyhow!("Signature verification failed for {}", artifact.url))
+}
+
+/// Parses a release version, allowing a leading `v` and a missing minor or patch (`v2`, `1.4`).
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let split = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(split);
+    let missing = 3usize.saturating_sub(core.split('.').count());
+    semver::Version::parse(&format!("{}{}{}", core, ".0".repeat(missing), suffix)).ok()
+}
+
+/// Whether `candidate` is a later release than `current` by semver precedence, so a pre-release
+/// (`1.0.0-rc.2`) is older than its release (`1.0.0`). Unparseable versions are never newer.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+/// File next to the executable whose modification time records the last update check.
+pub fn check_stamp_path(exe_path: &Path) -> PathBuf {
+    exe_path.with_extension("update-check")
+}
+
+/// Whether the last update check recorded at `stamp` is more than `interval` ago (or unknown).
+/// A due check is recorded right away, so a failing one is not retried on every launch either.
+pub fn take_check(stamp: &Path, interval: Duration) -> bool {
+    let last = fs::metadata(stamp).and_then(|m| m.modified()).ok();
+    let due = last.and_then(|t| t.elapsed().ok()).is_none_or(|age| age >= interval);
+    if due {
+        let _ = fs::write(stamp, b"");
+    }
+    due
+}
+
+pub fn resolve_url(manifest_url: &str, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    match manifest_url.rfind('/') {
+        Some(idx) => format!("{}/{}", &manifest_url[..idx], url.trim_start_matches('/')),
+        None => url.to_string(),
+    }
+}
+
+fn read_entries<R: Read + Seek>(archive: &mut ZipArchive<R>) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        entries.insert(file.name().to_string(), content);
+    }
+    Ok(entries)
+}
+
+/// Builds a delta holding only the entries that changed between two payloads.
+pub fn create_delta(old_payload: &[u8], new_payload: &[u8], from: &str, to: &str) -> anyhow::Result<Vec<u8>> {
+    let old = read_entries(&mut ZipArchive::new(Cursor::new(old_payload))?)?;
+    let new = read_entries(&mut ZipArchive::new(Cursor::new(new_payload))?)?;
+    let info = DeltaInfo {
+        from: from.to_string(),
+        to: to.to_string(),
+        removed: old.keys().filter(|name| !new.contains_key(*name)).cloned().collect(),
+    };
+
+    let mut buffer = Vec::new();
+    let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(DELTA_INFO_NAME, options)?;
+    zip.write_all(serde_json::to_string(&info)?.as_bytes())?;
+    for (name, content) in &new {
+        if old.get(name) != Some(content) {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(content)?;
+        }
+    }
+    zip.finish()?;
+    drop(zip);
+    Ok(buffer)
+}
+
+/// Rebuilds a full payload from the current one plus a delta, copying entries without recompressing.
+pub fn apply_delta<R: Read + Seek>(current: &mut ZipArchive<R>, current_version: &str, delta: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut delta = ZipArchive::new(Cursor::new(delta))?;
+    let info: DeltaInfo = {
+        let mut content = String::new();
+        delta.by_name(DELTA_INFO_NAME)?.read_to_string(&mut content)?;
+        serde_json::from_str(&content)?
+    };
+    if info.from != current_version {
+        return Err(anyhow::anyhow!("Delta applies to {} but this build is {}", info.from, current_version));
+    }
+    let replaced: Vec<String> = delta.file_names().map(String::from).collect();
+
+    let mut buffer = Vec::new();
+    let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+    for i in 0..current.len() {
+        let file = current.by_index_raw(i)?;
+        let name = file.name().to_string();
+        if info.removed.contains(&name) || replaced.contains(&name) {
+            continue;
+        }
+        zip.raw_copy_file(file)?;
+    }
+    for i in 0..delta.len() {
+        let file = delta.by_index_raw(i)?;
+        if file.name() == DELTA_INFO_NAME {
+            continue;
+        }
+        zip.raw_copy_file(file)?;
+    }
+    zip.finish()?;
+    drop(zip);
+    Ok(buffer)
+}
+
+fn download(url: &str, timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    let response = ureq::get(url).timeout(timeout).call()?;
+    let mut bytes = Vec::new();
+    response.into_reader().read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn fetch_release(channel: &UpdateChannel) -> anyhow::Result<Option<ChannelRelease>> {
+    let manifest: UpdateManifest = serde_json::from_slice(&download(&channel.url, CHECK_TIMEOUT)?)?;
+    Ok(manifest.channels.get(&channel.channel).cloned())
+}
+
+fn read_payload_manifest(payload: &[u8]) -> anyhow::Result<BuildManifest> {
+    let mut archive = ZipArchive::new(Cursor::new(payload))?;
+    let mut content = String::new();
+    archive.by_name(MANIFEST_NAME)?.read_to_string(&mut content)?;
+    BuildManifest::from_json(&content).ok_or_else(|| anyhow::anyhow!("Invalid manifest in update payload"))
+}
+
+/// Called by the stub before extraction. Downloads and installs a newer payload when the
+/// channel advertises one, and returns the path of the replaced executable so it can be relaunched.
+pub fn check_and_apply(exe_path: &Path, manifest: &BuildManifest) -> anyhow::Result<Option<PathBuf>> {
+    let Some(channel) = &manifest.updates else { return Ok(None) };
+    let Some(release) = fetch_release(channel)? else { return Ok(None) };
+    if !is_newer(&release.version, &manifest.version) {
+        return Ok(None);
+    }
+
+    let mut archive = ZipArchive::new(File::open(exe_path)?)?;
+    let stub_len = archive.offset() as usize;
+
+    // Prefer the delta for this version and fall back to the full payload if it does not apply
+    let mut payload = None;
+    if let Some(delta) = release.deltas.get(&manifest.version) {
+        let attempt = download(&resolve_url(&channel.url, &delta.url), DOWNLOAD_TIMEOUT).and_then(|bytes| {
+            verify_artifact(&channel.public_key, delta, &bytes)?;
+            apply_delta(&mut archive, &manifest.version, &bytes)
+        });
+        match attempt {
+            Ok(bytes) => payload = Some(bytes),
+            Err(e) => eprintln!("[Lunu Stub] Delta update failed ({}), downloading full payload.", e),
+        }
+    }
+    let payload = match payload {
+        Some(bytes) => bytes,
+        None => {
+            let bytes = download(&resolve_url(&channel.url, &release.payload.url), DOWNLOAD_TIMEOUT)?;
+            verify_artifact(&channel.public_key, &release.payload, &bytes)?;
+            bytes
+        }
+    };
+    let new_manifest = read_payload_manifest(&payload)?;
+    if new_manifest.version != release.version {
+        return Err(anyhow::anyhow!(
+            "Update payload is version {} but the channel advertised {}",
+            new_manifest.version,
+            release.version
+        ));
+    }
+
+    let mut stub = vec![0u8; stub_len];
+    File::open(exe_path)?.read_exact(&mut stub)?;
+    install_executable(exe_path, &stub, &payload)?;
+    eprintln!("[Lunu Stub] Updated {} from {} to {}.", manifest.app, manifest.version, release.version);
+    Ok(Some(exe_path.to_path_buf()))
+}
+
+/// Swaps in the new executable with renames so a failed write never leaves a broken app behind.
+/// A running executable cannot be deleted on Windows, so the old one is moved aside and
+/// removed on the next start by `cleanup_previous`.
+fn install_executable(exe_path: &Path, stub: &[u8], payload: &[u8]) -> anyhow::Result<()> {
+    let new_path = exe_path.with_extension("new");
+    let old_path = exe_path.with_extension("old");
+    {
+        let mut file = File::create(&new_path)?;
+        file.write_all(stub)?;
+        file.write_all(payload)?;
+        file.sync_all()?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new_path, fs::Permissions::from_mode(0o755))?;
+    }
+    let _ = fs::remove_file(&old_path);
+    fs::rename(exe_path, &old_path)?;
+    if let Err(e) = fs::rename(&new_path, exe_path) {
+        let _ = fs::rename(&old_path, exe_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+pub fn cleanup_previous(exe_path: &Path) {
+    let _ = fs::remove_file(exe_path.with_extension("old"));
+    let _ = fs::remove_file(exe_path.with_extension("new"));
+}
+
+fn manifest_file_name(url: &str) -> String {
+    url.rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("updates.json")
+        .to_string()
+}
+
+/// Writes the signed payload (and a delta from the previous build, when one is found) next to
+/// the executable and merges the release into the update manifest for publishing.
+pub fn write_release(out_dir: &Path, stem: &str, settings: &UpdateSettings, payload: &[u8]) -> anyhow::Result<PathBuf> {
+    let key_path = settings
+        .signing_key
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("[build.updates] signing_key is required to publish updates"))?;
+    let key = load_signing_key(key_path)?;
+    if hex::encode(key.verifying_key().to_bytes()) != settings.channel.public_key.trim().to_lowercase() {
+        return Err(anyhow::anyhow!("signing_key does not match [build.updates] public_key"));
+    }
+
+    let payload_name = format!("{}-{}.payload", stem, settings.version);
+    fs::write(out_dir.join(&payload_name), payload)?;
+    let mut release = ChannelRelease {
+        version: settings.version.clone(),
+        payload: sign_artifact(&key, &payload_name, payload),
+        deltas: BTreeMap::new(),
+    };
+
+    let manifest_path = out_dir.join(manifest_file_name(&settings.channel.url));
+    let mut manifest: UpdateManifest = fs::read_to_string(&manifest_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+
+    if let Some(previous) = manifest.channels.get(&settings.channel.channel) {
+        let previous_path = out_dir.join(&previous.payload.url);
+        if previous.version != settings.version {
+            if let Ok(old_payload) = fs::read(&previous_path) {
+                let delta = create_delta(&old_payload, payload, &previous.version, &settings.version)?;
+                let delta_name = format!("{}-{}-to-{}.delta", stem, previous.version, settings.version);
+                fs::write(out_dir.join(&delta_name), &delta)?;
+                release.deltas.insert(previous.version.clone(), sign_artifact(&key, &delta_name, &delta));
+            }
+        }
+    }
+
+    manifest.channels.insert(settings.channel.channel.clone(), release);
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut buffer));
+        for (name, content) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        drop(zip);
+        buffer
+    }
+
+    #[test]
+    fn signatures_verify_and_reject_tampering() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public = hex::encode(key.verifying_key().to_bytes());
+        let artifact = sign_artifact(&key, "app.payload", b"payload");
+        assert!(verify_artifact(&public, &artifact, b"payload").is_ok());
+        assert!(verify_artifact(&public, &artifact, b"tampered").is_err());
+        let other = hex::encode(SigningKey::from_bytes(&[8u8; 32]).verifying_key().to_bytes());
+        assert!(verify_artifact(&other, &artifact, b"payload").is_err());
+    }
+
+    #[test]
+    fn delta_roundtrip_rebuilds_new_payload() {
+        let old = payload(&[("src/main.luau", "print(1)"), ("assets/a.txt", "a"), ("assets/gone.txt", "x")]);
+        let new = payload(&[("src/main.luau", "print(2)"), ("assets/a.txt", "a"), ("assets/b.txt", "b")]);
+        let delta = create_delta(&old, &new, "1.0.0", "1.1.0").unwrap();
+
+        let delta_entries = read_entries(&mut ZipArchive::new(Cursor::new(&delta)).unwrap()).unwrap();
+        assert!(!delta_entries.contains_key("assets/a.txt"));
+
+        let mut current = ZipArchive::new(Cursor::new(old)).unwrap();
+        let rebuilt = apply_delta(&mut current, "1.0.0", &delta).unwrap();
+        let rebuilt = read_entries(&mut ZipArchive::new(Cursor::new(rebuilt)).unwrap()).unwrap();
+        let expected = read_entries(&mut ZipArchive::new(Cursor::new(new)).unwrap()).unwrap();
+        assert_eq!(rebuilt, expected);
+
+        let mut current = ZipArchive::new(Cursor::new(payload(&[("a", "a")]))).unwrap();
+        assert!(apply_delta(&mut current, "0.9.0", &delta).is_err());
+    }
+
+    #[test]
+    fn keys_are_private_and_checks_are_rate_limited() {
+        let dir = std::env::temp_dir().join(format!("lunu-updates-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let key = dir.join("update.key");
+        let public = generate_key(&key).unwrap();
+        assert_eq!(hex::encode(load_signing_key(&key).unwrap().verifying_key().to_bytes()), public);
+        assert!(generate_key(&key).is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&key).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let stamp = check_stamp_path(&dir.join("app.exe"));
+        assert!(take_check(&stamp, CHECK_INTERVAL));
+        assert!(!take_check(&stamp, CHECK_INTERVAL));
+        assert!(take_check(&stamp, Duration::ZERO));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn compares_versions_and_resolves_urls() {
+        assert!(is_newer("1.10.0", "1.9.3"));
+        assert!(is_newer("v2.0", "1.99.99"));
+        assert!(!is_newer("1.0.0", "1.0"));
+        assert!(!is_newer("1.0.0-rc.2", "1.0.0"));
+        assert!(is_newer("1.0.0", "1.0.0-rc.2"));
+        assert!(is_newer("1.0.0-rc.10", "1.0.0-rc.2"));
+        assert!(is_newer("v2", "1.9"));
+        assert!(!is_newer("nightly", "1.0.0"));
+        assert_eq!(resolve_url("https://example.com/app/updates.json", "app-1.0.payload"), "https://example.com/app/app-1.0.payload");
+        assert_eq!(resolve_url("https://example.com/u.json", "https://cdn.example.com/p"), "https://cdn.example.com/p");
+    }
+}
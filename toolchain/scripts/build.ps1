@@ -1,3 +1,8 @@
+param(
+    # Optional Rust target triple, e.g. aarch64-pc-windows-msvc
+    [string]$Target = ""
+)
+
 $ErrorActionPreference = "Stop"
 
 Write-Host "Building Lunu CLI..." -ForegroundColor Cyan
@@ -21,20 +26,28 @@ if (-not (Test-Path $BinDir)) {
 
 cd "$PSScriptRoot\.."
 
+$TargetArgs = @()
+$ReleaseDir = "release"
+if ($Target -ne "") {
+    Write-Host "Target: $Target"
+    $TargetArgs = @("--target", $Target)
+    $ReleaseDir = "$Target\release"
+}
+
 # 1. Build Builder & Stub (in ../builder)
 Write-Host "Compiling builder binaries..."
 Push-Location "$PSScriptRoot\..\..\builder"
-cargo build --release
+cargo build --release @TargetArgs
 if ($LASTEXITCODE -ne 0) { Write-Error "Builder build failed"; exit 1 }
 Pop-Location
 
 # 2. Build Installer (which embeds all 3 binaries)
 Write-Host "Compiling installer (embedding payloads)..."
-cargo build --release --bin lunu
+cargo build --release --bin lunu @TargetArgs
 if ($LASTEXITCODE -ne 0) { Write-Error "Installer build failed"; exit 1 }
 
 # 3. Copy only the installer to bin
-$InstallerTarget = "$PSScriptRoot\..\target\release\lunu.exe"
+$InstallerTarget = "$PSScriptRoot\..\target\$ReleaseDir\lunu.exe"
 
 if (Test-Path $InstallerTarget) {
     Copy-Item $InstallerTarget "$BinDir\lunu.exe" -Force
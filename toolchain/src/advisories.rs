@@ -0,0 +1,183 @@
+//! Security advisories that `lunu audit --advisories` checks locked dependencies against. A feed
+//! is a JSON index, read from a file or fetched from a URL:
+//!
+//! ```json
+//! { "advisories": [{
+//!     "id": "LUNU-2026-0001",
+//!     "package": "github.com/acme/json",
+//!     "versions": "<1.4.2",
+//!     "commits": ["9f1e980"],
+//!     "severity": "high",
+//!     "summary": "decode() runs loadstring on untrusted input",
+//!     "url": "https://example.com/advisories/LUNU-2026-0001"
+//! }] }
+//! ```
+//!
+//! `package` is a repository in any URL form (`owner/*` for every repository of an owner, as in
+//! the denylist) or a registry package such as `wally:scope/name`. A dependency is affected when
+//! its locked version is in `versions` or its locked commit starts with one of `commits`; an
+//! advisory with neither affects every version.
+
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::denylist;
+use crate::http;
+use crate::lock::LockEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Moderate => "moderate",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    /// Semver range of affected versions, e.g. `>=1.0, <1.4.2`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub versions: Option<String>,
+    /// Affected commits (full or abbreviated SHAs)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<String>,
+    pub severity: Severity,
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Feed {
+    pub advisories: Vec<Advisory>,
+}
+
+/// Feed used without `--advisory-feed`: `$LUNU_ADVISORY_FEED`, else `~/.lunu/advisories.json` when
+/// it exists.
+pub fn default_source() -> Option<String> {
+    if let Some(source) = std::env::var("LUNU_ADVISORY_FEED").ok().filter(|s| !s.trim().is_empty()) {
+        return Some(source);
+    }
+    dirs::home_dir().map(|home| home.join(".lunu").join("advisories.json")).filter(|p| p.exists()).map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Reads the feed at `source`, an `http(s)://` URL or a file path.
+pub async fn load(source: &str) -> Result<Feed> {
+    let content = if source.starts_with("https://") || source.starts_with("http://") {
+        let client = http::client()?;
+        let bytes = http::get_bytes(&client, source).await.with_context(|| format!("Failed to fetch advisory feed {}", source))?;
+        String::from_utf8(bytes).context("Advisory feed is not UTF-8")?
+    } else {
+        let path = PathBuf::from(source);
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read advisory feed {:?}", path))?
+    };
+    parse(&content)
+}
+
+pub fn parse(content: &str) -> Result<Feed> {
+    serde_json::from_str(content).context("Invalid advisory feed")
+}
+
+fn package_matches(pattern: &str, entry: &LockEntry) -> bool {
+    let pattern = pattern.trim();
+    if pattern.starts_with("pesde:") || pattern.starts_with("wally:") {
+        return entry.registry.as_deref().is_some_and(|registry| registry.eq_ignore_ascii_case(pattern));
+    }
+    entry.url.as_deref().is_some_and(|url| denylist::parse(pattern).check(url).is_some())
+}
+
+fn locked_version(entry: &LockEntry) -> Option<semver::Version> {
+    [entry.version.as_deref(), entry.tag.as_deref()]
+        .into_iter()
+        .flatten()
+        .find_map(|v| semver::Version::parse(v.trim().trim_start_matches(['v', 'V'])).ok())
+}
+
+impl Advisory {
+    /// Whether the locked `entry` is affected.
+    pub fn affects(&self, entry: &LockEntry) -> bool {
+        if !package_matches(&self.package, entry) {
+            return false;
+        }
+        if self.versions.is_none() && self.commits.is_empty() {
+            return true;
+        }
+        let commit_hit = entry.commit.as_deref().is_some_and(|commit| {
+            self.commits.iter().any(|c| c.len() >= 7 && commit.to_lowercase().starts_with(&c.to_lowercase()))
+        });
+        let version_hit = match (&self.versions, locked_version(entry)) {
+            (Some(range), Some(version)) => semver::VersionReq::parse(range).is_ok_and(|req| req.matches(&version)),
+            _ => false,
+        };
+        commit_hit || version_hit
+    }
+}
+
+impl Feed {
+    pub fn matching<'a>(&'a self, entry: &LockEntry) -> Vec<&'a Advisory> {
+        self.advisories.iter().filter(|a| a.affects(entry)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: Option<&str>, version: Option<&str>, commit: Option<&str>) -> LockEntry {
+        LockEntry {
+            url: url.map(str::to_string),
+            version: version.map(str::to_string),
+            tag: None,
+            rev: None,
+            path: None,
+            subdir: None,
+            checksum: String::new(),
+            files: Default::default(),
+            installed_at: 0,
+            mirror: None,
+            registry: None,
+            commit: commit.map(str::to_string),
+            resolver: None,
+            installed_by: None,
+            mutable: false,
+        }
+    }
+
+    #[test]
+    fn matches_packages_by_version_range_and_commit() {
+        let feed = parse(
+            r#"{"advisories": [
+                {"id": "A-1", "package": "acme/json", "versions": ">=1.0, <1.4.2", "commits": ["deadbeef"], "severity": "high", "summary": "loadstring"},
+                {"id": "A-2", "package": "evil/*", "severity": "critical", "summary": "account takeover"},
+                {"id": "A-3", "package": "wally:acme/net", "versions": "<2", "severity": "low", "summary": "timing"}
+            ]}"#,
+        )
+        .unwrap();
+        let ids = |e: &LockEntry| feed.matching(e).iter().map(|a| a.id.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(ids(&entry(Some("https://github.com/Acme/json.git"), Some("v1.3.0"), None)), vec!["A-1"]);
+        assert!(ids(&entry(Some("https://github.com/acme/json"), Some("1.4.2"), None)).is_empty());
+        assert_eq!(ids(&entry(Some("git@github.com:acme/json"), None, Some("DEADBEEF0123"))), vec!["A-1"]);
+        assert!(ids(&entry(Some("https://gitlab.com/acme/json"), Some("1.0.0"), None)).is_empty());
+        assert_eq!(ids(&entry(Some("https://github.com/evil/anything"), None, None)), vec!["A-2"]);
+
+        let mut registry = entry(None, Some("1.9.0"), None);
+        registry.registry = Some("wally:acme/net".to_string());
+        assert_eq!(ids(&registry), vec!["A-3"]);
+        assert!(Severity::Critical > Severity::High && Severity::Moderate > Severity::Low);
+    }
+}
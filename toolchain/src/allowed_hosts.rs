@@ -0,0 +1,173 @@
+//! `security.allowed_hosts` of config/settings.json: the `Host` headers the bridge answers, which
+//! keeps pages on other sites from reaching it through DNS rebinding. An entry is a hostname
+//! (`localhost`), subdomains of one (`*.internal.corp`), an IP address (`::1`) or a CIDR range
+//! (`10.0.0.0/8`, `fd00::/8`). With `security.allow_any_private`, every loopback, private and
+//! link-local address is allowed too.
+
+use std::net::IpAddr;
+use anyhow::{anyhow, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    Name(String),
+    /// `*.internal.corp`, held as `.internal.corp`; matches subdomains at any depth
+    Subdomains(String),
+    Network { addr: IpAddr, prefix: u8 },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostAllowlist {
+    patterns: Vec<Pattern>,
+    allow_any_private: bool,
+}
+
+/// The host of a `Host` header: without the port, the brackets of an IPv6 address or a trailing
+/// dot, lowercase.
+pub fn host_name(header: &str) -> String {
+    let host = header.trim();
+    let host = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        // More than one colon is a bare IPv6 address, which cannot carry a port
+        None if host.matches(':').count() == 1 => host.split(':').next().unwrap_or(host),
+        None => host,
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// `ip` as IPv4 when it is an IPv4-mapped IPv6 address (`::ffff:10.0.0.1`).
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        ip => ip,
+    }
+}
+
+fn in_network(ip: IpAddr, addr: IpAddr, prefix: u8) -> bool {
+    let mask = |bits: u32| if prefix == 0 { 0 } else { u128::MAX << (bits - u32::from(prefix)) };
+    match (ip, addr) {
+        (IpAddr::V4(ip), IpAddr::V4(addr)) => {
+            let mask = mask(32) as u32;
+            u32::from(ip) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(addr)) => {
+            let mask = mask(128);
+            u128::from(ip) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+        // Loopback, unique local (fc00::/7) and link-local (fe80::/10)
+        IpAddr::V6(v6) => v6.is_loopback() || v6.segments()[0] & 0xfe00 == 0xfc00 || v6.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+fn parse_ip(text: &str) -> Option<IpAddr> {
+    text.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+impl Pattern {
+    fn parse(entry: &str) -> Result<Self> {
+        let pattern = entry.trim().trim_end_matches('.').to_ascii_lowercase();
+        if let Some((addr, prefix)) = pattern.split_once('/') {
+            let addr = parse_ip(addr).ok_or_else(|| anyhow!("invalid address in CIDR range '{}'", entry))?;
+            let bits = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix = prefix.parse::<u8>().ok().filter(|p| *p <= bits).ok_or_else(|| {
+                anyhow!("invalid prefix length in CIDR range '{}' (0 to {})", entry, bits)
+            })?;
+            return Ok(Pattern::Network { addr, prefix });
+        }
+        if let Some(addr) = parse_ip(&pattern) {
+            let prefix = if addr.is_ipv4() { 32 } else { 128 };
+            return Ok(Pattern::Network { addr: canonical(addr), prefix });
+        }
+        if let Some(suffix) = pattern.strip_prefix("*.").filter(|s| !s.is_empty() && !s.contains('*')) {
+            return Ok(Pattern::Subdomains(format!(".{}", suffix)));
+        }
+        if pattern.is_empty() || pattern.contains('*') {
+            return Err(anyhow!("invalid host '{}': wildcards are only allowed as a leading `*.`, e.g. *.internal.corp", entry));
+        }
+        Ok(Pattern::Name(pattern))
+    }
+
+    fn matches(&self, host: &str, ip: Option<IpAddr>) -> bool {
+        match self {
+            Pattern::Name(name) => host == name,
+            Pattern::Subdomains(suffix) => host.len() > suffix.len() && host.ends_with(suffix.as_str()),
+            Pattern::Network { addr, prefix } => ip.is_some_and(|ip| in_network(ip, *addr, *prefix)),
+        }
+    }
+}
+
+impl HostAllowlist {
+    pub fn parse(entries: &[String], allow_any_private: bool) -> Result<Self> {
+        let patterns = entries.iter().map(|entry| Pattern::parse(entry)).collect::<Result<_>>()?;
+        Ok(Self { patterns, allow_any_private })
+    }
+
+    /// Whether the bridge answers requests with this `Host` header (port optional).
+    pub fn allows(&self, header: &str) -> bool {
+        let host = host_name(header);
+        let ip = parse_ip(&host).map(canonical);
+        if self.allow_any_private && (host == "localhost" || ip.is_some_and(is_private)) {
+            return true;
+        }
+        self.patterns.iter().any(|pattern| pattern.matches(&host, ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(entries: &[&str], allow_any_private: bool) -> HostAllowlist {
+        HostAllowlist::parse(&entries.iter().map(|e| e.to_string()).collect::<Vec<_>>(), allow_any_private).unwrap()
+    }
+
+    #[test]
+    fn strips_ports_from_hosts() {
+        assert_eq!(host_name("localhost:8000"), "localhost");
+        assert_eq!(host_name("API.Internal.Corp."), "api.internal.corp");
+        assert_eq!(host_name("[::1]:8000"), "::1");
+        assert_eq!(host_name("[fe80::1]"), "fe80::1");
+        assert_eq!(host_name("fd00::1"), "fd00::1");
+        assert_eq!(host_name("10.0.0.5:443"), "10.0.0.5");
+    }
+
+    #[test]
+    fn matches_names_wildcards_and_ranges() {
+        let hosts = allowlist(&["localhost", "*.internal.corp", "10.0.0.0/8", "fd00::/8", "::1", "192.168.1.7"], false);
+        assert!(hosts.allows("localhost:8000"));
+        assert!(hosts.allows("api.internal.corp") && hosts.allows("a.b.internal.corp:8443"));
+        assert!(!hosts.allows("internal.corp") && !hosts.allows("evilinternal.corp"));
+        assert!(hosts.allows("10.20.30.40:8000") && !hosts.allows("11.0.0.1"));
+        assert!(hosts.allows("[::1]:8000") && hosts.allows("[fd00::abcd]") && !hosts.allows("[fe00::1]"));
+        assert!(hosts.allows("[::ffff:10.1.2.3]:80"));
+        assert!(hosts.allows("192.168.1.7") && !hosts.allows("192.168.1.8"));
+        assert!(!hosts.allows("localhost.evil.com"));
+    }
+
+    #[test]
+    fn allow_any_private_admits_local_networks() {
+        let hosts = allowlist(&["lunu.example.com"], true);
+        for host in ["127.0.0.1:8000", "localhost", "10.1.2.3", "172.16.0.1", "192.168.0.10", "169.254.1.1", "[::1]:8000", "[fd12::1]", "[fe80::1]:80"] {
+            assert!(hosts.allows(host), "{}", host);
+        }
+        for host in ["8.8.8.8", "172.32.0.1", "[2001:db8::1]", "example.com"] {
+            assert!(!hosts.allows(host), "{}", host);
+        }
+        assert!(hosts.allows("lunu.example.com:443"));
+        assert!(!allowlist(&[], false).allows("127.0.0.1"));
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        for entry in ["10.0.0.0/33", "fd00::/129", "10.0.0/8", "*", "api.*.corp", "*."] {
+            assert!(HostAllowlist::parse(&[entry.to_string()], false).is_err(), "{}", entry);
+        }
+        assert!(allowlist(&["0.0.0.0/0"], false).allows("203.0.113.9"));
+    }
+}
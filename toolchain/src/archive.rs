@@ -0,0 +1,190 @@
+//! Download formats for `url` dependencies that are not git repositories: `.zip`, `.tar`,
+//! `.tar.gz`, `.tar.xz` and `.tar.zst` archives, and raw single-file `.luau`/`.lua` modules.
+//! The URL's extension only marks it as a download; the format is sniffed from the content's magic
+//! bytes, since release assets are often served under a different name than they are packed with.
+
+use std::io::Read;
+use std::path::Path;
+use anyhow::{Context, Result};
+use lunu_builder::timings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    /// A single Luau source file, installed as the module's `init.luau`
+    Luau,
+}
+
+const EXTENSIONS: &[(&str, ArchiveFormat)] = &[
+    (".zip", ArchiveFormat::Zip),
+    (".tar", ArchiveFormat::Tar),
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tzst", ArchiveFormat::TarZst),
+    (".luau", ArchiveFormat::Luau),
+    (".lua", ArchiveFormat::Luau),
+];
+
+/// Format named by the extension of `url`'s path (query and fragment ignored).
+pub fn from_extension(url: &str) -> Option<ArchiveFormat> {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    EXTENSIONS.iter().find(|(ext, _)| path.ends_with(ext)).map(|(_, format)| *format)
+}
+
+/// Whether `url` is downloaded and unpacked rather than cloned with git.
+pub fn is_download_url(url: &str) -> bool {
+    from_extension(url).is_some()
+}
+
+/// Format of `bytes` by magic number. Plain source files have none, so `Luau` is only returned
+/// for UTF-8 text when `hint` (the URL's extension) says so.
+pub fn sniff(bytes: &[u8], hint: Option<ArchiveFormat>) -> Option<ArchiveFormat> {
+    if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        return Some(ArchiveFormat::Zip);
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return Some(ArchiveFormat::TarGz);
+    }
+    if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        return Some(ArchiveFormat::TarXz);
+    }
+    if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        return Some(ArchiveFormat::TarZst);
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return Some(ArchiveFormat::Tar);
+    }
+    (hint == Some(ArchiveFormat::Luau) && std::str::from_utf8(bytes).is_ok()).then_some(ArchiveFormat::Luau)
+}
+
+/// Unpacks `bytes` into `dest`, detecting the format with `sniff`. Entries escaping `dest` are
+/// rejected (zip) or skipped (tar).
+pub fn unpack(bytes: &[u8], dest: &Path, hint: Option<ArchiveFormat>) -> Result<ArchiveFormat> {
+    let _timing = timings::span("extraction");
+    let format = sniff(bytes, hint).ok_or_else(|| anyhow::anyhow!("Unrecognized archive format (expected zip, tar, tar.gz, tar.xz, tar.zst or a .luau file)"))?;
+    std::fs::create_dir_all(dest)?;
+    match format {
+        ArchiveFormat::Zip => unpack_zip(bytes, dest)?,
+        ArchiveFormat::Tar => unpack_tar(bytes, dest)?,
+        ArchiveFormat::TarGz => unpack_tar(flate2::read::GzDecoder::new(bytes), dest)?,
+        ArchiveFormat::TarXz => unpack_tar(xz2::read::XzDecoder::new(bytes), dest)?,
+        ArchiveFormat::TarZst => unpack_tar(zstd::stream::read::Decoder::new(bytes)?, dest)?,
+        ArchiveFormat::Luau => std::fs::write(dest.join("init.luau"), bytes)?,
+    }
+    Ok(format)
+}
+
+fn unpack_tar(reader: impl Read, dest: &Path) -> Result<()> {
+    tar::Archive::new(reader)
+        .unpack(dest)
+        .with_context(|| format!("Failed to extract archive into {:?}", dest))
+}
+
+fn unpack_zip(bytes: &[u8], dest: &Path) -> Result<()> {
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).context("Invalid zip archive")?;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let relative = entry
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow::anyhow!("Archive entry escapes the module directory: {}", entry.name()))?;
+        let path = dest.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        std::fs::write(&path, content)?;
+    }
+    Ok(())
+}
+
+/// Moves the contents of a lone top-level directory (`json-1.2.0/` in release tarballs) up into
+/// `dir`.
+pub fn strip_single_root(dir: &Path) -> Result<()> {
+    let entries: Vec<_> = std::fs::read_dir(dir)?.flatten().collect();
+    let [only] = entries.as_slice() else {
+        return Ok(());
+    };
+    if !only.path().is_dir() {
+        return Ok(());
+    }
+    let nested = dir.join(format!(".lunu-unpack-{}", std::process::id()));
+    std::fs::rename(only.path(), &nested)?;
+    for entry in std::fs::read_dir(&nested)?.flatten() {
+        std::fs::rename(entry.path(), dir.join(entry.file_name()))?;
+    }
+    std::fs::remove_dir(&nested)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn tar_bytes(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+        tar.into_inner().unwrap()
+    }
+
+    #[test]
+    fn sniffs_formats_regardless_of_the_url() {
+        assert_eq!(from_extension("https://example.com/json-1.2.0.tar.zst?raw=1"), Some(ArchiveFormat::TarZst));
+        assert_eq!(from_extension("https://github.com/acme/json"), None);
+        assert!(is_download_url("https://example.com/JSON.LUAU"));
+
+        let tar = tar_bytes(&[("json-1.2.0/init.luau", "return {}")]);
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(&tar).unwrap();
+        let zst = zstd::stream::encode_all(tar.as_slice(), 0).unwrap();
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("init.luau", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"return {}").unwrap();
+
+        // A .tar.gz link serving zstd is still read as zstd
+        let hint = Some(ArchiveFormat::TarGz);
+        assert_eq!(sniff(&zst, hint), Some(ArchiveFormat::TarZst));
+        assert_eq!(sniff(&xz.finish().unwrap(), hint), Some(ArchiveFormat::TarXz));
+        assert_eq!(sniff(&gz.finish().unwrap(), None), Some(ArchiveFormat::TarGz));
+        assert_eq!(sniff(&tar, None), Some(ArchiveFormat::Tar));
+        assert_eq!(sniff(&zip.finish().unwrap().into_inner(), None), Some(ArchiveFormat::Zip));
+        assert_eq!(sniff(b"return {}", Some(ArchiveFormat::Luau)), Some(ArchiveFormat::Luau));
+        assert_eq!(sniff(b"<html>Not found</html>", hint), None);
+    }
+
+    #[test]
+    fn unpacks_and_strips_the_release_directory() {
+        let tar = tar_bytes(&[("json-1.2.0/init.luau", "return {}"), ("json-1.2.0/src/decode.luau", "return nil")]);
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("json");
+        let zst = zstd::stream::encode_all(tar.as_slice(), 0).unwrap();
+        assert_eq!(unpack(&zst, &dest, None).unwrap(), ArchiveFormat::TarZst);
+        strip_single_root(&dest).unwrap();
+        assert!(dest.join("init.luau").is_file() && dest.join("src/decode.luau").is_file());
+
+        let single = dir.path().join("single");
+        unpack(b"return 1", &single, from_extension("https://example.com/single.luau")).unwrap();
+        strip_single_root(&single).unwrap();
+        assert_eq!(std::fs::read_to_string(single.join("init.luau")).unwrap(), "return 1");
+    }
+}
@@ -0,0 +1,299 @@
+//! Append-only record of bridge calls, enabled with `"audit"` in config/settings.json. Each line of
+//! `logs/audit.jsonl` names the caller's key (by hash), the module and method, a hash of every
+//! argument, the status and the duration. Argument values themselves are never written, and the
+//! argument hashes are keyed with `.lunu/audit.key`, so short or guessable values cannot be
+//! recovered from the log by hashing candidates.
+//!
+//! The active file is only ever appended to. Once it passes `max_size_mb` it is renamed to
+//! `audit-<unix ms>.jsonl`; rotated files beyond `max_files` or older than `retention_days` are deleted.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use crate::secret_store;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_file")]
+    pub file: String,
+    /// Rotate the active file once it grows past this size
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Rotated files to keep; older ones are deleted first
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Delete rotated files older than this; 0 keeps them until `max_files` is reached
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+fn default_file() -> String {
+    "logs/audit.jsonl".to_string()
+}
+
+fn default_max_size_mb() -> u64 {
+    10
+}
+
+fn default_max_files() -> usize {
+    10
+}
+
+fn default_retention_days() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    /// Unix time in milliseconds
+    pub ts: u64,
+    /// `key_id` of the API key the caller sent; absent when auth is disabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub module: String,
+    pub method: String,
+    pub args: Vec<String>,
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+/// Short, stable identifier for an API key that does not reveal it.
+pub fn key_id(key: &str) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..8])
+}
+
+/// HMAC-SHA256 (RFC 2104) of `message` under `key`.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+/// HMAC of each argument's JSON encoding under `key`, so identical calls can be matched without
+/// logging data.
+pub fn hash_args(key: &[u8], args: &[Value]) -> Vec<String> {
+    args.iter()
+        .map(|arg| hex::encode(hmac_sha256(key, arg.to_string().as_bytes())))
+        .collect()
+}
+
+/// Where the key of the argument hashes is kept, readable only by the current user.
+pub fn key_path(base_dir: &Path) -> PathBuf {
+    base_dir.join(".lunu").join("audit.key")
+}
+
+/// The key of `key_path`, created on first use. It stays the same across restarts so records of
+/// different runs can still be matched.
+fn load_or_create_key(base_dir: &Path) -> std::io::Result<Vec<u8>> {
+    let path = key_path(base_dir);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => hex::decode(content.trim()).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{:?}: {}", path, e))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let mut key = vec![0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut key);
+            secret_store::write_private(&path, &hex::encode(&key)).map_err(std::io::Error::other)?;
+            Ok(key)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    key: Vec<u8>,
+    max_bytes: u64,
+    max_files: usize,
+    retention: Option<Duration>,
+    /// Serializes appends and rotation between concurrent requests
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    /// `file` is resolved against `base_dir` when relative. Expired rotated files are pruned here.
+    pub fn open(base_dir: &Path, settings: &AuditSettings) -> std::io::Result<Self> {
+        let path = base_dir.join(&settings.file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let log = Self {
+            path,
+            key: load_or_create_key(base_dir)?,
+            max_bytes: settings.max_size_mb.max(1) * 1024 * 1024,
+            max_files: settings.max_files,
+            retention: (settings.retention_days > 0).then(|| Duration::from_secs(settings.retention_days * 86400)),
+            lock: Mutex::new(()),
+        };
+        log.prune()?;
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `hash_args` under this log's key.
+    pub fn hash_args(&self, args: &[Value]) -> Vec<String> {
+        hash_args(&self.key, args)
+    }
+
+    pub fn append(&self, record: &AuditRecord) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            let rotated = self.rotated_path(now_ms());
+            std::fs::rename(&self.path, rotated)?;
+            self.prune()?;
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    fn parts(&self) -> (String, String) {
+        let stem = self.path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let ext = self.path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        (stem, ext)
+    }
+
+    fn rotated_path(&self, stamp: u64) -> PathBuf {
+        let (stem, ext) = self.parts();
+        let name = if ext.is_empty() { format!("{}-{}", stem, stamp) } else { format!("{}-{}.{}", stem, stamp, ext) };
+        self.path.with_file_name(name)
+    }
+
+    /// Rotated files, oldest first (the stamp in the name sorts numerically).
+    fn rotated_files(&self) -> Vec<(u64, PathBuf)> {
+        let (stem, ext) = self.parts();
+        let prefix = format!("{}-", stem);
+        let suffix = if ext.is_empty() { String::new() } else { format!(".{}", ext) };
+        let Some(dir) = self.path.parent() else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut files: Vec<(u64, PathBuf)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let stamp = name.strip_prefix(&prefix)?.strip_suffix(suffix.as_str())?.parse().ok()?;
+                Some((stamp, entry.path()))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn prune(&self) -> std::io::Result<()> {
+        let files = self.rotated_files();
+        let cutoff = self.retention.map(|keep| now_ms().saturating_sub(keep.as_millis() as u64));
+        let excess = files.len().saturating_sub(self.max_files);
+        for (index, (stamp, path)) in files.iter().enumerate() {
+            if index < excess || cutoff.is_some_and(|cutoff| *stamp < cutoff) {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn record(method: &str) -> AuditRecord {
+        AuditRecord {
+            ts: now_ms(),
+            key_id: Some(key_id("secret")),
+            module: "ml".to_string(),
+            method: method.to_string(),
+            args: hash_args(b"key", &[json!(1), json!("two")]),
+            status: 200,
+            duration_ms: 3,
+        }
+    }
+
+    #[test]
+    fn appends_records_without_argument_values() {
+        let dir = tempdir().unwrap();
+        let settings: AuditSettings = serde_json::from_value(json!({ "enabled": true })).unwrap();
+        let log = AuditLog::open(dir.path(), &settings).unwrap();
+        log.append(&record("fit")).unwrap();
+        log.append(&record("predict")).unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join("logs").join("audit.jsonl")).unwrap();
+        let lines: Vec<AuditRecord> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].method, "predict");
+        assert_eq!(lines[0].args.len(), 2);
+        assert!(!content.contains("two") && !content.contains("secret"));
+    }
+
+    #[test]
+    fn argument_hashes_are_keyed() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")[..4], [0x60, 0xe4, 0x31, 0x59]);
+        assert_ne!(hash_args(b"one", &[json!(1)]), hash_args(b"two", &[json!(1)]));
+
+        let dir = tempdir().unwrap();
+        let settings: AuditSettings = serde_json::from_value(json!({ "enabled": true })).unwrap();
+        let first = AuditLog::open(dir.path(), &settings).unwrap().hash_args(&[json!("pin")]);
+        assert_eq!(AuditLog::open(dir.path(), &settings).unwrap().hash_args(&[json!("pin")]), first);
+        assert_ne!(first[0], hex::encode(Sha256::digest(b"\"pin\"")));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(key_path(dir.path())).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn rotates_and_prunes_old_files() {
+        let dir = tempdir().unwrap();
+        let settings = AuditSettings {
+            enabled: true,
+            file: "audit.jsonl".to_string(),
+            max_size_mb: 1,
+            max_files: 2,
+            retention_days: 1,
+        };
+        let mut log = AuditLog::open(dir.path(), &settings).unwrap();
+        log.max_bytes = 1;
+        // Expired by age, and one too many by count
+        std::fs::write(log.rotated_path(1), "").unwrap();
+        for stamp in [now_ms() - 3000, now_ms() - 2000, now_ms() - 1000] {
+            std::fs::write(log.rotated_path(stamp), "").unwrap();
+        }
+        log.append(&record("fit")).unwrap();
+        log.append(&record("fit")).unwrap();
+
+        let rotated = log.rotated_files();
+        assert_eq!(rotated.len(), 2);
+        assert!(rotated.iter().all(|(stamp, _)| *stamp > now_ms() - 2500));
+        assert_eq!(std::fs::read_to_string(log.path()).unwrap().lines().count(), 1);
+    }
+}
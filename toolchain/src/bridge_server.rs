@@ -9,33 +9,43 @@ use axum::{
 use serde::{Deserialize, Serialize, Deserializer};
 use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::SocketAddr,
     path::{Path as StdPath, PathBuf},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{ChildStdin, Command};
-use tokio::sync::{Mutex, oneshot};
-use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{Mutex, Notify, broadcast, oneshot};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use rand::RngCore;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use crate::{allowed_hosts, audit_log, http, permissions, response_cache, secret_store, worker_pool, worker_requires};
+use crate::response_cache::{CacheSpec, ResponseCache};
+use crate::worker_scheduler::{Priority, Scheduler};
+use crate::config_schema::{self, ConfigFile};
 
 #[derive(Clone, Deserialize)]
 struct ServerConfig {
     host: String,
     http_port: u16,
     ssl_enabled: bool,
+    #[serde(rename = "ssl_cert_path")]
     _ssl_cert_path: String,
+    #[serde(rename = "ssl_key_path")]
     _ssl_key_path: String,
 }
 
 #[derive(Clone, Deserialize)]
 struct SecurityConfig {
     auth_enabled: bool,
+    /// Hostnames, `*.` wildcards, IP addresses and CIDR ranges (see `allowed_hosts`)
     allowed_hosts: Vec<String>,
+    /// Also allow any loopback, private or link-local address
+    #[serde(default)]
+    allow_any_private: bool,
 }
 
 #[derive(Clone, Deserialize)]
@@ -50,6 +60,8 @@ struct Config {
     security: SecurityConfig,
     logging: LoggingConfig,
     modules_dir: Option<String>,
+    #[serde(default)]
+    audit: Option<audit_log::AuditSettings>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -57,12 +69,81 @@ struct Secrets {
     api_key: String,
 }
 
+/// How long a shutdown waits for in-flight calls before stopping workers anyway.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a worker gets to exit after its stdin closes before it is killed.
+const WORKER_STOP_GRACE: Duration = Duration::from_secs(2);
+
 struct AppState {
     config: Config,
     secrets: Secrets,
-    _base_dir: PathBuf,
+    base_dir: PathBuf,
     modules_dir: PathBuf,
+    /// `Host` headers the bridge answers, from `security`
+    hosts: allowed_hosts::HostAllowlist,
+    /// Running workers by `instance_key`
     workers: Mutex<HashMap<String, Arc<WorkerHandle>>>,
+    /// Next pool instance to call, by module
+    rotation: std::sync::Mutex<HashMap<String, usize>>,
+    /// Modules with a legacy `commands` bridge.json that were warned about
+    legacy_warned: std::sync::Mutex<HashSet<String>>,
+    events: Option<broadcast::Sender<BridgeEvent>>,
+    audit: Option<audit_log::AuditLog>,
+    http: reqwest::Client,
+    /// Results of methods that declare `"cache"` (see `response_cache`)
+    cache: ResponseCache,
+    /// Set by `/api/v1/system/shutdown`; new module calls are refused from then on
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    /// Notified once draining is done, to stop the HTTP server
+    stopped: Arc<Notify>,
+}
+
+impl AppState {
+    /// Instance of `module_name`'s worker pool for the next call, each in turn.
+    fn next_instance(&self, module_name: &str, pool_size: usize) -> usize {
+        let mut rotation = self.rotation.lock().unwrap_or_else(|e| e.into_inner());
+        let next = rotation.entry(module_name.to_string()).or_insert(0);
+        let index = *next % pool_size;
+        *next = index + 1;
+        index
+    }
+}
+
+/// Key of instance `index` of a module's worker pool in `AppState::workers`: the module name for
+/// the first, `name#<index>` for the others. Persistent workers use it as their registry name.
+fn instance_key(module_name: &str, index: usize) -> String {
+    match index {
+        0 => module_name.to_string(),
+        _ => format!("{}#{}", module_name, index),
+    }
+}
+
+/// Decrements `in_flight` when a module call finishes, however it finishes.
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct DrainSummary {
+    completed: usize,
+    abandoned: usize,
+    workers_stopped: usize,
+    /// Persistent workers are only disconnected; they stay warm for the next session
+    workers_detached: usize,
+}
+
+/// Published for every module call when the bridge is started with an event channel.
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeEvent {
+    pub module: String,
+    pub method: String,
+    pub status: u16,
+    pub latency_ms: u64,
 }
 
 #[derive(Deserialize)]
@@ -87,8 +168,36 @@ where
 #[derive(Deserialize)]
 struct BridgeConfig {
     _protocol: Option<String>,
-    worker: WorkerSpec,
+    #[serde(default)]
+    worker: Option<WorkerSpec>,
+    /// Forward calls to another Lunu bridge instead of starting a local worker
+    #[serde(default)]
+    remote: Option<RemoteSpec>,
+    #[serde(default)]
     methods: HashMap<String, MethodSpec>,
+    /// Legacy bridge.json from before workers: a command per method, run once per call
+    #[serde(default)]
+    commands: HashMap<String, WorkerSpec>,
+}
+
+impl BridgeConfig {
+    fn local_worker(&self) -> Option<&WorkerSpec> {
+        self.worker.as_ref().filter(|w| !w.cmd.is_empty())
+    }
+
+    /// Whether this is a legacy `commands` bridge.json, served by `call_legacy_command`.
+    fn is_legacy(&self) -> bool {
+        self.local_worker().is_none() && self.remote.is_none() && !self.commands.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct RemoteSpec {
+    /// Base URL of the remote bridge, e.g. https://host:8000
+    url: String,
+    /// Environment variable holding the remote bridge's API key
+    key_env: Option<String>,
+    timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -97,16 +206,57 @@ struct WorkerSpec {
     cwd: Option<String>,
     env: Option<HashMap<String, String>>,
     timeout_ms: Option<u64>,
-    _idle_timeout_ms: Option<u64>,
-}#[derive(Deserialize)]
+    /// Stop the worker after this long without calls; the next call starts it again
+    idle_timeout_ms: Option<u64>,
+    /// Keep the worker running across dev-server restarts (see `worker_pool`)
+    #[serde(default)]
+    persistent: bool,
+    /// Start the worker with the bridge instead of on the first call
+    #[serde(default)]
+    preload: bool,
+    /// Calls the worker is sent at once; the rest wait by method priority (see `worker_scheduler`)
+    concurrency: Option<usize>,
+    /// Worker processes to run; calls go to each in turn
+    pool_size: Option<usize>,
+}
+
+impl WorkerSpec {
+    fn pool_size(&self) -> usize {
+        self.pool_size.unwrap_or(1).max(1)
+    }
+}
+#[derive(Deserialize)]
 struct MethodSpec {
     timeout_ms: Option<u64>,
+    #[serde(default)]
+    priority: Priority,
+    cache: Option<CacheSpec>,
 }
 
 struct WorkerHandle {
-    stdin: Mutex<ChildStdin>,
+    stdin: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
     pending: Mutex<HashMap<String, oneshot::Sender<Result<Value, WorkerError>>>>,
+    scheduler: Arc<Scheduler>,
     alive: AtomicBool,
+    /// Stops a child worker started by this bridge, and the task that reaps it
+    process: Mutex<Option<(oneshot::Sender<()>, tokio::task::JoinHandle<()>)>>,
+    created: Instant,
+    /// Milliseconds after `created` of the last call
+    last_active_ms: AtomicU64,
+}
+
+impl WorkerHandle {
+    fn touch(&self) {
+        self.last_active_ms.store(self.created.elapsed().as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Time since the last call started or finished, or zero while calls run or wait.
+    fn idle_for(&self) -> Duration {
+        if self.scheduler.load() != (0, 0) {
+            return Duration::ZERO;
+        }
+        self.created.elapsed().saturating_sub(Duration::from_millis(self.last_active_ms.load(Ordering::SeqCst)))
+    }
 }
 
 #[derive(Clone)]
@@ -115,11 +265,67 @@ struct WorkerError {
     message: String,
 }
 
+/// How long a preloaded worker must stay up after starting to count as healthy.
+const PRELOAD_SETTLE: Duration = Duration::from_millis(250);
+
+/// What `lunu dev` reports once it can take calls (or why it cannot), printed as one JSON line
+/// and written to `.lunu/daemon.json` for scripts waiting on it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReadyEvent {
+    /// `ready` or `failed`
+    pub event: String,
+    pub pid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Preloaded workers that started
+    #[serde(default)]
+    pub workers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub ts: u64,
+}
+
+pub fn ready_path(base_dir: &StdPath) -> PathBuf {
+    base_dir.join(".lunu").join("daemon.json")
+}
+
+pub fn read_ready(base_dir: &StdPath) -> Option<ReadyEvent> {
+    let content = std::fs::read_to_string(ready_path(base_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn publish_ready(base_dir: &StdPath, event: &ReadyEvent) -> anyhow::Result<()> {
+    let line = serde_json::to_string(event)?;
+    println!("{}", line);
+    let path = ready_path(base_dir);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written whole and renamed, so a waiting reader never sees half a file
+    let tmp = path.with_extension(format!("json.{}.tmp", event.pid));
+    std::fs::write(&tmp, line)?;
+    if cfg!(windows) && path.exists() {
+        let _ = std::fs::remove_file(&path);
+    }
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Removes `.lunu/daemon.json` when this process wrote it.
+fn clear_ready(base_dir: &StdPath) {
+    if read_ready(base_dir).is_some_and(|e| e.pid == std::process::id()) {
+        let _ = std::fs::remove_file(ready_path(base_dir));
+    }
+}
+
 pub async fn run() -> anyhow::Result<()> {
     let base_dir = std::env::current_dir()?;
-    let config = load_config(&base_dir)?;
-    let secrets = load_or_create_secrets(&base_dir)?;
-    let modules_dir = resolve_modules_dir(&base_dir, &config);
+    // A previous session's file would announce a bridge that is not there
+    let _ = std::fs::remove_file(ready_path(&base_dir));
+    let config = match load_config(&base_dir) {
+        Ok(config) => config,
+        Err(err) => return Err(report_failure(&base_dir, err)),
+    };
 
     let log_path = resolve_log_path(&base_dir, &config.logging.file);
     let log_dir = log_path.parent().unwrap_or_else(|| StdPath::new("."));
@@ -134,14 +340,145 @@ pub async fn run() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer().with_writer(file_writer))
         .init();
 
+    let (listener, app, state) = match bind(base_dir.clone(), config, None).await {
+        Ok(bound) => bound,
+        Err(err) => return Err(report_failure(&base_dir, err)),
+    };
+    let workers = match preload_workers(&state).await {
+        Ok(workers) => workers,
+        Err(err) => {
+            state.draining.store(true, Ordering::SeqCst);
+            drain(&state, Duration::ZERO).await;
+            return Err(report_failure(&base_dir, err));
+        }
+    };
+    publish_ready(&base_dir, &ReadyEvent {
+        event: "ready".to_string(),
+        pid: std::process::id(),
+        url: Some(format!("http://{}", listener.local_addr()?)),
+        workers,
+        error: None,
+        ts: audit_log::now_ms(),
+    })?;
+
+    let stopped = state.stopped.clone();
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { stopped.notified().await })
+        .await;
+    clear_ready(&base_dir);
+    served?;
+    Ok(())
+}
+
+/// Publishes a `failed` event for `err` and hands it back.
+fn report_failure(base_dir: &StdPath, err: anyhow::Error) -> anyhow::Error {
+    let event = ReadyEvent {
+        event: "failed".to_string(),
+        pid: std::process::id(),
+        url: None,
+        workers: Vec::new(),
+        error: Some(format!("{:#}", err)),
+        ts: audit_log::now_ms(),
+    };
+    if let Err(write_err) = publish_ready(base_dir, &event) {
+        error!("Failed to write {:?}: {}", ready_path(base_dir), write_err);
+    }
+    err
+}
+
+/// Starts every local worker with `"preload": true` and checks it is still running shortly
+/// after. Returns the started modules; the first one that fails is the error.
+async fn preload_workers(state: &Arc<AppState>) -> anyhow::Result<Vec<String>> {
+    let mut started = Vec::new();
+    for (name, dir) in bridge_modules(&state.modules_dir) {
+        let Ok(content) = std::fs::read_to_string(dir.join("bridge.json")) else {
+            continue;
+        };
+        let Ok(cfg) = serde_json::from_str::<BridgeConfig>(&content) else {
+            continue;
+        };
+        let Some(spec) = cfg.local_worker().filter(|w| w.preload && cfg.remote.is_none()) else {
+            continue;
+        };
+        let failed = |message: String| anyhow::anyhow!("Preloading worker '{}' failed: {}", name, message);
+        check_permissions(&state.base_dir, &name, &content).map_err(|e| failed(e.message))?;
+        let mut instances = Vec::new();
+        for index in 0..spec.pool_size() {
+            let key = instance_key(&name, index);
+            let worker = get_or_start_instance(state, &name, &key, &dir, spec, &content).await.map_err(|e| failed(e.message))?;
+            instances.push((key, worker));
+        }
+        tokio::time::sleep(PRELOAD_SETTLE).await;
+        if instances.iter().any(|(_, worker)| !worker.alive.load(Ordering::SeqCst)) {
+            let mut workers = state.workers.lock().await;
+            for (key, _) in &instances {
+                workers.remove(key);
+            }
+            return Err(failed("the worker exited right after starting".to_string()));
+        }
+        info!("Preloaded worker '{}'", name);
+        started.push(name);
+    }
+    Ok(started)
+}
+
+/// Starts the bridge for `base_dir` in the background and publishes call events to `events`.
+/// Logging is left to the caller so the bridge does not write over its terminal output.
+pub async fn spawn_with_events(base_dir: PathBuf, events: broadcast::Sender<BridgeEvent>) -> anyhow::Result<SocketAddr> {
+    let config = load_config(&base_dir)?;
+    let (listener, app, state) = bind(base_dir, config, Some(events)).await?;
+    let stopped = state.stopped.clone();
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        match axum::serve(listener, app).with_graceful_shutdown(async move { stopped.notified().await }).await {
+            // A shutdown request ends the whole process, as it does for `lunu dev`
+            Ok(()) => std::process::exit(0),
+            Err(err) => error!("Bridge server stopped: {}", err),
+        }
+    });
+    Ok(addr)
+}
+
+async fn bind(
+    base_dir: PathBuf,
+    config: Config,
+    events: Option<broadcast::Sender<BridgeEvent>>,
+) -> anyhow::Result<(tokio::net::TcpListener, Router, Arc<AppState>)> {
+    // Without auth there is nothing to check the key against; don't create one or touch the keychain
+    let secrets = if config.security.auth_enabled {
+        load_or_create_secrets(&base_dir)?
+    } else {
+        Secrets { api_key: String::new() }
+    };
+    let modules_dir = resolve_modules_dir(&base_dir, &config);
+    let audit = match config.audit.as_ref().filter(|a| a.enabled) {
+        Some(settings) => Some(
+            audit_log::AuditLog::open(&base_dir, settings)
+                .map_err(|e| anyhow::anyhow!("Failed to open audit log {:?}: {}", settings.file, e))?,
+        ),
+        None => None,
+    };
+    let hosts = allowed_hosts::HostAllowlist::parse(&config.security.allowed_hosts, config.security.allow_any_private)
+        .map_err(|e| anyhow::anyhow!("Invalid security.allowed_hosts in config/settings.json: {}", e))?;
     let host = config.server.host.clone();
     let port = config.server.http_port;
     let state = Arc::new(AppState {
         config,
         secrets,
-        _base_dir: base_dir,
+        base_dir,
         modules_dir,
+        hosts,
         workers: Mutex::new(HashMap::new()),
+        rotation: Default::default(),
+        legacy_warned: Default::default(),
+        events,
+        audit,
+        // Remote forwarding identifies itself and follows `[network]` like every other request
+        http: http::client()?,
+        cache: ResponseCache::new(response_cache::DEFAULT_CAPACITY),
+        draining: AtomicBool::new(false),
+        in_flight: AtomicUsize::new(0),
+        stopped: Arc::new(Notify::new()),
     });
 
     if state.config.server.ssl_enabled {
@@ -151,6 +488,9 @@ pub async fn run() -> anyhow::Result<()> {
     let protected = Router::new()
         .route("/api/v1/system/shutdown", post(shutdown))
         .route("/api/v1/system/info", get(system_info).post(system_info))
+        .route("/api/v1/system/modules", get(list_modules))
+        .route("/api/v1/system/metrics", get(metrics))
+        .route("/api/v1/system/cache/invalidate", post(invalidate_cache))
         .route("/api/v1/:module_name/:func_name", post(module_bridge))
         .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
 
@@ -158,18 +498,26 @@ pub async fn run() -> anyhow::Result<()> {
         .route("/health", get(health))
         .merge(protected)
         .layer(middleware::from_fn_with_state(state.clone(), host_middleware))
-        .with_state(state);
+        .with_state(state.clone());
 
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
     info!("Lunu Bridge listening on http://{}", addr);
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .map_err(|e| anyhow::anyhow!("Failed to bind bridge to {}: {}", addr, e))?;
+    Ok((listener, app, state))
 }
 
 fn load_config(base_dir: &PathBuf) -> anyhow::Result<Config> {
     let config_path = base_dir.join("config").join("settings.json");
     let content = std::fs::read_to_string(&config_path)?;
+    let issues = config_schema::validate(ConfigFile::Settings, &content)?;
+    if !issues.is_empty() {
+        let report = config_schema::format_issues(ConfigFile::Settings.file_name(), &issues);
+        if config_schema::has_errors(&issues) {
+            return Err(anyhow::anyhow!("Invalid bridge settings:\n{}", report));
+        }
+        eprintln!("{}", report);
+    }
     let config: Config = serde_json::from_str(&content)?;
     Ok(config)
 }
@@ -194,20 +542,8 @@ fn resolve_modules_dir(base_dir: &PathBuf, config: &Config) -> PathBuf {
 }
 
 fn load_or_create_secrets(base_dir: &PathBuf) -> anyhow::Result<Secrets> {
-    let secrets_path = base_dir.join("config").join(".secrets.json");
-    if secrets_path.exists() {
-        let content = std::fs::read_to_string(&secrets_path)?;
-        let secrets: Secrets = serde_json::from_str(&content)?;
-        return Ok(secrets);
-    }
-
-    let mut bytes = [0u8; 32];
-    rand::rngs::OsRng.fill_bytes(&mut bytes);
-    let api_key = hex::encode(bytes);
-    let secrets = Secrets { api_key };
-    let content = serde_json::to_string(&secrets)?;
-    std::fs::write(&secrets_path, content)?;
-    Ok(secrets)
+    let key = secret_store::load_or_create(base_dir)?;
+    Ok(Secrets { api_key: key.key })
 }
 
 async fn health() -> impl IntoResponse {
@@ -226,24 +562,250 @@ async fn system_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     }))
 }
 
-async fn shutdown() -> impl IntoResponse {
-    tokio::spawn(async {
-        tokio::time::sleep(Duration::from_millis(500)).await;
-        std::process::exit(0);
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(json!({
+        "in_flight": state.in_flight.load(Ordering::SeqCst),
+        "workers": state.workers.lock().await.len(),
+        "cache": state.cache.stats(),
+    }))
+}
+
+#[derive(Default, Deserialize)]
+struct InvalidateRequest {
+    module: Option<String>,
+    method: Option<String>,
+}
+
+/// Drops cached responses: all of them, a module's, or one method's.
+async fn invalidate_cache(State(state): State<Arc<AppState>>, body: Option<Json<InvalidateRequest>>) -> impl IntoResponse {
+    let request = body.map(|Json(r)| r).unwrap_or_default();
+    let module = request.module.map(|m| module_name_from_segment(&m).unwrap_or(m));
+    let invalidated = state.cache.invalidate(module.as_deref(), request.method.as_deref());
+    Json(json!({ "invalidated": invalidated }))
+}
+
+/// Directories under `modules_dir` with a bridge.json, by module name (`@scope/name` for nested ones).
+fn bridge_modules(modules_dir: &StdPath) -> Vec<(String, PathBuf)> {
+    let mut modules = Vec::new();
+    let Ok(entries) = std::fs::read_dir(modules_dir) else {
+        return modules;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        if name.starts_with('@') {
+            for nested in std::fs::read_dir(&path).into_iter().flatten().flatten() {
+                if nested.path().join("bridge.json").is_file() {
+                    modules.push((format!("{}/{}", name, nested.file_name().to_string_lossy()), nested.path()));
+                }
+            }
+        } else if path.join("bridge.json").is_file() {
+            modules.push((name, path));
+        }
+    }
+    modules.sort();
+    modules
+}
+
+/// Discovery: every bridge module with its methods, requested permissions and whether the
+/// `[permissions]` policy of lunu.toml lets it run.
+async fn list_modules(State(state): State<Arc<AppState>>) -> Result<Json<Value>, AppError> {
+    let policy = load_policy(&state.base_dir)?;
+    let mut modules = Vec::new();
+    for (name, dir) in bridge_modules(&state.modules_dir) {
+        let Ok(content) = std::fs::read_to_string(dir.join("bridge.json")) else {
+            continue;
+        };
+        let (Ok(cfg), Ok(requested)) = (serde_json::from_str::<BridgeConfig>(&content), permissions::declared(&content)) else {
+            modules.push(json!({ "name": name, "error": "Invalid bridge config" }));
+            continue;
+        };
+        let mut methods: Vec<&String> = if cfg.is_legacy() { cfg.commands.keys().collect() } else { cfg.methods.keys().collect() };
+        methods.sort();
+        let violations = policy.as_ref().map(|p| p.violations(&name, requested.as_ref())).unwrap_or_default();
+        modules.push(json!({
+            "name": name,
+            "kind": if cfg.remote.is_some() { "remote" } else if cfg.is_legacy() { "legacy" } else { "worker" },
+            "methods": methods,
+            "permissions": requested,
+            "allowed": violations.is_empty(),
+            "violations": violations,
+        }));
+    }
+    Ok(Json(json!({ "policy": policy.is_some(), "modules": modules })))
+}
+
+fn load_policy(base_dir: &StdPath) -> Result<Option<permissions::Policy>, AppError> {
+    permissions::Policy::load(base_dir).map_err(|e| {
+        error!("{:#}", e);
+        AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Invalid [permissions] in lunu.toml")
+    })
+}
+
+/// Refuses a local worker whose bridge.json requests more than the project policy allows.
+fn check_permissions(base_dir: &StdPath, module_name: &str, cfg_content: &str) -> Result<(), AppError> {
+    let Some(policy) = load_policy(base_dir)? else {
+        return Ok(());
+    };
+    let requested = permissions::declared(cfg_content)
+        .map_err(|_| AppError::new(StatusCode::BAD_REQUEST, "Invalid bridge config"))?;
+    let violations = policy.violations(module_name, requested.as_ref());
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let message = format!("Module '{}' is denied by [permissions] in lunu.toml: it {}", module_name, violations.join("; "));
+    error!("{}", message);
+    Err(AppError::new(StatusCode::FORBIDDEN, message))
+}
+
+async fn shutdown(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    if state.draining.swap(true, Ordering::SeqCst) {
+        return Json(json!({ "result": "already shutting down" }));
+    }
+    info!("Shutdown requested; draining {} in-flight call(s)", state.in_flight.load(Ordering::SeqCst));
+    tokio::spawn(async move {
+        let summary = drain(&state, DRAIN_TIMEOUT).await;
+        info!(
+            "Bridge shut down: {} call(s) completed, {} abandoned, {} worker(s) stopped, {} persistent worker(s) left running",
+            summary.completed, summary.abandoned, summary.workers_stopped, summary.workers_detached
+        );
+        state.stopped.notify_one();
     });
     Json(json!({ "result": "shutting down" }))
 }
 
+/// Waits up to `timeout` for in-flight calls, then stops every worker this bridge started.
+/// Expects `draining` to be set already, so no new calls arrive meanwhile.
+async fn drain(state: &AppState, timeout: Duration) -> DrainSummary {
+    let waiting = state.in_flight.load(Ordering::SeqCst);
+    let deadline = Instant::now() + timeout;
+    while state.in_flight.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let abandoned = state.in_flight.load(Ordering::SeqCst);
+    let mut summary = DrainSummary {
+        completed: waiting.saturating_sub(abandoned),
+        abandoned,
+        ..DrainSummary::default()
+    };
+
+    let workers: Vec<Arc<WorkerHandle>> = state.workers.lock().await.drain().map(|(_, w)| w).collect();
+    for worker in workers {
+        if stop_worker(&worker).await {
+            summary.workers_stopped += 1;
+        } else {
+            summary.workers_detached += 1;
+        }
+    }
+    summary
+}
+
+/// Disconnects a worker and stops its process when this bridge started it. Returns whether there
+/// was a process to stop (persistent workers are only disconnected).
+async fn stop_worker(worker: &WorkerHandle) -> bool {
+    // Dropping the writer closes the worker's stdin (or its warm-worker socket)
+    *worker.stdin.lock().await = Box::new(tokio::io::sink());
+    let Some((stop, reaper)) = worker.process.lock().await.take() else {
+        return false;
+    };
+    let _ = stop.send(());
+    let _ = reaper.await;
+    true
+}
+
+/// Stops `worker` once it has had no calls for `idle_timeout`, unless it was replaced or exited
+/// before. The next call to the module starts a new one.
+async fn stop_when_idle(state: Arc<AppState>, key: String, worker: Arc<WorkerHandle>, idle_timeout: Duration) {
+    loop {
+        tokio::time::sleep(idle_timeout.saturating_sub(worker.idle_for()).max(Duration::from_millis(10))).await;
+        if !worker.alive.load(Ordering::SeqCst) {
+            return;
+        }
+        {
+            // Calls take the worker under this lock and mark it active, so none can be handed a
+            // worker that is being stopped
+            let mut workers = state.workers.lock().await;
+            if !workers.get(&key).is_some_and(|w| Arc::ptr_eq(w, &worker)) {
+                return;
+            }
+            if worker.idle_for() < idle_timeout {
+                continue;
+            }
+            workers.remove(&key);
+        }
+        stop_worker(&worker).await;
+        info!("Stopped worker '{}' after {}ms without calls", key, idle_timeout.as_millis());
+        return;
+    }
+}
+
 async fn module_bridge(
     Path((module_name, func_name)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<Payload>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Json<Value>, AppError> {
+    state.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _in_flight = InFlight(&state.in_flight);
+    if state.draining.load(Ordering::SeqCst) {
+        return Err(AppError::new(StatusCode::SERVICE_UNAVAILABLE, "Bridge is shutting down"));
+    }
+    let started = Instant::now();
+    let ts = audit_log::now_ms();
+    let module_name = module_name_from_segment(&module_name).unwrap_or(module_name);
+    let arg_hashes = state.audit.as_ref().map(|audit| audit.hash_args(&payload.args));
+    let result = call_module(&state, &module_name, &func_name, payload).await;
+    let status = match &result {
+        Ok(_) => StatusCode::OK,
+        Err(err) => err.status,
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    if let (Some(audit), Some(args)) = (&state.audit, arg_hashes) {
+        // Auth already accepted the key, so it identifies the caller
+        let key_id = headers
+            .get("X-LUNU-KEY")
+            .and_then(|v| v.to_str().ok())
+            .filter(|_| state.config.security.auth_enabled)
+            .map(audit_log::key_id);
+        let record = audit_log::AuditRecord {
+            ts,
+            key_id,
+            module: module_name.clone(),
+            method: func_name.clone(),
+            args,
+            status: status.as_u16(),
+            duration_ms: latency_ms,
+        };
+        if let Err(err) = audit.append(&record) {
+            error!("Failed to write audit log {:?}: {}", audit.path(), err);
+        }
+    }
+    if let Some(events) = &state.events {
+        // No subscribers is fine; the monitor may have exited already
+        let _ = events.send(BridgeEvent {
+            module: module_name,
+            method: func_name,
+            status: status.as_u16(),
+            latency_ms,
+        });
+    }
+    result
+}
+
+async fn call_module(
+    state: &Arc<AppState>,
+    module_name: &str,
+    func_name: &str,
+    payload: Payload,
+) -> Result<Json<Value>, AppError> {
     if module_name == "system" {
         return Err(AppError::new(StatusCode::NOT_FOUND, "Function not found"));
     }
+    if module_name_from_segment(module_name).as_deref() != Some(module_name) {
+        return Err(AppError::new(StatusCode::NOT_FOUND, "Module not found"));
+    }
 
-    let module_dir = state.modules_dir.join(&module_name);
+    let module_dir = state.modules_dir.join(module_name);
     if !module_dir.is_dir() {
         return Err(AppError::new(StatusCode::NOT_FOUND, "Module not found"));
     }
@@ -257,16 +819,56 @@ async fn module_bridge(
         .map_err(|_| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read bridge config"))?;
     let cfg: BridgeConfig = serde_json::from_str(&cfg_content)
         .map_err(|_| AppError::new(StatusCode::BAD_REQUEST, "Invalid bridge config"))?;
+    check_permissions(&state.base_dir, module_name, &cfg_content)?;
+
+    if let Some(remote) = &cfg.remote {
+        // Without a methods list every call is forwarded and the remote decides
+        let spec = cfg.methods.get(func_name);
+        if spec.is_none() && !cfg.methods.is_empty() {
+            return Err(AppError::new(StatusCode::NOT_FOUND, "Function not found"));
+        }
+        let timeout_ms = spec.and_then(|s| s.timeout_ms).or(remote.timeout_ms).unwrap_or(15000);
+        let cache = spec.and_then(|s| s.cache).map(|c| (ResponseCache::key(module_name, func_name, &c, &payload.args), c));
+        if let Some(result) = cache.as_ref().and_then(|(key, _)| state.cache.get(key)) {
+            return Ok(Json(json!({ "result": result })));
+        }
+        let response = call_remote(state, remote, module_name, func_name, payload, timeout_ms).await?;
+        remember(state, cache, module_name, func_name, &response);
+        return Ok(response);
+    }
+
+    if cfg.is_legacy() {
+        let command = cfg.commands.get(func_name)
+            .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Function not found"))?;
+        let first = state.legacy_warned.lock().unwrap_or_else(|e| e.into_inner()).insert(module_name.to_string());
+        if first {
+            warn!(
+                "Module '{}' uses the deprecated \"commands\" bridge.json and starts a process per call; move it to \"worker\" and \"methods\"",
+                module_name
+            );
+        }
+        let timeout = Duration::from_millis(command.timeout_ms.unwrap_or(15000));
+        let result = call_legacy_command(&module_dir, command, &payload.args, timeout).await?;
+        return Ok(Json(json!({ "result": result })));
+    }
 
-    let spec = cfg.methods.get(&func_name)
+    let spec = cfg.methods.get(func_name)
         .ok_or_else(|| AppError::new(StatusCode::NOT_FOUND, "Function not found"))?;
 
-    if cfg.worker.cmd.is_empty() {
-        return Err(AppError::new(StatusCode::BAD_REQUEST, "Invalid worker command"));
+    let worker_spec = cfg.local_worker()
+        .ok_or_else(|| AppError::new(StatusCode::BAD_REQUEST, "Invalid worker command"))?;
+    let cache = spec.cache.map(|c| (ResponseCache::key(module_name, func_name, &c, &payload.args), c));
+    if let Some(result) = cache.as_ref().and_then(|(key, _)| state.cache.get(key)) {
+        return Ok(Json(json!({ "result": result })));
     }
 
-    let worker = get_or_start_worker(&state, &module_name, &module_dir, &cfg).await?;
-    let timeout_ms = spec.timeout_ms.or(cfg.worker.timeout_ms).unwrap_or(15000);
+    let (key, worker) = get_or_start_worker(state, module_name, &module_dir, worker_spec, &cfg_content).await?;
+    let timeout_ms = spec.timeout_ms.or(worker_spec.timeout_ms).unwrap_or(15000);
+    // The timeout covers the wait for a slot as well as the call itself
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+    let Ok(_slot) = tokio::time::timeout_at(deadline, worker.scheduler.acquire(spec.priority)).await else {
+        return Err(AppError::new(StatusCode::REQUEST_TIMEOUT, "Worker timeout"));
+    };
     let request_id = new_request_id();
     let request = json!({
         "id": request_id,
@@ -284,23 +886,25 @@ async fn module_bridge(
         let mut stdin = worker.stdin.lock().await;
         if let Err(_) = stdin.write_all(line.as_bytes()).await {
             remove_pending(&worker, request["id"].as_str().unwrap_or_default()).await;
-            state.workers.lock().await.remove(&module_name);
+            state.workers.lock().await.remove(&key);
             return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker write failed"));
         }
         if let Err(_) = stdin.flush().await {
             remove_pending(&worker, request["id"].as_str().unwrap_or_default()).await;
-            state.workers.lock().await.remove(&module_name);
+            state.workers.lock().await.remove(&key);
             return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker flush failed"));
         }
     }
 
-    let response = match tokio::time::timeout(Duration::from_millis(timeout_ms), rx).await {
+    let outcome = tokio::time::timeout_at(deadline, rx).await;
+    worker.touch();
+    let response = match outcome {
         Ok(Ok(Ok(value))) => value,
         Ok(Ok(Err(err))) => {
             return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, err.message));
         }
         Ok(Err(_)) => {
-            state.workers.lock().await.remove(&module_name);
+            state.workers.lock().await.remove(&key);
             return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker response failed"));
         }
         Err(_) => {
@@ -309,7 +913,105 @@ async fn module_bridge(
         }
     };
 
-    Ok(Json(json!({ "result": response })))
+    let response = Json(json!({ "result": response }));
+    remember(state, cache, module_name, func_name, &response);
+    Ok(response)
+}
+
+/// Runs one method of a legacy `commands` bridge.json in its own process. The arguments are written
+/// to its stdin as a JSON array and its stdout is the result: JSON, or else the text itself. A
+/// non-zero exit fails the call with the last line of stderr.
+async fn call_legacy_command(module_dir: &PathBuf, command: &WorkerSpec, args: &[Value], timeout: Duration) -> Result<Value, AppError> {
+    if command.cmd.is_empty() {
+        return Err(AppError::new(StatusCode::BAD_REQUEST, "Invalid worker command"));
+    }
+    let mut cmd = build_worker_command(module_dir, command)?;
+    // Dropped on timeout, which kills it
+    cmd.kill_on_drop(true);
+    let mut child = cmd.spawn().map_err(|_| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to start command"))?;
+    let input = serde_json::to_vec(args).map_err(|_| AppError::new(StatusCode::BAD_REQUEST, "Invalid payload"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written alongside the wait, so a command that prints before reading cannot block it
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+    }
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| AppError::new(StatusCode::REQUEST_TIMEOUT, "Command timeout"))?
+        .map_err(|_| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Command failed"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().rev().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+            .unwrap_or_else(|| format!("Command exited with {}", output.status));
+        return Err(AppError::new(StatusCode::INTERNAL_SERVER_ERROR, message));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = stdout.trim();
+    if stdout.is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_str(stdout).unwrap_or_else(|_| Value::String(stdout.to_string())))
+}
+
+/// Caches a successful call's result when its method declares `"cache"`.
+fn remember(state: &AppState, cache: Option<(String, CacheSpec)>, module_name: &str, func_name: &str, response: &Json<Value>) {
+    if let Some((key, spec)) = cache {
+        let result = response.0.get("result").cloned().unwrap_or(Value::Null);
+        state.cache.insert(key, module_name, func_name, result, Duration::from_millis(spec.ttl_ms));
+    }
+}
+
+/// Module name from a URL segment: `name`, or a nested `modules/@scope/name` written as
+/// `@scope~name` (or `@scope%2Fname`). `None` for anything that could leave the modules directory.
+pub fn module_name_from_segment(segment: &str) -> Option<String> {
+    let name = segment.replace('~', "/");
+    let parts: Vec<&str> = name.split('/').collect();
+    let (module, scopes) = parts.split_last()?;
+    let valid_part = |p: &str| !p.is_empty() && p != "." && p != ".." && !p.contains('\\') && !p.contains(':');
+    (valid_part(module) && !module.starts_with('@') && scopes.iter().all(|s| s.starts_with('@') && valid_part(s))).then_some(name)
+}
+
+fn remote_endpoint(base: &str, module_name: &str, func_name: &str) -> String {
+    format!("{}/api/v1/{}/{}", base.trim_end_matches('/'), module_name.replace('/', "~"), func_name)
+}
+
+/// Proxies a call to the same module on a remote bridge, passing its status and detail through.
+async fn call_remote(
+    state: &Arc<AppState>,
+    remote: &RemoteSpec,
+    module_name: &str,
+    func_name: &str,
+    payload: Payload,
+    timeout_ms: u64,
+) -> Result<Json<Value>, AppError> {
+    let mut request = state.http
+        .post(remote_endpoint(&remote.url, module_name, func_name))
+        .timeout(Duration::from_millis(timeout_ms))
+        .json(&json!({ "args": payload.args }));
+    if let Some(key_env) = &remote.key_env {
+        let key = std::env::var(key_env).map_err(|_| {
+            AppError::new(StatusCode::INTERNAL_SERVER_ERROR, format!("Remote bridge key variable {} is not set", key_env))
+        })?;
+        request = request.header("X-LUNU-KEY", key);
+    }
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() {
+            AppError::new(StatusCode::REQUEST_TIMEOUT, "Remote bridge timeout")
+        } else {
+            error!("Remote bridge {} unreachable: {}", remote.url, e);
+            AppError::new(StatusCode::BAD_GATEWAY, "Remote bridge unreachable")
+        }
+    })?;
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let body: Value = response.json().await
+        .map_err(|_| AppError::new(StatusCode::BAD_GATEWAY, "Invalid response from remote bridge"))?;
+    if status.is_success() {
+        return Ok(Json(json!({ "result": body.get("result").cloned().unwrap_or(Value::Null) })));
+    }
+    let detail = body.get("detail").and_then(Value::as_str).unwrap_or("Remote bridge error");
+    Err(AppError::new(status, format!("Remote: {}", detail)))
 }
 
 fn is_safe_path(base: &PathBuf, target: &PathBuf) -> bool {
@@ -386,24 +1088,76 @@ fn new_request_id() -> String {
     hex::encode(bytes)
 }
 
+/// The worker of `module_name`'s pool whose turn it is, started if it is not running, and its
+/// `instance_key`.
 async fn get_or_start_worker(
     state: &Arc<AppState>,
     module_name: &str,
     module_dir: &PathBuf,
-    cfg: &BridgeConfig,
+    spec: &WorkerSpec,
+    cfg_content: &str,
+) -> Result<(String, Arc<WorkerHandle>), AppError> {
+    let key = instance_key(module_name, state.next_instance(module_name, spec.pool_size()));
+    let worker = get_or_start_instance(state, module_name, &key, module_dir, spec, cfg_content).await?;
+    Ok((key, worker))
+}
+
+async fn get_or_start_instance(
+    state: &Arc<AppState>,
+    module_name: &str,
+    key: &str,
+    module_dir: &PathBuf,
+    spec: &WorkerSpec,
+    cfg_content: &str,
 ) -> Result<Arc<WorkerHandle>, AppError> {
-    if let Some(existing) = state.workers.lock().await.get(module_name).cloned() {
-        if existing.alive.load(Ordering::SeqCst) {
-            return Ok(existing);
-        }
+    if let Some(existing) = state.workers.lock().await.get(key).filter(|w| w.alive.load(Ordering::SeqCst)) {
+        // Under the workers lock, so `stop_when_idle` sees the call coming
+        existing.touch();
+        return Ok(existing.clone());
+    }
+
+    let requirements_dir = module_dir.clone();
+    let requirements_cfg = cfg_content.to_string();
+    let unsatisfied = tokio::task::spawn_blocking(move || worker_requires::check(&requirements_dir, &requirements_cfg))
+        .await
+        .map_err(|_| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to start worker"))?
+        .map_err(|e| AppError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    if let Some(requirement) = unsatisfied.first() {
+        let message = format!("Module '{}' {}. {}", module_name, requirement, requirement.remediation());
+        error!("{}", message);
+        return Err(AppError::new(StatusCode::FAILED_DEPENDENCY, message));
     }
 
-    let worker = start_worker(module_dir, &cfg.worker).await?;
-    state.workers.lock().await.insert(module_name.to_string(), worker.clone());
+    let worker = if spec.persistent {
+        let fingerprint = worker_pool::worker_fingerprint(cfg_content);
+        let stream = worker_pool::attach_or_spawn(&state.base_dir, key, module_dir, &fingerprint)
+            .await
+            .map_err(|e| {
+                error!("Warm worker '{}' unavailable: {}", key, e);
+                AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to start worker")
+            })?;
+        let (reader, writer) = stream.into_split();
+        attach_worker(Box::new(writer), reader, spec.concurrency)
+    } else {
+        start_worker(module_dir, spec).await?
+    };
+    state.workers.lock().await.insert(key.to_string(), worker.clone());
+    // Warm workers outlive the bridge on purpose (see `worker_pool`)
+    if let Some(ms) = spec.idle_timeout_ms.filter(|ms| *ms > 0 && !spec.persistent) {
+        tokio::spawn(stop_when_idle(state.clone(), key.to_string(), worker.clone(), Duration::from_millis(ms)));
+    }
     Ok(worker)
 }
 
-async fn start_worker(module_dir: &PathBuf, spec: &WorkerSpec) -> Result<Arc<WorkerHandle>, AppError> {
+/// Builds the worker process described by a module's bridge.json, with piped stdio.
+pub(crate) fn worker_command(module_dir: &StdPath, cfg_content: &str) -> anyhow::Result<Command> {
+    let cfg: BridgeConfig = serde_json::from_str(cfg_content)
+        .map_err(|e| anyhow::anyhow!("Invalid bridge config: {}", e))?;
+    let spec = cfg.local_worker().ok_or_else(|| anyhow::anyhow!("Invalid worker command"))?;
+    build_worker_command(&module_dir.to_path_buf(), spec).map_err(|e| anyhow::anyhow!(e.message))
+}
+
+fn build_worker_command(module_dir: &PathBuf, spec: &WorkerSpec) -> Result<Command, AppError> {
     // Security: Validate exec path is within allowed directories or is a system command
     let exec_path = resolve_exec_path(module_dir, &spec.cmd[0]);
     if !is_safe_path(module_dir, &exec_path) {
@@ -429,16 +1183,58 @@ async fn start_worker(module_dir: &PathBuf, spec: &WorkerSpec) -> Result<Arc<Wor
     cmd.stdin(std::process::Stdio::piped());
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
+    Ok(cmd)
+}
 
+async fn start_worker(module_dir: &PathBuf, spec: &WorkerSpec) -> Result<Arc<WorkerHandle>, AppError> {
+    let mut cmd = build_worker_command(module_dir, spec)?;
     let mut child = cmd.spawn().map_err(|_| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to start worker"))?;
     let stdin = child.stdin.take().ok_or_else(|| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker stdin unavailable"))?;
     let stdout = child.stdout.take().ok_or_else(|| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker stdout unavailable"))?;
     let stderr = child.stderr.take().ok_or_else(|| AppError::new(StatusCode::INTERNAL_SERVER_ERROR, "Worker stderr unavailable"))?;
 
+    let handle = attach_worker(Box::new(stdin), stdout, spec.concurrency);
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !line.trim().is_empty() {
+                error!("{}", line);
+            }
+        }
+    });
+
+    let (stop, stop_rx) = oneshot::channel::<()>();
+    let reaper = tokio::spawn(async move {
+        tokio::select! {
+            _ = child.wait() => {}
+            Ok(()) = stop_rx => {
+                // Workers normally exit on stdin EOF; kill the ones that do not
+                if tokio::time::timeout(WORKER_STOP_GRACE, child.wait()).await.is_err() {
+                    let _ = child.kill().await;
+                }
+            }
+        }
+    });
+    *handle.process.lock().await = Some((stop, reaper));
+
+    Ok(handle)
+}
+
+/// Wires a worker connection (child stdio or a warm worker socket) into a handle that sends it
+/// `concurrency` calls at a time (one by default).
+fn attach_worker<R>(writer: Box<dyn AsyncWrite + Send + Unpin>, stdout: R, concurrency: Option<usize>) -> Arc<WorkerHandle>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
     let handle = Arc::new(WorkerHandle {
-        stdin: Mutex::new(stdin),
+        stdin: Mutex::new(writer),
         pending: Mutex::new(HashMap::new()),
+        scheduler: Scheduler::new(concurrency.unwrap_or(1)),
         alive: AtomicBool::new(true),
+        process: Mutex::new(None),
+        created: Instant::now(),
+        last_active_ms: AtomicU64::new(0),
     });
 
     let reader_handle = handle.clone();
@@ -467,20 +1263,7 @@ async fn start_worker(module_dir: &PathBuf, spec: &WorkerSpec) -> Result<Arc<Wor
         }
     });
 
-    tokio::spawn(async move {
-        let mut lines = BufReader::new(stderr).lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            if !line.trim().is_empty() {
-                error!("{}", line);
-            }
-        }
-    });
-
-    tokio::spawn(async move {
-        let _ = child.wait().await;
-    });
-
-    Ok(handle)
+    handle
 }
 
 fn response_id(value: &Value) -> Option<String> {
@@ -560,12 +1343,440 @@ async fn host_middleware(
         .and_then(|v| v.to_str().ok())
         .unwrap_or_default();
 
-    if !host.is_empty() {
-        let host_only = host.split(':').next().unwrap_or(host);
-        if !state.config.security.allowed_hosts.iter().any(|h| h == host_only) {
-            return Err(AppError::new(StatusCode::BAD_REQUEST, "Host not allowed"));
-        }
+    if !host.is_empty() && !state.hosts.allows(host) {
+        return Err(AppError::new(StatusCode::BAD_REQUEST, "Host not allowed"));
     }
 
     Ok(next.run(request).await)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    async fn fake_remote(
+        Path((module_name, func_name)): Path<(String, String)>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> Response {
+        if headers.get("X-LUNU-KEY").and_then(|v| v.to_str().ok()) != Some("remote-secret") {
+            return (StatusCode::FORBIDDEN, Json(json!({ "detail": "Invalid API Key" }))).into_response();
+        }
+        if func_name == "missing" {
+            return (StatusCode::NOT_FOUND, Json(json!({ "detail": "Function not found" }))).into_response();
+        }
+        Json(json!({ "result": { "module": module_name, "method": func_name, "args": body["args"] } })).into_response()
+    }
+
+    fn write_settings(base_dir: &StdPath) {
+        std::fs::create_dir_all(base_dir.join("config")).unwrap();
+        std::fs::write(base_dir.join("config").join("settings.json"), json!({
+            "server": { "host": "127.0.0.1", "http_port": 0, "ssl_enabled": false, "ssl_cert_path": "", "ssl_key_path": "" },
+            "security": { "auth_enabled": false, "allowed_hosts": ["127.0.0.1"] },
+            "logging": { "level": "info", "file": "logs/server.log" }
+        }).to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn remote_modules_are_proxied() {
+        let remote = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let remote_addr = remote.local_addr().unwrap();
+        let app = Router::new().route("/api/v1/:module_name/:func_name", post(fake_remote));
+        tokio::spawn(async move { axum::serve(remote, app).await.unwrap() });
+
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        let module = dir.path().join("modules").join("trainer");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), json!({
+            "remote": { "url": format!("http://{}/", remote_addr), "key_env": "LUNU_TEST_REMOTE_KEY" }
+        }).to_string()).unwrap();
+        std::env::set_var("LUNU_TEST_REMOTE_KEY", "remote-secret");
+
+        let (events, _) = broadcast::channel(8);
+        let addr = spawn_with_events(dir.path().to_path_buf(), events).await.unwrap();
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/trainer/fit", addr))
+            .json(&json!({ "args": [1, "two"] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"], json!({ "module": "trainer", "method": "fit", "args": [1, "two"] }));
+
+        let response = client
+            .post(format!("http://{}/api/v1/trainer/missing", addr))
+            .json(&json!({ "args": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["detail"], "Remote: Function not found");
+
+        let nested = dir.path().join("modules").join("@lab").join("trainer");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::copy(module.join("bridge.json"), nested.join("bridge.json")).unwrap();
+        let response = client
+            .post(format!("http://{}/api/v1/@lab~trainer/fit", addr))
+            .json(&json!({ "args": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["result"]["module"], "@lab~trainer");
+    }
+
+    #[tokio::test]
+    async fn workers_with_unmet_requirements_are_not_started() {
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        let module = dir.path().join("modules").join("ml");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), json!({
+            "worker": { "cmd": ["lunu-missing-interpreter", "worker.py"] },
+            "requires": { "lunu-missing-interpreter": ">=3.10" },
+            "methods": { "predict": {} }
+        }).to_string()).unwrap();
+
+        let (events, _) = broadcast::channel(8);
+        let addr = spawn_with_events(dir.path().to_path_buf(), events).await.unwrap();
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/api/v1/ml/predict", addr))
+            .json(&json!({ "args": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 424);
+        let body: Value = response.json().await.unwrap();
+        let detail = body["detail"].as_str().unwrap();
+        assert!(detail.starts_with("Module 'ml' needs lunu-missing-interpreter >=3.10"), "{}", detail);
+    }
+
+    #[tokio::test]
+    async fn permission_policy_denies_and_lists_modules() {
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        std::fs::write(dir.path().join("lunu.toml"), "[project]\nname = \"app\"\nentry = \"src/main.luau\"\nmodules_dir = \"modules\"\n\n[permissions]\nfilesystem = [\"data\"]\n").unwrap();
+        let module = dir.path().join("modules").join("ml");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), json!({
+            "worker": { "cmd": ["lunu-missing-interpreter", "worker.py"] },
+            "permissions": { "network": true, "filesystem": ["data/models"] },
+            "methods": { "predict": {} }
+        }).to_string()).unwrap();
+
+        let (events, _) = broadcast::channel(8);
+        let addr = spawn_with_events(dir.path().to_path_buf(), events).await.unwrap();
+        let client = reqwest::Client::new();
+        let response = client
+            .post(format!("http://{}/api/v1/ml/predict", addr))
+            .json(&json!({ "args": [] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 403);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["detail"], "Module 'ml' is denied by [permissions] in lunu.toml: it requests network access");
+
+        let body: Value = client.get(format!("http://{}/api/v1/system/modules", addr)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(body["policy"], true);
+        assert_eq!(body["modules"][0]["name"], "ml");
+        assert_eq!(body["modules"][0]["methods"], json!(["predict"]));
+        assert_eq!(body["modules"][0]["permissions"], json!({ "network": true, "filesystem": ["data/models"], "subprocess": false }));
+        assert_eq!(body["modules"][0]["allowed"], false);
+    }
+
+    #[tokio::test]
+    async fn permission_policy_denies_remote_modules() {
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        std::fs::write(dir.path().join("lunu.toml"), "[project]\nname = \"app\"\nentry = \"src/main.luau\"\nmodules_dir = \"modules\"\n\n[permissions]\n").unwrap();
+        for (name, bridge) in [
+            ("quiet", json!({ "remote": { "url": "http://127.0.0.1:9/", "key_env": "LUNU_TEST_DENIED_KEY" } })),
+            ("loud", json!({ "remote": { "url": "http://127.0.0.1:9/" }, "permissions": {} })),
+        ] {
+            let module = dir.path().join("modules").join(name);
+            std::fs::create_dir_all(&module).unwrap();
+            std::fs::write(module.join("bridge.json"), bridge.to_string()).unwrap();
+        }
+
+        let (events, _) = broadcast::channel(8);
+        let addr = spawn_with_events(dir.path().to_path_buf(), events).await.unwrap();
+        let client = reqwest::Client::new();
+        for (name, reason) in [("quiet", "declares no permissions in bridge.json"), ("loud", "requests network access")] {
+            let response = client
+                .post(format!("http://{}/api/v1/{}/fit", addr, name))
+                .json(&json!({ "args": [] }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status().as_u16(), 403);
+            let body: Value = response.json().await.unwrap();
+            assert_eq!(body["detail"], format!("Module '{}' is denied by [permissions] in lunu.toml: it {}", name, reason));
+        }
+
+        let body: Value = client.get(format!("http://{}/api/v1/system/modules", addr)).send().await.unwrap().json().await.unwrap();
+        let modules = body["modules"].as_array().unwrap();
+        assert!(modules.iter().all(|m| m["kind"] == "remote" && m["allowed"] == false), "{:?}", modules);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cached_methods_skip_the_worker() {
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        let module = dir.path().join("modules").join("geo");
+        std::fs::create_dir_all(&module).unwrap();
+        // Answers each call with the number of calls it has seen
+        let worker = r#"n=0; while read -r line; do n=$((n+1)); id=$(printf '%s' "$line" | sed 's/.*"id":"\([^"]*\)".*/\1/'); printf '{"id":"%s","result":%d}\n' "$id" "$n"; done"#;
+        std::fs::write(module.join("bridge.json"), json!({
+            "worker": { "cmd": ["sh", "-c", worker] },
+            "methods": { "lookup": { "cache": { "ttl_ms": 60000 } }, "now": {} }
+        }).to_string()).unwrap();
+
+        let addr = spawn_with_events(dir.path().to_path_buf(), broadcast::channel(8).0).await.unwrap();
+        let client = reqwest::Client::new();
+        let call = |method: &'static str, args: Value| {
+            let request = client.post(format!("http://{}/api/v1/geo/{}", addr, method)).json(&json!({ "args": args }));
+            async move { request.send().await.unwrap().json::<Value>().await.unwrap()["result"].clone() }
+        };
+        assert_eq!(call("lookup", json!(["paris"])).await, 1);
+        assert_eq!(call("lookup", json!(["paris"])).await, 1);
+        assert_eq!(call("lookup", json!(["rome"])).await, 2);
+        assert_eq!(call("now", json!([])).await, 3);
+        assert_eq!(call("now", json!([])).await, 4);
+
+        let metrics: Value = client.get(format!("http://{}/api/v1/system/metrics", addr)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(metrics["cache"]["hits"], 1);
+        assert_eq!(metrics["cache"]["misses"], 2);
+        assert_eq!(metrics["cache"]["entries"], 2);
+
+        let invalidated: Value = client
+            .post(format!("http://{}/api/v1/system/cache/invalidate", addr))
+            .json(&json!({ "module": "geo", "method": "lookup" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(invalidated["invalidated"], 2);
+        assert_eq!(call("lookup", json!(["paris"])).await, 5);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn legacy_commands_run_once_per_call() {
+        let dir = tempdir().unwrap();
+        write_settings(dir.path());
+        let module = dir.path().join("modules").join("old");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), json!({
+            "commands": {
+                "echo": { "cmd": ["sh", "-c", "cat"] },
+                "greet": { "cmd": ["sh", "-c", "echo hello"] },
+                "fail": { "cmd": ["sh", "-c", "echo 'no model loaded' >&2; exit 3"] },
+                "hang": { "cmd": ["sleep", "5"], "timeout_ms": 100 }
+            }
+        }).to_string()).unwrap();
+
+        let addr = spawn_with_events(dir.path().to_path_buf(), broadcast::channel(8).0).await.unwrap();
+        let client = reqwest::Client::new();
+        let call = |method: &'static str, args: Value| {
+            let request = client.post(format!("http://{}/api/v1/old/{}", addr, method)).json(&json!({ "args": args }));
+            async move {
+                let response = request.send().await.unwrap();
+                (response.status().as_u16(), response.json::<Value>().await.unwrap())
+            }
+        };
+        assert_eq!(call("echo", json!([1, {"a": true}])).await, (200, json!({ "result": [1, {"a": true}] })));
+        assert_eq!(call("greet", json!([])).await, (200, json!({ "result": "hello" })));
+        let (status, body) = call("fail", json!([])).await;
+        assert_eq!((status, body["detail"].as_str()), (500, Some("no model loaded")));
+        assert_eq!(call("hang", json!([])).await.0, 408);
+        assert_eq!(call("missing", json!([])).await.0, 404);
+
+        let listed: Value = client.get(format!("http://{}/api/v1/system/modules", addr)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed["modules"][0]["kind"], "legacy");
+        assert_eq!(listed["modules"][0]["methods"], json!(["echo", "fail", "greet", "hang"]));
+    }
+
+    #[test]
+    fn remote_endpoint_joins_paths() {
+        assert_eq!(remote_endpoint("https://gpu-box:8000/", "ml", "train"), "https://gpu-box:8000/api/v1/ml/train");
+        assert_eq!(remote_endpoint("http://10.0.0.2:8000", "ml", "train"), "http://10.0.0.2:8000/api/v1/ml/train");
+        assert_eq!(remote_endpoint("http://gpu-box", "@lab/ml", "train"), "http://gpu-box/api/v1/@lab~ml/train");
+    }
+
+    #[test]
+    fn nested_module_segments_map_to_scoped_names() {
+        assert_eq!(module_name_from_segment("ml").as_deref(), Some("ml"));
+        assert_eq!(module_name_from_segment("@lab~ml").as_deref(), Some("@lab/ml"));
+        assert_eq!(module_name_from_segment("@lab/ml").as_deref(), Some("@lab/ml"));
+        assert_eq!(module_name_from_segment("lab~ml"), None);
+        assert_eq!(module_name_from_segment("@lab"), None);
+        assert_eq!(module_name_from_segment("@lab~.."), None);
+        assert_eq!(module_name_from_segment(".."), None);
+        assert_eq!(module_name_from_segment("@lab~"), None);
+    }
+
+    fn test_state(base_dir: &StdPath) -> Arc<AppState> {
+        write_settings(base_dir);
+        Arc::new(AppState {
+            config: load_config(&base_dir.to_path_buf()).unwrap(),
+            secrets: Secrets { api_key: String::new() },
+            base_dir: base_dir.to_path_buf(),
+            modules_dir: base_dir.join("modules"),
+            hosts: Default::default(),
+            workers: Mutex::new(HashMap::new()),
+            rotation: Default::default(),
+            legacy_warned: Default::default(),
+            events: None,
+            audit: None,
+            http: reqwest::Client::new(),
+            cache: ResponseCache::new(2),
+            draining: AtomicBool::new(true),
+            in_flight: AtomicUsize::new(0),
+            stopped: Arc::new(Notify::new()),
+        })
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_in_flight_calls_up_to_the_timeout() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.in_flight.store(1, Ordering::SeqCst);
+        let finishing = state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            finishing.in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+        let summary = drain(&state, Duration::from_secs(5)).await;
+        assert_eq!(summary, DrainSummary { completed: 1, ..DrainSummary::default() });
+
+        state.in_flight.store(1, Ordering::SeqCst);
+        let summary = drain(&state, Duration::from_millis(50)).await;
+        assert_eq!(summary.abandoned, 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn preloads_workers_and_reports_failures() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        for (name, cmd, preload) in [("echo", "cat", true), ("lazy", "cat", false)] {
+            let module = dir.path().join("modules").join(name);
+            std::fs::create_dir_all(&module).unwrap();
+            std::fs::write(module.join("bridge.json"), json!({ "worker": { "cmd": [cmd], "preload": preload } }).to_string()).unwrap();
+        }
+        assert_eq!(preload_workers(&state).await.unwrap(), vec!["echo"]);
+        assert_eq!(state.workers.lock().await.len(), 1);
+        drain(&state, Duration::ZERO).await;
+
+        std::fs::write(dir.path().join("modules/echo/bridge.json"), json!({ "worker": { "cmd": ["false"], "preload": true } }).to_string()).unwrap();
+        let err = preload_workers(&state).await.unwrap_err().to_string();
+        assert_eq!(err, "Preloading worker 'echo' failed: the worker exited right after starting");
+        assert!(state.workers.lock().await.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn idle_workers_are_stopped_and_restarted_on_demand() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let module = dir.path().join("modules").join("echo");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), json!({
+            "worker": { "cmd": ["cat"], "idle_timeout_ms": 200 },
+            "methods": { "ping": {} }
+        }).to_string()).unwrap();
+        let call = || async { call_module(&state, "echo", "ping", Payload { args: Vec::new() }).await.map(|Json(_)| ()) };
+
+        call().await.unwrap();
+        let first = state.workers.lock().await.get("echo").cloned().unwrap();
+        // Calls keep it running
+        for _ in 0..4 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            call().await.unwrap();
+        }
+        assert!(Arc::ptr_eq(state.workers.lock().await.get("echo").unwrap(), &first));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(state.workers.lock().await.is_empty());
+        assert!(first.process.lock().await.is_none());
+
+        call().await.unwrap();
+        let second = state.workers.lock().await.get("echo").cloned().unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        drain(&state, Duration::ZERO).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn pooled_workers_take_calls_in_turn() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let module = dir.path().join("modules").join("ml");
+        std::fs::create_dir_all(&module).unwrap();
+        // Answers with its process id, after a pause for "slow" calls
+        let worker = r#"while read -r line; do id=$(printf '%s' "$line" | sed 's/.*"id":"\([^"]*\)".*/\1/'); case "$line" in *slow*) sleep 1;; esac; printf '{"id":"%s","result":%d}\n' "$id" "$$"; done"#;
+        std::fs::write(module.join("bridge.json"), json!({
+            "worker": { "cmd": ["sh", "-c", worker], "pool_size": 2 },
+            "methods": { "pid": {}, "slow": {} }
+        }).to_string()).unwrap();
+        let call = |method: &'static str| {
+            let state = state.clone();
+            async move { call_module(&state, "ml", method, Payload { args: Vec::new() }).await.unwrap().0["result"].clone() }
+        };
+
+        let pids = [call("pid").await, call("pid").await, call("pid").await, call("pid").await];
+        assert_ne!(pids[0], pids[1]);
+        assert_eq!((&pids[0], &pids[1]), (&pids[2], &pids[3]));
+        let mut keys: Vec<String> = state.workers.lock().await.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["ml", "ml#1"]);
+
+        // A slow call on one instance does not hold up the other
+        let started = Instant::now();
+        let (slow, (fast, fast_elapsed)) = tokio::join!(call("slow"), async { (call("pid").await, started.elapsed()) });
+        assert_eq!((slow, fast), (pids[0].clone(), pids[1].clone()));
+        assert!(fast_elapsed < Duration::from_millis(500));
+        drain(&state, Duration::ZERO).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn drain_stops_started_workers() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let spec = WorkerSpec {
+            cmd: vec!["cat".to_string()],
+            cwd: None,
+            env: None,
+            timeout_ms: None,
+            idle_timeout_ms: None,
+            persistent: false,
+            preload: false,
+            concurrency: None,
+            pool_size: None,
+        };
+        let worker = start_worker(&dir.path().to_path_buf(), &spec).await.unwrap();
+        state.workers.lock().await.insert("echo".to_string(), worker.clone());
+
+        let summary = drain(&state, Duration::from_secs(1)).await;
+        assert_eq!(summary.workers_stopped, 1);
+        assert!(state.workers.lock().await.is_empty());
+        for _ in 0..50 {
+            if !worker.alive.load(Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!worker.alive.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use lunu_builder::exclude::ExcludeSet;
+use lunu_builder::timings;
+use lunu_cli::secret_store;
+
+/// Metadata stored at the root of every `.lunu` bundle.
+pub const BUNDLE_META: &str = "lunu-bundle.json";
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// Per-machine environments and VCS data are not part of a bundle, wherever they appear.
+const BUNDLE_EXCLUDES: &[&str] = &[
+    ".git",
+    "node_modules",
+    ".venv",
+    "venv",
+    "__pycache__",
+    "*.pyc",
+    "*.lunu",
+    ".DS_Store",
+    "Thumbs.db",
+];
+
+/// Build output and pinned runtime binaries at the project root; the runtime is downloaded on import.
+const ROOT_EXCLUDES: &[&str] = &["dist", "bin"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundleMeta {
+    pub format: u32,
+    pub name: String,
+    /// `lute` or `lune`
+    pub runtime: String,
+    /// Runtime release the project was exported with; `lunu import` downloads this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_version: Option<String>,
+    pub lunu: String,
+}
+
+fn bundle_files(root: &Path, exclude: &ExcludeSet, skip: &[String]) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)?.flatten() {
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if exclude.is_excluded(&relative) || ROOT_EXCLUDES.contains(&relative.as_str()) || skip.contains(&relative) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+/// Writes the project at `root` to `archive`. Entries are sorted and carry a fixed timestamp, so
+/// exporting the same tree twice produces the same bytes. `skip` lists extra root-relative paths.
+pub fn write_bundle(root: &Path, meta: &BundleMeta, archive: &Path, skip: &[String]) -> Result<usize> {
+    let _timing = timings::span("zip");
+    let exclude = ExcludeSet::new(false, &BUNDLE_EXCLUDES.iter().map(|p| p.to_string()).collect::<Vec<_>>(), &[])
+        .with_paths(&secret_store::PRIVATE_PATHS.map(str::to_string));
+    let files = bundle_files(root, &exclude, skip)?;
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(archive).with_context(|| format!("Failed to create {:?}", archive))?);
+    zip.start_file(BUNDLE_META, options.unix_permissions(0o644))?;
+    zip.write_all(serde_json::to_string_pretty(meta)?.as_bytes())?;
+    for (relative, path) in &files {
+        let mode = if is_executable(path) { 0o755 } else { 0o644 };
+        zip.start_file(relative.as_str(), options.unix_permissions(mode))?;
+        zip.write_all(&std::fs::read(path)?)?;
+    }
+    zip.finish()?;
+    Ok(files.len())
+}
+
+pub fn read_meta(archive: &Path) -> Result<BundleMeta> {
+    let file = std::fs::File::open(archive).with_context(|| format!("Failed to open {:?}", archive))?;
+    let mut zip = zip::ZipArchive::new(file).with_context(|| format!("{:?} is not a Lunu bundle", archive))?;
+    let mut content = String::new();
+    zip.by_name(BUNDLE_META)
+        .with_context(|| format!("{:?} is not a Lunu bundle ({} is missing)", archive, BUNDLE_META))?
+        .read_to_string(&mut content)?;
+    let meta: BundleMeta = serde_json::from_str(&content).with_context(|| format!("Invalid {}", BUNDLE_META))?;
+    if meta.format > BUNDLE_FORMAT {
+        return Err(anyhow::anyhow!(
+            "Bundle format {} is newer than this Lunu supports ({}). Run 'lunu upgrade'.",
+            meta.format,
+            BUNDLE_FORMAT
+        ));
+    }
+    Ok(meta)
+}
+
+/// Unpacks every project file of `archive` into `dest`, rejecting entries that escape it.
+pub fn extract_bundle(archive: &Path, dest: &Path) -> Result<usize> {
+    let _timing = timings::span("extraction");
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(archive)?)?;
+    let mut count = 0;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let relative = entry
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow::anyhow!("Bundle entry escapes the project directory: {}", entry.name()))?;
+        if entry.is_dir() || relative == Path::new(BUNDLE_META) {
+            continue;
+        }
+        let path = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = std::fs::File::create(&path)?;
+        std::io::copy(&mut entry, &mut out)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn bundles_are_reproducible_and_round_trip() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        for dir in ["src", "modules/json/.git", "modules/json/bin", "bin", "dist"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("lunu.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        std::fs::write(root.join("src").join("main.luau"), "print('hi')").unwrap();
+        std::fs::write(root.join("modules/json/init.luau"), "return {}").unwrap();
+        std::fs::write(root.join("modules/json/.git/HEAD"), "ref").unwrap();
+        std::fs::write(root.join("modules/json/bin/tool.luau"), "return 1").unwrap();
+        std::fs::write(root.join("bin").join("lune"), "binary").unwrap();
+        std::fs::write(root.join("dist").join("app.zip"), "zip").unwrap();
+        std::fs::write(root.join("main"), "built").unwrap();
+
+        let meta = BundleMeta {
+            format: BUNDLE_FORMAT,
+            name: "demo".to_string(),
+            runtime: "lune".to_string(),
+            runtime_version: Some("v0.8.9".to_string()),
+            lunu: "0.1.1".to_string(),
+        };
+        let out = tempdir().unwrap();
+        let first = out.path().join("a.lunu");
+        let second = out.path().join("b.lunu");
+        assert_eq!(write_bundle(root, &meta, &first, &["main".to_string()]).unwrap(), 4);
+        write_bundle(root, &meta, &second, &["main".to_string()]).unwrap();
+        assert_eq!(std::fs::read(&first).unwrap(), std::fs::read(&second).unwrap());
+        assert_eq!(read_meta(&first).unwrap(), meta);
+
+        let dest = out.path().join("imported");
+        assert_eq!(extract_bundle(&first, &dest).unwrap(), 4);
+        assert_eq!(std::fs::read_to_string(dest.join("src/main.luau")).unwrap(), "print('hi')");
+        assert!(dest.join("modules/json/init.luau").exists());
+        assert!(dest.join("modules/json/bin/tool.luau").exists());
+        assert!(!dest.join("modules/json/.git").exists());
+        assert!(!dest.join("bin").exists());
+        assert!(!dest.join(BUNDLE_META).exists());
+    }
+
+    #[test]
+    fn bundles_leave_out_secrets() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        for dir in ["config", ".lunu"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("lunu.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        std::fs::write(root.join("config/settings.json"), "{}").unwrap();
+        std::fs::write(root.join("config/.secrets.json"), r#"{"api_key":"k"}"#).unwrap();
+        std::fs::write(root.join(".lunu/update.key"), "key").unwrap();
+
+        let meta = BundleMeta {
+            format: BUNDLE_FORMAT,
+            name: "demo".to_string(),
+            runtime: "lune".to_string(),
+            runtime_version: None,
+            lunu: "0.1.1".to_string(),
+        };
+        let out = tempdir().unwrap();
+        let archive = out.path().join("demo.lunu");
+        assert_eq!(write_bundle(root, &meta, &archive, &[]).unwrap(), 2);
+        let zip = zip::ZipArchive::new(std::fs::File::open(&archive).unwrap()).unwrap();
+        let names: Vec<&str> = zip.file_names().collect();
+        assert!(names.contains(&"config/settings.json"));
+        assert!(!names.iter().any(|n| n.starts_with(".lunu") || n.ends_with(".secrets.json")), "{:?}", names);
+    }
+
+    #[test]
+    fn rejects_non_bundles() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("plain.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        zip.start_file("readme.txt", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"hi").unwrap();
+        zip.finish().unwrap();
+        assert!(read_meta(&archive).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! What an installed runtime binary can do (`lunu runtime capabilities`): its version, the
+//! subcommands its `--help` lists and the standard libraries that load. Probing runs the binary a
+//! few times, so results are cached per binary and refreshed when the file changes.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use lunu_cli::worker_requires::parse_version;
+
+const LUNE_LIBRARIES: &[&str] = &["@lune/datetime", "@lune/fs", "@lune/luau", "@lune/net", "@lune/process", "@lune/regex", "@lune/roblox", "@lune/serde", "@lune/stdio", "@lune/task"];
+const LUTE_LIBRARIES: &[&str] = &["@lute/crypto", "@lute/fs", "@lute/io", "@lute/luau", "@lute/net", "@lute/process", "@lute/system", "@lute/task", "@lute/time", "@lute/vm", "@std/fs", "@std/path", "@std/process"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Capabilities {
+    pub runtime: String,
+    pub binary: PathBuf,
+    pub version: Option<String>,
+    /// Empty when `--help` could not be parsed; `supports` then assumes everything is available
+    pub subcommands: Vec<String>,
+    pub std_libraries: Vec<String>,
+    /// Size and modification time of `binary` when it was probed
+    fingerprint: String,
+}
+
+impl Capabilities {
+    pub fn supports(&self, subcommand: &str) -> bool {
+        self.subcommands.is_empty() || self.subcommands.iter().any(|s| s == subcommand)
+    }
+
+    /// Fails with an explanation when the runtime lacks `subcommand`.
+    pub fn require(&self, subcommand: &str) -> Result<()> {
+        if self.supports(subcommand) {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!(
+            "{} {} at {:?} does not support '{}' (available: {}). Update it with 'lunu runtime {} --update'.",
+            self.runtime,
+            self.version.as_deref().unwrap_or("(unknown version)"),
+            self.binary,
+            subcommand,
+            self.subcommands.join(", "),
+            self.runtime
+        ))
+    }
+}
+
+fn fingerprint(binary: &Path) -> Option<String> {
+    let meta = std::fs::metadata(binary).ok()?;
+    let modified = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs();
+    Some(format!("{}-{}", meta.len(), modified))
+}
+
+/// Subcommand names from a clap-style `Commands:`/`Subcommands:` help section.
+pub fn parse_subcommands(help: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut in_section = false;
+    for line in help.lines() {
+        let trimmed = line.trim();
+        if !line.starts_with(char::is_whitespace) {
+            in_section = matches!(trimmed.to_lowercase().as_str(), "commands:" | "subcommands:" | "available commands:");
+            continue;
+        }
+        if !in_section || trimmed.is_empty() {
+            continue;
+        }
+        if let Some(name) = trimmed.split_whitespace().next() {
+            if name != "help" && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                commands.push(name.to_string());
+            }
+        }
+    }
+    commands
+}
+
+fn output_text(binary: &Path, args: &[&str], cwd: &Path) -> Option<String> {
+    let output = Command::new(binary).args(args).current_dir(cwd).output().ok()?;
+    Some(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+fn probe_libraries(binary: &Path, runtime: &str) -> Vec<String> {
+    let candidates = if runtime == "lute" { LUTE_LIBRARIES } else { LUNE_LIBRARIES };
+    let Ok(dir) = tempfile::tempdir() else {
+        return Vec::new();
+    };
+    let script: String = candidates
+        .iter()
+        .map(|lib| format!("if pcall(require, \"{0}\") then print(\"{0}\") end\n", lib))
+        .collect();
+    let path = dir.path().join("probe.luau");
+    if std::fs::write(&path, script).is_err() {
+        return Vec::new();
+    }
+    let Some(output) = output_text(binary, &["run", "probe.luau"], dir.path()) else {
+        return Vec::new();
+    };
+    output.lines().map(str::trim).filter(|l| candidates.contains(l)).map(String::from).collect()
+}
+
+pub fn probe(binary: &Path, runtime: &str) -> Capabilities {
+    let cwd = std::env::temp_dir();
+    let version = output_text(binary, &["--version"], &cwd).and_then(|text| parse_version(&text)).map(|v| v.to_string());
+    let subcommands = output_text(binary, &["--help"], &cwd).map(|text| parse_subcommands(&text)).unwrap_or_default();
+    Capabilities {
+        runtime: runtime.to_string(),
+        binary: binary.to_path_buf(),
+        version,
+        subcommands,
+        std_libraries: probe_libraries(binary, runtime),
+        fingerprint: fingerprint(binary).unwrap_or_default(),
+    }
+}
+
+fn cache_path(cache_dir: &Path, runtime: &str) -> PathBuf {
+    cache_dir.join(format!("{}-capabilities.json", runtime))
+}
+
+/// Cached capabilities of `binary`, probing again when it moved or changed (or `refresh` is set).
+pub fn load_or_probe(cache_dir: &Path, binary: &Path, runtime: &str, refresh: bool) -> Capabilities {
+    let path = cache_path(cache_dir, runtime);
+    if !refresh {
+        let cached = std::fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str::<Capabilities>(&c).ok());
+        if let Some(cached) = cached.filter(|c| c.binary == binary && Some(&c.fingerprint) == fingerprint(binary).as_ref()) {
+            return cached;
+        }
+    }
+    let capabilities = probe(binary, runtime);
+    if let Ok(content) = serde_json::to_string_pretty(&capabilities) {
+        let _ = std::fs::create_dir_all(cache_dir);
+        let _ = std::fs::write(&path, content);
+    }
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clap_help_sections() {
+        let help = "A standalone Luau runtime\n\nUsage: lune <COMMAND>\n\nCommands:\n  run       Run a script\n  list      List scripts\n  setup     Set up type definitions\n  help      Print this message\n\nOptions:\n  -h, --help     Print help\n";
+        assert_eq!(parse_subcommands(help), vec!["run", "list", "setup"]);
+        assert!(parse_subcommands("usage: lute [options] file").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn probes_and_caches_runtime_binaries() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("lute");
+        let script = "#!/bin/sh\ncase \"$1\" in\n  --version) echo 'lute 0.1.0' ;;\n  --help) printf 'Usage: lute <command>\\n\\nCommands:\\n  run  Run a file\\n  check  Typecheck\\n' ;;\n  run) echo '@lute/fs'; echo '@std/path' ;;\nesac\n";
+        std::fs::write(&binary, script).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cache = dir.path().join("cache");
+        let caps = load_or_probe(&cache, &binary, "lute", false);
+        assert_eq!(caps.version.as_deref(), Some("0.1.0"));
+        assert_eq!(caps.subcommands, vec!["run", "check"]);
+        assert_eq!(caps.std_libraries, vec!["@lute/fs", "@std/path"]);
+        assert!(caps.supports("check"));
+        assert!(caps.require("compile").unwrap_err().to_string().contains("does not support 'compile'"));
+
+        // Served from the cache while the binary is unchanged
+        let mut cached: Capabilities = serde_json::from_str(&std::fs::read_to_string(cache_path(&cache, "lute")).unwrap()).unwrap();
+        cached.version = Some("9.9.9".to_string());
+        std::fs::write(cache_path(&cache, "lute"), serde_json::to_string(&cached).unwrap()).unwrap();
+        assert_eq!(load_or_probe(&cache, &binary, "lute", false).version.as_deref(), Some("9.9.9"));
+        assert_eq!(load_or_probe(&cache, &binary, "lute", true).version.as_deref(), Some("0.1.0"));
+    }
+}
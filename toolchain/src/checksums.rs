@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use lunu_builder::minisign::{self, SigningKey, VerifyingKey};
+
+pub const SUMS_FILE: &str = "SHA256SUMS";
+pub const SIGNATURE_FILE: &str = "SHA256SUMS.minisig";
+
+/// Result of checking a directory against its `SHA256SUMS`.
+#[derive(Debug, Default)]
+pub struct Verification {
+    pub verified: Vec<String>,
+    pub missing: Vec<String>,
+    pub mismatched: Vec<String>,
+    /// Trusted comment of a valid signature
+    pub signed: Option<String>,
+}
+
+impl Verification {
+    pub fn ok(&self, ignore_missing: bool) -> bool {
+        self.mismatched.is_empty() && (ignore_missing || self.missing.is_empty())
+    }
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Every file under `dir` (forward-slash relative paths, sorted), except the checksum files.
+fn artifact_files(dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if relative != SUMS_FILE && relative != SIGNATURE_FILE {
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Writes `dir/SHA256SUMS` in the `sha256sum` format and removes a stale signature.
+pub fn write_sums(dir: &Path) -> Result<PathBuf> {
+    let mut content = String::new();
+    for file in artifact_files(dir)? {
+        content.push_str(&format!("{}  {}\n", sha256_file(&dir.join(&file))?, file));
+    }
+    let path = dir.join(SUMS_FILE);
+    std::fs::write(&path, content)?;
+    let _ = std::fs::remove_file(dir.join(SIGNATURE_FILE));
+    Ok(path)
+}
+
+/// Signs `dir/SHA256SUMS`, writing `SHA256SUMS.minisig`.
+pub fn sign_sums(dir: &Path, key: &SigningKey) -> Result<PathBuf> {
+    let content = std::fs::read(dir.join(SUMS_FILE))?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let comment = format!("timestamp:{}\tfile:{}", timestamp, SUMS_FILE);
+    let path = dir.join(SIGNATURE_FILE);
+    std::fs::write(&path, minisign::sign(key, &content, &comment))?;
+    Ok(path)
+}
+
+pub fn parse_sums(content: &str) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (hash, name) = line
+            .split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("{} line {} is malformed", SUMS_FILE, index + 1))?;
+        // `sha256sum` marks binary mode with `*`
+        let name = name.trim_start_matches(' ').trim_start_matches('*');
+        if hash.len() != 64 || name.is_empty() {
+            return Err(anyhow::anyhow!("{} line {} is malformed", SUMS_FILE, index + 1));
+        }
+        entries.push((hash.to_lowercase(), name.to_string()));
+    }
+    Ok(entries)
+}
+
+/// Checks every listed file and, when `public_key` is given, the signature over `SHA256SUMS`.
+pub fn verify_dir(dir: &Path, public_key: Option<&VerifyingKey>) -> Result<Verification> {
+    let sums_path = dir.join(SUMS_FILE);
+    let content = std::fs::read_to_string(&sums_path)
+        .with_context(|| format!("{:?} not found", sums_path))?;
+    let mut result = Verification::default();
+    if let Some(key) = public_key {
+        let signature = std::fs::read_to_string(dir.join(SIGNATURE_FILE))
+            .with_context(|| format!("{} is missing; the artifacts are not signed", SIGNATURE_FILE))?;
+        result.signed = Some(minisign::verify(key, content.as_bytes(), &signature)?);
+    }
+    for (hash, name) in parse_sums(&content)? {
+        if name.split('/').any(|part| part == "..") || Path::new(&name).is_absolute() {
+            return Err(anyhow::anyhow!("{} lists a path outside the directory: {}", SUMS_FILE, name));
+        }
+        let path = dir.join(&name);
+        if !path.is_file() {
+            result.missing.push(name);
+        } else if sha256_file(&path)? == hash {
+            result.verified.push(name);
+        } else {
+            result.mismatched.push(name);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn writes_and_verifies_sums() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("modules")).unwrap();
+        std::fs::write(dir.path().join("app.zip"), "zip").unwrap();
+        std::fs::write(dir.path().join("modules").join("init.luau"), "return {}").unwrap();
+
+        let sums = std::fs::read_to_string(write_sums(dir.path()).unwrap()).unwrap();
+        let names: Vec<String> = parse_sums(&sums).unwrap().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(names, vec!["app.zip", "modules/init.luau"]);
+
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        sign_sums(dir.path(), &key).unwrap();
+        let result = verify_dir(dir.path(), Some(&key.verifying_key())).unwrap();
+        assert!(result.ok(false));
+        assert!(result.signed.unwrap().ends_with("file:SHA256SUMS"));
+
+        std::fs::write(dir.path().join("app.zip"), "tampered").unwrap();
+        std::fs::remove_file(dir.path().join("modules").join("init.luau")).unwrap();
+        let result = verify_dir(dir.path(), None).unwrap();
+        assert_eq!(result.mismatched, vec!["app.zip"]);
+        assert_eq!(result.missing, vec!["modules/init.luau"]);
+        assert!(!result.ok(true));
+
+        let other = SigningKey::from_bytes(&[6u8; 32]).verifying_key();
+        assert!(verify_dir(dir.path(), Some(&other)).is_err());
+    }
+
+    #[test]
+    fn parses_binary_mode_lines() {
+        let hash = "a".repeat(64);
+        let entries = parse_sums(&format!("{} *app.zip\n\n", hash)).unwrap();
+        assert_eq!(entries, vec![(hash, "app.zip".to_string())]);
+        assert!(parse_sums("nothex  app.zip").is_err());
+    }
+}
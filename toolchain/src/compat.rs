@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::Result;
 use tokio::fs;
 
@@ -27,11 +27,10 @@ impl CompatibilityLayer {
         Ok(())
     }
 
-    async fn generate_wrapper(path: &Path) -> Result<()> {
+    /// The fields of the generated init.luau, one per module file of `path` (unordered).
+    async fn wrapper_fields(path: &Path) -> Result<Vec<String>> {
         // Simple heuristic: expose all .luau files as fields in a table
-        let mut export_lines = Vec::new();
-        export_lines.push("return {".to_string());
-
+        let mut fields = Vec::new();
         let mut read_dir = fs::read_dir(path).await?;
         while let Ok(Some(entry)) = read_dir.next_entry().await {
             let p = entry.path();
@@ -39,24 +38,24 @@ impl CompatibilityLayer {
                 if ext == "luau" || ext == "lua" {
                     if let Some(stem) = p.file_stem().and_then(|s| s.to_str()) {
                         // Use string interpolation in generated Luau code
-                        export_lines.push(format!("    {} = require(`./{}`),", stem, stem));
+                        fields.push(format!("    {} = require(`./{}`),", stem, stem));
                     }
                 }
             }
         }
+        Ok(fields)
+    }
 
+    async fn generate_wrapper(path: &Path) -> Result<()> {
+        let mut export_lines = vec!["return {".to_string()];
+        export_lines.extend(Self::wrapper_fields(path).await?);
         export_lines.push("}".to_string());
         
         fs::write(path.join("init.luau"), export_lines.join("\n")).await?;
         Ok(())
     }
 
-    async fn ensure_manifest(path: &Path) -> Result<()> {
-        let manifest_path = path.join("lunu.toml");
-        if manifest_path.exists() {
-            return Ok(());
-        }
-
+    fn manifest_content(path: &Path) -> String {
         let name = path
             .file_name()
             .and_then(|s| s.to_str())
@@ -70,14 +69,46 @@ impl CompatibilityLayer {
             "init.luau"
         };
 
-        let content = format!(
+        format!(
             "name = \"{}\"\nversion = \"0.1.0\"\nentry = \"{}\"\nlanguage = \"luau\"\n",
             name, entry
-        );
+        )
+    }
 
-        fs::write(manifest_path, content).await?;
+    async fn ensure_manifest(path: &Path) -> Result<()> {
+        let manifest_path = path.join("lunu.toml");
+        if manifest_path.exists() {
+            return Ok(());
+        }
+        fs::write(manifest_path, Self::manifest_content(path)).await?;
         Ok(())
     }
+
+    /// The files of `path` that are exactly what `ensure_compat` writes (its lunu.toml, and an
+    /// init.luau wrapping the other module files), so checksums taken before it ran still verify.
+    pub async fn generated_files(path: &Path) -> Result<Vec<PathBuf>> {
+        let mut generated = Vec::new();
+        let manifest_path = path.join("lunu.toml");
+        if fs::read_to_string(&manifest_path).await.is_ok_and(|content| content == Self::manifest_content(path)) {
+            generated.push(manifest_path);
+        }
+        let init_path = path.join("init.luau");
+        if let Ok(content) = fs::read_to_string(&init_path).await {
+            let mut expected = Self::wrapper_fields(path).await?;
+            // The wrapper was written before init.luau existed, so it does not list itself
+            expected.retain(|field| !field.starts_with("    init = "));
+            expected.sort();
+            let mut lines: Vec<&str> = content.lines().collect();
+            if lines.first() == Some(&"return {") && lines.last() == Some(&"}") {
+                let mut fields: Vec<String> = lines.drain(1..lines.len() - 1).map(str::to_string).collect();
+                fields.sort();
+                if fields == expected {
+                    generated.push(init_path);
+                }
+            }
+        }
+        Ok(generated)
+    }
 }
 
 #[cfg(test)]
@@ -111,5 +142,23 @@ mod tests {
 
         let content = fs::read_to_string(init_path).await.unwrap();
         assert_eq!(content, "return { custom = true }");
+        assert_eq!(CompatibilityLayer::generated_files(module_dir).await.unwrap(), vec![module_dir.join("lunu.toml")]);
+    }
+
+    #[tokio::test]
+    async fn recognizes_generated_files() {
+        let dir = tempdir().unwrap();
+        let module_dir = dir.path();
+        fs::write(module_dir.join("hello.luau"), "return {}").await.unwrap();
+        fs::write(module_dir.join("util.lua"), "return {}").await.unwrap();
+        CompatibilityLayer::ensure_compat(module_dir).await.unwrap();
+        assert_eq!(
+            CompatibilityLayer::generated_files(module_dir).await.unwrap(),
+            vec![module_dir.join("lunu.toml"), module_dir.join("init.luau")]
+        );
+
+        // An edited file is no longer the generated one
+        fs::write(module_dir.join("init.luau"), "return { hello = require(`./hello`) }").await.unwrap();
+        assert_eq!(CompatibilityLayer::generated_files(module_dir).await.unwrap(), vec![module_dir.join("lunu.toml")]);
     }
 }
@@ -3,35 +3,41 @@ use std::collections::BTreeMap;
 use std::path::Path;
 use anyhow::{Result, Context};
 use tokio::fs;
+use crate::jsonc;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Luaurc {
     #[serde(default)]
     pub aliases: BTreeMap<String, String>,
     #[serde(flatten)]
     pub other: BTreeMap<String, serde_json::Value>,
+    /// Text the file was loaded from. `save` patches only the changed aliases into it, so comments,
+    /// key order and formatting survive.
+    #[serde(skip)]
+    source: Option<String>,
 }
 
 impl Luaurc {
+    /// Reads `.luaurc`, comments and trailing commas (JSONC) included.
     pub async fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
-            return Ok(Self {
-                aliases: BTreeMap::new(),
-                other: BTreeMap::new(),
-            });
+            return Ok(Self::default());
         }
         let content = fs::read_to_string(path).await
             .with_context(|| format!("Failed to read .luaurc at {:?}", path))?;
         
-        let config: Luaurc = serde_json::from_str(&content)
+        let mut config: Luaurc = serde_json::from_str(&jsonc::strip(&content))
             .with_context(|| "Failed to parse .luaurc")?;
+        config.source = Some(content);
             
         Ok(config)
     }
 
     pub async fn save(&self, path: &Path) -> Result<()> {
-        let content = serde_json::to_string_pretty(self)
-            .with_context(|| "Failed to serialize .luaurc")?;
+        let content = match self.source.as_deref().and_then(|source| self.patch(source)) {
+            Some(content) => content,
+            None => serde_json::to_string_pretty(self).with_context(|| "Failed to serialize .luaurc")?,
+        };
         
         fs::write(path, content).await
             .with_context(|| format!("Failed to write .luaurc to {:?}", path))?;
@@ -39,6 +45,32 @@ impl Luaurc {
         Ok(())
     }
 
+    /// `source` with the aliases that differ from it set or removed in place; `None` when the
+    /// file has to be rewritten instead (other settings changed, or it is not an object).
+    fn patch(&self, source: &str) -> Option<String> {
+        let original: Luaurc = serde_json::from_str(&jsonc::strip(source)).ok()?;
+        if original.other != self.other {
+            return None;
+        }
+        let mut text = source.to_string();
+        if !jsonc::has_object(&text, &["aliases"]) {
+            if self.aliases.is_empty() {
+                return Some(text);
+            }
+            let aliases = serde_json::to_value(&self.aliases).ok()?;
+            return jsonc::set_member(&text, &[], "aliases", &jsonc::render(&aliases, &text));
+        }
+        for name in original.aliases.keys().filter(|name| !self.aliases.contains_key(*name)) {
+            text = jsonc::remove_member(&text, &["aliases"], name)?;
+        }
+        for (name, target) in &self.aliases {
+            if original.aliases.get(name) != Some(target) {
+                text = jsonc::set_member(&text, &["aliases"], name, &serde_json::to_string(target).ok()?)?;
+            }
+        }
+        Some(text)
+    }
+
     pub fn add_alias(&mut self, name: &str, path: &str) {
         self.aliases.insert(name.to_string(), path.to_string());
     }
@@ -48,6 +80,41 @@ impl Luaurc {
     }
 }
 
+/// Which source claimed each alias while .luaurc is rebuilt, so two sources asking for the same
+/// alias fail with both named instead of the later one overwriting the earlier.
+#[derive(Debug, Default)]
+pub struct AliasClaims {
+    claims: BTreeMap<String, (String, String)>,
+}
+
+impl AliasClaims {
+    /// Records that `source` (e.g. "dependency 'json'") maps `alias` to `path`. Claiming an alias
+    /// again for the same path is fine: the packages of one `@scope` share its alias.
+    pub fn claim(&mut self, alias: &str, path: &str, source: &str) -> Result<()> {
+        match self.claims.get(alias) {
+            Some((other, other_path)) if other_path.trim_end_matches('/') != path.trim_end_matches('/') => Err(anyhow::anyhow!(
+                "Alias '{}' is claimed by both {} ({}) and {} ({}). Install one under another name with 'lunu add <package> --alias <name>'.",
+                alias,
+                other,
+                other_path,
+                source,
+                path
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.claims.insert(alias.to_string(), (source.to_string(), path.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn apply(&self, luaurc: &mut Luaurc) {
+        for (alias, (_, path)) in &self.claims {
+            luaurc.add_alias(alias, path);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,14 +135,53 @@ mod tests {
         let dir = tempdir().unwrap();
         let path = dir.path().join(".luaurc");
 
-        let mut config = Luaurc {
-            aliases: BTreeMap::new(),
-            other: BTreeMap::new(),
-        };
+        let mut config = Luaurc::default();
         config.add_alias("lunu", "modules/lunu/");
         config.save(&path).await.unwrap();
 
         let loaded = Luaurc::load(&path).await.unwrap();
         assert_eq!(loaded.aliases.get("lunu").unwrap(), "modules/lunu/");
     }
+
+    #[tokio::test]
+    async fn save_keeps_comments_and_other_settings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".luaurc");
+        let original = "{\n    // Checked in CI\n    \"languageMode\": \"strict\",\n    \"aliases\": {\n        \"lunu\": \"modules/lunu/\", // toolchain\n        \"old\": \"modules/old/\",\n    },\n    \"lint\": { \"*\": true }\n}\n";
+        std::fs::write(&path, original).unwrap();
+
+        let mut config = Luaurc::load(&path).await.unwrap();
+        assert_eq!(config.other["languageMode"], "strict");
+        config.remove_alias("old");
+        config.add_alias("json", "modules/json/");
+        config.save(&path).await.unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            saved,
+            "{\n    // Checked in CI\n    \"languageMode\": \"strict\",\n    \"aliases\": {\n        \"lunu\": \"modules/lunu/\", // toolchain\n        \"json\": \"modules/json/\",\n    },\n    \"lint\": { \"*\": true }\n}\n"
+        );
+
+        std::fs::write(&path, "{\n\t\"languageMode\": \"strict\"\n}\n").unwrap();
+        let mut config = Luaurc::load(&path).await.unwrap();
+        config.add_alias("lunu", "modules/lunu/");
+        config.save(&path).await.unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved, "{\n\t\"languageMode\": \"strict\",\n\t\"aliases\": {\n\t\t\"lunu\": \"modules/lunu/\"\n\t}\n}\n");
+    }
+
+    #[test]
+    fn conflicting_alias_claims_name_both_sources() {
+        let mut claims = AliasClaims::default();
+        claims.claim("acme", "modules/@acme/", "dependency '@acme/http'").unwrap();
+        claims.claim("acme", "modules/@acme", "dependency '@acme/json'").unwrap();
+        claims.claim("std", "/opt/lute/std/", "the Lute runtime").unwrap();
+        let err = claims.claim("std", "modules/std/", "dependency 'std'").unwrap_err().to_string();
+        assert!(err.contains("the Lute runtime (/opt/lute/std/)") && err.contains("dependency 'std' (modules/std/)"));
+        assert!(err.contains("--alias"));
+
+        let mut luaurc = Luaurc::default();
+        claims.apply(&mut luaurc);
+        assert_eq!(luaurc.aliases.get("std").map(String::as_str), Some("/opt/lute/std/"));
+        assert_eq!(luaurc.aliases.len(), 2);
+    }
 }
@@ -0,0 +1,352 @@
+//! Embedded JSON schemas for `lunu.toml` and `config/settings.json`, and a small validator that
+//! reports problems with the line they are on instead of serde's first-error-only messages.
+
+use serde_json::Value;
+use std::fmt;
+
+const LUNU_SCHEMA: &str = include_str!("schemas/lunu.schema.json");
+const SETTINGS_SCHEMA: &str = include_str!("schemas/settings.schema.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFile {
+    LunuToml,
+    Settings,
+}
+
+impl ConfigFile {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFile::LunuToml => "lunu.toml",
+            ConfigFile::Settings => "config/settings.json",
+        }
+    }
+
+    /// The JSON schema document, e.g. for editor integration (`lunu config schema`).
+    pub fn schema(self) -> &'static str {
+        match self {
+            ConfigFile::LunuToml => LUNU_SCHEMA,
+            ConfigFile::Settings => SETTINGS_SCHEMA,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Unknown fields: serde ignores them, but they are almost always typos
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 1-based line of the offending key (or its closest enclosing table/object)
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, label, self.message),
+            None => write!(f, "{}: {}", label, self.message),
+        }
+    }
+}
+
+/// Parses `text` and checks it against the embedded schema. Syntax errors are returned as `Err`.
+pub fn validate(file: ConfigFile, text: &str) -> anyhow::Result<Vec<Issue>> {
+    let value: Value = match file {
+        ConfigFile::LunuToml => {
+            let parsed: toml::Value = toml::from_str(text).map_err(|e| anyhow::anyhow!("Failed to parse lunu.toml: {}", e))?;
+            serde_json::to_value(parsed)?
+        }
+        ConfigFile::Settings => serde_json::from_str(text)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config/settings.json: {}", e))?,
+    };
+    let schema: Value = serde_json::from_str(file.schema())?;
+    let mut found = Vec::new();
+    check(&schema, &value, &mut Vec::new(), &mut found);
+    let mut issues: Vec<Issue> = found
+        .into_iter()
+        .map(|(severity, path, message)| Issue {
+            severity,
+            line: match file {
+                ConfigFile::LunuToml => toml_line(text, &path),
+                ConfigFile::Settings => json_line(text, &path),
+            },
+            message,
+        })
+        .collect();
+    issues.sort_by_key(|i| i.line);
+    Ok(issues)
+}
+
+/// `file` followed by one issue per line, as printed by `lunu config validate` and failed loads.
+pub fn format_issues(file: &str, issues: &[Issue]) -> String {
+    issues.iter().map(|issue| format!("{}: {}", file, issue)).collect::<Vec<_>>().join("\n")
+}
+
+pub fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+type Found = Vec<(Severity, Vec<String>, String)>;
+
+fn display_path(path: &[String]) -> String {
+    let mut out = String::new();
+    for segment in path {
+        if segment.starts_with('[') || out.is_empty() {
+            out.push_str(segment);
+        } else {
+            out.push('.');
+            out.push_str(segment);
+        }
+    }
+    out
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "table",
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        _ => true,
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &mut Vec<String>, found: &mut Found) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(t, value)) {
+            let names: Vec<&str> = types.iter().map(|t| if *t == "object" { "table" } else { t }).collect();
+            found.push((
+                Severity::Error,
+                path.clone(),
+                format!("`{}` should be a {}, found {}", display_path(path), names.join(" or "), type_name(value)),
+            ));
+            return;
+        }
+    }
+    if let (Some(allowed), Some(text)) = (schema.get("enum").and_then(Value::as_array), value.as_str()) {
+        if !allowed.iter().any(|a| a.as_str() == Some(text)) {
+            let options: Vec<&str> = allowed.iter().filter_map(Value::as_str).collect();
+            let hint = suggest(text, &options).map(|s| format!(", did you mean `{}`?", s)).unwrap_or_default();
+            found.push((
+                Severity::Error,
+                path.clone(),
+                format!("`{}` must be one of {}; found `{}`{}", display_path(path), options.join(", "), text, hint),
+            ));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        let min = schema.get("minimum").and_then(Value::as_f64);
+        let max = schema.get("maximum").and_then(Value::as_f64);
+        if min.is_some_and(|m| number < m) || max.is_some_and(|m| number > m) {
+            found.push((
+                Severity::Error,
+                path.clone(),
+                format!(
+                    "`{}` must be between {} and {}",
+                    display_path(path),
+                    min.unwrap_or(f64::MIN),
+                    max.unwrap_or(f64::MAX)
+                ),
+            ));
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            path.push(format!("[{}]", index));
+            check(items, item, path, found);
+            path.pop();
+        }
+    }
+    let Some(object) = value.as_object() else {
+        return;
+    };
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for required in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+        if !object.contains_key(required) {
+            let location = if path.is_empty() { String::new() } else { format!(" in [{}]", display_path(path)) };
+            found.push((Severity::Error, path.clone(), format!("missing field `{}`{}", required, location)));
+        }
+    }
+    for (key, child) in object {
+        path.push(key.clone());
+        match (properties.and_then(|p| p.get(key)), schema.get("additionalProperties")) {
+            (Some(child_schema), _) => check(child_schema, child, path, found),
+            (None, Some(Value::Bool(false))) => {
+                let known: Vec<&str> = properties.map(|p| p.keys().map(String::as_str).collect()).unwrap_or_default();
+                let hint = suggest(key, &known).map(|s| format!(", did you mean `{}`?", s)).unwrap_or_default();
+                let location = if path.len() > 1 { format!(" in [{}]", display_path(&path[..path.len() - 1])) } else { String::new() };
+                found.push((Severity::Warning, path.clone(), format!("unknown field `{}`{}{}", key, location, hint)));
+            }
+            (None, Some(extra)) if extra.is_object() => check(extra, child, path, found),
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The closest candidate within a couple of edits, scaled to the word length.
+pub fn suggest<'a>(word: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (word.chars().count() / 3).clamp(1, 3);
+    candidates
+        .iter()
+        .map(|c| (edit_distance(&word.to_lowercase(), &c.to_lowercase()), *c))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+fn unquote(key: &str) -> String {
+    key.trim().trim_matches('"').trim_matches('\'').to_string()
+}
+
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.').map(unquote).collect()
+}
+
+/// Line of `path` in a TOML document: the key itself, else the table header, else the closest parent.
+fn toml_line(text: &str, path: &[String]) -> Option<usize> {
+    let path: Vec<&str> = path.iter().filter(|s| !s.starts_with('[')).map(String::as_str).collect();
+    let mut table: Vec<String> = Vec::new();
+    let mut best: Option<(usize, usize)> = None;
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let full: Vec<String> = if let Some(header) = line.strip_prefix('[') {
+            let header = header.trim_start_matches('[');
+            let end = header.find(']').unwrap_or(header.len());
+            table = split_key(&header[..end]);
+            table.clone()
+        } else if let Some((key, _)) = line.split_once('=').filter(|_| !line.starts_with('#')) {
+            table.iter().cloned().chain(split_key(key)).collect()
+        } else {
+            continue;
+        };
+        let depth = full.iter().zip(&path).take_while(|(a, b)| a == *b).count();
+        // Only count lines that sit on the path, not siblings that share a prefix
+        if depth > 0 && (depth == full.len() || depth == path.len()) && best.is_none_or(|(d, _)| depth > d) {
+            best = Some((depth, index + 1));
+        }
+    }
+    best.map(|(_, line)| line)
+}
+
+/// Line of `path` in a JSON document, found by searching for each key after its parent's.
+fn json_line(text: &str, path: &[String]) -> Option<usize> {
+    let mut position = None;
+    let mut from = 0;
+    for segment in path.iter().filter(|s| !s.starts_with('[')) {
+        let needle = format!("\"{}\"", segment);
+        let Some(found) = text[from..].find(&needle).map(|i| i + from) else {
+            break;
+        };
+        position = Some(found);
+        from = found + needle.len();
+    }
+    position.map(|p| text[..p].matches('\n').count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"[project]
+name = "demo"
+entry = "src/main.luau"
+modues_dir = "modules"
+
+[dependencies]
+json = { url = "https://github.com/x/json" }
+
+[build]
+kind = "bridge"
+link = "http-bridge"
+modules = "bridge"
+cleanup = "sometimes"
+
+[build.gates]
+lint = "yes"
+"#;
+
+    #[test]
+    fn reports_lunu_toml_problems_with_lines() {
+        let issues = validate(ConfigFile::LunuToml, PROJECT).unwrap();
+        let lines: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert!(lines.contains(&"line 4: warning: unknown field `modues_dir` in [project], did you mean `modules_dir`?".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"line 1: error: missing field `modules_dir` in [project]".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"line 13: error: `build.cleanup` must be one of always, never, on-success, on_success; found `sometimes`".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"line 16: error: `build.gates.lint` should be a boolean, found string".to_string()), "{:?}", lines);
+        assert_eq!(issues.len(), 4);
+        assert!(has_errors(&issues));
+        assert!(validate(ConfigFile::LunuToml, "[project\nname = 1").is_err());
+    }
+
+    #[test]
+    fn reports_settings_problems_with_lines() {
+        let settings = r#"{
+  "server": { "host": "127.0.0.1", "http_port": 70000, "ssl_enabled": false, "ssl_cert_path": "", "ssl_key_path": "" },
+  "security": {
+    "auth_enabled": true,
+    "allowed_hosts": ["localhost", 1]
+  },
+  "loging": { "level": "info", "file": "logs/server.log" }
+}"#;
+        let issues = validate(ConfigFile::Settings, settings).unwrap();
+        let lines: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "error: missing field `logging`",
+                "line 2: error: `server.http_port` must be between 0 and 65535",
+                "line 5: error: `security.allowed_hosts[1]` should be a string, found integer",
+                "line 7: warning: unknown field `loging`, did you mean `logging`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn suggests_close_names_only() {
+        assert_eq!(suggest("modues_dir", &["name", "modules_dir"]), Some("modules_dir"));
+        assert_eq!(suggest("colour", &["name", "entry"]), None);
+    }
+}
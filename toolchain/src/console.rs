@@ -0,0 +1,100 @@
+//! What the terminal can display. Legacy Windows consoles use an ANSI code page and turn UTF-8
+//! into mojibake, and Unix terminals without a UTF-8 locale do the same. `init` probes the console
+//! once (switching Windows consoles to UTF-8 where possible); human-readable output then picks its
+//! symbols with `glyph` and `emoji`, which fall back to ASCII.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static UNICODE: AtomicBool = AtomicBool::new(true);
+static EMOJI: AtomicBool = AtomicBool::new(true);
+
+/// Probes the console; call once at startup. `LUNU_OUTPUT=ascii` or `LUNU_OUTPUT=unicode`
+/// overrides the probe.
+pub fn init() {
+    let (unicode, emoji) = match std::env::var("LUNU_OUTPUT").map(|v| v.to_lowercase()).as_deref() {
+        Ok("ascii") => (false, false),
+        Ok("unicode") | Ok("utf8") | Ok("utf-8") => (true, true),
+        _ => os::probe(),
+    };
+    UNICODE.store(unicode, Ordering::Relaxed);
+    EMOJI.store(unicode && emoji, Ordering::Relaxed);
+}
+
+pub fn unicode() -> bool {
+    UNICODE.load(Ordering::Relaxed)
+}
+
+/// `unicode` (e.g. `…`, `✓`) when the console can show it, otherwise `ascii`.
+pub fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if self::unicode() {
+        unicode
+    } else {
+        ascii
+    }
+}
+
+/// `emoji` where it renders, otherwise nothing; emoji are decoration only.
+pub fn emoji(emoji: &'static str) -> &'static str {
+    if EMOJI.load(Ordering::Relaxed) {
+        emoji
+    } else {
+        ""
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use winapi::um::consoleapi::GetConsoleOutputCP;
+    use winapi::um::wincon::SetConsoleOutputCP;
+
+    const CP_UTF8: u32 = 65001;
+
+    /// Switches the console to UTF-8 (supported since Windows 7). Emoji additionally need a
+    /// modern host: Windows Terminal or an editor terminal; the classic console draws boxes.
+    pub fn probe() -> (bool, bool) {
+        let unicode = unsafe { GetConsoleOutputCP() == CP_UTF8 || (SetConsoleOutputCP(CP_UTF8) != 0 && GetConsoleOutputCP() == CP_UTF8) };
+        let modern_host = std::env::var_os("WT_SESSION").is_some() || std::env::var_os("TERM_PROGRAM").is_some();
+        (unicode, modern_host)
+    }
+}
+
+#[cfg(not(windows))]
+mod os {
+    /// UTF-8 locales can show Unicode; the Linux virtual console has no emoji font.
+    pub fn probe() -> (bool, bool) {
+        let unicode = locale_is_utf8(|name| std::env::var(name).ok());
+        let emoji = std::env::var("TERM").map(|t| t != "linux").unwrap_or(true);
+        (unicode, emoji)
+    }
+
+    /// The first locale variable that is set decides, as in C's `setlocale`.
+    pub fn locale_is_utf8(var: impl Fn(&str) -> Option<String>) -> bool {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| var(name).filter(|v| !v.is_empty()))
+            .map(|locale| {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::os::locale_is_utf8;
+    use std::collections::HashMap;
+
+    #[test]
+    fn first_set_locale_variable_decides() {
+        let env = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            move |name: &str| vars.get(name).cloned()
+        };
+        assert!(locale_is_utf8(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(env(&[("LC_CTYPE", "C.utf8"), ("LANG", "C")])));
+        assert!(!locale_is_utf8(env(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")])));
+        assert!(locale_is_utf8(env(&[("LC_ALL", ""), ("LANG", "de_DE.UTF-8")])));
+        assert!(!locale_is_utf8(env(&[])));
+    }
+}
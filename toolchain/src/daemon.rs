@@ -0,0 +1,348 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Result, Context};
+use axum::{
+    extract::{State, ws::{Message, WebSocket, WebSocketUpgrade}},
+    http::{HeaderMap, StatusCode, Uri},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::broadcast;
+use crate::lock::LockFile;
+use crate::project::ProjectConfig;
+use lunu_cli::{allowed_hosts, secret_store};
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:7420";
+
+/// CLI subcommands editors may trigger through `task.run`.
+const TASKS: &[&str] = &["install", "update", "build", "test", "check", "package"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+struct DaemonState {
+    root: PathBuf,
+    token: String,
+    events: broadcast::Sender<Value>,
+    next_task: AtomicU64,
+}
+
+/// Where a running daemon keeps the token clients must present; only the current user can read it.
+pub fn token_path(root: &Path) -> PathBuf {
+    root.join(".lunu").join("daemon.token")
+}
+
+/// Serves JSON-RPC 2.0 for editor integrations: over WebSocket at `/ws` (with progress
+/// notifications) and as one-shot requests at `POST /rpc`. Each start writes a new token to
+/// `token_path`; requests without it, or sent by a page on another site, are refused.
+pub async fn serve(root: PathBuf, listen: &str) -> Result<()> {
+    let addr: SocketAddr = listen.parse().with_context(|| format!("Invalid listen address '{}'", listen))?;
+    if !addr.ip().is_loopback() {
+        println!("Warning: daemon is listening on {} which is reachable from other machines.", addr);
+    }
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    secret_store::write_private(&token_path(&root), &token)?;
+    let (events, _) = broadcast::channel(1024);
+    let state = Arc::new(DaemonState { root, token, events, next_task: AtomicU64::new(1) });
+
+    let app = Router::new()
+        .route("/ws", get(ws_handler))
+        .route("/rpc", post(rpc_http))
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .with_context(|| format!("Failed to bind daemon to {}", addr))?;
+    println!("Lunu daemon listening on ws://{}/ws", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Whether an `Origin` header names this machine. Browsers send one with every WebSocket upgrade
+/// and cross-site POST, so a page on another site never gets past this check.
+fn is_local_origin(origin: &str) -> bool {
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = allowed_hosts::host_name(authority.split('/').next().unwrap_or_default());
+    host == "localhost" || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// The token of a request: the `X-LUNU-KEY` header, or a `token` query parameter for clients
+/// (such as browsers' WebSocket API) that cannot set headers.
+fn request_token<'a>(headers: &'a HeaderMap, uri: &'a Uri) -> Option<&'a str> {
+    headers.get("X-LUNU-KEY").and_then(|v| v.to_str().ok()).or_else(|| {
+        uri.query()?.split('&').find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+/// Compares the SHA-256 digests of the tokens in constant time, so response timing does not
+/// reveal how much of a guess was right.
+fn token_matches(given: &str, expected: &str) -> bool {
+    let (given, expected) = (Sha256::digest(given.as_bytes()), Sha256::digest(expected.as_bytes()));
+    given.iter().zip(expected.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn auth_middleware(
+    State(state): State<Arc<DaemonState>>,
+    headers: HeaderMap,
+    request: axum::http::Request<axum::body::Body>,
+    next: middleware::Next,
+) -> Response {
+    if let Some(origin) = headers.get("origin") {
+        if !origin.to_str().is_ok_and(is_local_origin) {
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    if !request_token(&headers, request.uri()).is_some_and(|token| token_matches(token, &state.token)) {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid daemon token").into_response();
+    }
+    next.run(request).await
+}
+
+async fn rpc_http(State(state): State<Arc<DaemonState>>, Json(request): Json<Value>) -> Json<Value> {
+    Json(dispatch(&state, request).await)
+}
+
+async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<DaemonState>>) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: Arc<DaemonState>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = state.events.subscribe();
+    loop {
+        tokio::select! {
+            msg = receiver.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => continue,
+                };
+                let response = match serde_json::from_str::<Value>(&text) {
+                    Ok(request) => dispatch(&state, request).await,
+                    Err(err) => rpc_error(Value::Null, PARSE_ERROR, &err.to_string()),
+                };
+                if sender.send(Message::Text(response.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if sender.send(Message::Text(event.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+async fn dispatch(state: &Arc<DaemonState>, request: Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request.get("method").and_then(|m| m.as_str()) else {
+        return rpc_error(id, INVALID_REQUEST, "Missing method");
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    match method {
+        "project.state" => match project_state(&state.root).await {
+            Ok(value) => rpc_result(id, value),
+            Err(err) => rpc_error(id, INTERNAL_ERROR, &format!("{:#}", err)),
+        },
+        "task.list" => rpc_result(id, json!(TASKS)),
+        "task.run" => {
+            let Some(task) = params.get("task").and_then(|t| t.as_str()) else {
+                return rpc_error(id, INVALID_PARAMS, "Missing 'task'");
+            };
+            if !TASKS.contains(&task) {
+                return rpc_error(id, INVALID_PARAMS, &format!("Unknown task '{}'", task));
+            }
+            let args: Vec<String> = params
+                .get("args")
+                .and_then(|a| a.as_array())
+                .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                .unwrap_or_default();
+            match start_task(state, task, args) {
+                Ok(task_id) => rpc_result(id, json!({ "task_id": task_id })),
+                Err(err) => rpc_error(id, INTERNAL_ERROR, &format!("{:#}", err)),
+            }
+        }
+        _ => rpc_error(id, METHOD_NOT_FOUND, &format!("Unknown method '{}'", method)),
+    }
+}
+
+async fn project_state(root: &Path) -> Result<Value> {
+    let config_path = root.join("lunu.toml");
+    if !config_path.exists() {
+        return Ok(json!({ "root": root, "initialized": false }));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let lock = LockFile::load(&root.join("lunu.lock")).await?;
+    let dependencies: Vec<Value> = cfg
+        .dependencies
+        .iter()
+        .map(|(name, spec)| {
+            let locked = lock.dependencies.get(name);
+            json!({
+                "name": name,
+                "url": spec.url,
+                "version": spec.version,
+                "path": spec.path,
+                "installed": locked.is_some(),
+                "checksum": locked.map(|l| l.checksum.clone()),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "root": root,
+        "initialized": true,
+        "name": cfg.project.name,
+        "entry": cfg.project.entry,
+        "runtime": cfg.runtime.as_ref().map(|r| r.name.clone()),
+        "dependencies": dependencies,
+    }))
+}
+
+/// Runs `lunu <task> [args]` in the project root and publishes its output as `task.progress`
+/// notifications (structured `phase`/`percent` for progress events), followed by `task.finished`.
+fn start_task(state: &Arc<DaemonState>, task: &str, args: Vec<String>) -> Result<u64> {
+    let task_id = state.next_task.fetch_add(1, Ordering::SeqCst);
+    let exe = std::env::current_exe().with_context(|| "Failed to locate the lunu executable")?;
+    let mut child = tokio::process::Command::new(exe)
+        .arg(task)
+        .args(&args)
+        .args(["--progress", "json"])
+        .current_dir(&state.root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start task '{}'", task))?;
+
+    let events = state.events.clone();
+    let _ = events.send(notification("task.started", json!({ "task_id": task_id, "task": task, "args": args })));
+    let stdout = child.stdout.take().map(|s| publish_lines(s, events.clone(), task_id, "stdout"));
+    let stderr = child.stderr.take().map(|s| publish_lines(s, events.clone(), task_id, "stderr"));
+    tokio::spawn(async move {
+        let status = child.wait().await;
+        for reader in [stdout, stderr].into_iter().flatten() {
+            let _ = reader.await;
+        }
+        let (success, code) = match status {
+            Ok(s) => (s.success(), s.code()),
+            Err(_) => (false, None),
+        };
+        let _ = events.send(notification("task.finished", json!({ "task_id": task_id, "success": success, "code": code })));
+    });
+    Ok(task_id)
+}
+
+fn publish_lines<R>(reader: R, events: broadcast::Sender<Value>, task_id: u64, stream: &'static str) -> tokio::task::JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let params = match lunu_builder::progress::ProgressEvent::parse(&line) {
+                Some(event) => json!({
+                    "task_id": task_id,
+                    "stream": "progress",
+                    "phase": event.phase,
+                    "percent": event.percent,
+                    "message": event.message,
+                }),
+                None => json!({ "task_id": task_id, "stream": stream, "line": line }),
+            };
+            let _ = events.send(notification("task.progress", params));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn state(root: &Path) -> Arc<DaemonState> {
+        let (events, _) = broadcast::channel(16);
+        Arc::new(DaemonState { root: root.to_path_buf(), token: "secret".to_string(), events, next_task: AtomicU64::new(1) })
+    }
+
+    #[test]
+    fn requests_need_the_token_and_a_local_origin() {
+        for origin in ["http://localhost:3000", "http://127.0.0.1", "https://[::1]:8443/"] {
+            assert!(is_local_origin(origin), "{}", origin);
+        }
+        for origin in ["https://evil.example", "http://localhost.evil.example", "null", "http://192.168.0.10"] {
+            assert!(!is_local_origin(origin), "{}", origin);
+        }
+
+        let uri: Uri = "/ws?client=vscode&token=secret".parse().unwrap();
+        assert_eq!(request_token(&HeaderMap::new(), &uri), Some("secret"));
+        let mut headers = HeaderMap::new();
+        headers.insert("X-LUNU-KEY", "from-header".parse().unwrap());
+        assert_eq!(request_token(&headers, &"/rpc".parse().unwrap()), Some("from-header"));
+        assert_eq!(request_token(&HeaderMap::new(), &"/rpc".parse().unwrap()), None);
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secreT", "secret") && !token_matches("", "secret"));
+    }
+
+    #[tokio::test]
+    async fn dispatch_reports_unknown_methods_and_tasks() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+        let res = dispatch(&state, json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" })).await;
+        assert_eq!(res["error"]["code"], METHOD_NOT_FOUND);
+        let res = dispatch(&state, json!({ "id": 2, "method": "task.run", "params": { "task": "uninstall" } })).await;
+        assert_eq!(res["error"]["code"], INVALID_PARAMS);
+        assert_eq!(res["id"], 2);
+    }
+
+    #[tokio::test]
+    async fn project_state_lists_dependencies() {
+        let dir = tempdir().unwrap();
+        let state = state(dir.path());
+        let res = dispatch(&state, json!({ "id": 1, "method": "project.state" })).await;
+        assert_eq!(res["result"]["initialized"], false);
+
+        let mut cfg = ProjectConfig::new("Demo");
+        cfg.add_dependency("json", crate::project::DependencySpec {
+            url: Some("https://github.com/example/json".to_string()),
+            ..Default::default()
+        });
+        cfg.save(&dir.path().join("lunu.toml")).await.unwrap();
+        let res = dispatch(&state, json!({ "id": 1, "method": "project.state" })).await;
+        assert_eq!(res["result"]["name"], "Demo");
+        assert_eq!(res["result"]["dependencies"][0]["installed"], false);
+    }
+}
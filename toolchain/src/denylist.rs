@@ -0,0 +1,104 @@
+//! Package URLs that `lunu audit` reports as known-bad. The denylist is a text file with one
+//! repository per line and an optional `# reason`; `owner/*` denies every repository of an owner
+//! and `user/repo` without a host means GitHub:
+//!
+//! ```text
+//! github.com/evil/left-pad   # typosquat of left-pad
+//! compromised-org/*          # account takeover, 2026-03
+//! ```
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenyEntry {
+    pub pattern: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Denylist {
+    entries: Vec<DenyEntry>,
+}
+
+/// `~/.lunu/denylist.txt`, used when `--denylist` is not given and the file exists.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lunu").join("denylist.txt"))
+}
+
+pub fn load(path: &Path) -> Result<Denylist> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read denylist {:?}", path))?;
+    Ok(parse(&content))
+}
+
+pub fn parse(content: &str) -> Denylist {
+    let entries = content
+        .lines()
+        .filter_map(|line| {
+            let (pattern, reason) = match line.split_once('#') {
+                Some((pattern, reason)) => (pattern.trim(), Some(reason.trim().to_string()).filter(|r| !r.is_empty())),
+                None => (line.trim(), None),
+            };
+            (!pattern.is_empty()).then(|| DenyEntry { pattern: normalize(pattern), reason })
+        })
+        .collect();
+    Denylist { entries }
+}
+
+impl Denylist {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The entry denying `url`, if any.
+    pub fn check(&self, url: &str) -> Option<&DenyEntry> {
+        let url = normalize(url);
+        self.entries.iter().find(|entry| match entry.pattern.strip_suffix("/*") {
+            Some(owner) => url.starts_with(&format!("{}/", owner)),
+            None => url == entry.pattern,
+        })
+    }
+}
+
+/// `https://GitHub.com/User/Repo.git`, `git@github.com:user/repo` and `user/repo` all become
+/// `github.com/user/repo`.
+fn normalize(url: &str) -> String {
+    let mut url = url.trim().to_lowercase();
+    for prefix in ["https://", "http://", "ssh://", "git://", "git@"] {
+        if let Some(rest) = url.strip_prefix(prefix) {
+            url = rest.to_string();
+        }
+    }
+    url = url.replacen(':', "/", 1);
+    let url = url.trim_start_matches("www.").trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let host_given = url.split('/').next().is_some_and(|first| first.contains('.'));
+    if host_given {
+        url.to_string()
+    } else {
+        format!("github.com/{}", url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_repositories_and_owners_in_any_url_form() {
+        let list = parse(
+            "# known-bad packages\n\
+             github.com/evil/left-pad   # typosquat\n\
+             Compromised-Org/*\n\
+             \n\
+             gitlab.com/x/y\n",
+        );
+        assert_eq!(list.len(), 3);
+        let hit = list.check("https://github.com/Evil/left-pad.git").unwrap();
+        assert_eq!(hit.reason.as_deref(), Some("typosquat"));
+        assert!(list.check("git@github.com:compromised-org/anything").is_some());
+        assert!(list.check("https://gitlab.com/x/y/").is_some());
+        assert!(list.check("https://github.com/evil/left-pad-extra").is_none());
+        assert!(list.check("https://github.com/compromised-org-2/pkg").is_none());
+    }
+}
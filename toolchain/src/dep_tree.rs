@@ -0,0 +1,270 @@
+//! `lunu tree`: the resolved dependency graph. Direct dependencies come from lunu.toml and
+//! lunu.lock; what each installed package declares in its own lunu.toml, wally.toml or
+//! pesde.toml is matched to the installed packages by source. Lunu does not install transitive
+//! dependencies yet, so a declared one that nothing installed is shown as such:
+//!
+//! ```text
+//! app
+//! ├── json 1.2.0 (github.com/acme/json)
+//! │   └── utf8 0.3.1 (github.com/acme/utf8)
+//! └── net 2.0.1 (wally:acme/net)
+//!     └── json ^1.4 (github.com/acme/json) [not installed]
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use crate::metadata::parse_git_remote;
+use crate::project::DependencySpec;
+use crate::{pesde, wally};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    /// Dependency name, the alias the package is installed under
+    pub name: String,
+    /// Version, tag or short commit
+    pub version: Option<String>,
+    /// A repository, `wally:scope/name`, `pesde:scope/name` or a path
+    pub source: String,
+    pub installed: bool,
+    pub dev: bool,
+    pub requires: Vec<Requirement>,
+}
+
+/// A dependency a package declares.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub name: String,
+    /// Requirement or ref as declared, e.g. `^1.4`
+    pub version: Option<String>,
+    pub source: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub root: String,
+    /// Direct dependencies, by name
+    pub packages: BTreeMap<String, Package>,
+}
+
+/// `source` in a form that is the same for every way of writing it: `host/owner/repo` for
+/// repositories, lowercase registry names.
+pub fn source_key(source: &str) -> String {
+    let registry = source.starts_with(wally::PREFIX) || source.starts_with(pesde::PREFIX);
+    if let Some((host, path)) = parse_git_remote(source).filter(|_| !registry) {
+        return format!("{}/{}", host, path.to_lowercase());
+    }
+    source.trim().trim_end_matches('/').to_lowercase()
+}
+
+fn spec_source(spec: &DependencySpec) -> String {
+    if let Some(url) = &spec.url {
+        return match &spec.subdir {
+            Some(subdir) => format!("{}#{}", source_key(url), subdir),
+            None => source_key(url),
+        };
+    }
+    spec.registry().or_else(|| spec.path.clone()).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The dependencies the package in `dir` declares in its lunu.toml, wally.toml or pesde.toml.
+pub fn read_requirements(dir: &Path) -> Vec<Requirement> {
+    let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok().and_then(|c| toml::from_str::<toml::Value>(&c).ok());
+    let tables = |manifest: &toml::Value, names: &[&str]| -> Vec<(String, toml::Value)> {
+        names
+            .iter()
+            .filter_map(|name| manifest.get(name).and_then(toml::Value::as_table))
+            .flat_map(|table| table.iter().map(|(k, v)| (k.clone(), v.clone())))
+            .collect()
+    };
+    let mut requires = Vec::new();
+    if let Some(manifest) = read("lunu.toml") {
+        for (name, value) in tables(&manifest, &["dependencies"]) {
+            let Ok(spec) = value.try_into::<DependencySpec>() else { continue };
+            let version = spec.version.clone().or(spec.tag.clone()).or(spec.branch.clone()).or(spec.rev.clone());
+            requires.push(Requirement { name, version, source: spec_source(&spec) });
+        }
+    }
+    if let Some(manifest) = read("wally.toml") {
+        for (name, value) in tables(&manifest, &["dependencies", "server-dependencies"]) {
+            let Some(Ok((package, req))) = value.as_str().map(wally::parse_spec) else { continue };
+            requires.push(Requirement { name: name.to_lowercase(), version: Some(req.to_string()), source: wally::origin(&package) });
+        }
+    }
+    if let Some(manifest) = read("pesde.toml") {
+        for (name, value) in tables(&manifest, &["dependencies", "peer_dependencies"]) {
+            let Some(package) = value.get("name").and_then(toml::Value::as_str) else { continue };
+            let version = value.get("version").and_then(toml::Value::as_str).map(str::to_string);
+            requires.push(Requirement { name, version, source: pesde::origin(&package.to_lowercase()) });
+        }
+    }
+    requires
+}
+
+impl Graph {
+    /// The installed package `requirement` resolves to: one from the same source, preferring the
+    /// one installed under the same name.
+    fn resolve(&self, requirement: &Requirement) -> Option<&Package> {
+        let key = source_key(&requirement.source);
+        let mut matches = self.packages.values().filter(|p| p.installed && source_key(&p.source) == key);
+        let first = matches.next()?;
+        Some(std::iter::once(first).chain(matches).find(|p| p.name == requirement.name).unwrap_or(first))
+    }
+
+    /// The tree as lines, and every dependency cycle as the names along it.
+    pub fn render(&self, unicode: bool) -> (Vec<String>, Vec<Vec<String>>) {
+        let mut walk = Walk { graph: self, unicode, lines: vec![self.root.clone()], cycles: Vec::new(), path: Vec::new(), shown: BTreeSet::new() };
+        let names: Vec<&String> = self.packages.keys().collect();
+        for (i, name) in names.iter().enumerate() {
+            walk.package(&self.packages[*name], "", i + 1 == names.len());
+        }
+        (walk.lines, walk.cycles)
+    }
+
+    /// Sources installed more than once (under several names, usually at different versions),
+    /// each with its installs and what requires them.
+    pub fn duplicates(&self) -> Vec<String> {
+        let mut by_source: BTreeMap<String, Vec<&Package>> = BTreeMap::new();
+        for package in self.packages.values().filter(|p| p.installed) {
+            by_source.entry(source_key(&package.source)).or_default().push(package);
+        }
+        let mut lines = Vec::new();
+        for (source, packages) in by_source.into_iter().filter(|(_, p)| p.len() > 1) {
+            lines.push(format!("{} ({} installs)", source, packages.len()));
+            for package in packages {
+                let mut dependents = vec![self.root.clone()];
+                for other in self.packages.values() {
+                    if other.requires.iter().any(|r| self.resolve(r).is_some_and(|p| p.name == package.name)) {
+                        dependents.push(other.name.clone());
+                    }
+                }
+                lines.push(format!("  {} {} (required by {})", package.name, package.version.as_deref().unwrap_or("-"), dependents.join(", ")));
+            }
+        }
+        lines
+    }
+}
+
+struct Walk<'a> {
+    graph: &'a Graph,
+    unicode: bool,
+    lines: Vec<String>,
+    cycles: Vec<Vec<String>>,
+    /// Names from the root to the package being drawn
+    path: Vec<String>,
+    /// Packages whose dependencies were drawn already
+    shown: BTreeSet<String>,
+}
+
+impl Walk<'_> {
+    fn branch(&self, prefix: &str, last: bool) -> (String, String) {
+        let (branch, indent) = match (self.unicode, last) {
+            (true, true) => ("└── ", "    "),
+            (true, false) => ("├── ", "│   "),
+            (false, true) => ("`-- ", "    "),
+            (false, false) => ("|-- ", "|   "),
+        };
+        (format!("{}{}", prefix, branch), format!("{}{}", prefix, indent))
+    }
+
+    fn package(&mut self, package: &Package, prefix: &str, last: bool) {
+        let (branch, indent) = self.branch(prefix, last);
+        let mut line = format!("{}{} {} ({})", branch, package.name, package.version.as_deref().unwrap_or("-"), package.source);
+        if package.dev {
+            line.push_str(" [dev]");
+        }
+        if !package.installed {
+            self.lines.push(line + " [not installed]");
+            return;
+        }
+        if let Some(start) = self.path.iter().position(|n| *n == package.name) {
+            let mut cycle = self.path[start..].to_vec();
+            cycle.push(package.name.clone());
+            self.cycles.push(cycle);
+            self.lines.push(line + " (cycle)");
+            return;
+        }
+        if !package.requires.is_empty() && !self.shown.insert(package.name.clone()) {
+            // Drawn in full where it first appeared
+            self.lines.push(line + " (*)");
+            return;
+        }
+        self.lines.push(line);
+        self.path.push(package.name.clone());
+        for (i, requirement) in package.requires.iter().enumerate() {
+            let last = i + 1 == package.requires.len();
+            match self.graph.resolve(requirement) {
+                Some(resolved) => self.package(resolved, &indent, last),
+                None => {
+                    let (branch, _) = self.branch(&indent, last);
+                    self.lines.push(format!(
+                        "{}{} {} ({}) [not installed]",
+                        branch,
+                        requirement.name,
+                        requirement.version.as_deref().unwrap_or("*"),
+                        requirement.source
+                    ));
+                }
+            }
+        }
+        self.path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, source: &str, requires: &[(&str, &str)]) -> Package {
+        Package {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            source: source.to_string(),
+            installed: true,
+            dev: false,
+            requires: requires.iter().map(|(name, source)| Requirement { name: name.to_string(), version: None, source: source.to_string() }).collect(),
+        }
+    }
+
+    #[test]
+    fn draws_the_tree_with_cycles_and_duplicates() {
+        let mut graph = Graph { root: "app".to_string(), ..Default::default() };
+        for p in [
+            package("json", "1.2.0", "github.com/acme/json", &[("utf8", "https://github.com/Acme/utf8.git"), ("net", "wally:acme/net")]),
+            package("net", "2.0.1", "wally:acme/net", &[("json", "git@github.com:acme/json"), ("log", "github.com/acme/log")]),
+            package("old-json", "1.1.0", "https://github.com/acme/json", &[]),
+            package("utf8", "0.3.1", "github.com/acme/utf8", &[]),
+        ] {
+            graph.packages.insert(p.name.clone(), p);
+        }
+        let (lines, cycles) = graph.render(false);
+        assert_eq!(lines, vec![
+            "app",
+            "|-- json 1.2.0 (github.com/acme/json)",
+            "|   |-- utf8 0.3.1 (github.com/acme/utf8)",
+            "|   `-- net 2.0.1 (wally:acme/net)",
+            "|       |-- json 1.2.0 (github.com/acme/json) (cycle)",
+            "|       `-- log * (github.com/acme/log) [not installed]",
+            "|-- net 2.0.1 (wally:acme/net) (*)",
+            "|-- old-json 1.1.0 (https://github.com/acme/json)",
+            "`-- utf8 0.3.1 (github.com/acme/utf8)",
+        ]);
+        assert_eq!(cycles, vec![vec!["json", "net", "json"]]);
+        assert_eq!(graph.duplicates(), vec![
+            "github.com/acme/json (2 installs)",
+            "  json 1.2.0 (required by app, net)",
+            "  old-json 1.1.0 (required by app)",
+        ]);
+    }
+
+    #[test]
+    fn reads_declared_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lunu.toml"), "[project]\nname = \"json\"\n\n[dependencies.utf8]\nurl = \"https://github.com/acme/utf8\"\nversion = \"^0.3\"\n").unwrap();
+        std::fs::write(dir.path().join("wally.toml"), "[dependencies]\nPromise = \"evaera/promise@^4.0.0\"\n").unwrap();
+        let requires = read_requirements(dir.path());
+        assert_eq!(requires, vec![
+            Requirement { name: "utf8".to_string(), version: Some("^0.3".to_string()), source: "github.com/acme/utf8".to_string() },
+            Requirement { name: "promise".to_string(), version: Some("^4.0.0".to_string()), source: "wally:evaera/promise".to_string() },
+        ]);
+    }
+}
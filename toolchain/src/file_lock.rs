@@ -0,0 +1,257 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+use anyhow::{Result, Context};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+const STALE_AFTER: Duration = Duration::from_secs(600);
+/// How often a held lock's timestamp is refreshed, well inside `STALE_AFTER`.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Advisory lock backed by an exclusively-created file holding the owner's PID and a nonce. The
+/// file is removed on drop while it still names this owner. While held, its timestamp is refreshed
+/// every `REFRESH_INTERVAL`, so only a lock left by a crashed process grows older than
+/// `STALE_AFTER`; those are broken.
+pub struct FileLock {
+    path: PathBuf,
+    owner: String,
+    /// Dropping it stops the refresh thread
+    refresh: Option<mpsc::Sender<()>>,
+}
+
+impl FileLock {
+    pub async fn acquire(path: &Path) -> Result<Self> {
+        Self::acquire_with_timeout(path, DEFAULT_TIMEOUT).await
+    }
+
+    pub async fn acquire_with_timeout(path: &Path, timeout: Duration) -> Result<Self> {
+        let start = std::time::Instant::now();
+        let mut announced = false;
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if start.elapsed() >= timeout {
+                return Err(anyhow::anyhow!(
+                    "Timed out waiting for lock {:?}. Remove it manually if no other Lunu process is running.",
+                    path
+                ));
+            }
+            if !announced {
+                println!("Waiting for another Lunu process to release {:?}...", path);
+                announced = true;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                let owner = format!("{} {:016x}", std::process::id(), rand::random::<u64>());
+                file.write_all(owner.as_bytes())
+                    .with_context(|| format!("Failed to write lock file {:?}", path))?;
+                Ok(Some(Self::held(path, owner, REFRESH_INTERVAL)))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                break_if_stale(path);
+                Ok(None)
+            }
+            Err(err) => Err(err).with_context(|| format!("Failed to create lock file {:?}", path)),
+        }
+    }
+
+    /// The lock at `path`, just created by this process, with its timestamp refreshed every
+    /// `interval` until it is dropped (a long download must not look like a crashed process).
+    fn held(path: &Path, owner: String, interval: Duration) -> Self {
+        let (refresh, stop) = mpsc::channel::<()>();
+        let (touched, ours) = (path.to_path_buf(), owner.clone());
+        std::thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(interval) {
+                if !owned_by(&touched, &ours) {
+                    break;
+                }
+                // Never create the file again once the lock is gone
+                if let Ok(file) = OpenOptions::new().write(true).open(&touched) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+            }
+        });
+        Self { path: path.to_path_buf(), owner, refresh: Some(refresh) }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        self.refresh.take();
+        if owned_by(&self.path, &self.owner) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn owned_by(path: &Path, owner: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content == owner)
+}
+
+/// Removes the lock at `path` when it is stale. It is first renamed to a unique tombstone, which
+/// only one waiter can do; if what was moved is not the stale lock that was seen (another waiter
+/// broke it and took a fresh one in between), that lock is put back instead of deleted.
+fn break_if_stale(path: &Path) {
+    if !is_stale(path) {
+        return;
+    }
+    let Ok(seen) = fs::read_to_string(path) else {
+        return;
+    };
+    let tombstone = unique_temp_path(path);
+    if fs::rename(path, &tombstone).is_err() {
+        return;
+    }
+    if !(owned_by(&tombstone, &seen) && is_stale(&tombstone)) {
+        // Linking fails rather than replace a lock created since the rename
+        let _ = fs::hard_link(&tombstone, path);
+    }
+    let _ = fs::remove_file(&tombstone);
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map(|age| age > STALE_AFTER)
+        .unwrap_or(false)
+}
+
+/// Returns a sibling path that is unique to this process and call, for write-then-rename.
+pub fn unique_temp_path(path: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let seq = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("lunu");
+    path.with_file_name(format!(".{}.{}.{}.{}.tmp", file_name, std::process::id(), nanos, seq))
+}
+
+/// Writes `content` to a unique temp file next to `path`, then renames it into place.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = unique_temp_path(path);
+    fs::write(&tmp, content)?;
+    if let Err(err) = replace_file(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Moves `src` over `dst`. Windows refuses to rename onto an existing file, so remove it first.
+pub fn replace_file(src: &Path, dst: &Path) -> Result<()> {
+    if cfg!(windows) && dst.exists() {
+        let _ = fs::remove_file(dst);
+    }
+    fs::rename(src, dst).with_context(|| format!("Failed to move {:?} to {:?}", src, dst))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("runtime.lock");
+        let first = FileLock::try_acquire(&path).unwrap();
+        assert!(first.is_some());
+        assert!(FileLock::try_acquire(&path).unwrap().is_none());
+        drop(first);
+        assert!(FileLock::try_acquire(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn held_locks_stay_fresh() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("runtime.lock");
+        fs::write(&path, "1").unwrap();
+        let long_ago = SystemTime::now() - STALE_AFTER * 2;
+        fs::File::options().write(true).open(&path).unwrap().set_modified(long_ago).unwrap();
+        assert!(is_stale(&path));
+
+        let lock = FileLock::held(&path, "1".to_string(), Duration::from_millis(10));
+        let start = std::time::Instant::now();
+        while is_stale(&path) && start.elapsed() < Duration::from_secs(2) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!is_stale(&path));
+        drop(lock);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn contending_waiters_break_a_stale_lock_once() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("runtime.lock");
+        for _ in 0..50 {
+            fs::write(&path, "1 crashed").unwrap();
+            let long_ago = SystemTime::now() - STALE_AFTER * 2;
+            fs::File::options().write(true).open(&path).unwrap().set_modified(long_ago).unwrap();
+
+            let barrier = std::sync::Barrier::new(2);
+            let locks: Vec<Option<FileLock>> = std::thread::scope(|scope| {
+                let waiters: Vec<_> = (0..2)
+                    .map(|_| scope.spawn(|| {
+                        barrier.wait();
+                        (0..3).find_map(|_| FileLock::try_acquire(&path).unwrap())
+                    }))
+                    .collect();
+                waiters.into_iter().map(|w| w.join().unwrap()).collect()
+            });
+            let held: Vec<&FileLock> = locks.iter().flatten().collect();
+            assert_eq!(held.len(), 1);
+            assert!(owned_by(&path, &held[0].owner));
+            drop(locks);
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn drop_leaves_a_lock_taken_over_by_another_process() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("runtime.lock");
+        let lock = FileLock::try_acquire(&path).unwrap().unwrap();
+        fs::write(&path, "2 other").unwrap();
+        drop(lock);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2 other");
+    }
+
+    #[test]
+    fn unique_temp_paths_differ() {
+        let path = Path::new("cache").join("lune");
+        assert_ne!(unique_temp_path(&path), unique_temp_path(&path));
+    }
+
+    #[test]
+    fn write_atomic_replaces_content() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("meta.json");
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+    }
+}
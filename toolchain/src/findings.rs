@@ -0,0 +1,124 @@
+//! Findings reported by `lunu check`. Each has an ID that stays the same between runs (e.g.
+//! `dependency.checksum/<name>`) so `.lunu/check-baseline.json` can suppress acknowledged ones.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub id: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Finding {
+    pub fn error(id: impl Into<String>, message: impl Into<String>) -> Self {
+        Finding { id: id.into(), severity: Severity::Error, message: message.into(), hint: None }
+    }
+
+    pub fn warning(id: impl Into<String>, message: impl Into<String>) -> Self {
+        Finding { id: id.into(), severity: Severity::Warning, message: message.into(), hint: None }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// `ERROR [id]: message` plus the indented hint, the way `lunu check` prints it.
+    pub fn print(&self) {
+        let label = match self.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARN",
+        };
+        println!("{} [{}]: {}", label, self.id, self.message);
+        if let Some(hint) = &self.hint {
+            println!("       {}", hint);
+        }
+    }
+}
+
+pub fn baseline_path(root: &Path) -> PathBuf {
+    root.join(".lunu").join("check-baseline.json")
+}
+
+/// IDs of findings the team has acknowledged.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Baseline {
+    pub suppressed: BTreeSet<String>,
+}
+
+impl Baseline {
+    /// The project's baseline, or an empty one when there is none.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = baseline_path(root);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).with_context(|| format!("Invalid {:?}", path)),
+            Err(_) => Ok(Baseline::default()),
+        }
+    }
+
+    pub fn from_findings(findings: &[Finding]) -> Self {
+        Baseline { suppressed: findings.iter().map(|f| f.id.clone()).collect() }
+    }
+
+    pub fn save(&self, root: &Path) -> Result<PathBuf> {
+        let path = baseline_path(root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(path)
+    }
+}
+
+/// Splits `findings` into (active, suppressed) and lists baseline IDs that no longer occur.
+pub fn apply_baseline(findings: Vec<Finding>, baseline: &Baseline) -> (Vec<Finding>, Vec<Finding>, Vec<String>) {
+    let stale = baseline
+        .suppressed
+        .iter()
+        .filter(|id| !findings.iter().any(|f| &f.id == *id))
+        .cloned()
+        .collect();
+    let (suppressed, active) = findings.into_iter().partition(|f| baseline.suppressed.contains(&f.id));
+    (active, suppressed, stale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_suppresses_acknowledged_findings() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Baseline::load(dir.path()).unwrap(), Baseline::default());
+
+        let known = vec![
+            Finding::error("dependency.checksum/logger", "changed"),
+            Finding::warning("config.missing", "no lunu.toml"),
+        ];
+        Baseline::from_findings(&known[..1]).save(dir.path()).unwrap();
+        let mut baseline = Baseline::load(dir.path()).unwrap();
+        baseline.suppressed.insert("worker.requires/gone".to_string());
+
+        let (active, suppressed, stale) = apply_baseline(known, &baseline);
+        assert_eq!(active.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(), vec!["config.missing"]);
+        assert_eq!(suppressed[0].id, "dependency.checksum/logger");
+        assert_eq!(stale, vec!["worker.requires/gone".to_string()]);
+
+        let json = serde_json::to_value(&active[0]).unwrap();
+        assert_eq!(json["severity"], "warning");
+        assert!(json.get("hint").is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use reqwest::Client;
-use anyhow::{Result, anyhow};
+use anyhow::{Result, Context};
+use crate::http;
 
 #[derive(Debug, Deserialize)]
 struct GithubResponse {
@@ -14,15 +15,47 @@ struct Data {
 
 #[derive(Debug, Deserialize)]
 struct Search {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
     nodes: Vec<RepositoryNode>,
 }
 
+#[derive(Debug, Deserialize)]
+struct PageInfo {
+    #[serde(rename = "hasNextPage")]
+    has_next_page: bool,
+    #[serde(rename = "endCursor")]
+    end_cursor: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")] // Handling generic nodes if needed, but simple structure works for specific query
 struct RepositoryNode {
     name: String,
     owner: Owner,
     url: String,
+    #[serde(rename = "stargazerCount", default)]
+    stars: u64,
+    description: Option<String>,
+    #[serde(rename = "updatedAt")]
+    updated_at: Option<String>,
+    #[serde(rename = "repositoryTopics")]
+    topics: Option<TopicConnection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicConnection {
+    nodes: Vec<TopicNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopicNode {
+    topic: Topic,
+}
+
+#[derive(Debug, Deserialize)]
+struct Topic {
+    name: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,11 +63,102 @@ struct Owner {
     login: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct RepoDetails {
+    description: Option<String>,
+    license: Option<RepoLicense>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoLicense {
+    spdx_id: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoTag {
+    name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RepoMetadata {
+    pub description: Option<String>,
+    pub license: Option<String>,
+    pub latest_tags: Vec<String>,
+    pub readme: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct PackageInfo {
     pub owner: String,
     pub name: String,
     pub url: String,
+    pub stars: u64,
+    pub description: Option<String>,
+    pub topics: Vec<String>,
+    /// Last update as an ISO 8601 timestamp
+    pub updated_at: Option<String>,
+}
+
+/// Order of search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchSort {
+    /// Name matches first, then topic matches, each by stars
+    #[default]
+    Relevance,
+    Stars,
+    Updated,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub sort: SearchSort,
+    /// Results returned after sorting
+    pub limit: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self { sort: SearchSort::Relevance, limit: 10 }
+    }
+}
+
+/// Results per search request; GitHub allows up to 100.
+const SEARCH_PAGE_SIZE: usize = 30;
+
+/// Ranking by relevance looks at this many results even when fewer are returned, so the
+/// canonical package is found when GitHub's best match puts forks or mentions above it.
+const RELEVANCE_CANDIDATES: usize = 30;
+
+/// Lower is better: 0 for an exact name (or `owner/name`) match, 1 when the name contains the
+/// query, 2 when a topic matches the query or one of its words, 3 otherwise. `-` and `_` compare
+/// equal and case is ignored.
+fn relevance_tier(query: &str, package: &PackageInfo) -> u8 {
+    let normalize = |s: &str| s.trim().to_lowercase().replace('_', "-");
+    let query = normalize(query);
+    let (owner, name_query) = match query.split_once('/') {
+        Some((owner, name)) => (Some(owner.to_string()), name.to_string()),
+        None => (None, query.clone()),
+    };
+    let name = normalize(&package.name);
+    if name == name_query && owner.as_ref().is_none_or(|o| *o == normalize(&package.owner)) {
+        return 0;
+    }
+    if !name_query.is_empty() && name.contains(&name_query) {
+        return 1;
+    }
+    let words: Vec<&str> = name_query.split(|c: char| c.is_whitespace() || c == '-').filter(|w| !w.is_empty()).collect();
+    let topic_match = package.topics.iter().map(|t| normalize(t)).any(|topic| topic == name_query || words.contains(&topic.as_str()));
+    if topic_match { 2 } else { 3 }
+}
+
+/// Sorts `results` for `sort`; ties keep GitHub's order.
+pub fn rank_results(query: &str, results: &mut [PackageInfo], sort: SearchSort) {
+    match sort {
+        SearchSort::Relevance => results.sort_by_key(|r| (relevance_tier(query, r), std::cmp::Reverse(r.stars))),
+        SearchSort::Stars => results.sort_by_key(|r| std::cmp::Reverse(r.stars)),
+        SearchSort::Updated => results.sort_by(|a, b| b.updated_at.cmp(&a.updated_at)),
+    }
 }
 
 pub struct GithubClient {
@@ -42,13 +166,28 @@ pub struct GithubClient {
     token: Option<String>,
 }
 
+/// Body of `POST /repos/{owner}/{repo}/releases`.
+#[derive(Debug, serde::Serialize)]
+pub struct NewRelease {
+    pub tag_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_commitish: Option<String>,
+    pub name: String,
+    pub body: String,
+    pub draft: bool,
+    pub prerelease: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatedRelease {
+    pub html_url: String,
+    /// Hypermedia template, e.g. `https://uploads.github.com/repos/o/r/releases/1/assets{?name,label}`
+    pub upload_url: String,
+}
+
 impl GithubClient {
     pub fn new(token: Option<String>) -> Result<Self> {
         let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::USER_AGENT, 
-            reqwest::header::HeaderValue::from_static("lunu-cli/1.0")
-        );
         
         if let Some(ref t) = token {
             let mut auth_val = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", t))?;
@@ -56,7 +195,7 @@ impl GithubClient {
             headers.insert(reqwest::header::AUTHORIZATION, auth_val);
         }
 
-        let client = Client::builder()
+        let client = http::client_builder()?
             .default_headers(headers)
             .http2_prior_knowledge() // Optimize for HTTP/2
             .build()?;
@@ -64,56 +203,133 @@ impl GithubClient {
         Ok(Self { client, token })
     }
 
-    pub async fn search_packages(&self, query: &str) -> Result<Vec<PackageInfo>> {
+    /// Authenticates with `GITHUB_TOKEN` (or `GH_TOKEN`) when set.
+    pub fn from_env() -> Result<Self> {
+        let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .find(|value| !value.trim().is_empty());
+        Self::new(token)
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    // Release writes are not retried: a repeated POST could create a duplicate release or asset.
+    pub async fn create_release(&self, owner: &str, repo: &str, release: &NewRelease) -> Result<CreatedRelease> {
+        let url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+        let res = self.client.post(&url).json(release).send().await?;
+        let status = res.status();
+        if !status.is_success() {
+            let body = res.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Creating release {} failed ({}): {}", release.tag_name, status, body.trim()));
+        }
+        Ok(res.json().await?)
+    }
+
+    pub async fn upload_release_asset(&self, release: &CreatedRelease, name: &str, bytes: Vec<u8>) -> Result<()> {
+        let url = release.upload_url.split('{').next().unwrap_or(&release.upload_url);
+        let res = self.client.post(url)
+            .query(&[("name", name)])
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(bytes)
+            .send()
+            .await?;
+        let status = res.status();
+        if !status.is_success() {
+            let body = res.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Uploading {} failed ({}): {}", name, status, body.trim()));
+        }
+        Ok(())
+    }
+
+    /// Searches GitHub repositories, paging through the results, and returns the best
+    /// `options.limit` of them ordered by `options.sort`.
+    pub async fn search_packages(&self, query: &str, options: &SearchOptions) -> Result<Vec<PackageInfo>> {
+        let wanted = match options.sort {
+            SearchSort::Relevance => options.limit.max(RELEVANCE_CANDIDATES),
+            _ => options.limit,
+        };
+
+        // GitHub GraphQL API requires authentication; fall back to REST search without a token.
+        let mut results = if self.token.is_none() {
+            self.search_rest(query, options.sort, wanted).await?
+        } else {
+            self.search_graphql(query, options.sort, wanted).await?
+        };
+        rank_results(query, &mut results, options.sort);
+        results.truncate(options.limit);
+        Ok(results)
+    }
+
+    async fn search_graphql(&self, query: &str, sort: SearchSort, wanted: usize) -> Result<Vec<PackageInfo>> {
         let gql_query = r#"
-        query SearchRepos($q: String!) {
-            search(query: $q, type: REPOSITORY, first: 10) {
+        query SearchRepos($q: String!, $first: Int!, $after: String) {
+            search(query: $q, type: REPOSITORY, first: $first, after: $after) {
+                pageInfo { hasNextPage endCursor }
                 nodes {
                     ... on Repository {
                         name
                         owner { login }
                         url
+                        stargazerCount
+                        description
+                        updatedAt
+                        repositoryTopics(first: 10) { nodes { topic { name } } }
                     }
                 }
             }
         }
         "#;
+        let q = match sort {
+            SearchSort::Relevance => query.to_string(),
+            SearchSort::Stars => format!("{} sort:stars", query),
+            SearchSort::Updated => format!("{} sort:updated", query),
+        };
 
-        let payload = serde_json::json!({
-            "query": gql_query,
-            "variables": {
-                "q": query
-            }
-        });
-
-        // Use public endpoint if no token, but GraphQL often requires token.
-        // Fallback to REST search if GraphQL fails auth or try public access.
-        // GitHub GraphQL API requires authentication.
-        if self.token.is_none() {
-             return self.search_rest(query).await;
-        }
+        let mut packages = Vec::new();
+        let mut after: Option<String> = None;
+        while packages.len() < wanted {
+            let payload = serde_json::json!({
+                "query": gql_query,
+                "variables": {
+                    "q": q,
+                    "first": SEARCH_PAGE_SIZE.min(wanted - packages.len()),
+                    "after": after,
+                }
+            });
+            let body: GithubResponse = http::retry("GitHub search", || async {
+                let res = self.client.post("https://api.github.com/graphql")
+                    .json(&payload)
+                    .send()
+                    .await?;
 
-        let res = self.client.post("https://api.github.com/graphql")
-            .json(&payload)
-            .send()
-            .await?;
+                let status = res.status();
+                let res = res.error_for_status()
+                    .with_context(|| format!("GitHub API Error: {}", status))?;
+                Ok(res.json().await?)
+            }).await?;
 
-        if !res.status().is_success() {
-            return Err(anyhow!("GitHub API Error: {}", res.status()));
+            let page = body.data.search;
+            packages.extend(page.nodes.into_iter().map(|node| PackageInfo {
+                owner: node.owner.login,
+                name: node.name,
+                url: node.url,
+                stars: node.stars,
+                description: node.description,
+                topics: node.topics.map(|t| t.nodes.into_iter().map(|n| n.topic.name).collect()).unwrap_or_default(),
+                updated_at: node.updated_at,
+            }));
+            match page.page_info.end_cursor {
+                Some(cursor) if page.page_info.has_next_page => after = Some(cursor),
+                _ => break,
+            }
         }
-
-        let body: GithubResponse = res.json().await?;
-        
-        let packages = body.data.search.nodes.into_iter().map(|node| PackageInfo {
-            owner: node.owner.login,
-            name: node.name,
-            url: node.url,
-        }).collect();
-
         Ok(packages)
     }
 
-    async fn search_rest(&self, query: &str) -> Result<Vec<PackageInfo>> {
+    async fn search_rest(&self, query: &str, sort: SearchSort, wanted: usize) -> Result<Vec<PackageInfo>> {
         // Fallback for unauthenticated users
         #[derive(Deserialize)]
         struct RestSearch { items: Vec<RestRepo> }
@@ -121,20 +337,135 @@ impl GithubClient {
         struct RestRepo {
             name: String, 
             owner: Owner, 
-            html_url: String
+            html_url: String,
+            #[serde(default)]
+            stargazers_count: u64,
+            description: Option<String>,
+            #[serde(default)]
+            topics: Vec<String>,
+            updated_at: Option<String>,
         }
 
-        let res = self.client.get("https://api.github.com/search/repositories")
-            .query(&[("q", query), ("per_page", "10")])
-            .send()
-            .await?;
+        let sort = match sort {
+            SearchSort::Relevance => None,
+            SearchSort::Stars => Some("stars"),
+            SearchSort::Updated => Some("updated"),
+        };
+        let mut packages = Vec::new();
+        let mut page = 1;
+        while packages.len() < wanted {
+            let per_page = SEARCH_PAGE_SIZE.min(wanted - packages.len());
+            let mut params = vec![("q", query.to_string()), ("per_page", per_page.to_string()), ("page", page.to_string())];
+            if let Some(sort) = sort {
+                params.push(("sort", sort.to_string()));
+                params.push(("order", "desc".to_string()));
+            }
+            let body: RestSearch = http::retry("GitHub search", || async {
+                let res = self.client.get("https://api.github.com/search/repositories")
+                    .query(&params)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(res.json().await?)
+            }).await?;
 
-        let body: RestSearch = res.json().await?;
-        
-        Ok(body.items.into_iter().map(|item| PackageInfo {
-            owner: item.owner.login,
-            name: item.name,
-            url: item.html_url,
-        }).collect())
+            let last_page = body.items.len() < per_page;
+            packages.extend(body.items.into_iter().map(|item| PackageInfo {
+                owner: item.owner.login,
+                name: item.name,
+                url: item.html_url,
+                stars: item.stargazers_count,
+                description: item.description,
+                topics: item.topics,
+                updated_at: item.updated_at,
+            }));
+            if last_page {
+                break;
+            }
+            page += 1;
+        }
+        Ok(packages)
+    }
+
+    pub async fn fetch_metadata(&self, owner: &str, repo: &str) -> Result<RepoMetadata> {
+        let base = format!("https://api.github.com/repos/{}/{}", owner, repo);
+
+        let details: RepoDetails = http::retry("GitHub repository lookup", || async {
+            let res = self.client.get(&base).send().await?.error_for_status()?;
+            Ok(res.json().await?)
+        }).await?;
+
+        let tags: Vec<RepoTag> = http::retry("GitHub tags lookup", || async {
+            let res = self.client.get(format!("{}/tags", base))
+                .query(&[("per_page", "5")])
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(res.json().await?)
+        }).await?;
+
+        // Repositories without a readme answer 404, which is not an error here
+        let readme: Option<String> = http::retry("GitHub readme lookup", || async {
+            let res = self.client.get(format!("{}/readme", base))
+                .header(reqwest::header::ACCEPT, "application/vnd.github.raw")
+                .send()
+                .await?;
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Ok(Some(res.error_for_status()?.text().await?))
+        }).await?;
+
+        let license = details.license.and_then(|l| {
+            l.spdx_id.filter(|id| id != "NOASSERTION").or(l.name)
+        });
+
+        Ok(RepoMetadata {
+            description: details.description.filter(|d| !d.trim().is_empty()),
+            license,
+            latest_tags: tags.into_iter().map(|t| t.name).collect(),
+            readme,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(owner: &str, name: &str, stars: u64, topics: &[&str], updated_at: &str) -> PackageInfo {
+        PackageInfo {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            url: format!("https://github.com/{}/{}", owner, name),
+            stars,
+            description: None,
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+            updated_at: Some(updated_at.to_string()),
+        }
+    }
+
+    #[test]
+    fn ranks_name_matches_then_topics_then_stars() {
+        let results = vec![
+            package("someone", "awesome-luau", 900, &["luau"], "2024-01-01T00:00:00Z"),
+            package("fork", "numpy-luau", 2, &[], "2025-06-01T00:00:00Z"),
+            package("acme", "numpy_luau", 340, &["numpy"], "2024-03-01T00:00:00Z"),
+            package("other", "numpy-luau-extras", 50, &[], "2023-01-01T00:00:00Z"),
+            package("arrays", "ndarray", 120, &["numpy"], "2024-02-01T00:00:00Z"),
+        ];
+        let names = |results: &[PackageInfo]| results.iter().map(|r| format!("{}/{}", r.owner, r.name)).collect::<Vec<_>>();
+
+        let mut ranked = results.clone();
+        rank_results("numpy-luau", &mut ranked, SearchSort::Relevance);
+        assert_eq!(names(&ranked), ["acme/numpy_luau", "fork/numpy-luau", "other/numpy-luau-extras", "someone/awesome-luau", "arrays/ndarray"]);
+
+        rank_results("fork/numpy-luau", &mut ranked, SearchSort::Relevance);
+        assert_eq!(names(&ranked)[0], "fork/numpy-luau");
+
+        rank_results("numpy-luau", &mut ranked, SearchSort::Stars);
+        assert_eq!(names(&ranked)[0], "someone/awesome-luau");
+        rank_results("numpy-luau", &mut ranked, SearchSort::Updated);
+        assert_eq!(names(&ranked)[0], "fork/numpy-luau");
     }
 }
@@ -0,0 +1,158 @@
+//! GitLab project lookups (gitlab.com, or a self-hosted instance such as gitlab.example.com) for
+//! `lunu info` and `lunu status`: the description, license, newest tags and readme
+//! that `github` provides for GitHub. Set `GITLAB_TOKEN` for private projects; it is only sent to
+//! gitlab.com and the instances listed in `[gitlab] token_hosts` of `~/.lunu/config.toml`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use crate::github::RepoMetadata;
+use crate::http;
+use crate::metadata::parse_git_remote;
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    description: Option<String>,
+    license: Option<License>,
+    readme_url: Option<String>,
+    default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct License {
+    key: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GitlabSettings {
+    #[serde(default)]
+    token_hosts: Vec<String>,
+}
+
+/// `[gitlab] token_hosts` of a config file: the self-hosted instances trusted with `GITLAB_TOKEN`.
+pub fn parse_token_hosts(config: &str) -> Result<Vec<String>> {
+    let config: toml::Table = toml::from_str(config)?;
+    let settings: GitlabSettings = match config.get("gitlab") {
+        Some(gitlab) => gitlab.clone().try_into().context("Invalid [gitlab]")?,
+        None => GitlabSettings::default(),
+    };
+    Ok(settings.token_hosts.iter().map(|h| h.trim().trim_end_matches('.').to_lowercase()).collect())
+}
+
+/// `[gitlab] token_hosts` of `~/.lunu/config.toml`.
+pub fn token_hosts() -> Result<Vec<String>> {
+    match http::config_path().filter(|p| p.exists()) {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            parse_token_hosts(&content).with_context(|| format!("Invalid {:?}", path))
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Whether `GITLAB_TOKEN` may be sent to `host`: gitlab.com or a configured instance, never a
+/// host that merely has "gitlab" in its name.
+pub fn sends_token_to(host: &str, token_hosts: &[String]) -> bool {
+    host == "gitlab.com" || token_hosts.iter().any(|h| h == host)
+}
+
+/// `(host, project path)` when `url` is a GitLab project.
+pub fn parse_project(url: &str) -> Option<(String, String)> {
+    parse_git_remote(url).filter(|(host, _)| host.split('.').any(|label| label == "gitlab"))
+}
+
+/// `https://<host>/api/v4/projects/<path>` with the path as one encoded segment.
+fn project_api(host: &str, path: &str) -> Result<reqwest::Url> {
+    let mut url = reqwest::Url::parse(&format!("https://{}/api/v4/projects", host)).with_context(|| format!("Invalid GitLab host {}", host))?;
+    url.path_segments_mut().map_err(|_| anyhow::anyhow!("Invalid GitLab host {}", host))?.push(path);
+    Ok(url)
+}
+
+fn with_segments(mut url: reqwest::Url, segments: &[&str]) -> reqwest::Url {
+    if let Ok(mut path) = url.path_segments_mut() {
+        path.extend(segments);
+    }
+    url
+}
+
+pub async fn fetch_metadata(host: &str, path: &str) -> Result<RepoMetadata> {
+    let client = http::client()?;
+    let token = std::env::var("GITLAB_TOKEN").ok().filter(|t| !t.trim().is_empty()).filter(|_| sends_token_to(host, &token_hosts().unwrap_or_default()));
+    let get = |url: reqwest::Url| {
+        let request = client.get(url);
+        match &token {
+            Some(token) => request.header("PRIVATE-TOKEN", token),
+            None => request,
+        }
+    };
+    let base = project_api(host, path)?;
+
+    let project: Project = http::retry("GitLab project lookup", || async {
+        let res = get(base.clone()).query(&[("license", "true")]).send().await?.error_for_status()?;
+        Ok(res.json().await?)
+    }).await?;
+
+    let tags: Vec<Tag> = http::retry("GitLab tags lookup", || async {
+        let res = get(with_segments(base.clone(), &["repository", "tags"]))
+            .query(&[("order_by", "version"), ("per_page", "5")])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.json().await?)
+    }).await?;
+
+    // readme_url points at the rendered file: https://host/group/repo/-/blob/<branch>/README.md
+    let readme_file = project.readme_url.as_deref().and_then(|u| u.split_once("/-/blob/")).and_then(|(_, rest)| rest.split_once('/')).map(|(_, file)| file.to_string());
+    let readme = match (readme_file, &project.default_branch) {
+        (Some(file), Some(branch)) => http::retry("GitLab readme lookup", || async {
+            let res = get(with_segments(base.clone(), &["repository", "files", &file, "raw"]))
+                .query(&[("ref", branch.as_str())])
+                .send()
+                .await?;
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Ok(Some(res.error_for_status()?.text().await?))
+        }).await?,
+        _ => None,
+    };
+
+    Ok(RepoMetadata {
+        description: project.description.filter(|d| !d.trim().is_empty()),
+        license: project.license.and_then(|l| l.name.or(l.key)),
+        latest_tags: tags.into_iter().map(|t| t.name).collect(),
+        readme,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_gitlab_projects() {
+        assert_eq!(parse_project("https://gitlab.com/group/sub/json.git"), Some(("gitlab.com".to_string(), "group/sub/json".to_string())));
+        assert_eq!(parse_project("git@gitlab.example.com:team/json.git"), Some(("gitlab.example.com".to_string(), "team/json".to_string())));
+        assert_eq!(parse_project("https://codeberg.org/team/json"), None);
+        assert_eq!(
+            project_api("gitlab.com", "group/sub/json").unwrap().as_str(),
+            "https://gitlab.com/api/v4/projects/group%2Fsub%2Fjson"
+        );
+    }
+
+    #[test]
+    fn tokens_go_to_gitlab_com_and_configured_hosts_only() {
+        let hosts = parse_token_hosts("[gitlab]\ntoken_hosts = [\"GitLab.Corp.Example.\"]\n").unwrap();
+        assert_eq!(hosts, ["gitlab.corp.example"]);
+        assert!(sends_token_to("gitlab.com", &hosts) && sends_token_to("gitlab.corp.example", &hosts));
+        assert!(!sends_token_to("gitlab.evil.example", &hosts) && !sends_token_to("evilgitlab.com", &hosts));
+        assert_eq!(parse_token_hosts("[network]\nretries = 2\n").unwrap(), Vec::<String>::new());
+        assert!(parse_token_hosts("[gitlab]\nhosts = []\n").is_err());
+    }
+}
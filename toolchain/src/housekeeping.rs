@@ -0,0 +1,82 @@
+//! Removal of files that updates and interrupted writes leave behind: `*.old`, the binary
+//! `lunu upgrade` or `lunu uninstall` moved aside (it cannot be deleted while it runs), and `*.tmp`,
+//! a write-then-rename that never got renamed. Every command sweeps the install directory and the
+//! runtime cache for ones older than `STALE_AFTER`; `lunu clean` sweeps with a short grace period
+//! and reports what it reclaimed.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Age after which a leftover is removed at startup. A day leaves the `.old` binary of a running
+/// process alone on Windows, where it stays locked until that process exits.
+pub const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Age after which `lunu clean` removes a leftover; younger `.tmp` files may be writes in progress.
+pub const CLEAN_GRACE: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Swept {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+fn is_leftover(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "old" || ext == "tmp")
+}
+
+/// Removes leftovers older than `older_than` from `dirs` (recursively) and the given `files`.
+/// Files that cannot be removed, e.g. because they are in use, are skipped.
+pub fn sweep(dirs: &[PathBuf], files: &[PathBuf], older_than: Duration) -> Swept {
+    let mut candidates: Vec<PathBuf> = files.iter().filter(|f| is_leftover(f)).cloned().collect();
+    let mut pending: Vec<PathBuf> = dirs.to_vec();
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push(entry.path()),
+                Ok(kind) if kind.is_file() && is_leftover(&entry.path()) => candidates.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    let now = SystemTime::now();
+    let mut swept = Swept::default();
+    for path in candidates {
+        let Ok(meta) = std::fs::metadata(&path) else { continue };
+        let age = meta.modified().ok().and_then(|m| now.duration_since(m).ok()).unwrap_or_default();
+        if meta.is_file() && age >= older_than && std::fs::remove_file(&path).is_ok() {
+            swept.files += 1;
+            swept.bytes += meta.len();
+        }
+    }
+    swept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_only_old_leftovers() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        let runtimes = dir.path().join("runtimes").join("lune");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::create_dir_all(&runtimes).unwrap();
+        std::fs::write(bin.join("lunu"), "current").unwrap();
+        std::fs::write(bin.join("lunu.old"), "previous").unwrap();
+        std::fs::write(runtimes.join(".lune.42.7.0.tmp"), "partial").unwrap();
+        std::fs::write(runtimes.join("lune"), "runtime").unwrap();
+        let elsewhere = dir.path().join("lunu.exe.old");
+        std::fs::write(&elsewhere, "x").unwrap();
+        let dirs = [bin.clone(), dir.path().join("runtimes")];
+
+        assert_eq!(sweep(&dirs, std::slice::from_ref(&elsewhere), Duration::from_secs(3600)), Swept::default());
+        assert_eq!(sweep(&dirs, &[elsewhere.clone(), bin.join("lunu")], Duration::ZERO), Swept { files: 3, bytes: 16 });
+        assert!(bin.join("lunu").exists() && runtimes.join("lune").exists());
+        assert!(!bin.join("lunu.old").exists() && !elsewhere.exists());
+    }
+}
@@ -0,0 +1,391 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use anyhow::{Result, Context};
+use rand::Rng;
+use reqwest::{Client, ClientBuilder, StatusCode};
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use lunu_builder::timings;
+
+const DEFAULT_ATTEMPTS: u32 = 4;
+const DEFAULT_BASE_DELAY_MS: u64 = 500;
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 300_000;
+const MAX_DELAY_MS: u64 = 10_000;
+const ORG_HEADER: &str = "x-lunu-org";
+const INVOCATION_HEADER: &str = "x-lunu-invocation-id";
+
+/// `[network]` of `~/.lunu/config.toml`. A `[network.<command>]` table (e.g. `[network.install]`)
+/// overrides it for one command:
+///
+/// ```toml
+/// [network]
+/// connect_timeout_ms = 10000
+/// request_timeout_ms = 300000  # 0 for no limit
+/// retries = 4                  # attempts per request
+///
+/// [network.upgrade]
+/// request_timeout_ms = 900000
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkSettings {
+    pub connect_timeout_ms: Option<u64>,
+    pub request_timeout_ms: Option<u64>,
+    pub retries: Option<u32>,
+}
+
+impl NetworkSettings {
+    /// `[network]` of a config file with `[network.<command>]` applied on top.
+    pub fn parse(config: &str, command: Option<&str>) -> Result<Self> {
+        let config: toml::Table = toml::from_str(config)?;
+        let Some(network) = config.get("network") else {
+            return Ok(Self::default());
+        };
+        let network = network.as_table().ok_or_else(|| anyhow::anyhow!("[network] must be a table"))?;
+        let mut shared = network.clone();
+        shared.retain(|_, value| !value.is_table());
+        let shared: NetworkSettings = shared.try_into().context("Invalid [network]")?;
+        let Some(overrides) = command.and_then(|c| network.get(c)) else {
+            return Ok(shared);
+        };
+        let command = command.unwrap_or_default();
+        let overrides: NetworkSettings = overrides.clone().try_into().with_context(|| format!("Invalid [network.{}]", command))?;
+        Ok(NetworkSettings {
+            connect_timeout_ms: overrides.connect_timeout_ms.or(shared.connect_timeout_ms),
+            request_timeout_ms: overrides.request_timeout_ms.or(shared.request_timeout_ms),
+            retries: overrides.retries.or(shared.retries),
+        })
+    }
+
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.connect_timeout_ms.unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS))
+    }
+
+    /// `None` when `request_timeout_ms = 0`.
+    fn request_timeout(&self) -> Option<Duration> {
+        Some(self.request_timeout_ms.unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS)).filter(|ms| *ms > 0).map(Duration::from_millis)
+    }
+}
+
+static NETWORK: OnceLock<NetworkSettings> = OnceLock::new();
+
+/// `~/.lunu/config.toml`.
+pub fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lunu").join("config.toml"))
+}
+
+/// Loads the network settings for `command` from `~/.lunu/config.toml` (when it exists) and applies
+/// them to every client and retry of this process.
+pub fn configure(command: Option<&str>) -> Result<()> {
+    let settings = match config_path().filter(|p| p.exists()) {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            NetworkSettings::parse(&content, command).with_context(|| format!("Invalid {:?}", path))?
+        }
+        None => NetworkSettings::default(),
+    };
+    let _ = NETWORK.set(settings);
+    Ok(())
+}
+
+fn network() -> &'static NetworkSettings {
+    NETWORK.get_or_init(NetworkSettings::default)
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: DEFAULT_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(MAX_DELAY_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Reads `LUNU_HTTP_RETRIES` and `LUNU_HTTP_RETRY_DELAY_MS`, falling back to `retries` of
+    /// `[network]` and the defaults.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Some(attempts) = network().retries {
+            policy.attempts = attempts.max(1);
+        }
+        if let Some(attempts) = env_u64("LUNU_HTTP_RETRIES") {
+            policy.attempts = (attempts as u32).max(1);
+        }
+        if let Some(delay) = env_u64("LUNU_HTTP_RETRY_DELAY_MS") {
+            policy.base_delay = Duration::from_millis(delay);
+        }
+        policy
+    }
+
+    /// Exponential backoff for the given (1-based) attempt, with up to 50% random jitter.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let half = delay / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter_ms)
+    }
+}
+
+fn env_u64(key: &str) -> Option<u64> {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+pub async fn retry<T, F, Fut>(what: &str, op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    retry_with(&RetryPolicy::from_env(), what, op).await
+}
+
+pub async fn retry_with<T, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let _timing = timings::span("network");
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < policy.attempts && is_retryable(&err) => {
+                let delay = policy.delay_for(attempt);
+                tracing::warn!("{} failed (attempt {}/{}): {}. Retrying in {:?}...", what, attempt, policy.attempts, err, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(err) if is_timeout(&err) => {
+                return Err(err.context(format!(
+                    "{} timed out after {} attempt(s); raise connect_timeout_ms or request_timeout_ms under [network] in ~/.lunu/config.toml",
+                    what, attempt
+                )));
+            }
+            Err(err) => {
+                return Err(err.context(format!("{} failed after {} attempt(s)", what, attempt)));
+            }
+        }
+    }
+}
+
+/// Identifies this run in `X-Lunu-Invocation-Id`: `LUNU_INVOCATION_ID` when a parent process set
+/// one, else random and kept for the rest of the process.
+pub fn invocation_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| {
+        std::env::var("LUNU_INVOCATION_ID")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| format!("{:016x}", rand::thread_rng().gen::<u64>()))
+    })
+}
+
+fn org_tag() -> Option<String> {
+    std::env::var("LUNU_ORG").ok().map(|o| o.trim().to_string()).filter(|o| !o.is_empty())
+}
+
+/// `lunu-cli/<version> (<os>; <arch>)`, followed by `org/<tag>` when `LUNU_ORG` is set.
+/// `LUNU_USER_AGENT` replaces the whole string.
+pub fn user_agent() -> String {
+    if let Some(agent) = std::env::var("LUNU_USER_AGENT").ok().filter(|a| !a.trim().is_empty()) {
+        return agent;
+    }
+    build_user_agent(org_tag().as_deref())
+}
+
+fn build_user_agent(org: Option<&str>) -> String {
+    let agent = format!("lunu-cli/{} ({}; {})", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH);
+    match org {
+        Some(org) => format!("{} org/{}", agent, org),
+        None => agent,
+    }
+}
+
+/// Headers every Lunu request carries: the User-Agent, the invocation ID and `X-Lunu-Org`.
+pub fn identity_headers() -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_str(&user_agent()).context("Invalid LUNU_USER_AGENT")?);
+    headers.insert(INVOCATION_HEADER, HeaderValue::from_str(invocation_id()).context("Invalid LUNU_INVOCATION_ID")?);
+    if let Some(org) = org_tag() {
+        headers.insert(ORG_HEADER, HeaderValue::from_str(&org).context("Invalid LUNU_ORG")?);
+    }
+    Ok(headers)
+}
+
+/// Client builder with `identity_headers` and the `[network]` timeouts; every HTTP client of the
+/// CLI starts here.
+pub fn client_builder() -> Result<ClientBuilder> {
+    let settings = network();
+    let builder = Client::builder().default_headers(identity_headers()?).connect_timeout(settings.connect_timeout());
+    Ok(match settings.request_timeout() {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    })
+}
+
+pub fn client() -> Result<Client> {
+    Ok(client_builder()?.build()?)
+}
+
+pub async fn get_bytes(client: &Client, url: &str) -> Result<Vec<u8>> {
+    retry(&format!("GET {}", url), || async {
+        let resp = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(resp.bytes().await?.to_vec())
+    })
+    .await
+}
+
+/// Like [`get_bytes`], reporting `(received, total)` as chunks arrive. `total` comes from
+/// Content-Length and is `None` when the server does not send it.
+pub async fn get_bytes_with_progress(
+    client: &Client,
+    url: &str,
+    on_progress: &(dyn Fn(u64, Option<u64>) + Sync),
+) -> Result<Vec<u8>> {
+    retry(&format!("GET {}", url), || async {
+        let mut resp = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        let total = resp.content_length();
+        let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+        while let Some(chunk) = resp.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            on_progress(bytes.len() as u64, total);
+        }
+        Ok(bytes)
+    })
+    .await
+}
+
+pub async fn get_json<T: DeserializeOwned>(client: &Client, url: &str) -> Result<T> {
+    let bytes = get_bytes(client, url).await?;
+    serde_json::from_slice(&bytes).with_context(|| format!("Invalid JSON response from {}", url))
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_timeout(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout()))
+}
+
+fn is_retryable(err: &anyhow::Error) -> bool {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(status) = e.status() {
+                return is_retryable_status(status);
+            }
+            return e.is_connect() || e.is_timeout() || e.is_request() || e.is_body();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::UnexpectedEof
+                    | std::io::ErrorKind::BrokenPipe
+            );
+        }
+        if let Some(e) = cause.downcast_ref::<git2::Error>() {
+            return matches!(e.class(), git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssl);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn user_agent_names_version_and_org() {
+        let agent = build_user_agent(Some("acme-ci"));
+        assert!(agent.starts_with(&format!("lunu-cli/{} (", env!("CARGO_PKG_VERSION"))));
+        assert!(agent.ends_with(") org/acme-ci"));
+        assert!(!build_user_agent(None).contains("org/"));
+        assert_eq!(invocation_id(), invocation_id());
+        assert!(identity_headers().unwrap().contains_key(INVOCATION_HEADER));
+    }
+
+    #[test]
+    fn command_tables_override_network_settings() {
+        let config = "[network]\nconnect_timeout_ms = 2000\nretries = 2\n\n[network.install]\nretries = 6\nrequest_timeout_ms = 0\n";
+        let install = NetworkSettings::parse(config, Some("install")).unwrap();
+        assert_eq!(install, NetworkSettings { connect_timeout_ms: Some(2000), request_timeout_ms: Some(0), retries: Some(6) });
+        assert_eq!(install.request_timeout(), None);
+        let add = NetworkSettings::parse(config, Some("add")).unwrap();
+        assert_eq!(add.retries, Some(2));
+        assert_eq!(add.request_timeout(), Some(Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS)));
+        assert_eq!(NetworkSettings::parse("", None).unwrap(), NetworkSettings::default());
+        assert!(NetworkSettings::parse("[network]\ntimeout = 5\n", None).is_err());
+    }
+
+    #[test]
+    fn delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        let first = policy.delay_for(1);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+        let capped = policy.delay_for(10);
+        assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn retry_reports_attempt_count() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with(&fast_policy(3), "download", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into())
+        })
+        .await;
+        let err = result.unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(err.to_string().contains("after 3 attempt(s)"));
+    }
+
+    #[tokio::test]
+    async fn retry_stops_on_permanent_error() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with(&fast_policy(3), "parse", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow::anyhow!("bad input"))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
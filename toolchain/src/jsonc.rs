@@ -0,0 +1,325 @@
+//! Targeted edits of JSON-with-comments files such as `.luaurc`: members of one object are set or
+//! removed in the original text, so comments, key order and formatting elsewhere survive.
+//!
+//! Every function works on offsets into the original text. `strip` blanks comments and trailing
+//! commas with spaces (same length, newlines kept), so serde can parse the result and the
+//! offsets found in it hold for the original.
+
+/// `text` with comments and trailing commas replaced by spaces.
+pub fn strip(text: &str) -> String {
+    let mut out = strip_comments(text).into_bytes();
+    let mut last_comma = None;
+    let mut i = 0;
+    while i < out.len() {
+        match out[i] {
+            b'"' => {
+                last_comma = None;
+                i = string_end(&out, i);
+                continue;
+            }
+            b',' => last_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = last_comma.take() {
+                    out[comma] = b' ';
+                }
+            }
+            byte if !byte.is_ascii_whitespace() => last_comma = None,
+            _ => {}
+        }
+        i += 1;
+    }
+    // Only ASCII bytes outside strings were replaced, so this stays valid UTF-8
+    String::from_utf8(out).unwrap_or_else(|_| text.to_string())
+}
+
+/// `text` with comments replaced by spaces; commas are kept so edits can see trailing ones.
+fn strip_comments(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = string_end(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    out[i] = b' ';
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = text[i + 2..].find("*/").map(|e| i + 2 + e + 2).unwrap_or(bytes.len());
+                for byte in &mut out[i..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    String::from_utf8(out).unwrap_or_else(|_| text.to_string())
+}
+
+/// Offset just past the string starting at `start` (a `"`).
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_ws(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Offset just past the value starting at `start`.
+fn value_end(bytes: &[u8], start: usize) -> usize {
+    match bytes.get(start) {
+        Some(b'"') => string_end(bytes, start),
+        Some(b'{') | Some(b'[') => {
+            let mut depth = 0;
+            let mut i = start;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'"' => {
+                        i = string_end(bytes, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return i + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            bytes.len()
+        }
+        _ => {
+            let mut i = start;
+            while i < bytes.len() && !matches!(bytes[i], b',' | b'}' | b']') && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            i
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Member {
+    key: String,
+    key_start: usize,
+    value_start: usize,
+    value_end: usize,
+    /// The comma after the value, if any
+    comma: Option<usize>,
+}
+
+/// An object's `{` and `}` offsets and its members.
+#[derive(Debug)]
+struct Object {
+    open: usize,
+    close: usize,
+    members: Vec<Member>,
+}
+
+fn object_at(stripped: &str, open: usize) -> Option<Object> {
+    let bytes = stripped.as_bytes();
+    if bytes.get(open) != Some(&b'{') {
+        return None;
+    }
+    let mut members = Vec::new();
+    let mut i = skip_ws(bytes, open + 1);
+    while bytes.get(i) == Some(&b'"') {
+        let key_end = string_end(bytes, i);
+        let key: String = serde_json::from_str(&stripped[i..key_end]).ok()?;
+        let colon = skip_ws(bytes, key_end);
+        if bytes.get(colon) != Some(&b':') {
+            return None;
+        }
+        let value_start = skip_ws(bytes, colon + 1);
+        let end = value_end(bytes, value_start);
+        let after = skip_ws(bytes, end);
+        let comma = (bytes.get(after) == Some(&b',')).then_some(after);
+        members.push(Member { key, key_start: i, value_start, value_end: end, comma });
+        i = skip_ws(bytes, comma.map_or(after, |c| c + 1));
+    }
+    (bytes.get(i) == Some(&b'}')).then_some(Object { open, close: i, members })
+}
+
+/// The object at `path` (keys from the root object); `None` when a key is missing or not an object.
+fn find_object(text: &str, path: &[&str]) -> Option<Object> {
+    let stripped = strip_comments(text);
+    let mut object = object_at(&stripped, skip_ws(stripped.as_bytes(), 0))?;
+    for key in path {
+        let member = object.members.iter().find(|m| m.key == *key)?;
+        object = object_at(&stripped, member.value_start)?;
+    }
+    Some(object)
+}
+
+/// Whether `path` names an object of `text`.
+pub fn has_object(text: &str, path: &[&str]) -> bool {
+    find_object(text, path).is_some()
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |n| n + 1)
+}
+
+/// Leading whitespace of the line holding `offset`.
+fn indent_at(text: &str, offset: usize) -> &str {
+    let start = line_start(text, offset);
+    let line = &text[start..];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// One level of indentation as the file uses it (two spaces when it has no indented line).
+fn indent_unit(text: &str) -> String {
+    text.lines()
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+/// `value` pretty-printed with the indentation `text` uses, for `set_member`.
+pub fn render(value: &serde_json::Value, text: &str) -> String {
+    use serde::Serialize;
+    let unit = indent_unit(text);
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes()));
+    match value.serialize(&mut serializer) {
+        Ok(()) => String::from_utf8(out).unwrap_or_default(),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// Sets `key` of the object at `path` to `value` (JSON text; lines after the first are indented
+/// like the member): replaces the value in place, or appends the member after the last one.
+/// `None` when the object does not exist.
+pub fn set_member(text: &str, path: &[&str], key: &str, value: &str) -> Option<String> {
+    let object = find_object(text, path)?;
+    if let Some(member) = object.members.iter().find(|m| m.key == key) {
+        return Some(format!("{}{}{}", &text[..member.value_start], value, &text[member.value_end..]));
+    }
+    let key = serde_json::to_string(key).ok()?;
+    let multiline = text[object.open..object.close].contains('\n');
+    let Some(last) = object.members.last() else {
+        if !multiline {
+            return Some(format!("{}{{ {}: {} }}{}", &text[..object.open], key, value, &text[object.close + 1..]));
+        }
+        let outer = indent_at(text, object.open);
+        return Some(format!(
+            "{}{{\n{}{}{}: {}\n{}}}{}",
+            &text[..object.open],
+            outer,
+            indent_unit(text),
+            key,
+            value,
+            outer,
+            &text[object.close + 1..]
+        ));
+    };
+    if !multiline {
+        return Some(match last.comma {
+            Some(comma) => format!("{} {}: {},{}", &text[..comma + 1], key, value, &text[comma + 1..]),
+            None => format!("{}, {}: {}{}", &text[..last.value_end], key, value, &text[last.value_end..]),
+        });
+    }
+    // On a new line after the last member and any comment that ends its line; a trailing comma
+    // on the last member is kept on the new one
+    let stripped = strip_comments(text);
+    let after_last = last.comma.map_or(last.value_end, |c| c + 1);
+    let insert_at = stripped[after_last..]
+        .find('\n')
+        .map(|n| after_last + n)
+        .filter(|eol| stripped[after_last..*eol].trim().is_empty())
+        .unwrap_or(after_last);
+    let indent = indent_at(text, last.key_start);
+    let value = value.replace('\n', &format!("\n{}", indent));
+    let member = format!("\n{}{}: {}{}", indent, key, value, if last.comma.is_some() { "," } else { "" });
+    let head = match last.comma {
+        Some(_) => text[..insert_at].to_string(),
+        None => format!("{},{}", &text[..last.value_end], &text[last.value_end..insert_at]),
+    };
+    Some(format!("{}{}{}", head, member, &text[insert_at..]))
+}
+
+/// Removes `key` from the object at `path`, with its line when it has one to itself. Unchanged
+/// when the key is not there.
+pub fn remove_member(text: &str, path: &[&str], key: &str) -> Option<String> {
+    let object = find_object(text, path)?;
+    let index = object.members.iter().position(|m| m.key == key);
+    let Some(index) = index else {
+        return Some(text.to_string());
+    };
+    let member = &object.members[index];
+    let stripped = strip_comments(text);
+    let mut start = member.key_start;
+    let mut end = member.comma.map_or(member.value_end, |c| c + 1);
+    let line = line_start(text, start);
+    let line_end = stripped[end..].find('\n').map_or(stripped.len(), |n| end + n);
+    if text[line..start].trim().is_empty() && stripped[end..line_end].trim().is_empty() {
+        start = line;
+        end = (line_end + 1).min(text.len());
+    }
+    // Dropping the last member must not leave the previous one with a new trailing comma
+    let is_last = index + 1 == object.members.len();
+    let previous_comma = index.checked_sub(1).and_then(|i| object.members[i].comma);
+    match (is_last, member.comma, previous_comma) {
+        (true, None, Some(comma)) => Some(format!("{}{}{}", &text[..comma], &text[comma + 1..start], &text[end..])),
+        _ => Some(format!("{}{}", &text[..start], &text[end..])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LUAURC: &str = r#"{
+    // Strict everywhere
+    "languageMode": "strict",
+    "aliases": {
+        "lunu": "modules/lunu/", // toolchain
+        "json": "modules/json/"
+    },
+    "lint": { "*": true, },
+}
+"#;
+
+    #[test]
+    fn edits_members_without_touching_the_rest() {
+        let parsed: serde_json::Value = serde_json::from_str(&strip(LUAURC)).unwrap();
+        assert_eq!(parsed["aliases"]["lunu"], "modules/lunu/");
+
+        let added = set_member(LUAURC, &["aliases"], "signal", "\"modules/signal/\"").unwrap();
+        assert!(added.contains("        \"json\": \"modules/json/\",\n        \"signal\": \"modules/signal/\"\n    },"));
+        assert!(added.contains("// Strict everywhere") && added.contains("\"lint\": { \"*\": true, },"));
+
+        let changed = set_member(LUAURC, &["aliases"], "json", "\"vendor/json/\"").unwrap();
+        assert!(changed.contains("\"json\": \"vendor/json/\"\n"));
+
+        let removed = remove_member(LUAURC, &["aliases"], "json").unwrap();
+        assert!(removed.contains("\"lunu\": \"modules/lunu/\" // toolchain\n    },"));
+        let removed = remove_member(LUAURC, &["aliases"], "lunu").unwrap();
+        assert!(removed.contains("\"aliases\": {\n        \"json\": \"modules/json/\"\n    },"));
+        assert!(serde_json::from_str::<serde_json::Value>(&strip(&removed)).is_ok());
+
+        let created = set_member("{ \"languageMode\": \"strict\" }", &[], "aliases", "{ \"lunu\": \"modules/lunu/\" }").unwrap();
+        assert_eq!(created, "{ \"languageMode\": \"strict\", \"aliases\": { \"lunu\": \"modules/lunu/\" } }");
+        assert_eq!(set_member("{}", &["aliases"], "x", "1"), None);
+    }
+}
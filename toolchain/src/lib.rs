@@ -1 +1,12 @@
+pub mod allowed_hosts;
+pub mod audit_log;
 pub mod bridge_server;
+pub mod config_schema;
+pub mod console;
+pub mod http;
+pub mod permissions;
+pub mod response_cache;
+pub mod secret_store;
+pub mod worker_pool;
+pub mod worker_requires;
+pub mod worker_scheduler;
@@ -3,35 +3,144 @@ use std::path::Path;
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
+use lunu_builder::timings;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LockEntry {
     pub url: Option<String>,
     pub version: Option<String>,
+    /// Tag that was checked out, from a `version` requirement or an explicit `tag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Commit a `rev` dependency is pinned to, as written in lunu.toml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
     pub path: Option<String>,
+    /// Folder of the repository that was installed (see `DependencySpec::subdir`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
     pub checksum: String,
+    /// SHA-256 of each file by relative path; `checksum` is their root (see
+    /// `package::root_checksum`). Empty for entries locked before per-file hashes were recorded.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
     pub installed_at: u64,
+    /// Internal clone of `url` (see `lunu mirror`); installs try it first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<String>,
+    /// Registry the package came from (e.g. `pesde:scope/name`); `url` and `path` are empty then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Commit a git source was checked out at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolver: Option<Resolver>,
+    /// `user@host` that performed the install
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_by: Option<String>,
+    /// Locally edited path dependency (`mutable = true`); `checksum` is left empty and not checked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mutable: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+/// How a dependency's source was chosen, for `lunu provenance`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolver {
+    /// `lunu add <query>` took the top GitHub search result
+    Search,
+    /// The `url` written in lunu.toml
+    Url,
+    /// A package registry (`pesde`, `wally`)
+    Registry,
+    /// A local `path` in lunu.toml
+    Path,
+}
+
+/// `user@host` of the current process, as recorded in `installed_by`.
+pub fn current_actor() -> Option<String> {
+    let user = ["USER", "USERNAME"].iter().find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))?;
+    let host = ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty());
+    Some(match host {
+        Some(host) => format!("{}@{}", user, host),
+        None => user,
+    })
+}
+
+/// Format version of the lunu.lock this Lunu writes. Version 1 is the original, unversioned
+/// format; `migrate` brings older files up to date when they are loaded.
+pub const CURRENT_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LockFile {
+    /// Format version (see `CURRENT_VERSION`)
+    pub version: u32,
     #[serde(default)]
     pub dependencies: BTreeMap<String, LockEntry>,
 }
 
+impl Default for LockFile {
+    fn default() -> Self {
+        Self { version: CURRENT_VERSION, dependencies: BTreeMap::new() }
+    }
+}
+
 impl LockFile {
     pub async fn load(path: &Path) -> Result<Self> {
+        let _timing = timings::span("resolution");
         if !path.exists() {
             return Ok(Self::default());
         }
         let content = fs::read_to_string(path).await
             .with_context(|| format!("Failed to read lunu.lock at {:?}", path))?;
-        let lock: LockFile = toml::from_str(&content)
-            .with_context(|| "Failed to parse lunu.lock")?;
-        Ok(lock)
+        if conflict_sides(&content).is_some() {
+            return Err(anyhow::anyhow!(
+                "lunu.lock has unresolved merge conflicts. Run 'lunu lock merge' to rebuild it from lunu.toml."
+            ));
+        }
+        Self::parse(&content)
     }
 
+    /// Reads lunu.lock in any format up to `CURRENT_VERSION`, migrating older ones.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut value: toml::Value = toml::from_str(content).with_context(|| "Failed to parse lunu.lock")?;
+        let table = value.as_table_mut().ok_or_else(|| anyhow::anyhow!("Failed to parse lunu.lock: not a table"))?;
+        let version = match table.get("version") {
+            None => 1,
+            Some(version) => version
+                .as_integer()
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| anyhow::anyhow!("Invalid lunu.lock format version: {}", version))?,
+        };
+        if version > CURRENT_VERSION {
+            return Err(anyhow::anyhow!(
+                "lunu.lock uses format version {}, but this Lunu only understands up to version {}. Upgrade Lunu with 'lunu upgrade'.",
+                version,
+                CURRENT_VERSION
+            ));
+        }
+        if version < CURRENT_VERSION {
+            migrate(table, version);
+        }
+        value.try_into().with_context(|| "Failed to parse lunu.lock")
+    }
+
+    /// Writes lunu.lock; only the current format is ever written.
     pub async fn save(&self, path: &Path) -> Result<()> {
+        if self.version != CURRENT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Refusing to write lunu.lock in format version {} (this Lunu writes version {})",
+                self.version,
+                CURRENT_VERSION
+            ));
+        }
         let content = toml::to_string_pretty(self)
             .with_context(|| "Failed to serialize lunu.lock")?;
         fs::write(path, content).await
@@ -48,6 +157,67 @@ impl LockFile {
     }
 }
 
+/// Upgrades a lunu.lock table from format `from` to `CURRENT_VERSION`, one version at a time.
+fn migrate(table: &mut toml::value::Table, from: u32) {
+    if from < 2 {
+        // Version 1 had no `version` key, and early releases left `checksum` and `installed_at`
+        // out of entries they had nothing to record for
+        if let Some(dependencies) = table.get_mut("dependencies").and_then(toml::Value::as_table_mut) {
+            for (_, entry) in dependencies.iter_mut() {
+                let Some(entry) = entry.as_table_mut() else {
+                    continue;
+                };
+                entry.entry("checksum").or_insert_with(|| toml::Value::String(String::new()));
+                entry.entry("installed_at").or_insert(toml::Value::Integer(0));
+            }
+        }
+    }
+    table.insert("version".to_string(), toml::Value::Integer(i64::from(CURRENT_VERSION)));
+}
+
+/// Splits a file with git conflict markers into its two sides ("ours", "theirs"), keeping the
+/// lines outside the conflicts in both. The merge base of `diff3`-style conflicts is dropped.
+/// `None` when there are no markers.
+pub fn conflict_sides(content: &str) -> Option<(String, String)> {
+    enum Section {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+    let marker = |line: &str, prefix: &str| line == prefix || line.starts_with(&format!("{} ", prefix));
+    let mut section = Section::Both;
+    let (mut ours, mut theirs) = (String::new(), String::new());
+    let mut conflicted = false;
+    for line in content.lines() {
+        match section {
+            Section::Both if marker(line, "<<<<<<<") => {
+                section = Section::Ours;
+                conflicted = true;
+            }
+            Section::Ours if marker(line, "|||||||") => section = Section::Base,
+            Section::Ours | Section::Base if marker(line, "=======") => section = Section::Theirs,
+            Section::Theirs if marker(line, ">>>>>>>") => section = Section::Both,
+            Section::Both => {
+                ours.push_str(line);
+                ours.push('\n');
+                theirs.push_str(line);
+                theirs.push('\n');
+            }
+            Section::Ours => {
+                ours.push_str(line);
+                ours.push('\n');
+            }
+            Section::Base => {}
+            Section::Theirs => {
+                theirs.push_str(line);
+                theirs.push('\n');
+            }
+        }
+    }
+    conflicted.then_some((ours, theirs))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,13 +231,97 @@ mod tests {
         lock.set("example", LockEntry {
             url: Some("https://github.com/example/repo".to_string()),
             version: None,
+            tag: None,
+            rev: None,
             path: Some("modules/example".to_string()),
+            subdir: None,
             checksum: "abc123".to_string(),
+            files: BTreeMap::new(),
+            installed_at: 1,
+            mirror: None,
+            registry: None,
+            commit: Some("0123abcd".to_string()),
+            resolver: Some(Resolver::Search),
+            installed_by: Some("dev@build-01".to_string()),
+            mutable: false,
+        });
+        lock.set("local", LockEntry {
+            url: None,
+            version: None,
+            tag: None,
+            rev: None,
+            path: Some("modules/local".to_string()),
+            subdir: None,
+            checksum: String::new(),
+            files: BTreeMap::new(),
             installed_at: 1,
+            mirror: None,
+            registry: None,
+            commit: None,
+            resolver: None,
+            installed_by: None,
+            mutable: true,
         });
         lock.save(&path).await.unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("mutable = false"));
 
         let loaded = LockFile::load(&path).await.unwrap();
         assert!(loaded.dependencies.contains_key("example"));
+        assert!(loaded.dependencies["local"].mutable);
+        assert!(!loaded.dependencies["example"].mutable);
+        assert_eq!(loaded.dependencies["example"].resolver, Some(Resolver::Search));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("resolver = \"search\""));
+    }
+
+    #[tokio::test]
+    async fn migrates_unversioned_lockfiles_and_refuses_newer_ones() {
+        let v1 = "[dependencies.json]\nurl = \"https://github.com/example/json\"\npath = \"modules/json\"\n";
+        let lock = LockFile::parse(v1).unwrap();
+        assert_eq!(lock.version, CURRENT_VERSION);
+        assert_eq!(lock.dependencies["json"].checksum, "");
+        assert_eq!(lock.dependencies["json"].installed_at, 0);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lunu.lock");
+        lock.save(&path).await.unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with(&format!("version = {}\n", CURRENT_VERSION)));
+
+        let err = LockFile::parse("version = 99\n").unwrap_err().to_string();
+        assert!(err.contains("format version 99"));
+        assert!(LockFile::parse("version = 0\n").is_err());
+        let old = LockFile { version: 1, dependencies: BTreeMap::new() };
+        assert!(old.save(&path).await.unwrap_err().to_string().contains("Refusing to write"));
+    }
+
+    #[tokio::test]
+    async fn conflicted_lockfile_splits_into_both_sides() {
+        let content = "\
+[dependencies.json]
+url = \"https://github.com/example/json\"
+<<<<<<< HEAD
+version = \"1.2.0\"
+checksum = \"aaa\"
+||||||| base
+version = \"1.1.0\"
+checksum = \"000\"
+=======
+version = \"1.3.0\"
+checksum = \"bbb\"
+>>>>>>> feature
+installed_at = 1
+";
+        let (ours, theirs) = conflict_sides(content).unwrap();
+        let ours = LockFile::parse(&ours).unwrap();
+        let theirs = LockFile::parse(&theirs).unwrap();
+        assert_eq!(ours.dependencies["json"].version.as_deref(), Some("1.2.0"));
+        assert_eq!(theirs.dependencies["json"].checksum, "bbb");
+        assert_eq!(theirs.dependencies["json"].installed_at, 1);
+        assert!(conflict_sides("[dependencies]\n").is_none());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lunu.lock");
+        std::fs::write(&path, content).unwrap();
+        let err = LockFile::load(&path).await.unwrap_err();
+        assert!(err.to_string().contains("lunu lock merge"));
     }
 }
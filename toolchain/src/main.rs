@@ -1,33 +1,72 @@
 mod config;
+mod jsonc;
 mod github;
+mod gitlab;
 mod package;
+mod package_cache;
 mod compat;
 mod project;
 mod lock;
+mod file_lock;
+mod metadata;
+mod monitor;
+mod daemon;
+mod typedefs;
+mod checksums;
+mod bundle;
+mod snapshot;
+mod typecheck;
+mod capabilities;
+mod sandbox;
+mod findings;
+mod stats;
+mod requires;
+mod denylist;
+mod advisories;
+mod template;
+mod pesde;
+mod wally;
+mod archive;
+mod publish;
+mod script_bundle;
+mod smoke;
+mod offline_installer;
+mod vendor;
+mod dep_tree;
+mod test_logs;
+mod housekeeping;
+#[cfg(not(windows))]
+mod shell_path;
 
-use clap::{Parser, Subcommand, ValueEnum};
-use lunu_cli::bridge_server;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use lunu_cli::{bridge_server, console, http, permissions, secret_store, worker_pool};
+use lunu_cli::worker_requires::{self, worker_language};
+use lunu_cli::config_schema::{self, ConfigFile};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf, Component};
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::process::Command;
-use std::fs::{self, File};
-use reqwest::header::USER_AGENT;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::process::{Command, Stdio};
+use std::fs;
 use anyhow::{Result, Context};
-use config::Luaurc;
-use github::GithubClient;
-use package::PackageManager;
+use config::{AliasClaims, Luaurc};
+use github::{GithubClient, PackageInfo, SearchOptions, SearchSort};
+use package::{FetchMethod, GitRef, InstalledPackage, PackageManager};
 use compat::CompatibilityLayer;
-use project::{ProjectConfig, DependencySpec, RuntimeConfig, BuildConfig};
-use lock::{LockFile, LockEntry};
-use fs_extra::dir::{copy as copy_dir, CopyOptions};
+use project::{ProjectConfig, DependencySpec, RuntimeConfig, BuildConfig, BuildExclude, TestGate};
+use lock::{LockFile, LockEntry, Resolver};
+use file_lock::{FileLock, replace_file, unique_temp_path, write_atomic};
+use metadata::{MetadataCache, PackageMetadata, parse_git_remote, parse_github_repo, readme_summary};
+use findings::{Finding, Severity};
+use lunu_builder::exclude::ExcludeSet;
+use lunu_builder::progress;
+use lunu_builder::timings;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::fs as async_fs;
 use flate2::read::GzDecoder;
 use tar::Archive;
 
-use std::io::{self, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 #[cfg(windows)]
 use winreg::enums::*;
 #[cfg(windows)]
@@ -49,25 +88,228 @@ use std::os::unix::fs::PermissionsExt;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Progress output: json also writes one event per line to stderr (phase, percent, message)
+    #[arg(long, global = true, value_enum, default_value_t = ProgressFormat::Text)]
+    progress: ProgressFormat,
+    /// Print a per-phase time breakdown and cache hit/miss counts when the command finishes
+    #[arg(long, global = true)]
+    timings: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum ProgressFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum PackageAction {
+    /// Check a downloaded artifact set against its SHA256SUMS (and signature)
+    Verify {
+        /// Directory containing SHA256SUMS
+        #[arg(default_value = "dist")]
+        dir: PathBuf,
+        /// Public key (hex, minisign base64 or a minisign.pub path); defaults to [build.signing] public_key
+        #[arg(long)]
+        public_key: Option<String>,
+        /// Only check the files that are present
+        #[arg(long)]
+        ignore_missing: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the JSON schema of a config file (for editors and CI)
+    Schema {
+        #[arg(value_enum, default_value_t = ConfigTarget::Lunu)]
+        file: ConfigTarget,
+    },
+    /// Check lunu.toml and config/settings.json, listing every problem with its line
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum AnalyzeAction {
+    /// Resolve every require through .luaurc and report broken, unused and undeclared modules
+    Requires {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeyAction {
+    /// Show where the bridge API key is stored and a masked form of it
+    Show {
+        /// Print the full key (only the key, so it can be piped)
+        #[arg(long)]
+        reveal: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RuntimeAction {
+    /// Probe the runtime binary: version, subcommands and standard libraries (cached)
+    Capabilities {
+        /// Defaults to the project's runtime
+        #[arg(value_enum)]
+        runtime: Option<RuntimeTarget>,
+        #[arg(long)]
+        json: bool,
+        /// Probe again even if the binary has not changed
+        #[arg(long)]
+        refresh: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LockAction {
+    /// Rebuild a lunu.lock with merge conflicts: keep each dependency's locked version from either
+    /// side that still satisfies lunu.toml, then reinstall and recompute checksums
+    Merge {
+        /// Reinstall from the package cache only
+        #[arg(long)]
+        offline: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Record lunu.toml, lunu.lock, .luaurc, the runtime version and the installed modules'
+    /// checksums under .lunu/snapshots/<name>
+    Create {
+        name: String,
+        /// Replace an existing snapshot of the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Put the project back to a snapshot: restore its files, reinstall every locked dependency
+    /// at its locked commit and pin its runtime version
+    Restore {
+        name: String,
+        /// Reinstall from the package cache only
+        #[arg(long)]
+        offline: bool,
+    },
+    /// List the project's snapshots
+    List,
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// Turn this project into a template for `lunu scaffold --from`
+    Export {
+        /// Output directory
+        #[arg(long, default_value = "dist/template")]
+        out: PathBuf,
+        /// Template name (defaults to the project name)
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        /// Author to replace with a parameter (defaults to git's user.name)
+        #[arg(long)]
+        author: Option<String>,
+        /// Commit the template and push it to this Git repository (branch main)
+        #[arg(long)]
+        push: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum SearchOrder {
+    /// Name matches first, then topic matches, each by stars
+    Relevance,
+    /// Most stars first
+    Stars,
+    /// Most recently updated first
+    Updated,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ConfigTarget {
+    /// lunu.toml
+    Lunu,
+    /// config/settings.json
+    Settings,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Add a library from GitHub
+    /// Add a library from GitHub, a registry or any git host
     Add {
-        /// Search query (e.g., "numpy-luau" or "user/repo")
-        query: String,
+        /// Search query (e.g., "numpy-luau" or "user/repo"); append `@<tag|branch|version>` to pin it
+        #[arg(required_unless_present = "git")]
+        query: Option<String>,
+
+        /// Clone this repository (HTTPS or SSH URL, any host) instead of searching GitHub
+        #[arg(long, value_name = "URL", conflicts_with = "query")]
+        git: Option<String>,
         
         /// Alias name for local usage (optional, defaults to repo name)
         #[arg(short, long)]
         alias: Option<String>,
+
+        /// Add it under [dev-dependencies]: installed for tests, left out of package and build
+        #[arg(long)]
+        dev: bool,
+
+        /// Install the top search result without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Show the bridge permissions the module requests and ask before keeping it
+        #[arg(long)]
+        review: bool,
+
+        /// Install only this folder of the repository (e.g. packages/signal of a monorepo)
+        #[arg(long, value_name = "SUBDIR")]
+        path: Option<String>,
+    },
+    /// Search GitHub for packages and pick one to install
+    Search {
+        query: String,
+        /// Order of the results
+        #[arg(long, value_enum, default_value_t = SearchOrder::Relevance)]
+        sort: SearchOrder,
+        /// Number of results to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Print the results as JSON instead of asking
+        #[arg(long)]
+        json: bool,
     },
     /// Start the Lunu Bridge Server in development mode (foreground)
-    Dev,
+    Dev {
+        /// Stop persistent (warm) workers left running by previous dev sessions and exit
+        #[arg(long)]
+        stop_workers: bool,
+        /// Start the bridge in the background and exit once it is ready (or failed)
+        #[arg(long, conflicts_with = "stop_workers")]
+        wait_ready: bool,
+        /// How long --wait-ready waits, e.g. 30s, 500ms or 2m
+        #[arg(long, default_value = "30s", value_parser = parse_duration, requires = "wait_ready")]
+        timeout: Duration,
+    },
+    /// Run a local JSON-RPC/WebSocket service for editor integrations
+    Daemon {
+        /// Address to listen on
+        #[arg(long, default_value = daemon::DEFAULT_LISTEN)]
+        listen: String,
+    },
+    /// Internal: hosts a persistent worker for the dev bridge
+    #[command(hide = true)]
+    WorkerHost {
+        module_dir: PathBuf,
+        #[arg(long)]
+        registry: PathBuf,
+    },
     /// Build a Luau script into an executable
     Build {
         /// The entry point script (e.g., main.luau)
-        script: PathBuf,
+        #[arg(required_unless_present_any = ["list", "diff", "clear_cache"])]
+        script: Option<PathBuf>,
 
         /// Output filename (optional)
         #[arg(short, long)]
@@ -77,6 +319,10 @@ enum Commands {
         #[arg(short, long)]
         force: bool,
 
+        /// Delete this project's cached runtime payload (then build, if a script is given)
+        #[arg(long)]
+        clear_cache: bool,
+
         /// Open the output after successful build
         #[arg(long)]
         open: bool,
@@ -87,36 +333,322 @@ enum Commands {
 
         #[arg(long, num_args = 0..=1, default_missing_value = "true")]
         open_cmd: Option<bool>,
+
+        /// Skip the [build.gates] checks
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Print the full payload size tree after building
+        #[arg(long)]
+        analyze: bool,
+
+        /// Use this runtime binary instead of the project/cached one for this invocation
+        #[arg(long, value_name = "PATH")]
+        runtime_path: Option<PathBuf>,
+
+        /// Bundle every file, ignoring the default and [build.exclude] patterns
+        #[arg(long)]
+        include_all: bool,
+
+        /// List the builds recorded under dist/history
+        #[arg(long, conflicts_with = "diff")]
+        list: bool,
+
+        /// Compare the payloads of two recorded builds (id or --list position, 1 = newest)
+        #[arg(long, num_args = 2, value_names = ["A", "B"])]
+        diff: Option<Vec<String>>,
+    },
+    /// Generate an ed25519 signing key for [build.updates]
+    UpdateKey {
+        /// Where to write the private key
+        #[arg(default_value = ".lunu/update.key")]
+        path: PathBuf,
     },
     /// Initialize a Lunu project in the current directory
-    Init,
+    Init {
+        /// Runtime for the project (skips the interactive prompt)
+        #[arg(long, value_enum)]
+        runtime: Option<RuntimeTarget>,
+        /// Project name written to lunu.toml (defaults to the folder name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Template used for src/main.luau
+        #[arg(short, long, value_enum)]
+        template: Option<TemplateKind>,
+        /// Accept defaults without prompting
+        #[arg(short, long)]
+        yes: bool,
+        /// Skip downloading runtime type definitions
+        #[arg(long)]
+        no_types: bool,
+    },
     /// Install dependencies from lunu.toml
-    Install,
+    Install {
+        /// Skip downloading runtime type definitions
+        #[arg(long)]
+        no_types: bool,
+        /// Install only from the package cache (~/.lunu/cache/packages), without network access
+        #[arg(long)]
+        offline: bool,
+        /// Install exactly what lunu.lock records and never rewrite it; fail if lunu.toml and
+        /// lunu.lock disagree or an installed checksum differs (for CI)
+        #[arg(long)]
+        frozen: bool,
+    },
     /// Remove a dependency
     Remove {
         /// Library name to remove
-        lib: String,
+        #[arg(required_unless_present = "unused")]
+        lib: Option<String>,
+
+        /// Remove every dependency that nothing in the project requires
+        #[arg(long, conflicts_with = "lib")]
+        unused: bool,
+
+        /// With --unused, list what would be removed without changing anything
+        #[arg(long, requires = "unused")]
+        dry_run: bool,
+    },
+    /// Delete installed modules that lunu.toml no longer refers to (`lunu install` and `lunu remove` do this too)
+    Prune {
+        /// List what would be deleted without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
+    /// Copy resolved dependencies (and Lute's runtime libraries) into vendor/ and use them from there
+    Vendor,
     /// Update dependencies
     Update {
         /// Library name to update (optional)
         lib: Option<String>,
+        /// Pick the dependencies to update from a list of current and available versions
+        #[arg(short, long, conflicts_with = "lib")]
+        interactive: bool,
+        /// Leave a dependency as locked (repeatable)
+        #[arg(long, value_name = "NAME")]
+        exclude: Vec<String>,
+        /// Update only the dependencies whose name matches a glob, e.g. `net-*`
+        #[arg(long, value_name = "GLOB")]
+        only: Option<String>,
+        /// Show what each dependency would change to without touching modules/, .luaurc or lunu.lock
+        #[arg(long, conflicts_with = "interactive")]
+        dry_run: bool,
     },
     /// List installed dependencies
-    List,
+    List {
+        /// Show cached description, license and latest tags
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Show the dependency graph with versions and sources
+    Tree {
+        /// Only list packages installed more than once
+        #[arg(long)]
+        duplicates: bool,
+    },
+    /// Show details about an installed dependency (works offline from the metadata cache)
+    Info {
+        /// Library name
+        lib: String,
+        /// Fetch fresh metadata from GitHub
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Show how an installed dependency got into the tree: source, commit, resolver and installer
+    Provenance {
+        /// Library name
+        dep: String,
+        /// Print the lock entry as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Package the project for distribution
-    Package,
+    Package {
+        #[command(subcommand)]
+        action: Option<PackageAction>,
+        /// Sign SHA256SUMS with this private key (defaults to [build.signing] key)
+        #[arg(long)]
+        sign_key: Option<PathBuf>,
+        /// Copy every file, ignoring the default and [build.exclude] patterns
+        #[arg(long)]
+        include_all: bool,
+        /// Assemble a runtime bundle for another platform (e.g. windows-x64, linux-arm64, macos-arm64); repeatable
+        #[arg(long = "platform", value_name = "PLATFORM")]
+        platforms: Vec<String>,
+        /// Run the packaged artifact from a temporary directory and fail unless its output contains this text
+        #[arg(long, value_name = "EXPECTED")]
+        smoke: Option<String>,
+        /// Run this script against the unpacked artifact instead (it gets the directory as its argument)
+        #[arg(long, value_name = "PATH")]
+        smoke_script: Option<PathBuf>,
+    },
+    /// Build, package and publish a GitHub release (needs GITHUB_TOKEN)
+    Release {
+        /// Tag to create, e.g. v1.2.0
+        #[arg(long)]
+        tag: String,
+        /// GitHub repository as owner/repo (defaults to the origin remote)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Also attach runtime bundles for these platforms (see `lunu package --platform`)
+        #[arg(long = "platform", value_name = "PLATFORM")]
+        platforms: Vec<String>,
+        /// Release notes file (defaults to commit subjects since the previous tag)
+        #[arg(long)]
+        notes: Option<PathBuf>,
+        /// Create the release as a draft
+        #[arg(long)]
+        draft: bool,
+        /// Mark the release as a prerelease
+        #[arg(long)]
+        prerelease: bool,
+        /// Stage the artifacts and notes without publishing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Tag and publish the library as a GitHub release that `lunu add` can install (needs GITHUB_TOKEN)
+    Publish {
+        /// GitHub repository as owner/repo (defaults to the origin remote)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Release notes file (defaults to commit subjects since the previous tag)
+        #[arg(long)]
+        notes: Option<PathBuf>,
+        /// Check the metadata and pack the library without tagging or publishing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Push a dependency's locked revision to an internal Git host and prefer it in lunu.lock
+    Mirror {
+        /// Dependency name
+        dep: String,
+        /// Mirror repository URL, or a base ending in `/` or `:` (the repo name is appended)
+        #[arg(long)]
+        to: String,
+    },
+    /// Maintain lunu.lock
+    Lock {
+        #[command(subcommand)]
+        action: LockAction,
+    },
+    /// Save the project's dependency state under a name and roll back to it (e.g. after a bad
+    /// `lunu update`)
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+    /// Write the project (sources, lunu.lock, installed modules, runtime version) to one reproducible archive
+    Export {
+        /// Bundle path (defaults to <project>.lunu)
+        output: Option<PathBuf>,
+    },
+    /// Recreate a project from a `lunu export` bundle, downloading only its runtime
+    Import {
+        /// Bundle to import
+        bundle: PathBuf,
+        /// Directory to create (defaults to the project name)
+        dir: Option<PathBuf>,
+    },
+    /// Inline a script and the local modules it requires into one Luau file that runs without Lunu
+    Bundle {
+        /// The entry point script (e.g., src/main.luau)
+        script: PathBuf,
+        /// Output file (defaults to dist/<script>.bundle.luau)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Inspect and validate project configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Static analysis of the project's Luau sources
+    Analyze {
+        #[command(subcommand)]
+        action: AnalyzeAction,
+    },
+    /// Inspect the bridge API key (kept in the OS keychain when available)
+    Key {
+        #[command(subcommand)]
+        action: KeyAction,
+    },
+    /// Summarize the project: runtime, dependencies, bridge modules, last build and warnings
+    Status,
     /// Validate project environment
-    Check,
+    Check {
+        /// Print findings with stable IDs and severities as JSON
+        #[arg(long)]
+        json: bool,
+        /// Record every current finding in .lunu/check-baseline.json so it no longer fails the check
+        #[arg(long)]
+        update_baseline: bool,
+    },
+    /// Type-check src/ with `lute check` (Lute) or `luau-analyze` and the runtime's definitions (Lune)
+    Typecheck {
+        /// Check installed modules as well
+        #[arg(long)]
+        modules: bool,
+        /// Check in strict mode and fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+        /// Print the diagnostics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Recompute every locked dependency's checksum and check package URLs against a denylist
+    Audit {
+        /// Denylist file (one repository per line); defaults to ~/.lunu/denylist.txt when present
+        #[arg(long)]
+        denylist: Option<PathBuf>,
+        /// Check locked versions and commits against a security advisory feed
+        #[arg(long)]
+        advisories: bool,
+        /// Advisory feed (file or URL); defaults to $LUNU_ADVISORY_FEED, then ~/.lunu/advisories.json
+        #[arg(long, requires = "advisories")]
+        advisory_feed: Option<String>,
+        /// Print the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Recompute the checksum of every locked module and fail when any differs from lunu.lock
+    Verify {
+        /// Print each dependency's status as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Create a new project
     Create {
         /// Project name (creates a folder with this name)
         name: String,
     },
     /// Upgrade Lunu to the latest version
-    Upgrade,
+    Upgrade {
+        /// Release channel to follow (beta includes prereleases)
+        #[arg(long, value_enum, default_value_t = UpgradeChannel::Stable)]
+        channel: UpgradeChannel,
+        /// Allow installing an older version than the current one
+        #[arg(long)]
+        force: bool,
+        /// Release asset name to download when automatic platform matching fails
+        #[arg(long)]
+        asset_override: Option<String>,
+    },
     /// Uninstall Lunu from the system
     Uninstall,
+    /// Write an installer for this platform into a directory (run its lunu binary to install)
+    MakeInstaller {
+        /// Output directory
+        out: PathBuf,
+        /// Bundle runtimes and templates so the installer works without internet access
+        #[arg(long)]
+        offline: bool,
+        /// Runtime to bundle, optionally pinned: lune, lute, lune@v0.8.9 (repeatable; default lune and lute)
+        #[arg(long = "runtime", value_name = "NAME[@VERSION]", requires = "offline")]
+        runtimes: Vec<String>,
+        /// Template to bundle: an exported template directory or Git repository URL (repeatable)
+        #[arg(long = "template", value_name = "SOURCE", requires = "offline")]
+        templates: Vec<String>,
+    },
     /// Scaffold a new project with a template
     Scaffold {
         /// Project name (creates a folder with this name)
@@ -124,6 +656,17 @@ enum Commands {
         /// Template type
         #[arg(short, long, value_enum, default_value_t = TemplateKind::App)]
         template: TemplateKind,
+        /// Render an exported template instead: a directory, an installed template or a Git repository URL
+        #[arg(long, conflicts_with = "template")]
+        from: Option<String>,
+        /// Template parameter (repeatable); the project name is set from <NAME>
+        #[arg(long = "set", value_name = "KEY=VALUE", requires = "from")]
+        set: Vec<String>,
+    },
+    /// Share this project's layout as a template
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
     },
     /// Create a bridge module scaffold
     Module {
@@ -140,11 +683,32 @@ enum Commands {
         /// Number of runs
         #[arg(short, long, default_value_t = 1)]
         runs: u32,
+        /// Use this runtime binary instead of the project/cached one for this invocation
+        #[arg(long, value_name = "PATH")]
+        runtime_path: Option<PathBuf>,
     },
     /// Run a script using the project runtime (Lute or Lune) resolved from config/env
     Run {
         /// The entry point script (e.g., src/main.luau)
         script: PathBuf,
+        /// Run with a profile (bridge: start the dev bridge and show a live call monitor)
+        #[arg(long, value_enum)]
+        profile: Option<RunProfile>,
+        /// Use this runtime binary instead of the project/cached one for this invocation
+        #[arg(long, value_name = "PATH")]
+        runtime_path: Option<PathBuf>,
+        /// Use the runtime version, path and env of [runtimes.<NAME>] in lunu.toml
+        #[arg(long, value_name = "NAME")]
+        runtime_profile: Option<String>,
+        /// Run with only system env vars and an empty temporary HOME, like a fresh machine
+        #[arg(long)]
+        sandbox: bool,
+        /// Pass this env var through to the sandbox (repeatable)
+        #[arg(long, value_name = "NAME", requires = "sandbox")]
+        keep_env: Vec<String>,
+        /// Disable the bridge and HTTP proxies inside the sandbox
+        #[arg(long, requires = "sandbox", conflicts_with = "profile")]
+        no_network: bool,
         #[arg(trailing_var_arg = true)]
         args: Vec<String>,
     },
@@ -153,14 +717,32 @@ enum Commands {
         /// Specific test file to run (optional)
         #[arg(short, long)]
         file: Option<PathBuf>,
+        /// Only run test files whose path contains this text
+        #[arg(long)]
+        filter: Option<String>,
+        /// Use this runtime binary instead of the project/cached one for this invocation
+        #[arg(long, value_name = "PATH")]
+        runtime_path: Option<PathBuf>,
+        /// Use the runtime version, path and env of [runtimes.<NAME>] in lunu.toml
+        #[arg(long, value_name = "NAME")]
+        runtime_profile: Option<String>,
+        /// Stream every test file's output, not only that of failures
+        #[arg(long)]
+        show_output: bool,
     },
     /// Manage a specific runtime
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Runtime {
-        #[arg(value_enum)]
-        runtime: RuntimeTarget,
+        #[command(subcommand)]
+        action: Option<RuntimeAction>,
+        #[arg(value_enum, required = true)]
+        runtime: Option<RuntimeTarget>,
         /// Update the runtime from the official GitHub release
         #[arg(long)]
         update: bool,
+        /// Release asset name to download when automatic platform matching fails
+        #[arg(long, requires = "update")]
+        asset_override: Option<String>,
     },
     /// Manage all runtimes
     Runtimes {
@@ -168,14 +750,49 @@ enum Commands {
         #[arg(long)]
         update: bool,
     },
-    /// Clean internal cache
+    /// Clean the runtime cache and files left behind by updates
     Clean,
+    /// Summarize cache sizes and locally recorded command usage (recording is opt-in)
+    Stats {
+        /// Start recording commands, durations and projects to ~/.lunu/stats (never uploaded)
+        #[arg(long, conflicts_with = "disable")]
+        enable: bool,
+        /// Stop recording (the history is kept until --clear)
+        #[arg(long)]
+        disable: bool,
+        /// Delete the recorded history
+        #[arg(long)]
+        clear: bool,
+    },
 }
 
-#[derive(ValueEnum, Clone)]
+impl Commands {
+    /// Commands that only read the project and local caches, never the network. They run on a
+    /// single-threaded runtime without the log subscriber, which keeps them quick to start.
+    fn is_local(&self) -> bool {
+        matches!(
+            self,
+            Commands::List { .. }
+                | Commands::Tree { .. }
+                | Commands::Check { .. }
+                | Commands::Provenance { .. }
+                | Commands::Verify { .. }
+                // `--advisories` may download the advisory feed
+                | Commands::Audit { advisories: false, .. }
+                | Commands::Config { .. }
+                | Commands::Analyze { .. }
+                | Commands::Stats { .. }
+                | Commands::Bundle { .. }
+        )
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Default, Debug, PartialEq)]
 enum TemplateKind {
+    #[default]
     App,
     Game,
+    Service,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -185,13 +802,24 @@ enum ModuleLang {
     Rust,
 }
 
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum RunProfile {
+    Bridge,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum UpgradeChannel {
+    Stable,
+    Beta,
+}
+
 #[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
 enum RuntimeTarget {
     Lute,
     Lune,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum RuntimeKind {
     Lute,
     Lune,
@@ -201,8 +829,19 @@ enum RuntimeKind {
 
 const LUTE_REPO: &str = "luau-lang/lute";
 const LUNE_REPO: &str = "lune-org/lune";
+const LUNU_REPO: &str = "tlipe/Lunu";
 const LUTE_EMBEDDED_VERSION: &str = "0.1.0";
 const UPDATE_CHECK_INTERVAL_SECS: u64 = 6 * 60 * 60;
+const RELEASE_NOTES_MAX_LINES: usize = 40;
+
+#[derive(Default)]
+struct InitOptions {
+    runtime: Option<RuntimeKind>,
+    name: Option<String>,
+    template: Option<TemplateKind>,
+    assume_yes: bool,
+    skip_types: bool,
+}
 
 struct ToolchainDetection {
     c_compiler: Option<PathBuf>,
@@ -247,6 +886,14 @@ fn runtime_from_env() -> Option<RuntimeKind> {
 struct GithubRelease {
     tag_name: String,
     assets: Vec<GithubAsset>,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    html_url: Option<String>,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -264,6 +911,8 @@ struct RuntimeMeta {
 struct RuntimeUpdate {
     version: String,
     url: String,
+    notes: Option<String>,
+    notes_url: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -278,6 +927,13 @@ fn runtime_target_from_kind(kind: RuntimeKind) -> RuntimeTarget {
     }
 }
 
+fn runtime_kind_from_target(target: RuntimeTarget) -> RuntimeKind {
+    match target {
+        RuntimeTarget::Lute => RuntimeKind::Lute,
+        RuntimeTarget::Lune => RuntimeKind::Lune,
+    }
+}
+
 fn runtime_repo(target: RuntimeTarget) -> &'static str {
     match target {
         RuntimeTarget::Lute => LUTE_REPO,
@@ -285,6 +941,13 @@ fn runtime_repo(target: RuntimeTarget) -> &'static str {
     }
 }
 
+fn upgrade_channel_name(channel: UpgradeChannel) -> &'static str {
+    match channel {
+        UpgradeChannel::Stable => "stable",
+        UpgradeChannel::Beta => "beta",
+    }
+}
+
 fn runtime_name(target: RuntimeTarget) -> &'static str {
     match target {
         RuntimeTarget::Lute => "lute",
@@ -324,6 +987,14 @@ fn old_exe_path(current_exe: &Path) -> PathBuf {
     }
 }
 
+/// Where `housekeeping` looks for leftovers: the install directory's binaries and the runtime
+/// cache, plus the `.old` copy of the running binary wherever it is installed.
+fn housekeeping_targets() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let dirs = dirs::home_dir().map(|home| home.join(".lunu").join("bin")).into_iter().chain([runtime_cache_dir()]).collect();
+    let files = std::env::current_exe().map(|exe| old_exe_path(&exe)).into_iter().collect();
+    (dirs, files)
+}
+
 fn builder_bin_filename() -> String {
     if cfg!(windows) {
         "lunu-builder.exe".to_string()
@@ -332,52 +1003,241 @@ fn builder_bin_filename() -> String {
     }
 }
 
-fn platform_os_keys() -> Vec<&'static str> {
-    match std::env::consts::OS {
-        "windows" => vec!["windows", "win"],
-        "macos" => vec!["macos", "darwin", "osx"],
+struct HostPlatform {
+    os: &'static str,
+    arch: &'static str,
+    musl: bool,
+}
+
+impl HostPlatform {
+    fn current() -> Self {
+        Self {
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            musl: host_uses_musl(),
+        }
+    }
+}
+
+fn host_uses_musl() -> bool {
+    if cfg!(target_env = "musl") {
+        return true;
+    }
+    if std::env::consts::OS != "linux" {
+        return false;
+    }
+    std::fs::read_dir("/lib")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+        .unwrap_or(false)
+}
+
+fn platform_os_keys(os: &str) -> Vec<&str> {
+    match os {
+        "windows" => vec!["windows", "win", "win64", "win32"],
+        "macos" => vec!["macos", "darwin", "osx", "apple"],
         "linux" => vec!["linux"],
         other => vec![other],
     }
 }
 
-fn platform_arch_keys() -> Vec<&'static str> {
-    match std::env::consts::ARCH {
-        "x86_64" => vec!["x86_64", "amd64", "x64"],
-        "aarch64" => vec!["aarch64", "arm64"],
-        "arm" => vec!["armv7", "arm"],
-        other => vec![other],
+/// Known architectures and the spellings release assets use for them.
+/// `arm64` must be checked before plain `arm`.
+const KNOWN_ARCHES: &[(&str, &[&str])] = &[
+    ("x86_64", &["x86_64", "amd64", "x64"]),
+    ("aarch64", &["aarch64", "arm64"]),
+    ("arm", &["armv7", "armv7l", "armhf", "arm"]),
+    ("x86", &["i686", "i386", "x86", "win32"]),
+];
+
+fn asset_tokens(name: &str) -> Vec<&str> {
+    name.split(['-', '.', ' ', '+'])
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn asset_arch(tokens: &[&str]) -> Option<&'static str> {
+    KNOWN_ARCHES
+        .iter()
+        .find(|(_, keys)| keys.iter().any(|k| tokens.contains(k)))
+        .map(|(arch, _)| *arch)
+}
+
+/// Architectures the host can run through emulation (Windows on ARM, Rosetta).
+fn emulated_arches(host: &HostPlatform) -> &'static [&'static str] {
+    match (host.os, host.arch) {
+        ("windows", "aarch64") | ("macos", "aarch64") => &["x86_64"],
+        ("windows", "x86_64") => &["x86"],
+        _ => &[],
     }
 }
 
-fn asset_matches_platform(name: &str, require_arch: bool) -> bool {
-    let os_keys = platform_os_keys();
-    let arch_keys = platform_arch_keys();
-    let has_os = os_keys.iter().any(|k| name.contains(k));
-    if !has_os {
-        return false;
+/// Scores how well an asset name fits the host. `None` means the asset is for another platform.
+fn asset_platform_score(name: &str, host: &HostPlatform) -> Option<i32> {
+    let lower = name.to_lowercase();
+    let tokens = asset_tokens(&lower);
+    let os_keys = platform_os_keys(host.os);
+    if !os_keys.iter().any(|k| tokens.contains(k)) {
+        return None;
+    }
+    let mut score = 0;
+    match asset_arch(&tokens) {
+        Some(arch) if arch == host.arch => score += 4,
+        Some(arch) if emulated_arches(host).contains(&arch) => score += 1,
+        Some(_) => return None,
+        None => {}
+    }
+    if host.os == "linux" {
+        let is_musl = tokens.iter().any(|t| *t == "musl" || *t == "static");
+        if is_musl == host.musl {
+            score += 2;
+        }
+    }
+    Some(score)
+}
+
+fn pick_platform_asset<'a, T>(items: &'a [T], host: &HostPlatform, name_of: impl Fn(&T) -> &str) -> Option<&'a T> {
+    let mut best: Option<(i32, &T)> = None;
+    for item in items {
+        if let Some(score) = asset_platform_score(name_of(item), host) {
+            if best.map(|(b, _)| score > b).unwrap_or(true) {
+                best = Some((score, item));
+            }
+        }
+    }
+    best.map(|(_, item)| item)
+}
+
+impl HostPlatform {
+    /// Parses `lunu package --platform` values such as `windows-x64`, `linux-arm64` or `linux-x64-musl`.
+    fn parse(spec: &str) -> Result<Self> {
+        let lower = spec.trim().to_lowercase();
+        let tokens: Vec<&str> = lower.split('-').filter(|t| !t.is_empty()).collect();
+        let invalid = || anyhow::anyhow!(
+            "Unknown platform '{}'. Expected <os>-<arch>[-musl], e.g. windows-x64, linux-arm64 or macos-arm64",
+            spec
+        );
+        let (os_token, rest) = tokens.split_first().ok_or_else(invalid)?;
+        let os = ["windows", "linux", "macos"]
+            .into_iter()
+            .find(|os| platform_os_keys(os).contains(os_token))
+            .ok_or_else(invalid)?;
+        let arch = rest.first().and_then(|arch| asset_arch(&[*arch])).ok_or_else(invalid)?;
+        let musl = match &rest[1..] {
+            [] => false,
+            ["musl"] if os == "linux" => true,
+            _ => return Err(invalid()),
+        };
+        Ok(Self { os, arch, musl })
+    }
+
+    /// Name used for dist folders and archives, e.g. `linux-aarch64`.
+    fn label(&self) -> String {
+        let mut label = format!("{}-{}", self.os, self.arch);
+        if self.musl {
+            label.push_str("-musl");
+        }
+        label
+    }
+}
+
+fn find_asset_override<'a>(assets: &'a [GithubAsset], wanted: &str) -> Result<&'a GithubAsset> {
+    assets
+        .iter()
+        .find(|a| a.name.eq_ignore_ascii_case(wanted))
+        .or_else(|| {
+            let wanted = wanted.to_lowercase();
+            assets.iter().find(|a| a.name.to_lowercase().contains(&wanted))
+        })
+        .ok_or_else(|| {
+            let names: Vec<&str> = assets.iter().map(|a| a.name.as_str()).collect();
+            anyhow::anyhow!("Asset '{}' not found in release. Available: {}", wanted, names.join(", "))
+        })
+}
+
+/// `[runtime.assets]` of `~/.lunu/config.toml`: glob patterns (`*` and `?`) for runtime release
+/// assets named in a way `pick_runtime_asset_for` does not recognize. A table named after an OS or
+/// a platform holds patterns for that one only:
+///
+/// ```toml
+/// [runtime.assets]
+/// lune = "lune-*-win64.zip"
+///
+/// [runtime.assets.linux-arm64]
+/// lute = "lute-*-aarch64-unknown-linux.tar.gz"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RuntimeAssetPatterns {
+    /// Patterns by table (`""` for every platform, else an OS or a platform label), then runtime
+    tables: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl RuntimeAssetPatterns {
+    fn parse(config: &str) -> Result<Self> {
+        let config: toml::Table = toml::from_str(config)?;
+        let Some(assets) = config.get("runtime").and_then(|r| r.get("assets")) else {
+            return Ok(Self::default());
+        };
+        let assets = assets.as_table().ok_or_else(|| anyhow::anyhow!("[runtime.assets] must be a table"))?;
+        let mut tables: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        let pattern = |table: &str, runtime: &str, value: &toml::Value| -> Result<String> {
+            if runtime != "lune" && runtime != "lute" {
+                return Err(anyhow::anyhow!("Unknown runtime '{}' in [runtime.assets{}]; expected lune or lute", runtime, table));
+            }
+            value.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("[runtime.assets{}] {} must be a string", table, runtime))
+        };
+        for (key, value) in assets {
+            let Some(platform) = value.as_table() else {
+                tables.entry(String::new()).or_default().insert(key.clone(), pattern("", key, value)?);
+                continue;
+            };
+            let name = if ["windows", "linux", "macos"].contains(&key.as_str()) { key.clone() } else { HostPlatform::parse(key)?.label() };
+            for (runtime, value) in platform {
+                let pattern = pattern(&format!(".{}", key), runtime, value)?;
+                tables.entry(name.clone()).or_default().insert(runtime.clone(), pattern);
+            }
+        }
+        Ok(Self { tables })
     }
-    if require_arch {
-        let has_arch = arch_keys.iter().any(|k| name.contains(k));
-        if !has_arch {
-            return false;
+
+    fn load() -> Result<Self> {
+        match http::config_path().filter(|p| p.exists()) {
+            Some(path) => {
+                let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+                Self::parse(&content).with_context(|| format!("Invalid {:?}", path))
+            }
+            None => Ok(Self::default()),
         }
     }
-    true
+
+    /// Patterns for `target` on `platform`, most specific first, and whether each is a shared one.
+    fn patterns(&self, target: RuntimeTarget, platform: &HostPlatform) -> Vec<(&str, bool)> {
+        [platform.label().as_str(), platform.os, ""]
+            .iter()
+            .filter_map(|table| Some((self.tables.get(*table)?.get(runtime_name(target))?.as_str(), table.is_empty())))
+            .collect()
+    }
 }
 
-fn asset_extension_supported(name: &str) -> bool {
+fn asset_extension_supported(name: &str, os: &str) -> bool {
     if name.ends_with(".zip") || name.ends_with(".tar.gz") || name.ends_with(".tgz") {
         return true;
     }
-    if cfg!(windows) && name.ends_with(".exe") {
+    let windows = os == "windows";
+    if windows && name.ends_with(".exe") {
         return true;
     }
-    let plain = !name.contains('.') && !name.ends_with('/') && !name.ends_with('\\');
-    if !cfg!(windows) && plain {
-        return true;
+    if windows || name.ends_with('/') || name.ends_with('\\') {
+        return false;
+    }
+    // Plain binaries have no extension, though version numbers may contain dots (lune-0.8.9-linux-musl)
+    match name.rsplit_once('.') {
+        None => true,
+        Some((_, ext)) => ext.contains('-') || ext.chars().all(|c| c.is_ascii_digit()),
     }
-    false
 }
 
 fn ensure_executable(path: &Path) -> Result<()> {
@@ -397,6 +1257,7 @@ fn matches_candidate_name(file_name: &str, candidates: &[String]) -> bool {
 }
 
 fn extract_binary_from_zip(bytes: &[u8], candidates: &[String]) -> Result<Vec<u8>> {
+    let _timing = timings::span("extraction");
     let reader = std::io::Cursor::new(bytes);
     let mut zip = zip::ZipArchive::new(reader)?;
     for i in 0..zip.len() {
@@ -416,6 +1277,7 @@ fn extract_binary_from_zip(bytes: &[u8], candidates: &[String]) -> Result<Vec<u8
 }
 
 fn extract_binary_from_tar_gz(bytes: &[u8], candidates: &[String]) -> Result<Vec<u8>> {
+    let _timing = timings::span("extraction");
     let reader = std::io::Cursor::new(bytes);
     let decoder = GzDecoder::new(reader);
     let mut archive = Archive::new(decoder);
@@ -455,6 +1317,9 @@ fn lute_sources_root() -> PathBuf {
 }
 
 fn runtime_available(root: &Path, target: RuntimeTarget) -> bool {
+    if runtime_path_override().is_some() {
+        return true;
+    }
     let local = root.join("bin").join(runtime_bin_filename(target));
     if local.exists() {
         return true;
@@ -477,9 +1342,11 @@ fn runtime_available(root: &Path, target: RuntimeTarget) -> bool {
 
 async fn ensure_runtime_available(root: &Path, target: RuntimeTarget) -> Result<()> {
     if runtime_available(root, target) {
+        timings::cache_hit("runtime");
         return Ok(());
     }
-    if let Err(err) = update_runtime(target).await {
+    timings::cache_miss("runtime");
+    if let Err(err) = update_runtime(target, None).await {
         if target == RuntimeTarget::Lute && ensure_embedded_lute().is_some() {
             return Ok(());
         }
@@ -500,12 +1367,8 @@ fn read_runtime_meta(target: RuntimeTarget) -> Option<RuntimeMeta> {
 
 fn write_runtime_meta(target: RuntimeTarget, meta: &RuntimeMeta) -> Result<()> {
     let path = runtime_meta_path(target);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     let content = serde_json::to_string(meta)?;
-    std::fs::write(path, content)?;
-    Ok(())
+    write_atomic(&path, content.as_bytes())
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -528,12 +1391,8 @@ fn read_update_check_cache() -> UpdateCheckCache {
 
 fn write_update_check_cache(cache: &UpdateCheckCache) -> Result<()> {
     let path = update_check_cache_path();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     let content = serde_json::to_string(cache)?;
-    std::fs::write(path, content)?;
-    Ok(())
+    write_atomic(&path, content.as_bytes())
 }
 
 fn should_check_update(cache: &UpdateCheckCache, target: RuntimeTarget) -> bool {
@@ -563,9 +1422,7 @@ fn ensure_embedded_lute() -> Option<PathBuf> {
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
-        if let Ok(mut f) = File::create(&path) {
-            let _ = f.write_all(bytes);
-        }
+        let _ = install_runtime_binary(&path, bytes);
         if path.exists() {
             if read_runtime_meta(target).is_none() {
                 let _ = write_runtime_meta(
@@ -586,45 +1443,61 @@ async fn fetch_latest_release(target: RuntimeTarget) -> Result<GithubRelease> {
     let repo = runtime_repo(target);
     // Use /releases instead of /releases/latest to catch pre-releases (nightly)
     let url = format!("https://api.github.com/repos/{}/releases", repo);
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, "Lunu-CLI")
-        .send()
-        .await?
-        .error_for_status()?;
+    let client = http::client()?;
     
     // The API returns an array of releases. We want the first one.
-    let releases: Vec<GithubRelease> = resp.json().await?;
+    let releases: Vec<GithubRelease> = http::get_json(&client, &url).await?;
     releases.into_iter().next().ok_or_else(|| anyhow::anyhow!("No releases found for {}", repo))
 }
 
-fn pick_runtime_asset(release: &GithubRelease, target: RuntimeTarget) -> Option<GithubAsset> {
+fn pick_runtime_asset(release: &GithubRelease, target: RuntimeTarget, asset_override: Option<&str>) -> Result<GithubAsset> {
+    if let Some(wanted) = asset_override {
+        return find_asset_override(&release.assets, wanted).cloned();
+    }
+    let patterns = RuntimeAssetPatterns::load()?;
+    pick_runtime_asset_for(release, target, &HostPlatform::current(), &patterns).ok_or_else(|| anyhow::anyhow!(
+        "No compatible runtime asset found in latest {} release. Use --asset-override <name> to pick one manually, or set a pattern under [runtime.assets] in ~/.lunu/config.toml.",
+        runtime_name(target)
+    ))
+}
+
+/// The asset of `release` for `platform`: the first one matching a `[runtime.assets]` pattern,
+/// else the best match by name.
+fn pick_runtime_asset_for(release: &GithubRelease, target: RuntimeTarget, platform: &HostPlatform, patterns: &RuntimeAssetPatterns) -> Option<GithubAsset> {
+    // A shared pattern written for one OS must not pick that OS's asset for another
+    let other_os = |name: &str| {
+        let tokens = asset_tokens(name);
+        ["windows", "linux", "macos"].into_iter().filter(|os| *os != platform.os).any(|os| platform_os_keys(os).iter().any(|k| tokens.contains(k)))
+    };
+    for (pattern, shared) in patterns.patterns(target, platform) {
+        let pattern = pattern.to_lowercase();
+        let mut matches: Vec<&GithubAsset> = release
+            .assets
+            .iter()
+            .filter(|a| lunu_builder::exclude::wildcard(&pattern, &a.name.to_lowercase()) && !(shared && other_os(&a.name.to_lowercase())))
+            .collect();
+        matches.sort_by_key(|a| a.name.to_lowercase());
+        if let Some(asset) = matches.first() {
+            return Some((*asset).clone());
+        }
+    }
     let name = runtime_name(target);
     let mut candidates: Vec<GithubAsset> = release
         .assets
         .iter()
         .filter(|a| {
             let n = a.name.to_lowercase();
-            n.contains(name) && asset_extension_supported(&n)
+            n.contains(name) && asset_extension_supported(&n, platform.os)
         })
         .cloned()
         .collect();
 
     candidates.sort_by_key(|a| a.name.to_lowercase());
 
-    for require_arch in [true, false] {
-        if let Some(asset) = candidates.iter().find(|a| {
-            let n = a.name.to_lowercase();
-            asset_matches_platform(&n, require_arch)
-        }) {
-            return Some(asset.clone());
-        }
-    }
-    None
+    pick_platform_asset(&candidates, platform, |a| &a.name).cloned()
 }
 
-async fn find_runtime_update(target: RuntimeTarget) -> Result<Option<RuntimeUpdate>> {
+async fn find_runtime_update(target: RuntimeTarget, asset_override: Option<&str>) -> Result<Option<RuntimeUpdate>> {
     let latest = fetch_latest_release(target).await?;
     let current = read_runtime_meta(target).map(|m| m.version);
     if let Some(ref current) = current {
@@ -632,27 +1505,57 @@ async fn find_runtime_update(target: RuntimeTarget) -> Result<Option<RuntimeUpda
             return Ok(None);
         }
     }
-    let asset = pick_runtime_asset(&latest, target)
-        .ok_or_else(|| anyhow::anyhow!("No compatible runtime asset found in latest {} release", runtime_name(target)))?;
+    let asset = pick_runtime_asset(&latest, target, asset_override)?;
     Ok(Some(RuntimeUpdate {
         version: latest.tag_name,
         url: asset.browser_download_url,
+        notes: latest.body,
+        notes_url: latest.html_url,
     }))
 }
 
+fn runtime_lock_path(target: RuntimeTarget) -> PathBuf {
+    runtime_cache_dir().join(format!("{}.lock", runtime_name(target)))
+}
+
+fn install_runtime_binary(path: &Path, content: &[u8]) -> Result<()> {
+    let tmp = unique_temp_path(path);
+    std::fs::write(&tmp, content)?;
+    ensure_executable(&tmp)?;
+    if let Err(err) = replace_file(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Emits `download` progress events, at most once per whole percent (or per MB without a known size).
+fn download_progress(label: String) -> impl Fn(u64, Option<u64>) + Sync {
+    let last = std::sync::atomic::AtomicU64::new(u64::MAX);
+    move |received, total| {
+        let percent = total.filter(|t| *t > 0).map(|t| received as f64 * 100.0 / t as f64);
+        let bucket = percent.map(|p| p as u64).unwrap_or(received / (1024 * 1024));
+        if last.swap(bucket, std::sync::atomic::Ordering::Relaxed) != bucket {
+            let message = format!("Downloading {} ({})", label, lunu_builder::report::format_size(received));
+            progress::emit("download", percent, &message);
+        }
+    }
+}
+
 async fn download_runtime(target: RuntimeTarget, update: &RuntimeUpdate) -> Result<PathBuf> {
+    let path = runtime_cache_bin(target);
+    let _lock = FileLock::acquire(&runtime_lock_path(target)).await?;
+    // Another process may have installed this version while we waited for the lock
+    if path.exists() && read_runtime_meta(target).map(|m| m.version).as_deref() == Some(update.version.as_str()) {
+        println!("{} {} is already installed.", runtime_name(target), update.version);
+        return Ok(path);
+    }
+
     let url = &update.url;
     println!("Downloading {} from {}...", runtime_name(target), url);
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, "Lunu-CLI")
-        .send()
-        .await?
-        .error_for_status()?;
-    let bytes = resp.bytes().await?;
+    let client = http::client()?;
+    let bytes = http::get_bytes_with_progress(&client, url, &download_progress(runtime_name(target).to_string())).await?;
     
-    let path = runtime_cache_bin(target);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
@@ -695,13 +1598,7 @@ async fn download_runtime(target: RuntimeTarget, update: &RuntimeUpdate) -> Resu
             std::fs::write(&out_path, &content)?;
         }
         let content = runtime_bytes.ok_or(anyhow::anyhow!("Runtime binary not found in zip"))?;
-        let tmp = path.with_extension("tmp");
-        std::fs::write(&tmp, content)?;
-        if path.exists() {
-            let _ = std::fs::remove_file(&path);
-        }
-        std::fs::rename(&tmp, &path)?;
-        ensure_executable(&path)?;
+        install_runtime_binary(&path, &content)?;
     } else if url_lower.ends_with(".tar.gz") || url_lower.ends_with(".tgz") {
         let reader = std::io::Cursor::new(bytes);
         let decoder = GzDecoder::new(reader);
@@ -737,21 +1634,9 @@ async fn download_runtime(target: RuntimeTarget, update: &RuntimeUpdate) -> Resu
             std::fs::write(&out_path, &content)?;
         }
         let content = runtime_bytes.ok_or(anyhow::anyhow!("Runtime binary not found in tarball"))?;
-        let tmp = path.with_extension("tmp");
-        std::fs::write(&tmp, content)?;
-        if path.exists() {
-            let _ = std::fs::remove_file(&path);
-        }
-        std::fs::rename(&tmp, &path)?;
-        ensure_executable(&path)?;
+        install_runtime_binary(&path, &content)?;
     } else {
-        let tmp = path.with_extension("tmp");
-        std::fs::write(&tmp, bytes)?;
-        if path.exists() {
-             let _ = std::fs::remove_file(&path);
-        }
-        std::fs::rename(&tmp, &path)?;
-        ensure_executable(&path)?;
+        install_runtime_binary(&path, &bytes)?;
     }
 
     write_runtime_meta(
@@ -764,42 +1649,65 @@ async fn download_runtime(target: RuntimeTarget, update: &RuntimeUpdate) -> Resu
     Ok(path)
 }
 
-async fn update_runtime(target: RuntimeTarget) -> Result<()> {
-    match find_runtime_update(target).await? {
+async fn update_runtime(target: RuntimeTarget, asset_override: Option<&str>) -> Result<Option<RuntimeUpdate>> {
+    match find_runtime_update(target, asset_override).await? {
         Some(update) => {
             let path = download_runtime(target, &update).await?;
             println!("Updated {} runtime to {} at {:?}", runtime_name(target), update.version, path);
+            Ok(Some(update))
         }
         None => {
             println!("{} runtime is up to date", runtime_name(target));
+            Ok(None)
         }
     }
+}
+
+async fn update_runtime_with_notes(target: RuntimeTarget, asset_override: Option<&str>) -> Result<()> {
+    if let Some(update) = update_runtime(target, asset_override).await? {
+        let title = format!("{} {}", runtime_name(target), update.version);
+        print_release_notes(&title, update.notes.as_deref(), update.notes_url.as_deref());
+    }
     Ok(())
 }
 
+fn render_release_notes(title: &str, notes: &str, url: Option<&str>) -> String {
+    let lines: Vec<&str> = notes.trim().lines().map(|l| l.trim_end()).collect();
+    let mut out = vec![format!("Release notes for {}:", title)];
+    for line in lines.iter().take(RELEASE_NOTES_MAX_LINES) {
+        out.push(format!("  {}", line));
+    }
+    if lines.len() > RELEASE_NOTES_MAX_LINES {
+        out.push(format!("  ... {} more line(s)", lines.len() - RELEASE_NOTES_MAX_LINES));
+    }
+    if let Some(url) = url {
+        out.push(format!("Full notes: {}", url));
+    }
+    out.join("\n")
+}
+
+fn print_release_notes(title: &str, notes: Option<&str>, url: Option<&str>) {
+    match notes.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(notes) => println!("\n{}", render_release_notes(title, notes, url)),
+        None => {
+            if let Some(url) = url {
+                println!("Release notes: {}", url);
+            }
+        }
+    }
+}
+
 async fn fetch_repo_default_branch(repo: &str) -> Result<String> {
     let url = format!("https://api.github.com/repos/{}", repo);
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, "Lunu-CLI")
-        .send()
-        .await?
-        .error_for_status()?;
-    let info: GithubRepoInfo = resp.json().await?;
+    let client = http::client()?;
+    let info: GithubRepoInfo = http::get_json(&client, &url).await?;
     Ok(info.default_branch)
 }
 
 async fn download_repo_zip(repo: &str, branch: &str) -> Result<Vec<u8>> {
     let url = format!("https://codeload.github.com/{}/zip/refs/heads/{}", repo, branch);
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, "Lunu-CLI")
-        .send()
-        .await?
-        .error_for_status()?;
-    Ok(resp.bytes().await?.to_vec())
+    let client = http::client()?;
+    http::get_bytes(&client, &url).await
 }
 
 async fn ensure_lute_sources() -> Result<PathBuf> {
@@ -854,7 +1762,7 @@ async fn ensure_lute_sources() -> Result<PathBuf> {
 }
 
 async fn maybe_prompt_update(target: RuntimeTarget) -> Result<()> {
-    if !stdin_is_interactive() {
+    if !stdin_is_interactive() || runtime_path_override().is_some() {
         return Ok(());
     }
     let mut cache = read_update_check_cache();
@@ -864,7 +1772,7 @@ async fn maybe_prompt_update(target: RuntimeTarget) -> Result<()> {
     record_update_check(&mut cache, target);
     let _ = write_update_check_cache(&cache);
     tokio::spawn(async move {
-        let update = match find_runtime_update(target).await {
+        let update = match find_runtime_update(target, None).await {
             Ok(value) => value,
             Err(err) => {
                 println!("Runtime update check failed for {}: {}", runtime_name(target), err);
@@ -883,11 +1791,172 @@ async fn maybe_prompt_update(target: RuntimeTarget) -> Result<()> {
     Ok(())
 }
 
-fn select_runtime() -> Result<RuntimeKind> {
+/// Answers collected by the first-run wizard before anything is changed.
+#[derive(Debug, PartialEq)]
+struct SetupPlan {
+    install: bool,
+    download_runtimes: bool,
+    project: Option<ProjectPlan>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ProjectPlan {
+    name: String,
+    runtime: RuntimeKind,
+    template: TemplateKind,
+    editor: bool,
+}
+
+fn ask(input: &mut impl BufRead, question: &str, default: &str) -> Result<String> {
+    print!("{} [{}]: ", question, default);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+}
+
+fn ask_yes_no(input: &mut impl BufRead, question: &str, default: bool) -> Result<bool> {
+    loop {
+        print!("{} [{}]: ", question, if default { "Y/n" } else { "y/N" });
+        io::stdout().flush()?;
+        let mut line = String::new();
+        input.read_line(&mut line)?;
+        match line.trim().to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+fn ask_choice(input: &mut impl BufRead, question: &str, options: &[&str]) -> Result<usize> {
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}) {}", index + 1, option);
+    }
+    loop {
+        let answer = ask(input, question, "1")?;
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=options.len()).contains(&n) => return Ok(n - 1),
+            _ => println!("Please enter a number between 1 and {}.", options.len()),
+        }
+    }
+}
+
+/// `  1) owner/name  ★ 1.2k  description` (the star is ASCII on consoles that cannot show it).
+fn format_search_result(index: usize, result: &PackageInfo) -> String {
+    let stars = match result.stars {
+        n if n >= 1000 => format!("{:.1}k", n as f64 / 1000.0),
+        n => n.to_string(),
+    };
+    let description = result.description.as_deref().map(|d| format!("  {}", d.trim())).unwrap_or_default();
+    format!("  {}) {}/{}  {} {}{}", index + 1, result.owner, result.name, console::glyph("★", "*"), stars, description)
+}
+
+/// Lists `results` and reads the chosen number. Enter picks `default` (1-based), or nothing when
+/// there is none.
+fn ask_search_result(input: &mut impl BufRead, results: &[PackageInfo], default: Option<usize>) -> Result<Option<usize>> {
+    for (index, result) in results.iter().enumerate() {
+        println!("{}", format_search_result(index, result));
+    }
+    loop {
+        match default {
+            Some(default) => print!("Install which? [{}]: ", default),
+            None => print!("Install which? (Enter to skip): "),
+        }
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(default.map(|d| d - 1));
+        }
+        match line.trim() {
+            "" => return Ok(default.map(|d| d - 1)),
+            answer => match answer.parse::<usize>() {
+                Ok(n) if (1..=results.len()).contains(&n) => return Ok(Some(n - 1)),
+                _ => println!("Please enter a number between 1 and {}.", results.len()),
+            },
+        }
+    }
+}
+
+/// Which search result `lunu add` installs: an exact `owner/name` match, the only result, or the
+/// top one with `--yes` or without a terminal to ask in; otherwise the user picks.
+fn pick_search_result(query: &str, results: &[PackageInfo], yes: bool) -> Result<Option<usize>> {
+    let exact = results.iter().position(|r| format!("{}/{}", r.owner, r.name).eq_ignore_ascii_case(query.trim()));
+    if let Some(index) = exact {
+        return Ok(Some(index));
+    }
+    if yes || results.len() == 1 || !(io::stdin().is_terminal() && stdin_is_interactive()) {
+        return Ok(Some(0));
+    }
+    ask_search_result(&mut io::stdin().lock(), results, Some(1))
+}
+
+/// The interactive first run: `lunu` without a command, started from outside ~/.lunu/bin.
+fn ask_setup_plan(input: &mut impl BufRead) -> Result<SetupPlan> {
+    println!("Welcome to Lunu! Let's get you set up. Press Enter to accept the [default].\n");
+    let install = ask_yes_no(input, "Install lunu to ~/.lunu/bin and add it to PATH?", true)?;
+    let download_runtimes = ask_yes_no(input, "Download the Lune and Lute runtimes now?", true)?;
+    let project = if ask_yes_no(input, "Create a new project?", true)? {
+        let name = ask(input, "Project name", "my-lunu-app")?;
+        println!("Runtime:");
+        let runtime = match ask_choice(input, "Choose", &["Lune - sandboxed, bridge workers in Python/Node/Rust", "Lute - native, C/C++ modules, @lute and @std"])? {
+            0 => RuntimeKind::Lune,
+            _ => RuntimeKind::Lute,
+        };
+        println!("Template:");
+        let template = match ask_choice(input, "Choose", &["app", "game", "service"])? {
+            0 => TemplateKind::App,
+            1 => TemplateKind::Game,
+            _ => TemplateKind::Service,
+        };
+        let editor = ask_yes_no(input, "Set up editor integration (luau-lsp type definitions)?", true)?;
+        Some(ProjectPlan { name, runtime, template, editor })
+    } else {
+        None
+    };
+    Ok(SetupPlan { install, download_runtimes, project })
+}
+
+async fn run_setup_plan(cwd: &Path, plan: &SetupPlan) -> Result<()> {
+    if plan.install {
+        install_self(false).await?;
+    }
+    if plan.download_runtimes {
+        for target in [RuntimeTarget::Lune, RuntimeTarget::Lute] {
+            if let Err(err) = ensure_runtime_available(cwd, target).await {
+                println!("Warning: could not download {}: {:#}. Retry later with 'lunu runtime {} --update'.", runtime_name(target), err, runtime_name(target));
+            }
+        }
+    }
+    if let Some(project) = &plan.project {
+        let options = InitOptions {
+            runtime: Some(project.runtime),
+            name: Some(project.name.clone()),
+            template: Some(project.template),
+            assume_yes: true,
+            skip_types: !project.editor,
+        };
+        create_project(cwd, &project.name, &options).await?;
+        println!("\nNext steps:");
+        println!("  cd {}", project.name);
+        println!("  lunu run src/main.luau");
+    }
+    if plan.install {
+        println!("\nRestart your terminal so 'lunu' is on PATH.");
+    }
+    Ok(())
+}
+
+fn select_runtime(requested: Option<RuntimeKind>, assume_yes: bool) -> Result<RuntimeKind> {
+    if let Some(runtime) = requested {
+        return Ok(runtime);
+    }
     if let Some(runtime) = runtime_from_env() {
         return Ok(runtime);
     }
-    if !stdin_is_interactive() {
+    if assume_yes || !stdin_is_interactive() {
         return Ok(RuntimeKind::Lune);
     }
 
@@ -992,6 +2061,14 @@ fn build_config_for(runtime: RuntimeKind, toolchain: Option<ToolchainDetection>)
                     .as_ref()
                     .map(|p| p.to_string_lossy().to_string()),
                 toolchain: toolchain.toolchain,
+                gates: None,
+                updates: None,
+                extract_dir: None,
+                cleanup: None,
+                exclude: None,
+                signing: None,
+                history: None,
+                smoke: None,
             }
         }
         RuntimeKind::Lune => BuildConfig {
@@ -1006,6 +2083,14 @@ fn build_config_for(runtime: RuntimeKind, toolchain: Option<ToolchainDetection>)
             c_compiler: None,
             cpp_compiler: None,
             toolchain: None,
+            gates: None,
+            updates: None,
+            extract_dir: None,
+            cleanup: None,
+            exclude: None,
+            signing: None,
+            history: None,
+            smoke: None,
         },
     }
 }
@@ -1065,12 +2150,108 @@ fn detect_cpp_toolchain() -> ToolchainDetection {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+// No `#[tokio::main]`: `--help` and `--version` exit while parsing, before a runtime exists, and
+// local commands get a single-threaded one (see `Commands::is_local`).
+fn main() -> Result<()> {
+    console::init();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    http::configure(matches.subcommand_name())?;
+    progress::enable_json(cli.progress == ProgressFormat::Json);
+    timings::enable(cli.timings);
+    let started = std::time::Instant::now();
+    let local = cli.command.as_ref().is_some_and(Commands::is_local);
+    let runtime = if local { tokio::runtime::Builder::new_current_thread() } else { tokio::runtime::Builder::new_multi_thread() }
+        .enable_all()
+        .build()?;
+    if matches.subcommand_name() != Some("worker-host") {
+        let (dirs, files) = housekeeping_targets();
+        housekeeping::sweep(&dirs, &files, housekeeping::STALE_AFTER);
+    }
+    let result = runtime.block_on(run(cli));
+    print_timings();
+    if let Some(command) = matches.subcommand_name().filter(|c| !matches!(*c, "worker-host" | "stats")) {
+        record_usage(command, started.elapsed(), result.is_ok());
+    }
+    result
+}
+
+/// Appends to the local `lunu stats` history; a no-op unless recording was enabled.
+fn record_usage(command: &str, elapsed: std::time::Duration, success: bool) {
+    let Some(dir) = stats::dir().filter(|d| stats::is_enabled(d)) else {
+        return;
+    };
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_root(&cwd))
+        .filter(|root| project_config_path(root).exists())
+        .map(|root| stats::project_id(&root));
+    let invocation = stats::Invocation {
+        command: command.to_string(),
+        started_at: current_timestamp().saturating_sub(elapsed.as_secs()),
+        duration_ms: elapsed.as_millis() as u64,
+        success,
+        project,
+    };
+    if let Err(err) = stats::record(&dir, &invocation) {
+        tracing::debug!("Could not record usage: {}", err);
+    }
+}
+
+fn print_usage_stats(enable: bool, disable: bool, clear: bool) -> Result<()> {
+    let dir = stats::dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    if enable || disable {
+        stats::set_enabled(&dir, enable)?;
+        println!("Usage recording {}.", if enable { "enabled" } else { "disabled" });
+    }
+    if clear {
+        stats::clear(&dir)?;
+        println!("Usage history cleared.");
+    }
+    if enable || disable || clear {
+        return Ok(());
+    }
+
+    println!("Disk usage:");
+    let lunu_cache = runtime_cache_dir().parent().map(Path::to_path_buf);
+    let builder_cache = dirs::cache_dir().map(|d| d.join("lunu-builder"));
+    let install_dir = dirs::home_dir().map(|h| h.join(".lunu"));
+    let package_cache = package_cache::dir();
+    for (label, path) in [("Lunu cache", lunu_cache), ("Builder cache", builder_cache), ("Package cache", package_cache), ("Install dir", install_dir)] {
+        if let Some(path) = path {
+            println!("  {:<14} {:>10}  {:?}", label, stats::format_size(stats::dir_size(&path)), path);
+        }
+    }
+    println!("  'lunu clean' clears the runtimes in the Lunu cache.");
+
+    if !stats::is_enabled(&dir) {
+        println!("\nUsage recording is off. Run 'lunu stats --enable' to keep a local history (never uploaded).");
+    }
+    let history = stats::load(&dir);
+    if history.is_empty() {
+        return Ok(());
+    }
+    println!("\nUsage ({} recorded runs, {} projects):", history.len(), stats::projects_seen(&history));
+    let mut summary: Vec<_> = stats::summarize(&history).into_iter().collect();
+    summary.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(&b.0)));
+    for (command, s) in summary {
+        let failed = if s.failures > 0 { format!(", {} failed", s.failures) } else { String::new() };
+        println!("  {:<12} {:>5} runs  avg {:>8}{}", command, s.count, format!("{} ms", s.avg_ms), failed);
+    }
+    Ok(())
+}
+
+fn print_timings() {
+    if timings::is_enabled() {
+        eprint!("{}", timings::report());
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
 
-    // Only init default logging if NOT bridge/dev
-    if !matches!(cli.command, Some(Commands::Dev)) {
+    // Only init default logging if NOT bridge/dev; local commands log nothing unless RUST_LOG asks
+    let local = cli.command.as_ref().is_some_and(Commands::is_local);
+    if !matches!(cli.command, Some(Commands::Dev { .. }) | Some(Commands::WorkerHost { .. })) && (!local || std::env::var_os("RUST_LOG").is_some()) {
          tracing_subscriber::fmt::init();
     }
 
@@ -1081,8 +2262,22 @@ async fn main() -> Result<()> {
     // Search up for .luaurc
     let root = find_root(&cwd).unwrap_or(cwd.clone());
     
-    // Don't print "Lunu Root" for bridge/dev command to keep stdout clean
-    if !matches!(cli.command, Some(Commands::Dev)) && cli.command.is_some() {
+    // Don't print "Lunu Root" for bridge/dev command or schema/JSON output to keep stdout clean
+    let quiet = matches!(
+        cli.command,
+        Some(Commands::Dev { .. })
+            | Some(Commands::WorkerHost { .. })
+            | Some(Commands::Config { action: ConfigAction::Schema { .. } })
+            | Some(Commands::Check { json: true, .. })
+            | Some(Commands::Typecheck { json: true, .. })
+            | Some(Commands::Audit { json: true, .. })
+            | Some(Commands::Provenance { json: true, .. })
+            | Some(Commands::Search { json: true, .. })
+            | Some(Commands::Verify { json: true })
+            | Some(Commands::Analyze { action: AnalyzeAction::Requires { json: true } })
+            | Some(Commands::Key { action: KeyAction::Show { reveal: true } })
+    );
+    if !quiet && cli.command.is_some() {
         println!("Lunu Root: {:?}", root);
     }
 
@@ -1091,78 +2286,303 @@ async fn main() -> Result<()> {
             // Check if we are installed
             if is_installed()? {
                  println!("Lunu is installed! Run 'lunu --help' to see available commands.");
+            } else if io::stdin().is_terminal() && stdin_is_interactive() {
+                 let plan = ask_setup_plan(&mut io::stdin().lock())?;
+                 run_setup_plan(&cwd, &plan).await?;
             } else {
-                 install_self().await?;
+                 install_self(true).await?;
             }
         },
-        Some(Commands::Init) => {
-            init_project(&cwd).await?;
+        Some(Commands::Init { runtime, name, template, yes, no_types }) => {
+            let options = InitOptions {
+                runtime: runtime.map(runtime_kind_from_target),
+                name,
+                template,
+                assume_yes: yes,
+                skip_types: no_types,
+            };
+            init_project(&cwd, &options).await?;
         },
         Some(Commands::Create { name }) => {
-            create_project(&cwd, &name).await?;
+            create_project(&cwd, &name, &InitOptions::default()).await?;
+        },
+        Some(Commands::Install { no_types, offline, frozen }) => {
+            install_from_config(&root, offline, frozen).await?;
+            let vendored = ProjectConfig::load(&project_config_path(&root)).await.is_ok_and(|cfg| cfg.project.vendored);
+            if !no_types && !offline && !vendored {
+                let runtime = resolve_runtime_for_root(&root).await?;
+                install_type_definitions(&root, runtime).await;
+            }
+        },
+        Some(Commands::Remove { lib, unused, dry_run }) => {
+            if unused {
+                remove_unused_dependencies(&root, dry_run).await?;
+            } else if let Some(lib) = lib {
+                remove_dependency(&root, &lib).await?;
+            }
+        },
+        Some(Commands::Prune { dry_run }) => {
+            prune_modules(&root, dry_run, false).await?;
+        },
+        Some(Commands::Vendor) => {
+            vendor_project(&root).await?;
+        },
+        Some(Commands::Update { lib, interactive, exclude, only, dry_run }) => {
+            update_dependencies(&root, lib.as_deref(), &UpdateFilter { interactive, exclude, only }, dry_run).await?;
+        },
+        Some(Commands::List { verbose }) => {
+            list_dependencies(&root, verbose).await?;
+        },
+        Some(Commands::Tree { duplicates }) => {
+            show_dependency_tree(&root, duplicates).await?;
+        },
+        Some(Commands::Info { lib, refresh }) => {
+            show_package_info(&root, &lib, refresh).await?;
+        },
+        Some(Commands::Provenance { dep, json }) => {
+            show_provenance(&root, &dep, json).await?;
+        },
+        Some(Commands::Package { action: Some(PackageAction::Verify { dir, public_key, ignore_missing }), .. }) => {
+            let dir = if dir.is_absolute() { dir } else { cwd.join(dir) };
+            verify_package(&root, &dir, public_key.as_deref(), ignore_missing).await?;
+        },
+        Some(Commands::Package { action: None, sign_key, include_all, platforms, smoke, smoke_script }) => {
+            if platforms.is_empty() {
+                package_project(&root, include_all).await?;
+            } else {
+                package_platforms(&root, include_all, &platforms).await?;
+            }
+            write_checksums(&root, &root.join("dist"), sign_key).await?;
+            let smoke_script = smoke_script.map(|path| if path.is_absolute() { path } else { cwd.join(path) });
+            smoke_test_package(&root, &platforms, smoke, smoke_script).await?;
+        },
+        Some(Commands::Release { tag, repo, platforms, notes, draft, prerelease, dry_run }) => {
+            let options = ReleaseOptions { tag, repo, platforms, notes, draft, prerelease, dry_run };
+            release_project(&root, &options).await?;
+        },
+        Some(Commands::Publish { repo, notes, dry_run }) => {
+            publish_library(&root, repo.as_deref(), notes.as_deref(), dry_run).await?;
+        },
+        Some(Commands::Mirror { dep, to }) => {
+            mirror_dependency(&root, &dep, &to).await?;
+        },
+        Some(Commands::Lock { action: LockAction::Merge { offline } }) => {
+            merge_lockfile(&root, offline).await?;
+        },
+        Some(Commands::Snapshot { action }) => match action {
+            SnapshotAction::Create { name, force } => create_snapshot(&root, &name, force).await?,
+            SnapshotAction::Restore { name, offline } => restore_snapshot(&root, &name, offline).await?,
+            SnapshotAction::List => list_snapshots(&root),
+        },
+        Some(Commands::Export { output }) => {
+            let output = output.map(|o| if o.is_absolute() { o } else { cwd.join(o) });
+            export_project(&root, &cwd, output).await?;
         },
-        Some(Commands::Install) => {
-            install_from_config(&root).await?;
+        Some(Commands::Import { bundle, dir }) => {
+            import_project(&cwd, &cwd.join(bundle), dir.map(|d| cwd.join(d))).await?;
         },
-        Some(Commands::Remove { lib }) => {
-            remove_dependency(&root, &lib).await?;
+        Some(Commands::Bundle { script, output }) => {
+            let output = output.map(|o| if o.is_absolute() { o } else { cwd.join(o) });
+            bundle_script(&root, &cwd.join(script), output).await?;
         },
-        Some(Commands::Update { lib }) => {
-            update_dependencies(&root, lib.as_deref()).await?;
+        Some(Commands::Config { action: ConfigAction::Schema { file } }) => {
+            let file = match file {
+                ConfigTarget::Lunu => ConfigFile::LunuToml,
+                ConfigTarget::Settings => ConfigFile::Settings,
+            };
+            print!("{}", file.schema());
+        },
+        Some(Commands::Config { action: ConfigAction::Validate }) => {
+            if !validate_config(&root)? {
+                print_timings();
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Audit { denylist, advisories, advisory_feed, json }) => {
+            let denylist = denylist.map(|d| if d.is_absolute() { d } else { cwd.join(d) });
+            let advisory_feed = if advisories {
+                let source = advisory_feed.or_else(advisories::default_source).ok_or_else(|| {
+                    anyhow::anyhow!("No advisory feed: pass --advisory-feed <file|url>, set LUNU_ADVISORY_FEED or create ~/.lunu/advisories.json")
+                })?;
+                let is_url = source.starts_with("https://") || source.starts_with("http://");
+                Some(if is_url || Path::new(&source).is_absolute() { source } else { cwd.join(source).to_string_lossy().into_owned() })
+            } else {
+                None
+            };
+            if !audit_dependencies(&root, denylist.as_deref(), advisory_feed.as_deref(), json).await? {
+                print_timings();
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Verify { json }) => {
+            if !verify_lock(&root, json).await? {
+                print_timings();
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Analyze { action: AnalyzeAction::Requires { json } }) => {
+            let report = analyze_requires(&root).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_require_report(&report);
+            }
+            if !report.unresolved.is_empty() {
+                print_timings();
+                std::process::exit(1);
+            }
+        },
+        Some(Commands::Key { action: KeyAction::Show { reveal } }) => {
+            match secret_store::load(&root)? {
+                Some(key) if reveal => println!("{}", key.key),
+                Some(key) => {
+                    println!("Bridge API key: {} (stored in {})", key.masked(), key.location);
+                    println!("Run 'lunu key show --reveal' to print the full key.");
+                }
+                None => println!("No bridge API key yet; one is created when the bridge first starts with auth enabled."),
+            }
         },
-        Some(Commands::List) => {
-            list_dependencies(&root).await?;
+        Some(Commands::Status) => {
+            let status = collect_status(&root).await?;
+            print_status(&status);
         },
-        Some(Commands::Package) => {
-            package_project(&root).await?;
+        Some(Commands::Check { json, update_baseline }) => {
+            check_environment(&root, json, update_baseline).await?;
         },
-        Some(Commands::Check) => {
-            check_environment(&root).await?;
+        Some(Commands::Typecheck { modules, strict, json }) => {
+            typecheck_project(&root, modules, strict, json).await?;
         },
-        Some(Commands::Dev) => {
+        Some(Commands::Dev { stop_workers, wait_ready, timeout }) => {
+            if wait_ready {
+                return wait_for_dev_server(&cwd, timeout).await;
+            }
+            if stop_workers {
+                let stopped = worker_pool::stop_all(&cwd);
+                if stopped.is_empty() {
+                    println!("No persistent workers running.");
+                } else {
+                    println!("Stopped persistent workers: {}", stopped.join(", "));
+                }
+                return Ok(());
+            }
             println!("Starting Lunu Dev Server...");
             bridge_server::run().await?;
         },
-        Some(Commands::Build { script, output, force, open, icon, open_cmd }) => {
+        Some(Commands::Daemon { listen }) => {
+            daemon::serve(root.clone(), &listen).await?;
+        },
+        Some(Commands::WorkerHost { module_dir, registry }) => {
+            worker_pool::host_worker(module_dir, registry).await?;
+        },
+        Some(Commands::Build { list: true, .. }) => {
+            print_build_history(&root)?;
+        },
+        Some(Commands::Build { diff: Some(refs), .. }) => {
+            let records = lunu_builder::history::list(&root)?;
+            let from = lunu_builder::history::find(&records, &refs[0])?;
+            let to = lunu_builder::history::find(&records, &refs[1])?;
+            println!("{}", lunu_builder::history::diff(from, to));
+        },
+        Some(Commands::Build { script: None, clear_cache: true, .. }) => {
+            clear_payload_cache(&root, &cwd)?;
+        },
+        Some(Commands::Build { script: Some(script), output, force, clear_cache, open, icon, open_cmd, no_verify, analyze, runtime_path, include_all, .. }) => {
+            if clear_cache {
+                clear_payload_cache(&root, &cwd)?;
+            }
+            set_runtime_path_override(&cwd, runtime_path)?;
             check_bridge_dependencies(&root).await;
             let runtime = resolve_runtime_for_root(&root).await?;
+            if no_verify {
+                println!("Skipping build gates (--no-verify).");
+            } else {
+                run_build_gates(&root, runtime).await?;
+            }
             match runtime {
                 RuntimeKind::Lute => {
                     build_with_lute(&root, &script, output, open, &icon, &open_cmd)?;
                 }
                 RuntimeKind::Lune => {
                     let runtime_path = runtime_cache_bin(RuntimeTarget::Lune);
-                    let final_path = if runtime_path.exists() {
+                    let final_path = if let Some(path) = runtime_path_override() {
+                        Some(path.to_path_buf())
+                    } else if runtime_path.exists() {
                         Some(runtime_path)
                     } else {
                         find_lune_executable(&root)
                     };
-                    lunu_builder::build_executable(&script, output, force, open, icon, open_cmd, final_path)?;
+                    let mut settings = load_build_settings(&root).await?;
+                    settings.analyze = analyze;
+                    if include_all {
+                        settings.exclude = ExcludeSet::none();
+                    }
+                    lunu_builder::build_executable(&script, output, force, open, icon, open_cmd, final_path, settings)?;
                 }
             }
         },
-        Some(Commands::Scaffold { name, template }) => {
-            scaffold_project(&cwd, &name, template).await?;
+        Some(Commands::Build { script: None, .. }) => unreachable!("clap requires a script unless --list, --diff or --clear-cache is given"),
+        Some(Commands::UpdateKey { path }) => {
+            let path = if path.is_absolute() { path } else { root.join(path) };
+            let public_key = lunu_builder::updates::generate_key(&path)?;
+            println!("Signing key written to {:?}. Keep it private and out of version control.", path);
+            println!("Add to lunu.toml:\n\n[build.updates]\npublic_key = \"{}\"\nsigning_key = \"{}\"", public_key, path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().replace('\\', "/"));
+        },
+        Some(Commands::Scaffold { name, template, from, set }) => match from {
+            Some(source) => scaffold_from_template(&cwd, &name, &source, &set)?,
+            None => scaffold_project(&cwd, &name, template).await?,
+        },
+        Some(Commands::Template { action: TemplateAction::Export { out, name, description, author, push } }) => {
+            export_template(&root, &out, name, description, author, push.as_deref()).await?;
         },
         Some(Commands::Module { name, lang }) => {
             create_module(&root, &name, lang).await?;
         },
-        Some(Commands::Profile { script, runs }) => {
+        Some(Commands::Profile { script, runs, runtime_path }) => {
+            set_runtime_path_override(&cwd, runtime_path)?;
             profile_script(&root, &script, runs)?;
         },
-        Some(Commands::Run { script, args }) => {
+        Some(Commands::Run { script, profile, runtime_path, runtime_profile, sandbox, keep_env, no_network, args }) => {
+            set_runtime_path_override(&cwd, runtime_path)?;
+            apply_runtime_profile(&root, runtime_profile.as_deref()).await?;
             let runtime = resolve_runtime_for_root(&root).await?;
             ensure_runtime_aliases(&root, runtime).await?;
             maybe_prompt_update(runtime_target_from_kind(runtime)).await?;
-            run_script(&root, &script, &args, runtime)?;
+            let mut command = script_command(&root, &script, &args, runtime)?;
+            let _sandbox = if sandbox { Some(sandbox::apply(&mut command, &keep_env, no_network)?) } else { None };
+            let status = match profile {
+                Some(RunProfile::Bridge) => monitor::run_with_bridge_monitor(&root, command).await?,
+                None => command
+                    .status()
+                    .with_context(|| format!("Failed to run {}", runtime_name(runtime_target_from_kind(runtime))))?,
+            };
+            if !status.success() {
+                return Err(anyhow::anyhow!("Script execution failed"));
+            }
         },
-        Some(Commands::Test { file }) => {
+        Some(Commands::Test { file, filter, runtime_path, runtime_profile, show_output }) => {
+            set_runtime_path_override(&cwd, runtime_path)?;
+            apply_runtime_profile(&root, runtime_profile.as_deref()).await?;
             let runtime = resolve_runtime_for_root(&root).await?;
-            run_tests(&root, file, runtime).await?;
+            run_tests(&root, file, filter.as_deref(), runtime, show_output).await?;
+        },
+        Some(Commands::Runtime { action: Some(RuntimeAction::Capabilities { runtime, json, refresh }), .. }) => {
+            let target = match runtime {
+                Some(target) => target,
+                None => runtime_target_from_kind(resolve_runtime_for_root(&root).await?),
+            };
+            let caps = runtime_capabilities(&root, target, refresh)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&caps)?);
+            } else {
+                println!("{} {} at {:?}", caps.runtime, caps.version.as_deref().unwrap_or("(unknown version)"), caps.binary);
+                let subcommands = if caps.subcommands.is_empty() { "unknown".to_string() } else { caps.subcommands.join(", ") };
+                println!("  subcommands: {}", subcommands);
+                println!("  std libraries: {}", caps.std_libraries.join(", "));
+            }
         },
-        Some(Commands::Runtime { runtime, update }) => {
+        Some(Commands::Runtime { runtime: Some(runtime), update, asset_override, .. }) => {
             if update {
-                update_runtime(runtime).await?;
+                update_runtime_with_notes(runtime, asset_override.as_deref()).await?;
             } else {
                 let path = runtime_cache_bin(runtime);
                 let meta = read_runtime_meta(runtime);
@@ -1175,10 +2595,11 @@ async fn main() -> Result<()> {
                 }
             }
         },
+        Some(Commands::Runtime { runtime: None, .. }) => unreachable!("clap requires a runtime without a subcommand"),
         Some(Commands::Runtimes { update }) => {
             if update {
-                update_runtime(RuntimeTarget::Lute).await?;
-                update_runtime(RuntimeTarget::Lune).await?;
+                update_runtime_with_notes(RuntimeTarget::Lute, None).await?;
+                update_runtime_with_notes(RuntimeTarget::Lune, None).await?;
             } else {
                 let lute_path = runtime_cache_bin(RuntimeTarget::Lute);
                 let lune_path = runtime_cache_bin(RuntimeTarget::Lune);
@@ -1200,147 +2621,192 @@ async fn main() -> Result<()> {
                 }
             }
         },
-        Some(Commands::Add { query, alias }) => {
+        Some(Commands::Add { query, git, alias, dev, yes, review, path }) => {
+            let from_git = git.is_some();
+            let (query, requested) = split_requested(git.as_deref().or(query.as_deref()).unwrap_or_default());
+            let is_registry = !from_git && (pesde::parse_package(&query).is_some() || wally::parse_package(&query).is_some());
+            if path.is_some() && is_registry {
+                return Err(anyhow::anyhow!("--path selects a folder of a git repository; registry packages are installed whole"));
+            }
+            let subdir = path.as_deref().map(package::normalize_subdir).transpose()?;
+            let options = AddOptions { alias: alias.clone(), requested: requested.clone(), subdir, dev, review };
+            if from_git {
+                let target = package_from_git_url(&query).ok_or_else(|| {
+                    anyhow::anyhow!("'{}' is not a git URL; expected e.g. https://gitlab.com/org/repo.git or git@gitlab.com:org/repo.git", query)
+                })?;
+                return add_git_dependency(&root, &target, options, Resolver::Url).await;
+            }
+            if let Some(package) = pesde::parse_package(&query) {
+                let spec = DependencySpec { pesde: Some(package), version: requested, ..Default::default() };
+                return add_registry_dependency(&root, spec, alias, dev, review).await;
+            }
+            if let Some(package) = wally::parse_package(&query) {
+                let wally = requested.map(|r| format!("{}@{}", package, r)).unwrap_or(package);
+                let spec = DependencySpec { wally: Some(wally), ..Default::default() };
+                return add_registry_dependency(&root, spec, alias, dev, review).await;
+            }
+            if let Some(target) = package_from_url(&query) {
+                return add_git_dependency(&root, &target, options, Resolver::Url).await;
+            }
             println!("Searching for '{}'...", query);
             
             // 1. Search
             let gh = GithubClient::new(None)?;
-            let results = gh.search_packages(&query).await?;
+            let results = gh.search_packages(&query, &SearchOptions::default()).await?;
             
             if results.is_empty() {
                 println!("No packages found.");
                 return Ok(());
             }
 
-            let target = &results[0];
-            println!("Found: {}/{} ({})", target.owner, target.name, target.url);
-
-            // 2. Install
-            let pm = PackageManager::new(root.clone());
-            let install_name = alias.unwrap_or(target.name.clone());
-            
-            let (path, checksum) = pm.install_package(&target.url, None, &install_name).await?;
-            println!("Installed to {:?} (Checksum: {})", path, checksum);
-
-            // 3. Compat
-            CompatibilityLayer::ensure_compat(&path).await?;
-
-            // 4. Update Config
-            let config_path = root.join(".luaurc");
-            let mut config = Luaurc::load(&config_path).await?;
-            
-            // Lunu specific mapping: mapping modules/name to alias
-            // Standard Lune alias format: "alias": "path/to/module"
-            // Relative to .luaurc
-            let rel_path = pathdiff::diff_paths(&path, &root).unwrap_or(path);
-            let rel_path_str = rel_path.to_string_lossy().replace("\\", "/") + "/"; // Add trailing slash for directory modules
-            
-            config.add_alias(&install_name, &rel_path_str);
-            config.save(&config_path).await?;
-            
-            println!("Updated .luaurc with alias '{}'", install_name);
-
-            let config_path = project_config_path(&root);
-            let runtime = resolve_runtime_for_root(&root).await?;
-            let build_cfg = Some(build_config_for(runtime, None));
-            let mut proj = load_or_init_project(&root, &config_path, runtime, build_cfg).await?;
-            let mut spec = DependencySpec::default();
-            spec.url = Some(target.url.clone());
-            spec.path = Some(rel_path_str.trim_end_matches('/').to_string());
-            proj.add_dependency(&install_name, spec);
-            proj.save(&config_path).await?;
-
-            let lock_path = lock_path(&root);
-            let mut lock = LockFile::load(&lock_path).await?;
-            lock.set(&install_name, LockEntry {
-                url: Some(target.url.clone()),
-                version: None,
-                path: Some(rel_path_str.trim_end_matches('/').to_string()),
-                checksum,
-                installed_at: current_timestamp(),
-            });
-            lock.save(&lock_path).await?;
+            let Some(index) = pick_search_result(&query, &results, yes)? else {
+                return Ok(());
+            };
+            let target = &results[index];
+            println!("Selected: {}/{} ({})", target.owner, target.name, target.url);
+            add_git_dependency(&root, target, options, Resolver::Search).await?;
+        },
+        Some(Commands::Search { query, sort, limit, json }) => {
+            let sort = match sort {
+                SearchOrder::Relevance => SearchSort::Relevance,
+                SearchOrder::Stars => SearchSort::Stars,
+                SearchOrder::Updated => SearchSort::Updated,
+            };
+            let results = GithubClient::new(None)?.search_packages(&query, &SearchOptions { sort, limit }).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+                return Ok(());
+            }
+            if results.is_empty() {
+                println!("No packages found.");
+                return Ok(());
+            }
+            if !(io::stdin().is_terminal() && stdin_is_interactive()) {
+                for (index, result) in results.iter().enumerate() {
+                    println!("{}", format_search_result(index, result));
+                }
+                return Ok(());
+            }
+            let Some(index) = ask_search_result(&mut io::stdin().lock(), &results, None)? else {
+                return Ok(());
+            };
+            add_git_dependency(&root, &results[index], AddOptions::default(), Resolver::Search).await?;
+        },
+        Some(Commands::Stats { enable, disable, clear }) => {
+            print_usage_stats(enable, disable, clear)?;
         },
         Some(Commands::Clean) => {
+            let (dirs, files) = housekeeping_targets();
+            let swept = housekeeping::sweep(&dirs, &files, housekeeping::CLEAN_GRACE);
+            if swept.files > 0 {
+                println!("Removed {} leftover update file(s) ({}).", swept.files, stats::format_size(swept.bytes));
+            }
             let cache_dir = runtime_cache_dir();
+            let mut reclaimed = swept.bytes;
             if cache_dir.exists() {
                 println!("Cleaning cache at {:?}...", cache_dir);
+                let size = stats::dir_size(&cache_dir);
                 async_fs::remove_dir_all(&cache_dir).await?;
+                reclaimed += size;
                 println!("Cache cleaned.");
             } else {
                 println!("Cache is already empty.");
             }
+            println!("Reclaimed {}.", stats::format_size(reclaimed));
         },
-        Some(Commands::Upgrade) => {
-            self_update().await?;
+        Some(Commands::Upgrade { channel, force, asset_override }) => {
+            self_update(channel, force, asset_override.as_deref()).await?;
         },
         Some(Commands::Uninstall) => {
             self_uninstall().await?;
         }
+        Some(Commands::MakeInstaller { out, offline, runtimes, templates }) => {
+            make_installer(&cwd.join(out), offline, &runtimes, &templates).await?;
+        }
     }
 
     Ok(())
 }
 
-async fn self_update() -> Result<()> {
-    println!("Checking for updates...");
-    let client = reqwest::Client::new();
-    let resp = client.get("https://api.github.com/repos/tlipe/Lunu/releases/latest")
-        .header("User-Agent", "Lunu-CLI")
-        .send()
-        .await?
-        .json::<Value>()
-        .await?;
+fn parse_release_version(tag: &str) -> Option<semver::Version> {
+    let trimmed = tag.trim().trim_start_matches(['v', 'V']);
+    semver::Version::parse(trimmed).ok()
+}
+
+fn pick_upgrade_release(releases: Vec<GithubRelease>, channel: UpgradeChannel) -> Option<(GithubRelease, semver::Version)> {
+    releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter(|r| channel == UpgradeChannel::Beta || !r.prerelease)
+        .filter_map(|r| {
+            let version = parse_release_version(&r.tag_name)?;
+            if channel == UpgradeChannel::Stable && !version.pre.is_empty() {
+                return None;
+            }
+            Some((r, version))
+        })
+        .max_by(|a, b| a.1.cmp(&b.1))
+}
 
-    let latest_tag = resp["tag_name"].as_str().ok_or(anyhow::anyhow!("Failed to parse release tag"))?;
-    let current_version = env!("CARGO_PKG_VERSION");
+async fn self_update(channel: UpgradeChannel, force: bool, asset_override: Option<&str>) -> Result<()> {
+    println!("Checking for updates...");
+    let client = http::client()?;
+    let releases_url = format!("https://api.github.com/repos/{}/releases", LUNU_REPO);
+    let releases: Vec<GithubRelease> = http::get_json(&client, &releases_url).await?;
+
+    let (release, latest_version) = pick_upgrade_release(releases, channel)
+        .ok_or_else(|| anyhow::anyhow!("No releases found on the {} channel", upgrade_channel_name(channel)))?;
+    let latest_tag = release.tag_name.clone();
+    let current_version = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
     let current_tag = format!("v{}", current_version);
 
-    if latest_tag == current_tag {
+    if latest_version == current_version {
         println!("Lunu is already up to date ({})", current_tag);
         return Ok(());
     }
+    if latest_version < current_version && !force {
+        println!(
+            "Current version {} is newer than the latest {} release {}. Use --force to downgrade.",
+            current_tag,
+            upgrade_channel_name(channel),
+            latest_tag
+        );
+        return Ok(());
+    }
 
     println!("New version available: {} (Current: {})", latest_tag, current_tag);
     println!("Updating...");
 
     // Find asset
-    let assets = resp["assets"].as_array().ok_or(anyhow::anyhow!("No assets found"))?;
-    let mut candidates: Vec<(String, String)> = assets
-        .iter()
-        .filter_map(|a| {
-            let name = a["name"].as_str()?.to_string();
-            let url = a["browser_download_url"].as_str()?.to_string();
-            let name_lower = name.to_lowercase();
-            if !name_lower.contains("lunu") || !asset_extension_supported(&name_lower) {
-                return None;
-            }
-            Some((name, url))
-        })
-        .collect();
-    if candidates.is_empty() {
-        return Err(anyhow::anyhow!("No compatible assets found"));
-    }
-    candidates.sort_by_key(|(name, _)| name.to_lowercase());
-    let expected = lunu_bin_filename();
-    let mut picked = candidates
-        .iter()
-        .find(|(name, _)| name.eq_ignore_ascii_case(&expected))
-        .cloned();
-    if picked.is_none() {
-        for require_arch in [true, false] {
-            if let Some(item) = candidates.iter().find(|(name, _)| {
-                let n = name.to_lowercase();
-                asset_matches_platform(&n, require_arch)
-            }) {
-                picked = Some(item.clone());
-                break;
-            }
-        }
-    }
-    let (asset_name, download_url) = picked.unwrap_or_else(|| candidates[0].clone());
+    let (asset_name, download_url) = if let Some(wanted) = asset_override {
+        let asset = find_asset_override(&release.assets, wanted)?;
+        (asset.name.clone(), asset.browser_download_url.clone())
+    } else {
+        let mut candidates: Vec<(String, String)> = release
+            .assets
+            .iter()
+            .filter_map(|a| {
+                let name_lower = a.name.to_lowercase();
+                if !name_lower.contains("lunu") || !asset_extension_supported(&name_lower, std::env::consts::OS) {
+                    return None;
+                }
+                Some((a.name.clone(), a.browser_download_url.clone()))
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err(anyhow::anyhow!("No compatible assets found. Use --asset-override <name> to pick one manually."));
+        }
+        candidates.sort_by_key(|(name, _)| name.to_lowercase());
+        let expected = lunu_bin_filename();
+        let picked = candidates
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&expected))
+            .or_else(|| pick_platform_asset(&candidates, &HostPlatform::current(), |(name, _)| name))
+            .cloned();
+        picked.unwrap_or_else(|| candidates[0].clone())
+    };
 
-    let bytes = client.get(&download_url).send().await?.bytes().await?;
+    let bytes = http::get_bytes(&client, &download_url).await?;
     
     let current_exe = std::env::current_exe()?;
     let old_exe = old_exe_path(&current_exe);
@@ -1366,6 +2832,7 @@ async fn self_update() -> Result<()> {
     ensure_executable(&current_exe)?;
     
     println!("Updated successfully to {}!", latest_tag);
+    print_release_notes(&format!("Lunu {}", latest_tag), release.body.as_deref(), release.html_url.as_deref());
     Ok(())
 }
 
@@ -1427,7 +2894,32 @@ async fn self_uninstall() -> Result<()> {
         println!("Removed .lunu directory.");
     }
 
+    let mut left = Vec::new();
+    #[cfg(not(windows))]
+    left.extend(remove_from_path(&home_dir, &install_dir.join("bin")));
+    let builder_cache = dirs::cache_dir().map(|d| d.join("lunu-builder"));
+    let caches = [runtime_cache_dir().parent().map(Path::to_path_buf), builder_cache];
+    for cache in caches.into_iter().flatten().filter(|c| c.exists()) {
+        match async_fs::remove_dir_all(&cache).await {
+            Ok(()) => println!("Removed cache {:?}.", cache),
+            Err(err) => left.push(format!("{:?}: cache could not be removed ({})", cache, err)),
+        }
+    }
+    // Data of apps built with lunu (extracted payloads, crash reports) belongs to those apps
+    if let Some(data) = dirs::data_local_dir().map(|d| d.join("lunu")).filter(|d| d.exists()) {
+        left.push(format!("{:?}: data and crash reports of apps built with lunu", data));
+    }
+    if std::env::var("PATH").map(|p| p.contains(".lunu")).unwrap_or(false) {
+        left.push("PATH of this shell still lists ~/.lunu/bin until it is restarted".to_string());
+    }
+
     println!("Lunu uninstalled successfully.");
+    if !left.is_empty() {
+        println!("Left behind (remove manually if no longer needed):");
+        for item in left {
+            println!("  - {}", item);
+        }
+    }
     Ok(())
 }
 
@@ -1446,6 +2938,22 @@ fn current_timestamp() -> u64 {
         .as_secs()
 }
 
+/// `YYYY-MM-DD HH:MM:SS UTC` for a Unix timestamp.
+fn format_utc(secs: u64) -> String {
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
 fn resolve_lunu_root(root: &Path) -> PathBuf {
     if root.join("config").join("settings.json").exists() {
         return root.to_path_buf();
@@ -1475,7 +2983,7 @@ fn is_installed() -> Result<bool> {
     Ok(current_exe.starts_with(&install_dir))
 }
 
-async fn install_self() -> Result<()> {
+async fn install_self(pause: bool) -> Result<()> {
     let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
     let install_dir = home_dir.join(".lunu").join("bin");
 
@@ -1499,14 +3007,20 @@ async fn install_self() -> Result<()> {
     println!("  Copying {}...", lunu_bin_filename());
     async_fs::copy(&current_exe, &target_lunu).await?;
     ensure_executable(&target_lunu)?;
+    if let Some(payload) = offline_installer::find(&current_exe) {
+        install_offline_payload(&payload, &home_dir)?;
+    }
 
     // 3. Setup PATH
     println!("Setting up PATH...");
     setup_path(&install_dir)?;
 
-    println!("\nInstallation Successful! 🎉");
+    println!("\nInstallation Successful!{}", console::emoji(" 🎉"));
     println!("Please restart your terminal (or VS Code) for changes to take effect.");
     println!("Try running: lunu --help");
+    if !pause {
+        return Ok(());
+    }
 
     // Pause before exit
     print!("\nPress Enter to exit...");
@@ -1516,6 +3030,137 @@ async fn install_self() -> Result<()> {
     Ok(())
 }
 
+/// Installs the runtimes and templates an offline installer carries (see `offline_installer`).
+fn install_offline_payload(payload: &Path, home_dir: &Path) -> Result<()> {
+    let manifest = offline_installer::Manifest::load(payload)?;
+    let platform = HostPlatform::current();
+    if manifest.platform != platform.label() {
+        return Err(anyhow::anyhow!("This installer bundle is for {}, but this machine is {}", manifest.platform, platform.label()));
+    }
+    println!("Installing bundled runtimes...");
+    let mut defaults = Vec::new();
+    for entry in &manifest.runtimes {
+        let target = RuntimeTarget::from_str(&entry.name, true)
+            .map_err(|_| anyhow::anyhow!("Unknown runtime '{}' in the installer payload", entry.name))?;
+        let bytes = manifest.runtime_bytes(payload, entry)?;
+        let pinned = platform_runtime_cache_path(target, &platform, &entry.version);
+        fs::create_dir_all(pinned.parent().unwrap_or(payload))?;
+        install_runtime_binary(&pinned, &bytes)?;
+        // The first bundled version of each runtime becomes the one projects run on by default
+        if !defaults.contains(&target) {
+            defaults.push(target);
+            fs::create_dir_all(runtime_cache_dir())?;
+            install_runtime_binary(&runtime_cache_bin(target), &bytes)?;
+            write_runtime_meta(target, &RuntimeMeta { version: entry.version.clone(), source: "offline".to_string() })?;
+        }
+        println!("  {} {}", entry.name, entry.version);
+    }
+    if !manifest.templates.is_empty() {
+        println!("Installing bundled templates...");
+    }
+    let templates_dir = offline_installer::templates_dir(home_dir);
+    for name in &manifest.templates {
+        let target = templates_dir.join(name);
+        if target.exists() {
+            fs::remove_dir_all(&target).with_context(|| format!("Failed to replace {:?}", target))?;
+        }
+        copy_dir_filtered(&offline_installer::Manifest::template_dir(payload, name), &target, &ExcludeSet::new(false, &[], &[]))?;
+        println!("  {} (lunu scaffold <name> --from {})", name, name);
+    }
+    Ok(())
+}
+
+/// `lunu make-installer [--offline] <out>`: a copy of this binary, which installs itself when run
+/// without arguments, plus with `--offline` the runtimes and templates for it to install.
+async fn make_installer(out: &Path, offline: bool, runtimes: &[String], templates: &[String]) -> Result<()> {
+    fs::create_dir_all(out).with_context(|| format!("Failed to create {:?}", out))?;
+    let installer = out.join(lunu_bin_filename());
+    fs::copy(std::env::current_exe()?, &installer).with_context(|| format!("Failed to write {:?}", installer))?;
+    ensure_executable(&installer)?;
+    let payload = out.join(offline_installer::DIR);
+    // Built beside the final payload and moved into place at the end, so a failure leaves none
+    let staging = out.join(format!(".{}.partial", offline_installer::DIR));
+    for dir in [&payload, &staging] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).with_context(|| format!("Failed to replace {:?}", dir))?;
+        }
+    }
+    if !offline {
+        println!("Installer written to {:?}; it downloads runtimes when they are first needed.", installer);
+        return Ok(());
+    }
+
+    let platform = HostPlatform::current();
+    let mut manifest = offline_installer::Manifest::new(env!("CARGO_PKG_VERSION"), &platform.label(), current_timestamp());
+    let defaults = ["lune".to_string(), "lute".to_string()];
+    for spec in if runtimes.is_empty() { &defaults[..] } else { runtimes } {
+        let (name, pinned) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec.as_str(), None),
+        };
+        let target = RuntimeTarget::from_str(name, true)
+            .map_err(|_| anyhow::anyhow!("Unknown runtime '{}' (expected lune or lute)", name))?;
+        let (version, binary) = match pinned {
+            Some(version) => {
+                let cached = platform_runtime_cache_path(target, &platform, version);
+                let binary = if cached.exists() {
+                    fs::read(&cached)?
+                } else {
+                    let release = fetch_release_by_tag(target, version)
+                        .await
+                        .with_context(|| format!("{} release '{}' not found", runtime_name(target), version))?;
+                    download_platform_runtime(target, &release, &platform).await?
+                };
+                (version.to_string(), binary)
+            }
+            None => {
+                let path = runtime_cache_bin(target);
+                if !path.exists() {
+                    if let Err(err) = update_runtime(target, None).await {
+                        if target != RuntimeTarget::Lute || ensure_embedded_lute().is_none() {
+                            return Err(err.context(format!("Failed to get {} for the installer", runtime_name(target))));
+                        }
+                    }
+                }
+                let version = read_runtime_meta(target).map(|m| m.version).ok_or_else(|| anyhow::anyhow!(
+                    "No version is recorded for the cached {}; pass --runtime {}@<version> to pin one",
+                    runtime_name(target),
+                    runtime_name(target)
+                ))?;
+                (version, fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?)
+            }
+        };
+        manifest.add_runtime(&staging, runtime_name(target), &version, &runtime_bin_filename(target), &binary)?;
+    }
+    for source in templates {
+        let scratch = tempfile::tempdir()?;
+        let dir = fetch_template(source, scratch.path())?;
+        let name = template::TemplateManifest::load(&dir)?.template.name;
+        let valid = !name.is_empty() && !name.starts_with('.') && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(anyhow::anyhow!("Template '{}' from {} cannot be bundled: use letters, digits, '-', '_' and '.' in its name", name, source));
+        }
+        if manifest.templates.contains(&name) {
+            return Err(anyhow::anyhow!("Two bundled templates are named '{}'", name));
+        }
+        copy_dir_filtered(&dir, &offline_installer::Manifest::template_dir(&staging, &name), &ExcludeSet::new(false, &[".git".to_string()], &[]))?;
+        manifest.templates.push(name);
+    }
+    manifest.save(&staging)?;
+    fs::rename(&staging, &payload).with_context(|| format!("Failed to write {:?}", payload))?;
+
+    println!("Offline installer written to {:?} for {}:", out, manifest.platform);
+    println!("  lunu {}", manifest.lunu);
+    for runtime in &manifest.runtimes {
+        println!("  {} {}", runtime.name, runtime.version);
+    }
+    for name in &manifest.templates {
+        println!("  template {}", name);
+    }
+    println!("Copy the directory to the target machine and run {} there to install.", lunu_bin_filename());
+    Ok(())
+}
+
 #[cfg(windows)]
 fn setup_path(bin_dir: &Path) -> Result<()> {
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
@@ -1562,26 +3207,195 @@ fn setup_path(bin_dir: &Path) -> Result<()> {
 }
 
 #[cfg(not(windows))]
-fn setup_path(_bin_dir: &Path) -> Result<()> {
-    println!("Manual PATH configuration required for non-Windows systems.");
+fn setup_path(bin_dir: &Path) -> Result<()> {
+    let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    for rc in shell_path::rc_files(&home_dir) {
+        let content = fs::read_to_string(&rc).unwrap_or_default();
+        match shell_path::add_block(&content, bin_dir) {
+            Some(updated) => {
+                fs::write(&rc, updated).with_context(|| format!("Failed to update {:?}", rc))?;
+                println!("Added to PATH in {:?}.", rc);
+            }
+            None => println!("Already in PATH in {:?}.", rc),
+        }
+    }
+    // ~/.local/bin is usually on PATH already, so the link works before the shell restarts
+    let local_bin = home_dir.join(".local").join("bin");
+    let link = local_bin.join(lunu_bin_filename());
+    if local_bin.is_dir() && fs::symlink_metadata(&link).is_err() {
+        std::os::unix::fs::symlink(bin_dir.join(lunu_bin_filename()), &link)?;
+        println!("Linked {:?}.", link);
+    }
     Ok(())
 }
 
-fn scan_modules(root: &Path) -> BTreeMap<String, DependencySpec> {
-    let mut deps = BTreeMap::new();
-    let modules_dir = root.join("modules");
-    if let Ok(read_dir) = std::fs::read_dir(modules_dir) {
+/// Takes the installer's block out of shell startup files and removes symlinks into `bin_dir`;
+/// returns what could not be cleaned up automatically.
+#[cfg(not(windows))]
+fn remove_from_path(home_dir: &Path, bin_dir: &Path) -> Vec<String> {
+    let mut left = Vec::new();
+    for name in [".profile", ".bashrc", ".zshrc", ".bash_profile", ".zprofile", ".config/fish/config.fish"] {
+        let rc = home_dir.join(name);
+        let Ok(content) = fs::read_to_string(&rc) else {
+            continue;
+        };
+        if let Some(cleaned) = shell_path::remove_block(&content) {
+            match fs::write(&rc, cleaned) {
+                Ok(()) => println!("Removed PATH setup from {:?}.", rc),
+                Err(err) => left.push(format!("{:?}: could not remove the lunu PATH block ({})", rc, err)),
+            }
+        }
+        for line in shell_path::leftover_lines(&content, ".lunu") {
+            left.push(format!("{:?}: {}", rc, line));
+        }
+    }
+    for dir in [home_dir.join(".local").join("bin"), PathBuf::from("/usr/local/bin")] {
+        let link = dir.join(lunu_bin_filename());
+        let Ok(target) = fs::read_link(&link) else {
+            continue;
+        };
+        if !target.starts_with(bin_dir) {
+            continue;
+        }
+        match fs::remove_file(&link) {
+            Ok(()) => println!("Removed symlink {:?}.", link),
+            Err(err) => left.push(format!("{:?}: symlink to {:?} could not be removed ({})", link, target, err)),
+        }
+    }
+    left
+}
+
+async fn analyze_requires(root: &Path) -> Result<requires::RequireReport> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let luaurc = Luaurc::load(&root.join(".luaurc")).await?;
+    let declared = cfg.all_dependencies().into_keys().collect();
+    Ok(requires::analyze(root, &luaurc.aliases, &module_dirs(root), &declared))
+}
+
+/// `30s`, `500ms`, `2m` or a bare number of seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("invalid duration '{}' (e.g. 30s, 500ms, 2m)", value))?;
+    match unit.trim() {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        _ => Err(format!("invalid duration '{}' (e.g. 30s, 500ms, 2m)", value)),
+    }
+}
+
+/// `lunu dev --wait-ready`: starts `lunu dev` in the background (output in .lunu/dev.log) and
+/// returns once its ready event is in .lunu/daemon.json. Fails when it reports an error, exits or
+/// is not ready within `timeout`; it is stopped in the last case.
+async fn wait_for_dev_server(cwd: &Path, timeout: Duration) -> Result<()> {
+    let log_path = cwd.join(".lunu").join("dev.log");
+    fs::create_dir_all(cwd.join(".lunu"))?;
+    let log = fs::File::create(&log_path).with_context(|| format!("Failed to create {:?}", log_path))?;
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.arg("dev")
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    worker_pool::detach(&mut cmd);
+    let mut child = cmd.spawn().context("Failed to start lunu dev")?;
+
+    let started = std::time::Instant::now();
+    loop {
+        if let Some(event) = bridge_server::read_ready(cwd).filter(|e| e.pid == child.id()) {
+            println!("{}", serde_json::to_string(&event)?);
+            return match event.error {
+                Some(error) => Err(anyhow::anyhow!("lunu dev failed to start: {}", error)),
+                None => Ok(()),
+            };
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(anyhow::anyhow!("lunu dev exited ({}) before it was ready; see {:?}", status, log_path));
+        }
+        if started.elapsed() >= timeout {
+            worker_pool::kill_process(child.id());
+            let _ = child.wait();
+            return Err(anyhow::anyhow!("lunu dev was not ready within {:?}; see {:?}", timeout, log_path));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+async fn bundle_script(root: &Path, script: &Path, output: Option<PathBuf>) -> Result<()> {
+    let luaurc = Luaurc::load(&root.join(".luaurc")).await?;
+    let bundled = script_bundle::bundle(root, &luaurc.aliases, script)?;
+    let output = output.unwrap_or_else(|| {
+        let stem = script.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "main".to_string());
+        root.join("dist").join(format!("{}.bundle.luau", stem))
+    });
+    if let Some(parent) = output.parent() {
+        async_fs::create_dir_all(parent).await?;
+    }
+    async_fs::write(&output, &bundled.source).await.with_context(|| format!("Failed to write {:?}", output))?;
+    println!("Bundled {} module(s) into {:?}", bundled.modules.len(), output);
+    Ok(())
+}
+
+fn print_require_report(report: &requires::RequireReport) {
+    println!("Scanned {} Luau files.", report.files_scanned);
+    if report.is_clean() {
+        println!("All requires resolve and every dependency is used.");
+        return;
+    }
+    if !report.unresolved.is_empty() {
+        println!("Unresolved requires:");
+        for issue in &report.unresolved {
+            println!("  {}:{}  require(\"{}\")", issue.file, issue.line, issue.require);
+        }
+    }
+    if !report.unused.is_empty() {
+        println!("Declared in lunu.toml but never required: {}", report.unused.join(", "));
+    }
+    if !report.undeclared.is_empty() {
+        println!("Required but not declared in lunu.toml: {}", report.undeclared.join(", "));
+    }
+}
+
+/// Every module directory under `modules/`, keyed by name. `@scope` directories are namespaces:
+/// `modules/@scope/name` is the module `@scope/name`.
+fn module_dirs(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut modules = Vec::new();
+    let mut pending = vec![(String::new(), root.join("modules"))];
+    while let Some((prefix, dir)) = pending.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
         for entry in read_dir.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                    let mut spec = DependencySpec::default();
-                    spec.path = Some(format!("modules/{}", name));
-                    deps.insert(name.to_string(), spec);
-                }
+            let Some(name) = path.file_name().and_then(|s| s.to_str()).map(|n| format!("{}{}", prefix, n)) else {
+                continue;
+            };
+            if !path.is_dir() {
+                continue;
+            }
+            if name.rsplit('/').next().is_some_and(|n| n.starts_with('@')) {
+                pending.push((format!("{}/", name), path));
+            } else {
+                modules.push((name, path));
             }
         }
     }
+    modules.sort();
+    modules
+}
+
+fn scan_modules(root: &Path) -> BTreeMap<String, DependencySpec> {
+    let mut deps = BTreeMap::new();
+    for (name, _) in module_dirs(root) {
+        let spec = DependencySpec { path: Some(format!("modules/{}", name)), ..Default::default() };
+        deps.insert(name, spec);
+    }
     deps
 }
 
@@ -1610,9 +3424,9 @@ async fn load_or_init_project(
     Ok(cfg)
 }
 
-fn main_template(runtime: RuntimeKind) -> String {
-    match runtime {
-        RuntimeKind::Lute => [
+fn main_template(runtime: RuntimeKind, template: TemplateKind) -> String {
+    match (runtime, template) {
+        (RuntimeKind::Lute, TemplateKind::App) => [
             "local process = require(\"@lute/process\")",
             "local path = require(\"@std/path\")",
             "",
@@ -1622,11 +3436,36 @@ fn main_template(runtime: RuntimeKind) -> String {
             "",
         ]
         .join("\n"),
-        RuntimeKind::Lune => "print(\"Hello from Lunu\")\n".to_string(),
+        (RuntimeKind::Lune, TemplateKind::App) => "print(\"Hello from Lunu\")\n".to_string(),
+        // Plain Luau, so the same game and service run on either runtime
+        (_, TemplateKind::Game) => [
+            "local frame = 0",
+            "while frame < 3 do",
+            "    print(\"tick\", frame)",
+            "    frame = frame + 1",
+            "end",
+            "",
+        ]
+        .join("\n"),
+        (_, TemplateKind::Service) => [
+            "local function handle(request)",
+            "    if request == \"ping\" then",
+            "        return \"pong\"",
+            "    end",
+            "    return `unknown request: {request}`",
+            "end",
+            "",
+            "print(\"Service started\")",
+            "for _, request in { \"ping\", \"status\" } do",
+            "    print(request, \"->\", handle(request))",
+            "end",
+            "",
+        ]
+        .join("\n"),
     }
 }
 
-async fn ensure_project_files(root: &Path, runtime: RuntimeKind) -> Result<()> {
+async fn ensure_project_files(root: &Path, runtime: RuntimeKind, template: TemplateKind) -> Result<()> {
     let src_dir = root.join("src");
     let modules_dir = root.join("modules");
     let config_dir = root.join("config");
@@ -1653,7 +3492,7 @@ async fn ensure_project_files(root: &Path, runtime: RuntimeKind) -> Result<()> {
 
     let main_path = src_dir.join("main.luau");
     if !main_path.exists() {
-        async_fs::write(&main_path, main_template(runtime)).await?;
+        async_fs::write(&main_path, main_template(runtime, template)).await?;
     }
 
     let settings_path = config_dir.join("settings.json");
@@ -1681,53 +3520,42 @@ async fn ensure_project_files(root: &Path, runtime: RuntimeKind) -> Result<()> {
     Ok(())
 }
 
+/// Rebuilds the runtime and dependency aliases of .luaurc. Two of them claiming the same alias for
+/// different directories is an error naming both, rather than the later one silently winning.
 async fn update_luaurc(root: &Path, deps: &BTreeMap<String, DependencySpec>, runtime: RuntimeKind) -> Result<()> {
+    write_luaurc(root, deps, runtime, true).await
+}
+
+/// `update_luaurc`; without `fetch_sources`, Lute aliases are only written when the runtime's
+/// libraries are already on disk, so the command stays offline.
+async fn write_luaurc(root: &Path, deps: &BTreeMap<String, DependencySpec>, runtime: RuntimeKind, fetch_sources: bool) -> Result<()> {
     let config_path = root.join(".luaurc");
     let mut luaurc = Luaurc::load(&config_path).await?;
+    let mut claims = AliasClaims::default();
     let path_to_alias = |path: &Path| -> String {
         path.to_string_lossy().replace("\\", "/") + "/"
     };
     if runtime == RuntimeKind::Lute {
         luaurc.remove_alias("@lute");
         luaurc.remove_alias("@std");
-        let mut added = false;
-        let runtime_root = runtime_lib_root(RuntimeTarget::Lute);
-        let lute_dir = runtime_root.join("lute");
-        let std_dir = runtime_root.join("std");
-        let lute_std_libs = runtime_root.join("lute").join("std").join("libs");
-        if lute_std_libs.exists() {
-            luaurc.add_alias("lute", &path_to_alias(&lute_std_libs));
-            added = true;
-        } else if lute_dir.exists() {
-            luaurc.add_alias("lute", &path_to_alias(&lute_dir));
-            added = true;
-        }
-        if std_dir.exists() {
-            luaurc.add_alias("std", &path_to_alias(&std_dir));
-            added = true;
-        } else if lute_std_libs.exists() {
-            luaurc.add_alias("std", &path_to_alias(&lute_std_libs));
-            added = true;
-        }
-        if !added {
-            let source_root = ensure_lute_sources().await?;
-            let lute_src = source_root.join("lute");
-            let std_src = source_root.join("std");
-            let source_lute_std_libs = source_root.join("lute").join("std").join("libs");
-            if source_lute_std_libs.exists() {
-                luaurc.add_alias("lute", &path_to_alias(&source_lute_std_libs));
-                luaurc.add_alias("std", &path_to_alias(&source_lute_std_libs));
-            } else {
-                if lute_src.exists() {
-                    luaurc.add_alias("lute", &path_to_alias(&lute_src));
-                }
-                if std_src.exists() {
-                    luaurc.add_alias("std", &path_to_alias(&std_src));
-                }
+        // Libraries copied by `lunu vendor` win over the runtime's own
+        let vendored = |alias: &str| root.join(vendor::runtime_path(alias)).is_dir().then(|| vendor::runtime_path(alias) + "/");
+        let (lute, std) = match (vendored("lute"), vendored("std")) {
+            (None, None) => {
+                let (lute, std) = lute_library_dirs(fetch_sources).await?;
+                (lute.map(|d| path_to_alias(&d)), std.map(|d| path_to_alias(&d)))
             }
+            dirs => dirs,
+        };
+        if let Some(lute) = lute {
+            claims.claim("lute", &lute, "the Lute runtime")?;
+        }
+        if let Some(std) = std {
+            claims.claim("std", &std, "the Lute runtime")?;
         }
     }
-    if runtime == RuntimeKind::Lune {
+    // A project that vendors the Lunu library as its `lunu` dependency requires it from there
+    if runtime == RuntimeKind::Lune && !deps.contains_key("lunu") {
         let lunu_alias = if root.join("Lunu").exists() {
             "Lunu/".to_string()
         } else if root.parent().map(|p| p.join("Lunu").exists()).unwrap_or(false) {
@@ -1735,31 +3563,83 @@ async fn update_luaurc(root: &Path, deps: &BTreeMap<String, DependencySpec>, run
         } else {
             "Lunu/".to_string()
         };
-        luaurc.add_alias("lunu", &lunu_alias);
+        claims.claim("lunu", &lunu_alias, "the Lune runtime")?;
     }
     for (name, spec) in deps {
         if let Some(path) = &spec.path {
-            let rel_path = path.trim_start_matches("./");
-            let rel_path_str = rel_path.replace("\\", "/") + "/";
-            luaurc.add_alias(name, &rel_path_str);
+            let mut rel_path = path.trim_start_matches("./").replace("\\", "/");
+            // Path dependencies outside the project are vendored like downloaded ones
+            if rel_path.starts_with("../") && root.join(vendor::dependency_path(name)).is_dir() {
+                rel_path = vendor::dependency_path(name);
+            }
+            // `@scope/name` is required through an alias for the scope directory
+            let source = format!("dependency '{}'", name);
+            match (name.split_once('/'), rel_path.rsplit_once('/')) {
+                (Some((scope, _)), Some((scope_dir, _))) => claims.claim(scope.trim_start_matches('@'), &format!("{}/", scope_dir), &source)?,
+                _ => claims.claim(name, &(rel_path + "/"), &source)?,
+            }
         }
     }
+    claims.apply(&mut luaurc);
     luaurc.save(&config_path).await?;
     Ok(())
 }
 
+/// Directories behind the `@lute` and `@std` aliases of Lute projects: the runtime's libraries,
+/// else the Lute sources (downloaded when `fetch_sources`).
+async fn lute_library_dirs(fetch_sources: bool) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    let runtime_root = runtime_lib_root(RuntimeTarget::Lute);
+    let lute_dir = runtime_root.join("lute");
+    let std_dir = runtime_root.join("std");
+    let lute_std_libs = lute_dir.join("std").join("libs");
+    let lute = [&lute_std_libs, &lute_dir].into_iter().find(|d| d.exists()).cloned();
+    let std = [&std_dir, &lute_std_libs].into_iter().find(|d| d.exists()).cloned();
+    if lute.is_some() || std.is_some() || !fetch_sources {
+        return Ok((lute, std));
+    }
+    let source_root = ensure_lute_sources().await?;
+    let source_lute_std_libs = source_root.join("lute").join("std").join("libs");
+    if source_lute_std_libs.exists() {
+        return Ok((Some(source_lute_std_libs.clone()), Some(source_lute_std_libs)));
+    }
+    let existing = |dir: PathBuf| dir.exists().then_some(dir);
+    Ok((existing(source_root.join("lute")), existing(source_root.join("std"))))
+}
+
+/// Fails before `lunu add` installs anything when `alias` already points somewhere other than
+/// `path` in .luaurc, naming what owns it.
+async fn ensure_alias_free(root: &Path, alias: &str, path: &str) -> Result<()> {
+    let luaurc = Luaurc::load(&root.join(".luaurc")).await?;
+    let Some(existing) = luaurc.aliases.get(alias) else {
+        return Ok(());
+    };
+    let config_path = project_config_path(root);
+    let deps = if config_path.exists() { ProjectConfig::load(&config_path).await?.all_dependencies() } else { BTreeMap::new() };
+    let owner = deps
+        .iter()
+        .find(|(_, spec)| spec.path.as_deref().is_some_and(|p| p.trim_start_matches("./").trim_end_matches('/') == existing.trim_end_matches('/')))
+        .map(|(name, _)| format!("dependency '{}'", name))
+        .unwrap_or_else(|| match alias {
+            "lunu" | "lute" | "std" => "the runtime".to_string(),
+            _ => "an existing .luaurc entry".to_string(),
+        });
+    let mut claims = AliasClaims::default();
+    claims.claim(alias, existing, &owner)?;
+    claims.claim(alias, path, "the new dependency")
+}
+
 async fn ensure_runtime_aliases(root: &Path, runtime: RuntimeKind) -> Result<()> {
     let config_path = project_config_path(root);
     if !config_path.exists() {
         return Ok(());
     }
     let cfg = ProjectConfig::load(&config_path).await?;
-    update_luaurc(root, &cfg.dependencies, runtime).await?;
+    update_luaurc(root, &cfg.all_dependencies(), runtime).await?;
     Ok(())
 }
 
-async fn init_project(root: &Path) -> Result<()> {
-    let runtime = select_runtime()?;
+async fn init_project(root: &Path, options: &InitOptions) -> Result<()> {
+    let runtime = select_runtime(options.runtime, options.assume_yes)?;
     ensure_runtime_available(root, runtime_target_from_kind(runtime)).await?;
     let toolchain = if runtime == RuntimeKind::Lute {
         if find_lute_executable(root).is_none() {
@@ -1779,7 +3659,7 @@ async fn init_project(root: &Path) -> Result<()> {
     };
 
     let build_cfg = Some(build_config_for(runtime, toolchain));
-    ensure_project_files(root, runtime).await?;
+    ensure_project_files(root, runtime, options.template.unwrap_or_default()).await?;
     
     if runtime == RuntimeKind::Lune {
         let modules_dir = root.join("modules");
@@ -1790,10 +3670,21 @@ async fn init_project(root: &Path) -> Result<()> {
             async_fs::write(lunu_mod_dir.join("init.luau"), init_content).await?;
             println!("Installed Lunu core library to modules/lunu");
         }
+        let resources_path = lunu_mod_dir.join("resources.luau");
+        if !resources_path.exists() {
+            async_fs::write(&resources_path, include_str!("../../resources.luau")).await?;
+        }
+        let test_path = lunu_mod_dir.join("test.luau");
+        if !test_path.exists() {
+            async_fs::write(&test_path, include_str!("../../test.luau")).await?;
+        }
     }
 
     let config_path = project_config_path(root);
     let mut cfg = load_or_init_project(root, &config_path, runtime, build_cfg).await?;
+    if let Some(name) = &options.name {
+        cfg.project.name = name.clone();
+    }
 
     let discovered = scan_modules(root);
     for (name, spec) in discovered {
@@ -1824,62 +3715,256 @@ async fn init_project(root: &Path) -> Result<()> {
         if let Some(path) = &spec.path {
             let full_path = root.join(path);
             if full_path.exists() {
-                let checksum = pm.calculate_dir_checksum(&full_path).await?;
+                let (checksum, files) = locked_checksum(&pm, spec, &full_path).await?;
                 lock.set(name, LockEntry {
                     url: spec.url.clone(),
                     version: spec.version.clone(),
+                    tag: None,
+                    rev: None,
                     path: Some(path.clone()),
+                    subdir: None,
                     checksum,
+                    files,
                     installed_at: current_timestamp(),
+                    mirror: None,
+                    registry: None,
+                    commit: None,
+                    resolver: Some(Resolver::Path),
+                    installed_by: lock::current_actor(),
+                    mutable: spec.is_mutable(),
                 });
             }
         }
     }
     lock.save(&lock_path).await?;
 
+    if !options.skip_types {
+        install_type_definitions(root, runtime).await;
+    }
+
     println!("Project initialized at {:?}", root);
     Ok(())
 }
 
-async fn create_project(cwd: &Path, name: &str) -> Result<()> {
+fn types_runtime(runtime: RuntimeKind) -> typedefs::TypesRuntime {
+    match runtime {
+        RuntimeKind::Lute => typedefs::TypesRuntime::Lute,
+        RuntimeKind::Lune => typedefs::TypesRuntime::Lune,
+    }
+}
+
+/// Version of the runtime the project runs on: the cached install's version, or `<exe> --version`.
+fn pinned_runtime_version(root: &Path, runtime: RuntimeKind) -> Option<String> {
+    let target = runtime_target_from_kind(runtime);
+    if let Some(meta) = read_runtime_meta(target) {
+        return Some(meta.version);
+    }
+    let exe = match runtime {
+        RuntimeKind::Lute => find_lute_executable(root),
+        RuntimeKind::Lune => find_lune_executable(root),
+    }?;
+    let output = Command::new(exe).arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.split_whitespace()
+        .last()
+        .filter(|v| v.trim_start_matches('v').starts_with(|c: char| c.is_ascii_digit()))
+        .map(String::from)
+}
+
+async fn download_repo_tag_zip(repo: &str, tag: &str) -> Result<Vec<u8>> {
+    let url = format!("https://codeload.github.com/{}/zip/refs/tags/{}", repo, tag);
+    let client = http::client()?;
+    http::get_bytes(&client, &url).await
+}
+
+/// Best effort: missing type definitions only degrade editor support, so failures warn.
+async fn install_type_definitions(root: &Path, runtime: RuntimeKind) {
+    if let Err(err) = try_install_type_definitions(root, runtime).await {
+        println!("Warning: could not install {} type definitions: {:#}", runtime_name(runtime_target_from_kind(runtime)), err);
+    }
+}
+
+async fn try_install_type_definitions(root: &Path, runtime: RuntimeKind) -> Result<()> {
+    let manifest = fetch_type_definitions(root, runtime).await?;
+    typedefs::write_lsp_settings(root, &manifest)?;
+    if runtime == RuntimeKind::Lune {
+        ensure_lune_types_alias(root).await?;
+    }
+    Ok(())
+}
+
+/// Points the `lune` alias at the installed Lune definitions so `@lune/...` requires resolve for
+/// luau-analyze, unless the project already maps it somewhere.
+async fn ensure_lune_types_alias(root: &Path) -> Result<()> {
+    if !typedefs::types_dir(root).join("lune").is_dir() {
+        return Ok(());
+    }
+    let path = root.join(".luaurc");
+    let mut luaurc = Luaurc::load(&path).await?;
+    if luaurc.aliases.contains_key("lune") {
+        return Ok(());
+    }
+    luaurc.add_alias("lune", ".lunu/types/lune/");
+    luaurc.save(&path).await?;
+    println!("Added the 'lune' alias to .luaurc for the Lune type definitions.");
+    Ok(())
+}
+
+/// Installs the type definitions of the pinned runtime version into .lunu/types (reusing them when
+/// already there), without touching editor settings or .luaurc.
+async fn fetch_type_definitions(root: &Path, runtime: RuntimeKind) -> Result<typedefs::TypesManifest> {
+    let target = runtime_target_from_kind(runtime);
+    let name = runtime_name(target);
+    let repo = runtime_repo(target);
+    let version = pinned_runtime_version(root, runtime);
+
+    if let (Some(manifest), Some(version)) = (typedefs::read_manifest(root), version.as_deref()) {
+        if manifest.runtime == name && manifest.version == version {
+            return Ok(manifest);
+        }
+    }
+
+    let mut archive = None;
+    for tag in version.as_deref().map(typedefs::tag_candidates).unwrap_or_default() {
+        if let Ok(bytes) = download_repo_tag_zip(repo, &tag).await {
+            archive = Some((tag, bytes));
+            break;
+        }
+    }
+    let (source, bytes) = match archive {
+        Some(found) => found,
+        None => {
+            let branch = fetch_repo_default_branch(repo).await.unwrap_or_else(|_| "main".to_string());
+            println!("Could not fetch {} sources for the pinned version; using {} branch type definitions.", name, branch);
+            let bytes = download_repo_zip(repo, &branch).await?;
+            (branch, bytes)
+        }
+    };
+    let recorded = version.unwrap_or(source);
+    let manifest = typedefs::install_from_zip(root, types_runtime(runtime), &recorded, &bytes)?;
+    println!("Installed {} type definitions ({}) to .lunu/types", name, recorded);
+    Ok(manifest)
+}
+
+async fn create_project(cwd: &Path, name: &str, options: &InitOptions) -> Result<()> {
     let project_dir = cwd.join(name);
     if project_dir.exists() {
         return Err(anyhow::anyhow!("Directory '{}' already exists", name));
     }
     async_fs::create_dir_all(&project_dir).await?;
-    init_project(&project_dir).await?;
+    init_project(&project_dir, options).await?;
     Ok(())
 }
 
 async fn scaffold_project(cwd: &Path, name: &str, template: TemplateKind) -> Result<()> {
-    create_project(cwd, name).await?;
-    let project_dir = cwd.join(name);
-    let config_path = project_config_path(&project_dir);
-    if config_path.exists() {
-        if let Ok(cfg) = ProjectConfig::load(&config_path).await {
-            if cfg.runtime.as_ref().map(|r| r.name.as_str()) == Some("lute") {
-                println!("Scaffold created at {:?}", project_dir);
-                return Ok(());
-            }
+    let options = InitOptions {
+        template: Some(template),
+        ..InitOptions::default()
+    };
+    create_project(cwd, name, &options).await?;
+    println!("Scaffold created at {:?}", cwd.join(name));
+    Ok(())
+}
+
+/// Writes the project as a template (see `template`), leaving out the modules `lunu install`
+/// restores, and optionally pushes it.
+async fn export_template(root: &Path, out: &Path, name: Option<String>, description: Option<String>, author: Option<String>, push: Option<&str>) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let skip = cfg
+        .all_dependencies()
+        .iter()
+        .filter(|(_, spec)| spec.is_remote())
+        .map(|(dep, _)| {
+            lock.dependencies
+                .get(dep)
+                .and_then(|entry| entry.path.clone())
+                .unwrap_or_else(|| format!("modules/{}", dep))
+        })
+        .collect();
+    let author = author.or_else(|| {
+        git2::Repository::discover(root)
+            .and_then(|repo| repo.config())
+            .or_else(|_| git2::Config::open_default())
+            .and_then(|config| config.get_string("user.name"))
+            .ok()
+    });
+    let out = root.join(out);
+    let info = template::TemplateInfo { name: name.unwrap_or_else(|| cfg.project.name.clone()), description };
+    let options = template::ExportOptions { author, skip };
+    let manifest = template::export(root, &out, info, &cfg.project.name, &options)?;
+    println!("Template '{}' written to {:?}", manifest.template.name, out);
+    for (param, spec) in &manifest.params {
+        let default = spec.default.as_deref().map(|d| format!(" (default {})", d)).unwrap_or_default();
+        println!("  {{{{{}}}}}{}", param, default);
+    }
+
+    if let Some(url) = push {
+        if !out.join(".git").exists() {
+            run_git(&["init", "--quiet"], &out)?;
+        }
+        run_git(&["add", "-A"], &out)?;
+        let unchanged = Command::new("git").args(["diff", "--cached", "--quiet"]).current_dir(&out).status()?.success();
+        let has_commits = Command::new("git").args(["rev-parse", "--quiet", "--verify", "HEAD"]).current_dir(&out).output()?.status.success();
+        if !unchanged || !has_commits {
+            run_git(&["commit", "--quiet", "-m", &format!("Export template {}", manifest.template.name)], &out)?;
         }
+        println!("Pushing template to {} ...", url);
+        run_git(&["push", "--quiet", "--", git_operand(url, "template URL")?, "HEAD:refs/heads/main"], &out)?;
     }
-    let main_path = project_dir.join("src").join("main.luau");
-    let content = match template {
-        TemplateKind::App => "print(\"Hello from Lunu\")\n".to_string(),
-        TemplateKind::Game => {
-            [
-                "local frame = 0",
-                "while frame < 3 do",
-                "    print(\"tick\", frame)",
-                "    frame = frame + 1",
-                "end",
-                "",
-            ]
-            .join("\n")
+    println!("Create a project from it with 'lunu scaffold <name> --from {}'", push.map(str::to_string).unwrap_or_else(|| out.display().to_string()));
+    Ok(())
+}
+
+/// The template directory for `source`: a directory, the name of a template installed by an
+/// offline installer, or a Git repository cloned into `scratch`.
+fn fetch_template(source: &str, scratch: &Path) -> Result<PathBuf> {
+    if Path::new(source).is_dir() {
+        return Ok(PathBuf::from(source));
+    }
+    if let Some(installed) = dirs::home_dir().map(|home| offline_installer::templates_dir(&home).join(source)) {
+        if !source.contains(['/', '\\', ':']) && !source.starts_with('.') && installed.join(template::MANIFEST).is_file() {
+            return Ok(installed);
         }
-    };
-    async_fs::write(&main_path, content).await?;
-    println!("Scaffold created at {:?}", project_dir);
+    }
+    println!("Fetching template {} ...", source);
+    let dir = scratch.join("template");
+    run_git(&["clone", "--quiet", "--depth", "1", "--", git_operand(source, "template source")?, &dir.to_string_lossy()], scratch)?;
+    if !dir.join(template::MANIFEST).exists() {
+        // `lunu template export --push` writes main; a bare repository's HEAD may still name master
+        std::fs::remove_dir_all(&dir)?;
+        run_git(&["clone", "--quiet", "--depth", "1", "--branch", "main", "--", source, &dir.to_string_lossy()], scratch)?;
+    }
+    Ok(dir)
+}
+
+/// `lunu scaffold <name> --from <dir, installed template or git url>`.
+fn scaffold_from_template(cwd: &Path, name: &str, source: &str, set: &[String]) -> Result<()> {
+    let project_dir = cwd.join(name);
+    if project_dir.exists() {
+        return Err(anyhow::anyhow!("Directory '{}' already exists", name));
+    }
+    let mut values = BTreeMap::from([("name".to_string(), name.to_string())]);
+    for pair in set {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected KEY=VALUE for --set, got '{}'", pair))?;
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    let clone = tempfile::tempdir()?;
+    let template_dir = fetch_template(source, clone.path())?;
+    let manifest = template::TemplateManifest::load(&template_dir)?;
+    if let Err(err) = template::render(&template_dir, &project_dir, &values) {
+        std::fs::remove_dir_all(&project_dir).ok();
+        return Err(err);
+    }
+    println!("Scaffold created at {:?} from template '{}'", project_dir, manifest.template.name);
+    println!("Run 'lunu install' inside it to fetch its dependencies.");
     Ok(())
 }
 
@@ -2148,7 +4233,71 @@ fn profile_script(root: &Path, script: &Path, runs: u32) -> Result<()> {
     Ok(())
 }
 
+/// `--runtime-path` for the current command; takes precedence over bin/, env vars, the cache and PATH.
+/// Applies `[runtimes.<name>]`: its env is set for the runtime process, and its path (or downloaded
+/// version) is used unless `--runtime-path` already chose a binary.
+async fn apply_runtime_profile(root: &Path, name: Option<&str>) -> Result<()> {
+    let Some(name) = name else {
+        return Ok(());
+    };
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let profile = cfg.runtimes.get(name).ok_or_else(|| {
+        let known: Vec<&str> = cfg.runtimes.keys().map(String::as_str).collect();
+        let hint = config_schema::suggest(name, &known).map(|s| format!(" Did you mean '{}'?", s)).unwrap_or_default();
+        anyhow::anyhow!("No [runtimes.{}] in lunu.toml (defined: {}).{}", name, if known.is_empty() { "none".to_string() } else { known.join(", ") }, hint)
+    })?;
+    for (key, value) in &profile.env {
+        std::env::set_var(key, value);
+    }
+    if runtime_path_override().is_some() {
+        return Ok(());
+    }
+    if let Some(path) = &profile.path {
+        return set_runtime_path_override(root, Some(PathBuf::from(path)))
+            .with_context(|| format!("[runtimes.{}] path", name));
+    }
+    if let Some(version) = &profile.version {
+        let target = runtime_target_from_kind(runtime_kind_from_config(&cfg));
+        let platform = HostPlatform::current();
+        let path = platform_runtime_cache_path(target, &platform, version);
+        if !path.exists() {
+            let release = fetch_release_by_tag(target, version)
+                .await
+                .with_context(|| format!("{} release '{}' ([runtimes.{}]) not found", runtime_name(target), version, name))?;
+            download_platform_runtime(target, &release, &platform).await?;
+        }
+        ensure_executable(&path)?;
+        set_runtime_path_override(root, Some(path))?;
+    }
+    Ok(())
+}
+
+static RUNTIME_PATH_OVERRIDE: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+fn set_runtime_path_override(cwd: &Path, path: Option<PathBuf>) -> Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let path = if path.is_absolute() { path } else { cwd.join(path) };
+    if !path.is_file() {
+        return Err(anyhow::anyhow!("Runtime binary not found at {:?}", path));
+    }
+    let _ = RUNTIME_PATH_OVERRIDE.set(path);
+    Ok(())
+}
+
+fn runtime_path_override() -> Option<&'static Path> {
+    RUNTIME_PATH_OVERRIDE.get().map(PathBuf::as_path)
+}
+
 fn find_lune_executable(root: &Path) -> Option<PathBuf> {
+    if let Some(path) = runtime_path_override() {
+        return Some(path.to_path_buf());
+    }
     let local = root.join("bin").join(runtime_bin_filename(RuntimeTarget::Lune));
     if local.exists() {
         return Some(local);
@@ -2166,7 +4315,20 @@ fn find_lune_executable(root: &Path) -> Option<PathBuf> {
     find_in_path(&runtime_bin_filename(RuntimeTarget::Lune))
 }
 
+/// Capabilities of the runtime binary the project at `root` runs with.
+fn runtime_capabilities(root: &Path, target: RuntimeTarget, refresh: bool) -> Result<capabilities::Capabilities> {
+    let binary = match target {
+        RuntimeTarget::Lute => find_lute_executable(root),
+        RuntimeTarget::Lune => find_lune_executable(root),
+    }
+    .ok_or_else(|| anyhow::anyhow!("{} runtime not found. Run 'lunu runtime {} --update'.", runtime_name(target), runtime_name(target)))?;
+    Ok(capabilities::load_or_probe(&runtime_cache_dir(), &binary, runtime_name(target), refresh))
+}
+
 fn find_lute_executable(root: &Path) -> Option<PathBuf> {
+    if let Some(path) = runtime_path_override() {
+        return Some(path.to_path_buf());
+    }
     let local = root.join("bin").join(runtime_bin_filename(RuntimeTarget::Lute));
     if local.exists() {
         return Some(local);
@@ -2209,8 +4371,38 @@ fn embedded_lute_bytes() -> Option<&'static [u8]> {
     }
 }
 
-async fn run_tests(root: &Path, specific_file: Option<PathBuf>, runtime: RuntimeKind) -> Result<()> {
-    println!("Running tests using {} runtime...", match runtime { RuntimeKind::Lute => "Lute", RuntimeKind::Lune => "Lune" });
+/// Scratch space for one test file (`@lunu/test`): an empty directory plus a copy of `config/`.
+/// Removed when dropped, so tests never write into the project tree.
+struct TestSandbox {
+    dir: tempfile::TempDir,
+}
+
+impl TestSandbox {
+    fn create(root: &Path) -> Result<Self> {
+        let dir = tempfile::Builder::new().prefix("lunu-test-").tempdir()?;
+        fs::create_dir_all(dir.path().join("tmp"))?;
+        let config = root.join("config");
+        if config.is_dir() {
+            copy_dir_filtered(&config, &dir.path().join("config"), &ExcludeSet::new(false, &[], &[]))?;
+        } else {
+            fs::create_dir_all(dir.path().join("config"))?;
+        }
+        Ok(Self { dir })
+    }
+
+    fn env(&self, root: &Path) -> Vec<(&'static str, PathBuf)> {
+        vec![
+            ("LUNU_TEST_TMPDIR", self.dir.path().join("tmp")),
+            ("LUNU_TEST_CONFIG_DIR", self.dir.path().join("config")),
+            ("LUNU_TEST_FIXTURES_DIR", root.join("tests").join("fixtures")),
+        ]
+    }
+}
+
+/// `lunu test`; each file's output is logged (see `test_logs`) and shown for failures, or for every
+/// file with `show_output`.
+async fn run_tests(root: &Path, specific_file: Option<PathBuf>, filter: Option<&str>, runtime: RuntimeKind, show_output: bool) -> Result<()> {
+    println!("Running tests using {} runtime...", match runtime { RuntimeKind::Lute => "Lute", RuntimeKind::Lune => "Lune" });
     
     let mut test_files = Vec::new();
     
@@ -2236,7 +4428,10 @@ async fn run_tests(root: &Path, specific_file: Option<PathBuf>, runtime: Runtime
                         dirs.push(path);
                     }
                 } else if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.ends_with(".test.luau") || name.ends_with(".spec.luau") {
+                    let matches_filter = filter
+                        .map(|f| path.strip_prefix(root).unwrap_or(&path).to_string_lossy().contains(f))
+                        .unwrap_or(true);
+                    if (name.ends_with(".test.luau") || name.ends_with(".spec.luau")) && matches_filter {
                         test_files.push(path);
                     }
                 }
@@ -2253,43 +4448,38 @@ async fn run_tests(root: &Path, specific_file: Option<PathBuf>, runtime: Runtime
     let mut failed = 0;
 
     for file in test_files {
-        print!("Testing {:?} ... ", file.file_name().unwrap());
-        io::stdout().flush()?;
-        
+        if show_output {
+            println!("Testing {:?} ...", file.file_name().unwrap());
+        } else {
+            print!("Testing {:?} ... ", file.file_name().unwrap());
+            io::stdout().flush()?;
+        }
+
         let start = std::time::Instant::now();
-        let status = match runtime {
-            RuntimeKind::Lute => {
-                let lute = find_lute_executable(root).ok_or_else(|| anyhow::anyhow!("Lute not found"))?;
-                Command::new(&lute)
-                    .arg("run")
-                    .arg(&file)
-                    .current_dir(root)
-                    .output()
-                    .with_context(|| "Failed to run lute")?
-            }
-            RuntimeKind::Lune => {
-                let lune = find_lune_executable(root).ok_or_else(|| anyhow::anyhow!("Lune not found"))?;
-                Command::new(&lune)
-                    .arg("run")
-                    .arg(&file)
-                    .current_dir(root)
-                    .output()
-                    .with_context(|| "Failed to run lune")?
-            }
+        let sandbox = TestSandbox::create(root)?;
+        let (runtime_name, binary) = match runtime {
+            RuntimeKind::Lute => ("lute", find_lute_executable(root).ok_or_else(|| anyhow::anyhow!("Lute not found"))?),
+            RuntimeKind::Lune => ("lune", find_lune_executable(root).ok_or_else(|| anyhow::anyhow!("Lune not found"))?),
         };
+        let log = test_logs::log_path(root, &file);
+        let mut command = Command::new(&binary);
+        command.arg("run").arg(&file).envs(sandbox.env(root)).current_dir(root);
+        let (success, output) = test_logs::run(&mut command, &log, show_output).with_context(|| format!("Failed to run {}", runtime_name))?;
 
         let duration = start.elapsed();
-        if status.status.success() {
+        if success {
             println!("OK ({:.2?})", duration);
         } else {
             println!("FAIL ({:.2?})", duration);
-            println!("--- Output ---");
-            io::stdout().write_all(&status.stdout)?;
-            io::stderr().write_all(&status.stderr)?;
-            println!("--------------");
+            if !show_output {
+                println!("--- Output ---");
+                io::stdout().write_all(&output)?;
+                println!("--------------");
+            }
             failed += 1;
         }
     }
+    println!("Test output is in {}/", test_logs::DIR);
 
     if failed > 0 {
         return Err(anyhow::anyhow!("{} test(s) failed.", failed));
@@ -2299,11 +4489,175 @@ async fn run_tests(root: &Path, specific_file: Option<PathBuf>, runtime: Runtime
     Ok(())
 }
 
-fn run_script(root: &Path, script: &Path, args: &[String], runtime: RuntimeKind) -> Result<()> {
+/// Runs an external checker (stylua, selene) over the project sources for a build gate.
+fn run_gate_tool(root: &Path, tool: &str, args: &[&str]) -> Result<()> {
+    let binary = match executable_extension() {
+        Some(ext) => format!("{}.{}", tool, ext),
+        None => tool.to_string(),
+    };
+    let exe = find_in_path(&binary)
+        .ok_or_else(|| anyhow::anyhow!("{} not found on PATH", tool))?;
+    let status = Command::new(exe)
+        .args(args)
+        .current_dir(root)
+        .status()
+        .with_context(|| format!("Failed to run {}", tool))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} reported problems", tool));
+    }
+    Ok(())
+}
+
+fn exclude_set_for(exclude: Option<&BuildExclude>) -> ExcludeSet {
+    match exclude {
+        Some(exclude) => ExcludeSet::new(exclude.defaults, &exclude.patterns, &exclude.keep),
+        None => ExcludeSet::default(),
+    }
+}
+
+/// Copies `src` into `dst`, skipping paths (relative to `src`) that `exclude` rejects.
+fn copy_dir_filtered(src: &Path, dst: &Path, exclude: &ExcludeSet) -> Result<()> {
+    let mut pending = vec![src.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let relative_dir = dir.strip_prefix(src).unwrap_or(&dir);
+        std::fs::create_dir_all(dst.join(relative_dir))?;
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            let relative = path.strip_prefix(src).unwrap_or(&path);
+            if exclude.is_excluded(&relative.to_string_lossy()) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                std::fs::copy(&path, dst.join(relative))
+                    .with_context(|| format!("Failed to copy {:?}", path))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `lunu build --clear-cache`: drops the project's runtime payload so the next build recreates it.
+fn clear_payload_cache(root: &Path, cwd: &Path) -> Result<()> {
+    let cache = lunu_builder::payload_cache::PayloadCache::for_project(&lunu_builder::payload_cache::builder_cache_dir(cwd), root);
+    if cache.clear()? {
+        println!("Cleared the payload cache of this project ({:?}).", cache.dir());
+    } else {
+        println!("This project has no cached payload.");
+    }
+    Ok(())
+}
+
+/// Reads the `[build]` settings the builder embeds, resolving paths relative to the project.
+fn print_build_history(root: &Path) -> Result<()> {
+    let records = lunu_builder::history::list(root)?;
+    if records.is_empty() {
+        println!("No builds recorded in dist/history yet.");
+        return Ok(());
+    }
+    for (index, record) in records.iter().enumerate() {
+        println!(
+            "{:>3}. {:<14} {:>10}  {} files  {}",
+            index + 1,
+            record.id,
+            lunu_builder::report::format_size(record.total()),
+            record.files.len(),
+            record.output
+        );
+    }
+    Ok(())
+}
+
+async fn load_build_settings(root: &Path) -> Result<lunu_builder::manifest::BuildSettings> {
+    let mut settings = lunu_builder::manifest::BuildSettings::default();
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Ok(settings);
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    settings.dev_modules = cfg.dev_module_paths();
+    let Some(build) = cfg.build else {
+        return Ok(settings);
+    };
+    settings.updates = build.updates.map(|updates| lunu_builder::manifest::UpdateSettings {
+        version: updates.version,
+        channel: lunu_builder::manifest::UpdateChannel {
+            url: updates.url,
+            channel: updates.channel,
+            public_key: updates.public_key,
+        },
+        signing_key: updates.signing_key.map(|p| root.join(p)),
+    });
+    if let Some(dir) = build.extract_dir {
+        settings.extract.dir = dir;
+    }
+    settings.exclude = exclude_set_for(build.exclude.as_ref());
+    settings.history = build.history;
+    if let Some(cleanup) = build.cleanup {
+        settings.extract.cleanup = cleanup.parse().with_context(|| "Invalid [build] cleanup in lunu.toml")?;
+    }
+    Ok(settings)
+}
+
+async fn run_build_gates(root: &Path, runtime: RuntimeKind) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Ok(());
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let Some(gates) = cfg.build.and_then(|b| b.gates).filter(|g| !g.is_empty()) else {
+        return Ok(());
+    };
+
+    let src = Path::new(&cfg.project.entry)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| ".".to_string());
+    let mut failed = Vec::new();
+    if gates.fmt {
+        println!("Build gate: fmt");
+        if let Err(err) = run_gate_tool(root, "stylua", &["--check", &src]) {
+            println!("fmt gate failed: {}", err);
+            failed.push("fmt");
+        }
+    }
+    if gates.lint {
+        println!("Build gate: lint");
+        if let Err(err) = run_gate_tool(root, "selene", &[&src]) {
+            println!("lint gate failed: {}", err);
+            failed.push("lint");
+        }
+    }
+    let filter = match &gates.test {
+        TestGate::Enabled(false) => None,
+        TestGate::Enabled(true) => Some(None),
+        TestGate::Filter(f) => Some(Some(f.as_str())),
+    };
+    if let Some(filter) = filter {
+        println!("Build gate: test{}", filter.map(|f| format!(" ({})", f)).unwrap_or_default());
+        if let Err(err) = run_tests(root, None, filter, runtime, false).await {
+            println!("test gate failed: {}", err);
+            failed.push("test");
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Build gates failed: {}. Fix them or pass --no-verify to build anyway.",
+            failed.join(", ")
+        ));
+    }
+    println!("Build gates passed.");
+    Ok(())
+}
+
+fn script_command(root: &Path, script: &Path, args: &[String], runtime: RuntimeKind) -> Result<Command> {
     if !script.exists() {
         return Err(anyhow::anyhow!(format!("Input script not found: {:?}", script)));
     }
-    let status = match runtime {
+    let mut command = match runtime {
         RuntimeKind::Lute => {
             let lute = find_lute_executable(root).ok_or_else(|| anyhow::anyhow!(format!(
                 "Lute runtime not found. Set LUTE_PATH, place bin/{} in the project, or add {} to PATH.",
@@ -2311,28 +4665,14 @@ fn run_script(root: &Path, script: &Path, args: &[String], runtime: RuntimeKind)
                 runtime_bin_filename(RuntimeTarget::Lute)
             )))?;
             Command::new(&lute)
-                .arg("run")
-                .arg(script)
-                .args(args)
-                .current_dir(root)
-                .status()
-                .with_context(|| "Failed to run lute")?
         }
         RuntimeKind::Lune => {
             let lune = find_lune_executable(root).ok_or_else(|| anyhow::anyhow!("Lune runtime not found. Set LUNE_PATH or add to PATH."))?;
             Command::new(&lune)
-                .arg("run")
-                .arg(script)
-                .args(args)
-                .current_dir(root)
-                .status()
-                .with_context(|| "Failed to run lune")?
         }
     };
-    if !status.success() {
-        return Err(anyhow::anyhow!("Script execution failed"));
-    }
-    Ok(())
+    command.arg("run").arg(script).args(args).current_dir(root);
+    Ok(command)
 }
 
 fn build_with_lute(
@@ -2361,6 +4701,8 @@ fn build_with_lute(
         p
     };
     let out_path = output.clone().unwrap_or(default_out);
+    capabilities::load_or_probe(&runtime_cache_dir(), &lute, "lute", false).require("compile")?;
+    let compiling = timings::span("compile");
     let status = Command::new(&lute)
         .arg("compile")
         .arg(script)
@@ -2369,6 +4711,7 @@ fn build_with_lute(
         .current_dir(root)
         .status()
         .with_context(|| "Failed to run lute compile")?;
+    drop(compiling);
     if !status.success() {
         return Err(anyhow::anyhow!("Lute compile failed"));
     }
@@ -2384,244 +4727,3133 @@ fn build_with_lute(
     Ok(())
 }
 
-async fn install_from_config(root: &Path) -> Result<()> {
+/// Checksum and per-file hashes recorded in lunu.lock; mutable path dependencies are locked
+/// without either.
+async fn locked_checksum(pm: &PackageManager, spec: &DependencySpec, dir: &Path) -> Result<(String, BTreeMap<String, String>)> {
+    if spec.is_mutable() {
+        return Ok((String::new(), BTreeMap::new()));
+    }
+    let files = pm.file_hashes(dir).await?;
+    Ok((package::root_checksum(&files), files))
+}
+
+/// Locked dependencies whose files no longer match their recorded checksum.
+async fn checksum_mismatches(root: &Path, lock: &LockFile, pm: &PackageManager) -> Result<Vec<String>> {
+    let mut mismatched = Vec::new();
+    for (name, entry) in &lock.dependencies {
+        if entry.mutable || entry.checksum.is_empty() {
+            continue;
+        }
+        let Some(dir) = entry.path.as_ref().map(|p| root.join(p)).filter(|d| d.is_dir()) else {
+            continue;
+        };
+        if !pm.checksum_matches(&dir, &entry.checksum).await? {
+            mismatched.push(name.clone());
+        }
+    }
+    Ok(mismatched)
+}
+
+/// Findings for every lunu.lock entry: files that no longer match the locked checksum, missing
+/// install directories and URLs (upstream or mirror) on the denylist.
+async fn dependency_audit_findings(root: &Path, lock: &LockFile, denylist: &denylist::Denylist) -> Result<Vec<Finding>> {
+    let pm = PackageManager::new(root.to_path_buf());
+    let mut findings = Vec::new();
+    for (name, entry) in &lock.dependencies {
+        for url in entry.url.iter().chain(entry.mirror.iter()) {
+            if let Some(denied) = denylist.check(url) {
+                let reason = denied.reason.as_deref().map(|r| format!(" ({})", r)).unwrap_or_default();
+                findings.push(
+                    Finding::error(format!("dependency.denied/{}", name), format!("Dependency '{}' comes from a denylisted package: {}{}", name, url, reason))
+                        .with_hint(format!("Remove it with 'lunu remove {}' or replace it.", name)),
+                );
+            }
+        }
+        let Some(dir) = entry.path.as_ref().map(|p| root.join(p)) else {
+            continue;
+        };
+        if !dir.is_dir() {
+            findings.push(
+                Finding::warning(format!("dependency.missing/{}", name), format!("Dependency '{}' is locked but not installed at {:?}.", name, dir))
+                    .with_hint("Run 'lunu install'."),
+            );
+        } else if !entry.mutable && !entry.checksum.is_empty() && !pm.checksum_matches(&dir, &entry.checksum).await? {
+            findings.push(
+                Finding::error(format!("dependency.checksum/{}", name), format!("Dependency '{}' does not match its checksum in lunu.lock (tampered or edited locally).", name))
+                    .with_hint(format!("Reinstall it with 'lunu update {}' or mark a path dependency as mutable.", name)),
+            );
+        }
+    }
+    Ok(findings)
+}
+
+/// Permissions each installed bridge module requests (`None` when undeclared), and a finding for
+/// every module the `[permissions]` policy of lunu.toml denies.
+async fn permission_audit(root: &Path) -> Result<(BTreeMap<String, Option<permissions::Permissions>>, Vec<Finding>)> {
+    let policy = permission_policy(root).await?;
+    let mut requested = BTreeMap::new();
+    let mut findings = Vec::new();
+    for (name, dir) in module_dirs(root) {
+        let Ok(bridge_json) = fs::read_to_string(dir.join("bridge.json")) else {
+            continue;
+        };
+        let permissions = match permissions::declared(&bridge_json) {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                findings.push(Finding::warning(format!("permissions.invalid/{}", name), format!("Bridge module '{}': {:#}.", name, err)));
+                continue;
+            }
+        };
+        let violations = policy.as_ref().map(|p| p.violations(&name, permissions.as_ref())).unwrap_or_default();
+        if !violations.is_empty() {
+            let key = if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') { name.clone() } else { format!("\"{}\"", name) };
+            findings.push(
+                Finding::error(format!("permissions.denied/{}", name), format!("Bridge module '{}' {}; [permissions] in lunu.toml does not allow it.", name, violations.join("; ")))
+                    .with_hint(format!("Allow it under [permissions.modules.{}] or remove it with 'lunu remove {}'.", key, name)),
+            );
+        }
+        requested.insert(name, permissions);
+    }
+    Ok((requested, findings))
+}
+
+/// A finding for every advisory in `feed` that affects a locked dependency; high and critical
+/// ones are errors.
+fn advisory_findings(lock: &LockFile, feed: &advisories::Feed) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (name, entry) in &lock.dependencies {
+        for advisory in feed.matching(entry) {
+            let version = entry.version.as_deref().or(entry.tag.as_deref()).or(entry.commit.as_deref()).unwrap_or("(unknown version)");
+            let message = format!("Dependency '{}' {} is affected by {} ({}): {}", name, version, advisory.id, advisory.severity.as_str(), advisory.summary);
+            let finding = if advisory.severity >= advisories::Severity::High {
+                Finding::error(format!("advisory/{}/{}", advisory.id, name), message)
+            } else {
+                Finding::warning(format!("advisory/{}/{}", advisory.id, name), message)
+            };
+            let hint = match &advisory.url {
+                Some(url) => format!("Update it with 'lunu update {}' or remove it; details: {}", name, url),
+                None => format!("Update it with 'lunu update {}' or remove it.", name),
+            };
+            findings.push(finding.with_hint(hint));
+        }
+    }
+    findings
+}
+
+/// `lunu audit`; returns false when an error-level finding should fail the command.
+async fn audit_dependencies(root: &Path, denylist_path: Option<&Path>, advisory_feed: Option<&str>, json: bool) -> Result<bool> {
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let denylist_path = match denylist_path {
+        Some(path) => Some(path.to_path_buf()),
+        None => denylist::default_path().filter(|p| p.exists()),
+    };
+    let denylist = match &denylist_path {
+        Some(path) => denylist::load(path)?,
+        None => denylist::Denylist::default(),
+    };
+    let mut findings = dependency_audit_findings(root, &lock, &denylist).await?;
+    let advisory_count = match advisory_feed {
+        Some(source) => {
+            let feed = advisories::load(source).await?;
+            findings.extend(advisory_findings(&lock, &feed));
+            Some(feed.advisories.len())
+        }
+        None => None,
+    };
+    let (module_permissions, permission_findings) = permission_audit(root).await?;
+    findings.extend(permission_findings);
+    let failed = findings.iter().any(|f| f.severity == Severity::Error);
+
+    if json {
+        let output = serde_json::json!({
+            "audited": lock.dependencies.len(),
+            "denylist": denylist_path,
+            "advisory_feed": advisory_feed,
+            "permissions": module_permissions,
+            "findings": findings,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(!failed);
+    }
+    match &denylist_path {
+        Some(path) => println!("Audited {} locked dependencies against {:?} ({} entries).", lock.dependencies.len(), path, denylist.len()),
+        None => println!("Audited {} locked dependencies (no denylist).", lock.dependencies.len()),
+    }
+    if let (Some(source), Some(count)) = (advisory_feed, advisory_count) {
+        println!("Checked against {} advisories from {}.", count, source);
+    }
+    if !module_permissions.is_empty() {
+        println!("Bridge module permissions:");
+        for (name, requested) in &module_permissions {
+            println!("  {}: {}", name, requested.as_ref().map(|p| p.summary()).unwrap_or_else(|| "undeclared".to_string()));
+        }
+    }
+    for finding in &findings {
+        finding.print();
+    }
+    if findings.is_empty() {
+        println!("No problems found.");
+    }
+    Ok(!failed)
+}
+
+/// What `lunu verify` found for one lunu.lock entry.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum LockStatus {
+    Ok,
+    /// Files differ from the locked checksum
+    Drift,
+    Missing,
+    /// Locked before checksums covered subdirectories; only top-level files were compared
+    Legacy,
+    /// Mutable or locked without a checksum
+    Unchecked,
+}
+
+async fn lock_statuses(root: &Path, lock: &LockFile) -> Result<Vec<(String, LockStatus)>> {
+    let pm = PackageManager::new(root.to_path_buf());
+    let mut statuses = Vec::new();
+    for (name, entry) in &lock.dependencies {
+        let Some(dir) = entry.path.as_ref().map(|p| root.join(p)) else {
+            statuses.push((name.clone(), LockStatus::Unchecked));
+            continue;
+        };
+        let status = if !dir.is_dir() {
+            LockStatus::Missing
+        } else if entry.mutable || entry.checksum.is_empty() {
+            LockStatus::Unchecked
+        } else if !pm.checksum_matches(&dir, &entry.checksum).await? {
+            LockStatus::Drift
+        } else if package::is_legacy_checksum(&entry.checksum) {
+            LockStatus::Legacy
+        } else {
+            LockStatus::Ok
+        };
+        statuses.push((name.clone(), status));
+    }
+    Ok(statuses)
+}
+
+/// Files of a drifted module that differ from their locked hashes; empty when the entry was locked
+/// without per-file hashes, so only the root checksum is known.
+async fn drifted_files(pm: &PackageManager, root: &Path, entry: &LockEntry) -> Result<Vec<(package::FileChange, String)>> {
+    match &entry.path {
+        Some(path) if !entry.files.is_empty() => Ok(package::changed_files(&entry.files, &pm.file_hashes(&root.join(path)).await?)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// `lunu verify`; returns false when a module drifted from lunu.lock or is missing.
+async fn verify_lock(root: &Path, json: bool) -> Result<bool> {
+    let lock_path = lock_path(root);
+    if !lock_path.exists() {
+        return Err(anyhow::anyhow!("lunu.lock not found. Run 'lunu install' first."));
+    }
+    let lock = LockFile::load(&lock_path).await?;
+    let statuses = lock_statuses(root, &lock).await?;
+    let failed = statuses.iter().any(|(_, s)| matches!(s, LockStatus::Drift | LockStatus::Missing));
+
+    let pm = PackageManager::new(root.to_path_buf());
+    let mut changes = BTreeMap::new();
+    for (name, status) in &statuses {
+        if *status == LockStatus::Drift {
+            changes.insert(name.as_str(), drifted_files(&pm, root, &lock.dependencies[name]).await?);
+        }
+    }
+
+    if json {
+        let dependencies: BTreeMap<&str, LockStatus> = statuses.iter().map(|(name, status)| (name.as_str(), *status)).collect();
+        let changed: BTreeMap<&str, Vec<Value>> = changes
+            .iter()
+            .map(|(name, files)| (*name, files.iter().map(|(change, path)| serde_json::json!({ "change": change.label(), "path": path })).collect()))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "ok": !failed, "dependencies": dependencies, "changed": changed }))?);
+        return Ok(!failed);
+    }
+    for (name, status) in &statuses {
+        let path = lock.dependencies[name].path.as_deref().unwrap_or("(no path)");
+        match status {
+            LockStatus::Drift => {
+                println!("  drift: {} ({}) does not match its locked checksum", name, path);
+                for (change, file) in changes.get(name.as_str()).into_iter().flatten() {
+                    println!("    {}: {}", change.label(), file);
+                }
+            }
+            LockStatus::Missing => println!("  missing: {} is locked but not installed at {}", name, path),
+            LockStatus::Legacy => println!("  note: {} was locked before checksums covered subdirectories; 'lunu install' re-locks it", name),
+            LockStatus::Ok | LockStatus::Unchecked => {}
+        }
+    }
+    let checked = statuses.iter().filter(|(_, s)| *s != LockStatus::Unchecked).count();
+    if failed {
+        println!("lunu.lock verification failed. Reinstall with 'lunu install' or 'lunu update <dep>'.");
+    } else {
+        println!("Verified {} of {} locked dependencies; no drift.", checked, statuses.len());
+    }
+    Ok(!failed)
+}
+
+/// Installs every dependency of lunu.toml. `frozen` installs the locked versions without writing
+/// lunu.lock, and fails when the two files disagree or an installed checksum is not the locked one.
+async fn install_from_config(root: &Path, offline: bool, frozen: bool) -> Result<()> {
     let config_path = project_config_path(root);
     if !config_path.exists() {
         return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
     }
     let cfg = ProjectConfig::load(&config_path).await?;
     let mut lock = LockFile::load(&lock_path(root)).await?;
-    let pm = PackageManager::new(root.to_path_buf());
+    if cfg.project.vendored {
+        return check_vendored(root, &cfg, &lock).await;
+    }
+    let pm = PackageManager::new(root.to_path_buf()).with_offline(offline);
 
-    if cfg.dependencies.is_empty() {
+    let dependencies = cfg.all_dependencies();
+    if dependencies.is_empty() {
         println!("No dependencies listed in lunu.toml.");
+        prune_modules(root, false, true).await?;
         return Ok(());
     }
+    if frozen {
+        let problems = frozen_lock_problems(&dependencies, &lock);
+        if !problems.is_empty() {
+            return Err(anyhow::anyhow!(
+                "lunu.lock does not match lunu.toml (--frozen):\n{}\nRun 'lunu install' without --frozen to update lunu.lock.",
+                problems.iter().map(|p| format!("  - {}", p)).collect::<Vec<_>>().join("\n")
+            ));
+        }
+    }
+    let expected = lock.clone();
+    if offline {
+        let missing = uncached_dependencies(root, &pm, &dependencies, &lock).await?;
+        if !missing.is_empty() {
+            let cache = package_cache::dir().map(|d| format!(" ({})", d.display())).unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Cannot install offline; not in the package cache{}:\n{}\nRun 'lunu install' once with network access to cache them.",
+                cache,
+                missing.iter().map(|m| format!("  - {}", m)).collect::<Vec<_>>().join("\n")
+            ));
+        }
+    }
 
-    for (name, spec) in &cfg.dependencies {
+    let total = dependencies.len();
+    for (index, (name, spec)) in dependencies.iter().enumerate() {
+        progress::emit("install", progress::fraction(index, total), &format!("Installing {}", name));
         if let Some(url) = &spec.url {
-            let (path, checksum) = pm.install_package(url, spec.version.as_deref(), name).await?;
+            let mirror = lock.dependencies.get(name).and_then(|e| e.mirror.clone());
+            let locked = lock.dependencies.get(name).filter(|e| e.url.as_ref() == Some(url)).and_then(|e| e.version.clone());
+            let installed = install_preferring_mirror(&pm, url, mirror.as_deref(), &spec.git_ref(locked)?, name, spec).await?;
+            let path = installed.path;
             CompatibilityLayer::ensure_compat(&path).await?;
+            let files = pm.file_hashes(&path).await?;
+            let checksum = package::root_checksum(&files);
 
             let rel_path = pathdiff::diff_paths(&path, root).unwrap_or(path);
             let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+            let resolver = url_resolver(&lock, name, url);
             lock.set(name, LockEntry {
                 url: Some(url.clone()),
-                version: spec.version.clone(),
+                version: installed.version.map(|v| v.to_string()),
+                tag: installed.tag,
+                rev: spec.rev.clone(),
                 path: Some(rel_path_str.clone()),
+                subdir: spec.subdir.clone(),
                 checksum,
+                files,
                 installed_at: current_timestamp(),
+                mirror,
+                registry: None,
+                commit: installed.commit,
+                resolver,
+                installed_by: lock::current_actor(),
+                mutable: false,
             });
+        } else if spec.is_remote() {
+            if offline {
+                // Checked by `uncached_dependencies`: the locked install is still in place
+                continue;
+            }
+            let locked = lock.dependencies.get(name).filter(|e| e.registry.is_some() && e.registry == spec.registry()).and_then(|e| e.version.clone());
+            let entry = install_registry_package(root, name, spec, locked.as_deref()).await?;
+            lock.set(name, entry);
         } else if let Some(path) = &spec.path {
             let full_path = root.join(path);
             if full_path.exists() {
-                let checksum = pm.calculate_dir_checksum(&full_path).await?;
+                let (checksum, files) = locked_checksum(&pm, spec, &full_path).await?;
                 lock.set(name, LockEntry {
                     url: None,
                     version: spec.version.clone(),
+                    tag: None,
+                    rev: None,
                     path: Some(path.clone()),
+                    subdir: None,
                     checksum,
+                    files,
                     installed_at: current_timestamp(),
+                    mirror: None,
+                    registry: None,
+                    commit: None,
+                    resolver: Some(Resolver::Path),
+                    installed_by: lock::current_actor(),
+                    mutable: spec.is_mutable(),
                 });
             }
         }
     }
 
-    update_luaurc(root, &cfg.dependencies, runtime_kind_from_config(&cfg)).await?;
-    lock.save(&lock_path(root)).await?;
+    update_luaurc(root, &dependencies, runtime_kind_from_config(&cfg)).await?;
+    if frozen {
+        let mut failed: Vec<String> = expected
+            .dependencies
+            .iter()
+            .filter(|(_, entry)| !entry.path.as_ref().is_some_and(|p| root.join(p).is_dir()))
+            .map(|(name, _)| format!("{} (not installed)", name))
+            .collect();
+        failed.extend(checksum_mismatches(root, &expected, &pm).await?.into_iter().map(|name| format!("{} (checksum differs from lunu.lock)", name)));
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!("Frozen install does not reproduce lunu.lock: {}", failed.join(", ")));
+        }
+        println!("Verified {} locked checksums; lunu.lock left unchanged.", expected.dependencies.values().filter(|e| !e.mutable).count());
+    } else {
+        lock.save(&lock_path(root)).await?;
+    }
+    if !offline {
+        refresh_package_metadata(root, &remote_dependencies(&dependencies, None), false).await?;
+    }
+    prune_modules(root, false, true).await?;
+    progress::emit("install", Some(100.0), "Dependencies installed");
     println!("Dependencies installed successfully.");
+    let unmet = worker_requirement_findings(root);
+    for finding in &unmet {
+        finding.print();
+    }
+    if !unmet.is_empty() {
+        println!("Some bridge workers will not start until their interpreters are updated (see above).");
+    }
     Ok(())
 }
 
-async fn remove_dependency(root: &Path, lib: &str) -> Result<()> {
+/// Ways lunu.lock disagrees with the dependencies of lunu.toml, for `lunu install --frozen`.
+fn frozen_lock_problems(dependencies: &BTreeMap<String, DependencySpec>, lock: &LockFile) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, spec) in dependencies {
+        match lock.dependencies.get(name) {
+            None => problems.push(format!("'{}' is in lunu.toml but not locked", name)),
+            Some(entry) if !lock_entry_matches(spec, entry) => {
+                problems.push(format!("'{}' is locked from a different source or version than lunu.toml asks for", name))
+            }
+            Some(entry) if entry.checksum.is_empty() && !spec.is_mutable() => problems.push(format!("'{}' has no locked checksum", name)),
+            Some(_) => {}
+        }
+    }
+    for name in lock.dependencies.keys().filter(|name| !dependencies.contains_key(*name)) {
+        problems.push(format!("'{}' is locked but no longer in lunu.toml", name));
+    }
+    problems
+}
+
+/// Whether a locked entry still fits `spec`: it can seed `lunu lock merge` and passes `--frozen`.
+fn lock_entry_matches(spec: &DependencySpec, entry: &LockEntry) -> bool {
+    if !spec.is_remote() {
+        return spec.path.is_some() && entry.path == spec.path;
+    }
+    if entry.url != spec.url || entry.registry != spec.registry() || entry.subdir != spec.subdir {
+        return false;
+    }
+    match (&spec.version, &spec.tag) {
+        (Some(req), _) => {
+            let version = entry.version.as_deref().and_then(|v| semver::Version::parse(v.trim_start_matches(['v', 'V'])).ok());
+            match (package::parse_version_req(req), version) {
+                (Ok(req), Some(version)) => req.matches(&version),
+                _ => false,
+            }
+        }
+        (None, Some(tag)) => entry.tag.as_ref() == Some(tag),
+        (None, None) => entry.rev == spec.rev,
+    }
+}
+
+/// `lunu lock merge`: the dependencies of the merged lunu.toml, each seeded with the newest locked
+/// version either side of the conflict agrees with, reinstalled so checksums are recomputed.
+async fn merge_lockfile(root: &Path, offline: bool) -> Result<()> {
     let config_path = project_config_path(root);
     if !config_path.exists() {
         return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
     }
-    let mut cfg = ProjectConfig::load(&config_path).await?;
-    cfg.remove_dependency(lib);
-    cfg.save(&config_path).await?;
-
-    let mut lock = LockFile::load(&lock_path(root)).await?;
-    lock.remove(lib);
-    lock.save(&lock_path(root)).await?;
-
-    let pm = PackageManager::new(root.to_path_buf());
-    pm.remove_package(lib).await?;
+    if lock::conflict_sides(&async_fs::read_to_string(&config_path).await?).is_some() {
+        return Err(anyhow::anyhow!("lunu.toml has merge conflicts; resolve them first, then run 'lunu lock merge'."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let path = lock_path(root);
+    let content = if path.exists() { async_fs::read_to_string(&path).await? } else { String::new() };
+    let sides = match lock::conflict_sides(&content) {
+        Some((ours, theirs)) => vec![
+            LockFile::parse(&ours).context("Could not parse our side of lunu.lock")?,
+            LockFile::parse(&theirs).context("Could not parse their side of lunu.lock")?,
+        ],
+        None => {
+            println!("lunu.lock has no conflict markers; re-resolving it from lunu.toml.");
+            vec![LockFile::parse(&content)?]
+        }
+    };
 
-    let luaurc_path = root.join(".luaurc");
-    let mut luaurc = Luaurc::load(&luaurc_path).await?;
-    luaurc.remove_alias(lib);
-    luaurc.save(&luaurc_path).await?;
+    let dependencies = cfg.all_dependencies();
+    let mut seed = LockFile::default();
+    for (name, spec) in &dependencies {
+        let candidates: Vec<&LockEntry> = sides
+            .iter()
+            .filter_map(|side| side.dependencies.get(name))
+            .filter(|entry| lock_entry_matches(spec, entry))
+            .collect();
+        let newest = candidates.iter().max_by_key(|entry| {
+            entry.version.as_deref().and_then(|v| semver::Version::parse(v.trim_start_matches(['v', 'V'])).ok())
+        });
+        match newest {
+            Some(entry) => {
+                if let Some(version) = &entry.version {
+                    println!("  {} {}", name, version);
+                }
+                seed.set(name, (*entry).clone());
+            }
+            None => println!("  {} (re-resolved; neither side matches lunu.toml)", name),
+        }
+    }
+    let dropped: std::collections::BTreeSet<&String> = sides
+        .iter()
+        .flat_map(|side| side.dependencies.keys())
+        .filter(|name| !dependencies.contains_key(*name))
+        .collect();
+    for name in dropped {
+        println!("  {} (removed; not in lunu.toml)", name);
+    }
 
-    println!("Removed dependency '{}'.", lib);
+    seed.save(&path).await?;
+    install_from_config(root, offline, false).await?;
+    println!("lunu.lock merged.");
     Ok(())
 }
 
-async fn update_dependencies(root: &Path, lib: Option<&str>) -> Result<()> {
+/// `lunu snapshot create`: copies of the dependency files plus the runtime version and the
+/// checksum of each installed module.
+async fn create_snapshot(root: &Path, name: &str, force: bool) -> Result<()> {
     let config_path = project_config_path(root);
     if !config_path.exists() {
         return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
     }
+    snapshot::validate_name(name)?;
+    if !force && snapshot::dir(root).join(name).exists() {
+        return Err(anyhow::anyhow!("Snapshot '{}' already exists; pass --force to replace it.", name));
+    }
     let cfg = ProjectConfig::load(&config_path).await?;
-    let mut lock = LockFile::load(&lock_path(root)).await?;
+    let runtime = runtime_kind_from_config(&cfg);
+    let lock = LockFile::load(&lock_path(root)).await?;
     let pm = PackageManager::new(root.to_path_buf());
-
-    let targets: Vec<(&String, &DependencySpec)> = cfg.dependencies.iter().collect();
-    for (name, spec) in targets {
-        if let Some(filter) = lib {
-            if name != filter {
-                continue;
+    let mut modules = BTreeMap::new();
+    for (dep, entry) in &lock.dependencies {
+        match entry.path.as_ref().map(|p| root.join(p)).filter(|d| d.is_dir()) {
+            Some(dir) => {
+                modules.insert(dep.clone(), package::root_checksum(&pm.file_hashes(&dir).await?));
             }
-        }
-
-        if let Some(url) = &spec.url {
-            let (path, checksum) = pm.install_package(url, spec.version.as_deref(), name).await?;
-            CompatibilityLayer::ensure_compat(&path).await?;
-            let rel_path = pathdiff::diff_paths(&path, root).unwrap_or(path);
-            let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
-            lock.set(name, LockEntry {
-                url: Some(url.clone()),
-                version: spec.version.clone(),
-                path: Some(rel_path_str),
-                checksum,
-                installed_at: current_timestamp(),
-            });
+            None => println!("Warning: '{}' is not installed; the snapshot records only its lunu.lock entry.", dep),
         }
     }
-
-    lock.save(&lock_path(root)).await?;
-    println!("Dependencies updated.");
+    let snapshot = snapshot::Snapshot {
+        name: name.to_string(),
+        created_at: current_timestamp(),
+        lunu: env!("CARGO_PKG_VERSION").to_string(),
+        runtime: runtime_name(runtime_target_from_kind(runtime)).to_string(),
+        runtime_version: pinned_runtime_version(root, runtime),
+        modules,
+    };
+    let dir = snapshot::save(root, &snapshot)?;
+    println!(
+        "Saved snapshot '{}' ({} modules, {} {}) to {:?}.",
+        name,
+        snapshot.modules.len(),
+        snapshot.runtime,
+        snapshot.runtime_version.as_deref().unwrap_or("version unknown"),
+        dir
+    );
     Ok(())
 }
 
-async fn list_dependencies(root: &Path) -> Result<()> {
+/// `lunu snapshot restore`: puts back the snapshot's files, reinstalls each locked git
+/// dependency at its locked commit (registry packages at their locked version), checks the
+/// modules against the snapshot's checksums and pins the runtime version it was taken with.
+/// lunu.lock is left exactly as the snapshot saved it.
+async fn restore_snapshot(root: &Path, name: &str, offline: bool) -> Result<()> {
+    let snapshot = snapshot::load(root, name)?;
+    let current = LockFile::load(&lock_path(root)).await.unwrap_or_default();
+    snapshot::restore_files(root, name)?;
+    let config_path = project_config_path(root);
+    let cfg = ProjectConfig::load(&config_path).await?;
     let lock = LockFile::load(&lock_path(root)).await?;
-    if lock.dependencies.is_empty() {
-        println!("No dependencies installed.");
-        return Ok(());
+    let dependencies = cfg.all_dependencies();
+    let pm = PackageManager::new(root.to_path_buf()).with_offline(offline);
+
+    // Modules installed after the snapshot was taken; local path dependencies are never deleted
+    for (dep, entry) in &current.dependencies {
+        let remote = entry.url.is_some() || entry.registry.is_some();
+        if remote && !lock.dependencies.contains_key(dep) {
+            if let Some(dir) = entry.path.as_ref().map(|p| root.join(p)).filter(|d| d.is_dir()) {
+                println!("Removing {} (not in the snapshot)", dep);
+                async_fs::remove_dir_all(dir).await?;
+            }
+        }
     }
 
+    for (dep, entry) in &lock.dependencies {
+        let spec = dependencies.get(dep).cloned().unwrap_or_else(|| DependencySpec {
+            url: entry.url.clone(),
+            subdir: entry.subdir.clone(),
+            ..Default::default()
+        });
+        if let Some(url) = &entry.url {
+            let reference = match &entry.commit {
+                Some(commit) => GitRef::Rev(commit.clone()),
+                None => spec.git_ref(entry.version.clone())?,
+            };
+            let installed = install_preferring_mirror(&pm, url, entry.mirror.as_deref(), &reference, dep, &spec).await?;
+            CompatibilityLayer::ensure_compat(&installed.path).await?;
+        } else if entry.registry.is_some() {
+            if offline {
+                println!("Warning: '{}' comes from a package registry and is left as installed (--offline).", dep);
+                continue;
+            }
+            install_registry_package(root, dep, &spec, entry.version.as_deref()).await?;
+        }
+    }
+
+    let mut differing = Vec::new();
+    for (dep, expected) in &snapshot.modules {
+        let Some(dir) = lock.dependencies.get(dep).and_then(|e| e.path.as_ref()).map(|p| root.join(p)) else {
+            continue;
+        };
+        if !dir.is_dir() {
+            differing.push(format!("{} (not installed)", dep));
+        } else if !pm.checksum_matches(&dir, expected).await? {
+            differing.push(dep.clone());
+        }
+    }
+
+    let target = match snapshot.runtime.as_str() {
+        "lune" => RuntimeTarget::Lune,
+        _ => RuntimeTarget::Lute,
+    };
+    let runtime = runtime_kind_from_config(&cfg);
+    if let Some(version) = snapshot.runtime_version.as_deref().filter(|v| !pinned_runtime_version(root, runtime).is_some_and(|p| version_tags(v).contains(&p))) {
+        if offline {
+            println!("Warning: the snapshot ran on {} {}; run 'lunu snapshot restore {}' online to pin it.", snapshot.runtime, version, name);
+        } else {
+            let (installed, binary) = runtime_binary_for_version(target, &HostPlatform::current(), Some(version))
+                .await
+                .with_context(|| format!("Could not restore snapshot '{}': the {} {} it ran on cannot be downloaded", name, snapshot.runtime, version))?;
+            fs::create_dir_all(root.join("bin"))?;
+            install_runtime_binary(&root.join("bin").join(runtime_bin_filename(target)), &binary)?;
+            println!("Pinned {} {} in bin/.", snapshot.runtime, installed);
+        }
+    }
+
+    if !differing.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Restored snapshot '{}', but these modules differ from it: {}. Modules edited in place before the snapshot cannot be reinstalled.",
+            name,
+            differing.join(", ")
+        ));
+    }
+    println!("Restored snapshot '{}' ({} dependencies).", name, lock.dependencies.len());
+    Ok(())
+}
+
+fn list_snapshots(root: &Path) {
+    let snapshots = snapshot::list(root);
+    if snapshots.is_empty() {
+        println!("No snapshots. Create one with 'lunu snapshot create <name>'.");
+        return;
+    }
+    let now = current_timestamp();
+    for snapshot in snapshots {
+        println!(
+            "{:<24} {:<10} {} modules, {} {}",
+            snapshot.name,
+            format_age(now.saturating_sub(snapshot.created_at)),
+            snapshot.modules.len(),
+            snapshot.runtime,
+            snapshot.runtime_version.as_deref().unwrap_or("version unknown")
+        );
+    }
+}
+
+async fn remove_dependency(root: &Path, lib: &str) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let mut cfg = ProjectConfig::load(&config_path).await?;
+    cfg.remove_dependency(lib);
+    cfg.save(&config_path).await?;
+
+    let mut lock = LockFile::load(&lock_path(root)).await?;
+    lock.remove(lib);
+    lock.save(&lock_path(root)).await?;
+
+    let meta_path = MetadataCache::path(root);
+    if meta_path.exists() {
+        let mut meta = MetadataCache::load(&meta_path).await?;
+        meta.remove(lib);
+        meta.save(&meta_path).await?;
+    }
+
+    let pm = PackageManager::new(root.to_path_buf());
+    pm.remove_package(lib).await?;
+
+    let luaurc_path = root.join(".luaurc");
+    let mut luaurc = Luaurc::load(&luaurc_path).await?;
+    luaurc.remove_alias(lib);
+    // Aliases added under another name (`lunu add --alias`) still point at the removed directory
+    let module_dir = format!("modules/{}", lib);
+    luaurc.aliases.retain(|_, target| target.trim_end_matches('/') != module_dir);
+    luaurc.save(&luaurc_path).await?;
+
+    println!("Removed dependency '{}'.", lib);
+    prune_modules(root, false, true).await?;
+    Ok(())
+}
+
+/// Installed modules nothing in lunu.toml refers to any more, e.g. left behind by a renamed alias
+/// or a dependency deleted from lunu.toml by hand. Only directories with the lunu.toml every
+/// install gets from the compatibility layer count, so modules written in place (`lunu module
+/// create`, the Lunu core library) are never pruned. With `locked_only`, only directories some
+/// lunu.lock entry records are.
+fn orphaned_modules(root: &Path, cfg: &ProjectConfig, lock: &LockFile, locked_only: bool) -> Vec<(String, PathBuf)> {
+    let normalize = |path: &str| path.trim_start_matches("./").replace('\\', "/").trim_end_matches('/').to_string();
+    let locked: std::collections::BTreeSet<String> = lock.dependencies.values().filter_map(|e| e.path.as_deref()).map(normalize).collect();
+    let mut referenced = std::collections::BTreeSet::new();
+    for (name, spec) in cfg.all_dependencies() {
+        referenced.extend(lock.dependencies.get(&name).and_then(|e| e.path.as_deref()).map(normalize));
+        referenced.extend(spec.path.as_deref().map(normalize));
+        referenced.insert(format!("modules/{}", name));
+    }
+    module_dirs(root)
+        .into_iter()
+        .filter(|(name, dir)| name != "lunu" && dir.join("lunu.toml").is_file())
+        .filter(|(name, _)| {
+            let path = format!("modules/{}", name);
+            !referenced.contains(&path) && (!locked_only || locked.contains(&path))
+        })
+        .collect()
+}
+
+/// Deletes `orphaned_modules` with their .luaurc aliases and drops lock entries of dependencies
+/// that are no longer in lunu.toml. With `dry_run`, only lists them. `install` and `remove` prune
+/// with `locked_only`, so a directory Lunu never recorded is only deleted by `lunu prune`.
+async fn prune_modules(root: &Path, dry_run: bool, locked_only: bool) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let mut lock = LockFile::load(&lock_path(root)).await?;
+    let orphans = orphaned_modules(root, &cfg, &lock, locked_only);
+    let dependencies = cfg.all_dependencies();
+    let stale: Vec<String> = lock.dependencies.keys().filter(|name| !dependencies.contains_key(*name)).cloned().collect();
+    if dry_run {
+        if orphans.is_empty() && stale.is_empty() {
+            println!("Nothing to prune.");
+            return Ok(());
+        }
+        if !orphans.is_empty() {
+            println!("Would delete {} unreferenced module(s):", orphans.len());
+            for (name, _) in &orphans {
+                println!("  - modules/{}", name);
+            }
+        }
+        if !stale.is_empty() {
+            println!("Would drop lunu.lock entries no longer in lunu.toml: {}", stale.join(", "));
+        }
+        println!("Run 'lunu prune' to delete them.");
+        return Ok(());
+    }
+
+    if !orphans.is_empty() {
+        let luaurc_path = root.join(".luaurc");
+        let mut luaurc = Luaurc::load(&luaurc_path).await?;
+        for (name, dir) in &orphans {
+            async_fs::remove_dir_all(dir).await.with_context(|| format!("Failed to delete {:?}", dir))?;
+            // An `@scope` directory left empty goes too
+            if let Some(scope) = dir.parent().filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with('@'))) {
+                if fs::read_dir(scope).is_ok_and(|mut entries| entries.next().is_none()) {
+                    let _ = fs::remove_dir(scope);
+                }
+            }
+            let module_dir = format!("modules/{}", name);
+            luaurc.aliases.retain(|_, target| target.trim_end_matches('/') != module_dir);
+        }
+        luaurc.save(&luaurc_path).await?;
+        println!("Pruned {} unreferenced module(s): {}", orphans.len(), orphans.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", "));
+    }
+    if !stale.is_empty() {
+        for name in &stale {
+            lock.remove(name);
+        }
+        lock.save(&lock_path(root)).await?;
+        println!("Dropped lunu.lock entries no longer in lunu.toml: {}", stale.join(", "));
+    }
+    Ok(())
+}
+
+/// Dependencies `lunu vendor` copies: all but path dependencies inside the project.
+fn vendorable_dependencies(cfg: &ProjectConfig) -> BTreeMap<String, DependencySpec> {
+    cfg.all_dependencies()
+        .into_iter()
+        .filter(|(_, spec)| spec.path.as_deref().is_none_or(|p| p.replace('\\', "/").trim_start_matches("./").starts_with("../")))
+        .collect()
+}
+
+/// `lunu vendor` (see `vendor`).
+async fn vendor_project(root: &Path) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let mut cfg = ProjectConfig::load(&config_path).await?;
+    let runtime = runtime_kind_from_config(&cfg);
+    let vendor_dir = root.join(vendor::DIR);
+    let source_of = |name: &str, spec: &DependencySpec, lock: &LockFile| -> Option<PathBuf> {
+        let installed = lock.dependencies.get(name).and_then(|e| e.path.clone()).or_else(|| spec.path.clone())
+            .unwrap_or_else(|| format!("{}/{}", cfg.project.modules_dir, name));
+        [root.join(installed), root.join(vendor::dependency_path(name))].into_iter().find(|dir| dir.is_dir())
+    };
+
+    // Vendoring copies what is installed, so anything missing is installed first
+    let dependencies = vendorable_dependencies(&cfg);
+    let lock = LockFile::load(&lock_path(root)).await?;
+    if dependencies.iter().any(|(name, spec)| !lock.dependencies.contains_key(name) || source_of(name, spec, &lock).is_none()) {
+        println!("Installing dependencies before vendoring...");
+        if cfg.project.vendored {
+            cfg.project.vendored = false;
+            cfg.save(&config_path).await?;
+        }
+        install_from_config(root, false, false).await?;
+    }
+    let lock = LockFile::load(&lock_path(root)).await?;
+
+    // Built beside vendor/ and swapped in at the end, as the old copies may be the sources
+    let staging = root.join(format!(".{}.partial", vendor::DIR));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let exclude = ExcludeSet::new(false, &[".git".to_string()], &[]);
+    let mut moves = Vec::new();
+    for (name, spec) in &dependencies {
+        let source = source_of(name, spec, &lock).ok_or_else(|| anyhow::anyhow!("Dependency '{}' is not installed", name))?;
+        copy_dir_filtered(&source, &staging.join(name), &exclude)?;
+        let installed = pathdiff::diff_paths(&source, root).unwrap_or(source).to_string_lossy().replace('\\', "/");
+        // `@scope/name` is required through the scope directory's alias
+        if let (Some((scope, _)), Some((scope_dir, _))) = (name.split_once('/'), installed.rsplit_once('/')) {
+            moves.push((scope_dir.to_string(), vendor::dependency_path(scope)));
+        }
+        moves.push((installed, vendor::dependency_path(name)));
+    }
+    let mut runtime_libraries = Vec::new();
+    if runtime == RuntimeKind::Lute {
+        let (lute, std) = match lute_library_dirs(true).await {
+            Ok(dirs) => dirs,
+            // Offline, a project vendored before keeps its copies
+            Err(err) if !vendor_dir.join(vendor::RUNTIME_DIR).is_dir() => return Err(err.context("Failed to get the Lute runtime libraries")),
+            Err(_) => (None, None),
+        };
+        for (alias, dir) in [("lute", lute), ("std", std)] {
+            let dir = dir.or_else(|| Some(root.join(vendor::runtime_path(alias))).filter(|d| d.is_dir()));
+            if let Some(dir) = dir {
+                copy_dir_filtered(&dir, &staging.join(vendor::RUNTIME_DIR).join(alias), &exclude)?;
+                runtime_libraries.push(alias);
+            }
+        }
+    }
+    if vendor_dir.exists() {
+        fs::remove_dir_all(&vendor_dir).with_context(|| format!("Failed to replace {:?}", vendor_dir))?;
+    }
+    if staging.exists() {
+        fs::rename(&staging, &vendor_dir).with_context(|| format!("Failed to write {:?}", vendor_dir))?;
+    }
+
+    if !cfg.project.vendored {
+        cfg.project.vendored = true;
+        cfg.save(&config_path).await?;
+    }
+    write_luaurc(root, &cfg.all_dependencies(), runtime, false).await?;
+    let luaurc_path = root.join(".luaurc");
+    let mut luaurc = Luaurc::load(&luaurc_path).await?;
+    for (alias, target) in vendor::rewrite_aliases(&luaurc.aliases, &moves) {
+        luaurc.add_alias(&alias, &target);
+    }
+    for name in dependencies.keys() {
+        let (alias, target) = match name.split_once('/') {
+            Some((scope, _)) => (scope.trim_start_matches('@'), vendor::dependency_path(scope)),
+            None => (name.as_str(), vendor::dependency_path(name)),
+        };
+        if !luaurc.aliases.values().any(|t| t.trim_end_matches('/') == target) {
+            luaurc.add_alias(alias, &format!("{}/", target));
+        }
+    }
+    luaurc.save(&luaurc_path).await?;
+
+    println!(
+        "Vendored {} dependenc{} into {}/ ({}).",
+        dependencies.len(),
+        if dependencies.len() == 1 { "y" } else { "ies" },
+        vendor::DIR,
+        lunu_builder::report::format_size(stats::dir_size(&vendor_dir))
+    );
+    if !runtime_libraries.is_empty() {
+        println!("Vendored the Lute runtime's @{} libraries into {}/{}/.", runtime_libraries.join(" and @"), vendor::DIR, vendor::RUNTIME_DIR);
+    }
+    println!("Commit {}/; 'lunu install' now checks it against lunu.lock instead of downloading.", vendor::DIR);
+    Ok(())
+}
+
+/// `lunu install` for a vendored project: every vendored dependency must be there and match
+/// lunu.lock; nothing is downloaded.
+async fn check_vendored(root: &Path, cfg: &ProjectConfig, lock: &LockFile) -> Result<()> {
+    let pm = PackageManager::new(root.to_path_buf()).with_offline(true);
+    let dependencies = vendorable_dependencies(cfg);
+    let mut problems = Vec::new();
+    for name in dependencies.keys() {
+        let dir = root.join(vendor::dependency_path(name));
+        let Some(entry) = lock.dependencies.get(name) else {
+            problems.push(format!("{} (not in lunu.lock)", name));
+            continue;
+        };
+        if !dir.is_dir() {
+            problems.push(format!("{} (not in {}/)", name, vendor::DIR));
+        } else if !entry.mutable && !entry.checksum.is_empty() && !pm.checksum_matches(&dir, &entry.checksum).await? {
+            problems.push(format!("{} (checksum differs from lunu.lock)", name));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{}/ does not match lunu.toml and lunu.lock:\n{}\nRun 'lunu vendor' to refresh it.",
+            vendor::DIR,
+            problems.iter().map(|p| format!("  - {}", p)).collect::<Vec<_>>().join("\n")
+        ));
+    }
+    println!("Project is vendored: {}/ matches lunu.lock, nothing to install.", vendor::DIR);
+    Ok(())
+}
+
+/// Removes the dependencies `lunu analyze requires` reports as unused.
+async fn remove_unused_dependencies(root: &Path, dry_run: bool) -> Result<()> {
+    let report = analyze_requires(root).await?;
+    if report.unused.is_empty() {
+        println!("No unused dependencies ({} files scanned).", report.files_scanned);
+        return Ok(());
+    }
+    if dry_run {
+        println!("Would remove {} unused dependencies:", report.unused.len());
+        for name in &report.unused {
+            println!("  - {}", name);
+        }
+        println!("Run 'lunu remove --unused' to remove them.");
+        return Ok(());
+    }
+    for name in &report.unused {
+        remove_dependency(root, name).await?;
+    }
+    Ok(())
+}
+
+/// `url` dependencies that `lunu install --offline` cannot find in the package cache, as
+/// `name (url)`. The lock's mirror counts, like it does online. Registry packages are not cached,
+/// so they only pass while their locked install is unchanged.
+async fn uncached_dependencies(root: &Path, pm: &PackageManager, dependencies: &BTreeMap<String, DependencySpec>, lock: &LockFile) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    for (name, spec) in dependencies {
+        if spec.url.is_none() && spec.is_remote() {
+            let origin = spec.registry().or_else(|| spec.wally.clone()).unwrap_or_default();
+            let entry = lock.dependencies.get(name).filter(|e| e.registry.as_ref() == Some(&origin));
+            let dir = entry.and_then(|e| e.path.as_ref()).map(|p| root.join(p)).filter(|d| d.is_dir());
+            let intact = match (entry, dir) {
+                (Some(entry), Some(dir)) => pm.checksum_matches(&dir, &entry.checksum).await?,
+                _ => false,
+            };
+            if !intact {
+                missing.push(format!("{} ({})", name, origin));
+            }
+            continue;
+        }
+        let Some(url) = &spec.url else {
+            continue;
+        };
+        let entry = lock.dependencies.get(name);
+        let locked = entry.filter(|e| e.url.as_ref() == Some(url)).and_then(|e| e.version.clone());
+        let reference = spec.git_ref(locked)?;
+        let mirror = entry.and_then(|e| e.mirror.as_deref());
+        if !pm.is_cached(url, &reference) && !mirror.is_some_and(|m| pm.is_cached(m, &reference)) {
+            missing.push(format!("{} ({})", name, url));
+        }
+    }
+    Ok(missing)
+}
+
+/// Resolver to record for `name` installed from `url`: the locked one while the source is
+/// unchanged, so repositories `lunu add` found by search keep saying so.
+fn url_resolver(lock: &LockFile, name: &str, url: &str) -> Option<Resolver> {
+    let kept = lock.dependencies.get(name).filter(|e| e.url.as_deref() == Some(url)).and_then(|e| e.resolver);
+    Some(kept.unwrap_or(Resolver::Url))
+}
+
+/// Installs from the lockfile's mirror when one is recorded, falling back to upstream, and keeps
+/// only the spec's `subdir` when it names one.
+async fn install_preferring_mirror(
+    pm: &PackageManager,
+    url: &str,
+    mirror: Option<&str>,
+    reference: &GitRef,
+    name: &str,
+    spec: &DependencySpec,
+) -> Result<InstalledPackage> {
+    let mut installed = None;
+    if let Some(mirror) = mirror {
+        match pm.install_package(mirror, reference, name, spec.fetch).await {
+            Ok(package) => installed = Some(package),
+            Err(err) => println!("Mirror {} unavailable ({:#}); falling back to {}", mirror, err, url),
+        }
+    }
+    let installed = match installed {
+        Some(package) => package,
+        None => pm.install_package(url, reference, name, spec.fetch).await?,
+    };
+    if let Some(subdir) = &spec.subdir {
+        package::keep_subdir(&installed.path, subdir).await.with_context(|| format!("Cannot install '{}' from {}", name, url))?;
+    }
+    Ok(installed)
+}
+
+/// `lunu add <query>[@<ref>]` split at the last `@`, leaving the user of `git@host:owner/repo` and
+/// `https://user@host/...` alone.
+fn split_requested(query: &str) -> (String, Option<String>) {
+    match query.rsplit_once('@') {
+        Some((q, r)) if !q.is_empty() && !r.is_empty() && !r.contains(':') && q.split_once("://").is_none_or(|(_, rest)| rest.contains('/')) => {
+            (q.to_string(), Some(r.to_string()))
+        }
+        _ => (query.to_string(), None),
+    }
+}
+
+/// A git URL from any host (GitHub, GitLab, Codeberg, a self-hosted server) or an archive URL,
+/// installed as given instead of searched for.
+fn package_from_url(query: &str) -> Option<PackageInfo> {
+    let url = query.trim();
+    let (owner, name) = if url.contains("://") && archive::is_download_url(url) {
+        let file = url.split(['?', '#']).next().unwrap_or(url).trim_end_matches('/').rsplit('/').next()?;
+        (String::new(), file.split('.').next().unwrap_or(file).to_string())
+    } else {
+        return package_from_git_url(url);
+    };
+    Some(PackageInfo { owner, name, url: url.to_string(), stars: 0, description: None, topics: Vec::new(), updated_at: None })
+}
+
+/// A repository by its HTTPS or SSH URL (`git@gitlab.com:org/repo.git`), for `lunu add --git`.
+fn package_from_git_url(url: &str) -> Option<PackageInfo> {
+    let url = url.trim();
+    let (_, path) = parse_git_remote(url)?;
+    let (owner, name) = path.rsplit_once('/')?;
+    Some(PackageInfo { owner: owner.to_string(), name: name.to_string(), url: url.to_string(), stars: 0, description: None, topics: Vec::new(), updated_at: None })
+}
+
+/// How `lunu add` installs a git dependency.
+#[derive(Debug, Default)]
+struct AddOptions {
+    /// Name under modules/ and in `.luaurc`; defaults to the repository (or `subdir`) name
+    alias: Option<String>,
+    /// Tag, branch or version requirement
+    requested: Option<String>,
+    /// Folder of the repository to install instead of all of it
+    subdir: Option<String>,
+    dev: bool,
+    review: bool,
+}
+
+/// Installs a search result of `lunu add`/`lunu search` or a git URL as `options` say.
+async fn add_git_dependency(root: &Path, target: &PackageInfo, options: AddOptions, resolver: Resolver) -> Result<()> {
+    let AddOptions { alias, requested, subdir, dev, review } = options;
+    // 1. Install
+    let pm = PackageManager::new(root.to_path_buf());
+    let default_name = subdir.as_deref().and_then(|s| s.rsplit('/').next()).unwrap_or(&target.name).to_string();
+    let install_name = alias.unwrap_or(default_name);
+    ensure_alias_free(root, &install_name, &format!("modules/{}/", install_name)).await?;
+    
+    let reference = match requested.as_deref() {
+        Some(name) => pm.classify_ref(&target.url, name).await?,
+        None => GitRef::Default,
+    };
+    let InstalledPackage { path, tag, version, commit } = pm.install_package(&target.url, &reference, &install_name, FetchMethod::Auto).await?;
+    if let Some(subdir) = &subdir {
+        package::keep_subdir(&path, subdir).await.with_context(|| format!("Cannot install {} from {}", subdir, target.url))?;
+    }
+
+    // 2. Compat (generated wrappers are part of the locked checksum)
+    CompatibilityLayer::ensure_compat(&path).await?;
+    if !review_permissions(root, &install_name, &path, review).await? {
+        async_fs::remove_dir_all(&path).await?;
+        println!("Removed {:?}; nothing was added.", path);
+        return Ok(());
+    }
+    let files = pm.file_hashes(&path).await?;
+    let checksum = package::root_checksum(&files);
+    println!("Installed to {:?} (Checksum: {})", path, checksum);
+
+    // 3. Update Config
+    let config_path = root.join(".luaurc");
+    let mut config = Luaurc::load(&config_path).await?;
+    
+    // Lunu specific mapping: mapping modules/name to alias
+    // Standard Lune alias format: "alias": "path/to/module"
+    // Relative to .luaurc
+    let rel_path = pathdiff::diff_paths(&path, root).unwrap_or(path);
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/") + "/"; // Add trailing slash for directory modules
+    
+    config.add_alias(&install_name, &rel_path_str);
+    config.save(&config_path).await?;
+    
+    println!("Updated .luaurc with alias '{}'", install_name);
+
+    let config_path = project_config_path(root);
+    let runtime = resolve_runtime_for_root(root).await?;
+    let build_cfg = Some(build_config_for(runtime, None));
+    let mut proj = load_or_init_project(root, &config_path, runtime, build_cfg).await?;
+    let rev = match &reference {
+        GitRef::Rev(rev) => Some(rev.clone()),
+        _ => None,
+    };
+    let mut spec = DependencySpec {
+        url: Some(target.url.clone()),
+        path: Some(rel_path_str.trim_end_matches('/').to_string()),
+        subdir: subdir.clone(),
+        ..Default::default()
+    };
+    match reference {
+        GitRef::Default => {}
+        GitRef::Version { req, .. } => spec.version = Some(req),
+        GitRef::Tag(tag) => spec.tag = Some(tag),
+        GitRef::Branch(branch) => spec.branch = Some(branch),
+        GitRef::Rev(rev) => spec.rev = Some(rev),
+    }
+    if dev {
+        proj.add_dev_dependency(&install_name, spec);
+    } else {
+        proj.add_dependency(&install_name, spec);
+    }
+    proj.save(&config_path).await?;
+
+    let lock_path = lock_path(root);
+    let mut lock = LockFile::load(&lock_path).await?;
+    lock.set(&install_name, LockEntry {
+        url: Some(target.url.clone()),
+        version: version.map(|v| v.to_string()),
+        tag,
+        rev,
+        path: Some(rel_path_str.trim_end_matches('/').to_string()),
+        subdir,
+        checksum,
+        files,
+        installed_at: current_timestamp(),
+        mirror: None,
+        registry: None,
+        commit,
+        resolver: Some(resolver),
+        installed_by: lock::current_actor(),
+        mutable: false,
+    });
+    lock.save(&lock_path).await?;
+
+    refresh_package_metadata(root, &[(install_name.clone(), target.url.clone())], true).await?;
+    Ok(())
+}
+
+/// The `[permissions]` policy of the project's lunu.toml, if it has one.
+async fn permission_policy(root: &Path) -> Result<Option<permissions::Policy>> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    Ok(ProjectConfig::load(&config_path).await?.permissions)
+}
+
+/// Bridge permissions a freshly installed module requests. With `review` they are listed and the
+/// user confirms (without a terminal, a module the policy denies is refused); otherwise only a
+/// module the policy denies is mentioned. Returns false when the module should not be kept.
+async fn review_permissions(root: &Path, name: &str, dir: &Path, review: bool) -> Result<bool> {
+    let Ok(bridge_json) = async_fs::read_to_string(dir.join("bridge.json")).await else {
+        if review {
+            println!("'{}' is not a bridge module; it requests no permissions.", name);
+        }
+        return Ok(true);
+    };
+    let requested = permissions::declared(&bridge_json)?;
+    let violations = permission_policy(root).await?.map(|p| p.violations(name, requested.as_ref())).unwrap_or_default();
+    if review {
+        let summary = requested.as_ref().map(|p| p.summary()).unwrap_or_else(|| "undeclared".to_string());
+        println!("'{}' requests bridge permissions: {}", name, summary);
+    }
+    for violation in &violations {
+        println!("  denied by [permissions] in lunu.toml: it {}", violation);
+    }
+    if !review {
+        if !violations.is_empty() {
+            println!("The bridge will refuse to run '{}' until [permissions] in lunu.toml allows it.", name);
+        }
+        return Ok(true);
+    }
+    if !(io::stdin().is_terminal() && stdin_is_interactive()) {
+        return Ok(violations.is_empty());
+    }
+    ask_yes_no(&mut io::stdin().lock(), &format!("Keep '{}'?", name), violations.is_empty())
+}
+
+/// `lunu add pesde:scope/name` or `lunu add wally:scope/name`: installs from a package registry
+/// instead of a GitHub repository and records the dependency with its registry origin.
+async fn add_registry_dependency(root: &Path, mut spec: DependencySpec, alias: Option<String>, dev: bool, review: bool) -> Result<()> {
+    let origin = spec.registry().ok_or_else(|| anyhow::anyhow!("Invalid registry package: {:?}", spec.wally))?;
+    let install_name = alias.unwrap_or_else(|| origin.rsplit('/').next().unwrap_or(&origin).to_string());
+    ensure_alias_free(root, &install_name, &format!("modules/{}/", install_name)).await?;
+    let entry = install_registry_package(root, &install_name, &spec, None).await?;
+    let rel_path = entry.path.clone().unwrap_or_else(|| format!("modules/{}", install_name));
+    if !review_permissions(root, &install_name, &root.join(&rel_path), review).await? {
+        async_fs::remove_dir_all(root.join(&rel_path)).await?;
+        println!("Removed {}; nothing was added.", rel_path);
+        return Ok(());
+    }
+    println!("Installed {} {} to {} (Checksum: {})", origin, entry.version.as_deref().unwrap_or("?"), rel_path, entry.checksum);
+
+    let luaurc_path = root.join(".luaurc");
+    let mut luaurc = Luaurc::load(&luaurc_path).await?;
+    luaurc.add_alias(&install_name, &format!("{}/", rel_path));
+    luaurc.save(&luaurc_path).await?;
+    println!("Updated .luaurc with alias '{}'", install_name);
+
+    let config_path = project_config_path(root);
+    let runtime = resolve_runtime_for_root(root).await?;
+    let build_cfg = Some(build_config_for(runtime, None));
+    let mut proj = load_or_init_project(root, &config_path, runtime, build_cfg).await?;
+    spec.path = Some(rel_path);
+    if dev {
+        proj.add_dev_dependency(&install_name, spec);
+    } else {
+        proj.add_dependency(&install_name, spec);
+    }
+    proj.save(&config_path).await?;
+
+    let lock_path = lock_path(root);
+    let mut lock = LockFile::load(&lock_path).await?;
+    lock.set(&install_name, entry);
+    lock.save(&lock_path).await
+}
+
+/// Installs a `pesde` or `wally` dependency into `modules/<name>`, keeping the `locked` version
+/// while it still satisfies the requirement, and returns its lock entry.
+async fn install_registry_package(root: &Path, name: &str, spec: &DependencySpec, locked: Option<&str>) -> Result<LockEntry> {
+    let rel_path = format!("modules/{}", name);
+    let dir = root.join(&rel_path);
+    let (origin, version, contents) = if let Some(package) = &spec.pesde {
+        let req = match &spec.version {
+            Some(req) => package::parse_version_req(req)?,
+            None => semver::VersionReq::STAR,
+        };
+        let targets = pesde::targets_for(resolve_runtime_for_root(root).await? == RuntimeKind::Lune);
+        let client = pesde::PesdeClient::from_env()?;
+        let releases = client.releases(package).await?;
+        let release = pesde::pick_release(&releases, &req, locked, targets).ok_or_else(|| {
+            anyhow::anyhow!("No release of pesde:{} matches '{}' for the {} target", package, req, targets.join(" or "))
+        })?;
+        println!("Downloading pesde:{} {} ({})...", package, release.version, release.target);
+        (pesde::origin(package), release.version.clone(), client.download(package, &release).await?)
+    } else if let Some(wally) = &spec.wally {
+        if spec.version.is_some() {
+            return Err(anyhow::anyhow!("Wally dependency '{}' takes its version in the package string (scope/name@1.2.3)", name));
+        }
+        let (package, req) = wally::parse_spec(wally)?;
+        let client = wally::WallyClient::from_env()?;
+        let versions = client.versions(&package).await?;
+        let version = wally::pick_version(&versions, &req, locked)
+            .ok_or_else(|| anyhow::anyhow!("No version of wally:{} matches '{}'", package, req))?;
+        println!("Downloading wally:{} {}...", package, version);
+        (wally::origin(&package), version.clone(), client.download(&package, &version).await?)
+    } else {
+        return Err(anyhow::anyhow!("Dependency '{}' is not from a package registry", name));
+    };
+
+    if dir.exists() {
+        async_fs::remove_dir_all(&dir).await?;
+    }
+    archive::unpack(&contents, &dir, None)?;
+    let unmanaged = if spec.pesde.is_some() { pesde::map_manifest(&dir)? } else { wally::map_project(&dir)? };
+    if !unmanaged.is_empty() {
+        println!("Note: {} depends on {}; these are not installed automatically.", origin, unmanaged.join(", "));
+    }
+    CompatibilityLayer::ensure_compat(&dir).await?;
+    let files = PackageManager::new(root.to_path_buf()).file_hashes(&dir).await?;
+    let checksum = package::root_checksum(&files);
+    Ok(LockEntry {
+        url: None,
+        version: Some(version.to_string()),
+        tag: None,
+        rev: None,
+        path: Some(rel_path),
+        subdir: None,
+        checksum,
+        files,
+        installed_at: current_timestamp(),
+        mirror: None,
+        registry: Some(origin),
+        commit: None,
+        resolver: Some(Resolver::Registry),
+        installed_by: lock::current_actor(),
+        mutable: false,
+    })
+}
+
+/// `git@internal:mirrors/` + `https://github.com/owner/json` -> `git@internal:mirrors/json.git`.
+fn mirror_url(to: &str, upstream: &str, dep: &str) -> String {
+    let to = to.trim();
+    if !to.ends_with('/') && !to.ends_with(':') {
+        return to.to_string();
+    }
+    let repo = parse_github_repo(upstream)
+        .map(|(_, repo)| repo)
+        .or_else(|| {
+            upstream
+                .trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .map(|r| r.trim_end_matches(".git").to_string())
+        })
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| dep.to_string());
+    format!("{}{}.git", to, repo)
+}
+
+fn run_git(args: &[&str], cwd: &Path) -> Result<()> {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .status()
+        .with_context(|| "Failed to run git. Is it installed and on PATH?")?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("git {} failed", args.join(" ")));
+    }
+    Ok(())
+}
+
+/// `value` as a positional git argument; one starting with `-` would be read as an option
+/// (`--upload-pack=...` runs a command), so it is refused.
+fn git_operand<'a>(value: &'a str, what: &str) -> Result<&'a str> {
+    if value.starts_with('-') {
+        return Err(anyhow::anyhow!("Refusing {} '{}': it would be passed to git as an option", what, value));
+    }
+    Ok(value)
+}
+
+async fn mirror_dependency(root: &Path, dep: &str, to: &str) -> Result<()> {
+    let mut lock = LockFile::load(&lock_path(root)).await?;
+    let entry = lock
+        .dependencies
+        .get(dep)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not in lunu.lock. Run 'lunu install' first.", dep))?;
+    let upstream = entry
+        .url
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("'{}' is a local path dependency; there is nothing to mirror.", dep))?;
+    let module_path = root.join(entry.path.as_deref().unwrap_or(&format!("modules/{}", dep)));
+    let reference = match &entry.tag {
+        Some(tag) => GitRef::Tag(tag.clone()),
+        None => ProjectConfig::load(&project_config_path(root))
+            .await?
+            .all_dependencies()
+            .get(dep)
+            .map(|spec| spec.git_ref(None))
+            .transpose()?
+            .unwrap_or(GitRef::Default),
+    };
+    let revision = entry.commit.clone()
+        .or_else(|| {
+            git2::Repository::open(&module_path)
+                .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id().to_string()))
+                .ok()
+        })
+        .or_else(|| PackageManager::new(root.to_path_buf()).cached_commit(&upstream, &reference))
+        .ok_or_else(|| anyhow::anyhow!("Could not read the installed revision of '{}' at {:?}; run 'lunu install' first", dep, module_path))?;
+    if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("Locked revision '{}' of '{}' is not a commit id", revision, dep));
+    }
+    let target = mirror_url(to, &upstream, dep);
+    git_operand(&upstream, "upstream URL")?;
+    git_operand(&target, "mirror URL")?;
+
+    // Installed modules are shallow clones, which cannot be pushed; take a full bare copy of upstream
+    let work = tempfile::tempdir()?;
+    let bare = work.path().join("repo.git");
+    println!("Fetching {} ...", upstream);
+    run_git(&["clone", "--bare", "--quiet", "--", &upstream, &bare.to_string_lossy()], work.path())?;
+    run_git(&["cat-file", "-e", &format!("{}^{{commit}}", revision)], &bare)
+        .with_context(|| format!("Locked revision {} no longer exists upstream", revision))?;
+    println!("Pushing {} (locked at {}) to {} ...", dep, &revision[..12.min(revision.len())], target);
+    run_git(&["push", "--quiet", "--", &target, "refs/heads/*:refs/heads/*", "refs/tags/*:refs/tags/*"], &bare)?;
+
+    if let Some(entry) = lock.dependencies.get_mut(dep) {
+        entry.mirror = Some(target.clone());
+    }
+    lock.save(&lock_path(root)).await?;
+    println!("lunu.lock now prefers {} for '{}' (falls back to {}).", target, dep, upstream);
+    Ok(())
+}
+
+/// Which dependencies `lunu update` moves, besides the single `lib` it may be given.
+struct UpdateFilter {
+    interactive: bool,
+    exclude: Vec<String>,
+    only: Option<String>,
+}
+
+impl UpdateFilter {
+    fn includes(&self, name: &str) -> bool {
+        !self.exclude.iter().any(|e| e == name) && self.only.as_deref().is_none_or(|glob| lunu_builder::exclude::wildcard(glob, name))
+    }
+}
+
+/// A row of `lunu update --interactive`: the locked and the available revision of a dependency.
+struct UpdateChoice {
+    name: String,
+    current: String,
+    available: String,
+    checked: bool,
+}
+
+fn short_commit(commit: &str) -> String {
+    commit.chars().take(7).collect()
+}
+
+/// What `lunu update` would move `spec` to: the newest version its requirement allows or the
+/// branch's current commit. Pinned tags and revs come back as themselves, with `true`.
+async fn update_candidate(root: &Path, pm: &PackageManager, spec: &DependencySpec) -> Result<(String, bool)> {
+    if let Some(url) = &spec.url {
+        if archive::is_download_url(url) {
+            return Ok(("latest download".to_string(), false));
+        }
+        return Ok(match spec.git_ref(None)? {
+            GitRef::Version { req, .. } => (pm.resolve_version(url, &req, None).await?.version.to_string(), false),
+            GitRef::Tag(tag) => (format!("{} (pinned)", tag), true),
+            GitRef::Rev(rev) => (format!("{} (pinned)", short_commit(&rev)), true),
+            reference => (pm.latest_commit(url, &reference).await?.map(|c| short_commit(&c)).unwrap_or_else(|| "unknown".to_string()), false),
+        });
+    }
+    if let Some(package) = &spec.pesde {
+        let req = match &spec.version {
+            Some(req) => package::parse_version_req(req)?,
+            None => semver::VersionReq::STAR,
+        };
+        let targets = pesde::targets_for(resolve_runtime_for_root(root).await? == RuntimeKind::Lune);
+        let releases = pesde::PesdeClient::from_env()?.releases(package).await?;
+        return Ok((pesde::pick_release(&releases, &req, None, targets).map(|r| r.version.to_string()).unwrap_or_else(|| "none".to_string()), false));
+    }
+    if let Some(wally) = &spec.wally {
+        let (package, req) = wally::parse_spec(wally)?;
+        let versions = wally::WallyClient::from_env()?.versions(&package).await?;
+        return Ok((wally::pick_version(&versions, &req, None).map(|v| v.to_string()).unwrap_or_else(|| "none".to_string()), false));
+    }
+    Ok(("local path".to_string(), true))
+}
+
+fn format_update_choice(index: usize, choice: &UpdateChoice, width: usize) -> String {
+    format!(
+        "  [{}] {}) {:<width$}  {} {} {}",
+        if choice.checked { "x" } else { " " },
+        index + 1,
+        choice.name,
+        choice.current,
+        console::glyph("→", "->"),
+        choice.available,
+        width = width
+    )
+}
+
+/// Lists `choices` with checkboxes and toggles them by number until Enter (or end of input).
+fn ask_update_selection(input: &mut impl BufRead, choices: &mut [UpdateChoice]) -> Result<()> {
+    let width = choices.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    loop {
+        for (index, choice) in choices.iter().enumerate() {
+            println!("{}", format_update_choice(index, choice, width));
+        }
+        print!("Toggle by number (e.g. \"1 3\"), a = all, n = none, Enter to update the checked ones: ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        match line.trim() {
+            "" => return Ok(()),
+            "a" => choices.iter_mut().for_each(|c| c.checked = true),
+            "n" => choices.iter_mut().for_each(|c| c.checked = false),
+            answer => {
+                let picked: Option<Vec<usize>> = answer
+                    .split([' ', ','])
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.parse::<usize>().ok().filter(|n| (1..=choices.len()).contains(n)))
+                    .collect();
+                match picked {
+                    Some(picked) => picked.into_iter().for_each(|n| choices[n - 1].checked = !choices[n - 1].checked),
+                    None => println!("Please enter numbers between 1 and {}, a or n.", choices.len()),
+                }
+            }
+        }
+    }
+}
+
+async fn update_dependencies(root: &Path, lib: Option<&str>, filter: &UpdateFilter, dry_run: bool) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let mut lock = LockFile::load(&lock_path(root)).await?;
+    let pm = PackageManager::new(root.to_path_buf());
+
+    let mut dependencies: BTreeMap<String, DependencySpec> = cfg
+        .all_dependencies()
+        .into_iter()
+        .filter(|(name, spec)| spec.is_remote() && lib.is_none_or(|lib| lib == name) && filter.includes(name))
+        .collect();
+    if filter.interactive {
+        if !(io::stdin().is_terminal() && stdin_is_interactive()) {
+            return Err(anyhow::anyhow!("'lunu update --interactive' needs a terminal; use --only and --exclude instead."));
+        }
+        let mut choices = Vec::new();
+        for (name, spec) in &dependencies {
+            let entry = lock.dependencies.get(name);
+            let current = entry
+                .and_then(|e| e.version.clone().or_else(|| e.tag.clone()).or_else(|| e.commit.as_deref().map(short_commit)))
+                .unwrap_or_else(|| "not installed".to_string());
+            let (available, checked) = match update_candidate(root, &pm, spec).await {
+                Ok((available, pinned)) => {
+                    let checked = !pinned && available != current;
+                    (available, checked)
+                }
+                Err(err) => (format!("unknown ({:#})", err), false),
+            };
+            choices.push(UpdateChoice { name: name.clone(), current, available, checked });
+        }
+        if choices.is_empty() {
+            println!("No dependencies to update.");
+            return Ok(());
+        }
+        ask_update_selection(&mut io::stdin().lock(), &mut choices)?;
+        let picked: std::collections::BTreeSet<String> = choices.into_iter().filter(|c| c.checked).map(|c| c.name).collect();
+        dependencies.retain(|name, _| picked.contains(name));
+        if dependencies.is_empty() {
+            println!("Nothing selected; lunu.lock left unchanged.");
+            return Ok(());
+        }
+    }
+
+    if dry_run {
+        return preview_updates(root, &lock, &dependencies).await;
+    }
+    for (name, spec) in &dependencies {
+        lock.set(name, updated_entry(root, &pm, &lock, name, spec).await?);
+    }
+
+    lock.save(&lock_path(root)).await?;
+    refresh_package_metadata(root, &remote_dependencies(&dependencies, None), true).await?;
+    println!("Dependencies updated.");
+    Ok(())
+}
+
+/// Installs the newest revision the spec of remote dependency `name` allows into `root` and returns
+/// its new lock entry.
+async fn updated_entry(root: &Path, pm: &PackageManager, lock: &LockFile, name: &str, spec: &DependencySpec) -> Result<LockEntry> {
+    let Some(url) = &spec.url else {
+        return install_registry_package(root, name, spec, None).await;
+    };
+    // Unlike install, update moves to the newest release the requirement allows
+    let mirror = lock.dependencies.get(name).and_then(|e| e.mirror.clone());
+    let installed = install_preferring_mirror(pm, url, mirror.as_deref(), &spec.git_ref(None)?, name, spec).await?;
+    let path = installed.path;
+    CompatibilityLayer::ensure_compat(&path).await?;
+    let files = pm.file_hashes(&path).await?;
+    let checksum = package::root_checksum(&files);
+    let rel_path = pathdiff::diff_paths(&path, root).unwrap_or(path);
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+    let resolver = url_resolver(lock, name, url);
+    Ok(LockEntry {
+        url: Some(url.clone()),
+        version: installed.version.map(|v| v.to_string()),
+        tag: installed.tag,
+        rev: spec.rev.clone(),
+        path: Some(rel_path_str),
+        subdir: spec.subdir.clone(),
+        checksum,
+        files,
+        installed_at: current_timestamp(),
+        mirror,
+        registry: None,
+        commit: installed.commit,
+        resolver,
+        installed_by: lock::current_actor(),
+        mutable: false,
+    })
+}
+
+/// `lunu update --dry-run`: installs each update into a scratch project (from the package cache
+/// when it has it) and prints the old and new ref and checksum of every dependency.
+async fn preview_updates(root: &Path, lock: &LockFile, dependencies: &BTreeMap<String, DependencySpec>) -> Result<()> {
+    if dependencies.is_empty() {
+        println!("No dependencies to update.");
+        return Ok(());
+    }
+    let scratch = tempfile::tempdir()?;
+    // Registry releases are picked for the project's runtime
+    fs::copy(project_config_path(root), project_config_path(scratch.path()))?;
+    let pm = PackageManager::new(scratch.path().to_path_buf());
+    let mut rows = Vec::new();
+    for (name, spec) in dependencies {
+        let updated = updated_entry(scratch.path(), &pm, lock, name, spec)
+            .await
+            .with_context(|| format!("Failed to resolve an update for '{}'", name))?;
+        rows.push((name.clone(), lock.dependencies.get(name).cloned(), updated));
+    }
+    let changed = rows.iter().filter(|(_, old, new)| old.as_ref().is_none_or(|old| old.checksum != new.checksum)).count();
+    for line in update_preview_lines(&rows) {
+        println!("{}", line);
+    }
+    println!("{} of {} dependencies would change. Dry run: nothing was written.", changed, rows.len());
+    Ok(())
+}
+
+/// How a lock entry names what it installed: version, tag or rev, and the commit when it adds something.
+fn locked_ref(entry: &LockEntry) -> String {
+    let named = entry.version.clone().or_else(|| entry.tag.clone()).or_else(|| entry.rev.as_deref().map(short_commit));
+    match (named, entry.commit.as_deref().map(short_commit)) {
+        (Some(named), Some(commit)) if !named.starts_with(&commit) => format!("{} ({})", named, commit),
+        (Some(named), _) => named,
+        (None, Some(commit)) => commit,
+        (None, None) => "-".to_string(),
+    }
+}
+
+/// The table `lunu update --dry-run` prints, one row per `(name, locked entry, updated entry)`.
+fn update_preview_lines(rows: &[(String, Option<LockEntry>, LockEntry)]) -> Vec<String> {
+    let arrow = console::glyph("→", "->");
+    // Without the `tree-sha256:` scheme prefix
+    let checksum = |entry: &LockEntry| entry.checksum.rsplit(':').next().unwrap_or_default().chars().take(12).collect::<String>();
+    let cells: Vec<[String; 4]> = rows
+        .iter()
+        .map(|(name, old, new)| {
+            let from = old.as_ref().map(locked_ref).unwrap_or_else(|| "not installed".to_string());
+            let change = match old {
+                Some(old) if old.checksum == new.checksum => "unchanged".to_string(),
+                Some(old) => format!("{} {} {}", checksum(old), arrow, checksum(new)),
+                None => format!("{} {}", arrow, checksum(new)),
+            };
+            [name.clone(), from, locked_ref(new), change]
+        })
+        .collect();
+    let header = ["Dependency", "Locked", "Update", "Checksum"].map(str::to_string);
+    let widths: Vec<usize> = (0..3).map(|i| cells.iter().chain([&header]).map(|row| row[i].chars().count()).max().unwrap_or(0)).collect();
+    std::iter::once(&header)
+        .chain(&cells)
+        .map(|row| format!("{:<w0$}  {:<w1$}  {:<w2$}  {}", row[0], row[1], row[2], row[3], w0 = widths[0], w1 = widths[1], w2 = widths[2]))
+        .collect()
+}
+
+async fn list_dependencies(root: &Path, verbose: bool) -> Result<()> {
+    let lock = LockFile::load(&lock_path(root)).await?;
+    if lock.dependencies.is_empty() {
+        println!("No dependencies installed.");
+        return Ok(());
+    }
+    let meta = if verbose {
+        MetadataCache::load(&MetadataCache::path(root)).await?
+    } else {
+        MetadataCache::default()
+    };
+
     for (name, entry) in lock.dependencies {
-        let source = entry.url.or(entry.path).unwrap_or_else(|| "unknown".to_string());
+        let source = entry.url.or(entry.registry).or(entry.path).unwrap_or_else(|| "unknown".to_string());
         let version = entry.version.unwrap_or_else(|| "latest".to_string());
         println!("{} | {} | {}", name, version, source);
+        if let Some(m) = meta.get(&name) {
+            if let Some(desc) = &m.description {
+                println!("    {}", desc);
+            }
+            let license = m.license.as_deref().unwrap_or("unknown");
+            let latest = m.latest_tags.first().map(String::as_str).unwrap_or("none");
+            println!("    license: {} | latest tag: {}", license, latest);
+        }
+    }
+    Ok(())
+}
+
+/// The project's dependencies as a `dep_tree::Graph`, with what each installed one declares.
+async fn dependency_graph(root: &Path) -> Result<dep_tree::Graph> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let mut graph = dep_tree::Graph { root: cfg.project.name.clone(), ..Default::default() };
+    for (name, spec) in cfg.all_dependencies() {
+        let entry = lock.dependencies.get(&name);
+        let source = entry
+            .and_then(|e| e.url.clone().or(e.registry.clone()).or(e.path.clone()))
+            .or_else(|| spec.url.clone().or(spec.registry()).or(spec.path.clone()))
+            .map(|s| if s.contains("://") { dep_tree::source_key(&s) } else { s })
+            .unwrap_or_else(|| "unknown".to_string());
+        let version = entry.and_then(|e| {
+            e.version.clone().or(e.tag.clone()).or(e.commit.as_ref().map(|c| c.chars().take(7).collect()))
+        });
+        let dir = if cfg.project.vendored && vendorable_dependencies(&cfg).contains_key(&name) {
+            root.join(vendor::dependency_path(&name))
+        } else {
+            root.join(entry.and_then(|e| e.path.clone()).or(spec.path.clone()).unwrap_or_else(|| format!("{}/{}", cfg.project.modules_dir, name)))
+        };
+        let installed = entry.is_some() && dir.is_dir();
+        graph.packages.insert(name.clone(), dep_tree::Package {
+            requires: if installed { dep_tree::read_requirements(&dir) } else { Vec::new() },
+            dev: cfg.dev_dependencies.contains_key(&name),
+            name,
+            version,
+            source,
+            installed,
+        });
+    }
+    Ok(graph)
+}
+
+/// `lunu tree`
+async fn show_dependency_tree(root: &Path, duplicates: bool) -> Result<()> {
+    let graph = dependency_graph(root).await?;
+    if duplicates {
+        let lines = graph.duplicates();
+        if lines.is_empty() {
+            println!("No package is installed more than once.");
+        }
+        for line in lines {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+    let (lines, cycles) = graph.render(console::unicode());
+    for line in lines {
+        println!("{}", line);
+    }
+    for cycle in cycles {
+        println!("Warning: dependency cycle {}", cycle.join(" -> "));
+    }
+    Ok(())
+}
+
+const METADATA_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+fn remote_dependencies(deps: &BTreeMap<String, DependencySpec>, only: Option<&str>) -> Vec<(String, String)> {
+    deps.iter()
+        .filter(|(name, _)| only.map(|o| o == name.as_str()).unwrap_or(true))
+        .filter_map(|(name, spec)| spec.url.clone().map(|url| (name.clone(), url)))
+        .filter(|(_, url)| !archive::is_download_url(url))
+        .collect()
+}
+
+/// Semver tags of any git remote, newest first, as `latest_tags` for hosts without an API.
+async fn git_tags_newest_first(url: &str) -> Result<Vec<String>> {
+    let tags = PackageManager::new(PathBuf::new()).list_refs(url).await?.tags;
+    let mut versions: Vec<(semver::Version, String)> = tags
+        .into_iter()
+        .filter_map(|tag| semver::Version::parse(tag.trim_start_matches(['v', 'V'])).ok().map(|v| (v, tag)))
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(versions.into_iter().take(5).map(|(_, tag)| tag).collect())
+}
+
+/// Metadata from the GitHub or GitLab API; other hosts (Codeberg, self-hosted git) only get
+/// their tags, read over git.
+async fn fetch_package_metadata(url: &str) -> Result<PackageMetadata> {
+    let remote = if let Some((owner, repo)) = parse_github_repo(url) {
+        GithubClient::new(None)?.fetch_metadata(&owner, &repo).await?
+    } else if let Some((host, path)) = gitlab::parse_project(url) {
+        gitlab::fetch_metadata(&host, &path).await?
+    } else {
+        github::RepoMetadata { latest_tags: git_tags_newest_first(url).await?, ..Default::default() }
+    };
+    Ok(PackageMetadata {
+        url: url.to_string(),
+        description: remote.description,
+        license: remote.license,
+        latest_tags: remote.latest_tags,
+        readme_summary: remote.readme.as_deref().and_then(readme_summary),
+        fetched_at: current_timestamp(),
+    })
+}
+
+/// Best effort: metadata is informational, so fetch failures only warn.
+async fn refresh_package_metadata(root: &Path, packages: &[(String, String)], force: bool) -> Result<()> {
+    if packages.is_empty() {
+        return Ok(());
+    }
+    let path = MetadataCache::path(root);
+    let mut cache = MetadataCache::load(&path).await?;
+    let now = current_timestamp();
+    let mut changed = false;
+    for (name, url) in packages {
+        let fresh = cache
+            .get(name)
+            .map(|m| m.url == *url && !m.is_stale(now, METADATA_MAX_AGE_SECS))
+            .unwrap_or(false);
+        if fresh && !force {
+            timings::cache_hit("metadata");
+            continue;
+        }
+        timings::cache_miss("metadata");
+        match fetch_package_metadata(url).await {
+            Ok(meta) => {
+                cache.set(name, meta);
+                changed = true;
+            }
+            Err(err) => println!("Warning: could not fetch metadata for '{}': {}", name, err),
+        }
+    }
+    if changed {
+        cache.save(&path).await?;
+    }
+    Ok(())
+}
+
+async fn show_package_info(root: &Path, lib: &str, refresh: bool) -> Result<()> {
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let entry = lock.dependencies.get(lib)
+        .ok_or_else(|| anyhow::anyhow!("Dependency '{}' is not installed.", lib))?;
+
+    if let Some(url) = &entry.url {
+        let cache = MetadataCache::load(&MetadataCache::path(root)).await?;
+        if refresh || cache.get(lib).is_none() {
+            refresh_package_metadata(root, &[(lib.to_string(), url.clone())], true).await?;
+        }
+    }
+    let cache = MetadataCache::load(&MetadataCache::path(root)).await?;
+
+    println!("{}", lib);
+    println!("  version: {}", entry.version.as_deref().unwrap_or("latest"));
+    if let Some(url) = &entry.url {
+        println!("  url: {}", url);
+    }
+    if let Some(path) = &entry.path {
+        println!("  path: {}", path);
+    }
+    if entry.mutable {
+        println!("  checksum: not enforced (mutable)");
+    } else {
+        println!("  checksum: {}", entry.checksum);
+    }
+    match cache.get(lib) {
+        Some(meta) => {
+            if let Some(desc) = &meta.description {
+                println!("  description: {}", desc);
+            }
+            println!("  license: {}", meta.license.as_deref().unwrap_or("unknown"));
+            if !meta.latest_tags.is_empty() {
+                println!("  latest tags: {}", meta.latest_tags.join(", "));
+            }
+            if let Some(summary) = &meta.readme_summary {
+                println!("  readme: {}", summary);
+            }
+        }
+        None => println!("  (no cached metadata)"),
+    }
+    Ok(())
+}
+
+/// `lunu provenance <dep>`: where a locked dependency came from and who installed it.
+async fn show_provenance(root: &Path, dep: &str, json: bool) -> Result<()> {
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let entry = lock.dependencies.get(dep)
+        .ok_or_else(|| anyhow::anyhow!("Dependency '{}' is not in lunu.lock.", dep))?;
+    if json {
+        let mut value = serde_json::to_value(entry)?;
+        value["name"] = Value::String(dep.to_string());
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    let unknown = "unknown (locked before provenance was recorded)";
+    println!("{}", dep);
+    let source = entry.url.as_ref().or(entry.registry.as_ref()).or(entry.path.as_ref());
+    println!("  source: {}", source.map(String::as_str).unwrap_or("unknown"));
+    if let Some(mirror) = &entry.mirror {
+        println!("  mirror: {}", mirror);
+    }
+    match (&entry.version, &entry.tag) {
+        (Some(version), Some(tag)) if tag.trim_start_matches(['v', 'V']) != version => println!("  version: {} (tag {})", version, tag),
+        (Some(version), _) => println!("  version: {}", version),
+        (None, Some(tag)) => println!("  version: tag {}", tag),
+        (None, None) => {}
+    }
+    if entry.url.as_deref().is_some_and(|url| !archive::is_download_url(url)) {
+        println!("  commit: {}", entry.commit.as_deref().unwrap_or(unknown));
+    }
+    let resolver = match entry.resolver {
+        Some(Resolver::Search) => "search (top GitHub search result for 'lunu add')",
+        Some(Resolver::Url) => "url (from lunu.toml)",
+        Some(Resolver::Registry) => "registry",
+        Some(Resolver::Path) => "path (local directory)",
+        None => unknown,
+    };
+    println!("  resolver: {}", resolver);
+    println!("  installed: {} by {}", format_utc(entry.installed_at), entry.installed_by.as_deref().unwrap_or("unknown user"));
+    if entry.mutable {
+        println!("  checksum: not enforced (mutable)");
+    } else {
+        println!("  checksum: {}", entry.checksum);
+    }
+    Ok(())
+}
+
+async fn package_project(root: &Path, include_all: bool) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let exclude = if include_all {
+        ExcludeSet::none()
+    } else {
+        exclude_set_for(cfg.build.as_ref().and_then(|b| b.exclude.as_ref()))
+    };
+    let dist_dir = root.join("dist");
+    if dist_dir.exists() {
+        async_fs::remove_dir_all(&dist_dir).await?;
+    }
+    async_fs::create_dir_all(&dist_dir).await?;
+
+    let entry_path = PathBuf::from(&cfg.project.entry);
+    let stem = entry_path.file_stem().and_then(|s| s.to_str()).unwrap_or("main");
+    let exe_path = if let Some(ext) = executable_extension() {
+        root.join(format!("{}.{}", stem, ext))
+    } else {
+        root.join(stem)
+    };
+    if exe_path.exists() {
+        async_fs::copy(&exe_path, dist_dir.join(exe_path.file_name().unwrap())).await?;
+    } else {
+        println!("Warning: executable not found at {:?}", exe_path);
+    }
+
+    let modules_dir = root.join("modules");
+    if modules_dir.exists() {
+        let exclude = exclude.clone().with_paths(&cfg.dev_module_paths());
+        copy_dir_filtered(&modules_dir, &dist_dir.join("modules"), &exclude)?;
+    }
+
+    let assets_dir = root.join("assets");
+    if assets_dir.exists() {
+        copy_dir_filtered(&assets_dir, &dist_dir.join("assets"), &exclude)?;
+    }
+
+    let lock_path = lock_path(root);
+    if lock_path.exists() {
+        async_fs::copy(&lock_path, dist_dir.join("lunu.lock")).await?;
+    }
+    async_fs::copy(&config_path, dist_dir.join("lunu.toml")).await?;
+
+    println!("Package created at {:?}", dist_dir);
+    Ok(())
+}
+
+async fn package_signing(root: &Path) -> Option<project::BuildSigning> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return None;
+    }
+    ProjectConfig::load(&config_path).await.ok()?.build?.signing
+}
+
+/// Writes `dir/SHA256SUMS` and, when a key is configured, its minisign signature.
+async fn write_checksums(root: &Path, dir: &Path, sign_key: Option<PathBuf>) -> Result<()> {
+    let sums = checksums::write_sums(dir)?;
+    println!("Checksums written to {:?}", sums);
+    let key_path = match sign_key {
+        Some(path) => Some(path),
+        None => package_signing(root).await.and_then(|s| s.key).map(PathBuf::from),
+    };
+    let Some(key_path) = key_path else {
+        return Ok(());
+    };
+    let key_path = if key_path.is_absolute() { key_path } else { root.join(key_path) };
+    let key = lunu_builder::updates::load_signing_key(&key_path)?;
+    let signature = checksums::sign_sums(dir, &key)?;
+    println!("Signature written to {:?}", signature);
+    println!(
+        "Verify with: lunu package verify --public-key {}\n         or: minisign -Vm SHA256SUMS -P {}",
+        hex::encode(key.verifying_key().to_bytes()),
+        lunu_builder::minisign::public_key_base64(&key.verifying_key())
+    );
+    Ok(())
+}
+
+async fn verify_package(root: &Path, dir: &Path, public_key: Option<&str>, ignore_missing: bool) -> Result<()> {
+    let public_key = match public_key {
+        Some(key) => Some(key.to_string()),
+        None => package_signing(root).await.and_then(|s| s.public_key),
+    };
+    let public_key = match public_key {
+        Some(key) => {
+            let path = root.join(&key);
+            let text = if path.is_file() { fs::read_to_string(&path)? } else { key };
+            Some(lunu_builder::minisign::parse_public_key(&text)?)
+        }
+        None => None,
+    };
+    let result = checksums::verify_dir(dir, public_key.as_ref())?;
+    for name in &result.mismatched {
+        println!("FAILED   {}", name);
+    }
+    for name in &result.missing {
+        println!("MISSING  {}", name);
+    }
+    match &result.signed {
+        Some(comment) => println!("Signature OK ({})", comment.replace('\t', " ")),
+        None if dir.join(checksums::SIGNATURE_FILE).exists() => {
+            println!("Warning: {} was not checked; pass --public-key to verify it.", checksums::SIGNATURE_FILE)
+        }
+        None => {}
+    }
+    if !result.ok(ignore_missing) {
+        return Err(anyhow::anyhow!(
+            "Verification failed: {} mismatched, {} missing",
+            result.mismatched.len(),
+            result.missing.len()
+        ));
+    }
+    println!("{} file(s) verified.", result.verified.len());
+    Ok(())
+}
+
+struct ReleaseOptions {
+    tag: String,
+    repo: Option<String>,
+    platforms: Vec<String>,
+    notes: Option<PathBuf>,
+    draft: bool,
+    prerelease: bool,
+    dry_run: bool,
+}
+
+fn git_output(root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).current_dir(root).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn previous_tag(root: &Path, tag: &str) -> Option<String> {
+    let latest = git_output(root, &["describe", "--tags", "--abbrev=0", "HEAD"])?;
+    if latest == tag {
+        git_output(root, &["describe", "--tags", "--abbrev=0", "HEAD^"])
+    } else {
+        Some(latest)
+    }
+}
+
+fn format_release_notes(tag: &str, previous: Option<&str>, commits: &[String]) -> String {
+    let mut notes = String::from("## Changes\n\n");
+    if commits.is_empty() {
+        notes.push_str("- No changes recorded\n");
+    }
+    for commit in commits {
+        notes.push_str(&format!("- {}\n", commit));
+    }
+    if let Some(previous) = previous {
+        notes.push_str(&format!("\n**Full changelog**: {}...{}\n", previous, tag));
+    }
+    notes
+}
+
+/// Commit subjects since the previous tag, newest first.
+fn draft_release_notes(root: &Path, tag: &str) -> String {
+    let previous = previous_tag(root, tag);
+    let range = previous.as_ref().map(|p| format!("{}..HEAD", p)).unwrap_or_else(|| "HEAD".to_string());
+    let log = git_output(root, &["log", "--no-merges", "--pretty=format:%s (%h)", &range]).unwrap_or_default();
+    let commits: Vec<String> = log.lines().map(String::from).collect();
+    format_release_notes(tag, previous.as_deref(), &commits)
+}
+
+/// Asset names carry the same `<os>-<arch>` suffix the runtime and self-update pickers look for.
+fn release_asset_name(project: &str, tag: &str, platform: &HostPlatform, extension: Option<&str>) -> String {
+    let name = format!("{}-{}-{}", project, tag, platform.label());
+    match extension {
+        Some(ext) => format!("{}.{}", name, ext),
+        None => name,
+    }
+}
+
+fn release_repo(root: &Path, repo: Option<&str>) -> Result<(String, String)> {
+    let parsed = match repo {
+        Some(repo) => repo
+            .trim()
+            .split_once('/')
+            .filter(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+            .map(|(owner, name)| (owner.to_string(), name.to_string())),
+        None => git_output(root, &["remote", "get-url", "origin"]).and_then(|url| parse_github_repo(&url)),
+    };
+    parsed.ok_or_else(|| anyhow::anyhow!("Could not determine the GitHub repository. Pass --repo <owner/repo>."))
+}
+
+async fn release_project(root: &Path, options: &ReleaseOptions) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let (owner, repo) = release_repo(root, options.repo.as_deref())?;
+    let gh = GithubClient::from_env()?;
+    if !options.dry_run && !gh.has_token() {
+        return Err(anyhow::anyhow!("Set GITHUB_TOKEN (or GH_TOKEN) to publish releases."));
+    }
+
+    let staging = root.join("dist").join(format!("release-{}", options.tag));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    if !options.platforms.is_empty() {
+        package_platforms(root, false, &options.platforms).await?;
+        for spec in &options.platforms {
+            let label = HostPlatform::parse(spec)?.label();
+            let bundle = format!("{}-{}.zip", cfg.project.name, label);
+            let target = format!("{}-{}-{}.zip", cfg.project.name, options.tag, label);
+            fs::copy(root.join("dist").join(&bundle), staging.join(target))?;
+        }
+    }
+
+    let exe_name = release_asset_name(&cfg.project.name, &options.tag, &HostPlatform::current(), executable_extension());
+    println!("Building {}...", exe_name);
+    let status = Command::new(std::env::current_exe()?)
+        .arg("build")
+        .arg(&cfg.project.entry)
+        .arg("--output")
+        .arg(staging.join(&exe_name))
+        .current_dir(root)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Build failed; nothing was published."));
+    }
+    write_checksums(root, &staging, None).await?;
+
+    let notes = match &options.notes {
+        Some(path) => fs::read_to_string(root.join(path)).with_context(|| format!("Failed to read {:?}", path))?,
+        None => draft_release_notes(root, &options.tag),
+    };
+    let mut assets: Vec<PathBuf> = fs::read_dir(&staging)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    assets.sort();
+
+    if options.dry_run {
+        println!("Dry run: staged {} artifact(s) in {:?} for {}/{}.", assets.len(), staging, owner, repo);
+        println!("\n{}", notes);
+        return Ok(());
+    }
+
+    let release = gh
+        .create_release(&owner, &repo, &github::NewRelease {
+            tag_name: options.tag.clone(),
+            target_commitish: git_output(root, &["rev-parse", "HEAD"]),
+            name: options.tag.clone(),
+            body: notes,
+            draft: options.draft,
+            prerelease: options.prerelease,
+        })
+        .await?;
+    for asset in &assets {
+        let name = asset.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        println!("Uploading {}...", name);
+        gh.upload_release_asset(&release, &name, fs::read(asset)?).await?;
+    }
+    println!("Published {} ({} assets): {}", options.tag, assets.len(), release.html_url);
+    Ok(())
+}
+
+/// `lunu publish`: packs the library, creates and pushes its `v<version>` tag and publishes a
+/// GitHub release with the archive attached.
+async fn publish_library(root: &Path, repo: Option<&str>, notes: Option<&Path>, dry_run: bool) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let problems = publish::metadata_problems(root, &cfg);
+    if !problems.is_empty() {
+        return Err(anyhow::anyhow!("Cannot publish {}:\n  {}", cfg.project.name, problems.join("\n  ")));
+    }
+    let version = cfg.project.version.clone().unwrap_or_default();
+    let tag = format!("v{}", version);
+    let (owner, repo) = release_repo(root, repo)?;
+    let gh = GithubClient::from_env()?;
+    if !dry_run && !gh.has_token() {
+        return Err(anyhow::anyhow!("Set GITHUB_TOKEN (or GH_TOKEN) to publish releases."));
+    }
+    if git_output(root, &["rev-parse", "-q", "--verify", &format!("refs/tags/{}", tag)]).is_some() {
+        return Err(anyhow::anyhow!("Tag {} already exists. Raise [project] version in lunu.toml first.", tag));
+    }
+    if !dry_run && git_output(root, &["status", "--porcelain", "--untracked-files=no"]).is_some() {
+        return Err(anyhow::anyhow!("The working tree has uncommitted changes; commit them so {} tags what is published.", tag));
+    }
+
+    let exclude = publish::package_exclude(root, &cfg, exclude_set_for(cfg.build.as_ref().and_then(|b| b.exclude.as_ref())));
+    let (archive, files) = publish::pack(&publish::library_dir(root, &cfg), &exclude)?;
+    let asset_name = format!("{}-{}.tar.gz", cfg.project.name, version);
+    let staging = root.join("dist").join(format!("publish-{}", tag));
+    fs::create_dir_all(&staging)?;
+    fs::write(staging.join(&asset_name), &archive)?;
+    println!("Packed {} file(s) into {:?}.", files.len(), staging.join(&asset_name));
+
+    let notes = match notes {
+        Some(path) => fs::read_to_string(root.join(path)).with_context(|| format!("Failed to read {:?}", path))?,
+        None => draft_release_notes(root, &tag),
+    };
+    if dry_run {
+        for file in &files {
+            println!("  {}", file);
+        }
+        println!("Dry run: would tag {} and publish it to {}/{}.", tag, owner, repo);
+        println!("\n{}", notes);
+        return Ok(());
+    }
+
+    run_git(&["tag", "-a", &tag, "-m", &format!("{} {}", cfg.project.name, tag)], root)?;
+    run_git(&["push", "origin", &tag], root).with_context(|| format!("Tag {} was created locally but could not be pushed", tag))?;
+    let release = gh
+        .create_release(&owner, &repo, &github::NewRelease {
+            tag_name: tag.clone(),
+            target_commitish: None,
+            name: format!("{} {}", cfg.project.name, tag),
+            body: notes,
+            draft: false,
+            prerelease: semver::Version::parse(&version).is_ok_and(|v| !v.pre.is_empty()),
+        })
+        .await?;
+    println!("Uploading {}...", asset_name);
+    gh.upload_release_asset(&release, &asset_name, archive).await?;
+    println!("Published {} {}: {}", cfg.project.name, tag, release.html_url);
+    println!("Install it with 'lunu add {}/{}@{}'.", owner, repo, tag);
+    Ok(())
+}
+
+/// Builds `dist/<name>-<platform>/` and a matching zip for each platform: the project sources
+/// plus that platform's runtime binary and a launcher script.
+async fn package_platforms(root: &Path, include_all: bool, platforms: &[String]) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let platforms = platforms.iter().map(|p| HostPlatform::parse(p)).collect::<Result<Vec<_>>>()?;
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let runtime = runtime_kind_from_config(&cfg);
+    for platform in &platforms {
+        let binary = platform_runtime_binary(root, runtime, platform).await?;
+        let bundle = assemble_platform_bundle(root, &cfg, include_all, platform, &binary)?;
+        println!("Package created at {:?}", bundle);
+    }
+    Ok(())
+}
+
+/// Runs the smoke test of `lunu package` (`--smoke`, `--smoke-script` or `[build.smoke]`) against
+/// what it just produced: a copy of dist/ and its executable, or the bundle for this machine
+/// unpacked from its zip. Bundles for other platforms cannot run here and are skipped.
+async fn smoke_test_package(root: &Path, platforms: &[String], expect: Option<String>, script: Option<PathBuf>) -> Result<()> {
+    let cfg = ProjectConfig::load(&project_config_path(root)).await?;
+    let settings = cfg.build.as_ref().and_then(|b| b.smoke.clone());
+    if settings.is_none() && expect.is_none() && script.is_none() {
+        return Ok(());
+    }
+    let settings = settings.unwrap_or_default();
+    let test = smoke::SmokeTest {
+        expect: expect.or(settings.expect),
+        script: script.or_else(|| settings.script.map(|s| root.join(s))),
+        args: settings.args,
+        timeout: settings.timeout.map(Duration::from_secs).unwrap_or(smoke::DEFAULT_TIMEOUT),
+    };
+    let dist_dir = root.join("dist");
+    let staging = tempfile::tempdir()?;
+
+    if platforms.is_empty() {
+        let stem = Path::new(&cfg.project.entry).file_stem().and_then(|s| s.to_str()).unwrap_or("main").to_string();
+        let exe = match executable_extension() {
+            Some(ext) => format!("{}.{}", stem, ext),
+            None => stem,
+        };
+        if !dist_dir.join(&exe).is_file() {
+            return Err(anyhow::anyhow!("Smoke test failed: the package has no {} to run; run 'lunu build' before 'lunu package'", exe));
+        }
+        let dir = staging.path().join(&cfg.project.name);
+        copy_dir_filtered(&dist_dir, &dir, &ExcludeSet::none())?;
+        test.run(&dir, &dir.join(&exe))?;
+        println!("Smoke test passed ({}).", exe);
+        return Ok(());
+    }
+
+    let host = HostPlatform::current();
+    let target = runtime_target_from_kind(runtime_kind_from_config(&cfg));
+    let mut tested = false;
+    for platform in platforms.iter().map(|p| HostPlatform::parse(p)).collect::<Result<Vec<_>>>()? {
+        if platform.label() != host.label() {
+            println!("Skipping the smoke test of the {} bundle; it cannot run on this machine.", platform.label());
+            continue;
+        }
+        let bundle_name = format!("{}-{}", cfg.project.name, platform.label());
+        let dir = staging.path().join(&bundle_name);
+        archive::unpack(&fs::read(dist_dir.join(format!("{}.zip", bundle_name)))?, &dir, None)?;
+        archive::strip_single_root(&dir)?;
+        // Zip entries are unpacked without their modes
+        let (launcher, _) = platform_launcher(target, &platform, &cfg.project.entry);
+        ensure_executable(&dir.join("bin").join(platform_bin_filename(target, &platform)))?;
+        ensure_executable(&dir.join(&launcher))?;
+        test.run(&dir, &dir.join(&launcher))?;
+        println!("Smoke test passed ({}).", bundle_name);
+        tested = true;
+    }
+    if !tested {
+        println!("No bundle for this machine ({}); the smoke test did not run.", host.label());
+    }
+    Ok(())
+}
+
+fn platform_bin_filename(target: RuntimeTarget, platform: &HostPlatform) -> String {
+    if platform.os == "windows" {
+        format!("{}.exe", runtime_name(target))
+    } else {
+        runtime_name(target).to_string()
+    }
+}
+
+/// Runtime binaries for other platforms live beside, not in place of, the host install.
+fn platform_runtime_cache_path(target: RuntimeTarget, platform: &HostPlatform, version: &str) -> PathBuf {
+    runtime_cache_dir()
+        .join("platforms")
+        .join(format!("{}-{}", runtime_name(target), version))
+        .join(platform.label())
+        .join(platform_bin_filename(target, platform))
+}
+
+async fn fetch_release_by_tag(target: RuntimeTarget, tag: &str) -> Result<GithubRelease> {
+    let url = format!("https://api.github.com/repos/{}/releases/tags/{}", runtime_repo(target), tag);
+    let client = http::client()?;
+    http::get_json(&client, &url).await
+}
+
+/// The tags `version` may be released under: as given, and with the `v` prefix added or removed
+/// (`--version` prints `0.8.9` for the release tagged `v0.8.9`).
+fn version_tags(version: &str) -> [String; 2] {
+    match version.strip_prefix('v') {
+        Some(bare) => [version.to_string(), bare.to_string()],
+        None => [version.to_string(), format!("v{}", version)],
+    }
+}
+
+/// The release of exactly `version`, under either form of its tag.
+async fn fetch_release_for_version(target: RuntimeTarget, version: &str) -> Result<GithubRelease> {
+    let [tag, other] = version_tags(version);
+    match fetch_release_by_tag(target, &tag).await {
+        Ok(release) => Ok(release),
+        Err(err) => fetch_release_by_tag(target, &other)
+            .await
+            .map_err(|_| err.context(format!("{} release {} not found", runtime_name(target), version))),
+    }
+}
+
+/// The runtime binary for `platform`, matching the version the project runs on locally when known.
+async fn platform_runtime_binary(root: &Path, runtime: RuntimeKind, platform: &HostPlatform) -> Result<Vec<u8>> {
+    let target = runtime_target_from_kind(runtime);
+    let pinned = pinned_runtime_version(root, runtime);
+    match runtime_binary_for_version(target, platform, pinned.as_deref()).await {
+        Ok((_, binary)) => Ok(binary),
+        Err(err) if pinned.is_some() => {
+            println!("Warning: {:#}; packaging the latest {} for {} instead.", err, runtime_name(target), platform.label());
+            Ok(runtime_binary_for_version(target, platform, None).await?.1)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Downloads (or reuses the cached) `version` of the runtime for `platform`, or the latest release
+/// when unknown, with the version it is. Fails rather than substitute another version for `pinned`.
+async fn runtime_binary_for_version(target: RuntimeTarget, platform: &HostPlatform, pinned: Option<&str>) -> Result<(String, Vec<u8>)> {
+    if let Some(version) = pinned {
+        for tag in version_tags(version) {
+            let cached = platform_runtime_cache_path(target, platform, &tag);
+            if cached.exists() {
+                timings::cache_hit("runtime");
+                return Ok((tag, fs::read(cached)?));
+            }
+        }
+    }
+    let release = match pinned {
+        Some(version) => fetch_release_for_version(target, version).await?,
+        None => fetch_latest_release(target).await?,
+    };
+    let binary = download_platform_runtime(target, &release, platform).await?;
+    Ok((release.tag_name, binary))
+}
+
+/// Downloads `release` for `platform` into the per-platform runtime cache (reusing a cached copy).
+async fn download_platform_runtime(target: RuntimeTarget, release: &GithubRelease, platform: &HostPlatform) -> Result<Vec<u8>> {
+    let cached = platform_runtime_cache_path(target, platform, &release.tag_name);
+    if cached.exists() {
+        timings::cache_hit("runtime");
+        return Ok(fs::read(cached)?);
+    }
+    timings::cache_miss("runtime");
+    let asset = pick_runtime_asset_for(release, target, platform, &RuntimeAssetPatterns::load()?).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} {} has no release asset for {}; set a pattern under [runtime.assets.{}] in ~/.lunu/config.toml if it is named unusually",
+            runtime_name(target),
+            release.tag_name,
+            platform.label(),
+            platform.label()
+        )
+    })?;
+    println!("Downloading {} {} for {}...", runtime_name(target), release.tag_name, platform.label());
+    let client = http::client()?;
+    let label = format!("{} for {}", runtime_name(target), platform.label());
+    let bytes = http::get_bytes_with_progress(&client, &asset.browser_download_url, &download_progress(label)).await?;
+    let candidates = vec![platform_bin_filename(target, platform), runtime_name(target).to_string()];
+    let name = asset.name.to_lowercase();
+    let binary = if name.ends_with(".zip") {
+        extract_binary_from_zip(&bytes, &candidates)?
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        extract_binary_from_tar_gz(&bytes, &candidates)?
+    } else {
+        bytes
+    };
+    write_atomic(&cached, &binary)?;
+    Ok(binary)
+}
+
+/// Prints every problem in the project's config files; false when any file has issues.
+fn validate_config(root: &Path) -> Result<bool> {
+    let mut clean = true;
+    for file in [ConfigFile::LunuToml, ConfigFile::Settings] {
+        let path = root.join(file.file_name());
+        if !path.exists() {
+            if file == ConfigFile::LunuToml {
+                return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+            }
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        match config_schema::validate(file, &content) {
+            Ok(issues) if issues.is_empty() => println!("{}: ok", file.file_name()),
+            Ok(issues) => {
+                clean = false;
+                println!("{}", config_schema::format_issues(file.file_name(), &issues));
+            }
+            Err(err) => {
+                clean = false;
+                println!("{}: {:#}", file.file_name(), err);
+            }
+        }
+    }
+    Ok(clean)
+}
+
+async fn export_project(root: &Path, cwd: &Path, output: Option<PathBuf>) -> Result<()> {
+    let config_path = project_config_path(root);
+    if !config_path.exists() {
+        return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
+    }
+    let cfg = ProjectConfig::load(&config_path).await?;
+    let runtime = runtime_kind_from_config(&cfg);
+    let meta = bundle::BundleMeta {
+        format: bundle::BUNDLE_FORMAT,
+        name: cfg.project.name.clone(),
+        runtime: runtime_name(runtime_target_from_kind(runtime)).to_string(),
+        runtime_version: pinned_runtime_version(root, runtime),
+        lunu: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let missing: Vec<&String> = cfg
+        .dependencies
+        .iter()
+        .filter(|(name, spec)| spec.is_remote() && !root.join("modules").join(name).exists())
+        .map(|(name, _)| name)
+        .collect();
+    if !missing.is_empty() {
+        println!("Warning: not installed, the bundle will not contain them: {:?}. Run 'lunu install' first.", missing);
+    }
+
+    // Built executables are per-platform; the importer rebuilds them
+    let stem = Path::new(&cfg.project.entry).file_stem().and_then(|s| s.to_str()).unwrap_or("main");
+    let skip = vec![stem.to_string(), format!("{}.exe", stem)];
+    let output = output.unwrap_or_else(|| cwd.join(format!("{}.lunu", cfg.project.name)));
+    let files = bundle::write_bundle(root, &meta, &output, &skip)?;
+    println!(
+        "Exported {} files to {:?} ({} {}).",
+        files,
+        output,
+        meta.runtime,
+        meta.runtime_version.as_deref().unwrap_or("version unknown")
+    );
+    Ok(())
+}
+
+/// The directory `lunu import` creates for a bundle's project name. The name comes from the
+/// bundle, so anything but a single plain component (`../x`, `/tmp/x`, `a/b`) is refused.
+fn import_dir_name(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(path),
+        _ => Err(anyhow::anyhow!(
+            "The bundle's project name '{}' is not a plain directory name. Pass the directory to import into: lunu import <bundle> <dir>",
+            name
+        )),
+    }
+}
+
+async fn import_project(cwd: &Path, archive: &Path, dir: Option<PathBuf>) -> Result<()> {
+    let meta = bundle::read_meta(archive)?;
+    let dest = match dir {
+        Some(dir) => dir,
+        None => cwd.join(import_dir_name(&meta.name)?),
+    };
+    if dest.exists() && fs::read_dir(&dest)?.next().is_some() {
+        return Err(anyhow::anyhow!("{:?} already exists and is not empty.", dest));
+    }
+    let files = bundle::extract_bundle(archive, &dest)?;
+    println!("Imported {} ({} files) into {:?}", meta.name, files, dest);
+
+    let target = match meta.runtime.as_str() {
+        "lune" => RuntimeTarget::Lune,
+        _ => RuntimeTarget::Lute,
+    };
+    let version = meta.runtime_version.as_deref();
+    match runtime_binary_for_version(target, &HostPlatform::current(), version).await {
+        Ok((installed, binary)) => {
+            let path = dest.join("bin").join(runtime_bin_filename(target));
+            fs::create_dir_all(dest.join("bin"))?;
+            install_runtime_binary(&path, &binary)?;
+            println!("Pinned {} {} in bin/.", runtime_name(target), installed);
+        }
+        Err(err) => println!(
+            "Warning: could not download {} {}: {:#}. Run 'lunu runtime {} --update' inside the project.",
+            runtime_name(target),
+            version.unwrap_or("(latest)"),
+            err,
+            runtime_name(target)
+        ),
+    }
+    Ok(())
+}
+
+fn platform_launcher(target: RuntimeTarget, platform: &HostPlatform, entry: &str) -> (String, String) {
+    let bin = platform_bin_filename(target, platform);
+    if platform.os == "windows" {
+        let entry = entry.replace('/', "\\");
+        let script = format!("@echo off\r\n\"%~dp0bin\\{}\" run \"%~dp0{}\" %*\r\n", bin, entry);
+        ("run.bat".to_string(), script)
+    } else {
+        let script = format!(
+            "#!/bin/sh\nDIR=\"$(cd \"$(dirname \"$0\")\" && pwd)\"\nexec \"$DIR/bin/{}\" run \"$DIR/{}\" \"$@\"\n",
+            bin, entry
+        );
+        ("run.sh".to_string(), script)
+    }
+}
+
+fn assemble_platform_bundle(
+    root: &Path,
+    cfg: &ProjectConfig,
+    include_all: bool,
+    platform: &HostPlatform,
+    runtime_binary: &[u8],
+) -> Result<PathBuf> {
+    let exclude = if include_all {
+        ExcludeSet::none()
+    } else {
+        exclude_set_for(cfg.build.as_ref().and_then(|b| b.exclude.as_ref()))
+    };
+    let target = runtime_target_from_kind(runtime_kind_from_config(cfg));
+    let bundle_name = format!("{}-{}", cfg.project.name, platform.label());
+    let dist_dir = root.join("dist");
+    let bundle = dist_dir.join(&bundle_name);
+    if bundle.exists() {
+        fs::remove_dir_all(&bundle)?;
+    }
+    fs::create_dir_all(bundle.join("bin"))?;
+
+    let (launcher, script) = platform_launcher(target, platform, &cfg.project.entry);
+    let runtime_path = format!("bin/{}", platform_bin_filename(target, platform));
+    fs::write(bundle.join(&runtime_path), runtime_binary)?;
+    ensure_executable(&bundle.join(&runtime_path))?;
+    fs::write(bundle.join(&launcher), script)?;
+    ensure_executable(&bundle.join(&launcher))?;
+    let executables = [runtime_path, launcher];
+
+    let entry_dir = Path::new(&cfg.project.entry)
+        .components()
+        .next()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default();
+    for dir in [entry_dir.as_str(), "modules", "assets"] {
+        let src = root.join(dir);
+        if !dir.is_empty() && src.is_dir() {
+            copy_dir_filtered(&src, &bundle.join(dir), &exclude)?;
+        }
+    }
+    for file in ["lunu.toml", "lunu.lock", ".luaurc"] {
+        if root.join(file).is_file() {
+            fs::copy(root.join(file), bundle.join(file))?;
+        }
+    }
+
+    let archive = dist_dir.join(format!("{}.zip", bundle_name));
+    zip_bundle(&bundle, &bundle_name, &archive, &executables)?;
+    Ok(archive)
+}
+
+/// Zips `dir` under a top-level `prefix` folder, marking `executables` as such for Unix targets.
+fn zip_bundle(dir: &Path, prefix: &str, archive: &Path, executables: &[String]) -> Result<()> {
+    let _timing = timings::span("zip");
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    let mut zip = zip::ZipWriter::new(fs::File::create(archive)?);
+    for (relative, path) in files {
+        let mode = if executables.contains(&relative) { 0o755 } else { 0o644 };
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(mode);
+        zip.start_file(format!("{}/{}", prefix, relative), options)?;
+        zip.write_all(&fs::read(&path)?)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+
+/// What `lunu check` found: environment facts (JSON key, label, value) and findings.
+struct CheckReport {
+    environment: Vec<(&'static str, &'static str, Value)>,
+    findings: Vec<Finding>,
+    /// Output of `lute check`, when it ran
+    lute_output: Option<String>,
+}
+
+async fn collect_check(root: &Path) -> Result<CheckReport> {
+    let lunu_root = resolve_lunu_root(root);
+    let config_path = project_config_path(root);
+    let lock_path = lock_path(root);
+    let modules_dir = root.join("modules");
+    let src_main = root.join("src").join("main.luau");
+    let builder_exe = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|d| d.join("lunu-builder.exe")))
+        .unwrap_or_else(|| root.join("bin").join("lunu-builder.exe"));
+
+    let mut environment = vec![
+        ("lunu_directory", "Lunu directory", Value::from(lunu_root.exists())),
+        ("project_config", "Project config (lunu.toml)", Value::from(config_path.exists())),
+        ("lock_file", "Lock file (lunu.lock)", Value::from(lock_path.exists())),
+        ("modules_directory", "Modules directory", Value::from(modules_dir.exists())),
+        ("entry_file", "Entry file", Value::from(src_main.exists())),
+        ("builder_executable", "Builder executable", Value::from(builder_exe.exists())),
+    ];
+    let mut findings = worker_requirement_findings(root);
+    let mut lute_output = None;
+    let lock = LockFile::load(&lock_path).await?;
+    for name in checksum_mismatches(root, &lock, &PackageManager::new(root.to_path_buf())).await? {
+        findings.push(
+            Finding::error(format!("dependency.checksum/{}", name), format!("Dependency '{}' does not match its checksum in lunu.lock.", name))
+                .with_hint("Reinstall it with 'lunu install', or set `mutable = true` on a path dependency you are editing."),
+        );
+    }
+
+    if !config_path.exists() {
+        findings.push(Finding::warning("config.missing", "No lunu.toml in this project.").with_hint("Run 'lunu init' to create one."));
+    } else if let Ok(cfg) = ProjectConfig::load(&config_path).await {
+        let runtime_kind = runtime_kind_from_config(&cfg);
+        let _ = write_luaurc(root, &cfg.all_dependencies(), runtime_kind, false).await;
+        if let Some(runtime) = cfg.runtime {
+            environment.push(("runtime", "Runtime", Value::from(runtime.name.clone())));
+            if runtime.name == "lute" {
+                let lute = find_lute_executable(root);
+                let toolchain = detect_cpp_toolchain();
+                environment.push(("lute_executable", "Lute executable", Value::from(lute.as_ref().map(|p| p.to_string_lossy().to_string()))));
+                environment.push(("c_compiler", "C compiler", Value::from(toolchain.c_compiler.is_some())));
+                environment.push(("cpp_compiler", "C++ compiler", Value::from(toolchain.cpp_compiler.is_some())));
+                let entry = root.join(&cfg.project.entry);
+                if !entry.exists() {
+                    findings.push(Finding::error("entry.missing", format!("Entry file not found for Lute check: {:?}", entry)));
+                } else if let Some(lute) = lute {
+                    match capabilities::load_or_probe(&runtime_cache_dir(), &lute, "lute", false).require("check") {
+                        Err(err) => findings.push(Finding::error("runtime.capability/check", err.to_string())),
+                        Ok(()) => {
+                            let output = Command::new(&lute)
+                                .arg("check")
+                                .arg(&entry)
+                                .current_dir(root)
+                                .output()
+                                .with_context(|| "Failed to run lute check")?;
+                            lute_output = Some(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)));
+                            if !output.status.success() {
+                                findings.push(Finding::error("lute.check", "Lute check failed."));
+                            }
+                        }
+                    }
+                } else {
+                    findings.push(
+                        Finding::error("runtime.missing/lute", "Lute runtime not found.")
+                            .with_hint("Set LUTE_PATH, place bin/lute.exe in the project, or add lute.exe to PATH."),
+                    );
+                }
+            }
+            if runtime.name == "lune" {
+                let lune = find_lune_executable(root);
+                environment.push(("lune_executable", "Lune executable", Value::from(lune.map(|p| p.to_string_lossy().to_string()))));
+            }
+        }
+    }
+    Ok(CheckReport { environment, findings, lute_output })
+}
+
+/// `lunu check`: prints the report (or JSON) and fails on errors not suppressed by the baseline.
+async fn check_environment(root: &Path, json: bool, update_baseline: bool) -> Result<()> {
+    let report = collect_check(root).await?;
+    if update_baseline {
+        let path = findings::Baseline::from_findings(&report.findings).save(root)?;
+        println!("Recorded {} finding(s) in {:?}.", report.findings.len(), path);
+        return Ok(());
+    }
+    let baseline = findings::Baseline::load(root)?;
+    let (active, suppressed, stale) = findings::apply_baseline(report.findings, &baseline);
+    let errors = active.iter().filter(|f| f.severity == Severity::Error).count();
+
+    if json {
+        let environment: serde_json::Map<String, Value> = report.environment.into_iter().map(|(key, _, value)| (key.to_string(), value)).collect();
+        let output = serde_json::json!({
+            "ok": errors == 0,
+            "environment": environment,
+            "findings": active,
+            "suppressed": suppressed,
+            "stale_baseline": stale,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        println!("Environment check:");
+        for (_, label, value) in &report.environment {
+            match value {
+                Value::String(path) => println!("- {}: {}", label, path),
+                Value::Null => println!("- {}: false", label),
+                other => println!("- {}: {}", label, other),
+            }
+        }
+        if let Some(output) = &report.lute_output {
+            print!("{}", output);
+        }
+        for finding in &active {
+            finding.print();
+        }
+        if !suppressed.is_empty() {
+            println!("{} finding(s) suppressed by {}.", suppressed.len(), findings::baseline_path(root).display());
+        }
+        if !stale.is_empty() {
+            println!("No longer found (remove from the baseline): {}", stale.join(", "));
+        }
+    }
+    if errors > 0 {
+        return Err(anyhow::anyhow!("lunu check found {} error(s)", errors));
+    }
+    Ok(())
+}
+
+/// Numeric parts of a Luau release tag (`0.650`), for picking the newest cached analyzer.
+fn luau_version_key(tag: &str) -> Vec<u64> {
+    tag.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+/// `luau-analyze` from the Luau release `pinned` (the newest one otherwise) and its version,
+/// downloaded once into the runtime cache. Without a pin and without network, the newest cached
+/// analyzer is used.
+async fn ensure_luau_analyze(pinned: Option<&str>) -> Result<(PathBuf, String)> {
+    let platform = HostPlatform::current();
+    let windows = platform.os == "windows";
+    let filename = typecheck::analyzer_filename(windows);
+    let luau_dir = runtime_cache_dir().join("luau");
+    if let Some(version) = pinned {
+        let cached = luau_dir.join(version).join(filename);
+        if cached.exists() {
+            timings::cache_hit("luau-analyze");
+            return Ok((cached, version.to_string()));
+        }
+    }
+    let url = match pinned {
+        Some(version) => format!("https://api.github.com/repos/luau-lang/luau/releases/tags/{}", version),
+        None => "https://api.github.com/repos/luau-lang/luau/releases/latest".to_string(),
+    };
+    let client = http::client()?;
+    let release: GithubRelease = match http::get_json(&client, &url).await {
+        Ok(release) => release,
+        Err(err) => {
+            let newest = fs::read_dir(&luau_dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|e| e.path().join(filename).exists())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .max_by_key(|tag| luau_version_key(tag));
+            return match (pinned, newest) {
+                (None, Some(version)) => Ok((luau_dir.join(&version).join(filename), version)),
+                _ => Err(err.context(format!("Failed to look up Luau release {}", pinned.unwrap_or("latest")))),
+            };
+        }
+    };
+    let cached = luau_dir.join(&release.tag_name).join(filename);
+    if cached.exists() {
+        timings::cache_hit("luau-analyze");
+        return Ok((cached, release.tag_name));
+    }
+    timings::cache_miss("luau-analyze");
+    let asset = typecheck::release_asset(platform.os)
+        .and_then(|wanted| release.assets.iter().find(|a| a.name == wanted))
+        .ok_or_else(|| anyhow::anyhow!("Luau {} has no luau-analyze build for {}", release.tag_name, platform.label()))?;
+    println!("Downloading luau-analyze {}...", release.tag_name);
+    let label = format!("luau-analyze {}", release.tag_name);
+    let bytes = http::get_bytes_with_progress(&client, &asset.browser_download_url, &download_progress(label)).await?;
+    let binary = typecheck::extract_analyzer(&bytes, windows)?;
+    fs::create_dir_all(luau_dir.join(&release.tag_name))?;
+    install_runtime_binary(&cached, &binary)?;
+    Ok((cached, release.tag_name))
+}
+
+async fn typecheck_project(root: &Path, modules: bool, strict: bool, json: bool) -> Result<()> {
+    let config_path = project_config_path(root);
+    let cfg = if config_path.exists() { Some(ProjectConfig::load(&config_path).await?) } else { None };
+    let settings = cfg.as_ref().and_then(|c| c.typecheck.clone()).unwrap_or_default();
+    let runtime = resolve_runtime_for_root(root).await?;
+
+    let mut files = requires::luau_files(&root.join("src"));
+    if modules || settings.modules {
+        let modules_dir = cfg.as_ref().map(|c| c.project.modules_dir.clone()).unwrap_or_else(|| "modules".to_string());
+        files.extend(requires::luau_files(&root.join(modules_dir)));
+    }
+    let files: Vec<PathBuf> = files.iter().map(|f| f.strip_prefix(root).unwrap_or(f).to_path_buf()).collect();
+    if files.is_empty() {
+        println!("No Luau files to check.");
+        return Ok(());
+    }
+
+    let (checker, mut command) = match runtime {
+        RuntimeKind::Lute => {
+            let lute = find_lute_executable(root).ok_or_else(|| {
+                anyhow::anyhow!("Lute runtime not found. Set LUTE_PATH, place bin/lute.exe in the project, or add lute.exe to PATH.")
+            })?;
+            capabilities::load_or_probe(&runtime_cache_dir(), &lute, "lute", false).require("check")?;
+            let mut command = Command::new(lute);
+            command.arg("check");
+            ("lute check".to_string(), command)
+        }
+        RuntimeKind::Lune => {
+            let (analyzer, version) = ensure_luau_analyze(settings.luau.as_deref()).await?;
+            // A read-only check: definitions go to .lunu/types, but .luaurc is left to init and install
+            if let Err(err) = fetch_type_definitions(root, runtime).await {
+                println!("Warning: could not install Lune type definitions: {:#}", err);
+            }
+            if !Luaurc::load(&root.join(".luaurc")).await?.aliases.contains_key("lune") {
+                println!("Note: .luaurc has no 'lune' alias, so @lune requires will not resolve; 'lunu install' adds it.");
+            }
+            let mut command = Command::new(analyzer);
+            if strict {
+                command.arg("--mode=strict");
+            }
+            (format!("luau-analyze {}", version), command)
+        }
+    };
+    let output = command
+        .args(&files)
+        .current_dir(root)
+        .output()
+        .with_context(|| format!("Failed to run {}", checker))?;
+    let text = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let (diagnostics, other) = typecheck::parse(&text, root);
+    if !output.status.success() && diagnostics.is_empty() {
+        return Err(anyhow::anyhow!("{} failed:\n{}", checker, text.trim_end()));
+    }
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+    let failed = errors > 0 || (strict && warnings > 0);
+
+    if json {
+        let report = serde_json::json!({
+            "ok": !failed,
+            "checker": checker,
+            "files": files.len(),
+            "diagnostics": diagnostics,
+            "output": other,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for line in &other {
+            println!("{}", line);
+        }
+        for diagnostic in &diagnostics {
+            println!("{}", diagnostic.render());
+        }
+        let mut codes: BTreeMap<&str, usize> = BTreeMap::new();
+        for diagnostic in &diagnostics {
+            *codes.entry(diagnostic.code.as_str()).or_default() += 1;
+        }
+        let by_code = if codes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", codes.iter().map(|(code, count)| format!("{} {}", code, count)).collect::<Vec<_>>().join(", "))
+        };
+        println!("Checked {} file(s) with {}: {} error(s), {} warning(s){}.", files.len(), checker, errors, warnings, by_code);
+    }
+    if failed {
+        return Err(anyhow::anyhow!("lunu typecheck found {} error(s) and {} warning(s)", errors, warnings));
+    }
+    Ok(())
+}
+
+/// Bridge modules whose `requires` is not met by their interpreter.
+fn worker_requirement_findings(root: &Path) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (name, module) in module_dirs(root) {
+        let Ok(content) = fs::read_to_string(module.join("bridge.json")) else {
+            continue;
+        };
+        match worker_requires::check(&module, &content) {
+            Ok(unsatisfied) => {
+                for requirement in unsatisfied {
+                    findings.push(
+                        Finding::error(format!("worker.requires/{}/{}", name, requirement.language), format!("Module '{}' {}.", name, requirement))
+                            .with_hint(requirement.remediation()),
+                    );
+                }
+            }
+            Err(err) => findings.push(Finding::error(format!("worker.requires/{}", name), format!("Module '{}': {}", name, err))),
+        }
+    }
+    findings
+}
+
+struct ProjectStatus {
+    name: String,
+    runtime: RuntimeKind,
+    runtime_version: Option<String>,
+    /// Where the runtime binary comes from: `bin/` (pinned), LUNE_PATH/LUTE_PATH, cache or PATH
+    runtime_source: Option<String>,
+    dependencies: usize,
+    outdated: Vec<String>,
+    modified: Vec<String>,
+    bridge_modules: Vec<(String, String)>,
+    last_build: Option<(PathBuf, u64)>,
+    warnings: Vec<String>,
+}
+
+fn runtime_source(root: &Path, target: RuntimeTarget) -> Option<String> {
+    if root.join("bin").join(runtime_bin_filename(target)).exists() {
+        return Some("pinned in bin/".to_string());
+    }
+    let env_key = match target {
+        RuntimeTarget::Lute => "LUTE_PATH",
+        RuntimeTarget::Lune => "LUNE_PATH",
+    };
+    if std::env::var(env_key).map(|p| Path::new(&p).exists()).unwrap_or(false) {
+        return Some(env_key.to_string());
     }
-    Ok(())
+    if runtime_cache_bin(target).exists() {
+        return Some("cache".to_string());
+    }
+    find_in_path(&runtime_bin_filename(target)).map(|_| "PATH".to_string())
+}
+
+/// Language of a bridge module, guessed from its worker command.
+fn bridge_modules(root: &Path) -> Vec<(String, String, Option<String>)> {
+    let mut modules = Vec::new();
+    for (name, path) in module_dirs(root) {
+        let Ok(content) = fs::read_to_string(path.join("bridge.json")) else {
+            continue;
+        };
+        let json = serde_json::from_str::<Value>(&content).ok();
+        if let Some(url) = json.as_ref().and_then(|j| j.pointer("/remote/url")).and_then(Value::as_str) {
+            modules.push((name, "remote".to_string(), Some(url.to_string())));
+            continue;
+        }
+        let cmd = json
+            .as_ref()
+            .and_then(|json| json.pointer("/worker/cmd/0").and_then(Value::as_str).map(String::from));
+        let language = cmd.as_deref().map(worker_language).unwrap_or_else(|| "unknown".to_string());
+        modules.push((name, language, cmd));
+    }
+    modules.sort();
+    modules
 }
 
-async fn package_project(root: &Path) -> Result<()> {
+/// Tracked files changed since the dependency was cloned (generated wrappers are untracked).
+/// Modules linked from the package store have no repository; `collect_status` checks their
+/// lunu.lock checksum instead.
+fn dependency_modified(path: &Path) -> bool {
+    let Ok(repo) = git2::Repository::open(path) else {
+        return false;
+    };
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+    repo.statuses(Some(&mut options)).map(|s| !s.is_empty()).unwrap_or(false)
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+async fn collect_status(root: &Path) -> Result<ProjectStatus> {
     let config_path = project_config_path(root);
     if !config_path.exists() {
         return Err(anyhow::anyhow!("lunu.toml not found. Run 'lunu init' first."));
     }
     let cfg = ProjectConfig::load(&config_path).await?;
-    let dist_dir = root.join("dist");
-    if dist_dir.exists() {
-        async_fs::remove_dir_all(&dist_dir).await?;
+    let runtime = runtime_kind_from_config(&cfg);
+    let target = runtime_target_from_kind(runtime);
+    let lock = LockFile::load(&lock_path(root)).await?;
+    let metadata = MetadataCache::load(&MetadataCache::path(root)).await.unwrap_or_default();
+    let mut warnings = Vec::new();
+
+    let runtime_source = runtime_source(root, target);
+    if runtime_source.is_none() {
+        warnings.push(format!("{} runtime not found; run 'lunu runtime {} --update'", runtime_name(target), runtime_name(target)));
+    }
+    if !root.join(&cfg.project.entry).exists() {
+        warnings.push(format!("Entry file {} is missing", cfg.project.entry));
+    }
+    let dependencies = cfg.all_dependencies();
+    if !dependencies.is_empty() && !lock_path(root).exists() {
+        warnings.push("lunu.lock is missing; run 'lunu install'".to_string());
+    }
+
+    let mut outdated = Vec::new();
+    let mut modified = Vec::new();
+    let mismatched = checksum_mismatches(root, &lock, &PackageManager::new(root.to_path_buf())).await?;
+    for (name, spec) in &dependencies {
+        let entry = lock.dependencies.get(name);
+        let installed = entry.and_then(|e| e.path.as_ref()).map(|p| root.join(p));
+        match &installed {
+            Some(path) if path.exists() => {
+                if spec.is_remote() && (dependency_modified(path) || mismatched.contains(name)) {
+                    modified.push(name.clone());
+                }
+            }
+            _ => warnings.push(format!("Dependency '{}' is not installed", name)),
+        }
+        let version = entry.and_then(|e| e.version.clone()).or_else(|| spec.version.clone());
+        let latest = metadata.get(name).and_then(|m| m.latest_tags.first());
+        if let (Some(version), Some(latest)) = (version, latest) {
+            if version.trim_start_matches('v') != latest.trim_start_matches('v') {
+                outdated.push(format!("{} ({} -> {})", name, version, latest));
+            }
+        }
     }
-    async_fs::create_dir_all(&dist_dir).await?;
 
-    let entry_path = PathBuf::from(&cfg.project.entry);
-    let stem = entry_path.file_stem().and_then(|s| s.to_str()).unwrap_or("main");
-    let exe_path = if let Some(ext) = executable_extension() {
-        root.join(format!("{}.{}", stem, ext))
-    } else {
-        root.join(stem)
-    };
-    if exe_path.exists() {
-        async_fs::copy(&exe_path, dist_dir.join(exe_path.file_name().unwrap())).await?;
-    } else {
-        println!("Warning: executable not found at {:?}", exe_path);
+    let mut modules = Vec::new();
+    for (name, language, cmd) in bridge_modules(root) {
+        let system_cmd = cmd.filter(|c| !c.contains('/') && !c.contains('\\'));
+        if let Some(cmd) = system_cmd {
+            let bundled = root.join("modules").join(&name).join(&cmd).exists();
+            let on_path = find_in_path(&cmd).is_some() || find_in_path(&format!("{}.exe", cmd)).is_some();
+            if !bundled && !on_path {
+                warnings.push(format!("Module '{}' needs '{}', which is not on PATH", name, cmd));
+            }
+        }
+        modules.push((name, language));
     }
 
-    let modules_dir = root.join("modules");
-    if modules_dir.exists() {
-        let mut options = CopyOptions::new();
-        options.copy_inside = true;
-        copy_dir(&modules_dir, dist_dir.join("modules"), &options)?;
-    }
+    let stem = Path::new(&cfg.project.entry).file_stem().and_then(|s| s.to_str()).unwrap_or("main").to_string();
+    let now = SystemTime::now();
+    let last_build = [format!("{}.exe", stem), stem]
+        .into_iter()
+        .map(|name| root.join(name))
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).ok().filter(|m| m.is_file())?.modified().ok()?;
+            Some((path, now.duration_since(modified).unwrap_or_default().as_secs()))
+        })
+        .min_by_key(|(_, age)| *age);
+
+    Ok(ProjectStatus {
+        name: cfg.project.name.clone(),
+        runtime,
+        runtime_version: pinned_runtime_version(root, runtime),
+        runtime_source,
+        dependencies: dependencies.len(),
+        outdated,
+        modified,
+        bridge_modules: modules,
+        last_build,
+        warnings,
+    })
+}
 
-    let assets_dir = root.join("assets");
-    if assets_dir.exists() {
-        let mut options = CopyOptions::new();
-        options.copy_inside = true;
-        copy_dir(&assets_dir, dist_dir.join("assets"), &options)?;
+fn print_status(status: &ProjectStatus) {
+    println!("Project: {}", status.name);
+    let version = status.runtime_version.as_deref().unwrap_or("unknown version");
+    let source = status.runtime_source.as_deref().unwrap_or("not installed");
+    println!("Runtime: {} {} ({})", runtime_name(runtime_target_from_kind(status.runtime)), version, source);
+    println!(
+        "Dependencies: {} ({} outdated, {} modified)",
+        status.dependencies,
+        status.outdated.len(),
+        status.modified.len()
+    );
+    for name in &status.outdated {
+        println!("  outdated: {}", name);
+    }
+    for name in &status.modified {
+        println!("  modified: {}", name);
+    }
+    if status.bridge_modules.is_empty() {
+        println!("Bridge modules: none");
+    } else {
+        let modules: Vec<String> = status.bridge_modules.iter().map(|(name, lang)| format!("{} ({})", name, lang)).collect();
+        println!("Bridge modules: {}", modules.join(", "));
     }
-
-    let lock_path = lock_path(root);
-    if lock_path.exists() {
-        async_fs::copy(&lock_path, dist_dir.join("lunu.lock")).await?;
+    match &status.last_build {
+        Some((path, age)) => println!("Last build: {} ({})", path.file_name().unwrap_or_default().to_string_lossy(), format_age(*age)),
+        None => println!("Last build: none"),
     }
-    async_fs::copy(&config_path, dist_dir.join("lunu.toml")).await?;
-
-    println!("Package created at {:?}", dist_dir);
-    Ok(())
-}
-
-
-async fn check_environment(root: &Path) -> Result<()> {
-    let lunu_root = resolve_lunu_root(root);
-    let config_path = project_config_path(root);
-    let lock_path = lock_path(root);
-    let modules_dir = root.join("modules");
-    let src_main = root.join("src").join("main.luau");
-    let builder_exe = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|d| d.join("lunu-builder.exe")))
-        .unwrap_or_else(|| root.join("bin").join("lunu-builder.exe"));
-
-    println!("Environment check:");
-    println!("- Lunu directory: {}", lunu_root.exists());
-    println!("- Project config (lunu.toml): {}", config_path.exists());
-    println!("- Lock file (lunu.lock): {}", lock_path.exists());
-    println!("- Modules directory: {}", modules_dir.exists());
-    println!("- Entry file: {}", src_main.exists());
-    println!("- Builder executable: {}", builder_exe.exists());
-
-    if config_path.exists() {
-        if let Ok(cfg) = ProjectConfig::load(&config_path).await {
-            let runtime_kind = runtime_kind_from_config(&cfg);
-            let _ = update_luaurc(root, &cfg.dependencies, runtime_kind).await;
-            if let Some(runtime) = cfg.runtime {
-                println!("- Runtime: {}", runtime.name);
-                if runtime.name == "lute" {
-                    let lute = find_lute_executable(root);
-                    let toolchain = detect_cpp_toolchain();
-                    let c_found = toolchain.c_compiler.is_some();
-                    let cpp_found = toolchain.cpp_compiler.is_some();
-                    println!("- Lute executable: {}", lute.is_some());
-                    println!("- C compiler: {}", c_found);
-                    println!("- C++ compiler: {}", cpp_found);
-                    let entry = root.join(&cfg.project.entry);
-                    if !entry.exists() {
-                        return Err(anyhow::anyhow!("Entry file not found for Lute check: {:?}", entry));
-                    }
-                    if let Some(p) = &lute {
-                        println!("  Path: {:?}", p);
-                    }
-                    let lute = lute.ok_or_else(|| anyhow::anyhow!("Lute runtime not found. Set LUTE_PATH, place bin/lute.exe in the project, or add lute.exe to PATH."))?;
-                    let status = Command::new(&lute)
-                        .arg("check")
-                        .arg(&entry)
-                        .current_dir(root)
-                        .status()
-                        .with_context(|| "Failed to run lute check")?;
-                    if !status.success() {
-                        return Err(anyhow::anyhow!("Lute check failed"));
-                    }
-                }
-                if runtime.name == "lune" {
-                    let lune_path = find_lune_executable(root);
-                    println!("- Lune executable: {}", lune_path.is_some());
-                    if let Some(p) = lune_path {
-                        println!("  Path: {:?}", p);
-                    }
-                }
-            }
-        }
+    if status.warnings.is_empty() {
+        println!("No warnings.");
+    }
+    for warning in &status.warnings {
+        println!("WARN: {}", warning);
     }
-    Ok(())
 }
 
 fn find_root(start: &std::path::Path) -> Option<PathBuf> {
@@ -2638,23 +7870,9 @@ fn find_root(start: &std::path::Path) -> Option<PathBuf> {
 }
 
 async fn check_bridge_dependencies(root: &Path) {
-    let modules_dir = root.join("modules");
-    if !modules_dir.exists() {
-        return;
-    }
-
-    let mut dir = match async_fs::read_dir(&modules_dir).await {
-        Ok(d) => d,
-        Err(_) => return,
-    };
-
-    while let Ok(Some(entry)) = dir.next_entry().await {
-        let path = entry.path();
-        if path.is_dir() {
-            let bridge_json = path.join("bridge.json");
-            if bridge_json.exists() {
-                check_module_dependency(&path).await;
-            }
+    for (_, path) in module_dirs(root) {
+        if path.join("bridge.json").exists() {
+            check_module_dependency(&path).await;
         }
     }
 }
@@ -2695,6 +7913,15 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    /// The files `lunu init` writes for a Lune project, without fetching the runtime.
+    async fn local_project(root: &Path) -> ProjectConfig {
+        ensure_project_files(root, RuntimeKind::Lune, TemplateKind::App).await.unwrap();
+        let config_path = project_config_path(root);
+        let cfg = load_or_init_project(root, &config_path, RuntimeKind::Lune, Some(build_config_for(RuntimeKind::Lune, None))).await.unwrap();
+        cfg.save(&config_path).await.unwrap();
+        cfg
+    }
+
     #[test]
     fn project_name_from_root_works() {
         let dir = tempdir().unwrap();
@@ -2707,8 +7934,481 @@ mod tests {
         let dir = tempdir().unwrap();
         let modules_dir = dir.path().join("modules");
         std::fs::create_dir_all(modules_dir.join("demo")).unwrap();
+        std::fs::create_dir_all(modules_dir.join("@acme").join("http")).unwrap();
+        std::fs::create_dir_all(modules_dir.join("@acme").join("json")).unwrap();
         let deps = scan_modules(dir.path());
         assert!(deps.contains_key("demo"));
+        assert!(!deps.contains_key("@acme"));
+        assert_eq!(deps["@acme/http"].path.as_deref(), Some("modules/@acme/http"));
+        assert!(deps.contains_key("@acme/json"));
+    }
+
+    #[tokio::test]
+    async fn mutable_path_dependencies_skip_checksum_enforcement() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for name in ["pinned", "local"] {
+            std::fs::create_dir_all(root.join("modules").join(name)).unwrap();
+            std::fs::write(root.join("modules").join(name).join("init.luau"), "return 1").unwrap();
+        }
+        let pm = PackageManager::new(root.to_path_buf());
+        let mut lock = LockFile::default();
+        for (name, mutable) in [("pinned", false), ("local", true)] {
+            let spec = DependencySpec { path: Some(format!("modules/{}", name)), mutable, ..Default::default() };
+            let dir = root.join(spec.path.as_ref().unwrap());
+            lock.set(name, LockEntry {
+                url: None,
+                version: None,
+                tag: None,
+                rev: None,
+                path: spec.path.clone(),
+                subdir: None,
+                checksum: locked_checksum(&pm, &spec, &dir).await.unwrap().0,
+                files: BTreeMap::new(),
+                installed_at: 0,
+                mirror: None,
+                registry: None,
+                commit: None,
+                resolver: None,
+                installed_by: None,
+                mutable: spec.is_mutable(),
+            });
+        }
+        assert!(lock.dependencies["local"].checksum.is_empty());
+        assert!(checksum_mismatches(root, &lock, &pm).await.unwrap().is_empty());
+
+        for name in ["pinned", "local"] {
+            std::fs::write(root.join("modules").join(name).join("init.luau"), "return 2").unwrap();
+        }
+        assert_eq!(checksum_mismatches(root, &lock, &pm).await.unwrap(), vec!["pinned".to_string()]);
+
+        let remote = DependencySpec { url: Some("https://github.com/a/b".to_string()), mutable: true, ..Default::default() };
+        assert!(!remote.is_mutable());
+    }
+
+    #[tokio::test]
+    async fn audit_flags_modified_missing_and_denied_dependencies() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let pm = PackageManager::new(root.to_path_buf());
+        let mut lock = LockFile::default();
+        for (name, url) in [("json", "https://github.com/good/json"), ("pad", "https://github.com/evil/pad.git"), ("gone", "https://github.com/good/gone")] {
+            let module = root.join("modules").join(name);
+            std::fs::create_dir_all(&module).unwrap();
+            std::fs::write(module.join("init.luau"), "return 1").unwrap();
+            lock.set(name, LockEntry {
+                url: Some(url.to_string()),
+                version: None,
+                tag: None,
+                rev: None,
+                path: Some(format!("modules/{}", name)),
+                subdir: None,
+                checksum: pm.calculate_dir_checksum(&module).await.unwrap(),
+                files: BTreeMap::new(),
+                installed_at: 0,
+                mirror: None,
+                registry: None,
+                commit: None,
+                resolver: None,
+                installed_by: None,
+                mutable: false,
+            });
+        }
+        std::fs::write(root.join("modules").join("json").join("init.luau"), "return 2").unwrap();
+        std::fs::remove_dir_all(root.join("modules").join("gone")).unwrap();
+
+        let denylist = denylist::parse("github.com/evil/*  # typosquats\n");
+        let findings = dependency_audit_findings(root, &lock, &denylist).await.unwrap();
+        let ids: Vec<&str> = findings.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["dependency.missing/gone", "dependency.checksum/json", "dependency.denied/pad"]);
+        assert!(findings[2].message.contains("typosquats"));
+    }
+
+    #[test]
+    fn formats_unix_timestamps_as_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_utc(1_792_149_985), "2026-10-16 11:26:25 UTC");
+    }
+
+    #[tokio::test]
+    async fn verify_detects_drift_in_nested_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let pm = PackageManager::new(root.to_path_buf());
+        let mut lock = LockFile::default();
+        for name in ["json", "http", "local"] {
+            let module = root.join("modules").join(name);
+            std::fs::create_dir_all(module.join("src")).unwrap();
+            std::fs::write(module.join("init.luau"), "return require(\"./src\")").unwrap();
+            std::fs::write(module.join("src").join("init.luau"), "return {}").unwrap();
+            lock.set(name, LockEntry {
+                url: None,
+                version: None,
+                tag: None,
+                rev: None,
+                path: Some(format!("modules/{}", name)),
+                subdir: None,
+                checksum: pm.calculate_dir_checksum(&module).await.unwrap(),
+                files: pm.file_hashes(&module).await.unwrap(),
+                installed_at: 0,
+                mirror: None,
+                registry: None,
+                commit: None,
+                resolver: None,
+                installed_by: None,
+                mutable: name == "local",
+            });
+        }
+        lock.set("gone", LockEntry { path: Some("modules/gone".to_string()), ..lock.dependencies["json"].clone() });
+        std::fs::write(root.join("modules/json/src/init.luau"), "return nil").unwrap();
+        std::fs::write(root.join("modules/local/src/init.luau"), "return nil").unwrap();
+
+        let statuses: BTreeMap<String, LockStatus> = lock_statuses(root, &lock).await.unwrap().into_iter().collect();
+        assert_eq!(statuses["json"], LockStatus::Drift);
+        assert_eq!(statuses["http"], LockStatus::Ok);
+        assert_eq!(statuses["local"], LockStatus::Unchecked);
+        assert_eq!(statuses["gone"], LockStatus::Missing);
+
+        std::fs::write(root.join("modules/json/extra.luau"), "return 2").unwrap();
+        let changes = drifted_files(&pm, root, &lock.dependencies["json"]).await.unwrap();
+        assert_eq!(changes, vec![(package::FileChange::Added, "extra.luau".to_string()), (package::FileChange::Modified, "src/init.luau".to_string())]);
+    }
+
+    #[test]
+    fn parses_wait_ready_timeouts() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("15").unwrap(), Duration::from_secs(15));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("3h").is_err());
+    }
+
+    #[test]
+    fn adds_urls_from_any_git_host() {
+        assert_eq!(split_requested("acme/json@^1.2"), ("acme/json".to_string(), Some("^1.2".to_string())));
+        assert_eq!(split_requested("git@codeberg.org:acme/json.git"), ("git@codeberg.org:acme/json.git".to_string(), None));
+        assert_eq!(split_requested("git@codeberg.org:acme/json.git@v1.0.0").1.as_deref(), Some("v1.0.0"));
+        assert_eq!(split_requested("https://ci@gitlab.com/acme/json"), ("https://ci@gitlab.com/acme/json".to_string(), None));
+
+        let target = package_from_url("https://gitlab.com/acme/luau/json.git").unwrap();
+        assert_eq!((target.owner.as_str(), target.name.as_str()), ("acme/luau", "json"));
+        assert_eq!(package_from_url("https://example.com/dl/json-1.0.zip").unwrap().name, "json-1");
+        assert!(package_from_url("acme/json").is_none());
+        assert!(package_from_url("json luau").is_none());
+
+        let target = package_from_git_url("git@bitbucket.org:acme/json.git").unwrap();
+        assert_eq!((target.name.as_str(), target.url.as_str()), ("json", "git@bitbucket.org:acme/json.git"));
+        assert_eq!(package_from_git_url("ssh://git@gitlab.example.com:2222/acme/json.git").unwrap().owner, "acme");
+        assert!(package_from_git_url("json").is_none());
+    }
+
+    #[test]
+    fn picks_search_results() {
+        let result = |owner: &str, name: &str, stars: u64| PackageInfo {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            url: format!("https://github.com/{}/{}", owner, name),
+            stars,
+            description: Some("JSON for Luau".to_string()),
+            topics: Vec::new(),
+            updated_at: None,
+        };
+        let results = vec![result("fork", "json", 3), result("acme", "json", 1234)];
+        assert_eq!(pick_search_result("Acme/JSON", &results, false).unwrap(), Some(1));
+        assert_eq!(pick_search_result("json", &results, true).unwrap(), Some(0));
+        assert!(format_search_result(1, &results[1]).starts_with("  2) acme/json  "));
+        assert!(format_search_result(1, &results[1]).ends_with("1.2k  JSON for Luau"));
+
+        let mut input = std::io::Cursor::new("7\n2\n");
+        assert_eq!(ask_search_result(&mut input, &results, Some(1)).unwrap(), Some(1));
+        assert_eq!(ask_search_result(&mut std::io::Cursor::new("\n"), &results, Some(1)).unwrap(), Some(0));
+        assert_eq!(ask_search_result(&mut std::io::Cursor::new("\n"), &results, None).unwrap(), None);
+    }
+
+    #[test]
+    fn selects_dependencies_to_update() {
+        let filter = UpdateFilter { interactive: false, exclude: vec!["net-http".to_string()], only: Some("net-*".to_string()) };
+        assert!(filter.includes("net-ws") && !filter.includes("net-http") && !filter.includes("json"));
+
+        let choice = |name: &str, current: &str, available: &str| UpdateChoice {
+            name: name.to_string(),
+            current: current.to_string(),
+            available: available.to_string(),
+            checked: current != available,
+        };
+        let mut choices = vec![choice("json", "1.2.0", "1.4.1"), choice("signal", "v2.0.0", "v2.0.0"), choice("net", "abc1234", "abc1234")];
+        assert!(format_update_choice(0, &choices[0], 6).starts_with("  [x] 1) json    1.2.0 "));
+        ask_update_selection(&mut std::io::Cursor::new("9\n1 3\n"), &mut choices).unwrap();
+        assert_eq!(choices.iter().map(|c| c.checked).collect::<Vec<_>>(), vec![false, false, true]);
+        ask_update_selection(&mut std::io::Cursor::new("n\n"), &mut choices).unwrap();
+        assert!(choices.iter().all(|c| !c.checked));
+    }
+
+    #[test]
+    fn previews_updates_as_a_table() {
+        let entry = |version: Option<&str>, commit: &str, checksum: &str| LockEntry {
+            url: Some("https://github.com/a/json".to_string()),
+            version: version.map(str::to_string),
+            tag: None,
+            rev: None,
+            path: None,
+            subdir: None,
+            checksum: checksum.to_string(),
+            files: BTreeMap::new(),
+            installed_at: 0,
+            mirror: None,
+            registry: None,
+            commit: Some(commit.to_string()),
+            resolver: None,
+            installed_by: None,
+            mutable: false,
+        };
+        let rows = vec![
+            ("json".to_string(), Some(entry(Some("1.2.0"), "aaaaaaa111", "0123456789abcdef")), entry(Some("1.4.1"), "bbbbbbb222", "fedcba9876543210")),
+            ("signal".to_string(), Some(entry(None, "ccccccc333", "same")), entry(None, "ccccccc333", "same")),
+            ("net".to_string(), None, entry(None, "ddddddd444", "0011")),
+        ];
+        let lines = update_preview_lines(&rows);
+        assert_eq!(lines[0], "Dependency  Locked           Update           Checksum");
+        assert!(lines[1].starts_with("json        1.2.0 (aaaaaaa)  1.4.1 (bbbbbbb)  0123456789ab "));
+        assert!(lines[1].ends_with(" fedcba987654"));
+        assert_eq!(lines[2], "signal      ccccccc          ccccccc          unchanged");
+        assert!(lines[3].starts_with("net         not installed    ddddddd"));
+    }
+
+    #[test]
+    fn frozen_install_requires_lock_to_match_config() {
+        let locked = |url: &str, version: &str, checksum: &str| LockEntry {
+            url: Some(url.to_string()),
+            version: Some(version.to_string()),
+            tag: Some(format!("v{}", version)),
+            rev: None,
+            path: None,
+            subdir: None,
+            checksum: checksum.to_string(),
+            files: BTreeMap::new(),
+            installed_at: 0,
+            mirror: None,
+            registry: None,
+            commit: None,
+            resolver: None,
+            installed_by: None,
+            mutable: false,
+        };
+        let spec = |url: &str| DependencySpec { url: Some(url.to_string()), version: Some("^1.2".to_string()), ..Default::default() };
+        let mut dependencies = BTreeMap::new();
+        dependencies.insert("json".to_string(), spec("https://github.com/a/json"));
+        let mut lock = LockFile::default();
+        lock.set("json", locked("https://github.com/a/json", "1.2.3", "abc"));
+        assert!(frozen_lock_problems(&dependencies, &lock).is_empty());
+
+        dependencies.insert("http".to_string(), spec("https://github.com/a/http"));
+        lock.set("json", locked("https://github.com/a/json", "2.0.0", "abc"));
+        lock.set("old", locked("https://github.com/a/old", "1.0.0", "abc"));
+        let problems = frozen_lock_problems(&dependencies, &lock);
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("'http'") && problems[1].contains("'json'") && problems[2].contains("'old'"));
+
+        lock.set("json", locked("https://github.com/a/json", "1.2.3", ""));
+        assert!(frozen_lock_problems(&dependencies, &lock).iter().any(|p| p.contains("no locked checksum")));
+    }
+
+    #[tokio::test]
+    async fn removes_only_unused_dependencies() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut cfg = ProjectConfig::new("Pruned");
+        let mut luaurc = Luaurc::load(&root.join(".luaurc")).await.unwrap();
+        for (name, alias) in [("json", "json"), ("yaml", "yml")] {
+            std::fs::create_dir_all(root.join("modules").join(name)).unwrap();
+            std::fs::write(root.join("modules").join(name).join("init.luau"), "return {}").unwrap();
+            cfg.add_dependency(name, DependencySpec { path: Some(format!("modules/{}", name)), ..Default::default() });
+            luaurc.add_alias(alias, &format!("modules/{}/", name));
+        }
+        cfg.save(&project_config_path(root)).await.unwrap();
+        luaurc.save(&root.join(".luaurc")).await.unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src").join("main.luau"), "local json = require(\"@json\")").unwrap();
+
+        remove_unused_dependencies(root, true).await.unwrap();
+        assert!(root.join("modules").join("yaml").exists());
+
+        remove_unused_dependencies(root, false).await.unwrap();
+        assert!(!root.join("modules").join("yaml").exists());
+        assert!(root.join("modules").join("json").exists());
+        let cfg = ProjectConfig::load(&project_config_path(root)).await.unwrap();
+        assert_eq!(cfg.dependencies.keys().collect::<Vec<_>>(), vec!["json"]);
+        let luaurc = Luaurc::load(&root.join(".luaurc")).await.unwrap();
+        assert!(luaurc.aliases.contains_key("json") && !luaurc.aliases.contains_key("yml"));
+    }
+
+    #[tokio::test]
+    async fn vendors_dependencies_and_checks_them_on_install() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("app");
+        let shared = dir.path().join("shared");
+        for lib in [shared.clone(), root.join("libs").join("own")] {
+            std::fs::create_dir_all(&lib).unwrap();
+            std::fs::write(lib.join("init.luau"), "return {}").unwrap();
+        }
+        let mut cfg = ProjectConfig::new("Vendored");
+        cfg.add_dependency("shared", DependencySpec { path: Some("../shared".to_string()), ..Default::default() });
+        cfg.add_dependency("own", DependencySpec { path: Some("libs/own".to_string()), ..Default::default() });
+        cfg.save(&project_config_path(&root)).await.unwrap();
+
+        vendor_project(&root).await.unwrap();
+        assert!(root.join("vendor/shared/init.luau").is_file());
+        assert!(!root.join("vendor/own").exists());
+        let luaurc = Luaurc::load(&root.join(".luaurc")).await.unwrap();
+        assert_eq!(luaurc.aliases["shared"], "vendor/shared/");
+        assert_eq!(luaurc.aliases["own"], "libs/own/");
+        assert!(ProjectConfig::load(&project_config_path(&root)).await.unwrap().project.vendored);
+
+        // Installing only checks the copies, so the original is no longer needed
+        std::fs::remove_dir_all(&shared).unwrap();
+        install_from_config(&root, false, false).await.unwrap();
+        std::fs::write(root.join("vendor/shared/init.luau"), "return { patched = true }").unwrap();
+        let err = install_from_config(&root, false, false).await.unwrap_err().to_string();
+        assert!(err.contains("shared (checksum differs from lunu.lock)"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn prunes_only_unreferenced_installed_modules() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut cfg = ProjectConfig::new("Pruned");
+        cfg.add_dependency("json", DependencySpec { url: Some("https://github.com/a/json".to_string()), ..Default::default() });
+        cfg.save(&project_config_path(root)).await.unwrap();
+        // Installed modules get a lunu.toml; `own` was written in place and has none
+        for name in ["json", "old-json", "@scope/gone", "lunu"] {
+            std::fs::create_dir_all(root.join("modules").join(name)).unwrap();
+            std::fs::write(root.join("modules").join(name).join("lunu.toml"), "name = \"x\"\n").unwrap();
+        }
+        std::fs::create_dir_all(root.join("modules").join("own")).unwrap();
+        let mut lock = LockFile::default();
+        lock.set("old-json", LockEntry {
+            url: Some("https://github.com/a/json".to_string()),
+            version: None,
+            tag: None,
+            rev: None,
+            path: Some("modules/old-json".to_string()),
+            subdir: None,
+            checksum: String::new(),
+            files: BTreeMap::new(),
+            installed_at: 0,
+            mirror: None,
+            registry: None,
+            commit: None,
+            resolver: None,
+            installed_by: None,
+            mutable: false,
+        });
+        lock.save(&lock_path(root)).await.unwrap();
+        let mut luaurc = Luaurc::default();
+        luaurc.add_alias("oldjson", "modules/old-json/");
+        luaurc.save(&root.join(".luaurc")).await.unwrap();
+
+        let cfg = ProjectConfig::load(&project_config_path(root)).await.unwrap();
+        let lock = LockFile::load(&lock_path(root)).await.unwrap();
+        let orphans: Vec<String> = orphaned_modules(root, &cfg, &lock, false).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(orphans, vec!["@scope/gone", "old-json"]);
+        // Installs and removals only prune what lunu.lock recorded
+        let orphans: Vec<String> = orphaned_modules(root, &cfg, &lock, true).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(orphans, vec!["old-json"]);
+
+        prune_modules(root, true, false).await.unwrap();
+        assert!(root.join("modules").join("old-json").exists());
+        prune_modules(root, false, false).await.unwrap();
+        for gone in ["old-json", "@scope"] {
+            assert!(!root.join("modules").join(gone).exists());
+        }
+        for kept in ["json", "lunu", "own"] {
+            assert!(root.join("modules").join(kept).exists());
+        }
+        assert!(LockFile::load(&lock_path(root)).await.unwrap().dependencies.is_empty());
+        assert!(Luaurc::load(&root.join(".luaurc")).await.unwrap().aliases.is_empty());
+    }
+
+    fn release(tag: &str, prerelease: bool) -> GithubRelease {
+        GithubRelease {
+            tag_name: tag.to_string(),
+            assets: Vec::new(),
+            prerelease,
+            draft: false,
+            body: None,
+            html_url: None,
+        }
+    }
+
+    #[test]
+    fn pick_upgrade_release_respects_channel() {
+        let releases = || vec![
+            release("v0.1.0", false),
+            release("v0.3.0-beta.1", true),
+            release("v0.2.0", false),
+        ];
+        let (stable, _) = pick_upgrade_release(releases(), UpgradeChannel::Stable).unwrap();
+        assert_eq!(stable.tag_name, "v0.2.0");
+        let (beta, _) = pick_upgrade_release(releases(), UpgradeChannel::Beta).unwrap();
+        assert_eq!(beta.tag_name, "v0.3.0-beta.1");
+    }
+
+    #[test]
+    fn parse_release_version_orders_prereleases() {
+        let pre = parse_release_version("v1.0.0-rc.1").unwrap();
+        let stable = parse_release_version("1.0.0").unwrap();
+        assert!(pre < stable);
+        assert!(parse_release_version("nightly").is_none());
+    }
+
+    fn host(os: &'static str, arch: &'static str, musl: bool) -> HostPlatform {
+        HostPlatform { os, arch, musl }
+    }
+
+    #[test]
+    fn asset_selection_prefers_windows_arm64() {
+        let names = vec![
+            "lune-0.8.9-windows-aarch64.zip".to_string(),
+            "lune-0.8.9-windows-x86_64.zip".to_string(),
+            "lune-0.8.9-macos-aarch64.zip".to_string(),
+        ];
+        let arm = pick_platform_asset(&names, &host("windows", "aarch64", false), |n| n).unwrap();
+        assert_eq!(arm, "lune-0.8.9-windows-aarch64.zip");
+        let x64 = pick_platform_asset(&names, &host("windows", "x86_64", false), |n| n).unwrap();
+        assert_eq!(x64, "lune-0.8.9-windows-x86_64.zip");
+    }
+
+    #[test]
+    fn asset_selection_matches_musl_hosts() {
+        let names = vec![
+            "lune-0.8.9-linux-x86_64.zip".to_string(),
+            "lune-0.8.9-linux-x86_64-musl.zip".to_string(),
+            "lune-0.8.9-linux-aarch64.zip".to_string(),
+        ];
+        let musl = pick_platform_asset(&names, &host("linux", "x86_64", true), |n| n).unwrap();
+        assert_eq!(musl, "lune-0.8.9-linux-x86_64-musl.zip");
+        let gnu = pick_platform_asset(&names, &host("linux", "x86_64", false), |n| n).unwrap();
+        assert_eq!(gnu, "lune-0.8.9-linux-x86_64.zip");
+        assert!(pick_platform_asset(&names[..1], &host("linux", "aarch64", false), |n| n).is_none());
+    }
+
+    #[test]
+    fn asset_os_keys_do_not_match_darwin_as_windows() {
+        assert!(asset_platform_score("lune-macos-darwin-x86_64.zip", &host("windows", "x86_64", false)).is_none());
+    }
+
+    #[test]
+    fn render_release_notes_truncates_long_notes() {
+        let notes = (0..RELEASE_NOTES_MAX_LINES + 5)
+            .map(|i| format!("- change {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rendered = render_release_notes("Lunu v1.0.0", &notes, Some("https://example.com"));
+        assert!(rendered.starts_with("Release notes for Lunu v1.0.0:"));
+        assert!(rendered.contains("... 5 more line(s)"));
+        assert!(rendered.ends_with("Full notes: https://example.com"));
     }
 
     #[tokio::test]
@@ -2718,14 +8418,69 @@ mod tests {
 
         std::env::remove_var("LUNU_RUNTIME");
         std::env::remove_var("LUNU_INIT_RUNTIME");
-        init_project(root).await.unwrap();
+        init_project(root, &InitOptions { assume_yes: true, skip_types: true, ..InitOptions::default() }).await.unwrap();
 
         assert!(root.join("lunu.toml").exists());
         assert!(root.join("lunu.lock").exists());
         assert!(root.join(".luaurc").exists());
         assert!(root.join("modules").join("lunu").join("init.luau").exists());
+        assert!(root.join("modules").join("lunu").join("resources.luau").exists());
+        assert!(root.join("modules").join("lunu").join("test.luau").exists());
         assert!(root.join("src").join("main.luau").exists());
         assert!(root.join("config").join("settings.json").exists());
+        assert!(validate_config(root).unwrap());
+    }
+
+    #[test]
+    fn setup_wizard_collects_answers() {
+        let mut input = io::Cursor::new("n\n\nyes\ndemo\n2\nmaybe\n3\nn\n");
+        let plan = ask_setup_plan(&mut input).unwrap();
+        assert_eq!(plan, SetupPlan {
+            install: false,
+            download_runtimes: true,
+            project: Some(ProjectPlan {
+                name: "demo".to_string(),
+                runtime: RuntimeKind::Lute,
+                template: TemplateKind::Service,
+                editor: false,
+            }),
+        });
+
+        // Enter everywhere (or a closed stdin) takes the defaults
+        let plan = ask_setup_plan(&mut io::Cursor::new("")).unwrap();
+        let project = plan.project.unwrap();
+        assert!(plan.install && plan.download_runtimes);
+        assert_eq!((project.name.as_str(), project.runtime, project.template), ("my-lunu-app", RuntimeKind::Lune, TemplateKind::App));
+    }
+
+    #[test]
+    fn only_offline_commands_run_locally() {
+        let local = |args: &[&str]| {
+            let cli = Cli::try_parse_from(std::iter::once("lunu").chain(args.iter().copied())).unwrap();
+            cli.command.as_ref().is_some_and(Commands::is_local)
+        };
+        assert!(local(&["audit"]) && local(&["verify"]) && local(&["tree"]));
+        assert!(!local(&["audit", "--advisories"]));
+        assert!(!local(&["install"]) && !local(&[]));
+    }
+
+    #[test]
+    fn templates_apply_to_both_runtimes() {
+        for runtime in [RuntimeKind::Lute, RuntimeKind::Lune] {
+            let [app, game, service] = [TemplateKind::App, TemplateKind::Game, TemplateKind::Service].map(|t| main_template(runtime, t));
+            assert!(app != game && game != service && app != service);
+            assert!(game.contains("tick") && service.contains("pong"));
+        }
+        assert!(main_template(RuntimeKind::Lute, TemplateKind::App).contains("@lute/process"));
+    }
+
+    #[test]
+    fn generated_configs_match_schema() {
+        for runtime in [RuntimeKind::Lute, RuntimeKind::Lune] {
+            let cfg = ProjectConfig::new_with_runtime("demo", runtime_config_for(runtime), Some(build_config_for(runtime, None)));
+            let content = toml::to_string_pretty(&cfg).unwrap();
+            assert_eq!(config_schema::validate(ConfigFile::LunuToml, &content).unwrap(), Vec::new());
+        }
     }
 
     #[tokio::test]
@@ -2735,19 +8490,176 @@ mod tests {
 
         std::env::remove_var("LUNU_RUNTIME");
         std::env::remove_var("LUNU_INIT_RUNTIME");
-        init_project(root).await.unwrap();
+        init_project(root, &InitOptions { assume_yes: true, skip_types: true, ..InitOptions::default() }).await.unwrap();
         let exe_name = if let Some(ext) = executable_extension() {
             format!("main.{}", ext)
         } else {
             "main".to_string()
         };
         std::fs::write(root.join(&exe_name), "stub").unwrap();
+        let pycache = root.join("modules").join("lunu").join("__pycache__");
+        std::fs::create_dir_all(&pycache).unwrap();
+        std::fs::write(pycache.join("x.pyc"), "bytecode").unwrap();
 
-        package_project(root).await.unwrap();
+        package_project(root, false).await.unwrap();
 
         let dist = root.join("dist");
         assert!(dist.exists());
         assert!(dist.join(&exe_name).exists());
         assert!(dist.join("lunu.toml").exists());
+        assert!(dist.join("modules").join("lunu").join("init.luau").exists());
+        assert!(!dist.join("modules").join("lunu").join("__pycache__").exists());
+
+        package_project(root, true).await.unwrap();
+        assert!(dist.join("modules").join("lunu").join("__pycache__").join("x.pyc").exists());
+    }
+
+    #[test]
+    fn parses_package_platforms() {
+        let windows = HostPlatform::parse("windows-x64").unwrap();
+        assert_eq!(windows.label(), "windows-x86_64");
+        assert_eq!(HostPlatform::parse("macos-arm64").unwrap().label(), "macos-aarch64");
+        assert_eq!(HostPlatform::parse("Linux-AMD64-musl").unwrap().label(), "linux-x86_64-musl");
+        assert!(HostPlatform::parse("linux").is_err());
+        assert!(HostPlatform::parse("macos-arm64-musl").is_err());
+        assert!(HostPlatform::parse("beos-x64").is_err());
+
+        let asset = |name: &str| GithubAsset { name: name.to_string(), browser_download_url: String::new() };
+        let release = GithubRelease {
+            tag_name: "v0.8.9".to_string(),
+            assets: vec![
+                asset("lune-0.8.9-linux-x86_64.zip"),
+                asset("lune-0.8.9-macos-aarch64.zip"),
+                asset("lune-0.8.9-windows-x86_64.zip"),
+            ],
+            prerelease: false,
+            draft: false,
+            body: None,
+            html_url: None,
+        };
+        let none = RuntimeAssetPatterns::default();
+        let picked = pick_runtime_asset_for(&release, RuntimeTarget::Lune, &windows, &none).unwrap();
+        assert_eq!(picked.name, "lune-0.8.9-windows-x86_64.zip");
+        assert!(pick_runtime_asset_for(&release, RuntimeTarget::Lune, &HostPlatform::parse("linux-arm64").unwrap(), &none).is_none());
+    }
+
+    #[test]
+    fn runtime_asset_patterns_pick_unusual_names() {
+        let asset = |name: &str| GithubAsset { name: name.to_string(), browser_download_url: String::new() };
+        let release = GithubRelease {
+            tag_name: "v0.9.0".to_string(),
+            assets: vec![asset("lune-0.9.0-win64.zip"), asset("lune-0.9.0-linux-x86_64.zip"), asset("lune-0.9.0-rpi.tar.gz")],
+            prerelease: false,
+            draft: false,
+            body: None,
+            html_url: None,
+        };
+        let patterns = RuntimeAssetPatterns::parse(
+            "[runtime.assets]\nlune = \"LUNE-*-win64.zip\"\n\n[runtime.assets.linux-arm64]\nlune = \"lune-*-rpi.tar.gz\"\n",
+        )
+        .unwrap();
+        let pick = |platform: &str| pick_runtime_asset_for(&release, RuntimeTarget::Lune, &HostPlatform::parse(platform).unwrap(), &patterns).map(|a| a.name);
+        assert_eq!(pick("windows-x64").as_deref(), Some("lune-0.9.0-win64.zip"));
+        assert_eq!(pick("linux-arm64").as_deref(), Some("lune-0.9.0-rpi.tar.gz"));
+        // The shared pattern skips assets named for another OS, and the usual matching applies
+        assert_eq!(pick("linux-x64").as_deref(), Some("lune-0.9.0-linux-x86_64.zip"));
+        assert!(pick_runtime_asset_for(&release, RuntimeTarget::Lute, &HostPlatform::parse("windows-x64").unwrap(), &patterns).is_none());
+
+        assert!(RuntimeAssetPatterns::parse("[runtime.assets]\nluna = \"*\"\n").unwrap_err().to_string().contains("Unknown runtime 'luna'"));
+        assert!(RuntimeAssetPatterns::parse("[runtime.assets.beos]\nlune = \"*\"\n").is_err());
+        assert_eq!(RuntimeAssetPatterns::parse("[network]\nretries = 2\n").unwrap(), RuntimeAssetPatterns::default());
+    }
+
+    #[test]
+    fn mirror_url_appends_repo_name_to_bases() {
+        let upstream = "https://github.com/tlipe/json-luau";
+        assert_eq!(mirror_url("git@internal:mirrors/", upstream, "json"), "git@internal:mirrors/json-luau.git");
+        assert_eq!(mirror_url("git@internal:", upstream, "json"), "git@internal:json-luau.git");
+        assert_eq!(mirror_url("https://git.corp/mirrors/json.git", upstream, "json"), "https://git.corp/mirrors/json.git");
+        assert_eq!(mirror_url("ssh://git.corp/m/", "https://gitlab.com/a/lib.git", "lib2"), "ssh://git.corp/m/lib.git");
+    }
+
+    #[test]
+    fn git_operands_cannot_be_options() {
+        assert_eq!(git_operand("https://github.com/tlipe/json-luau", "upstream URL").unwrap(), "https://github.com/tlipe/json-luau");
+        assert!(git_operand("--upload-pack=touch /tmp/pwned", "upstream URL").is_err());
+    }
+
+    #[tokio::test]
+    async fn status_summarizes_project() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        local_project(root).await;
+        let module = root.join("modules").join("ml");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("bridge.json"), r#"{"worker":{"cmd":["lunu-missing-interpreter","worker.py"]}}"#).unwrap();
+        std::fs::write(root.join("main.exe"), "stub").unwrap();
+
+        let status = collect_status(root).await.unwrap();
+        assert_eq!(status.bridge_modules, vec![("ml".to_string(), "lunu-missing-interpreter".to_string())]);
+        assert_eq!(status.last_build.as_ref().unwrap().0, root.join("main.exe"));
+        assert!(status.warnings.iter().any(|w| w.contains("lunu-missing-interpreter")));
+
+        assert_eq!(worker_language("python3"), "python");
+        assert_eq!(worker_language("target/release/ml.exe"), "rust");
+        assert_eq!(format_age(7200), "2h ago");
+    }
+
+    #[test]
+    fn release_names_and_notes() {
+        let linux = HostPlatform::parse("linux-x64").unwrap();
+        assert_eq!(release_asset_name("demo", "v1.2.0", &linux, None), "demo-v1.2.0-linux-x86_64");
+        let windows = HostPlatform::parse("windows-arm64").unwrap();
+        let exe = release_asset_name("demo", "v1.2.0", &windows, Some("exe"));
+        assert_eq!(exe, "demo-v1.2.0-windows-aarch64.exe");
+        assert!(asset_platform_score(&exe, &windows).is_some());
+        assert!(asset_platform_score(&exe, &linux).is_none());
+
+        let notes = format_release_notes("v1.2.0", Some("v1.1.0"), &["Fix crash (abc123)".to_string()]);
+        assert!(notes.contains("- Fix crash (abc123)"));
+        assert!(notes.contains("v1.1.0...v1.2.0"));
+        assert!(format_release_notes("v0.1.0", None, &[]).contains("No changes recorded"));
+
+        let dir = tempdir().unwrap();
+        assert_eq!(release_repo(dir.path(), Some("tlipe/demo")).unwrap(), ("tlipe".to_string(), "demo".to_string()));
+        assert!(release_repo(dir.path(), Some("demo")).is_err());
+    }
+
+    #[test]
+    fn import_dir_names_stay_in_cwd() {
+        assert_eq!(import_dir_name("demo").unwrap(), Path::new("demo"));
+        for name in ["../demo", "/tmp/demo", "a/b", "..", ".", ""] {
+            assert!(import_dir_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn pinned_versions_match_either_tag_form() {
+        assert_eq!(version_tags("0.8.9"), ["0.8.9", "v0.8.9"]);
+        assert_eq!(version_tags("v0.8.9"), ["v0.8.9", "0.8.9"]);
+    }
+
+    #[tokio::test]
+    async fn assembles_platform_bundle() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let cfg = local_project(root).await;
+
+        let windows = HostPlatform::parse("windows-x64").unwrap();
+        let archive = assemble_platform_bundle(root, &cfg, false, &windows, b"runtime").unwrap();
+        let bundle_name = format!("{}-windows-x86_64", cfg.project.name);
+        assert_eq!(archive, root.join("dist").join(format!("{}.zip", bundle_name)));
+        let bundle = root.join("dist").join(&bundle_name);
+        assert_eq!(std::fs::read(bundle.join("bin").join("lune.exe")).unwrap(), b"runtime");
+        assert!(std::fs::read_to_string(bundle.join("run.bat")).unwrap().contains("bin\\lune.exe"));
+        assert!(bundle.join("src").join("main.luau").exists());
+
+        let linux = HostPlatform::parse("linux-arm64").unwrap();
+        let archive = assemble_platform_bundle(root, &cfg, false, &linux, b"runtime").unwrap();
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(archive).unwrap()).unwrap();
+        let prefix = format!("{}-linux-aarch64", cfg.project.name);
+        assert_eq!(zip.by_name(&format!("{}/bin/lune", prefix)).unwrap().unix_mode().unwrap() & 0o777, 0o755);
+        assert!(zip.by_name(&format!("{}/lunu.toml", prefix)).is_ok());
+        assert!(zip.by_name(&format!("{}/run.sh", prefix)).is_ok());
     }
 }
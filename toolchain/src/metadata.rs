@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+const README_SUMMARY_MAX_CHARS: usize = 280;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PackageMetadata {
+    pub url: String,
+    pub description: Option<String>,
+    pub license: Option<String>,
+    #[serde(default)]
+    pub latest_tags: Vec<String>,
+    pub readme_summary: Option<String>,
+    pub fetched_at: u64,
+}
+
+impl PackageMetadata {
+    pub fn is_stale(&self, now: u64, max_age_secs: u64) -> bool {
+        now.saturating_sub(self.fetched_at) > max_age_secs
+    }
+}
+
+/// Sidecar cache for package metadata fetched from GitHub, so `info`/`list` work offline.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MetadataCache {
+    #[serde(default)]
+    pub packages: BTreeMap<String, PackageMetadata>,
+}
+
+impl MetadataCache {
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(".lunu").join("metadata.json")
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).await
+            .with_context(|| format!("Failed to read metadata cache at {:?}", path))?;
+        let cache: MetadataCache = serde_json::from_str(&content)
+            .with_context(|| "Failed to parse metadata cache")?;
+        Ok(cache)
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .with_context(|| "Failed to serialize metadata cache")?;
+        fs::write(path, content).await
+            .with_context(|| format!("Failed to write metadata cache to {:?}", path))?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PackageMetadata> {
+        self.packages.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, metadata: PackageMetadata) {
+        self.packages.insert(name.to_string(), metadata);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.packages.remove(name);
+    }
+}
+
+/// Extracts `(owner, repo)` from a GitHub URL such as `https://github.com/owner/repo.git`.
+pub fn parse_github_repo(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .trim()
+        .trim_start_matches("git+")
+        .split_once("github.com")?
+        .1
+        .trim_start_matches([':', '/']);
+    let mut parts = rest.split('/').filter(|p| !p.is_empty());
+    let owner = parts.next()?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    if owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+/// Splits any git remote into `(host, path)`: `https://gitlab.com/group/sub/repo.git`,
+/// `ssh://git@codeberg.org:2222/owner/repo` and `git@host:owner/repo.git` all work. The path has
+/// at least two segments and no `.git` suffix. Ports are kept for HTTP(S) remotes only.
+pub fn parse_git_remote(url: &str) -> Option<(String, String)> {
+    let url = url.trim().trim_start_matches("git+");
+    let (host, path) = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            let host = if scheme.starts_with("http") { host } else { host.split(':').next()? };
+            (host, path)
+        }
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        }
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.split('/').filter(|p| !p.is_empty()).count() < 2 {
+        return None;
+    }
+    Some((host.to_lowercase(), path.to_string()))
+}
+
+/// First prose paragraph of a markdown readme, skipping headings, badges and HTML.
+pub fn readme_summary(readme: &str) -> Option<String> {
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    for line in readme.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let skip = trimmed.starts_with('#')
+            || trimmed.starts_with("[![")
+            || trimmed.starts_with("![")
+            || trimmed.starts_with('<')
+            || trimmed.starts_with("---");
+        if trimmed.is_empty() || skip {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(trimmed);
+    }
+    if paragraph.is_empty() {
+        return None;
+    }
+    let text = paragraph.join(" ");
+    if text.chars().count() <= README_SUMMARY_MAX_CHARS {
+        return Some(text);
+    }
+    let truncated: String = text.chars().take(README_SUMMARY_MAX_CHARS).collect();
+    Some(format!("{}...", truncated.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn metadata_cache_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = MetadataCache::path(dir.path());
+        let mut cache = MetadataCache::default();
+        cache.set("example", PackageMetadata {
+            url: "https://github.com/example/repo".to_string(),
+            license: Some("MIT".to_string()),
+            latest_tags: vec!["v1.0.0".to_string()],
+            fetched_at: 10,
+            ..Default::default()
+        });
+        cache.save(&path).await.unwrap();
+
+        let loaded = MetadataCache::load(&path).await.unwrap();
+        let meta = loaded.get("example").unwrap();
+        assert_eq!(meta.license.as_deref(), Some("MIT"));
+        assert!(meta.is_stale(100, 60));
+        assert!(!meta.is_stale(50, 60));
+    }
+
+    #[test]
+    fn parses_github_urls() {
+        assert_eq!(
+            parse_github_repo("https://github.com/owner/repo.git"),
+            Some(("owner".to_string(), "repo".to_string()))
+        );
+        assert_eq!(
+            parse_github_repo("git@github.com:owner/repo"),
+            Some(("owner".to_string(), "repo".to_string()))
+        );
+        assert_eq!(parse_github_repo("https://gitlab.com/owner/repo"), None);
+    }
+
+    #[test]
+    fn parses_git_remotes_of_any_host() {
+        let parsed = |url: &str| parse_git_remote(url).map(|(host, path)| format!("{} {}", host, path));
+        assert_eq!(parsed("https://gitlab.com/group/sub/repo.git").as_deref(), Some("gitlab.com group/sub/repo"));
+        assert_eq!(parsed("git@codeberg.org:owner/repo.git").as_deref(), Some("codeberg.org owner/repo"));
+        assert_eq!(parsed("ssh://git@git.example.com:2222/team/lib").as_deref(), Some("git.example.com team/lib"));
+        assert_eq!(parsed("http://git.local:3000/team/lib/").as_deref(), Some("git.local:3000 team/lib"));
+        assert_eq!(parsed("https://example.com/lib"), None);
+        assert_eq!(parsed("owner/repo"), None);
+    }
+
+    #[test]
+    fn readme_summary_skips_headings_and_badges() {
+        let readme = "# Title\n[![CI](badge.svg)](ci)\n\nA small library\nfor doing things.\n\n## Usage\nMore text.";
+        assert_eq!(readme_summary(readme).as_deref(), Some("A small library for doing things."));
+        assert_eq!(readme_summary("# Only a heading"), None);
+    }
+}
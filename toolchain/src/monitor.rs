@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+use anyhow::{Result, Context};
+use lunu_cli::bridge_server::{self, BridgeEvent};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{broadcast, mpsc};
+
+const RECENT_CALLS: usize = 8;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default, Clone)]
+pub struct CallStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl CallStats {
+    fn avg_ms(&self) -> u64 {
+        self.total_ms.checked_div(self.calls).unwrap_or(0)
+    }
+}
+
+/// Aggregates bridge call events and renders the live panel and exit summary.
+#[derive(Default)]
+pub struct BridgeMonitor {
+    stats: BTreeMap<String, CallStats>,
+    recent: VecDeque<BridgeEvent>,
+}
+
+impl BridgeMonitor {
+    pub fn record(&mut self, event: BridgeEvent) {
+        let key = format!("{}.{}", event.module, event.method);
+        let entry = self.stats.entry(key).or_default();
+        entry.calls += 1;
+        if event.status >= 400 {
+            entry.errors += 1;
+        }
+        entry.total_ms += event.latency_ms;
+        entry.max_ms = entry.max_ms.max(event.latency_ms);
+        if self.recent.len() == RECENT_CALLS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(event);
+    }
+
+    pub fn total_calls(&self) -> u64 {
+        self.stats.values().map(|s| s.calls).sum()
+    }
+
+    fn panel_lines(&self) -> Vec<String> {
+        let errors: u64 = self.stats.values().map(|s| s.errors).sum();
+        let mut lines = vec![format!("-- bridge: {} call(s), {} error(s) --", self.total_calls(), errors)];
+        for event in &self.recent {
+            lines.push(format!(
+                "  {:<32} {:>3} {:>6}ms",
+                format!("{}.{}", event.module, event.method),
+                event.status,
+                event.latency_ms
+            ));
+        }
+        lines
+    }
+
+    pub fn summary(&self) -> String {
+        if self.stats.is_empty() {
+            return "Bridge summary: no calls.".to_string();
+        }
+        let mut out = format!(
+            "Bridge summary:\n  {:<32} {:>6} {:>6} {:>8} {:>8}\n",
+            "call", "count", "errors", "avg", "max"
+        );
+        for (name, s) in &self.stats {
+            out.push_str(&format!(
+                "  {:<32} {:>6} {:>6} {:>6}ms {:>6}ms\n",
+                name, s.calls, s.errors, s.avg_ms(), s.max_ms
+            ));
+        }
+        out.trim_end().to_string()
+    }
+}
+
+enum ScriptOutput {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Redraws the panel in place on stderr. Script output is printed above it.
+struct Panel {
+    live: bool,
+    drawn: usize,
+}
+
+impl Panel {
+    fn clear(&mut self) {
+        if self.drawn > 0 {
+            eprint!("\x1b[{}A\x1b[J", self.drawn);
+            self.drawn = 0;
+        }
+    }
+
+    fn draw(&mut self, monitor: &BridgeMonitor) {
+        if !self.live {
+            return;
+        }
+        self.clear();
+        let lines = monitor.panel_lines();
+        for line in &lines {
+            eprintln!("{}", line);
+        }
+        self.drawn = lines.len();
+        let _ = io::stderr().flush();
+    }
+}
+
+fn forward_lines<R>(reader: R, tx: mpsc::UnboundedSender<ScriptOutput>, wrap: fn(String) -> ScriptOutput)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send(wrap(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Starts the dev bridge for `root` in-process, runs `command` against it and shows bridge
+/// calls as they happen. Returns the script's exit status after printing a summary.
+pub async fn run_with_bridge_monitor(root: &Path, command: std::process::Command) -> Result<ExitStatus> {
+    let (events_tx, mut events) = broadcast::channel(256);
+    let addr = bridge_server::spawn_with_events(root.to_path_buf(), events_tx)
+        .await
+        .with_context(|| "Failed to start the dev bridge. Stop any running 'lunu dev' first.")?;
+    println!("Bridge monitor: dev bridge on http://{}", addr);
+
+    let mut cmd = tokio::process::Command::from(command);
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = cmd.spawn().with_context(|| "Failed to start script")?;
+
+    let (out_tx, mut output) = mpsc::unbounded_channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, out_tx.clone(), ScriptOutput::Stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, out_tx, ScriptOutput::Stderr);
+    }
+
+    let mut monitor = BridgeMonitor::default();
+    let mut panel = Panel { live: io::stderr().is_terminal(), drawn: 0 };
+    let mut dirty = false;
+    let mut ticker = tokio::time::interval(REDRAW_INTERVAL);
+    let mut status = None;
+    let mut output_open = true;
+    // A closed channel resolves `recv` at once, so it is no longer polled once the bridge is gone
+    let mut events_open = true;
+
+    while status.is_none() || output_open {
+        tokio::select! {
+            event = events.recv(), if events_open => match event {
+                Ok(event) => {
+                    monitor.record(event);
+                    dirty = true;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => events_open = false,
+            },
+            line = output.recv(), if output_open => match line {
+                Some(line) => {
+                    panel.clear();
+                    match line {
+                        ScriptOutput::Stdout(l) => println!("{}", l),
+                        ScriptOutput::Stderr(l) => eprintln!("{}", l),
+                    }
+                    dirty = true;
+                }
+                None => output_open = false,
+            },
+            exit = child.wait(), if status.is_none() => {
+                status = Some(exit.with_context(|| "Failed to wait for script")?);
+            },
+            _ = ticker.tick() => {
+                if dirty {
+                    panel.draw(&monitor);
+                    dirty = false;
+                }
+            },
+        }
+    }
+
+    while let Ok(event) = events.try_recv() {
+        monitor.record(event);
+    }
+    panel.clear();
+    println!("{}", monitor.summary());
+    status.ok_or_else(|| anyhow::anyhow!("Script did not exit"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(method: &str, status: u16, latency_ms: u64) -> BridgeEvent {
+        BridgeEvent {
+            module: "math".to_string(),
+            method: method.to_string(),
+            status,
+            latency_ms,
+        }
+    }
+
+    #[test]
+    fn monitor_aggregates_calls() {
+        let mut monitor = BridgeMonitor::default();
+        monitor.record(event("add", 200, 10));
+        monitor.record(event("add", 500, 30));
+        monitor.record(event("mul", 200, 5));
+        assert_eq!(monitor.total_calls(), 3);
+        let add = &monitor.stats["math.add"];
+        assert_eq!((add.calls, add.errors, add.avg_ms(), add.max_ms), (2, 1, 20, 30));
+        assert!(monitor.summary().contains("math.mul"));
+    }
+
+    #[test]
+    fn panel_keeps_recent_calls_only() {
+        let mut monitor = BridgeMonitor::default();
+        for i in 0..20 {
+            monitor.record(event("add", 200, i));
+        }
+        assert_eq!(monitor.panel_lines().len(), RECENT_CALLS + 1);
+    }
+}
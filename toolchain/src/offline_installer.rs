@@ -0,0 +1,136 @@
+//! Offline installer bundles for `lunu make-installer --offline`. A bundle is the lunu binary, which
+//! installs itself when run without arguments, next to a `payload/` directory:
+//!
+//! ```text
+//! out/lunu
+//! out/payload/manifest.json
+//! out/payload/runtimes/lune/v0.8.9/lune
+//! out/payload/templates/acme-service/template.toml
+//! ```
+//!
+//! When the installer finds a payload next to itself, it installs the runtimes into the runtime
+//! cache and the templates under `~/.lunu/templates`. Each runtime is checked against the manifest
+//! first, so a damaged copy on a USB stick fails the install instead of the first run.
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::file_lock::write_atomic;
+
+pub const DIR: &str = "payload";
+pub const MANIFEST: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Manifest {
+    /// Version of the lunu binary in the bundle
+    pub lunu: String,
+    /// Platform the binaries are for, e.g. `linux-x86_64`
+    pub platform: String,
+    /// Unix time the bundle was made
+    pub created_at: u64,
+    #[serde(default)]
+    pub runtimes: Vec<RuntimeEntry>,
+    /// Template names; each is a directory under `templates/`
+    #[serde(default)]
+    pub templates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuntimeEntry {
+    /// `lune` or `lute`
+    pub name: String,
+    pub version: String,
+    /// Path inside the payload, `/`-separated
+    pub file: String,
+    pub sha256: String,
+}
+
+impl Manifest {
+    pub fn new(lunu: &str, platform: &str, created_at: u64) -> Self {
+        Self { lunu: lunu.to_string(), platform: platform.to_string(), created_at, runtimes: Vec::new(), templates: Vec::new() }
+    }
+
+    /// Writes a runtime binary into `payload` and records it.
+    pub fn add_runtime(&mut self, payload: &Path, name: &str, version: &str, filename: &str, binary: &[u8]) -> Result<()> {
+        let file = format!("runtimes/{}/{}/{}", name, version, filename);
+        let path = payload.join(&file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, binary).with_context(|| format!("Failed to write {:?}", path))?;
+        self.runtimes.push(RuntimeEntry {
+            name: name.to_string(),
+            version: version.to_string(),
+            file,
+            sha256: hex::encode(Sha256::digest(binary)),
+        });
+        Ok(())
+    }
+
+    /// Where the template called `name` goes in `payload`; record it with `templates.push`.
+    pub fn template_dir(payload: &Path, name: &str) -> PathBuf {
+        payload.join("templates").join(name)
+    }
+
+    /// The bytes of `entry`, checked against the recorded checksum.
+    pub fn runtime_bytes(&self, payload: &Path, entry: &RuntimeEntry) -> Result<Vec<u8>> {
+        let path = payload.join(&entry.file);
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?} from the installer payload", path))?;
+        if hex::encode(Sha256::digest(&bytes)) != entry.sha256 {
+            return Err(anyhow::anyhow!(
+                "{} {} in the installer payload is damaged (checksum mismatch); copy the bundle again",
+                entry.name,
+                entry.version
+            ));
+        }
+        Ok(bytes)
+    }
+
+    pub fn save(&self, payload: &Path) -> Result<()> {
+        write_atomic(&payload.join(MANIFEST), serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    pub fn load(payload: &Path) -> Result<Self> {
+        let path = payload.join(MANIFEST);
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid installer manifest {:?}", path))
+    }
+}
+
+/// The payload directory next to the running installer, when there is one.
+pub fn find(exe: &Path) -> Option<PathBuf> {
+    let payload = exe.parent()?.join(DIR);
+    payload.join(MANIFEST).is_file().then_some(payload)
+}
+
+/// Where installed templates live; `lunu scaffold --from <name>` looks here.
+pub fn templates_dir(home: &Path) -> PathBuf {
+    home.join(".lunu").join("templates")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_and_checks_payload_runtimes() {
+        let dir = tempfile::tempdir().unwrap();
+        let payload = dir.path().join(DIR);
+        let mut manifest = Manifest::new("0.2.0", "linux-x86_64", 10);
+        manifest.add_runtime(&payload, "lune", "v0.8.9", "lune", b"lune binary").unwrap();
+        manifest.templates.push("service".to_string());
+        manifest.save(&payload).unwrap();
+
+        assert_eq!(find(&dir.path().join("lunu")), Some(payload.clone()));
+        assert_eq!(find(&payload.join("lunu")), None);
+        let loaded = Manifest::load(&payload).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.runtimes[0].file, "runtimes/lune/v0.8.9/lune");
+        assert_eq!(loaded.runtime_bytes(&payload, &loaded.runtimes[0]).unwrap(), b"lune binary");
+
+        std::fs::write(payload.join("runtimes/lune/v0.8.9/lune"), b"truncated").unwrap();
+        let err = loaded.runtime_bytes(&payload, &loaded.runtimes[0]).unwrap_err().to_string();
+        assert!(err.contains("lune v0.8.9 in the installer payload is damaged"), "{}", err);
+    }
+}
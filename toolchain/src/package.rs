@@ -1,49 +1,462 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use git2::{FetchOptions, build::RepoBuilder};
-use anyhow::Result;
+use git2::{FetchOptions, Oid, Repository, build::{CheckoutBuilder, RepoBuilder}};
+use anyhow::{Context, Result};
+use semver::{Version, VersionReq};
 use tokio::fs;
 use sha2::{Sha256, Digest};
 use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use crate::{archive, gitlab, http, package_cache};
+use crate::metadata::parse_git_remote;
+use crate::compat::CompatibilityLayer;
 
 pub struct PackageManager {
     root_dir: PathBuf,
+    /// Global package store; every online install is added here and linked into the project
+    cache_dir: Option<PathBuf>,
+    /// Install from `cache_dir` only, without touching the network
+    offline: bool,
+}
+
+/// The release tag a dependency's `version` requirement resolved to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedVersion {
+    pub tag: String,
+    pub version: Version,
+}
+
+/// Which revision of a repository to install.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitRef {
+    /// HEAD of the default branch
+    Default,
+    /// Highest tag matching a semver requirement; `locked` is kept while it still matches
+    Version { req: String, locked: Option<String> },
+    Tag(String),
+    Branch(String),
+    /// Exact commit, full or abbreviated SHA
+    Rev(String),
+}
+
+/// How a github.com dependency is fetched (`fetch` in lunu.toml).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchMethod {
+    /// The codeload zip of the ref, cloning when that fails (private repositories) and for other hosts
+    #[default]
+    Auto,
+    /// The codeload zip only
+    Archive,
+    /// Always clone with git
+    Git,
+}
+
+impl FetchMethod {
+    pub fn is_auto(&self) -> bool {
+        *self == FetchMethod::Auto
+    }
+}
+
+/// `https://codeload.github.com/<owner>/<repo>/zip/<refname>` for a github.com repository;
+/// `refname` is `refs/tags/<tag>`, `refs/heads/<branch>` or `None` for the default branch.
+fn codeload_url(url: &str, refname: Option<&str>) -> Option<String> {
+    let (host, path) = parse_git_remote(url)?;
+    if host != "github.com" || path.split('/').count() != 2 {
+        return None;
+    }
+    Some(format!("https://codeload.github.com/{}/zip/{}", path, refname.unwrap_or("HEAD")))
+}
+
+/// Commit of a `git archive` zip, which GitHub stores as the archive comment.
+fn archive_commit(bytes: &[u8]) -> Option<String> {
+    let zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).ok()?;
+    let comment = std::str::from_utf8(zip.comment()).ok()?.trim();
+    (comment.len() == 40 && comment.chars().all(|c| c.is_ascii_hexdigit())).then(|| comment.to_string())
+}
+
+/// A package folder inside a repository (`subdir` in lunu.toml, `lunu add --path`), relative with
+/// `/` separators; `..` and absolute paths are rejected.
+pub fn normalize_subdir(subdir: &str) -> Result<String> {
+    let normalized = subdir.trim().replace('\\', "/");
+    let parts: Vec<&str> = normalized.split('/').filter(|p| !p.is_empty() && *p != ".").collect();
+    if parts.is_empty() || normalized.starts_with('/') || normalized.contains(':') || parts.contains(&"..") {
+        return Err(anyhow::anyhow!("Invalid package path '{}': expected a folder inside the repository, e.g. packages/signal", subdir));
+    }
+    Ok(parts.join("/"))
+}
+
+/// Replaces the checkout at `install_path` with its `subdir`, so only that folder of a larger
+/// repository ends up in modules/.
+pub async fn keep_subdir(install_path: &Path, subdir: &str) -> Result<()> {
+    let subdir = normalize_subdir(subdir)?;
+    if !install_path.join(&subdir).is_dir() {
+        fs::remove_dir_all(install_path).await.ok();
+        return Err(anyhow::anyhow!("The repository has no folder '{}'", subdir));
+    }
+    let name = install_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let staging = install_path.with_file_name(format!(".{}.repository", name));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).await?;
+    }
+    fs::rename(install_path, &staging).await?;
+    fs::rename(staging.join(&subdir), install_path).await?;
+    fs::remove_dir_all(&staging).await?;
+    Ok(())
+}
+
+pub struct InstalledPackage {
+    pub path: PathBuf,
+    /// Tag that was checked out, from a version requirement or an explicit tag
+    pub tag: Option<String>,
+    /// Semver version of `tag`, when it has one
+    pub version: Option<Version>,
+    /// Commit that was checked out; `None` for downloads
+    pub commit: Option<String>,
+}
+
+/// Parses a dependency `version` from lunu.toml. A bare version (`1.2.3`, `v1.2.3`) pins exactly
+/// that release; anything else is a semver range such as `^1.2`, `~0.3.1` or `>=1.0, <2`.
+pub fn parse_version_req(spec: &str) -> Result<VersionReq> {
+    let spec = spec.trim();
+    if let Ok(version) = Version::parse(spec.trim_start_matches(['v', 'V'])) {
+        return Ok(VersionReq::parse(&format!("={}", version))?);
+    }
+    VersionReq::parse(spec).with_context(|| format!("Invalid version requirement '{}'", spec))
+}
+
+/// Highest tag satisfying `req`. The `locked` version is kept while it still satisfies `req`,
+/// so `lunu install` reproduces lunu.lock and only `lunu update` moves to newer releases.
+pub fn resolve_tag(tags: &[String], req: &VersionReq, locked: Option<&str>) -> Option<ResolvedVersion> {
+    let candidates: Vec<ResolvedVersion> = tags
+        .iter()
+        .filter_map(|tag| {
+            let version = Version::parse(tag.trim_start_matches(['v', 'V'])).ok()?;
+            req.matches(&version).then(|| ResolvedVersion { tag: tag.clone(), version })
+        })
+        .collect();
+    let locked = locked.and_then(|l| Version::parse(l.trim_start_matches(['v', 'V'])).ok());
+    if let Some(kept) = candidates.iter().find(|c| Some(&c.version) == locked.as_ref()) {
+        return Some(kept.clone());
+    }
+    candidates.into_iter().max_by(|a, b| a.version.cmp(&b.version))
+}
+
+/// Tags and branches advertised by a remote (`git ls-remote --tags --heads`).
+#[derive(Debug, Default)]
+pub struct RemoteRefs {
+    pub tags: Vec<String>,
+    pub branches: Vec<String>,
+}
+
+/// A credential `lunu` offers a git server that asks for one.
+#[derive(Debug, Clone, PartialEq)]
+enum Credential {
+    /// The user name alone, which SSH servers ask for before the key when the URL has none
+    Username(String),
+    SshAgent(String),
+    SshKey { user: String, key: PathBuf },
+    /// git's configured credential helpers (`credential.helper`)
+    Helper,
+    /// An access token from the environment
+    Token { user: &'static str, token: String },
+}
+
+/// The credentials to try, in order, for `url` when the server accepts `allowed`: the SSH agent,
+/// then the default keys in `ssh_dir` for SSH; git's credential helpers, then a token from the
+/// environment (`GITHUB_TOKEN`, `GITLAB_TOKEN`, `BITBUCKET_TOKEN`) for HTTPS. Tokens go only to
+/// their exact host (for GitLab, gitlab.com or one of `gitlab_hosts`) and never over plain HTTP.
+fn credentials(
+    url: &str,
+    username: Option<&str>,
+    allowed: git2::CredentialType,
+    ssh_dir: Option<&Path>,
+    gitlab_hosts: &[String],
+    env: impl Fn(&str) -> Option<String>,
+) -> Vec<Credential> {
+    let user = username.unwrap_or("git").to_string();
+    let mut credentials = Vec::new();
+    if allowed.contains(git2::CredentialType::USERNAME) {
+        credentials.push(Credential::Username(user.clone()));
+    }
+    if allowed.contains(git2::CredentialType::SSH_KEY) {
+        credentials.push(Credential::SshAgent(user.clone()));
+        for name in ["id_ed25519", "id_ecdsa", "id_rsa"] {
+            let key = ssh_dir.map(|dir| dir.join(name)).filter(|key| key.is_file());
+            credentials.extend(key.map(|key| Credential::SshKey { user: user.clone(), key }));
+        }
+    }
+    if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+        credentials.push(Credential::Helper);
+        let host = parse_git_remote(url).map(|(host, _)| host).filter(|_| url.trim().starts_with("https://")).unwrap_or_default();
+        let tokens: &[(&str, &str)] = match host.as_str() {
+            "github.com" => &[("x-access-token", "GITHUB_TOKEN"), ("x-access-token", "GH_TOKEN")],
+            "bitbucket.org" => &[("x-token-auth", "BITBUCKET_TOKEN")],
+            host if gitlab::sends_token_to(host, gitlab_hosts) => &[("oauth2", "GITLAB_TOKEN")],
+            _ => &[],
+        };
+        for (user, var) in tokens {
+            if let Some(token) = env(var).filter(|t| !t.trim().is_empty()) {
+                credentials.push(Credential::Token { user, token });
+            }
+        }
+    }
+    credentials
+}
+
+/// Remote callbacks that answer credential requests from `credentials`, offering one not tried yet
+/// each time the server rejects the last, until none are left.
+fn remote_callbacks<'a>() -> git2::RemoteCallbacks<'a> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    let mut tried = Vec::new();
+    let gitlab_hosts = gitlab::token_hosts().unwrap_or_else(|err| {
+        tracing::warn!("{:#}; GITLAB_TOKEN is only sent to gitlab.com", err);
+        Vec::new()
+    });
+    callbacks.credentials(move |url, username, allowed| {
+        let ssh_dir = dirs::home_dir().map(|home| home.join(".ssh"));
+        for credential in credentials(url, username, allowed, ssh_dir.as_deref(), &gitlab_hosts, |var| std::env::var(var).ok()) {
+            if tried.contains(&credential) {
+                continue;
+            }
+            tried.push(credential.clone());
+            let cred = match credential {
+                Credential::Username(user) => git2::Cred::username(&user),
+                Credential::SshAgent(user) => git2::Cred::ssh_key_from_agent(&user),
+                Credential::SshKey { user, key } => git2::Cred::ssh_key(&user, None, &key, None),
+                Credential::Helper => git2::Config::open_default().and_then(|config| git2::Cred::credential_helper(&config, url, username)),
+                Credential::Token { user, token } => git2::Cred::userpass_plaintext(user, &token),
+            };
+            if cred.is_ok() {
+                return cred;
+            }
+        }
+        Err(git2::Error::from_str(&format!(
+            "No accepted credentials for {} (tried the SSH agent and ~/.ssh keys, git credential helpers and access tokens)",
+            url
+        )))
+    });
+    callbacks
+}
+
+/// Fetch options with `remote_callbacks`.
+fn fetch_options<'a>() -> FetchOptions<'a> {
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(remote_callbacks());
+    options
+}
+
+fn remote_refs(url: &str) -> Result<RemoteRefs, git2::Error> {
+    let mut remote = git2::Remote::create_detached(url)?;
+    let connection = remote.connect_auth(git2::Direction::Fetch, Some(remote_callbacks()), None)?;
+    let mut refs = RemoteRefs::default();
+    for head in connection.list()? {
+        if let Some(tag) = head.name().strip_prefix("refs/tags/").filter(|t| !t.ends_with("^{}")) {
+            refs.tags.push(tag.to_string());
+        } else if let Some(branch) = head.name().strip_prefix("refs/heads/") {
+            refs.branches.push(branch.to_string());
+        }
+    }
+    Ok(refs)
+}
+
+/// Commit that `refname` (or the default branch when `None`) points at on the remote.
+fn remote_commit(url: &str, refname: Option<&str>) -> Result<Option<String>, git2::Error> {
+    let mut remote = git2::Remote::create_detached(url)?;
+    let connection = remote.connect_auth(git2::Direction::Fetch, Some(remote_callbacks()), None)?;
+    let wanted = refname.unwrap_or("HEAD");
+    Ok(connection.list()?.iter().find(|head| head.name() == wanted).map(|head| head.oid().to_string()))
+}
+
+/// Shallow-fetches a single ref (`refs/tags/v1.0.0`, `refs/heads/dev`) into a fresh repository at
+/// `path` and checks it out (detached HEAD).
+fn clone_ref(url: &str, path: &Path, refname: &str) -> Result<(), git2::Error> {
+    let repo = Repository::init(path)?;
+    let mut remote = repo.remote("origin", url)?;
+    let mut fetch_opts = fetch_options();
+    fetch_opts.depth(1);
+    remote.fetch(&[&format!("+{0}:{0}", refname)], Some(&mut fetch_opts), None)?;
+    let commit = repo.revparse_single(refname)?.peel_to_commit()?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+    repo.set_head_detached(commit.id())?;
+    Ok(())
+}
+
+/// Fetches the commit `rev` (full or abbreviated SHA) of `url` into a fresh repository at `path`
+/// and checks it out (detached HEAD). A full SHA is fetched alone where the server allows it;
+/// otherwise every branch and tag is fetched and searched. `Ok(None)` when the remote has no
+/// such commit.
+fn clone_rev(url: &str, path: &Path, rev: &str) -> Result<Option<String>, git2::Error> {
+    let repo = Repository::init(path)?;
+    let mut remote = repo.remote("origin", url)?;
+    let full = rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit());
+    let mut fetched = false;
+    if full {
+        let mut fetch_opts = fetch_options();
+        fetch_opts.depth(1);
+        fetched = remote.fetch(&[rev], Some(&mut fetch_opts), None).is_ok();
+    }
+    if !fetched || repo.find_commit(Oid::from_str(rev)?).is_err() {
+        remote.fetch(&["+refs/heads/*:refs/remotes/origin/*", "+refs/tags/*:refs/tags/*"], Some(&mut fetch_options()), None)?;
+    }
+    let commit = match repo.revparse_single(rev).and_then(|object| object.peel_to_commit()) {
+        Ok(commit) => commit,
+        Err(err) if matches!(err.code(), git2::ErrorCode::NotFound | git2::ErrorCode::Ambiguous | git2::ErrorCode::InvalidSpec) => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+    repo.set_head_detached(commit.id())?;
+    Ok(Some(commit.id().to_string()))
+}
+
+fn classify(refs: &RemoteRefs, name: &str) -> Option<GitRef> {
+    if refs.tags.iter().any(|t| t == name) {
+        return Some(GitRef::Tag(name.to_string()));
+    }
+    if refs.branches.iter().any(|b| b == name) {
+        return Some(GitRef::Branch(name.to_string()));
+    }
+    if (7..=40).contains(&name.len()) && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(GitRef::Rev(name.to_lowercase()));
+    }
+    parse_version_req(name).ok().map(|_| GitRef::Version { req: name.to_string(), locked: None })
 }
 
 impl PackageManager {
     pub fn new(root_dir: PathBuf) -> Self {
-        Self { root_dir }
+        Self { root_dir, cache_dir: package_cache::dir(), offline: false }
     }
 
-    pub async fn install_package(&self, url: &str, _version: Option<&str>, target_name: &str) -> Result<(PathBuf, String)> {
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn is_cached(&self, url: &str, reference: &GitRef) -> bool {
+        self.cache_dir.as_ref().is_some_and(|cache| package_cache::lookup(cache, url, reference).is_some())
+    }
+
+    /// Commit the stored revision of `url` at `reference` was cloned from. Linked modules have no
+    /// `.git` of their own.
+    pub fn cached_commit(&self, url: &str, reference: &GitRef) -> Option<String> {
+        let cache = self.cache_dir.as_ref()?;
+        package_cache::lookup(cache, url, reference)?.commit
+    }
+
+    /// Installs `url` into `modules/<target_name>` at `reference`, from an archive or a clone as
+    /// `fetch` says.
+    pub async fn install_package(&self, url: &str, reference: &GitRef, target_name: &str, fetch: FetchMethod) -> Result<InstalledPackage> {
+        if self.offline {
+            return self.install_from_cache(url, reference, target_name).await;
+        }
+        if archive::is_download_url(url) {
+            return self.install_download(url, reference, target_name).await;
+        }
+        let (refname, tag, version) = match reference {
+            GitRef::Default => (None, None, None),
+            GitRef::Version { req, locked } => {
+                let resolved = self.resolve_version(url, req, locked.as_deref()).await?;
+                (Some(format!("refs/tags/{}", resolved.tag)), Some(resolved.tag), Some(resolved.version))
+            }
+            GitRef::Tag(tag) => {
+                let version = Version::parse(tag.trim_start_matches(['v', 'V'])).ok();
+                (Some(format!("refs/tags/{}", tag)), Some(tag.clone()), version)
+            }
+            GitRef::Branch(branch) => (Some(format!("refs/heads/{}", branch)), None, None),
+            GitRef::Rev(_) => (None, None, None),
+        };
+        // What the store records this install under: the tag a version resolved to, else the ref
+        let stored_ref = match &tag {
+            Some(tag) => GitRef::Tag(tag.clone()),
+            None => reference.clone(),
+        };
+
         // 1. Prepare Paths
         let install_path = self.root_dir.join("modules").join(target_name).clean();
+
+        if let Some(cached) = self.reusable(url, reference, tag.as_deref(), refname.as_deref()).await {
+            if install_path.exists() {
+                fs::remove_dir_all(&install_path).await?;
+            }
+            println!("Linking {} from the package store to {:?}...", url, install_path);
+            let (src, dst) = (cached.dir, install_path.clone());
+            tokio::task::spawn_blocking(move || package_cache::link_tree(&src, &dst)).await??;
+            return Ok(InstalledPackage { path: install_path, tag, version, commit: cached.commit });
+        }
         
         // 2. Clean existing
         if install_path.exists() {
             println!("Cleaning existing module at {:?}", install_path);
             fs::remove_dir_all(&install_path).await?;
         }
-        fs::create_dir_all(&install_path).await?;
 
-        // 3. Git Clone (Shallow)
-        println!("Cloning {} to {:?}...", url, install_path);
-        
-        // Run blocking git operation in spawn_blocking
-        let url_owned = url.to_string();
-        let path_owned = install_path.clone();
+        // 3. Codeload archive of GitHub repositories, without libgit2
+        let archive_ref = match reference {
+            GitRef::Rev(rev) => Some(rev.as_str()),
+            _ => refname.as_deref(),
+        };
+        let downloaded = match (fetch, codeload_url(url, archive_ref)) {
+            (FetchMethod::Git, _) | (FetchMethod::Auto, None) => None,
+            (FetchMethod::Archive, None) => {
+                return Err(anyhow::anyhow!("{} is not a github.com repository; fetch = \"archive\" needs one", url));
+            }
+            (_, Some(archive_url)) => match self.download_archive(url, &archive_url, &install_path).await {
+                Ok(commit) => Some(commit),
+                Err(err) if fetch == FetchMethod::Auto => {
+                    println!("Archive of {} unavailable ({:#}); cloning instead", url, err);
+                    None
+                }
+                Err(err) => return Err(err),
+            },
+        };
+        if let Some(commit) = downloaded {
+            self.store_and_link(url, &stored_ref, commit.as_deref(), &install_path).await?;
+            return Ok(InstalledPackage { path: install_path, tag, version, commit });
+        }
+
+        // 4. Git Clone (Shallow)
+        match reference {
+            GitRef::Default => println!("Cloning {} to {:?}...", url, install_path),
+            GitRef::Branch(branch) => println!("Cloning {} (branch {}) to {:?}...", url, branch, install_path),
+            GitRef::Rev(rev) => println!("Cloning {} at commit {} to {:?}...", url, rev, install_path),
+            _ => println!("Cloning {} at {} to {:?}...", url, tag.as_deref().unwrap_or_default(), install_path),
+        }
         
-        let _repo = tokio::task::spawn_blocking(move || {
-            let mut fetch_opts = FetchOptions::new();
-            fetch_opts.depth(1); // Shallow clone
+        http::retry(&format!("Clone {}", url), || async {
+            // A failed attempt can leave a partial checkout behind
+            if install_path.exists() {
+                fs::remove_dir_all(&install_path).await?;
+            }
+            fs::create_dir_all(&install_path).await?;
 
-            let mut builder = RepoBuilder::new();
-            builder.fetch_options(fetch_opts);
-            
-            builder.clone(&url_owned, &path_owned)
-        }).await??;
+            // Run blocking git operation in spawn_blocking
+            let url_owned = url.to_string();
+            let path_owned = install_path.clone();
+            let refname = refname.clone();
+            let rev = match reference {
+                GitRef::Rev(rev) => Some(rev.clone()),
+                _ => None,
+            };
+            tokio::task::spawn_blocking(move || {
+                if let Some(rev) = rev {
+                    return match clone_rev(&url_owned, &path_owned, &rev)? {
+                        Some(_) => Ok(()),
+                        None => Err(git2::Error::from_str(&format!("Commit {} not found in {}", rev, url_owned))),
+                    };
+                }
+                if let Some(refname) = refname {
+                    return clone_ref(&url_owned, &path_owned, &refname);
+                }
+                let mut fetch_opts = fetch_options();
+                fetch_opts.depth(1); // Shallow clone
 
-        // 4. Calculate Checksum
-        let checksum = self.calculate_dir_checksum(&install_path).await?;
+                let mut builder = RepoBuilder::new();
+                builder.fetch_options(fetch_opts);
+                
+                builder.clone(&url_owned, &path_owned).map(|_| ())
+            }).await??;
+            Ok(())
+        }).await?;
 
         // 5. Cleanup .venv if exists in the new package (Requisito 3.2)
         // Also cleanup global .venv if requested by user logic, but here we clean package specific artifacts
@@ -53,7 +466,167 @@ impl PackageManager {
             fs::remove_dir_all(venv_path).await.ok();
         }
 
-        Ok((install_path, checksum))
+        // 6. Move the checkout into the global store and link it back
+        let commit = Repository::open(&install_path)
+            .and_then(|repo| repo.head()?.peel_to_commit().map(|c| c.id().to_string()))
+            .ok();
+        self.store_and_link(url, &stored_ref, commit.as_deref(), &install_path).await?;
+
+        Ok(InstalledPackage { path: install_path, tag, version, commit })
+    }
+
+    /// Unpacks the codeload zip of a GitHub repository into `install_path`; returns the commit
+    /// the archive was made from when it names one.
+    async fn download_archive(&self, url: &str, archive_url: &str, install_path: &Path) -> Result<Option<String>> {
+        println!("Downloading {} to {:?}...", archive_url, install_path);
+        let client = http::client()?;
+        let bytes = http::retry(&format!("Download {}", archive_url), || async {
+            let res = client.get(archive_url).send().await?.error_for_status()?;
+            Ok(res.bytes().await?.to_vec())
+        })
+        .await?;
+        let commit = archive_commit(&bytes);
+        let (dst, url_owned) = (install_path.to_path_buf(), url.to_string());
+        let unpacked = tokio::task::spawn_blocking(move || -> Result<()> {
+            archive::unpack(&bytes, &dst, Some(archive::ArchiveFormat::Zip)).with_context(|| format!("Could not unpack the archive of {}", url_owned))?;
+            archive::strip_single_root(&dst)?;
+            // Same as a clone: no .venv left over from the repository
+            let venv = dst.join(".venv");
+            if venv.exists() {
+                std::fs::remove_dir_all(venv)?;
+            }
+            Ok(())
+        })
+        .await?;
+        if let Err(err) = unpacked {
+            if install_path.exists() {
+                fs::remove_dir_all(install_path).await.ok();
+            }
+            return Err(err);
+        }
+        Ok(commit)
+    }
+
+    /// Downloads an archive or single-file module (see `archive`) into `modules/<target_name>`.
+    /// Downloads have no refs, so they are stored like a repository's default branch.
+    async fn install_download(&self, url: &str, reference: &GitRef, target_name: &str) -> Result<InstalledPackage> {
+        if *reference != GitRef::Default {
+            return Err(anyhow::anyhow!("{} is a download, not a git repository; version, tag, branch and rev do not apply", url));
+        }
+        let install_path = self.root_dir.join("modules").join(target_name).clean();
+        println!("Downloading {} to {:?}...", url, install_path);
+        let client = http::client()?;
+        let bytes = http::retry(&format!("Download {}", url), || async {
+            let res = client.get(url).send().await?.error_for_status()?;
+            Ok(res.bytes().await?.to_vec())
+        })
+        .await?;
+
+        if install_path.exists() {
+            fs::remove_dir_all(&install_path).await?;
+        }
+        let (hint, dst, url_owned) = (archive::from_extension(url), install_path.clone(), url.to_string());
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            archive::unpack(&bytes, &dst, hint).with_context(|| format!("Could not unpack {}", url_owned))?;
+            archive::strip_single_root(&dst)
+        })
+        .await??;
+
+        self.store_and_link(url, &GitRef::Default, None, &install_path).await?;
+        Ok(InstalledPackage { path: install_path, tag: None, version: None, commit: None })
+    }
+
+    /// Moves a fresh install into the global store and links it back in place. A store failure
+    /// only warns; the install itself is complete.
+    async fn store_and_link(&self, url: &str, reference: &GitRef, commit: Option<&str>, install_path: &Path) -> Result<()> {
+        let Some(cache) = &self.cache_dir else {
+            return Ok(());
+        };
+        match package_cache::store(cache, url, reference, commit, install_path) {
+            Ok(stored) => {
+                fs::remove_dir_all(install_path).await?;
+                let dst = install_path.to_path_buf();
+                tokio::task::spawn_blocking(move || package_cache::link_tree(&stored, &dst)).await??;
+            }
+            Err(err) => println!("Warning: could not add {} to the package cache: {:#}", url, err),
+        }
+        Ok(())
+    }
+
+    /// A stored revision that is exactly what a clone would produce: the same tag or commit (tags
+    /// are treated as immutable), or a branch whose stored commit is still the remote's.
+    async fn reusable(&self, url: &str, reference: &GitRef, tag: Option<&str>, refname: Option<&str>) -> Option<package_cache::CachedPackage> {
+        let cache = self.cache_dir.as_ref()?;
+        if let Some(tag) = tag {
+            return package_cache::lookup(cache, url, &GitRef::Tag(tag.to_string()));
+        }
+        if let GitRef::Rev(_) = reference {
+            return package_cache::lookup(cache, url, reference);
+        }
+        let cached = package_cache::lookup(cache, url, reference)?;
+        let (url_owned, refname) = (url.to_string(), refname.map(str::to_string));
+        let remote = tokio::task::spawn_blocking(move || remote_commit(&url_owned, refname.as_deref())).await.ok()?.ok()??;
+        (cached.commit.as_deref() == Some(remote.as_str())).then_some(cached)
+    }
+
+    async fn install_from_cache(&self, url: &str, reference: &GitRef, target_name: &str) -> Result<InstalledPackage> {
+        let cached = self
+            .cache_dir
+            .as_ref()
+            .and_then(|cache| package_cache::lookup(cache, url, reference))
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the package cache; run 'lunu install' online first", url))?;
+        let install_path = self.root_dir.join("modules").join(target_name).clean();
+        if install_path.exists() {
+            fs::remove_dir_all(&install_path).await?;
+        }
+        println!("Installing {} from the package cache...", url);
+        let (src, dst) = (cached.dir.clone(), install_path.clone());
+        tokio::task::spawn_blocking(move || package_cache::link_tree(&src, &dst)).await??;
+        let version = match cached.resolved {
+            Some(resolved) => Some(resolved.version),
+            None => cached.tag.as_deref().and_then(|t| Version::parse(t.trim_start_matches(['v', 'V'])).ok()),
+        };
+        Ok(InstalledPackage { path: install_path, tag: cached.tag, version, commit: cached.commit })
+    }
+
+    /// Commit the remote's branch (or default branch) for `reference` points at now; `None` for
+    /// tags, versions and revs, which do not move.
+    pub async fn latest_commit(&self, url: &str, reference: &GitRef) -> Result<Option<String>> {
+        let refname = match reference {
+            GitRef::Default => None,
+            GitRef::Branch(branch) => Some(format!("refs/heads/{}", branch)),
+            _ => return Ok(None),
+        };
+        http::retry(&format!("Look up the latest commit of {}", url), || async {
+            let (url_owned, refname) = (url.to_string(), refname.clone());
+            Ok(tokio::task::spawn_blocking(move || remote_commit(&url_owned, refname.as_deref())).await??)
+        }).await
+    }
+
+    pub async fn list_refs(&self, url: &str) -> Result<RemoteRefs> {
+        http::retry(&format!("List refs of {}", url), || async {
+            let url_owned = url.to_string();
+            Ok(tokio::task::spawn_blocking(move || remote_refs(&url_owned)).await??)
+        }).await
+    }
+
+    /// Resolves a `version` requirement against the tags of `url`.
+    pub async fn resolve_version(&self, url: &str, spec: &str, locked: Option<&str>) -> Result<ResolvedVersion> {
+        let req = parse_version_req(spec)?;
+        let tags = self.list_refs(url).await?.tags;
+        resolve_tag(&tags, &req, locked).ok_or_else(|| {
+            let shown = if tags.is_empty() { "none".to_string() } else { tags.join(", ") };
+            anyhow::anyhow!("No tag of {} satisfies '{}' (tags: {})", url, spec, shown)
+        })
+    }
+
+    /// What `lunu add repo@<name>` refers to: an existing tag (a release's tag), then a branch,
+    /// then a semver requirement such as `^2.1`.
+    pub async fn classify_ref(&self, url: &str, name: &str) -> Result<GitRef> {
+        let refs = self.list_refs(url).await?;
+        classify(&refs, name).ok_or_else(|| {
+            anyhow::anyhow!("'{}' is not a tag, branch, commit or version requirement of {}", name, url)
+        })
     }
 
     pub async fn remove_package(&self, name: &str) -> Result<()> {
@@ -64,14 +637,45 @@ impl PackageManager {
         Ok(())
     }
 
+    /// SHA-256 of every file under `path` (`.git` excluded), keyed by `/`-separated relative path.
+    pub async fn file_hashes(&self, path: &Path) -> Result<BTreeMap<String, String>> {
+        let mut files = Vec::new();
+        collect_files(path, path, &mut files)?;
+        let mut hashes = BTreeMap::new();
+        for relative in files {
+            let bytes = fs::read(path.join(&relative)).await?;
+            hashes.insert(relative, hex::encode(Sha256::digest(&bytes)));
+        }
+        Ok(hashes)
+    }
+
+    /// Root of `file_hashes(path)` (see `root_checksum`), as recorded in lunu.lock.
     pub async fn calculate_dir_checksum(&self, path: &Path) -> Result<String> {
+        Ok(root_checksum(&self.file_hashes(path).await?))
+    }
+
+    /// Whether `path` still matches a lunu.lock checksum. Entries locked before checksums covered
+    /// subdirectories are compared the old way, over the top-level files only. Those were hashed
+    /// before the compatibility layer added its files, so files it generated may be left out.
+    pub async fn checksum_matches(&self, path: &Path, expected: &str) -> Result<bool> {
+        if is_legacy_checksum(expected) {
+            if self.legacy_dir_checksum(path, &[]).await? == expected {
+                return Ok(true);
+            }
+            let generated = CompatibilityLayer::generated_files(path).await?;
+            return Ok(!generated.is_empty() && self.legacy_dir_checksum(path, &generated).await? == expected);
+        }
+        Ok(self.calculate_dir_checksum(path).await? == expected)
+    }
+
+    async fn legacy_dir_checksum(&self, path: &Path, skip: &[PathBuf]) -> Result<String> {
         let mut hasher = Sha256::new();
         let mut entries = Vec::new();
 
         let mut read_dir = fs::read_dir(path).await?;
         while let Ok(Some(entry)) = read_dir.next_entry().await {
             let path = entry.path();
-            if path.is_file() {
+            if path.is_file() && !skip.contains(&path) {
                 entries.push(path);
             }
         }
@@ -86,11 +690,177 @@ impl PackageManager {
     }
 }
 
+/// Single checksum over a module's per-file hashes, so renaming, adding or removing a file
+/// changes it as well as editing one. Prefixed with `TREE_CHECKSUM_PREFIX`.
+pub fn root_checksum(files: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (path, hash) in files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+        hasher.update([b'\n']);
+    }
+    format!("{}{}", TREE_CHECKSUM_PREFIX, hex::encode(hasher.finalize()))
+}
+
+/// How one file differs from its lunu.lock hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl FileChange {
+    pub fn label(self) -> &'static str {
+        match self {
+            FileChange::Added => "added",
+            FileChange::Removed => "removed",
+            FileChange::Modified => "modified",
+        }
+    }
+}
+
+/// Files of `current` that differ from the `locked` hashes, in path order.
+pub fn changed_files(locked: &BTreeMap<String, String>, current: &BTreeMap<String, String>) -> Vec<(FileChange, String)> {
+    let mut changes: Vec<(FileChange, String)> = current
+        .iter()
+        .filter_map(|(path, hash)| match locked.get(path) {
+            None => Some((FileChange::Added, path.clone())),
+            Some(locked) if locked != hash => Some((FileChange::Modified, path.clone())),
+            Some(_) => None,
+        })
+        .chain(locked.keys().filter(|path| !current.contains_key(*path)).map(|path| (FileChange::Removed, path.clone())))
+        .collect();
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes
+}
+
+/// Marks checksums covering the whole module tree; older lock entries are bare hex digests.
+pub const TREE_CHECKSUM_PREFIX: &str = "tree-sha256:";
+
+/// Checksum recorded before checksums covered subdirectories.
+pub fn is_legacy_checksum(checksum: &str) -> bool {
+    !checksum.is_empty() && !checksum.starts_with(TREE_CHECKSUM_PREFIX)
+}
+
+/// Paths relative to `root` (with `/` separators) of every file under `dir`.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {:?}", dir))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|n| n == ".git") {
+                continue;
+            }
+            collect_files(root, &path, files)?;
+        } else if path.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn offers_ssh_keys_and_host_tokens() {
+        use git2::CredentialType;
+        let ssh = tempdir().unwrap();
+        std::fs::write(ssh.path().join("id_rsa"), "key").unwrap();
+        let env = |var: &str| (var == "GITLAB_TOKEN").then(|| "glpat-1".to_string());
+
+        let offered = credentials("git@gitlab.com:org/repo.git", None, CredentialType::USERNAME, Some(ssh.path()), &[], env);
+        assert_eq!(offered, vec![Credential::Username("git".to_string())]);
+        let offered = credentials("git@gitlab.com:org/repo.git", Some("git"), CredentialType::SSH_KEY, Some(ssh.path()), &[], env);
+        assert_eq!(offered, vec![
+            Credential::SshAgent("git".to_string()),
+            Credential::SshKey { user: "git".to_string(), key: ssh.path().join("id_rsa") },
+        ]);
+        let plaintext = |url: &str, hosts: &[String]| credentials(url, None, CredentialType::USER_PASS_PLAINTEXT, None, hosts, env);
+        let token = Credential::Token { user: "oauth2", token: "glpat-1".to_string() };
+        assert_eq!(plaintext("https://gitlab.com/org/repo.git", &[]), vec![Credential::Helper, token.clone()]);
+        let configured = ["gitlab.example.com".to_string()];
+        assert_eq!(plaintext("https://gitlab.example.com/org/repo.git", &configured), vec![Credential::Helper, token]);
+        // Unlisted hosts that look like GitLab, and plain HTTP, get no token
+        assert_eq!(plaintext("https://gitlab.example.com/org/repo.git", &[]), vec![Credential::Helper]);
+        assert_eq!(plaintext("https://gitlab.com.evil.example/org/repo.git", &configured), vec![Credential::Helper]);
+        assert_eq!(plaintext("http://gitlab.com/org/repo.git", &configured), vec![Credential::Helper]);
+        assert_eq!(plaintext("https://bitbucket.org/org/repo.git", &[]), vec![Credential::Helper]);
+    }
+
+    #[tokio::test]
+    async fn keeps_only_the_requested_subdir() {
+        assert_eq!(normalize_subdir("./packages\\signal/").unwrap(), "packages/signal");
+        assert!(normalize_subdir("../outside").is_err());
+        assert!(normalize_subdir("/etc").is_err());
+        assert!(normalize_subdir(" ").is_err());
+
+        let dir = tempdir().unwrap();
+        let install = dir.path().join("modules").join("signal");
+        fs::create_dir_all(install.join("packages/signal/src")).await.unwrap();
+        fs::write(install.join("packages/signal/init.luau"), "return {}").await.unwrap();
+        fs::write(install.join("README.md"), "monorepo").await.unwrap();
+        keep_subdir(&install, "packages/signal").await.unwrap();
+        assert!(install.join("init.luau").is_file() && install.join("src").is_dir());
+        assert!(!install.join("README.md").exists());
+        assert_eq!(std::fs::read_dir(dir.path().join("modules")).unwrap().count(), 1);
+
+        let err = keep_subdir(&install, "missing").await.unwrap_err();
+        assert!(err.to_string().contains("no folder 'missing'"));
+    }
+
+    #[tokio::test]
+    async fn codeload_archives_cover_github_refs_only() {
+        assert_eq!(
+            codeload_url("git@github.com:Acme/json.git", Some("refs/tags/v1.2.0")).as_deref(),
+            Some("https://codeload.github.com/Acme/json/zip/refs/tags/v1.2.0")
+        );
+        assert_eq!(codeload_url("https://github.com/acme/json", None).as_deref(), Some("https://codeload.github.com/acme/json/zip/HEAD"));
+        assert_eq!(codeload_url("https://gitlab.com/acme/json", None), None);
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.set_comment("0123456789abcdef0123456789abcdef01234567");
+        zip.start_file("json-main/init.luau", zip::write::FileOptions::default()).unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        assert_eq!(archive_commit(&bytes).as_deref(), Some("0123456789abcdef0123456789abcdef01234567"));
+
+        let dir = tempdir().unwrap();
+        let pm = PackageManager { root_dir: dir.path().to_path_buf(), cache_dir: None, offline: false };
+        let err = pm.install_package("https://gitlab.com/acme/json", &GitRef::Default, "json", FetchMethod::Archive).await.err().unwrap();
+        assert!(err.to_string().contains("not a github.com repository"));
+    }
+
+    #[tokio::test]
+    async fn installs_pinned_commits_and_rejects_missing_ones() {
+        let upstream = tempdir().unwrap();
+        let repo = Repository::init(upstream.path()).unwrap();
+        let signature = git2::Signature::now("lunu", "lunu@example.com").unwrap();
+        let mut commits = Vec::new();
+        for content in ["return 1", "return 2"] {
+            std::fs::write(upstream.path().join("init.luau"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("init.luau")).unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<_> = repo.head().ok().and_then(|h| h.peel_to_commit().ok()).into_iter().collect();
+            let parents: Vec<_> = parents.iter().collect();
+            commits.push(repo.commit(Some("HEAD"), &signature, &signature, content, &tree, &parents).unwrap().to_string());
+        }
+        let url = format!("file://{}", upstream.path().to_string_lossy().replace('\\', "/"));
+
+        let dir = tempdir().unwrap();
+        let pm = PackageManager { root_dir: dir.path().to_path_buf(), cache_dir: None, offline: false };
+        let installed = pm.install_package(&url, &GitRef::Rev(commits[0][..7].to_string()), "json", FetchMethod::Git).await.unwrap();
+        assert_eq!(installed.commit.as_deref(), Some(commits[0].as_str()));
+        assert_eq!(std::fs::read_to_string(installed.path.join("init.luau")).unwrap(), "return 1");
+
+        let err = pm.install_package(&url, &GitRef::Rev("0000000".to_string()), "json", FetchMethod::Git).await.err().unwrap();
+        assert!(format!("{:#}", err).contains("Commit 0000000 not found"));
+    }
+
     #[tokio::test]
     async fn calculate_dir_checksum_changes_on_content() {
         let dir = tempdir().unwrap();
@@ -106,7 +876,69 @@ mod tests {
         fs::write(root.join("b.txt"), "three").await.unwrap();
         let third = pm.calculate_dir_checksum(root).await.unwrap();
         assert_ne!(first, third);
+
+        fs::create_dir_all(root.join("src")).await.unwrap();
+        fs::write(root.join("src").join("c.luau"), "return 1").await.unwrap();
+        let nested = pm.calculate_dir_checksum(root).await.unwrap();
+        assert_ne!(third, nested);
+        fs::write(root.join("src").join("c.luau"), "return 2").await.unwrap();
+        assert!(!pm.checksum_matches(root, &nested).await.unwrap());
+
+        let hashes = pm.file_hashes(root).await.unwrap();
+        assert_eq!(root_checksum(&hashes), pm.calculate_dir_checksum(root).await.unwrap());
+        let mut locked = hashes.clone();
+        locked.insert("gone.txt".to_string(), "00".to_string());
+        locked.insert("src/c.luau".to_string(), "00".to_string());
+        locked.remove("a.txt");
+        let changes = changed_files(&locked, &hashes);
+        let changes: Vec<(FileChange, &str)> = changes.iter().map(|(c, p)| (*c, p.as_str())).collect();
+        assert_eq!(changes, vec![(FileChange::Added, "a.txt"), (FileChange::Removed, "gone.txt"), (FileChange::Modified, "src/c.luau")]);
+
+        // Lock entries from before tree checksums still verify against the top-level files
+        let legacy = pm.legacy_dir_checksum(root, &[]).await.unwrap();
+        assert!(is_legacy_checksum(&legacy) && pm.checksum_matches(root, &legacy).await.unwrap());
+
+        // ... including ones hashed before the compatibility layer generated init.luau and lunu.toml
+        let legacy_module = root.join("legacy");
+        fs::create_dir_all(&legacy_module).await.unwrap();
+        fs::write(legacy_module.join("hello.luau"), "return {}").await.unwrap();
+        let legacy = pm.legacy_dir_checksum(&legacy_module, &[]).await.unwrap();
+        CompatibilityLayer::ensure_compat(&legacy_module).await.unwrap();
+        assert!(pm.checksum_matches(&legacy_module, &legacy).await.unwrap());
+        fs::write(legacy_module.join("init.luau"), "return { evil = true }").await.unwrap();
+        assert!(!pm.checksum_matches(&legacy_module, &legacy).await.unwrap());
     }
 
-    
+    #[test]
+    fn resolves_version_requirements_against_tags() {
+        let tags: Vec<String> = ["v0.3.0", "v0.3.4", "v0.4.0", "1.2.0", "v1.2.7", "v1.3.0-beta.1", "v1.9.1", "nightly"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let tag = |spec: &str, locked: Option<&str>| {
+            resolve_tag(&tags, &parse_version_req(spec).unwrap(), locked).map(|r| r.tag)
+        };
+        assert_eq!(tag("^1.2", None).as_deref(), Some("v1.9.1"));
+        assert_eq!(tag("~0.3.1", None).as_deref(), Some("v0.3.4"));
+        assert_eq!(tag("1.2.0", None).as_deref(), Some("1.2.0"));
+        assert_eq!(tag("v1.2.7", None).as_deref(), Some("v1.2.7"));
+        assert_eq!(tag("^1.2", Some("1.2.7")).as_deref(), Some("v1.2.7"));
+        assert_eq!(tag("~0.3.1", Some("1.2.7")).as_deref(), Some("v0.3.4"));
+        assert_eq!(tag("^2", None), None);
+        assert!(parse_version_req("not a version").is_err());
+    }
+
+    #[test]
+    fn classifies_requested_refs() {
+        let refs = RemoteRefs {
+            tags: vec!["v2.1.0".to_string(), "nightly".to_string()],
+            branches: vec!["main".to_string(), "1.x".to_string()],
+        };
+        assert_eq!(classify(&refs, "v2.1.0"), Some(GitRef::Tag("v2.1.0".to_string())));
+        assert_eq!(classify(&refs, "nightly"), Some(GitRef::Tag("nightly".to_string())));
+        assert_eq!(classify(&refs, "1.x"), Some(GitRef::Branch("1.x".to_string())));
+        assert_eq!(classify(&refs, "^2"), Some(GitRef::Version { req: "^2".to_string(), locked: None }));
+        assert_eq!(classify(&refs, "9F1E980"), Some(GitRef::Rev("9f1e980".to_string())));
+        assert_eq!(classify(&refs, "feature/none"), None);
+    }
 }
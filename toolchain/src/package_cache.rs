@@ -0,0 +1,294 @@
+//! Global store of installed packages under `~/.lunu/cache/packages`, shared by every project.
+//! Each checkout (without `.git`) is stored once under the SHA-256 of its contents, and small ref
+//! files map a repository's tags and branches to that content:
+//!
+//! ```text
+//! packages/store/<content hash>/...
+//! packages/<repo>-<hash of url>/url
+//! packages/<repo>-<hash of url>/tags/v1.2.0         "<content hash> <commit>"
+//! packages/<repo>-<hash of url>/branches/dev
+//! packages/<repo>-<hash of url>/default
+//! packages/<repo>-<hash of url>/commits/<sha>      "<content hash> <commit>"
+//! ```
+//!
+//! Projects get hard links to the stored files (copies on Windows, or across file systems), so a
+//! package used by ten projects takes its space once and installing a cached revision needs no clone.
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use crate::package::{self, GitRef, ResolvedVersion};
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lunu").join("cache").join("packages"))
+}
+
+/// Directory holding every cached revision of `url`.
+fn repo_dir(cache: &Path, url: &str) -> PathBuf {
+    let normalized = url.trim().trim_end_matches('/').trim_end_matches(".git").to_lowercase();
+    let name: String = normalized
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or("package")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect();
+    let digest = Sha256::digest(normalized.as_bytes());
+    cache.join(format!("{}-{}", name, hex::encode(&digest[..6])))
+}
+
+/// Branch and tag names may contain `/`; keep each one a single directory.
+fn ref_dir(name: &str) -> String {
+    name.replace(['/', '\\'], "%2F")
+}
+
+/// Where a revision lives in the cache: `Default`, a tag or a branch. Version requirements are
+/// resolved to a tag first.
+fn entry_dir(cache: &Path, url: &str, tag: Option<&str>, branch: Option<&str>) -> PathBuf {
+    let repo = repo_dir(cache, url);
+    match (tag, branch) {
+        (Some(tag), _) => repo.join("tags").join(ref_dir(tag)),
+        (None, Some(branch)) => repo.join("branches").join(ref_dir(branch)),
+        (None, None) => repo.join("default"),
+    }
+}
+
+/// Every stored revision is also recorded under its commit, which `rev` dependencies look up.
+fn commit_entry(cache: &Path, url: &str, commit: &str) -> PathBuf {
+    repo_dir(cache, url).join("commits").join(commit.to_lowercase())
+}
+
+/// The stored commit `rev` (full or abbreviated) stands for, when exactly one matches.
+fn cached_commit_entry(cache: &Path, url: &str, rev: &str) -> Option<PathBuf> {
+    let rev = rev.to_lowercase();
+    let mut matches = std::fs::read_dir(repo_dir(cache, url).join("commits"))
+        .ok()?
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&rev))
+        .map(|e| e.path());
+    let found = matches.next()?;
+    matches.next().is_none().then_some(found)
+}
+
+fn content_dir(cache: &Path, hash: &str) -> PathBuf {
+    cache.join("store").join(hash)
+}
+
+/// Tags of `url` present in the cache.
+fn cached_tags(cache: &Path, url: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(repo_dir(cache, url).join("tags")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.file_name().to_string_lossy().replace("%2F", "/"))
+        .collect()
+}
+
+/// A cached revision matching `reference`: its directory in the store plus the tag, version and
+/// commit it stands for.
+pub struct CachedPackage {
+    pub dir: PathBuf,
+    pub tag: Option<String>,
+    pub resolved: Option<ResolvedVersion>,
+    pub commit: Option<String>,
+}
+
+pub fn lookup(cache: &Path, url: &str, reference: &GitRef) -> Option<CachedPackage> {
+    let (entry, tag, resolved) = match reference {
+        GitRef::Default => (entry_dir(cache, url, None, None), None, None),
+        GitRef::Tag(tag) => (entry_dir(cache, url, Some(tag), None), Some(tag.clone()), None),
+        GitRef::Branch(branch) => (entry_dir(cache, url, None, Some(branch)), None, None),
+        GitRef::Rev(rev) => (cached_commit_entry(cache, url, rev)?, None, None),
+        GitRef::Version { req, locked } => {
+            let req = package::parse_version_req(req).ok()?;
+            let resolved = package::resolve_tag(&cached_tags(cache, url), &req, locked.as_deref())?;
+            (entry_dir(cache, url, Some(&resolved.tag), None), Some(resolved.tag.clone()), Some(resolved))
+        }
+    };
+    // Caches written before the store kept a full copy per ref
+    if entry.is_dir() {
+        return Some(CachedPackage { dir: entry, tag, resolved, commit: None });
+    }
+    let content = std::fs::read_to_string(&entry).ok()?;
+    let mut fields = content.split_whitespace();
+    let dir = content_dir(cache, fields.next()?);
+    let commit = fields.next().map(str::to_string);
+    dir.is_dir().then_some(CachedPackage { dir, tag, resolved, commit })
+}
+
+/// Adds a fresh checkout of `url` to the store, unless identical content is already there, and
+/// points `reference` and `commit` at it. A version requirement must be passed as the tag it
+/// resolved to; a `Rev` is only recorded under its commit. Returns the stored directory.
+pub fn store(cache: &Path, url: &str, reference: &GitRef, commit: Option<&str>, checkout: &Path) -> Result<PathBuf> {
+    let hash = tree_hash(checkout)?;
+    let content = content_dir(cache, &hash);
+    if !content.is_dir() {
+        let parent = content.parent().context("Invalid package cache path")?;
+        std::fs::create_dir_all(parent)?;
+        // Copy next to the target and rename, so an interrupted store never leaves a partial entry
+        let staging = parent.join(format!(".staging-{}", std::process::id()));
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        copy_tree(checkout, &staging)?;
+        if let Err(err) = std::fs::rename(&staging, &content) {
+            // Another install stored the same content first
+            std::fs::remove_dir_all(&staging).ok();
+            if !content.is_dir() {
+                return Err(err).with_context(|| format!("Failed to store {:?} in the package cache", content));
+            }
+        }
+    }
+
+    let entries = match reference {
+        GitRef::Default => Some(entry_dir(cache, url, None, None)),
+        GitRef::Tag(tag) => Some(entry_dir(cache, url, Some(tag), None)),
+        GitRef::Branch(branch) => Some(entry_dir(cache, url, None, Some(branch))),
+        GitRef::Version { .. } | GitRef::Rev(_) => None,
+    };
+    std::fs::create_dir_all(repo_dir(cache, url))?;
+    std::fs::write(repo_dir(cache, url).join("url"), url)?;
+    let line = match commit {
+        Some(commit) => format!("{} {}\n", hash, commit),
+        None => format!("{}\n", hash),
+    };
+    for entry in entries.into_iter().chain(commit.map(|commit| commit_entry(cache, url, commit))) {
+        let parent = entry.parent().context("Invalid package cache path")?;
+        std::fs::create_dir_all(parent)?;
+        if entry.is_dir() {
+            std::fs::remove_dir_all(&entry)?;
+        }
+        let pending = parent.join(format!(".ref-{}", std::process::id()));
+        std::fs::write(&pending, &line)?;
+        std::fs::rename(&pending, &entry)?;
+    }
+    Ok(content)
+}
+
+/// SHA-256 over every file's relative path and contents, in path order, leaving out `.git`.
+pub fn tree_hash(dir: &Path) -> Result<String> {
+    fn collect(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+        for entry in std::fs::read_dir(dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == ".git" {
+                continue;
+            }
+            let relative = format!("{}{}", prefix, name);
+            if entry.path().is_dir() {
+                collect(&entry.path(), &format!("{}/", relative), files)?;
+            } else {
+                files.push((relative, entry.path()));
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    collect(dir, "", &mut files)?;
+    files.sort();
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Recreates `src` at `dst` with hard links to its files. Files are copied on Windows, and where a
+/// link fails (the project is on another file system).
+pub fn link_tree(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)?.flatten() {
+        let path = entry.path();
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            link_tree(&path, &target)?;
+        } else if cfg!(windows) || std::fs::hard_link(&path, &target).is_err() {
+            std::fs::copy(&path, &target).with_context(|| format!("Failed to copy {:?}", path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies `src` to `dst`, leaving out `.git`.
+fn copy_tree(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)?.flatten() {
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = dst.join(entry.file_name());
+        if path.is_dir() {
+            copy_tree(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target).with_context(|| format!("Failed to copy {:?}", path))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn stores_and_finds_revisions_by_ref() {
+        let cache = tempdir().unwrap();
+        let checkout = tempdir().unwrap();
+        std::fs::write(checkout.path().join("init.luau"), "return 1").unwrap();
+        std::fs::create_dir_all(checkout.path().join(".git")).unwrap();
+        let url = "https://github.com/user/json";
+
+        for tag in ["v1.2.0", "v1.4.1", "v2.0.0"] {
+            store(cache.path(), url, &GitRef::Tag(tag.to_string()), None, checkout.path()).unwrap();
+        }
+        store(cache.path(), url, &GitRef::Branch("feature/x".to_string()), Some("abc123"), checkout.path()).unwrap();
+
+        let found = lookup(cache.path(), "https://github.com/User/json.git", &GitRef::Version { req: "^1.2".to_string(), locked: None }).unwrap();
+        assert_eq!(found.tag.as_deref(), Some("v1.4.1"));
+        assert!(found.dir.join("init.luau").exists());
+        assert!(!found.dir.join(".git").exists());
+        let locked = GitRef::Version { req: "^1.2".to_string(), locked: Some("1.2.0".to_string()) };
+        assert_eq!(lookup(cache.path(), url, &locked).unwrap().tag.as_deref(), Some("v1.2.0"));
+        let branch = lookup(cache.path(), url, &GitRef::Branch("feature/x".to_string())).unwrap();
+        assert_eq!(branch.commit.as_deref(), Some("abc123"));
+        assert_eq!(branch.dir, found.dir);
+        assert_eq!(lookup(cache.path(), url, &GitRef::Rev("ABC1".to_string())).unwrap().dir, found.dir);
+        assert!(lookup(cache.path(), url, &GitRef::Rev("abd".to_string())).is_none());
+        assert!(lookup(cache.path(), url, &GitRef::Default).is_none());
+        assert!(lookup(cache.path(), "https://github.com/user/yaml", &GitRef::Tag("v1.2.0".to_string())).is_none());
+    }
+
+    #[test]
+    fn identical_content_is_stored_once_and_linked() {
+        let cache = tempdir().unwrap();
+        let checkout = tempdir().unwrap();
+        std::fs::create_dir_all(checkout.path().join("src")).unwrap();
+        std::fs::write(checkout.path().join("src").join("lib.luau"), "return 2").unwrap();
+        let a = store(cache.path(), "https://github.com/user/a", &GitRef::Tag("v1.0.0".to_string()), None, checkout.path()).unwrap();
+        let b = store(cache.path(), "https://github.com/user/b", &GitRef::Default, None, checkout.path()).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(std::fs::read_dir(cache.path().join("store")).unwrap().count(), 1);
+        assert_eq!(a.file_name().unwrap().to_string_lossy(), tree_hash(checkout.path()).unwrap());
+
+        let project = tempdir().unwrap();
+        let module = project.path().join("modules").join("a");
+        link_tree(&a, &module).unwrap();
+        assert_eq!(std::fs::read_to_string(module.join("src").join("lib.luau")).unwrap(), "return 2");
+        // Files added to a project's copy (compat wrappers) never reach the store
+        std::fs::write(module.join("init.luau"), "return {}").unwrap();
+        assert!(!a.join("init.luau").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let stored = std::fs::metadata(a.join("src").join("lib.luau")).unwrap();
+            assert_eq!(std::fs::metadata(module.join("src").join("lib.luau")).unwrap().ino(), stored.ino());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Permissions a bridge module asks for in its bridge.json and the project policy that bounds them.
+//!
+//! ```json
+//! "permissions": { "network": true, "filesystem": ["data/", "~/.cache/ml"], "subprocess": false }
+//! ```
+//!
+//! A `[permissions]` section in lunu.toml lists what modules may request, project-wide and per
+//! module (`[permissions.modules.<name>]`). Without the section every module is allowed. With it,
+//! the bridge refuses to start a module that requests more, and so does a module that declares
+//! nothing unless `undeclared = true`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Permissions {
+    /// Outgoing network connections
+    #[serde(default)]
+    pub network: bool,
+    /// Paths read or written outside the module directory, relative to the project root or
+    /// starting with `~/` or `/`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filesystem: Vec<String>,
+    /// Starting other processes
+    #[serde(default)]
+    pub subprocess: bool,
+}
+
+impl Permissions {
+    /// `network, subprocess, filesystem (data/, ~/.cache)`, or `none`.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.network {
+            parts.push("network".to_string());
+        }
+        if self.subprocess {
+            parts.push("subprocess".to_string());
+        }
+        if !self.filesystem.is_empty() {
+            parts.push(format!("filesystem ({})", self.filesystem.join(", ")));
+        }
+        if parts.is_empty() { "none".to_string() } else { parts.join(", ") }
+    }
+}
+
+/// The `permissions` block of a bridge.json; `None` when the module declares none. A `remote`
+/// module forwards its calls over the network, so it always requests `network`.
+pub fn declared(bridge_json: &str) -> Result<Option<Permissions>> {
+    #[derive(Deserialize)]
+    struct Manifest {
+        permissions: Option<Permissions>,
+        remote: Option<serde_json::Value>,
+    }
+    let manifest: Manifest = serde_json::from_str(bridge_json).context("Invalid bridge config")?;
+    let remote = manifest.remote.is_some_and(|r| !r.is_null());
+    Ok(manifest.permissions.map(|p| Permissions { network: p.network || remote, ..p }))
+}
+
+/// `[permissions]` of lunu.toml.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub network: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filesystem: Vec<String>,
+    #[serde(default)]
+    pub subprocess: bool,
+    /// Allow modules whose bridge.json has no `permissions` block
+    #[serde(default)]
+    pub undeclared: bool,
+    /// Allowed for one module on top of the project-wide permissions
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, Permissions>,
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn path_allowed(requested: &str, allowed: &str) -> bool {
+    let (requested, allowed) = (normalize_path(requested), normalize_path(allowed));
+    (allowed == "/" && requested.starts_with('/')) || requested == allowed || requested.starts_with(&format!("{}/", allowed))
+}
+
+impl Policy {
+    /// The policy in `root`'s lunu.toml; `None` when it has no `[permissions]` section.
+    pub fn load(root: &Path) -> Result<Option<Policy>> {
+        #[derive(Deserialize)]
+        struct Project {
+            permissions: Option<Policy>,
+        }
+        let path = root.join("lunu.toml");
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+        let project: Project = toml::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+        Ok(project.permissions)
+    }
+
+    /// What `module` requests beyond this policy, one line each; empty when it is allowed.
+    pub fn violations(&self, module: &str, requested: Option<&Permissions>) -> Vec<String> {
+        let Some(requested) = requested else {
+            return if self.undeclared {
+                Vec::new()
+            } else {
+                vec!["declares no permissions in bridge.json".to_string()]
+            };
+        };
+        let extra = self.modules.get(module);
+        let mut violations = Vec::new();
+        if requested.network && !self.network && !extra.is_some_and(|e| e.network) {
+            violations.push("requests network access".to_string());
+        }
+        if requested.subprocess && !self.subprocess && !extra.is_some_and(|e| e.subprocess) {
+            violations.push("requests spawning subprocesses".to_string());
+        }
+        let allowed_paths: Vec<&String> = self.filesystem.iter().chain(extra.into_iter().flat_map(|e| &e.filesystem)).collect();
+        for path in &requested.filesystem {
+            if !allowed_paths.iter().any(|allowed| path_allowed(path, allowed)) {
+                violations.push(format!("requests filesystem access to {}", path));
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_denies_what_it_does_not_allow() {
+        let requested = declared(r#"{"worker":{"cmd":["python","w.py"]},"permissions":{"network":true,"filesystem":["./data/cache/","~/.ml"]}}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(requested.summary(), "network, filesystem (./data/cache/, ~/.ml)");
+        assert_eq!(declared(r#"{"worker":{"cmd":["node"]}}"#).unwrap(), None);
+
+        let policy: Policy = toml::from_str("filesystem = [\"data\"]\n[modules.ml]\nnetwork = true\n").unwrap();
+        assert_eq!(policy.violations("ml", Some(&requested)), vec!["requests filesystem access to ~/.ml"]);
+        assert_eq!(
+            policy.violations("other", Some(&requested)),
+            vec!["requests network access", "requests filesystem access to ~/.ml"]
+        );
+        assert_eq!(policy.violations("ml", None), vec!["declares no permissions in bridge.json"]);
+        assert!(Policy { undeclared: true, ..policy.clone() }.violations("ml", None).is_empty());
+        assert!(policy.violations("ml", Some(&Permissions { filesystem: vec!["data2".to_string()], ..Default::default() })).len() == 1);
+
+        let remote = declared(r#"{"remote":{"url":"https://bridge.example.com"},"permissions":{}}"#).unwrap();
+        assert_eq!(policy.violations("other", remote.as_ref()), vec!["requests network access"]);
+        assert_eq!(declared(r#"{"remote":{"url":"https://bridge.example.com"}}"#).unwrap(), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! Client for the pesde registry, an alternate package source to GitHub: `lunu add
+//! pesde:scope/name@^1.2` installs a published package archive (unpacked by `archive`) instead of
+//! cloning a repository.
+//! The public registry is used unless `LUNU_PESDE_REGISTRY` names another one.
+//!
+//! lunu.toml records such a dependency as `json = { pesde = "scope/json", version = "^1.2" }` and
+//! lunu.lock as `registry = "pesde:scope/json"`.
+
+use std::path::Path;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use semver::{Version, VersionReq};
+use serde_json::Value;
+use crate::http;
+
+pub const DEFAULT_REGISTRY: &str = "https://registry.pesde.daimond113.com";
+pub const PREFIX: &str = "pesde:";
+
+/// `scope/name` of a `pesde:scope/name` query, or `None` for anything else.
+pub fn parse_package(query: &str) -> Option<String> {
+    let package = query.strip_prefix(PREFIX)?.trim().to_lowercase();
+    let (scope, name) = package.split_once('/')?;
+    let valid = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    (valid(scope) && valid(name)).then_some(package)
+}
+
+/// What lunu.lock records as the origin of a pesde package.
+pub fn origin(package: &str) -> String {
+    format!("{}{}", PREFIX, package)
+}
+
+/// Targets a runtime can load, most specific first.
+pub fn targets_for(lune: bool) -> &'static [&'static str] {
+    if lune {
+        &["lune", "luau"]
+    } else {
+        &["luau"]
+    }
+}
+
+/// One published version of a package for one target (`lune`, `luau`, `roblox`, ...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PesdeRelease {
+    pub version: Version,
+    pub target: String,
+    pub description: Option<String>,
+}
+
+/// Reads the registry's version listing. Both the list form (`[{ "version", "target" }]`) and the
+/// map form (`{ "versions": { "<version>": { "targets": ... } } }`) are accepted.
+pub fn parse_releases(body: &Value) -> Vec<PesdeRelease> {
+    fn target_kinds(value: Option<&Value>) -> Vec<String> {
+        match value {
+            Some(Value::String(kind)) => vec![kind.clone()],
+            Some(Value::Object(map)) if map.contains_key("kind") => map.get("kind").and_then(Value::as_str).map(str::to_string).into_iter().collect(),
+            Some(Value::Object(map)) => map.keys().cloned().collect(),
+            Some(Value::Array(items)) => items.iter().flat_map(|item| target_kinds(Some(item))).collect(),
+            _ => Vec::new(),
+        }
+    }
+    let mut releases = Vec::new();
+    let mut push = |version: &str, entry: &Value| {
+        let Ok(version) = Version::parse(version) else {
+            return;
+        };
+        let description = entry.get("description").and_then(Value::as_str).map(str::to_string);
+        for target in target_kinds(entry.get("targets").or_else(|| entry.get("target"))) {
+            releases.push(PesdeRelease { version: version.clone(), target, description: description.clone() });
+        }
+    };
+    match body {
+        Value::Array(items) => {
+            for item in items {
+                if let Some(version) = item.get("version").and_then(Value::as_str) {
+                    push(version, item);
+                }
+            }
+        }
+        Value::Object(map) => {
+            if let Some(Value::Object(versions)) = map.get("versions") {
+                for (version, entry) in versions {
+                    push(version, entry);
+                }
+            }
+        }
+        _ => {}
+    }
+    releases
+}
+
+/// Highest release matching `req` for the first of `targets` it was published for. A `locked`
+/// version is kept while it still matches, as with git tags.
+pub fn pick_release(releases: &[PesdeRelease], req: &VersionReq, locked: Option<&str>, targets: &[&str]) -> Option<PesdeRelease> {
+    let rank = |release: &PesdeRelease| targets.iter().position(|t| *t == release.target);
+    let mut candidates: Vec<&PesdeRelease> = releases.iter().filter(|r| req.matches(&r.version) && rank(r).is_some()).collect();
+    candidates.sort_by(|a, b| b.version.cmp(&a.version).then(rank(a).cmp(&rank(b))));
+    let locked = locked.and_then(|l| Version::parse(l.trim_start_matches(['v', 'V'])).ok());
+    candidates
+        .iter()
+        .find(|r| Some(&r.version) == locked.as_ref())
+        .or(candidates.first())
+        .map(|r| (*r).clone())
+}
+
+pub struct PesdeClient {
+    client: Client,
+    registry: String,
+}
+
+impl PesdeClient {
+    pub fn from_env() -> Result<Self> {
+        let registry = std::env::var("LUNU_PESDE_REGISTRY")
+            .ok()
+            .filter(|r| !r.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_REGISTRY.to_string());
+        let client = http::client()?;
+        Ok(Self { client, registry: registry.trim_end_matches('/').to_string() })
+    }
+
+    fn package_url(&self, package: &str) -> String {
+        format!("{}/v1/packages/{}", self.registry, package.replace('/', "%2F"))
+    }
+
+    pub async fn releases(&self, package: &str) -> Result<Vec<PesdeRelease>> {
+        let url = self.package_url(package);
+        let body: Value = http::retry(&format!("pesde lookup of {}", package), || async {
+            let res = self.client.get(&url).header(reqwest::header::ACCEPT, "application/json").send().await?;
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(anyhow::anyhow!("Package {} does not exist on the pesde registry", package));
+            }
+            Ok(res.error_for_status()?.json().await?)
+        })
+        .await?;
+        Ok(parse_releases(&body))
+    }
+
+    /// The `.tar.gz` archive of one release.
+    pub async fn download(&self, package: &str, release: &PesdeRelease) -> Result<Vec<u8>> {
+        let url = format!("{}/{}/{}/archive", self.package_url(package), release.version, release.target);
+        http::retry(&format!("Download {} {}", package, release.version), || async {
+            let res = self.client.get(&url).send().await?.error_for_status()?;
+            Ok(res.bytes().await?.to_vec())
+        })
+        .await
+    }
+}
+
+/// Maps the package's `pesde.toml` onto a Lunu module: when its library entry (`[target] lib`) is
+/// not the directory's `init.luau`, an `init.luau` re-exporting it is written. Returns the
+/// package's own dependencies, which are not installed automatically.
+pub fn map_manifest(dir: &Path) -> Result<Vec<String>> {
+    let path = dir.join("pesde.toml");
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(Vec::new());
+    };
+    let manifest: toml::Value = toml::from_str(&content).with_context(|| format!("Invalid {:?}", path))?;
+    let lib = manifest.get("target").and_then(|t| t.get("lib")).and_then(toml::Value::as_str);
+    let has_init = dir.join("init.luau").exists() || dir.join("init.lua").exists();
+    if let (Some(lib), false) = (lib, has_init) {
+        let module = lib.trim_start_matches("./").trim_end_matches(".luau").trim_end_matches(".lua");
+        std::fs::write(dir.join("init.luau"), format!("return require(\"./{}\")\n", module))?;
+    }
+    let dependencies = ["dependencies", "peer_dependencies"]
+        .iter()
+        .filter_map(|table| manifest.get(table).and_then(toml::Value::as_table))
+        .flat_map(|table| table.keys().cloned())
+        .collect();
+    Ok(dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn picks_the_newest_release_for_the_preferred_target() {
+        assert_eq!(parse_package("pesde:Acme/json-lib").as_deref(), Some("acme/json-lib"));
+        assert_eq!(parse_package("acme/json"), None);
+        assert_eq!(parse_package("pesde:acme"), None);
+
+        let listed = parse_releases(&json!([
+            { "version": "1.2.0", "target": { "kind": "luau" }, "description": "JSON" },
+            { "version": "1.3.0", "target": { "kind": "roblox" } },
+            { "version": "1.2.5", "targets": [{ "kind": "lune" }, { "kind": "luau" }] },
+            { "version": "not-semver", "target": "luau" },
+        ]));
+        let mapped = parse_releases(&json!({ "versions": { "2.0.0": { "targets": { "luau": {} } } } }));
+        assert_eq!(listed.len(), 4);
+        assert_eq!(mapped[0].version, Version::new(2, 0, 0));
+
+        let any = VersionReq::STAR;
+        let best = pick_release(&listed, &any, None, targets_for(true)).unwrap();
+        assert_eq!((best.version.to_string(), best.target.as_str()), ("1.2.5".to_string(), "lune"));
+        let kept = pick_release(&listed, &any, Some("1.2.0"), targets_for(false)).unwrap();
+        assert_eq!(kept.description.as_deref(), Some("JSON"));
+        assert!(pick_release(&listed, &VersionReq::parse("^1.3").unwrap(), None, targets_for(true)).is_none());
+    }
+
+    #[test]
+    fn unpacks_archive_and_maps_the_library_entry() {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (name, content) in [
+            ("pesde.toml", "name = \"acme/json\"\nversion = \"1.2.5\"\n[target]\nenvironment = \"luau\"\nlib = \"src/lib.luau\"\n[dependencies]\nutil = { name = \"acme/util\", version = \"^1\" }\n"),
+            ("src/lib.luau", "return {}"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar.into_inner().unwrap()).unwrap();
+        let archive = gz.finish().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("json");
+        crate::archive::unpack(&archive, &dest, None).unwrap();
+        assert_eq!(map_manifest(&dest).unwrap(), vec!["util".to_string()]);
+        assert_eq!(std::fs::read_to_string(dest.join("init.luau")).unwrap(), "return require(\"./src/lib\")\n");
+    }
+}
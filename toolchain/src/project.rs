@@ -1,21 +1,108 @@
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
+use lunu_builder::timings;
+use lunu_cli::config_schema::{self, ConfigFile};
+use lunu_cli::permissions::Policy;
+use crate::package::{FetchMethod, GitRef};
+
+/// Unknown-field warnings are printed by the first load only; most commands load lunu.toml several times.
+static WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+/// Configs this process parsed, with the text they came from: reloading an unchanged lunu.toml
+/// skips validation and parsing.
+static PARSED: Mutex<BTreeMap<PathBuf, (String, ProjectConfig)>> = Mutex::new(BTreeMap::new());
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProjectInfo {
     pub name: String,
     pub entry: String,
     pub modules_dir: String,
+    /// Release version for `lunu publish`, tagged as `v<version>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Library directory `lunu publish` packs (its `init.luau` is the module); the project root
+    /// when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lib: Option<String>,
+    /// Dependencies are used from `vendor/` (written by `lunu vendor`) instead of being installed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub vendored: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DependencySpec {
     pub url: Option<String>,
+    /// Semver requirement resolved against the repository's tags, or the pesde package's versions
     pub version: Option<String>,
+    /// Exact tag (e.g. a release's tag) to install instead of the default branch
+    pub tag: Option<String>,
+    /// Branch to install instead of the default branch
+    pub branch: Option<String>,
+    /// Commit (full or abbreviated SHA) to install; the install fails if the remote lacks it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
     pub path: Option<String>,
+    /// Folder of the `url` repository that holds the package (e.g. `packages/signal` of a
+    /// monorepo); only that folder is installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+    /// `scope/name` of a package on the pesde registry, installed instead of a `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pesde: Option<String>,
+    /// `scope/name@version` of a package on the Wally registry; the version follows wally.toml rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wally: Option<String>,
+    /// Path dependency under active development: locked without a checksum, so edits never
+    /// count as tampering. Ignored for remote dependencies, whose checksums are always enforced.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mutable: bool,
+    /// How a github.com `url` is fetched: its codeload archive with a clone as fallback (`auto`),
+    /// the archive only, or always a git clone
+    #[serde(default, skip_serializing_if = "FetchMethod::is_auto")]
+    pub fetch: FetchMethod,
+}
+
+impl DependencySpec {
+    pub fn is_mutable(&self) -> bool {
+        self.mutable && !self.is_remote()
+    }
+
+    /// Fetched from a git `url` or a package registry rather than read from a local `path`.
+    pub fn is_remote(&self) -> bool {
+        self.url.is_some() || self.pesde.is_some() || self.wally.is_some()
+    }
+
+    /// Origin recorded in lunu.lock for a registry package, e.g. `pesde:scope/name`.
+    pub fn registry(&self) -> Option<String> {
+        if let Some(package) = &self.pesde {
+            return Some(crate::pesde::origin(package));
+        }
+        let (package, _) = crate::wally::parse_spec(self.wally.as_deref()?).ok()?;
+        Some(crate::wally::origin(&package))
+    }
+
+    /// Revision of a `url` dependency to install; `locked` is the version lunu.lock recorded.
+    pub fn git_ref(&self, locked: Option<String>) -> Result<GitRef> {
+        match (&self.version, &self.tag, &self.branch, &self.rev) {
+            (None, None, None, None) => Ok(GitRef::Default),
+            (Some(req), None, None, None) => Ok(GitRef::Version { req: req.clone(), locked }),
+            (None, Some(tag), None, None) => Ok(GitRef::Tag(tag.clone())),
+            (None, None, Some(branch), None) => Ok(GitRef::Branch(branch.clone())),
+            (None, None, None, Some(rev)) => {
+                let rev = rev.trim();
+                if !(7..=40).contains(&rev.len()) || !rev.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(anyhow::anyhow!("rev '{}' is not a commit SHA (7 to 40 hex digits)", rev));
+                }
+                Ok(GitRef::Rev(rev.to_lowercase()))
+            }
+            _ => Err(anyhow::anyhow!("Set only one of version, tag, branch or rev for a dependency")),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,10 +110,43 @@ pub struct ProjectConfig {
     pub project: ProjectInfo,
     #[serde(default)]
     pub dependencies: BTreeMap<String, DependencySpec>,
+    /// Installed for `lunu test` like any dependency, but left out of `lunu package` and `lunu build`
+    #[serde(default, rename = "dev-dependencies", skip_serializing_if = "BTreeMap::is_empty")]
+    pub dev_dependencies: BTreeMap<String, DependencySpec>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub runtime: Option<RuntimeConfig>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub build: Option<BuildConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub runtimes: BTreeMap<String, RuntimeProfile>,
+    /// What bridge modules may request; enforced by the bridge (see `lunu_cli::permissions`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Policy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typecheck: Option<TypecheckConfig>,
+}
+
+/// `lunu typecheck` settings (`[typecheck]`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TypecheckConfig {
+    /// Luau release whose `luau-analyze` checks Lune projects, e.g. `0.650` (the newest otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub luau: Option<String>,
+    /// Check installed modules as well as `src/`, as with `--modules`
+    #[serde(default)]
+    pub modules: bool,
+}
+
+/// Named runtime variant (`[runtimes.ci]`) selected with `--runtime-profile`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RuntimeProfile {
+    /// Release tag of the project runtime to download and use, e.g. `v0.8.9`
+    pub version: Option<String>,
+    /// Runtime binary to use instead (relative to the project root); wins over `version`
+    pub path: Option<String>,
+    /// Extra environment variables for the runtime process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -49,6 +169,116 @@ pub struct BuildConfig {
     pub c_compiler: Option<String>,
     pub cpp_compiler: Option<String>,
     pub toolchain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gates: Option<BuildGates>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updates: Option<BuildUpdates>,
+    /// Where built executables unpack themselves: temp, localappdata, beside-exe or a path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract_dir: Option<String>,
+    /// When the extracted files are removed: always, never or on-success
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<BuildExclude>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<BuildSigning>,
+    /// Builds kept under dist/history for `lunu build --list`/`--diff` (default 10, 0 disables)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smoke: Option<BuildSmoke>,
+}
+
+/// Runs the packaged artifact after `lunu package` (`[build.smoke]`), like `--smoke`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BuildSmoke {
+    /// Text the artifact's output must contain
+    pub expect: Option<String>,
+    /// Script run against the unpacked artifact instead of the artifact itself (relative to the project root)
+    pub script: Option<String>,
+    /// Arguments for the artifact
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Seconds before the run counts as hung (default 60)
+    pub timeout: Option<u64>,
+}
+
+/// Key used to sign the `SHA256SUMS` that `lunu package` writes (`[build.signing]`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BuildSigning {
+    /// Private key path (create one with `lunu update-key`); keep it out of version control
+    pub key: Option<String>,
+    /// Public key `lunu package verify` checks signatures against
+    pub public_key: Option<String>,
+}
+
+/// Adjusts which files are left out of build payloads and `lunu package` (`[build.exclude]`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BuildExclude {
+    /// Keep the built-in set (.venv, __pycache__, *.pyc, tests, .git, ...)
+    #[serde(default = "default_true")]
+    pub defaults: bool,
+    /// Extra patterns to leave out
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Patterns to include even when another rule excludes them
+    #[serde(default)]
+    pub keep: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Checks `lunu build` runs before producing an executable (`[build.gates]`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BuildGates {
+    #[serde(default)]
+    pub fmt: bool,
+    #[serde(default)]
+    pub lint: bool,
+    #[serde(default)]
+    pub test: TestGate,
+}
+
+/// `test = true` runs every test file; `test = "smoke"` only those whose path contains "smoke".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TestGate {
+    Enabled(bool),
+    Filter(String),
+}
+
+impl Default for TestGate {
+    fn default() -> Self {
+        TestGate::Enabled(false)
+    }
+}
+
+/// Auto-update settings for built executables (`[build.updates]`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BuildUpdates {
+    /// URL of the update manifest the built app polls at startup
+    pub url: String,
+    #[serde(default = "default_update_channel")]
+    pub channel: String,
+    /// Version embedded in the payload and compared against the manifest
+    pub version: String,
+    /// Hex-encoded ed25519 public key payloads must be signed with
+    pub public_key: String,
+    /// Private key used by `lunu build` to sign payloads; keep it out of version control
+    pub signing_key: Option<String>,
+}
+
+fn default_update_channel() -> String {
+    "stable".to_string()
+}
+
+impl BuildGates {
+    pub fn is_empty(&self) -> bool {
+        !self.fmt && !self.lint && self.test == TestGate::Enabled(false)
+    }
 }
 
 impl ProjectConfig {
@@ -59,10 +289,18 @@ impl ProjectConfig {
                 name: name.to_string(),
                 entry: "src/main.luau".to_string(),
                 modules_dir: "modules".to_string(),
+                version: None,
+                description: None,
+                lib: None,
+                vendored: false,
             },
             dependencies: BTreeMap::new(),
+            dev_dependencies: BTreeMap::new(),
             runtime: None,
             build: None,
+            runtimes: BTreeMap::new(),
+            permissions: None,
+            typecheck: None,
         }
     }
 
@@ -72,18 +310,42 @@ impl ProjectConfig {
                 name: name.to_string(),
                 entry: "src/main.luau".to_string(),
                 modules_dir: "modules".to_string(),
+                version: None,
+                description: None,
+                lib: None,
+                vendored: false,
             },
             dependencies: BTreeMap::new(),
+            dev_dependencies: BTreeMap::new(),
             runtime: Some(runtime),
             build,
+            runtimes: BTreeMap::new(),
+            permissions: None,
+            typecheck: None,
         }
     }
 
     pub async fn load(path: &Path) -> Result<Self> {
+        let _timing = timings::span("resolution");
         let content = fs::read_to_string(path).await
             .with_context(|| format!("Failed to read project config at {:?}", path))?;
+        if let Some((parsed, cfg)) = PARSED.lock().ok().and_then(|cache| cache.get(path).cloned()) {
+            if parsed == content {
+                return Ok(cfg);
+            }
+        }
+        let issues = config_schema::validate(ConfigFile::LunuToml, &content)?;
+        if config_schema::has_errors(&issues) {
+            return Err(anyhow::anyhow!("Invalid lunu.toml:\n{}", config_schema::format_issues(&path.to_string_lossy(), &issues)));
+        }
+        if !issues.is_empty() && !WARNED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            eprintln!("{}", config_schema::format_issues(&path.to_string_lossy(), &issues));
+        }
         let cfg: ProjectConfig = toml::from_str(&content)
             .with_context(|| "Failed to parse lunu.toml")?;
+        if let Ok(mut cache) = PARSED.lock() {
+            cache.insert(path.to_path_buf(), (content, cfg.clone()));
+        }
         Ok(cfg)
     }
 
@@ -99,8 +361,37 @@ impl ProjectConfig {
         self.dependencies.insert(name.to_string(), spec);
     }
 
+    pub fn add_dev_dependency(&mut self, name: &str, spec: DependencySpec) {
+        self.dev_dependencies.insert(name.to_string(), spec);
+    }
+
     pub fn remove_dependency(&mut self, name: &str) {
         self.dependencies.remove(name);
+        self.dev_dependencies.remove(name);
+    }
+
+    /// `[dependencies]` and `[dev-dependencies]` together, for installing and aliasing.
+    pub fn all_dependencies(&self) -> BTreeMap<String, DependencySpec> {
+        let mut all = self.dev_dependencies.clone();
+        all.extend(self.dependencies.clone());
+        all
+    }
+
+    /// Module directories (relative to `modules/`) of dev-dependencies that are not also regular
+    /// dependencies; these are kept out of packaged and built payloads.
+    pub fn dev_module_paths(&self) -> Vec<String> {
+        self.dev_dependencies
+            .iter()
+            .filter(|(name, _)| !self.dependencies.contains_key(*name))
+            .filter_map(|(name, spec)| match &spec.path {
+                Some(path) => path
+                    .replace('\\', "/")
+                    .trim_start_matches("./")
+                    .strip_prefix(&format!("{}/", self.project.modules_dir))
+                    .map(str::to_string),
+                None => Some(name.clone()),
+            })
+            .collect()
     }
 }
 
@@ -123,5 +414,121 @@ mod tests {
         let loaded = ProjectConfig::load(&path).await.unwrap();
         assert_eq!(loaded.project.name, "TestProject");
         assert!(loaded.dependencies.contains_key("example"));
+
+        // A cached parse is only reused while the file is unchanged
+        cfg.remove_dependency("example");
+        cfg.save(&path).await.unwrap();
+        assert!(ProjectConfig::load(&path).await.unwrap().dependencies.is_empty());
+        // ... including edits made by hand between loads
+        let edited = std::fs::read_to_string(&path).unwrap().replace("TestProject", "Renamed");
+        std::fs::write(&path, edited).unwrap();
+        assert_eq!(ProjectConfig::load(&path).await.unwrap().project.name, "Renamed");
+    }
+
+    #[test]
+    fn parses_build_gates() {
+        let content = r#"
+[project]
+name = "Gated"
+entry = "src/main.luau"
+modules_dir = "modules"
+
+[build]
+kind = "bridge"
+link = "http-bridge"
+modules = "bridge"
+
+[build.gates]
+lint = true
+test = "smoke"
+"#;
+        let cfg: ProjectConfig = toml::from_str(content).unwrap();
+        let gates = cfg.build.unwrap().gates.unwrap();
+        assert!(gates.lint && !gates.fmt);
+        assert_eq!(gates.test, TestGate::Filter("smoke".to_string()));
+        assert!(!gates.is_empty());
+    }
+
+    #[test]
+    fn parses_build_updates_extract_and_exclude_settings() {
+        let content = r#"
+[project]
+name = "Updating"
+entry = "src/main.luau"
+modules_dir = "modules"
+
+[build]
+kind = "bridge"
+link = "http-bridge"
+modules = "bridge"
+
+extract_dir = "beside-exe"
+cleanup = "on-success"
+
+[build.exclude]
+patterns = ["*.psd"]
+
+[build.updates]
+url = "https://example.com/app/updates.json"
+version = "1.2.0"
+public_key = "abcd"
+"#;
+        let cfg: ProjectConfig = toml::from_str(content).unwrap();
+        let build = cfg.build.unwrap();
+        assert_eq!(build.extract_dir.as_deref(), Some("beside-exe"));
+        assert_eq!(build.cleanup.as_deref(), Some("on-success"));
+        let exclude = build.exclude.as_ref().unwrap();
+        assert!(exclude.defaults);
+        assert_eq!(exclude.patterns, vec!["*.psd"]);
+        let updates = build.updates.unwrap();
+        assert_eq!(updates.channel, "stable");
+        assert_eq!(updates.version, "1.2.0");
+        assert!(updates.signing_key.is_none());
+    }
+
+    #[test]
+    fn parses_runtime_profiles() {
+        let content = r#"
+[project]
+name = "Profiled"
+entry = "src/main.luau"
+modules_dir = "modules"
+
+[runtimes.ci]
+version = "v0.8.9"
+
+[runtimes.local]
+path = "tools/lune-nightly"
+env = { LUNE_LOG = "debug" }
+"#;
+        let cfg: ProjectConfig = toml::from_str(content).unwrap();
+        assert_eq!(cfg.runtimes["ci"].version.as_deref(), Some("v0.8.9"));
+        assert!(cfg.runtimes["ci"].env.is_empty());
+        let local = &cfg.runtimes["local"];
+        assert_eq!(local.path.as_deref(), Some("tools/lune-nightly"));
+        assert_eq!(local.env["LUNE_LOG"], "debug");
+        assert!(config_schema::validate(ConfigFile::LunuToml, content).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dev_dependencies_stay_out_of_payloads() {
+        let content = r#"
+[project]
+name = "Tested"
+entry = "src/main.luau"
+modules_dir = "modules"
+
+[dependencies]
+json = { url = "https://github.com/example/json" }
+
+[dev-dependencies]
+testez = { url = "https://github.com/example/testez", tag = "v0.4.1" }
+mocks = { path = "modules/mocks" }
+fixtures = { path = "../fixtures" }
+"#;
+        let cfg: ProjectConfig = toml::from_str(content).unwrap();
+        assert_eq!(cfg.all_dependencies().len(), 4);
+        assert_eq!(cfg.dev_module_paths(), vec!["mocks", "testez"]);
+        assert!(config_schema::validate(ConfigFile::LunuToml, content).unwrap().is_empty());
     }
 }
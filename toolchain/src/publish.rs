@@ -0,0 +1,116 @@
+//! `lunu publish`: releases a library so other projects can `lunu add owner/repo@<version>` it.
+//! The `[project]` metadata is checked, the library directory is packed into
+//! `<name>-<version>.tar.gz`, and the `v<version>` tag and its GitHub release are created by the
+//! caller.
+
+use std::path::Path;
+use anyhow::{Context, Result};
+use lunu_builder::exclude::ExcludeSet;
+use lunu_cli::secret_store;
+use semver::Version;
+use crate::project::ProjectConfig;
+
+/// What the package is installed from: `lib` of `[project]`, else the project root.
+pub fn library_dir(root: &Path, cfg: &ProjectConfig) -> std::path::PathBuf {
+    match &cfg.project.lib {
+        Some(lib) => root.join(lib),
+        None => root.to_path_buf(),
+    }
+}
+
+/// Everything in lunu.toml and the library directory that keeps the package from being published;
+/// empty when it is ready.
+pub fn metadata_problems(root: &Path, cfg: &ProjectConfig) -> Vec<String> {
+    let project = &cfg.project;
+    let mut problems = Vec::new();
+    if project.name.is_empty() || !project.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        problems.push(format!("[project] name '{}' may only contain letters, digits, '-' and '_'.", project.name));
+    }
+    match project.version.as_deref() {
+        None => problems.push("[project] version is missing (e.g. version = \"1.0.0\").".to_string()),
+        Some(version) if Version::parse(version).is_err() => problems.push(format!("[project] version '{}' is not a semver version.", version)),
+        Some(_) => {}
+    }
+    if project.description.as_deref().is_none_or(|d| d.trim().is_empty()) {
+        problems.push("[project] description is missing.".to_string());
+    }
+    let lib = library_dir(root, cfg);
+    if !lib.join("init.luau").is_file() && !lib.join("init.lua").is_file() {
+        problems.push(format!("{:?} has no init.luau for 'lunu add' to load; set [project] lib to the library directory.", lib));
+    }
+    problems
+}
+
+/// Files shipped from the library directory: build excludes apply, secrets never ship, and when
+/// the library is the project root its installed modules, build output and lock file stay out.
+pub fn package_exclude(root: &Path, cfg: &ProjectConfig, exclude: ExcludeSet) -> ExcludeSet {
+    let exclude = exclude.with_paths(&secret_store::PRIVATE_PATHS.map(str::to_string));
+    if library_dir(root, cfg) != root {
+        return exclude;
+    }
+    let paths = [cfg.project.modules_dir.as_str(), "dist", ".git", "lunu.lock"].map(str::to_string);
+    exclude.with_paths(&paths)
+}
+
+/// Packs `dir` into a `.tar.gz` with the files at its root, as URL installs expect. Returns the
+/// archive and the packed paths.
+pub fn pack(dir: &Path, exclude: &ExcludeSet) -> Result<(Vec<u8>, Vec<String>)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current).with_context(|| format!("Failed to read {:?}", current))?.flatten() {
+            let path = entry.path();
+            let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if exclude.is_excluded(&relative) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.is_file() {
+                files.push(relative);
+            }
+        }
+    }
+    files.sort();
+
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    for file in &files {
+        tar.append_path_with_name(dir.join(file), file).with_context(|| format!("Failed to pack {}", file))?;
+    }
+    Ok((tar.into_inner()?.finish()?, files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_metadata_and_packs_the_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut cfg = ProjectConfig::new("json-lib");
+        assert_eq!(metadata_problems(root, &cfg).len(), 3);
+
+        cfg.project.version = Some("1.2".to_string());
+        cfg.project.description = Some("JSON for Luau".to_string());
+        std::fs::create_dir_all(root.join("modules/dep")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("init.luau"), "return require(\"./src\")").unwrap();
+        std::fs::write(root.join("src/init.luau"), "return {}").unwrap();
+        std::fs::write(root.join("modules/dep/init.luau"), "return {}").unwrap();
+        std::fs::write(root.join("lunu.lock"), "").unwrap();
+        std::fs::create_dir_all(root.join("config")).unwrap();
+        std::fs::write(root.join("config/.secrets.json"), "{}").unwrap();
+        let problems = metadata_problems(root, &cfg);
+        assert!(problems.len() == 1 && problems[0].contains("'1.2'"));
+        cfg.project.version = Some("1.2.0".to_string());
+        assert!(metadata_problems(root, &cfg).is_empty());
+
+        let (archive, files) = pack(root, &package_exclude(root, &cfg, ExcludeSet::default())).unwrap();
+        assert_eq!(files, vec!["init.luau".to_string(), "src/init.luau".to_string()]);
+        let unpacked = root.join("unpacked");
+        assert_eq!(crate::archive::unpack(&archive, &unpacked, None).unwrap(), crate::archive::ArchiveFormat::TarGz);
+        assert!(unpacked.join("src/init.luau").is_file());
+    }
+}
@@ -0,0 +1,325 @@
+//! `lunu analyze requires`: a static scan of `require(...)` calls, resolved through `.luaurc`
+//! aliases, to find broken requires and dependencies that are declared but unused (or used but
+//! undeclared). Only string-literal requires are seen; computed paths are ignored.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use path_clean::PathClean;
+use serde::Serialize;
+
+/// Directories never scanned: build output, runtimes, VCS data and tool caches.
+const SKIPPED_DIRS: &[&str] = &["dist", "bin", "target", "node_modules", "__pycache__"];
+
+/// Runtime libraries that resolve without an alias.
+const BUILTIN_ALIASES: &[&str] = &["lune", "lute", "std"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequireIssue {
+    /// Relative to the project root, with `/` separators
+    pub file: String,
+    pub line: usize,
+    pub require: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RequireReport {
+    pub files_scanned: usize,
+    /// Requires in project files (outside `modules/`) that do not resolve to a file
+    pub unresolved: Vec<RequireIssue>,
+    /// Declared in lunu.toml, installed, but required by no project file or other module
+    pub unused: Vec<String>,
+    /// Required modules under `modules/` that lunu.toml does not declare
+    pub undeclared: Vec<String>,
+}
+
+impl RequireReport {
+    pub fn is_clean(&self) -> bool {
+        self.unresolved.is_empty() && self.unused.is_empty() && self.undeclared.is_empty()
+    }
+}
+
+/// `require("...")` calls with string-literal paths and their 1-based lines. Comments and other
+/// strings are skipped, as are method calls such as `loader.require("x")`.
+pub fn extract_requires(source: &str) -> Vec<(usize, String)> {
+    let bytes = source.as_bytes();
+    let mut found = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i += 2;
+                match long_bracket_level(bytes, i) {
+                    Some(level) => i = skip_long_bracket(bytes, i, level, &mut line),
+                    None => {
+                        while i < bytes.len() && bytes[i] != b'\n' {
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            b'[' => match long_bracket_level(bytes, i) {
+                Some(level) => i = skip_long_bracket(bytes, i, level, &mut line),
+                None => i += 1,
+            },
+            quote @ (b'"' | b'\'' | b'`') => i = skip_string(bytes, i, quote, &mut line),
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                let is_call = &source[start..i] == "require" && (start == 0 || !matches!(bytes[start - 1], b'.' | b':'));
+                if is_call {
+                    if let Some(path) = require_argument(source, i) {
+                        found.push((line, path));
+                    }
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
+
+/// The string literal passed to a `require` ending at `i`: `require("x")`, `require 'x'`.
+fn require_argument(source: &str, i: usize) -> Option<String> {
+    let rest = source[i..].trim_start_matches([' ', '\t']);
+    let rest = rest.strip_prefix('(').map(|r| r.trim_start()).unwrap_or(rest);
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let end = rest[1..].find([quote, '\n'])?;
+    let path = &rest[1..1 + end];
+    (rest[1 + end..].starts_with(quote) && !path.contains('\\')).then(|| path.to_string())
+}
+
+/// `[[`, `[=[`, ... at `i`: the number of `=`.
+fn long_bracket_level(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+    let level = bytes[i + 1..].iter().take_while(|b| **b == b'=').count();
+    (bytes.get(i + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+fn skip_long_bracket(bytes: &[u8], i: usize, level: usize, line: &mut usize) -> usize {
+    let close = [b"]".as_slice(), &vec![b'='; level], b"]"].concat();
+    let mut j = i + level + 2;
+    while j < bytes.len() {
+        if bytes[j..].starts_with(&close) {
+            return j + close.len();
+        }
+        if bytes[j] == b'\n' {
+            *line += 1;
+        }
+        j += 1;
+    }
+    j
+}
+
+fn skip_string(bytes: &[u8], i: usize, quote: u8, line: &mut usize) -> usize {
+    let mut j = i + 1;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => {
+                if bytes.get(j + 1) == Some(&b'\n') {
+                    *line += 1;
+                }
+                j += 2;
+                continue;
+            }
+            b'\n' if quote != b'`' => return j,
+            b'\n' => *line += 1,
+            c if c == quote => return j + 1,
+            _ => {}
+        }
+        j += 1;
+    }
+    j
+}
+
+/// What a require path points to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    File(PathBuf),
+    /// A runtime library (`@lune/fs`, `@std/json`) with no alias in `.luaurc`
+    Builtin,
+}
+
+/// Resolves `spec` as required from `from` (a file under `root`): `@alias/...` through `aliases`,
+/// `@self/...` from the file's directory, `./` and `../` from the file (and, for `init.luau`,
+/// also from its module's parent, as newer Luau does), and bare paths from the file or the root.
+pub fn resolve(root: &Path, aliases: &BTreeMap<String, String>, from: &Path, spec: &str) -> Option<Target> {
+    let dir = from.parent().unwrap_or(root);
+    let mut bases = Vec::new();
+    let rest = if let Some(aliased) = spec.strip_prefix('@') {
+        let (alias, rest) = aliased.split_once('/').unwrap_or((aliased, ""));
+        if alias.eq_ignore_ascii_case("self") {
+            bases.push(dir.to_path_buf());
+        } else {
+            let value = aliases
+                .iter()
+                .find(|(name, _)| name.trim_start_matches('@').eq_ignore_ascii_case(alias))
+                .map(|(_, value)| value);
+            match value {
+                Some(value) => bases.push(root.join(value)),
+                None if BUILTIN_ALIASES.contains(&alias.to_lowercase().as_str()) => return Some(Target::Builtin),
+                None => return None,
+            }
+        }
+        rest
+    } else if spec.starts_with("./") || spec.starts_with("../") {
+        bases.push(dir.to_path_buf());
+        if from.file_stem().is_some_and(|s| s == "init") {
+            bases.push(dir.parent().unwrap_or(root).to_path_buf());
+        }
+        spec
+    } else {
+        bases.push(dir.to_path_buf());
+        bases.push(root.to_path_buf());
+        spec
+    };
+    bases.into_iter().find_map(|base| find_module(&base.join(rest).clean())).map(Target::File)
+}
+
+/// `path.luau`, `path.lua`, `path` itself or `path/init.luau`.
+fn find_module(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let candidates = [
+        path.with_file_name(format!("{}.luau", file_name)),
+        path.with_file_name(format!("{}.lua", file_name)),
+        path.to_path_buf(),
+        path.join("init.luau"),
+        path.join("init.lua"),
+    ];
+    candidates.into_iter().find(|c| c.is_file())
+}
+
+/// Every `.luau` and `.lua` file under `root`, sorted, skipping hidden and build directories.
+pub fn luau_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+            } else if name.ends_with(".luau") || name.ends_with(".lua") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Scans every Luau file under `root`. `modules` are the installed module directories by name
+/// (`modules/@scope/name` as `@scope/name`) and `declared` the dependency names in lunu.toml.
+/// Bridge modules (with a `bridge.json`) are called through `@lunu`, so they never count as unused.
+pub fn analyze(
+    root: &Path,
+    aliases: &BTreeMap<String, String>,
+    modules: &[(String, PathBuf)],
+    declared: &BTreeSet<String>,
+) -> RequireReport {
+    let owner = |path: &Path| modules.iter().find(|(_, dir)| path.starts_with(dir)).map(|(name, _)| name.clone());
+    let mut report = RequireReport::default();
+    let mut used = BTreeSet::new();
+    for file in luau_files(root) {
+        let Ok(source) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        report.files_scanned += 1;
+        let from_module = owner(&file);
+        for (line, spec) in extract_requires(&source) {
+            match resolve(root, aliases, &file, &spec) {
+                Some(Target::File(target)) => {
+                    if let Some(module) = owner(&target).filter(|m| Some(m) != from_module.as_ref()) {
+                        used.insert(module);
+                    }
+                }
+                Some(Target::Builtin) => {}
+                // Third-party modules often rely on their own aliases; only the project's files are checked
+                None if from_module.is_none() => report.unresolved.push(RequireIssue {
+                    file: file.strip_prefix(root).unwrap_or(&file).to_string_lossy().replace('\\', "/"),
+                    line,
+                    require: spec,
+                }),
+                None => {}
+            }
+        }
+    }
+    let bridge = |name: &String| modules.iter().any(|(m, dir)| m == name && dir.join("bridge.json").exists());
+    report.unused = declared
+        .iter()
+        .filter(|name| modules.iter().any(|(m, _)| m == *name) && !used.contains(*name) && !bridge(name))
+        .cloned()
+        .collect();
+    report.undeclared = used.into_iter().filter(|name| !declared.contains(name)).collect();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn extracts_requires_outside_comments_and_strings() {
+        let source = r#"local a = require("@pkg/json")
+-- local b = require("commented")
+--[[ require("block")
+]]
+local s = "require('in a string')"
+local c = require './util'
+local d = loader.require("method")
+local e = require(`template`)
+"#;
+        assert_eq!(
+            extract_requires(source),
+            vec![(1, "@pkg/json".to_string()), (6, "./util".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_unresolved_unused_and_undeclared_modules() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let write = |rel: &str, content: &str| {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("src/main.luau", "local json = require(\"@json\")\nlocal util = require(\"./util\")\nlocal fs = require(\"@lune/fs\")\nlocal x = require(\"@missing/x\")\n");
+        write("src/util.luau", "return {}\n");
+        write("modules/json/init.luau", "local inner = require(\"./inner\")\nlocal http = require(\"@acme/http\")\n");
+        write("modules/json/inner.luau", "return {}\n");
+        write("modules/@acme/http/init.luau", "return {}\n");
+        write("modules/yaml/init.luau", "return {}\n");
+        write("modules/ml/bridge.json", "{}");
+
+        let aliases: BTreeMap<String, String> = [("json", "modules/json/"), ("acme", "modules/@acme/")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let modules: Vec<(String, PathBuf)> = ["json", "@acme/http", "yaml", "ml"]
+            .iter()
+            .map(|name| (name.to_string(), root.join("modules").join(name)))
+            .collect();
+        let declared: BTreeSet<String> = ["json", "yaml", "ml"].iter().map(|s| s.to_string()).collect();
+
+        let report = analyze(root, &aliases, &modules, &declared);
+        assert_eq!(report.files_scanned, 6);
+        assert_eq!(report.unresolved, vec![RequireIssue { file: "src/main.luau".to_string(), line: 4, require: "@missing/x".to_string() }]);
+        assert_eq!(report.unused, vec!["yaml".to_string()]);
+        assert_eq!(report.undeclared, vec!["@acme/http".to_string()]);
+    }
+}
@@ -0,0 +1,192 @@
+//! In-memory cache of bridge responses. A method that declares `"cache": {"ttl_ms": 60000}` in
+//! bridge.json is answered from here for repeated identical calls, without reaching the worker (or
+//! the remote bridge) until the entry expires. Only successful results are cached. The cache holds
+//! a bounded number of entries across all modules and evicts the least recently used one first.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Entries kept across all modules.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// `"cache"` of a method in bridge.json.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct CacheSpec {
+    pub ttl_ms: u64,
+    #[serde(default)]
+    pub key: CacheKey,
+}
+
+/// What makes two calls identical.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKey {
+    /// The same module, method and arguments
+    #[default]
+    Args,
+    /// The same module and method, whatever the arguments
+    Method,
+}
+
+/// Counters for `/api/v1/system/metrics`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to make room
+    pub evictions: u64,
+    /// Entries found past their TTL
+    pub expired: u64,
+    /// Entries removed through the invalidation endpoint
+    pub invalidated: u64,
+}
+
+pub struct ResponseCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// Keys by last use, oldest first
+    order: BTreeMap<u64, String>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+struct Entry {
+    module: String,
+    method: String,
+    result: Value,
+    expires: Instant,
+    used: u64,
+}
+
+impl Inner {
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used);
+        Some(entry)
+    }
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), inner: Mutex::default() }
+    }
+
+    /// Key of a call to `method` of `module`. Arguments are hashed, so large ones cost no memory.
+    pub fn key(module: &str, method: &str, spec: &CacheSpec, args: &[Value]) -> String {
+        let args = match spec.key {
+            CacheKey::Args => hex::encode(Sha256::digest(serde_json::to_vec(args).unwrap_or_default())),
+            CacheKey::Method => String::new(),
+        };
+        format!("{}\n{}\n{}", module, method, args)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The cached result for `key`, when there is one that has not expired.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let mut inner = self.lock();
+        let now = Instant::now();
+        if inner.entries.get(key).is_some_and(|e| e.expires <= now) {
+            inner.remove(key);
+            inner.stats.expired += 1;
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        let Some(entry) = inner.entries.get_mut(key) else {
+            inner.stats.misses += 1;
+            return None;
+        };
+        let (previous, result) = (std::mem::replace(&mut entry.used, tick), entry.result.clone());
+        inner.order.remove(&previous);
+        inner.order.insert(tick, key.to_string());
+        inner.stats.hits += 1;
+        Some(result)
+    }
+
+    /// Caches `result` for `ttl`, evicting the least recently used entries beyond the capacity.
+    pub fn insert(&self, key: String, module: &str, method: &str, result: Value, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+        let mut inner = self.lock();
+        inner.remove(&key);
+        inner.tick += 1;
+        let used = inner.tick;
+        inner.order.insert(used, key.clone());
+        let entry = Entry { module: module.to_string(), method: method.to_string(), result, expires: Instant::now() + ttl, used };
+        inner.entries.insert(key, entry);
+        while inner.entries.len() > self.capacity {
+            let Some((_, oldest)) = inner.order.pop_first() else { break };
+            inner.entries.remove(&oldest);
+            inner.stats.evictions += 1;
+        }
+    }
+
+    /// Drops the entries of `module` (all modules when `None`), optionally only those of `method`.
+    /// Returns how many were dropped.
+    pub fn invalidate(&self, module: Option<&str>, method: Option<&str>) -> usize {
+        let mut inner = self.lock();
+        let keys: Vec<String> = inner
+            .entries
+            .iter()
+            .filter(|(_, e)| module.is_none_or(|m| e.module == m) && method.is_none_or(|m| e.method == m))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            inner.remove(key);
+        }
+        inner.stats.invalidated += keys.len() as u64;
+        keys.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats { entries: inner.entries.len(), ..inner.stats.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serves_evicts_and_invalidates_entries() {
+        let cache = ResponseCache::new(2);
+        let spec = CacheSpec { ttl_ms: 60_000, key: CacheKey::Args };
+        let ttl = Duration::from_millis(spec.ttl_ms);
+        let lookup = |args: Value| ResponseCache::key("geo", "lookup", &spec, &[args]);
+        assert_ne!(lookup(json!("paris")), lookup(json!("rome")));
+        let by_method = CacheSpec { key: CacheKey::Method, ..spec };
+        assert_eq!(ResponseCache::key("geo", "all", &by_method, &[json!(1)]), ResponseCache::key("geo", "all", &by_method, &[]));
+
+        assert_eq!(cache.get(&lookup(json!("paris"))), None);
+        cache.insert(lookup(json!("paris")), "geo", "lookup", json!([48.8, 2.3]), ttl);
+        cache.insert(lookup(json!("rome")), "geo", "lookup", json!([41.9, 12.5]), ttl);
+        assert_eq!(cache.get(&lookup(json!("paris"))), Some(json!([48.8, 2.3])));
+        // Rome is now the least recently used
+        cache.insert(lookup(json!("oslo")), "geo", "lookup", json!([59.9, 10.7]), ttl);
+        assert_eq!(cache.get(&lookup(json!("rome"))), None);
+        assert_eq!(cache.get(&lookup(json!("oslo"))), Some(json!([59.9, 10.7])));
+
+        cache.insert("short".to_string(), "geo", "now", json!(1), Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get("short"), None);
+
+        assert_eq!(cache.invalidate(Some("other"), None), 0);
+        assert_eq!(cache.invalidate(Some("geo"), Some("lookup")), 1);
+        assert_eq!(cache.stats(), CacheStats { entries: 0, hits: 2, misses: 3, evictions: 2, expired: 1, invalidated: 1 });
+    }
+}
@@ -0,0 +1,109 @@
+//! `lunu run --sandbox`: runs a script the way a built executable sees a clean machine, with only a
+//! handful of system variables, an empty temporary HOME and (with `--no-network`) no bridge.
+
+use std::path::Path;
+use std::process::Command;
+use anyhow::Result;
+
+/// Variables a fresh Windows/Unix session has that programs cannot do without.
+const SYSTEM_VARS: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "OS",
+    "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TERM",
+    "TZ",
+];
+
+/// Set for the script when the bridge is off; `@lunu` refuses bridge calls.
+pub const BRIDGE_DISABLED_VAR: &str = "LUNU_BRIDGE_DISABLED";
+
+/// The environment of a sandboxed run: system variables, `LUNU_*` and `keep` from `current`,
+/// with every home/temp location pointed into `home`.
+pub fn environment(current: impl IntoIterator<Item = (String, String)>, home: &Path, keep: &[String], no_network: bool) -> Vec<(String, String)> {
+    let keep_var = |name: &str| {
+        let upper = name.to_uppercase();
+        SYSTEM_VARS.contains(&upper.as_str()) || upper.starts_with("LUNU_") || keep.iter().any(|k| k.eq_ignore_ascii_case(name))
+    };
+    let mut env: Vec<(String, String)> = current.into_iter().filter(|(name, _)| keep_var(name)).collect();
+    let path = |sub: &str| home.join(sub).to_string_lossy().to_string();
+    let overrides = [
+        ("HOME", home.to_string_lossy().to_string()),
+        ("USERPROFILE", home.to_string_lossy().to_string()),
+        ("APPDATA", path("AppData/Roaming")),
+        ("LOCALAPPDATA", path("AppData/Local")),
+        ("XDG_CONFIG_HOME", path(".config")),
+        ("XDG_CACHE_HOME", path(".cache")),
+        ("XDG_DATA_HOME", path(".local/share")),
+        ("TEMP", path("tmp")),
+        ("TMP", path("tmp")),
+        ("TMPDIR", path("tmp")),
+    ];
+    env.retain(|(name, _)| !overrides.iter().any(|(o, _)| o.eq_ignore_ascii_case(name)));
+    env.extend(overrides.into_iter().map(|(name, value)| (name.to_string(), value)));
+    if no_network {
+        // Unreachable proxies stop most HTTP clients; the bridge itself is switched off
+        for name in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"] {
+            env.push((name.to_string(), "http://127.0.0.1:9".to_string()));
+        }
+        env.push((BRIDGE_DISABLED_VAR.to_string(), "1".to_string()));
+    }
+    env
+}
+
+/// Points `command` at a fresh sandbox. The returned directory is the temporary HOME; the
+/// sandbox is deleted when it is dropped.
+pub fn apply(command: &mut Command, keep: &[String], no_network: bool) -> Result<tempfile::TempDir> {
+    let home = tempfile::Builder::new().prefix("lunu-sandbox-").tempdir()?;
+    for sub in ["AppData/Roaming", "AppData/Local", ".config", ".cache", ".local/share", "tmp"] {
+        std::fs::create_dir_all(home.path().join(sub))?;
+    }
+    command.env_clear().envs(environment(std::env::vars(), home.path(), keep, no_network));
+    Ok(home)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn get<'a>(env: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        env.iter().rev().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn keeps_only_system_and_requested_variables() {
+        let home = Path::new("/tmp/sandbox");
+        let current = vars(&[
+            ("Path", "/usr/bin"),
+            ("HOME", "/home/dev"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("LUNU_RUNTIME", "lune"),
+            ("API_TOKEN", "token"),
+        ]);
+        let env = environment(current.clone(), home, &["api_token".to_string()], false);
+        assert_eq!(get(&env, "Path"), Some("/usr/bin"));
+        assert_eq!(get(&env, "LUNU_RUNTIME"), Some("lune"));
+        assert_eq!(get(&env, "API_TOKEN"), Some("token"));
+        assert_eq!(get(&env, "AWS_SECRET_ACCESS_KEY"), None);
+        assert_eq!(get(&env, "HOME"), Some("/tmp/sandbox"));
+        assert_eq!(env.iter().filter(|(k, _)| k == "HOME").count(), 1);
+        assert_eq!(get(&env, BRIDGE_DISABLED_VAR), None);
+
+        let offline = environment(current, home, &[], true);
+        assert_eq!(get(&offline, BRIDGE_DISABLED_VAR), Some("1"));
+        assert_eq!(get(&offline, "HTTPS_PROXY"), Some("http://127.0.0.1:9"));
+        assert_eq!(get(&offline, "API_TOKEN"), None);
+    }
+}
@@ -0,0 +1,130 @@
+//! `lunu bundle`: inlines a script and every local module it requires (resolved like `lunu analyze
+//! requires`, `.luaurc` aliases included) into one Luau file that runs without Lunu.
+//!
+//! Each module becomes a function in a table, keyed by its path relative to the project. Inside
+//! it, `require` is a local that maps the module's own require strings to those keys. Anything it
+//! does not know (runtime libraries such as `@lune/fs`, computed paths) goes to the runtime's
+//! `require`. Sources are kept as they are; no require call is rewritten.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use crate::requires::{self, Target};
+
+const PRELUDE: &str = r#"local __lunu_runtime_require = require
+local __lunu_modules = {}
+local __lunu_loaded = {}
+
+local function __lunu_load(id, ...)
+	local cached = __lunu_loaded[id]
+	if cached == false then
+		error("cyclic require of " .. id, 2)
+	elseif cached ~= nil then
+		return cached[1]
+	end
+	__lunu_loaded[id] = false
+	local result = __lunu_modules[id](...)
+	__lunu_loaded[id] = { result }
+	return result
+end
+
+local function __lunu_requirer(paths)
+	return function(spec, ...)
+		local id = paths[spec]
+		if id then
+			return __lunu_load(id)
+		end
+		return __lunu_runtime_require(spec, ...)
+	end
+end
+"#;
+
+/// A bundled script and the modules inlined into it, entry first.
+#[derive(Debug)]
+pub struct ScriptBundle {
+    pub source: String,
+    pub modules: Vec<String>,
+}
+
+/// Module key of `path`: relative to `root` with `/` separators (`../Lunu/init.luau` outside it).
+fn module_id(root: &Path, path: &Path) -> String {
+    pathdiff::diff_paths(path, root).unwrap_or_else(|| path.to_path_buf()).to_string_lossy().replace('\\', "/")
+}
+
+/// Bundles `entry` (a file under `root`) with everything it requires. A require that resolves
+/// to nothing is an error naming the file and line.
+pub fn bundle(root: &Path, aliases: &BTreeMap<String, String>, entry: &Path) -> Result<ScriptBundle> {
+    let entry = entry.canonicalize().with_context(|| format!("Script not found: {:?}", entry))?;
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut modules: Vec<(String, String, BTreeMap<String, String>)> = Vec::new();
+    let mut seen: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let mut pending = VecDeque::from([entry.clone()]);
+    seen.insert(entry.clone(), module_id(&root, &entry));
+
+    while let Some(file) = pending.pop_front() {
+        let source = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {:?}", file))?;
+        let mut paths = BTreeMap::new();
+        for (line, spec) in requires::extract_requires(&source) {
+            match requires::resolve(&root, aliases, &file, &spec) {
+                Some(Target::File(target)) => {
+                    let target = target.canonicalize().unwrap_or(target);
+                    let id = seen.entry(target.clone()).or_insert_with(|| {
+                        pending.push_back(target.clone());
+                        module_id(&root, &target)
+                    });
+                    paths.insert(spec, id.clone());
+                }
+                Some(Target::Builtin) => {}
+                None => {
+                    return Err(anyhow::anyhow!("{}:{}: require(\"{}\") does not resolve to a file", module_id(&root, &file), line, spec));
+                }
+            }
+        }
+        modules.push((seen[&file].clone(), source, paths));
+    }
+
+    let entry_id = seen[&entry].clone();
+    let mut out = format!("-- Bundled by Lunu {} from {}; edit the sources instead.\n", env!("CARGO_PKG_VERSION"), entry_id);
+    out.push_str(PRELUDE);
+    for (id, source, paths) in &modules {
+        out.push_str(&format!("\n__lunu_modules[{}] = function(...)\n", lua_string(id)));
+        let entries: Vec<String> = paths.iter().map(|(spec, id)| format!("[{}] = {}", lua_string(spec), lua_string(id))).collect();
+        let map = if entries.is_empty() { "{}".to_string() } else { format!("{{ {} }}", entries.join(", ")) };
+        out.push_str(&format!("local require = __lunu_requirer({})\n", map));
+        out.push_str(source.trim_end());
+        out.push_str("\nend\n");
+    }
+    out.push_str(&format!("\nreturn __lunu_load({}, ...)\n", lua_string(&entry_id)));
+    Ok(ScriptBundle { source: out, modules: modules.into_iter().map(|(id, _, _)| id).collect() })
+}
+
+fn lua_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inlines_local_and_aliased_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("modules/json")).unwrap();
+        std::fs::write(root.join("src/main.luau"), "local fs = require(\"@lune/fs\")\nlocal util = require(\"./util\")\nlocal json = require(\"@json\")\nprint(util.name, ...)\n").unwrap();
+        std::fs::write(root.join("src/util.luau"), "local json = require(\"@json\")\nreturn { name = \"util\" }\n").unwrap();
+        std::fs::write(root.join("modules/json/init.luau"), "return {}\n").unwrap();
+        let aliases = BTreeMap::from([("json".to_string(), "modules/json/".to_string())]);
+
+        let bundled = bundle(root, &aliases, &root.join("src/main.luau")).unwrap();
+        assert_eq!(bundled.modules, vec!["src/main.luau", "src/util.luau", "modules/json/init.luau"]);
+        assert!(bundled.source.contains("local require = __lunu_requirer({ [\"./util\"] = \"src/util.luau\", [\"@json\"] = \"modules/json/init.luau\" })"));
+        assert_eq!(bundled.source.matches("__lunu_modules[\"modules/json/init.luau\"] = function").count(), 1);
+        assert!(bundled.source.trim_end().ends_with("return __lunu_load(\"src/main.luau\", ...)"));
+
+        std::fs::write(root.join("src/util.luau"), "return require(\"./missing\")\n").unwrap();
+        let err = bundle(root, &aliases, &root.join("src/main.luau")).unwrap_err().to_string();
+        assert!(err.contains("src/util.luau:1") && err.contains("./missing"));
+    }
+}
@@ -0,0 +1,289 @@
+//! Storage for the bridge API key: the OS keychain (Windows Credential Manager, macOS Keychain,
+//! libsecret via `secret-tool`) when available, otherwise `config/.secrets.json` readable only by
+//! the current user. Keys are never printed; `lunu key show --reveal` is the one way to see them.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::console;
+
+const SERVICE: &str = "lunu-bridge";
+
+/// Project paths that hold secrets (the API key file; `.lunu/` with the update signing key, the
+/// daemon token and the audit log key). Exports, templates and published packages leave them out.
+pub const PRIVATE_PATHS: [&str; 2] = [".lunu", "config/.secrets.json"];
+
+#[derive(Serialize, Deserialize)]
+struct SecretsFile {
+    api_key: String,
+}
+
+pub enum KeyLocation {
+    Keychain,
+    File(PathBuf),
+}
+
+impl fmt::Display for KeyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyLocation::Keychain => write!(f, "the OS keychain"),
+            KeyLocation::File(path) => write!(f, "{:?}", path),
+        }
+    }
+}
+
+/// Deliberately not `Debug`, so the key cannot end up in logs by accident.
+pub struct ApiKey {
+    pub key: String,
+    pub location: KeyLocation,
+}
+
+impl ApiKey {
+    /// First and last four characters, enough to tell keys apart.
+    pub fn masked(&self) -> String {
+        if self.key.len() <= 8 {
+            return "*".repeat(self.key.len());
+        }
+        format!("{}{}{}", &self.key[..4], console::glyph("…", "..."), &self.key[self.key.len() - 4..])
+    }
+}
+
+fn secrets_path(base_dir: &Path) -> PathBuf {
+    base_dir.join("config").join(".secrets.json")
+}
+
+/// Keychain entries are per project, keyed by a hash of its directory.
+fn account(base_dir: &Path) -> String {
+    let dir = base_dir.canonicalize().unwrap_or_else(|_| base_dir.to_path_buf());
+    let digest = Sha256::digest(dir.to_string_lossy().as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// `LUNU_SECRETS=file` keeps the key in `.secrets.json`, e.g. on CI machines without a keychain.
+fn keychain_enabled() -> bool {
+    std::env::var("LUNU_SECRETS").map(|v| v != "file").unwrap_or(true)
+}
+
+/// The project's key, or `None` before the bridge has run for the first time.
+pub fn load(base_dir: &Path) -> Result<Option<ApiKey>> {
+    load_from(base_dir, keychain_enabled())
+}
+
+/// The project's key, creating one on first use. A key found in `.secrets.json` moves into the
+/// keychain when one is available; otherwise the file is restricted to the current user.
+pub fn load_or_create(base_dir: &Path) -> Result<ApiKey> {
+    load_or_create_in(base_dir, keychain_enabled())
+}
+
+fn load_from(base_dir: &Path, use_keychain: bool) -> Result<Option<ApiKey>> {
+    if use_keychain {
+        if let Some(key) = os::get(&account(base_dir)) {
+            return Ok(Some(ApiKey { key, location: KeyLocation::Keychain }));
+        }
+    }
+    let path = secrets_path(base_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let file: SecretsFile = serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", path))?;
+    Ok(Some(ApiKey { key: file.api_key, location: KeyLocation::File(path) }))
+}
+
+fn load_or_create_in(base_dir: &Path, use_keychain: bool) -> Result<ApiKey> {
+    let key = match load_from(base_dir, use_keychain)? {
+        Some(ApiKey { location: KeyLocation::Keychain, key }) => {
+            return Ok(ApiKey { key, location: KeyLocation::Keychain });
+        }
+        Some(existing) => existing.key,
+        None => {
+            let mut bytes = [0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        }
+    };
+
+    let path = secrets_path(base_dir);
+    if use_keychain {
+        match os::set(&account(base_dir), &key) {
+            Ok(()) => {
+                if path.exists() {
+                    std::fs::remove_file(&path).with_context(|| format!("Key moved to the OS keychain, but {:?} could not be removed", path))?;
+                }
+                return Ok(ApiKey { key, location: KeyLocation::Keychain });
+            }
+            Err(err) => tracing::debug!("OS keychain unavailable ({}); keeping the API key in {:?}", err, path),
+        }
+    }
+    write_private(&path, &serde_json::to_string(&SecretsFile { api_key: key.clone() })?)?;
+    Ok(ApiKey { key, location: KeyLocation::File(path) })
+}
+
+/// Writes `content` so that only the current user can read it (0600 on Unix; on Windows the
+/// project directory's ACLs apply).
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        // `mode` only applies to new files; tighten one left behind by older versions
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(content.as_bytes())?;
+    }
+    #[cfg(not(unix))]
+    std::fs::write(path, content).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+#[cfg(windows)]
+mod os {
+    use super::SERVICE;
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::wincred::{CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC, PCREDENTIALW};
+
+    fn wide(s: &str) -> Vec<u16> {
+        OsStr::new(s).encode_wide().chain(Some(0)).collect()
+    }
+
+    fn target(account: &str) -> Vec<u16> {
+        wide(&format!("{}/{}", SERVICE, account))
+    }
+
+    pub fn get(account: &str) -> Option<String> {
+        let target = target(account);
+        let mut cred: PCREDENTIALW = std::ptr::null_mut();
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut cred) == 0 {
+                return None;
+            }
+            let blob = std::slice::from_raw_parts((*cred).CredentialBlob, (*cred).CredentialBlobSize as usize);
+            let key = String::from_utf8(blob.to_vec()).ok();
+            CredFree(cred as *mut _);
+            key
+        }
+    }
+
+    pub fn set(account: &str, key: &str) -> std::io::Result<()> {
+        let mut target = target(account);
+        let mut user = wide("lunu");
+        let mut blob = key.as_bytes().to_vec();
+        let mut cred: CREDENTIALW = unsafe { std::mem::zeroed() };
+        cred.Type = CRED_TYPE_GENERIC;
+        cred.TargetName = target.as_mut_ptr();
+        cred.UserName = user.as_mut_ptr();
+        cred.CredentialBlobSize = blob.len() as u32;
+        cred.CredentialBlob = blob.as_mut_ptr();
+        cred.Persist = CRED_PERSIST_LOCAL_MACHINE;
+        if unsafe { CredWriteW(&mut cred, 0) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod os {
+    use super::SERVICE;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    pub fn get(account: &str) -> Option<String> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", account, "-w"])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let key = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !key.is_empty()).then_some(key)
+    }
+
+    /// Goes through `security -i` so the key is passed on stdin instead of the command line.
+    pub fn set(account: &str, key: &str) -> std::io::Result<()> {
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "add-generic-password -U -s {} -a {} -w {}", SERVICE, account, key)?;
+        }
+        if !child.wait()?.success() {
+            return Err(std::io::Error::other("security add-generic-password failed"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod os {
+    use super::SERVICE;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    pub fn get(account: &str) -> Option<String> {
+        let output = Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "account", account])
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let key = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !key.is_empty()).then_some(key)
+    }
+
+    pub fn set(account: &str, key: &str) -> std::io::Result<()> {
+        let mut child = Command::new("secret-tool")
+            .args(["store", "--label", "Lunu bridge API key", "service", SERVICE, "account", account])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(key.as_bytes())?;
+        }
+        if !child.wait()?.success() {
+            return Err(std::io::Error::other("secret-tool store failed"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn file_fallback_creates_a_private_key_once() {
+        let dir = tempdir().unwrap();
+        assert!(load_from(dir.path(), false).unwrap().is_none());
+
+        let created = load_or_create_in(dir.path(), false).unwrap();
+        assert_eq!(created.key.len(), 64);
+        assert!(matches!(created.location, KeyLocation::File(_)));
+        assert!(!created.masked().contains(&created.key[4..60]));
+
+        let again = load_or_create_in(dir.path(), false).unwrap();
+        assert_eq!(again.key, created.key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(secrets_path(dir.path())).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! The PATH setup `lunu install` writes into Unix shell startup files, marked so that
+//! `lunu uninstall` can take exactly that block out again.
+
+use std::path::{Path, PathBuf};
+
+const BEGIN: &str = "# >>> lunu >>>";
+const END: &str = "# <<< lunu <<<";
+
+/// Startup files the installer updates: `.profile` always, the others when they exist.
+pub fn rc_files(home: &Path) -> Vec<PathBuf> {
+    let mut files = vec![home.join(".profile")];
+    for name in [".bashrc", ".zshrc"] {
+        let path = home.join(name);
+        if path.exists() {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// `content` with the PATH block for `bin_dir` appended, or `None` when it already has one.
+pub fn add_block(content: &str, bin_dir: &Path) -> Option<String> {
+    if content.contains(BEGIN) {
+        return None;
+    }
+    let mut out = content.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("{}\nexport PATH=\"{}:$PATH\"\n{}\n", BEGIN, bin_dir.display(), END));
+    Some(out)
+}
+
+/// `content` without the installer's block, or `None` when there is none.
+pub fn remove_block(content: &str) -> Option<String> {
+    let start = content.find(BEGIN)?;
+    let end = content[start..].find(END).map(|i| start + i + END.len())?;
+    let rest = content[end..].strip_prefix('\n').unwrap_or(&content[end..]);
+    Some(format!("{}{}", &content[..start], rest))
+}
+
+/// Lines outside the block that still mention `needle` (PATH edits made by hand).
+pub fn leftover_lines(content: &str, needle: &str) -> Vec<String> {
+    let cleaned = remove_block(content).unwrap_or_else(|| content.to_string());
+    cleaned.lines().filter(|l| l.contains(needle)).map(|l| l.trim().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_and_removes_only_the_marked_block() {
+        let bin = Path::new("/home/dev/.lunu/bin");
+        let original = "export EDITOR=vim\nalias ll='ls -l'";
+        let added = add_block(original, bin).unwrap();
+        assert!(added.contains("export PATH=\"/home/dev/.lunu/bin:$PATH\""));
+        assert!(add_block(&added, bin).is_none());
+
+        let edited = format!("{}export PATH=\"$HOME/.lunu/bin:$PATH\"\n", added);
+        assert_eq!(remove_block(&edited).unwrap(), "export EDITOR=vim\nalias ll='ls -l'\nexport PATH=\"$HOME/.lunu/bin:$PATH\"\n");
+        assert!(remove_block(original).is_none());
+        assert_eq!(leftover_lines(&edited, ".lunu/bin"), vec!["export PATH=\"$HOME/.lunu/bin:$PATH\"".to_string()]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Smoke tests of packaged artifacts for `lunu package --smoke`. The artifact runs from a copy in
+//! a temporary directory, away from the project's `modules/` and `.luaurc`, so a bundle that is
+//! missing a module or an asset fails packaging instead of failing for the people who download it.
+//!
+//! Either the artifact itself is run, or a script is run against it: the script gets the unpacked
+//! directory as its argument and the program in `LUNU_SMOKE_ARTIFACT`. Either way the run passes
+//! when it exits with 0 and its output contains the expected text, if any.
+
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct SmokeTest {
+    /// Text the artifact's output (stdout and stderr) must contain
+    pub expect: Option<String>,
+    /// Script to run against the artifact instead of running it directly
+    pub script: Option<PathBuf>,
+    /// Arguments for the artifact
+    pub args: Vec<String>,
+    pub timeout: Duration,
+}
+
+impl SmokeTest {
+    /// Runs `program` (inside `dir`, the unpacked artifact) or the script against it. Returns the
+    /// combined output, or an error holding it when the run fails, hangs or lacks `expect`.
+    pub fn run(&self, dir: &Path, program: &Path) -> Result<String> {
+        let (label, mut command) = match &self.script {
+            Some(script) => {
+                let mut command = Command::new(script);
+                command.arg(dir).env("LUNU_SMOKE_ARTIFACT", program);
+                (script.display().to_string(), command)
+            }
+            None => {
+                let mut command = Command::new(program);
+                command.args(&self.args);
+                (program.file_name().unwrap_or_default().to_string_lossy().to_string(), command)
+            }
+        };
+        command.current_dir(dir);
+        let (success, output) = run_with_timeout(&mut command, self.timeout).with_context(|| format!("Failed to run {}", label))?;
+        let Some(success) = success else {
+            return Err(anyhow::anyhow!("Smoke test failed: {} did not finish within {}s\n{}", label, self.timeout.as_secs(), output.trim_end()));
+        };
+        if !success {
+            return Err(anyhow::anyhow!("Smoke test failed: {} exited with an error\n{}", label, output.trim_end()));
+        }
+        if let Some(expected) = &self.expect {
+            if !output.contains(expected) {
+                return Err(anyhow::anyhow!("Smoke test failed: the output of {} does not contain {:?}\n{}", label, expected, output.trim_end()));
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Runs `command` with stdout and stderr captured to files, so a chatty program cannot block on a
+/// full pipe. `None` for success means it was killed after `timeout`.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Result<(Option<bool>, String)> {
+    let capture = tempfile::tempfile()?;
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(capture.try_clone()?))
+        .stderr(Stdio::from(capture.try_clone()?))
+        .spawn()?;
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let mut capture = capture;
+    capture.rewind()?;
+    let mut output = Vec::new();
+    capture.read_to_end(&mut output)?;
+    Ok((status.map(|s| s.success()), String::from_utf8_lossy(&output).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_artifacts_and_checks_their_output() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("app");
+        std::fs::write(&program, "#!/bin/sh\n[ -f assets/logo.txt ] || { echo 'missing asset' >&2; exit 1; }\necho \"hello $1\"\n").unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let test = |expect: &str| SmokeTest { expect: Some(expect.to_string()), script: None, args: vec!["world".to_string()], timeout: DEFAULT_TIMEOUT };
+
+        let err = test("hello").run(dir.path(), &program).unwrap_err().to_string();
+        assert!(err.contains("exited with an error") && err.contains("missing asset"));
+
+        std::fs::create_dir_all(dir.path().join("assets")).unwrap();
+        std::fs::write(dir.path().join("assets").join("logo.txt"), "logo").unwrap();
+        assert_eq!(test("hello world").run(dir.path(), &program).unwrap(), "hello world\n");
+        assert!(test("goodbye").run(dir.path(), &program).unwrap_err().to_string().contains("does not contain \"goodbye\""));
+
+        let hung = SmokeTest { expect: None, script: None, args: Vec::new(), timeout: Duration::from_millis(200) };
+        std::fs::write(&program, "#!/bin/sh\nsleep 5\n").unwrap();
+        assert!(hung.run(dir.path(), &program).unwrap_err().to_string().contains("did not finish"));
+
+        let script = dir.path().join("smoke.sh");
+        std::fs::write(&script, "#!/bin/sh\n[ -x \"$LUNU_SMOKE_ARTIFACT\" ] && [ -d \"$1/assets\" ]\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let scripted = SmokeTest { expect: None, script: Some(script), args: Vec::new(), timeout: DEFAULT_TIMEOUT };
+        assert!(scripted.run(dir.path(), &program).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+//! Named project snapshots for `lunu snapshot`: a copy of lunu.toml, lunu.lock and .luaurc, the
+//! runtime version and the checksum of every installed module, kept under `.lunu/snapshots`:
+//!
+//! ```text
+//! .lunu/snapshots/<name>/snapshot.json
+//! .lunu/snapshots/<name>/lunu.toml
+//! .lunu/snapshots/<name>/lunu.lock
+//! .lunu/snapshots/<name>/.luaurc
+//! ```
+//!
+//! Modules are not copied: restoring reinstalls each locked dependency at its locked commit (from
+//! the package cache when it has it) and checks the result against the recorded checksums.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::file_lock::write_atomic;
+
+pub const META: &str = "snapshot.json";
+
+/// Project files a snapshot keeps a copy of; those missing from the project are skipped.
+pub const FILES: &[&str] = &["lunu.toml", "lunu.lock", ".luaurc"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    pub name: String,
+    /// Unix time the snapshot was taken
+    pub created_at: u64,
+    pub lunu: String,
+    /// `lute` or `lune`
+    pub runtime: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_version: Option<String>,
+    /// Checksum of each installed module by dependency name (see `package::root_checksum`)
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+pub fn dir(root: &Path) -> PathBuf {
+    root.join(".lunu").join("snapshots")
+}
+
+/// Names become directory names: letters, digits, `-`, `_` and `.`, not starting with `.`.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(anyhow::anyhow!("Invalid snapshot name '{}': use letters, digits, '-', '_' and '.'", name));
+    }
+    Ok(())
+}
+
+/// Writes `snapshot` with copies of the project's `FILES`, replacing a snapshot of the same name.
+pub fn save(root: &Path, snapshot: &Snapshot) -> Result<PathBuf> {
+    validate_name(&snapshot.name)?;
+    let target = dir(root).join(&snapshot.name);
+    if target.exists() {
+        std::fs::remove_dir_all(&target).with_context(|| format!("Failed to replace snapshot {:?}", target))?;
+    }
+    std::fs::create_dir_all(&target)?;
+    for file in FILES {
+        let source = root.join(file);
+        if source.is_file() {
+            std::fs::copy(&source, target.join(file)).with_context(|| format!("Failed to copy {:?} into the snapshot", source))?;
+        }
+    }
+    write_atomic(&target.join(META), serde_json::to_string_pretty(snapshot)?.as_bytes())?;
+    Ok(target)
+}
+
+pub fn load(root: &Path, name: &str) -> Result<Snapshot> {
+    validate_name(name)?;
+    let path = dir(root).join(name).join(META);
+    if !path.exists() {
+        let known = list(root).into_iter().map(|s| s.name).collect::<Vec<_>>();
+        let known = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+        return Err(anyhow::anyhow!("No snapshot named '{}' (snapshots: {})", name, known));
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid snapshot {:?}", path))
+}
+
+/// Copies the snapshot's `FILES` back into the project. A file the snapshot has no copy of is
+/// removed, so the project matches the snapshot.
+pub fn restore_files(root: &Path, name: &str) -> Result<()> {
+    let source = dir(root).join(name);
+    for file in FILES {
+        let saved = source.join(file);
+        let target = root.join(file);
+        if saved.is_file() {
+            let content = std::fs::read(&saved).with_context(|| format!("Failed to read {:?}", saved))?;
+            write_atomic(&target, &content)?;
+        } else if target.is_file() {
+            std::fs::remove_file(&target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every snapshot of the project, oldest first.
+pub fn list(root: &Path) -> Vec<Snapshot> {
+    let Ok(entries) = std::fs::read_dir(dir(root)) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<Snapshot> = entries
+        .flatten()
+        .filter_map(|e| std::fs::read_to_string(e.path().join(META)).ok())
+        .filter_map(|content| serde_json::from_str(&content).ok())
+        .collect();
+    snapshots.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.name.cmp(&b.name)));
+    snapshots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn saves_and_restores_project_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("lunu.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        std::fs::write(root.join("lunu.lock"), "# before update\n").unwrap();
+        let snapshot = Snapshot {
+            name: "before-update".to_string(),
+            created_at: 10,
+            lunu: "0.1.0".to_string(),
+            runtime: "lute".to_string(),
+            runtime_version: Some("0.1.0".to_string()),
+            modules: BTreeMap::from([("json".to_string(), "abc".to_string())]),
+        };
+        save(root, &snapshot).unwrap();
+
+        std::fs::write(root.join("lunu.lock"), "# after update\n").unwrap();
+        std::fs::write(root.join(".luaurc"), "{}").unwrap();
+        assert_eq!(load(root, "before-update").unwrap(), snapshot);
+        restore_files(root, "before-update").unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("lunu.lock")).unwrap(), "# before update\n");
+        assert!(!root.join(".luaurc").exists());
+
+        assert_eq!(list(root).len(), 1);
+        assert!(load(root, "missing").unwrap_err().to_string().contains("snapshots: before-update"));
+        assert!(validate_name("../escape").is_err() && validate_name(".hidden").is_err());
+    }
+}
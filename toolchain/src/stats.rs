@@ -0,0 +1,183 @@
+//! `lunu stats`: a local record of which commands ran and how long they took. Recording is off
+//! until `lunu stats --enable`; nothing is ever sent anywhere.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invocation {
+    pub command: String,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// Hash of the project root, so projects can be counted without storing their paths
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CommandSummary {
+    pub count: usize,
+    pub failures: usize,
+    pub avg_ms: u64,
+}
+
+pub fn dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".lunu").join("stats"))
+}
+
+fn enabled_marker(dir: &Path) -> PathBuf {
+    dir.join("enabled")
+}
+
+fn history_path(dir: &Path) -> PathBuf {
+    dir.join("history.jsonl")
+}
+
+pub fn is_enabled(dir: &Path) -> bool {
+    enabled_marker(dir).exists()
+}
+
+pub fn set_enabled(dir: &Path, enabled: bool) -> Result<()> {
+    let marker = enabled_marker(dir);
+    if enabled {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&marker, "")?;
+    } else if marker.exists() {
+        std::fs::remove_file(&marker)?;
+    }
+    Ok(())
+}
+
+pub fn clear(dir: &Path) -> Result<()> {
+    let path = history_path(dir);
+    if path.exists() {
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+    }
+    Ok(())
+}
+
+pub fn project_id(root: &Path) -> String {
+    let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+    hex::encode(&digest[..8])
+}
+
+/// Appends `invocation` when recording is enabled.
+pub fn record(dir: &Path, invocation: &Invocation) -> Result<()> {
+    if !is_enabled(dir) {
+        return Ok(());
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(history_path(dir))?;
+    writeln!(file, "{}", serde_json::to_string(invocation)?)?;
+    Ok(())
+}
+
+/// Recorded invocations; lines that do not parse (e.g. from a crash mid-write) are skipped.
+pub fn load(dir: &Path) -> Vec<Invocation> {
+    std::fs::read_to_string(history_path(dir))
+        .map(|content| content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+        .unwrap_or_default()
+}
+
+pub fn summarize(history: &[Invocation]) -> BTreeMap<String, CommandSummary> {
+    let mut totals: BTreeMap<String, (CommandSummary, u64)> = BTreeMap::new();
+    for inv in history {
+        let (summary, total_ms) = totals.entry(inv.command.clone()).or_default();
+        summary.count += 1;
+        if !inv.success {
+            summary.failures += 1;
+        }
+        *total_ms += inv.duration_ms;
+    }
+    totals
+        .into_iter()
+        .map(|(command, (mut summary, total_ms))| {
+            summary.avg_ms = total_ms / summary.count as u64;
+            (command, summary)
+        })
+        .collect()
+}
+
+pub fn projects_seen(history: &[Invocation]) -> usize {
+    history.iter().filter_map(|inv| inv.project.as_ref()).collect::<BTreeSet<_>>().len()
+}
+
+/// Total size of the files under `path` (0 when it does not exist).
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn invocation(command: &str, duration_ms: u64, success: bool, project: Option<&str>) -> Invocation {
+        Invocation {
+            command: command.to_string(),
+            started_at: 1,
+            duration_ms,
+            success,
+            project: project.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn records_only_when_enabled_and_summarizes() {
+        let dir = tempdir().unwrap();
+        record(dir.path(), &invocation("build", 100, true, Some("a"))).unwrap();
+        assert!(load(dir.path()).is_empty());
+
+        set_enabled(dir.path(), true).unwrap();
+        record(dir.path(), &invocation("build", 100, true, Some("a"))).unwrap();
+        record(dir.path(), &invocation("build", 300, false, Some("b"))).unwrap();
+        record(dir.path(), &invocation("upgrade", 50, true, None)).unwrap();
+        let history = load(dir.path());
+        assert_eq!(history.len(), 3);
+        assert_eq!(projects_seen(&history), 2);
+
+        let summary = summarize(&history);
+        assert_eq!(summary["build"], CommandSummary { count: 2, failures: 1, avg_ms: 200 });
+        assert_eq!(summary["upgrade"].count, 1);
+
+        clear(dir.path()).unwrap();
+        assert!(load(dir.path()).is_empty());
+        assert!(is_enabled(dir.path()));
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
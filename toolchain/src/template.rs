@@ -0,0 +1,392 @@
+//! Project templates. `lunu template export` copies a project into a directory with a
+//! `template.toml` manifest, replacing the values that differ between projects (the name, ports in
+//! config/settings.json, the author) with `{{param}}` placeholders; `lunu scaffold --from` renders
+//! such a directory, or a Git repository holding one, into a new project.
+//!
+//! ```toml
+//! [template]
+//! name = "acme-service"
+//! description = "HTTP service with the team's bridge modules"
+//!
+//! [params.name]
+//! description = "Project name"
+//!
+//! [params.http_port]
+//! description = "server.http_port in config/settings.json"
+//! default = "8080"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::{Context, Result};
+use lunu_builder::exclude::ExcludeSet;
+use lunu_cli::secret_store;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const MANIFEST: &str = "template.toml";
+
+/// Local state, build output and secrets that never belong in a template.
+const EXCLUDES: &[&str] = &[
+    ".git",
+    ".lunu",
+    "dist",
+    "logs",
+    "bin",
+    ".venv",
+    "venv",
+    "node_modules",
+    "target",
+    "__pycache__",
+    "*.pyc",
+    ".env",
+    ".env.*",
+    "*.key",
+    ".DS_Store",
+    "Thumbs.db",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TemplateManifest {
+    pub template: TemplateInfo,
+    #[serde(default)]
+    pub params: BTreeMap<String, TemplateParam>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TemplateInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TemplateParam {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Used when `lunu scaffold --from` gets no `--set` for the parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// What `export` parameterizes besides the project name.
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    pub author: Option<String>,
+    /// Relative paths left out, such as the modules of git dependencies (`lunu install` restores them)
+    pub skip: Vec<String>,
+}
+
+impl TemplateManifest {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST);
+        let content = std::fs::read_to_string(&path).with_context(|| format!("{:?} is not a Lunu template (no {})", dir, MANIFEST))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {:?}", path))
+    }
+}
+
+/// Copies the project at `root` into `out` as a template and writes its manifest.
+pub fn export(root: &Path, out: &Path, info: TemplateInfo, project_name: &str, options: &ExportOptions) -> Result<TemplateManifest> {
+    if out.join(MANIFEST).exists() {
+        // Re-export over an earlier one, keeping its repository so pushes stay incremental
+        for entry in std::fs::read_dir(out)?.flatten().filter(|e| e.file_name() != ".git") {
+            if entry.path().is_dir() {
+                std::fs::remove_dir_all(entry.path())?;
+            } else {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+    } else if out.exists() && std::fs::read_dir(out)?.next().is_some() {
+        return Err(anyhow::anyhow!("{:?} already exists and is not a template; choose another --out", out));
+    }
+    let excludes: Vec<String> = EXCLUDES.iter().map(|p| p.to_string()).collect();
+    let mut skip = options.skip.clone();
+    if let Ok(relative) = out.strip_prefix(root) {
+        skip.push(relative.to_string_lossy().to_string());
+    }
+    let exclude = ExcludeSet::new(false, &excludes, &[])
+        .with_paths(&secret_store::PRIVATE_PATHS.map(str::to_string))
+        .with_paths(&skip);
+
+    let mut params = BTreeMap::new();
+    params.insert("name".to_string(), TemplateParam { description: Some("Project name".to_string()), default: None });
+    let ports = settings_ports(root);
+    for (key, port) in &ports {
+        let description = format!("{} in config/settings.json", key);
+        params.insert(param_name(key), TemplateParam { description: Some(description), default: Some(port.to_string()) });
+    }
+    let author = options.author.as_deref().filter(|a| !a.trim().is_empty());
+    let mut author_used = false;
+
+    for (relative, path) in files(root, &exclude)? {
+        let target = out.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let mut text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(err) => {
+                std::fs::write(&target, err.into_bytes())?;
+                continue;
+            }
+        };
+        if relative == "lunu.toml" {
+            text = replace_project_name(&text, project_name);
+        } else if names_project(&relative) {
+            text = replace_word(&text, project_name, "{{name}}");
+        }
+        if relative == "config/settings.json" {
+            for (key, port) in &ports {
+                text = replace_json_number(&text, key, *port, &format!("{{{{{}}}}}", param_name(key)));
+            }
+        }
+        if let Some(author) = author.filter(|_| relative == "lunu.toml" || names_project(&relative)) {
+            let replaced = replace_word(&text, author, "{{author}}");
+            author_used |= replaced != text;
+            text = replaced;
+        }
+        std::fs::write(&target, text)?;
+    }
+    if let (Some(author), true) = (author, author_used) {
+        params.insert("author".to_string(), TemplateParam { description: Some("Author".to_string()), default: Some(author.to_string()) });
+    }
+
+    let manifest = TemplateManifest { template: info, params };
+    std::fs::write(out.join(MANIFEST), toml::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+/// Renders the template in `src` into `dst`. `values` must cover every parameter without a default.
+pub fn render(src: &Path, dst: &Path, values: &BTreeMap<String, String>) -> Result<()> {
+    let manifest = TemplateManifest::load(src)?;
+    let mut resolved = BTreeMap::new();
+    for (name, param) in &manifest.params {
+        let value = values
+            .get(name)
+            .or(param.default.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("Template parameter '{}' has no default; pass --set {}=<value>", name, name))?;
+        resolved.insert(name.clone(), value.clone());
+    }
+    if let Some(unknown) = values.keys().find(|k| !manifest.params.contains_key(*k)) {
+        let known: Vec<&str> = manifest.params.keys().map(String::as_str).collect();
+        return Err(anyhow::anyhow!("Template '{}' has no parameter '{}' (parameters: {})", manifest.template.name, unknown, known.join(", ")));
+    }
+
+    let exclude = ExcludeSet::new(false, &[".git".to_string()], &[]).with_paths(&[MANIFEST.to_string()]);
+    for (relative, path) in files(src, &exclude)? {
+        let target = dst.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        match String::from_utf8(bytes) {
+            Ok(mut text) => {
+                for (name, value) in &resolved {
+                    text = text.replace(&format!("{{{{{}}}}}", name), value);
+                }
+                std::fs::write(&target, text)?;
+            }
+            Err(err) => std::fs::write(&target, err.into_bytes())?,
+        }
+    }
+    Ok(())
+}
+
+/// Files under `dir` that `exclude` keeps, as (forward-slash relative path, path), sorted.
+fn files(dir: &Path, exclude: &ExcludeSet) -> Result<Vec<(String, std::path::PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)?.flatten() {
+            let path = entry.path();
+            let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if exclude.is_excluded(&relative) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push((relative, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Integer settings whose key ends in `port`, as dotted paths (`server.http_port`).
+fn settings_ports(root: &Path) -> Vec<(String, u64)> {
+    fn walk(value: &Value, prefix: &str, ports: &mut Vec<(String, u64)>) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        for (key, value) in map {
+            let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+            match value.as_u64() {
+                Some(port) if key.ends_with("port") => ports.push((path, port)),
+                _ => walk(value, &path, ports),
+            }
+        }
+    }
+    let mut ports = Vec::new();
+    let settings = std::fs::read_to_string(root.join("config").join("settings.json")).ok();
+    if let Some(value) = settings.and_then(|s| serde_json::from_str::<Value>(&s).ok()) {
+        walk(&value, "", &mut ports);
+    }
+    ports
+}
+
+/// `server.http_port` is the `http_port` parameter.
+fn param_name(key: &str) -> String {
+    key.rsplit('.').next().unwrap_or(key).to_string()
+}
+
+/// Replaces the number after `"<last key segment>":` when it equals `value`.
+fn replace_json_number(text: &str, key: &str, value: u64, with: &str) -> String {
+    let needle = format!("\"{}\"", param_name(key));
+    let value = value.to_string();
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(&needle) {
+        let (before, after) = rest.split_at(index + needle.len());
+        out.push_str(before);
+        let trimmed = after.trim_start();
+        let Some(after_colon) = trimmed.strip_prefix(':') else {
+            rest = after;
+            continue;
+        };
+        let number = after_colon.trim_start();
+        let separator = &after[..after.len() - number.len()];
+        let ends = !number[value.len().min(number.len())..].starts_with(|c: char| c.is_ascii_digit());
+        if number.starts_with(&value) && ends {
+            out.push_str(separator);
+            out.push_str(with);
+            rest = &number[value.len()..];
+        } else {
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// READMEs and the files under config/, where the project name and author are metadata. Sources
+/// are left alone: a name such as `json` or `server` is as likely to be one of their identifiers.
+fn names_project(relative: &str) -> bool {
+    let file = relative.rsplit('/').next().unwrap_or(relative);
+    relative.starts_with("config/") || file.to_ascii_lowercase().starts_with("readme")
+}
+
+/// Replaces the `name` of lunu.toml's `[project]` table, and nothing else in it.
+fn replace_project_name(text: &str, name: &str) -> String {
+    let mut table = String::new();
+    let lines: Vec<String> = text
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                table = trimmed.trim_matches(|c| c == '[' || c == ']').trim().to_string();
+            } else if table == "project" && trimmed.split('=').next().is_some_and(|key| key.trim() == "name") {
+                return replace_word(line, name, "{{name}}");
+            }
+            line.to_string()
+        })
+        .collect();
+    lines.concat()
+}
+
+/// Replaces `word` where it is not part of a longer name (`acme` but not `acme_db` or `acme-docs`).
+fn replace_word(text: &str, word: &str, with: &str) -> String {
+    if word.is_empty() {
+        return text.to_string();
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(word) {
+        out.push_str(&rest[..index]);
+        let after = &rest[index + word.len()..];
+        let bounded = !out.chars().next_back().is_some_and(is_ident) && !after.chars().next().is_some_and(is_ident);
+        out.push_str(if bounded { with } else { word });
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn exported_project_renders_with_new_values() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        let write = |relative: &str, content: &str| {
+            let path = root.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("lunu.toml", "[project]\nname = \"acme-svc\"\nentry = \"src/main.luau\"\n");
+        write("config/settings.json", "{\n  \"server\": { \"host\": \"127.0.0.1\", \"http_port\": 8080 },\n  \"retries\": 8080\n}\n");
+        write("README.md", "# acme-svc\n\nMaintained by Jane Doe. See acme-svc-docs.\n");
+        write("src/main.luau", "print(\"acme-svc\")\n");
+        write("modules/json/init.luau", "return {}\n");
+        write("modules/util/init.luau", "return {}\n");
+        write(".lunu/update.key", "secret");
+        write("config/.secrets.json", "{\"api_key\":\"secret\"}");
+        write("logs/bridge.log", "log");
+
+        let out = root.join("dist").join("template");
+        let info = TemplateInfo { name: "acme".to_string(), description: None };
+        let options = ExportOptions { author: Some("Jane Doe".to_string()), skip: vec!["modules/json".to_string()] };
+        let manifest = export(root, &out, info, "acme-svc", &options).unwrap();
+        assert_eq!(manifest.params.keys().collect::<Vec<_>>(), ["author", "http_port", "name"]);
+        assert_eq!(manifest.params["http_port"].default.as_deref(), Some("8080"));
+        assert!(!out.join(".lunu").exists() && !out.join("logs").exists() && !out.join("modules/json").exists());
+        assert!(!out.join("config/.secrets.json").exists());
+        assert!(out.join("modules/util/init.luau").exists());
+        let settings = std::fs::read_to_string(out.join("config/settings.json")).unwrap();
+        assert!(settings.contains("\"http_port\": {{http_port}}") && settings.contains("\"retries\": 8080"));
+        let readme = std::fs::read_to_string(out.join("README.md")).unwrap();
+        assert_eq!(readme, "# {{name}}\n\nMaintained by {{author}}. See acme-svc-docs.\n");
+
+        let target = tempdir().unwrap();
+        let values = BTreeMap::from([("name".to_string(), "billing".to_string()), ("http_port".to_string(), "9090".to_string())]);
+        render(&out, target.path(), &values).unwrap();
+        let toml = std::fs::read_to_string(target.path().join("lunu.toml")).unwrap();
+        assert!(toml.contains("name = \"billing\""));
+        let settings: Value = serde_json::from_str(&std::fs::read_to_string(target.path().join("config/settings.json")).unwrap()).unwrap();
+        assert_eq!(settings["server"]["http_port"], 9090);
+        assert!(std::fs::read_to_string(target.path().join("README.md")).unwrap().contains("Maintained by Jane Doe"));
+        assert!(!target.path().join(MANIFEST).exists());
+
+        let unknown = BTreeMap::from([("name".to_string(), "x".to_string()), ("colour".to_string(), "red".to_string())]);
+        assert!(render(&out, tempdir().unwrap().path(), &unknown).is_err());
+        assert!(render(&out, tempdir().unwrap().path(), &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn project_name_is_only_replaced_in_metadata() {
+        let project = tempdir().unwrap();
+        let root = project.path();
+        let files = [
+            ("lunu.toml", "[project]\nname = \"json\"\nentry = \"src/main.luau\"\n\n[dependencies]\njson = { path = \"modules/json\" }\n"),
+            ("README.md", "# json\n"),
+            ("src/main.luau", "local json = require(\"@json\")\nprint(json.encode({}))\n"),
+        ];
+        for (relative, content) in files {
+            std::fs::create_dir_all(root.join(relative).parent().unwrap()).unwrap();
+            std::fs::write(root.join(relative), content).unwrap();
+        }
+        let out = root.join("dist").join("template");
+        let info = TemplateInfo { name: "json".to_string(), description: None };
+        export(root, &out, info, "json", &ExportOptions { author: None, skip: Vec::new() }).unwrap();
+        let read = |relative: &str| std::fs::read_to_string(out.join(relative)).unwrap();
+        assert_eq!(read("lunu.toml"), files[0].1.replacen("\"json\"", "\"{{name}}\"", 1));
+        assert_eq!(read("README.md"), "# {{name}}\n");
+        assert_eq!(read("src/main.luau"), files[2].1);
+    }
+}
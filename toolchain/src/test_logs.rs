@@ -0,0 +1,92 @@
+//! Output capture for `lunu test`. Each test file's stdout and stderr go, interleaved as they were
+//! written, to `.lunu/test-logs/<file>.log` (the test file's path under the project root), whether
+//! the file passes or not. The log is rewritten on every run, so diagnostics of passing tests can
+//! be read after the run; `--show-output` also streams the output to the terminal as it comes.
+
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
+
+pub const DIR: &str = ".lunu/test-logs";
+
+/// Log of `file`, e.g. `.lunu/test-logs/tests/json.test.luau.log` for `tests/json.test.luau`.
+pub fn log_path(root: &Path, file: &Path) -> PathBuf {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    let name = relative.file_name().unwrap_or_default().to_string_lossy();
+    let mut path = root.join(DIR);
+    // Files outside the project are logged by name only
+    if let Some(parent) = relative.parent().filter(|p| p.components().all(|c| matches!(c, Component::Normal(_)))) {
+        path.push(parent);
+    }
+    path.join(format!("{}.log", name))
+}
+
+/// Runs `command` with its output written to `log` (and echoed when `show_output`). Returns whether
+/// it succeeded and the output.
+pub fn run(command: &mut Command, log: &Path, show_output: bool) -> Result<(bool, Vec<u8>)> {
+    if let Some(parent) = log.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(log).with_context(|| format!("Failed to create {:?}", log))?;
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let output = Arc::new(Mutex::new((file, Vec::new())));
+    let copy = |mut stream: Box<dyn Read + Send>, stderr: bool| {
+        let output = output.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = stream.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+                let _ = output.0.write_all(&buf[..n]);
+                output.1.extend_from_slice(&buf[..n]);
+                if show_output {
+                    let _ = if stderr { std::io::stderr().write_all(&buf[..n]) } else { std::io::stdout().write_all(&buf[..n]) };
+                }
+            }
+        })
+    };
+    let readers = [
+        child.stdout.take().map(|s| copy(Box::new(s), false)),
+        child.stderr.take().map(|s| copy(Box::new(s), true)),
+    ];
+    let status = child.wait()?;
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+    let output = std::mem::take(&mut output.lock().unwrap_or_else(|e| e.into_inner()).1);
+    Ok((status.success(), output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_are_named_after_the_test_file() {
+        let root = Path::new("/work/app");
+        assert_eq!(log_path(root, &root.join("tests/json.test.luau")), root.join(".lunu/test-logs/tests/json.test.luau.log"));
+        assert_eq!(log_path(root, Path::new("main.spec.luau")), root.join(".lunu/test-logs/main.spec.luau.log"));
+        assert_eq!(log_path(root, Path::new("/elsewhere/x.test.luau")), root.join(".lunu/test-logs/x.test.luau.log"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn captures_output_of_passing_and_failing_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = log_path(dir.path(), &dir.path().join("tests/a.test.luau"));
+        let (ok, output) = run(Command::new("sh").args(["-c", "echo checked 3 cases; echo slow path >&2"]), &log, false).unwrap();
+        assert!(ok);
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.contains("checked 3 cases\n") && text.contains("slow path\n"));
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), text);
+
+        let (ok, output) = run(Command::new("sh").args(["-c", "echo boom >&2; exit 1"]), &log, false).unwrap();
+        assert!(!ok);
+        assert_eq!(output, b"boom\n");
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "boom\n");
+    }
+}
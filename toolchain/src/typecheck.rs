@@ -0,0 +1,141 @@
+//! `lunu typecheck`: Luau type checking with the checker that matches the project's runtime.
+//! Lute projects use `lute check`; Lune projects use `luau-analyze` from the Luau release named by
+//! `[typecheck] luau` in lunu.toml (the newest one otherwise), cached under the runtime cache.
+//! Both print diagnostics the way Luau's analysis does:
+//!
+//! ```text
+//! src/main.luau(12,7): TypeError: Type 'string' could not be converted into 'number'
+//! src/util.luau(3,1): LocalUnused: Variable 'x' is never used; prefix with '_' to silence
+//! ```
+//!
+//! which `parse` turns into diagnostics with the code (`TypeError`, `SyntaxError` or a lint name)
+//! kept, so the results of either checker are reported the same way.
+
+use std::path::Path;
+use serde::Serialize;
+use crate::findings::Severity;
+
+/// Executable inside a Luau release archive.
+pub fn analyzer_filename(windows: bool) -> &'static str {
+    if windows {
+        "luau-analyze.exe"
+    } else {
+        "luau-analyze"
+    }
+}
+
+/// Luau release asset holding `luau-analyze` for `os` (`windows`, `macos` or `linux`).
+pub fn release_asset(os: &str) -> Option<&'static str> {
+    match os {
+        "windows" => Some("luau-windows.zip"),
+        "macos" => Some("luau-macos.zip"),
+        "linux" => Some("luau-ubuntu.zip"),
+        _ => None,
+    }
+}
+
+/// The `luau-analyze` binary from a Luau release zip.
+pub fn extract_analyzer(bytes: &[u8], windows: bool) -> anyhow::Result<Vec<u8>> {
+    use std::io::Read;
+    let wanted = analyzer_filename(windows);
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.name().rsplit('/').next() == Some(wanted) {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            return Ok(content);
+        }
+    }
+    Err(anyhow::anyhow!("The Luau release archive has no {}", wanted))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// Path relative to the project root, `/`-separated
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    /// `TypeError`, `SyntaxError` or the name of a lint such as `LocalUnused`
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// `src/main.luau:12:7: error TypeError: ...`
+    pub fn render(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        format!("{}:{}:{}: {} {}: {}", self.file, self.line, self.column, severity, self.code, self.message)
+    }
+}
+
+fn severity_of(code: &str) -> Severity {
+    match code {
+        "TypeError" | "SyntaxError" => Severity::Error,
+        _ => Severity::Warning,
+    }
+}
+
+/// Diagnostics in checker output, plus the lines that are not diagnostics (progress messages,
+/// crashes). Paths are made relative to `root` when they are under it.
+pub fn parse(output: &str, root: &Path) -> (Vec<Diagnostic>, Vec<String>) {
+    let mut diagnostics = Vec::new();
+    let mut other = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        match parse_line(line, root) {
+            Some(diagnostic) => diagnostics.push(diagnostic),
+            None => other.push(line.to_string()),
+        }
+    }
+    (diagnostics, other)
+}
+
+/// `path(line,col): Code: message`
+fn parse_line(line: &str, root: &Path) -> Option<Diagnostic> {
+    let (location, rest) = line.split_once("): ")?;
+    let (file, position) = location.rsplit_once('(')?;
+    let (line_no, column) = position.split_once(',')?;
+    let (code, message) = rest.split_once(": ")?;
+    if file.is_empty() || code.contains(' ') {
+        return None;
+    }
+    let path = Path::new(file.trim());
+    let file = path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+    Some(Diagnostic {
+        file,
+        line: line_no.trim().parse().ok()?,
+        column: column.trim().parse().ok()?,
+        code: code.to_string(),
+        severity: severity_of(code),
+        message: message.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_luau_diagnostics() {
+        let output = "\
+src/main.luau(12,7): TypeError: Type 'string' could not be converted into 'number'
+/work/app/src/util.luau(3,1): LocalUnused: Variable 'x' is never used; prefix with '_' to silence
+Checked 2 files
+";
+        let (diagnostics, other) = parse(output, Path::new("/work/app"));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code, "TypeError");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[1].file, "src/util.luau");
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].render(), "src/util.luau:3:1: warning LocalUnused: Variable 'x' is never used; prefix with '_' to silence");
+        assert_eq!(other, vec!["Checked 2 files"]);
+
+        assert_eq!(release_asset("linux"), Some("luau-ubuntu.zip"));
+        assert_eq!(release_asset("freebsd"), None);
+    }
+}
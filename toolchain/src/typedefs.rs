@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const VSCODE_SETTINGS: &str = ".vscode/settings.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypesRuntime {
+    Lune,
+    Lute,
+}
+
+/// Records which runtime version the files under `.lunu/types/` were taken from.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TypesManifest {
+    pub runtime: String,
+    pub version: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+pub fn types_dir(root: &Path) -> PathBuf {
+    root.join(".lunu").join("types")
+}
+
+fn manifest_path(root: &Path) -> PathBuf {
+    types_dir(root).join("manifest.json")
+}
+
+pub fn read_manifest(root: &Path) -> Option<TypesManifest> {
+    let content = std::fs::read_to_string(manifest_path(root)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Git refs to try for a runtime version, most specific first.
+pub fn tag_candidates(version: &str) -> Vec<String> {
+    let version = version.trim();
+    if version.is_empty() {
+        return Vec::new();
+    }
+    let bare = version.trim_start_matches('v');
+    let mut tags = vec![format!("v{}", bare), bare.to_string()];
+    tags.dedup();
+    tags
+}
+
+/// Maps a path inside the runtime's source archive (without the top-level folder) to its
+/// destination under `.lunu/types/`, or `None` when the file is not a type definition.
+pub fn map_archive_path(runtime: TypesRuntime, relative: &str) -> Option<String> {
+    if !relative.ends_with(".luau") {
+        return None;
+    }
+    match runtime {
+        TypesRuntime::Lune => {
+            // 0.8+: crates/lune-std-<lib>/types.d.luau; older releases: types/<lib>.luau
+            if let Some(rest) = relative.strip_prefix("crates/lune-std-") {
+                let (lib, file) = rest.split_once('/')?;
+                return (file == "types.d.luau").then(|| format!("lune/{}.luau", lib));
+            }
+            let name = relative.strip_prefix("types/")?;
+            (!name.contains('/')).then(|| format!("lune/{}", name.replace(".d.luau", ".luau")))
+        }
+        TypesRuntime::Lute => {
+            if let Some(name) = relative.strip_prefix("definitions/") {
+                return Some(format!("lute/{}", name));
+            }
+            if let Some(rest) = relative.strip_prefix("lute/std/libs/") {
+                return Some(format!("std/{}", rest));
+            }
+            relative.strip_prefix("std/").map(|rest| format!("std/{}", rest))
+        }
+    }
+}
+
+/// Extracts the type definition files from a GitHub source zip into `.lunu/types/`.
+pub fn install_from_zip(root: &Path, runtime: TypesRuntime, version: &str, bytes: &[u8]) -> Result<TypesManifest> {
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut files: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        let Some((_, relative)) = name.split_once('/') else { continue };
+        let Some(dest) = map_archive_path(runtime, relative) else { continue };
+        if dest.split('/').any(|part| part == "..") {
+            continue;
+        }
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut content)?;
+        files.insert(dest, content);
+    }
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No type definitions found in the {} sources", runtime_label(runtime)));
+    }
+
+    let dir = types_dir(root);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to clear {:?}", dir))?;
+    }
+    for (dest, content) in &files {
+        let out_path = dir.join(dest);
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&out_path, content)?;
+    }
+    let manifest = TypesManifest {
+        runtime: runtime_label(runtime).to_string(),
+        version: version.to_string(),
+        files: files.into_keys().collect(),
+    };
+    std::fs::write(manifest_path(root), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+fn runtime_label(runtime: TypesRuntime) -> &'static str {
+    match runtime {
+        TypesRuntime::Lune => "lune",
+        TypesRuntime::Lute => "lute",
+    }
+}
+
+/// Points luau-lsp at the installed definitions: `@lune/`, `@lute/` and `@std/` directory
+/// aliases plus any global definition files, next to the user's own. Other settings, and the
+/// comments of the file (VS Code reads it as JSONC), are preserved; a file that does not parse is
+/// an error rather than being overwritten.
+pub fn write_lsp_settings(root: &Path, manifest: &TypesManifest) -> Result<()> {
+    let path = root.join(VSCODE_SETTINGS);
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => Some(source),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {:?}", path)),
+    };
+    let mut settings: Map<String, Value> = match &source {
+        Some(source) => serde_json::from_str(&crate::jsonc::strip(source))
+            .with_context(|| format!("Failed to parse {:?}; fix it so luau-lsp settings can be added", path))?,
+        None => Map::new(),
+    };
+
+    let mut aliases = settings
+        .get("luau-lsp.require.directoryAliases")
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+    for alias in ["@lune/", "@lute/", "@std/"] {
+        aliases.remove(alias);
+    }
+    // Definition files of earlier installs are replaced; the user's own stay first
+    let mut definition_files: Vec<Value> = settings
+        .get("luau-lsp.types.definitionFiles")
+        .and_then(|v| v.as_array())
+        .map(|files| files.iter().filter(|f| !f.as_str().is_some_and(|f| f.starts_with(".lunu/types/"))).cloned().collect())
+        .unwrap_or_default();
+    for file in &manifest.files {
+        let Some((dir, name)) = file.split_once('/') else { continue };
+        aliases.insert(format!("@{}/", dir), Value::String(format!(".lunu/types/{}/", dir)));
+        if name.starts_with("globals") {
+            definition_files.push(Value::String(format!(".lunu/types/{}", file)));
+        }
+    }
+    let mut updates = vec![
+        ("luau-lsp.require.directoryAliases", Value::Object(aliases)),
+        ("luau-lsp.types.definitionFiles", Value::Array(definition_files)),
+    ];
+    if !settings.contains_key("luau-lsp.require.mode") {
+        updates.push(("luau-lsp.require.mode", Value::String("relativeToFile".to_string())));
+    }
+
+    let patched = source.as_deref().and_then(|source| {
+        updates.iter().try_fold(source.to_string(), |text, (key, value)| {
+            let rendered = crate::jsonc::render(value, &text);
+            crate::jsonc::set_member(&text, &[], key, &rendered)
+        })
+    });
+    let content = match patched {
+        Some(content) => content,
+        None => {
+            settings.extend(updates.into_iter().map(|(key, value)| (key.to_string(), value)));
+            serde_json::to_string_pretty(&Value::Object(settings))?
+        }
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn maps_lune_and_lute_layouts() {
+        assert_eq!(map_archive_path(TypesRuntime::Lune, "crates/lune-std-fs/types.d.luau").as_deref(), Some("lune/fs.luau"));
+        assert_eq!(map_archive_path(TypesRuntime::Lune, "crates/lune-std-fs/src/lib.rs"), None);
+        assert_eq!(map_archive_path(TypesRuntime::Lune, "types/net.luau").as_deref(), Some("lune/net.luau"));
+        assert_eq!(map_archive_path(TypesRuntime::Lute, "definitions/fs.luau").as_deref(), Some("lute/fs.luau"));
+        assert_eq!(map_archive_path(TypesRuntime::Lute, "lute/std/libs/path/init.luau").as_deref(), Some("std/path/init.luau"));
+        assert_eq!(map_archive_path(TypesRuntime::Lute, "tests/fs.luau"), None);
+    }
+
+    #[test]
+    fn tag_candidates_prefer_v_prefix() {
+        assert_eq!(tag_candidates("0.8.9"), vec!["v0.8.9", "0.8.9"]);
+        assert_eq!(tag_candidates("v0.1.0"), vec!["v0.1.0", "0.1.0"]);
+        assert!(tag_candidates("").is_empty());
+    }
+
+    #[test]
+    fn installs_types_and_merges_lsp_settings() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let mut buf = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+            let opts = zip::write::FileOptions::default();
+            zip.start_file("lune-0.8.9/crates/lune-std-fs/types.d.luau", opts).unwrap();
+            zip.write_all(b"export type Fs = {}").unwrap();
+            zip.start_file("lune-0.8.9/README.md", opts).unwrap();
+            zip.write_all(b"readme").unwrap();
+            zip.finish().unwrap();
+        }
+        std::fs::create_dir_all(root.join(".vscode")).unwrap();
+        std::fs::write(
+            root.join(VSCODE_SETTINGS),
+            "{\n  // team settings\n  \"editor.tabSize\": 4,\n  \"luau-lsp.types.definitionFiles\": [\"types/roblox.d.luau\", \".lunu/types/lune/globals.d.luau\"],\n}\n",
+        )
+        .unwrap();
+
+        let manifest = install_from_zip(root, TypesRuntime::Lune, "0.8.9", &buf).unwrap();
+        assert_eq!(manifest.files, vec!["lune/fs.luau"]);
+        assert!(types_dir(root).join("lune").join("fs.luau").exists());
+        assert_eq!(read_manifest(root), Some(manifest));
+
+        write_lsp_settings(root, &read_manifest(root).unwrap()).unwrap();
+        let text = std::fs::read_to_string(root.join(VSCODE_SETTINGS)).unwrap();
+        assert!(text.contains("// team settings"));
+        let settings: Value = serde_json::from_str(&crate::jsonc::strip(&text)).unwrap();
+        assert_eq!(settings["editor.tabSize"], 4);
+        assert_eq!(settings["luau-lsp.require.directoryAliases"]["@lune/"], ".lunu/types/lune/");
+        assert_eq!(settings["luau-lsp.types.definitionFiles"], serde_json::json!(["types/roblox.d.luau"]));
+        assert_eq!(settings["luau-lsp.require.mode"], "relativeToFile");
+
+        std::fs::write(root.join(VSCODE_SETTINGS), "{ \"editor.tabSize\": ").unwrap();
+        assert!(write_lsp_settings(root, &read_manifest(root).unwrap()).is_err());
+        assert_eq!(std::fs::read_to_string(root.join(VSCODE_SETTINGS)).unwrap(), "{ \"editor.tabSize\": ");
+    }
+}
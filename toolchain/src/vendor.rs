@@ -0,0 +1,80 @@
+//! `lunu vendor`: copies every resolved dependency into `vendor/`, and for Lute projects the
+//! runtime's `@lute` and `@std` library sources too. It then points the .luaurc aliases at the
+//! copies and sets `vendored = true` under `[project]`:
+//!
+//! ```text
+//! vendor/json/            <- modules/json/
+//! vendor/@lab/ml/         <- modules/@lab/ml/
+//! vendor/_runtime/lute/   <- the Lute runtime's @lute library
+//! vendor/_runtime/std/    <- the Lute runtime's @std library
+//! ```
+//!
+//! `lunu install` on a vendored project checks the copies against lunu.lock instead of installing,
+//! so a checkout runs without network access. Path dependencies inside the project are used where
+//! they are and not copied.
+
+use std::collections::BTreeMap;
+
+pub const DIR: &str = "vendor";
+pub const RUNTIME_DIR: &str = "_runtime";
+
+/// Where dependency `name` is vendored, relative to the project root.
+pub fn dependency_path(name: &str) -> String {
+    format!("{}/{}", DIR, name)
+}
+
+/// Where the runtime library behind `alias` (`lute` or `std`) is vendored, relative to the root.
+pub fn runtime_path(alias: &str) -> String {
+    format!("{}/{}/{}", DIR, RUNTIME_DIR, alias)
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").trim_end_matches('/').to_string()
+}
+
+/// The aliases that change when the directories in `moves` (old, new; relative to the project
+/// root) are vendored: an alias pointing at a moved directory, or into one, follows it.
+pub fn rewrite_aliases(aliases: &BTreeMap<String, String>, moves: &[(String, String)]) -> BTreeMap<String, String> {
+    let mut changed = BTreeMap::new();
+    for (alias, target) in aliases {
+        let target = normalize(target);
+        for (from, to) in moves {
+            let from = normalize(from);
+            let rest = match target.strip_prefix(&from) {
+                Some("") => "",
+                Some(rest) if rest.starts_with('/') => rest,
+                _ => continue,
+            };
+            changed.insert(alias.clone(), format!("{}{}/", normalize(to), rest));
+            break;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_aliases_at_vendored_copies() {
+        let aliases = BTreeMap::from([
+            ("json".to_string(), "modules/json/".to_string()),
+            ("lab".to_string(), "./modules/@lab/".to_string()),
+            ("types".to_string(), "modules/json/types/".to_string()),
+            ("jsonx".to_string(), "modules/jsonx/".to_string()),
+            ("src".to_string(), "src/".to_string()),
+        ]);
+        let moves = vec![
+            ("modules/json".to_string(), dependency_path("json")),
+            ("modules/@lab".to_string(), dependency_path("@lab")),
+        ];
+        let changed = rewrite_aliases(&aliases, &moves);
+        assert_eq!(changed, BTreeMap::from([
+            ("json".to_string(), "vendor/json/".to_string()),
+            ("lab".to_string(), "vendor/@lab/".to_string()),
+            ("types".to_string(), "vendor/json/types/".to_string()),
+        ]));
+        assert_eq!(runtime_path("std"), "vendor/_runtime/std");
+    }
+}
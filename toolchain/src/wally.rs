@@ -0,0 +1,189 @@
+//! Client for the Wally registry: `wally = "scope/name@1.2.3"` in lunu.toml installs a published
+//! Wally package instead of a vendored copy. As in wally.toml, `1.2.3` means `^1.2.3`. The public
+//! API is used unless `LUNU_WALLY_API` names another one; lunu.lock records the package as
+//! `registry = "wally:scope/name"`.
+
+use std::path::Path;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use semver::{Version, VersionReq};
+use serde_json::Value;
+use crate::http;
+
+pub const DEFAULT_API: &str = "https://api.wally.run";
+pub const PREFIX: &str = "wally:";
+/// Wally client version the API expects in the `Wally-Version` header
+const WALLY_VERSION: &str = "0.3.2";
+
+fn valid_package(package: &str) -> bool {
+    let valid = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    package.split_once('/').is_some_and(|(scope, name)| valid(scope) && valid(name))
+}
+
+/// `scope/name` of a `wally:scope/name` query, or `None` for anything else.
+pub fn parse_package(query: &str) -> Option<String> {
+    let package = query.strip_prefix(PREFIX)?.trim().to_lowercase();
+    valid_package(&package).then_some(package)
+}
+
+/// Splits a lunu.toml `wally = "scope/name@1.2.3"` value into the package and its requirement;
+/// without a version any release matches.
+pub fn parse_spec(spec: &str) -> Result<(String, VersionReq)> {
+    let (package, req) = match spec.trim().split_once('@') {
+        Some((package, req)) => (package, Some(req)),
+        None => (spec.trim(), None),
+    };
+    let package = package.to_lowercase();
+    if !valid_package(&package) {
+        return Err(anyhow::anyhow!("Invalid Wally package '{}'; expected scope/name@version", spec));
+    }
+    let req = match req {
+        Some(req) => VersionReq::parse(req).with_context(|| format!("Invalid version in Wally package '{}'", spec))?,
+        None => VersionReq::STAR,
+    };
+    Ok((package, req))
+}
+
+/// What lunu.lock records as the origin of a Wally package.
+pub fn origin(package: &str) -> String {
+    format!("{}{}", PREFIX, package)
+}
+
+/// Versions listed by the registry's package metadata (`{ "versions": [{ "package": ... }] }`).
+/// Dev-realm packages are skipped; they only exist for tests of their own repository.
+pub fn parse_versions(body: &Value) -> Vec<Version> {
+    let Some(Value::Array(versions)) = body.get("versions") else {
+        return Vec::new();
+    };
+    versions
+        .iter()
+        .filter_map(|entry| entry.get("package"))
+        .filter(|package| package.get("realm").and_then(Value::as_str) != Some("dev"))
+        .filter_map(|package| package.get("version").and_then(Value::as_str))
+        .filter_map(|version| Version::parse(version).ok())
+        .collect()
+}
+
+/// Highest version matching `req`; a `locked` version is kept while it still matches.
+pub fn pick_version(versions: &[Version], req: &VersionReq, locked: Option<&str>) -> Option<Version> {
+    let locked = locked.and_then(|l| Version::parse(l.trim_start_matches(['v', 'V'])).ok());
+    if let Some(locked) = locked.filter(|l| req.matches(l) && versions.contains(l)) {
+        return Some(locked);
+    }
+    versions.iter().filter(|v| req.matches(v)).max().cloned()
+}
+
+pub struct WallyClient {
+    client: Client,
+    api: String,
+}
+
+impl WallyClient {
+    pub fn from_env() -> Result<Self> {
+        let api = std::env::var("LUNU_WALLY_API")
+            .ok()
+            .filter(|a| !a.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_API.to_string());
+        let client = http::client()?;
+        Ok(Self { client, api: api.trim_end_matches('/').to_string() })
+    }
+
+    pub async fn versions(&self, package: &str) -> Result<Vec<Version>> {
+        let url = format!("{}/v1/package-metadata/{}", self.api, package);
+        let body: Value = http::retry(&format!("Wally lookup of {}", package), || async {
+            let res = self.client.get(&url).header("Wally-Version", WALLY_VERSION).send().await?;
+            if res.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(anyhow::anyhow!("Package {} does not exist on the Wally registry", package));
+            }
+            Ok(res.error_for_status()?.json().await?)
+        })
+        .await?;
+        Ok(parse_versions(&body))
+    }
+
+    /// The `.zip` contents of one version.
+    pub async fn download(&self, package: &str, version: &Version) -> Result<Vec<u8>> {
+        let url = format!("{}/v1/package-contents/{}/{}", self.api, package, version);
+        http::retry(&format!("Download {} {}", package, version), || async {
+            let res = self.client.get(&url).header("Wally-Version", WALLY_VERSION).send().await?.error_for_status()?;
+            Ok(res.bytes().await?.to_vec())
+        })
+        .await
+    }
+}
+
+/// Maps a Rojo-style package onto a Lunu module: when the root has no `init.luau`, one
+/// re-exporting `default.project.json`'s `tree.$path` is written. Returns the package's own
+/// dependencies from wally.toml, which are not installed automatically.
+pub fn map_project(dir: &Path) -> Result<Vec<String>> {
+    let has_init = dir.join("init.luau").exists() || dir.join("init.lua").exists();
+    let project = std::fs::read_to_string(dir.join("default.project.json")).ok();
+    let tree_path = project
+        .as_deref()
+        .and_then(|p| serde_json::from_str::<Value>(p).ok())
+        .and_then(|p| p.pointer("/tree/$path").and_then(Value::as_str).map(str::to_string));
+    if let (Some(tree_path), false) = (tree_path, has_init) {
+        let module = tree_path.trim_start_matches("./").trim_end_matches('/').trim_end_matches(".luau").trim_end_matches(".lua");
+        std::fs::write(dir.join("init.luau"), format!("return require(\"./{}\")\n", module))?;
+    }
+
+    let Ok(manifest) = std::fs::read_to_string(dir.join("wally.toml")) else {
+        return Ok(Vec::new());
+    };
+    let manifest: toml::Value = toml::from_str(&manifest).context("Invalid wally.toml")?;
+    let dependencies = ["dependencies", "server-dependencies"]
+        .iter()
+        .filter_map(|table| manifest.get(table).and_then(toml::Value::as_table))
+        .flat_map(|table| table.values().filter_map(toml::Value::as_str).map(str::to_string))
+        .collect();
+    Ok(dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn parses_specs_and_picks_versions() {
+        let (package, req) = parse_spec("Roblox/Roact@1.4.0").unwrap();
+        assert_eq!(package, "roblox/roact");
+        assert!(req.matches(&Version::new(1, 4, 2)) && !req.matches(&Version::new(2, 0, 0)));
+        assert_eq!(parse_spec("roblox/roact").unwrap().1, VersionReq::STAR);
+        assert!(parse_spec("roact@1.0.0").is_err());
+        assert_eq!(parse_package("wally:sleitnick/signal").as_deref(), Some("sleitnick/signal"));
+        assert_eq!(parse_package("pesde:sleitnick/signal"), None);
+
+        let versions = parse_versions(&json!({ "versions": [
+            { "package": { "name": "roblox/roact", "version": "1.4.0", "realm": "shared" } },
+            { "package": { "name": "roblox/roact", "version": "1.4.4", "realm": "shared" } },
+            { "package": { "name": "roblox/roact", "version": "1.5.0", "realm": "dev" } },
+            { "package": { "name": "roblox/roact", "version": "2.0.0", "realm": "shared" } },
+        ]}));
+        assert_eq!(versions.len(), 3);
+        assert_eq!(pick_version(&versions, &req, None), Some(Version::new(1, 4, 4)));
+        assert_eq!(pick_version(&versions, &req, Some("1.4.0")), Some(Version::new(1, 4, 0)));
+        assert_eq!(pick_version(&versions, &req, Some("2.0.0")), Some(Version::new(1, 4, 4)));
+    }
+
+    #[test]
+    fn unpacks_contents_and_maps_the_rojo_tree() {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content) in [
+            ("wally.toml", "[package]\nname = \"acme/signal\"\nversion = \"1.0.0\"\nrealm = \"shared\"\n[dependencies]\nPromise = \"evaera/promise@4.0.0\"\n"),
+            ("default.project.json", "{ \"name\": \"signal\", \"tree\": { \"$path\": \"src\" } }"),
+            ("src/init.lua", "return {}"),
+        ] {
+            zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        let contents = zip.finish().unwrap().into_inner();
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("signal");
+        crate::archive::unpack(&contents, &dest, None).unwrap();
+        assert_eq!(map_project(&dest).unwrap(), vec!["evaera/promise@4.0.0".to_string()]);
+        assert_eq!(std::fs::read_to_string(dest.join("init.luau")).unwrap(), "return require(\"./src\")\n");
+    }
+}
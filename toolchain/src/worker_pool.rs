@@ -0,0 +1,377 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, Context};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::Mutex;
+use crate::secret_store;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const HOST_START_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Registry record written by a `lunu worker-host` process to `.lunu/workers/<module>.json`,
+/// readable only by the current user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegistryEntry {
+    pub module: String,
+    pub pid: u32,
+    pub port: u16,
+    pub fingerprint: String,
+    /// First line a client sends after connecting; the host drops connections without it. Hosts
+    /// started by older versions have none and are replaced.
+    #[serde(default)]
+    pub token: String,
+}
+
+pub fn registry_dir(base_dir: &Path) -> PathBuf {
+    base_dir.join(".lunu").join("workers")
+}
+
+pub fn registry_path(base_dir: &Path, module: &str) -> PathBuf {
+    // Scoped modules (`@scope/name`) get a flat file name
+    registry_dir(base_dir).join(format!("{}.json", module.replace('/', "~")))
+}
+
+/// Hash of the `worker` section of bridge.json; a changed command or env forces a cold start.
+pub fn worker_fingerprint(bridge_json: &str) -> String {
+    let worker = serde_json::from_str::<Value>(bridge_json)
+        .ok()
+        .and_then(|v| v.get("worker").cloned())
+        .unwrap_or(Value::Null);
+    hex::encode(Sha256::digest(worker.to_string().as_bytes()))
+}
+
+pub fn read_entry(path: &Path) -> Option<RegistryEntry> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_entry(path: &Path, entry: &RegistryEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    secret_store::write_private(&tmp, &serde_json::to_string_pretty(entry)?)?;
+    if cfg!(windows) && path.exists() {
+        let _ = std::fs::remove_file(path);
+    }
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write worker registry {:?}", path))?;
+    Ok(())
+}
+
+pub fn list_entries(base_dir: &Path) -> Vec<(PathBuf, RegistryEntry)> {
+    let Ok(read_dir) = std::fs::read_dir(registry_dir(base_dir)) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<(PathBuf, RegistryEntry)> = read_dir
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter_map(|p| read_entry(&p).map(|entry| (p, entry)))
+        .collect();
+    entries.sort_by(|a, b| a.1.module.cmp(&b.1.module));
+    entries
+}
+
+pub fn kill_process(pid: u32) -> bool {
+    let status = if cfg!(windows) {
+        std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    } else {
+        // The host leads its own process group, so this also stops the worker it spawned
+        std::process::Command::new("kill")
+            .args(["--", &format!("-{}", pid)])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    };
+    status.map(|s| s.success()).unwrap_or(false)
+}
+
+/// The command line of process `pid`, if it is running.
+#[cfg(target_os = "linux")]
+fn command_line(pid: u32) -> Option<String> {
+    let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    Some(String::from_utf8_lossy(&raw).replace('\0', " "))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn command_line(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps").args(["-o", "command=", "-p", &pid.to_string()]).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(windows)]
+fn command_line(pid: u32) -> Option<String> {
+    let query = format!("(Get-CimInstance Win32_Process -Filter 'ProcessId={}').CommandLine", pid);
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &query])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !line.is_empty()).then_some(line)
+}
+
+/// Whether `pid` is still the `lunu worker-host` that registered at `registry`. A host that died
+/// leaves its PID in the registry, and the system may since have given it to another process.
+pub fn is_worker_host(pid: u32, registry: &Path) -> bool {
+    let Some(file_name) = registry.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return false;
+    };
+    command_line(pid).is_some_and(|cmd| cmd.contains("worker-host") && cmd.contains(&file_name))
+}
+
+/// Stops the host registered at `path`, unless its PID now belongs to another process.
+fn stop_host(path: &Path, entry: &RegistryEntry) -> bool {
+    is_worker_host(entry.pid, path) && kill_process(entry.pid)
+}
+
+/// Stops every registered worker host under `base_dir`. Returns the modules that were stopped.
+pub fn stop_all(base_dir: &Path) -> Vec<String> {
+    let mut stopped = Vec::new();
+    for (path, entry) in list_entries(base_dir) {
+        if stop_host(&path, &entry) {
+            stopped.push(entry.module.clone());
+        }
+        let _ = std::fs::remove_file(path);
+    }
+    stopped
+}
+
+/// Connects to the host of `entry` and presents its token.
+async fn connect(entry: &RegistryEntry) -> Option<TcpStream> {
+    let mut stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(("127.0.0.1", entry.port)))
+        .await
+        .ok()
+        .and_then(|r| r.ok())?;
+    stream.write_all(format!("{}\n", entry.token).as_bytes()).await.ok()?;
+    Some(stream)
+}
+
+/// Connects to the warm host for `module`, starting a detached one when none is reachable or
+/// when its fingerprint no longer matches bridge.json.
+pub async fn attach_or_spawn(base_dir: &Path, module: &str, module_dir: &Path, fingerprint: &str) -> Result<TcpStream> {
+    let path = registry_path(base_dir, module);
+    if let Some(entry) = read_entry(&path) {
+        if entry.fingerprint == fingerprint && !entry.token.is_empty() {
+            if let Some(stream) = connect(&entry).await {
+                tracing::info!("Reattached to warm worker '{}' (pid {})", module, entry.pid);
+                return Ok(stream);
+            }
+        } else {
+            tracing::info!("Worker config for '{}' changed; restarting warm worker", module);
+            stop_host(&path, &entry);
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    spawn_detached_host(module_dir, &path)?;
+    let start = std::time::Instant::now();
+    while start.elapsed() < HOST_START_TIMEOUT {
+        if let Some(entry) = read_entry(&path) {
+            if let Some(stream) = connect(&entry).await {
+                tracing::info!("Started warm worker '{}' (pid {})", module, entry.pid);
+                return Ok(stream);
+            }
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Err(anyhow::anyhow!("Timed out waiting for warm worker '{}' to start", module))
+}
+
+fn spawn_detached_host(module_dir: &Path, registry: &Path) -> Result<()> {
+    let exe = std::env::current_exe().with_context(|| "Failed to locate the lunu executable")?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.arg("worker-host")
+        .arg(module_dir)
+        .arg("--registry")
+        .arg(registry)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut cmd);
+    cmd.spawn().with_context(|| "Failed to start worker host")?;
+    Ok(())
+}
+
+/// Starts `cmd` in its own process group (detached from the console on Windows), so Ctrl+C in
+/// the terminal that started it does not take it down.
+pub fn detach(cmd: &mut std::process::Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+}
+
+/// Entry point of `lunu worker-host`: runs the module's worker and relays its stdio to whichever
+/// bridge is currently connected. Exits (and unregisters) when the worker exits.
+pub async fn host_worker(module_dir: PathBuf, registry: PathBuf) -> Result<()> {
+    let cfg_content = std::fs::read_to_string(module_dir.join("bridge.json"))
+        .with_context(|| format!("Failed to read bridge.json in {:?}", module_dir))?;
+    let module = module_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("worker")
+        .to_string();
+
+    let mut child = crate::bridge_server::worker_command(&module_dir, &cfg_content)?
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start worker for '{}'", module))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("Worker stdin unavailable"))?;
+    let stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("Worker stdout unavailable"))?;
+    let stderr = child.stderr.take().ok_or_else(|| anyhow::anyhow!("Worker stderr unavailable"))?;
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    let token = Arc::new(hex::encode(bytes));
+    write_entry(&registry, &RegistryEntry {
+        module,
+        pid: std::process::id(),
+        port: listener.local_addr()?.port(),
+        fingerprint: worker_fingerprint(&cfg_content),
+        token: token.to_string(),
+    })?;
+
+    // Worker stderr goes to a log next to the registry entry since the host has no terminal
+    let log_path = registry.with_extension("log");
+    tokio::spawn(async move {
+        let Ok(mut log) = tokio::fs::File::create(&log_path).await else { return };
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = log.write_all(format!("{}\n", line).as_bytes()).await;
+        }
+    });
+
+    // Responses are forwarded to the current client; without one they are dropped
+    let client: Arc<Mutex<Option<OwnedWriteHalf>>> = Arc::new(Mutex::new(None));
+    let out_client = client.clone();
+    let mut relay_out = tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut guard = out_client.lock().await;
+            if let Some(writer) = guard.as_mut() {
+                if writer.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                    *guard = None;
+                }
+            }
+        }
+    });
+
+    let (requests_tx, mut requests) = tokio::sync::mpsc::unbounded_channel::<String>();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let (read_half, write_half) = stream.into_split();
+                let tx = requests_tx.clone();
+                let (client, token) = (client.clone(), token.clone());
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(read_half).lines();
+                    // Any local process can reach the port; only readers of the registry get the worker
+                    match tokio::time::timeout(CONNECT_TIMEOUT, lines.next_line()).await {
+                        Ok(Ok(Some(line))) if line == *token => *client.lock().await = Some(write_half),
+                        _ => return,
+                    }
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            Some(line) = requests.recv() => {
+                if stdin.write_all(format!("{}\n", line).as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+                    break;
+                }
+            }
+            _ = &mut relay_out => break,
+        }
+    }
+
+    let _ = child.kill().await;
+    if read_entry(&registry).map(|e| e.pid == std::process::id()).unwrap_or(false) {
+        let _ = std::fs::remove_file(&registry);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn fingerprint_ignores_methods() {
+        let a = r#"{"worker": {"cmd": ["python", "worker.py"]}, "methods": {"a": {}}}"#;
+        let b = r#"{"worker": {"cmd": ["python", "worker.py"]}, "methods": {"b": {}}}"#;
+        let c = r#"{"worker": {"cmd": ["python3", "worker.py"]}, "methods": {"a": {}}}"#;
+        assert_eq!(worker_fingerprint(a), worker_fingerprint(b));
+        assert_ne!(worker_fingerprint(a), worker_fingerprint(c));
+    }
+
+    #[test]
+    fn registry_entries_roundtrip() {
+        let dir = tempdir().unwrap();
+        let entry = RegistryEntry {
+            module: "ml".to_string(),
+            pid: 42,
+            port: 5000,
+            fingerprint: "abc".to_string(),
+            token: "secret".to_string(),
+        };
+        write_entry(&registry_path(dir.path(), "ml"), &entry).unwrap();
+        let listed = list_entries(dir.path());
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].1, entry);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&listed[0].0).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_registered_hosts_are_identified() {
+        let registry = registry_path(Path::new("/project"), "ml");
+        assert!(!is_worker_host(std::process::id(), &registry));
+        let mut host = std::process::Command::new("sh")
+            .args(["-c", "sleep 5", "worker-host"])
+            .arg(&registry)
+            .spawn()
+            .unwrap();
+        // Until `exec` completes the child still has the test binary's command line
+        let started = std::time::Instant::now();
+        while !is_worker_host(host.id(), &registry) && started.elapsed() < Duration::from_secs(2) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(is_worker_host(host.id(), &registry));
+        assert!(!is_worker_host(host.id(), &registry_path(Path::new("/project"), "other")));
+        host.kill().unwrap();
+        host.wait().unwrap();
+        assert!(!is_worker_host(host.id(), &registry));
+    }
+}
@@ -0,0 +1,158 @@
+//! Interpreter constraints from bridge.json (`"requires": {"python": ">=3.10"}`), checked by
+//! `lunu check`/`lunu install` and by the bridge before it starts a worker.
+
+use semver::{Version, VersionReq};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+/// A `requires` entry the resolved interpreter does not satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unsatisfied {
+    pub language: String,
+    pub requirement: String,
+    /// Interpreter that was asked for its version
+    pub command: String,
+    /// `None` when the interpreter could not be run
+    pub found: Option<String>,
+}
+
+impl fmt::Display for Unsatisfied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.found {
+            Some(found) => write!(f, "needs {} {}, found {} ({})", self.language, self.requirement, found, self.command),
+            None => write!(f, "needs {} {}, but `{}` could not be run", self.language, self.requirement, self.command),
+        }
+    }
+}
+
+impl Unsatisfied {
+    pub fn remediation(&self) -> String {
+        let install = match self.language.as_str() {
+            "python" => format!("Install Python {} (https://www.python.org/downloads/)", self.requirement),
+            "node" => format!("Install Node.js {} (https://nodejs.org/)", self.requirement),
+            other => format!("Install {} {}", other, self.requirement),
+        };
+        if self.found.is_none() {
+            format!("{} and make sure `{}` is on PATH, or set worker.cmd to its full path.", install, self.command)
+        } else {
+            format!("{}, or point worker.cmd at a compatible interpreter (e.g. one bundled in the module).", install)
+        }
+    }
+}
+
+/// Language family of a worker command (`python3` -> python, `npx` -> node).
+pub fn worker_language(cmd: &str) -> String {
+    let program = Path::new(cmd).file_stem().and_then(|s| s.to_str()).unwrap_or(cmd).to_lowercase();
+    match program.as_str() {
+        "python" | "python3" | "py" => "python".to_string(),
+        "node" | "npm" | "npx" | "bun" | "deno" => "node".to_string(),
+        _ if cmd.replace('\\', "/").contains("target/release/") => "rust".to_string(),
+        _ => program,
+    }
+}
+
+fn default_command(language: &str) -> &str {
+    match language {
+        "python" if cfg!(windows) => "python",
+        "python" => "python3",
+        "rust" => "rustc",
+        other => other,
+    }
+}
+
+/// First `x.y[.z]` in `<interpreter> --version` output (`Python 3.12.1`, `v20.11.0`, `go1.22.0`).
+pub fn parse_version(text: &str) -> Option<Version> {
+    text.split_whitespace().find_map(|token| {
+        let start = token.find(|c: char| c.is_ascii_digit())?;
+        let digits: String = token[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+        let parts: Vec<u64> = digits.trim_end_matches('.').split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+        match parts.as_slice() {
+            [major, minor] => Some(Version::new(*major, *minor, 0)),
+            [major, minor, patch, ..] => Some(Version::new(*major, *minor, *patch)),
+            _ => None,
+        }
+    })
+}
+
+fn interpreter_version(command: &Path) -> Option<Version> {
+    let output = Command::new(command).arg("--version").output().ok()?;
+    // Python 2 and some wrappers print the version on stderr
+    let text = format!("{} {}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    parse_version(&text)
+}
+
+/// Checks every `requires` entry of a module's bridge.json against the interpreter it resolves to:
+/// the worker command when it belongs to that language, otherwise the language's usual binary.
+pub fn check(module_dir: &Path, bridge_json: &str) -> anyhow::Result<Vec<Unsatisfied>> {
+    let cfg: Value = serde_json::from_str(bridge_json).map_err(|e| anyhow::anyhow!("Invalid bridge config: {}", e))?;
+    let Some(requires) = cfg.get("requires").and_then(Value::as_object) else {
+        return Ok(Vec::new());
+    };
+    let worker_cmd = cfg.pointer("/worker/cmd/0").and_then(Value::as_str);
+    let mut unsatisfied = Vec::new();
+    for (language, requirement) in requires {
+        let requirement = requirement
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("requires.{} must be a version range such as \">=3.10\"", language))?;
+        let req = VersionReq::parse(requirement)
+            .map_err(|e| anyhow::anyhow!("requires.{} = \"{}\" is not a valid version range: {}", language, requirement, e))?;
+        let command = match worker_cmd.filter(|cmd| worker_language(cmd) == *language) {
+            Some(cmd) if module_dir.join(cmd).exists() => module_dir.join(cmd),
+            Some(cmd) => cmd.into(),
+            None => default_command(language).into(),
+        };
+        let found = interpreter_version(&command);
+        if found.as_ref().is_some_and(|v| req.matches(v)) {
+            continue;
+        }
+        unsatisfied.push(Unsatisfied {
+            language: language.clone(),
+            requirement: requirement.to_string(),
+            command: command.to_string_lossy().to_string(),
+            found: found.map(|v| v.to_string()),
+        });
+    }
+    Ok(unsatisfied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_interpreter_versions() {
+        assert_eq!(parse_version("Python 3.12.0rc1\n"), Some(Version::new(3, 12, 0)));
+        assert_eq!(parse_version("v20.11.0"), Some(Version::new(20, 11, 0)));
+        assert_eq!(parse_version("go version go1.22 linux/amd64"), Some(Version::new(1, 22, 0)));
+        assert_eq!(parse_version("cargo 1.75.0 (1d8b05cdd 2023-11-20)"), Some(Version::new(1, 75, 0)));
+        assert_eq!(parse_version("command not found"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checks_requirements_against_the_worker_interpreter() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let python = dir.path().join("python3");
+        std::fs::write(&python, "#!/bin/sh\necho 'Python 3.8.10'\n").unwrap();
+        std::fs::set_permissions(&python, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let bridge = r#"{"worker": {"cmd": ["python3", "worker.py"]}, "requires": {"python": ">=3.10"}}"#;
+        let unsatisfied = check(dir.path(), bridge).unwrap();
+        assert_eq!(unsatisfied.len(), 1);
+        assert_eq!(unsatisfied[0].found.as_deref(), Some("3.8.10"));
+        assert!(unsatisfied[0].to_string().starts_with("needs python >=3.10, found 3.8.10"));
+        assert!(unsatisfied[0].remediation().contains("Install Python >=3.10"));
+
+        let relaxed = r#"{"worker": {"cmd": ["python3", "worker.py"]}, "requires": {"python": ">=3.8"}}"#;
+        assert!(check(dir.path(), relaxed).unwrap().is_empty());
+
+        let missing = r#"{"worker": {"cmd": ["python3"]}, "requires": {"lunu-missing-lang": ">=1"}}"#;
+        let unsatisfied = check(dir.path(), missing).unwrap();
+        assert_eq!(unsatisfied[0].found, None);
+        assert!(check(dir.path(), r#"{"requires": {"python": "newest"}}"#).is_err());
+    }
+}
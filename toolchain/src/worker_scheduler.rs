@@ -0,0 +1,189 @@
+//! Call scheduling for bridge workers. A worker takes at most `concurrency` calls at a time (one by
+//! default, as the worker templates answer requests in order); the other calls wait in one queue
+//! per priority class. When a call finishes, the next one comes from the classes with waiting calls
+//! by smooth weighted round-robin (weights 4, 2 and 1). A high-priority call therefore waits for at
+//! most the calls already running, while low-priority batches still get one slot in seven when every
+//! class has calls waiting.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+/// `"priority"` of a method in bridge.json.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Share of the slots the class gets while every class has calls waiting.
+    fn weight(self) -> i64 {
+        match self {
+            Priority::High => 4,
+            Priority::Normal => 2,
+            Priority::Low => 1,
+        }
+    }
+}
+
+pub struct Scheduler {
+    state: Mutex<State>,
+}
+
+struct State {
+    capacity: usize,
+    running: usize,
+    queues: [VecDeque<oneshot::Sender<Slot>>; 3],
+    /// Round-robin credit of each class
+    current: [i64; 3],
+}
+
+impl State {
+    /// Class of the next call to run, `None` when nothing waits.
+    fn next_class(&mut self) -> Option<Priority> {
+        let waiting: Vec<Priority> = Priority::ALL.into_iter().filter(|p| !self.queues[p.index()].is_empty()).collect();
+        // A class with nothing waiting starts over when calls arrive again
+        for class in Priority::ALL {
+            if !waiting.contains(&class) {
+                self.current[class.index()] = 0;
+            }
+        }
+        let total: i64 = waiting.iter().map(|p| p.weight()).sum();
+        for class in &waiting {
+            self.current[class.index()] += class.weight();
+        }
+        let chosen = *waiting.iter().max_by_key(|p| (self.current[p.index()], std::cmp::Reverse(p.index())))?;
+        self.current[chosen.index()] -= total;
+        Some(chosen)
+    }
+}
+
+/// A running call; dropping it hands the slot to the next waiting call.
+pub struct Slot {
+    scheduler: Option<Arc<Scheduler>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.release();
+        }
+    }
+}
+
+impl Scheduler {
+    /// A scheduler running up to `concurrency` calls at once (at least one).
+    pub fn new(concurrency: usize) -> Arc<Self> {
+        Arc::new(Scheduler {
+            state: Mutex::new(State {
+                capacity: concurrency.max(1),
+                running: 0,
+                queues: Default::default(),
+                current: [0; 3],
+            }),
+        })
+    }
+
+    /// Waits for a slot for a call of class `priority`. Dropping the future gives up its place.
+    pub async fn acquire(self: &Arc<Self>, priority: Priority) -> Slot {
+        let rx = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.running < state.capacity && state.queues.iter().all(VecDeque::is_empty) {
+                state.running += 1;
+                return Slot { scheduler: Some(self.clone()) };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.queues[priority.index()].push_back(tx);
+            rx
+        };
+        // The senders live as long as the scheduler, which the caller keeps alive
+        rx.await.unwrap_or(Slot { scheduler: None })
+    }
+
+    /// Passes a finished call's slot to the next waiting call, skipping calls that gave up.
+    fn release(self: &Arc<Self>) {
+        loop {
+            let next = {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                match state.next_class() {
+                    Some(class) => state.queues[class.index()].pop_front(),
+                    None => {
+                        state.running -= 1;
+                        return;
+                    }
+                }
+            };
+            let Some(next) = next else { continue };
+            match next.send(Slot { scheduler: Some(self.clone()) }) {
+                Ok(()) => return,
+                Err(mut slot) => slot.scheduler = None,
+            }
+        }
+    }
+
+    /// Calls running and calls waiting.
+    pub fn load(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.running, state.queues.iter().map(VecDeque::len).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn low_priority_batches_do_not_starve_high_priority_calls() {
+        let scheduler = Scheduler::new(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let running = scheduler.acquire(Priority::Low).await;
+
+        let mut calls = Vec::new();
+        let mut queue = |priority: Priority, label: String| {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            calls.push(tokio::spawn(async move {
+                let _slot = scheduler.acquire(priority).await;
+                order.lock().unwrap().push(label);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }));
+        };
+        for i in 0..6 {
+            queue(Priority::Low, format!("low{}", i));
+        }
+        for i in 0..5 {
+            queue(Priority::High, format!("high{}", i));
+        }
+        // Every call is queued before the first slot frees up
+        while scheduler.load().1 < 11 {
+            tokio::task::yield_now().await;
+        }
+        drop(running);
+        for call in calls {
+            call.await.unwrap();
+        }
+        let order = order.lock().unwrap().clone();
+        assert_eq!(order[..7], ["high0", "high1", "low0", "high2", "high3", "high4", "low1"]);
+        assert_eq!(scheduler.load(), (0, 0));
+
+        // A caller that gave up does not keep its slot
+        let held = scheduler.acquire(Priority::Normal).await;
+        let abandoned = tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(Priority::High)).await;
+        assert!(abandoned.is_err());
+        drop(held);
+        assert_eq!(scheduler.load(), (0, 0));
+        drop(scheduler.acquire(Priority::Low).await);
+    }
+}
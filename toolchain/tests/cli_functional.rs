@@ -5,7 +5,7 @@ use tempfile::tempdir;
 fn cli_init_creates_project_layout() {
     let dir = tempdir().unwrap();
     let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
-        .arg("init")
+        .args(["init", "--no-types"])
         .env("LUNU_INIT_RUNTIME", "lune")
         .current_dir(dir.path())
         .status()
@@ -20,7 +20,7 @@ fn cli_init_creates_project_layout() {
 fn cli_check_runs_on_initialized_project() {
     let dir = tempdir().unwrap();
     let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
-        .arg("init")
+        .args(["init", "--no-types"])
         .env("LUNU_INIT_RUNTIME", "lune")
         .current_dir(dir.path())
         .status()
@@ -34,3 +34,167 @@ fn cli_check_runs_on_initialized_project() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn cli_check_json_respects_baseline() {
+    let dir = tempdir().unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["init", "--no-types"])
+        .env("LUNU_INIT_RUNTIME", "lune")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let module = dir.path().join("modules").join("needs-python");
+    std::fs::create_dir_all(&module).unwrap();
+    std::fs::write(
+        module.join("bridge.json"),
+        r#"{"worker": {"cmd": ["lunu-missing-python"]}, "requires": {"python": ">=99"}}"#,
+    )
+    .unwrap();
+
+    let check = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_lunu")).arg("check").args(args).current_dir(dir.path()).output().unwrap();
+    let output = check(&["--json"]);
+    assert!(!output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["findings"][0]["id"], "worker.requires/needs-python/python");
+    assert_eq!(report["findings"][0]["severity"], "error");
+
+    assert!(check(&["--update-baseline"]).status.success());
+    assert!(dir.path().join(".lunu").join("check-baseline.json").exists());
+    let output = check(&["--json"]);
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["findings"].as_array().unwrap().len(), 0);
+    assert_eq!(report["suppressed"][0]["id"], "worker.requires/needs-python/python");
+}
+
+#[test]
+fn cli_init_accepts_runtime_and_name_flags() {
+    let dir = tempdir().unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["init", "--runtime", "lune", "--name", "myapp", "--template", "service", "--yes", "--no-types"])
+        .env_remove("LUNU_RUNTIME")
+        .env_remove("LUNU_INIT_RUNTIME")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let manifest = std::fs::read_to_string(dir.path().join("lunu.toml")).unwrap();
+    assert!(manifest.contains("name = \"myapp\""));
+    let main = std::fs::read_to_string(dir.path().join("src").join("main.luau")).unwrap();
+    assert!(main.contains("Service started"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_run_uses_runtime_path_override() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["init", "--no-types"])
+        .env("LUNU_INIT_RUNTIME", "lune")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let marker = dir.path().join("ran.txt");
+    let fake = dir.path().join("fake-lune");
+    std::fs::write(&fake, format!("#!/bin/sh\necho \"$@\" > \"{}\"\n", marker.display())).unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["run", "--runtime-path", "fake-lune", "src/main.luau"])
+        .env("LUNE_PATH", dir.path().join("missing-lune"))
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(std::fs::read_to_string(&marker).unwrap().contains("src/main.luau"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_run_uses_runtime_profile() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["init", "--no-types"])
+        .env("LUNU_INIT_RUNTIME", "lune")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let marker = dir.path().join("ran.txt");
+    let fake = dir.path().join("tools").join("lune-nightly");
+    std::fs::create_dir_all(fake.parent().unwrap()).unwrap();
+    std::fs::write(&fake, format!("#!/bin/sh\necho \"$GREETING $@\" > \"{}\"\n", marker.display())).unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let config = dir.path().join("lunu.toml");
+    let mut content = std::fs::read_to_string(&config).unwrap();
+    content.push_str("\n[runtimes.local]\npath = \"tools/lune-nightly\"\nenv = { GREETING = \"nightly\" }\n");
+    std::fs::write(&config, content).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["run", "--runtime-profile", "local", "src/main.luau"])
+        .env("LUNE_PATH", dir.path().join("missing-lune"))
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(std::fs::read_to_string(&marker).unwrap().starts_with("nightly "));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["run", "--runtime-profile", "locl", "src/main.luau"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Did you mean 'local'?"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cli_test_runs_each_file_in_a_scratch_sandbox() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["init", "--no-types"])
+        .env("LUNU_INIT_RUNTIME", "lune")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::write(dir.path().join("src").join("sandbox.test.luau"), "").unwrap();
+
+    let marker = dir.path().join("sandbox.txt");
+    let fake = dir.path().join("fake-lune");
+    std::fs::write(
+        &fake,
+        format!(
+            "#!/bin/sh\ntest -f \"$LUNU_TEST_CONFIG_DIR/settings.json\" || exit 1\necho changed > \"$LUNU_TEST_CONFIG_DIR/settings.json\"\ntouch \"$LUNU_TEST_TMPDIR/out.txt\"\necho \"$LUNU_TEST_TMPDIR\" > \"{}\"\n",
+            marker.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let settings = std::fs::read_to_string(dir.path().join("config").join("settings.json")).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_lunu"))
+        .args(["test", "--runtime-path", "fake-lune"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let sandbox = std::fs::read_to_string(&marker).unwrap();
+    assert!(!std::path::Path::new(sandbox.trim()).exists());
+    assert_eq!(std::fs::read_to_string(dir.path().join("config").join("settings.json")).unwrap(), settings);
+    assert!(dir.path().join("modules").join("lunu").join("test.luau").exists());
+}